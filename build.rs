@@ -0,0 +1,77 @@
+// build.rs
+//
+// Compiles data/resources into a GResource bundle. Without
+// glib-compile-resources the same files are embedded with include_str!.
+// Also records the git commit for the About window and embeds the release
+// signing key given in MEAUPDATER_RELEASE_KEY.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Sets MEAUPDATER_GIT_COMMIT, or "unknown" outside a git checkout
+fn record_git_commit() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|commit| !commit.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=MEAUPDATER_GIT_COMMIT={}", commit);
+
+    // Only watch files that exist, a missing one would rebuild every time
+    let head = Path::new(".git/HEAD");
+    if head.exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+        if let Some(reference) = fs::read_to_string(head).ok().as_deref().and_then(|h| h.strip_prefix("ref: ")) {
+            let reference = Path::new(".git").join(reference.trim());
+            if reference.exists() {
+                println!("cargo:rerun-if-changed={}", reference.display());
+            }
+        }
+    }
+}
+
+/// Copies the public key releases are signed with into OUT_DIR. Builds
+/// without it cannot install GitHub releases.
+fn embed_release_key() {
+    println!("cargo:rustc-check-cfg=cfg(meaupdater_release_key)");
+    println!("cargo:rerun-if-env-changed=MEAUPDATER_RELEASE_KEY");
+    let source = match env::var("MEAUPDATER_RELEASE_KEY") {
+        Ok(path) if !path.is_empty() => PathBuf::from(path),
+        _ => return,
+    };
+    println!("cargo:rerun-if-changed={}", source.display());
+    let target = PathBuf::from(env::var("OUT_DIR").unwrap()).join("release-key.gpg");
+    fs::copy(&source, &target).unwrap_or_else(|e| panic!("Could not read the release key {}: {}", source.display(), e));
+    println!("cargo:rustc-cfg=meaupdater_release_key");
+}
+
+fn main() {
+    record_git_commit();
+    embed_release_key();
+
+    let resources_dir = "data/resources";
+    let manifest = "meaupdater.gresource.xml";
+
+    println!("cargo:rustc-check-cfg=cfg(meaupdater_gresources)");
+    println!("cargo:rerun-if-changed={}", resources_dir);
+
+    let target = PathBuf::from(env::var("OUT_DIR").unwrap()).join("meaupdater.gresource");
+    let status = Command::new("glib-compile-resources")
+        .arg("--sourcedir")
+        .arg(resources_dir)
+        .arg("--target")
+        .arg(&target)
+        .arg(format!("{}/{}", resources_dir, manifest))
+        .status();
+
+    match status {
+        Ok(status) if status.success() => println!("cargo:rustc-cfg=meaupdater_gresources"),
+        Ok(status) => panic!("glib-compile-resources failed: {}", status),
+        Err(_) => println!("cargo:warning=glib-compile-resources not found, embedding resources without GResource"),
+    }
+}
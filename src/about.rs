@@ -1,15 +1,26 @@
 // src/about.rs
 
+use crate::app_state::{BusyState, Operation};
+use crate::apt::Transaction;
+use crate::error::{self, Error};
+use crate::journal::OperationKind;
+use crate::main_window;
+use crate::progress::ProgressWindow;
+use crate::self_update;
 use gtk::prelude::*;
 use gtk::{
-    Window, ApplicationWindow, Box as GtkBox, Label, Button,
+    glib, gio, Window, ApplicationWindow, Box as GtkBox, Label, Button,
     Orientation, gdk_pixbuf::Pixbuf, Picture, HeaderBar,
 };
+use std::cell::RefCell;
+use std::rc::Rc;
 
 pub struct AboutWindow;
 
 impl AboutWindow {
-    pub fn new(parent: &ApplicationWindow) -> Window {
+    /// Without `busy`, as in check-only mode, a newer release is only
+    /// linked and never installed
+    pub fn new(parent: &ApplicationWindow, busy: Option<Rc<BusyState>>) -> Window {
         let window = Window::builder()
             .transient_for(parent)
             .modal(true)
@@ -39,8 +50,7 @@ impl AboutWindow {
         logo_container.set_margin_bottom(25);
 
         // Logo
-        let logo_data = include_bytes!("../assets/logo.png");
-        match Pixbuf::from_read(std::io::Cursor::new(logo_data)) {
+        match Pixbuf::from_read(std::io::Cursor::new(crate::install::LOGO_PNG)) {
             Ok(pixbuf) => {
                 // Resize logo proportionally (maximum 160x160)
                 let scaled_pixbuf = if pixbuf.width() > 160 || pixbuf.height() > 160 {
@@ -75,12 +85,81 @@ impl AboutWindow {
         main_vbox.append(&title_label);
 
         // Version
-        let version_label = Label::new(Some("Version 0.5"));
-        version_label.set_markup("<span size='medium'>Version 0.5</span>");
+        let version = format!("Version {} ({})", self_update::VERSION, self_update::GIT_COMMIT);
+        let version_label = Label::new(Some(&version));
+        version_label.set_markup(&format!("<span size='medium'>{}</span>", version));
+        version_label.set_selectable(true);
         version_label.set_halign(gtk::Align::Center);
         version_label.set_margin_bottom(8);
         main_vbox.append(&version_label);
 
+        // Release check
+        let release_box = GtkBox::new(Orientation::Horizontal, 8);
+        release_box.set_halign(gtk::Align::Center);
+        release_box.set_margin_bottom(8);
+        let release_label = Label::new(None);
+        release_label.set_visible(false);
+        let release_button = Button::with_label("🔎 Check for New Release");
+        release_button.set_has_frame(false);
+        release_button.add_css_class("link");
+        // Only offered when no APT repository ships MeaUpdater
+        let install_button = Button::with_label("⬇️ Install Update");
+        install_button.add_css_class("suggested-action");
+        install_button.set_visible(false);
+        release_box.append(&release_button);
+        release_box.append(&release_label);
+        release_box.append(&install_button);
+        main_vbox.append(&release_box);
+
+        let latest = Rc::new(RefCell::new(None::<self_update::Release>));
+        let parent_install = parent.clone();
+        let latest_install = latest.clone();
+        let release_label_install = release_label.clone();
+        let can_install = busy.is_some();
+        install_button.connect_clicked(move |button| {
+            if let (Some(busy), Some(release)) = (busy.as_ref(), latest_install.borrow().clone()) {
+                install_release(&parent_install, busy, button, &release_label_install, release);
+            }
+        });
+
+        let release_url = Rc::new(RefCell::new(None::<String>));
+        release_button.connect_clicked(move |button| {
+            // Once a newer release was found the button opens its page
+            if let Some(url) = release_url.borrow().as_ref() {
+                if let Err(e) = open::that(url) {
+                    eprintln!("The link could not be opened: {}", e);
+                }
+                return;
+            }
+
+            button.set_sensitive(false);
+            release_label.set_visible(true);
+            release_label.set_text("Checking...");
+            let button = button.clone();
+            let release_label = release_label.clone();
+            let release_url = release_url.clone();
+            let install_button = install_button.clone();
+            let latest = latest.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(|| {
+                    self_update::latest_release().map(|release| (release, self_update::packaged_by_distribution()))
+                }).await;
+                button.set_sensitive(true);
+                match result {
+                    Ok(Ok((release, packaged))) if self_update::is_newer(&release.version, self_update::VERSION) => {
+                        release_label.set_text(&format!("🆕 MeaUpdater {} is available", release.version));
+                        button.set_label("🌐 Open Release Page");
+                        install_button.set_visible(can_install && !packaged);
+                        *release_url.borrow_mut() = Some(release.url.clone());
+                        *latest.borrow_mut() = Some(release);
+                    }
+                    Ok(Ok(_)) => release_label.set_text("✅ You have the latest version"),
+                    Ok(Err(e)) => release_label.set_text(&format!("⚠️ {}", e)),
+                    Err(_) => release_label.set_text("⚠️ The release check failed"),
+                }
+            });
+        });
+
         // Developer info
         let developer_container = GtkBox::new(Orientation::Horizontal, 0);
         developer_container.set_halign(gtk::Align::Center);
@@ -105,8 +184,8 @@ impl AboutWindow {
         main_vbox.append(&developer_container);
 
         // Description
-        let description_label = Label::new(Some("An update manager written in Rust for Debian-based systems"));
-        description_label.set_markup("<span size='small' style='italic'>An update manager written in Rust for Debian-based systems</span>");
+        let description_label = Label::new(Some(env!("CARGO_PKG_DESCRIPTION")));
+        description_label.set_markup(&format!("<span size='small' style='italic'>{}</span>", env!("CARGO_PKG_DESCRIPTION")));
         description_label.set_halign(gtk::Align::Center);
         description_label.set_wrap(true);
         description_label.set_max_width_chars(50);
@@ -114,8 +193,9 @@ impl AboutWindow {
         main_vbox.append(&description_label);
 
         // License
-        let license_label = Label::new(Some("Licensed under the GNU General Public License v3.0 (GPLv3)"));
-        license_label.set_markup("<span size='xx-small' alpha='70%'>Licensed under the GNU General Public License v3.0 (GPLv3)</span>");
+        let license = format!("Licensed under the GNU General Public License v3.0 ({})", env!("CARGO_PKG_LICENSE"));
+        let license_label = Label::new(Some(&license));
+        license_label.set_markup(&format!("<span size='xx-small' alpha='70%'>{}</span>", license));
         license_label.set_halign(gtk::Align::Center);
         license_label.set_wrap(true);
         license_label.set_max_width_chars(60);
@@ -142,27 +222,48 @@ impl AboutWindow {
 
         main_vbox.append(&close_button);
 
-        
-        let css = "
-        .link {
-            color: #1976d2;
-            text-decoration: underline;
-        }
-        .link:hover {
-            color: #1565c0;
-            background-color: rgba(25, 118, 210, 0.1);
-        }
-        ";
-
-        let provider = gtk::CssProvider::new();
-        provider.load_from_data(css);
-        gtk::style_context_add_provider_for_display(
-            &gtk::gdk::Display::default().expect("Could not connect to a display."),
-            &provider,
-            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
-
         window.set_child(Some(&main_vbox));
         window
     }
 }
+
+/// Downloads and verifies `release`, then installs it like any other
+/// package and offers to restart into it
+fn install_release(parent: &ApplicationWindow, busy: &Rc<BusyState>, button: &Button, status: &Label, release: self_update::Release) {
+    if !busy.try_begin(Operation::Installing) {
+        status.set_text(&format!("⏳ Wait until {} has finished", busy.current().description().to_lowercase()));
+        return;
+    }
+    button.set_sensitive(false);
+    status.set_text(&format!("Downloading MeaUpdater {}...", release.version));
+
+    let parent = parent.clone();
+    let busy = busy.clone();
+    let button = button.clone();
+    let status = status.clone();
+    glib::spawn_future_local(async move {
+        let download = gio::spawn_blocking(move || self_update::download_release(&release)).await
+            .unwrap_or_else(|_| Err(Error::Other("The download task panicked".to_string())));
+        let deb = match download {
+            Ok(deb) => deb,
+            Err(e) => {
+                busy.finish();
+                button.set_sensitive(true);
+                status.set_text("❌ The update could not be downloaded");
+                error::show_error_dialog(&parent, "❌ The MeaUpdater update could not be downloaded", &e, None);
+                return;
+            }
+        };
+        status.set_text("✅ Download verified");
+
+        let transaction = Transaction::install(&[deb.display().to_string()]).journal(OperationKind::Install);
+        let progress_window = ProgressWindow::new(&parent);
+        progress_window.show();
+        let result = progress_window.run_transaction(transaction, "✅ MeaUpdater was updated!").await;
+        busy.finish();
+        button.set_sensitive(true);
+        if result.is_ok() {
+            main_window::offer_restart(&parent);
+        }
+    });
+}
@@ -0,0 +1,149 @@
+// src/anomalies.rs
+
+use crate::apt::{self, PolicyCandidate, TransactionPlan, VersionChange};
+use crate::history;
+use crate::util;
+use std::cmp::Ordering;
+use std::fs;
+
+const APT_LISTS_DIR: &str = "/var/lib/apt/lists";
+
+/// Something unusual about a planned package change that may point to a
+/// misconfigured or compromised repository
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    Downgrade { from: String, to: String },
+    /// The package used to come from `previous` and would now come from `now`
+    OriginChanged { previous: String, now: String },
+    /// The new version comes from a repository whose index is not signed
+    Unsigned { origin: String },
+}
+
+impl Anomaly {
+    pub fn message(&self) -> String {
+        match self {
+            Anomaly::Downgrade { from, to } => format!("⬇️ would be downgraded from {} to {}", from, to),
+            Anomaly::OriginChanged { previous, now } => format!("🔀 would come from {} instead of {}", now, previous),
+            Anomaly::Unsigned { origin } => format!("🔓 would come from {}, which is not signed", origin),
+        }
+    }
+}
+
+/// Prefix of the files apt keeps in `/var/lib/apt/lists` for an origin
+/// such as "deb.debian.org/debian bookworm/main"
+pub fn list_file_prefix(origin: &str) -> Option<String> {
+    let (location, suite) = origin.split_once(' ')?;
+    // The component follows the last slash; flat repositories have none
+    let (dist, _) = suite.rsplit_once('/')?;
+    if dist.is_empty() || dist == "." {
+        return None;
+    }
+    let escaped = location.replace('_', "%5f").replace('/', "_");
+    Some(format!("{}_dists_{}", escaped, dist.replace('/', "_")))
+}
+
+/// Whether the index of `origin` was signed, judged from the downloaded
+/// list files. `None` when they cannot be found.
+pub fn release_signed(list_files: &[String], origin: &str) -> Option<bool> {
+    let prefix = list_file_prefix(origin)?;
+    let has = |suffix: &str| list_files.iter().any(|file| *file == format!("{}_{}", prefix, suffix));
+    if has("InRelease") || has("Release.gpg") {
+        Some(true)
+    } else if has("Release") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// The `Origin` field of a Release or InRelease file, e.g. "Debian"
+pub fn parse_release_origin(release: &str) -> Option<String> {
+    release.lines()
+        .find_map(|line| line.strip_prefix("Origin:"))
+        .map(|origin| origin.trim().to_string())
+        .filter(|origin| !origin.is_empty())
+}
+
+/// Whether two origins such as "deb.debian.org/debian bookworm/main" belong
+/// to the same repository. The suites of one archive, e.g. bookworm and
+/// bookworm-security, share the `Origin` of their Release files, which
+/// `release_origin` looks up; without it the URIs are compared.
+pub fn same_repository(previous: &str, now: &str, release_origin: &dyn Fn(&str) -> Option<String>) -> bool {
+    match (release_origin(previous), release_origin(now)) {
+        (Some(previous), Some(now)) => previous == now,
+        _ => previous.split(' ').next() == now.split(' ').next(),
+    }
+}
+
+/// Anomalies of one planned change. `downgrade` tells whether the new
+/// version sorts before the installed one.
+pub fn detect(
+    change: &VersionChange,
+    candidate: Option<&PolicyCandidate>,
+    previous_origin: Option<&str>,
+    release_origin: &dyn Fn(&str) -> Option<String>,
+    signed: Option<bool>,
+    downgrade: bool,
+) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    if let (true, Some(from), Some(to)) = (downgrade, &change.from, &change.to) {
+        anomalies.push(Anomaly::Downgrade { from: from.clone(), to: to.clone() });
+    }
+
+    // Only the candidate's origin is known; an explicitly chosen version may come from elsewhere
+    let origin = candidate
+        .filter(|candidate| change.to.as_deref() == Some(candidate.version.as_str()))
+        .and_then(|candidate| candidate.origin.as_deref());
+    if let Some(origin) = origin {
+        if let Some(previous) = previous_origin.filter(|previous| !same_repository(previous, origin, release_origin)) {
+            anomalies.push(Anomaly::OriginChanged { previous: previous.to_string(), now: origin.to_string() });
+        }
+        if signed == Some(false) {
+            anomalies.push(Anomaly::Unsigned { origin: origin.to_string() });
+        }
+    }
+    anomalies
+}
+
+fn list_files() -> Vec<String> {
+    fs::read_dir(APT_LISTS_DIR)
+        .map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// The `Origin` of `origin`'s Release file in the downloaded lists
+fn release_origin_from_lists(origin: &str) -> Option<String> {
+    let prefix = list_file_prefix(origin)?;
+    ["InRelease", "Release"].iter()
+        .find_map(|suffix| fs::read_to_string(format!("{}/{}_{}", APT_LISTS_DIR, prefix, suffix)).ok())
+        .and_then(|release| parse_release_origin(&release))
+}
+
+/// Unusual changes in `plan`, by package. Blocks while apt runs.
+pub fn find(plan: &TransactionPlan) -> Vec<(String, Anomaly)> {
+    let names: Vec<String> = plan.upgrade.iter().chain(&plan.install).cloned().collect();
+    if names.is_empty() {
+        return Vec::new();
+    }
+    let candidates = apt::get_policy_candidates(&names);
+    let origins = history::known_origins();
+    let files = list_files();
+
+    let mut found = Vec::new();
+    for name in names {
+        let change = plan.versions.get(&name).cloned().unwrap_or_default();
+        let candidate = candidates.get(&name);
+        let signed = candidate
+            .and_then(|candidate| candidate.origin.as_deref())
+            .and_then(|origin| release_signed(&files, origin));
+        let downgrade = match (&change.from, &change.to) {
+            (Some(from), Some(to)) => util::compare_versions(to, from) == Ordering::Less,
+            _ => false,
+        };
+        let previous = origins.get(&name).map(String::as_str);
+        for anomaly in detect(&change, candidate, previous, &release_origin_from_lists, signed, downgrade) {
+            found.push((name.clone(), anomaly));
+        }
+    }
+    found
+}
@@ -0,0 +1,212 @@
+// src/app_state.rs
+
+use crate::kernel_manager;
+use crate::model::PackageUpdate;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Long-running operation the main window can be busy with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Idle,
+    Checking,
+    Installing,
+}
+
+impl Operation {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Operation::Idle => "Idle",
+            Operation::Checking => "Checking for updates",
+            Operation::Installing => "Installing updates",
+        }
+    }
+}
+
+/// Window-level busy state. Only one operation may run at a time; the
+/// listeners are used to keep buttons and menu actions in sync.
+pub struct BusyState {
+    current: Cell<Operation>,
+    listeners: RefCell<Vec<Box<dyn Fn(Operation)>>>,
+}
+
+impl BusyState {
+    pub fn new() -> Self {
+        Self {
+            current: Cell::new(Operation::Idle),
+            listeners: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn current(&self) -> Operation {
+        self.current.get()
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.current.get() != Operation::Idle
+    }
+
+    /// Moves from `Idle` to `operation`. Returns `false` without changing
+    /// anything if another operation is already running.
+    pub fn try_begin(&self, operation: Operation) -> bool {
+        if operation == Operation::Idle || self.is_busy() {
+            return false;
+        }
+        self.set(operation);
+        true
+    }
+
+    /// Returns to `Idle`
+    pub fn finish(&self) {
+        if self.is_busy() {
+            self.set(Operation::Idle);
+        }
+    }
+
+    /// Registers a callback that is run on every state change and once
+    /// immediately with the current state.
+    pub fn connect_changed<F: Fn(Operation) + 'static>(&self, callback: F) {
+        callback(self.current.get());
+        self.listeners.borrow_mut().push(Box::new(callback));
+    }
+
+    fn set(&self, operation: Operation) {
+        self.current.set(operation);
+        for listener in self.listeners.borrow().iter() {
+            listener(operation);
+        }
+    }
+}
+
+impl Default for BusyState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// State shared by the views of one application instance. The update count
+/// is derived from the package list so notifications always match the rows.
+pub struct AppState {
+    busy: Rc<BusyState>,
+    packages: RefCell<Vec<PackageUpdate>>,
+    advisory_fixes: RefCell<HashMap<String, Vec<String>>>,
+    /// Known exploited CVEs fixed by a pending update, by package
+    exploited: RefCell<HashMap<String, Vec<String>>>,
+    /// Why `apt-get upgrade` would skip a pending update, by package
+    kept_back: RefCell<HashMap<String, String>>,
+    /// Kernel packages that are only installed together, lead first
+    kernel_set: RefCell<Vec<String>>,
+    queue_resume_offered: Cell<bool>,
+    /// Set in check-only mode, where nothing may change the system
+    check_only: Cell<bool>,
+    /// Set by the administrator's policy: only security updates are offered
+    security_only: Cell<bool>,
+}
+
+impl AppState {
+    pub fn new() -> Self {
+        Self {
+            busy: Rc::new(BusyState::new()),
+            packages: RefCell::new(Vec::new()),
+            advisory_fixes: RefCell::new(HashMap::new()),
+            exploited: RefCell::new(HashMap::new()),
+            kept_back: RefCell::new(HashMap::new()),
+            kernel_set: RefCell::new(Vec::new()),
+            queue_resume_offered: Cell::new(false),
+            check_only: Cell::new(false),
+            security_only: Cell::new(false),
+        }
+    }
+
+    pub fn busy(&self) -> Rc<BusyState> {
+        self.busy.clone()
+    }
+
+    /// Replaces the pending updates with the result of the latest check
+    pub fn set_packages(&self, packages: Vec<PackageUpdate>) {
+        *self.kernel_set.borrow_mut() = kernel_manager::kernel_update_set(&packages);
+        *self.packages.borrow_mut() = packages;
+    }
+
+    /// The pending kernel packages shown and installed as one update
+    pub fn kernel_set(&self) -> Vec<String> {
+        self.kernel_set.borrow().clone()
+    }
+
+    pub fn packages(&self) -> Vec<PackageUpdate> {
+        self.packages.borrow().clone()
+    }
+
+    pub fn package_names(&self) -> Vec<String> {
+        self.packages.borrow().iter().map(|p| p.name.clone()).collect()
+    }
+
+    pub fn update_count(&self) -> usize {
+        self.packages.borrow().len()
+    }
+
+    pub fn set_advisory_fixes(&self, fixes: HashMap<String, Vec<String>>) {
+        *self.advisory_fixes.borrow_mut() = fixes;
+    }
+
+    /// Advisory ids fixed by the pending update of `package`
+    pub fn advisories_fixed_by(&self, package: &str) -> Option<Vec<String>> {
+        self.advisory_fixes.borrow().get(package).cloned()
+    }
+
+    pub fn set_exploited(&self, exploited: HashMap<String, Vec<String>>) {
+        *self.exploited.borrow_mut() = exploited;
+    }
+
+    /// Actively exploited CVEs fixed by the pending update of `package`
+    pub fn exploited_cves(&self, package: &str) -> Option<Vec<String>> {
+        self.exploited.borrow().get(package).cloned()
+    }
+
+    pub fn set_kept_back(&self, reasons: HashMap<String, String>) {
+        *self.kept_back.borrow_mut() = reasons;
+    }
+
+    /// Why the pending update of `package` was kept back, if it was. The
+    /// lead of the kernel set counts as kept back with any of its members.
+    pub fn kept_back_reason(&self, package: &str) -> Option<String> {
+        let kept_back = self.kept_back.borrow();
+        if let Some(reason) = kept_back.get(package) {
+            return Some(reason.clone());
+        }
+        let kernel_set = self.kernel_set.borrow();
+        if kernel_set.first().map(String::as_str) != Some(package) {
+            return None;
+        }
+        kernel_set.iter().find_map(|member| kept_back.get(member).cloned())
+    }
+
+    pub fn set_check_only(&self, check_only: bool) {
+        self.check_only.set(check_only);
+    }
+
+    pub fn is_check_only(&self) -> bool {
+        self.check_only.get()
+    }
+
+    pub fn set_security_only(&self, security_only: bool) {
+        self.security_only.set(security_only);
+    }
+
+    pub fn is_security_only(&self) -> bool {
+        self.security_only.get()
+    }
+
+    /// Returns `true` the first time it is called, so the queue resume
+    /// dialog is only offered once per run.
+    pub fn take_queue_resume_offer(&self) -> bool {
+        !self.queue_resume_offered.replace(true)
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,73 +1,78 @@
 // src/apt.rs
 
 use crate::model::{PackageUpdate, UpdateType};
-use anyhow::{Context, Result};
-use std::process::Command;
-use std::collections::HashMap;
-
-/// Converts size to readable format
-fn format_size(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
-    let mut size = bytes as f64;
-    let mut unit_index = 0;
-    
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
-    }
-    
-    if unit_index == 0 {
-        format!("{:.0} {}", size, UNITS[unit_index])
-    } else {
-        format!("{:.1} {}", size, UNITS[unit_index])
-    }
-}
+use crate::conffile::{self, ConffileChoice};
+use crate::error::{Context, Error, Result};
+use crate::history;
+use crate::journal::{self, OperationKind};
+use crate::metadata;
+use crate::packagekit;
+use crate::paths;
+use crate::helper_protocol::Operation;
+use crate::privilege;
+use crate::repo_manager;
+use crate::repo_status;
+use crate::util;
+use std::process::{Command, Stdio};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+static LAST_APT_UPDATE: Mutex<Option<u64>> = Mutex::new(None);
+const APT_UPDATE_CACHE_DURATION: u64 = 300;
 
-fn determine_update_type(package_name: &str, repository: &str) -> UpdateType {
-    
+/// Classifies an `apt list --upgradable` entry. Security updates are
+/// recognised by the archives offering them (`bash/stable-security,stable`),
+/// never by the package name.
+pub fn determine_update_type(package_name: &str, repository: &str) -> UpdateType {
+    if is_firmware_package(package_name) {
+        return UpdateType::Firmware;
+    }
     if is_kernel_package(package_name) {
         return UpdateType::Kernel;
     }
-    
-    
-    if repository.contains("/security") || 
-       repository.contains("-security") ||
-       repository.contains("security.") {
+
+    if from_security_archive(repository) {
         return UpdateType::Security;
     }
-    
-    
-    let security_packages = [
-        "openssl", "ssl", "gnutls", "ssh", "openssh", "curl", "wget",
-        "firefox", "chromium", "thunderbird", "libreoffice",
-        "apache", "nginx", "php", "mysql", "postgresql",
-        "bind9", "dnsutils", "iptables", "ufw", "fail2ban",
-        "systemd", "sudo", "polkit", "pam", "login", "passwd",
-        "gpg", "gnupg", "ca-certificates", "certbot",
-        "kernel", "linux-", "firmware", "microcode",
-        "libc", "glibc", "zlib", "expat", "libxml", "libpng",
-        "jpeg", "tiff", "git", "subversion", "rsync"
-    ];
-    
-    let package_lower = package_name.to_lowercase();
-    for security_pkg in &security_packages {
-        if package_lower.contains(security_pkg) {
-            return UpdateType::Security;
-        }
-    }
-    
-    
     UpdateType::Software
 }
 
+/// Whether one of the archives of an `apt list` entry is a security archive
+pub fn from_security_archive(repository: &str) -> bool {
+    let suites = repository.split_once('/').map(|(_, suites)| suites).unwrap_or("");
+    suites.split(',').any(is_security_suite)
+}
+
+/// Security archives: Debian's `bookworm-security` and `stable-security`,
+/// Ubuntu's `noble-security` pocket
+pub fn is_security_suite(suite: &str) -> bool {
+    let archive = suite.split('/').next().unwrap_or(suite);
+    archive == "security" || archive.ends_with("-security")
+}
+
+/// Device firmware and CPU microcode, plus the fwupd stack that flashes it
+fn is_firmware_package(package_name: &str) -> bool {
+    let package_lower = package_name.to_lowercase();
+    package_lower.starts_with("firmware-")
+        || package_lower == "linux-firmware"
+        || package_lower.ends_with("-microcode")
+        || package_lower == "fwupd"
+        || package_lower.starts_with("fwupd-")
+        || package_lower.starts_with("libfwupd")
+}
+
 
 fn is_kernel_package(package_name: &str) -> bool {
     let kernel_keywords = [
         "linux-image",
         "linux-headers", 
         "linux-modules",
-        "linux-firmware",
         "linux-generic",
         "linux-lowlatency", 
         "linux-oem",
@@ -90,100 +95,381 @@ fn is_kernel_package(package_name: &str) -> bool {
 }
 
 
-pub fn get_package_sizes(package_names: &[String]) -> HashMap<String, String> {
+/// Reads the download size of each package from `apt show` or
+/// `apt-cache show` output, falling back to Installed-Size (in KB)
+pub fn parse_package_sizes(output: &str) -> HashMap<String, u64> {
     let mut sizes = HashMap::new();
-    
-    if package_names.is_empty() {
-        return sizes;
-    }
-    
-    // Get all packages with a single apt show command
-    let output = Command::new("apt")
-        .arg("show")
-        .args(package_names)
-        .env("LANG", "C")
-        .output();
-    
-    if let Ok(output) = output {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut current_package = String::new();
-        
-        for line in stdout.lines() {
-            let line = line.trim();
-            
-            if line.starts_with("Package:") {
-                if let Some(pkg_name) = line.split_whitespace().nth(1) {
-                    current_package = pkg_name.to_string();
-                }
-            } else if line.starts_with("Size:") && !current_package.is_empty() {
-                if let Some(size_str) = line.split_whitespace().nth(1) {
-                    if let Ok(size_bytes) = size_str.parse::<u64>() {
-                        sizes.insert(current_package.clone(), format_size(size_bytes));
-                    }
-                }
-                
-                current_package.clear();
+    let mut current_package = String::new();
+    let mut installed_size = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+        let value = || line.split_whitespace().nth(1).and_then(|size| size.parse::<u64>().ok());
+
+        if line.starts_with("Package:") {
+            current_package = line.split_whitespace().nth(1).unwrap_or("").to_string();
+            installed_size = None;
+        } else if current_package.is_empty() {
+            continue;
+        } else if line.starts_with("Size:") {
+            if let Some(size) = value() {
+                sizes.entry(current_package.clone()).or_insert(size);
+            }
+        } else if line.starts_with("Installed-Size:") {
+            installed_size = value().map(|kb| kb * 1024);
+        } else if line.is_empty() {
+            // End of the record
+            if let Some(size) = installed_size.take() {
+                sizes.entry(current_package.clone()).or_insert(size);
             }
+            current_package.clear();
         }
     }
-    
+    if let Some(size) = installed_size {
+        sizes.entry(current_package).or_insert(size);
+    }
+    sizes
+}
+
+/// `args` followed by the package names, for `metadata::query`
+fn with_names<'a>(args: &[&'a str], names: &'a [String]) -> Vec<&'a str> {
+    args.iter().copied().chain(names.iter().map(String::as_str)).collect()
+}
+
+/// Download sizes in bytes. Packages apt knows no size for are left out.
+pub fn get_package_sizes(package_names: &[String]) -> HashMap<String, u64> {
+    if package_names.is_empty() {
+        return HashMap::new();
+    }
+
+    // Get all packages with a single apt show command
+    let mut sizes = metadata::query("apt", &with_names(&["show"], package_names))
+        .map(|stdout| parse_package_sizes(&stdout))
+        .unwrap_or_default();
+
     // If apt show doesn't get the size, try apt-cache show
     let missing_packages: Vec<String> = package_names.iter()
         .filter(|pkg| !sizes.contains_key(*pkg))
         .cloned()
         .collect();
-    
     if !missing_packages.is_empty() {
-        if let Ok(output) = Command::new("apt-cache")
-            .arg("show")
-            .args(&missing_packages)
-            .env("LANG", "C")
-            .output()
-        {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let mut current_package = String::new();
-            
-            for line in stdout.lines() {
-                let line = line.trim();
-                
-                if line.starts_with("Package:") {
-                    if let Some(pkg_name) = line.split_whitespace().nth(1) {
-                        current_package = pkg_name.to_string();
-                    }
-                } else if line.starts_with("Size:") && !current_package.is_empty() {
-                    if let Some(size_str) = line.split_whitespace().nth(1) {
-                        if let Ok(size_bytes) = size_str.parse::<u64>() {
-                            sizes.insert(current_package.clone(), format_size(size_bytes));
-                        }
-                    }
-                    current_package.clear();
-                } else if line.starts_with("Installed-Size:") && !current_package.is_empty() && !sizes.contains_key(&current_package) {
-                    // If Size is not available use Installed-Size (in KB)
-                    if let Some(size_str) = line.split_whitespace().nth(1) {
-                        if let Ok(size_kb) = size_str.parse::<u64>() {
-                            sizes.insert(current_package.clone(), format_size(size_kb * 1024));
-                        }
+        if let Ok(stdout) = metadata::query("apt-cache", &with_names(&["show"], &missing_packages)) {
+            for (name, size) in parse_package_sizes(&stdout) {
+                sizes.entry(name).or_insert(size);
+            }
+        }
+    }
+
+    sizes
+}
+
+/// The version apt would install and the repository it comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyCandidate {
+    pub version: String,
+    pub origin: Option<String>,
+}
+
+/// Reads the candidate of each package from `apt-cache policy` output. The
+/// origin is the first repository listed for that version in the version
+/// table, e.g. `deb.debian.org/debian bookworm/non-free`.
+pub fn parse_policy(output: &str) -> HashMap<String, PolicyCandidate> {
+    let mut candidates = HashMap::new();
+    let mut current: Option<String> = None;
+    let mut in_candidate_version = false;
+
+    for line in output.lines() {
+        if !line.starts_with(' ') && line.ends_with(':') {
+            current = Some(line.trim_end_matches(':').to_string());
+            in_candidate_version = false;
+            continue;
+        }
+        let name = match &current {
+            Some(name) => name,
+            None => continue,
+        };
+        let trimmed = line.trim();
+
+        if let Some(version) = trimmed.strip_prefix("Candidate:") {
+            let version = version.trim();
+            if version != "(none)" {
+                candidates.insert(name.clone(), PolicyCandidate { version: version.to_string(), origin: None });
+            }
+            continue;
+        }
+
+        let candidate = match candidates.get_mut(name) {
+            Some(candidate) => candidate,
+            None => continue,
+        };
+        let fields: Vec<&str> = trimmed.trim_start_matches("***").split_whitespace().collect();
+        match fields.as_slice() {
+            // Version line: "535.183.01-1 500"
+            [version, priority] if priority.parse::<i32>().is_ok() => {
+                in_candidate_version = *version == candidate.version;
+            }
+            // Source line: "500 http://deb.debian.org/debian bookworm/non-free amd64 Packages"
+            [priority, uri, suite, ..] if in_candidate_version && priority.parse::<i32>().is_ok() => {
+                if candidate.origin.is_none() && uri.contains("://") {
+                    let location = uri.split("://").nth(1).unwrap_or(uri).trim_end_matches('/');
+                    candidate.origin = Some(format!("{} {}", location, suite));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    candidates
+}
+
+/// Runs one `apt-cache policy` for all `packages`
+pub fn get_policy_candidates(packages: &[String]) -> HashMap<String, PolicyCandidate> {
+    if packages.is_empty() {
+        return HashMap::new();
+    }
+    metadata::query("apt-cache", &with_names(&["policy"], packages))
+        .map(|stdout| parse_policy(&stdout))
+        .unwrap_or_default()
+}
+
+/// Updates whose new version is offered by a security archive, read from
+/// `apt-cache policy` output. On Ubuntu the same version usually sits in
+/// `-updates` too, so every source of the version counts.
+pub fn parse_security_updates(output: &str, packages: &[PackageUpdate]) -> HashSet<String> {
+    let tables = parse_version_table(output);
+    packages.iter()
+        .filter(|pkg| {
+            tables.get(&pkg.name).is_some_and(|versions| {
+                versions.iter().any(|v| v.version == pkg.new_version && v.releases.iter().any(|r| is_security_suite(r)))
+            })
+        })
+        .map(|pkg| pkg.name.clone())
+        .collect()
+}
+
+/// `apt list` is meant for people and its suite column may change, so the
+/// version table of `apt-cache policy` has the last word on which updates
+/// come from a security archive. Blocks while apt runs.
+pub fn mark_security_updates(packages: &mut [PackageUpdate]) {
+    let unconfirmed: Vec<PackageUpdate> = packages.iter()
+        .filter(|pkg| !pkg.from_security)
+        .cloned()
+        .collect();
+    if unconfirmed.is_empty() {
+        return;
+    }
+    let names: Vec<String> = unconfirmed.iter().map(|pkg| pkg.name.clone()).collect();
+    let security = metadata::query("apt-cache", &with_names(&["policy"], &names))
+        .map(|stdout| parse_security_updates(&stdout, &unconfirmed))
+        .unwrap_or_default();
+    for pkg in packages.iter_mut() {
+        if security.contains(&pkg.name) {
+            pkg.from_security = true;
+            if pkg.update_type == UpdateType::Software {
+                pkg.update_type = UpdateType::Security;
+            }
+        }
+    }
+}
+
+/// Which version of a package to install
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionSelector {
+    /// Whatever apt would pick
+    Candidate,
+    /// An exact version, `pkg=version`
+    Version(String),
+    /// The version from a release such as "bookworm-backports", `pkg/suite`
+    Release(String),
+}
+
+/// A package to install, in apt's `pkg`, `pkg=version` or `pkg/suite` form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageRequest {
+    pub name: String,
+    pub selector: VersionSelector,
+}
+
+fn is_package_name(name: &str) -> bool {
+    // Debian policy: lowercase letters, digits, "+", "-" and "."; an
+    // architecture may follow after ":"
+    let (package, arch) = name.split_once(':').unwrap_or((name, "amd64"));
+    package.len() >= 2
+        && package.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && package.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.".contains(c))
+        && !arch.is_empty()
+        && arch.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+fn is_version(version: &str) -> bool {
+    version.starts_with(|c: char| c.is_ascii_digit())
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || ".+-~:".contains(c))
+}
+
+fn is_release(release: &str) -> bool {
+    !release.is_empty() && release.chars().all(|c| c.is_ascii_alphanumeric() || "-._".contains(c))
+}
+
+impl PackageRequest {
+    pub fn candidate(name: &str) -> Self {
+        Self { name: name.to_string(), selector: VersionSelector::Candidate }
+    }
+
+    pub fn version(name: &str, version: &str) -> Self {
+        Self { name: name.to_string(), selector: VersionSelector::Version(version.to_string()) }
+    }
+
+    pub fn release(name: &str, release: &str) -> Self {
+        Self { name: name.to_string(), selector: VersionSelector::Release(release.to_string()) }
+    }
+
+    /// Reads "bash", "bash=5.2.15-2+b7" or "bash/bookworm-backports",
+    /// refusing anything apt would take as an option or a pattern
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let request = match (text.split_once('='), text.split_once('/')) {
+            (Some((name, version)), _) => Self::version(name, version),
+            (None, Some((name, release))) => Self::release(name, release),
+            (None, None) => Self::candidate(text),
+        };
+        if !is_package_name(&request.name) {
+            return Err(Error::Parse(format!("\"{}\" is not a valid package name", request.name)));
+        }
+        match &request.selector {
+            VersionSelector::Version(version) if !is_version(version) => {
+                Err(Error::Parse(format!("\"{}\" is not a valid version of {}", version, request.name)))
+            }
+            VersionSelector::Release(release) if !is_release(release) => {
+                Err(Error::Parse(format!("\"{}\" is not a valid release for {}", release, request.name)))
+            }
+            _ => Ok(request),
+        }
+    }
+
+    /// The argument for apt-get
+    pub fn to_arg(&self) -> String {
+        match &self.selector {
+            VersionSelector::Candidate => self.name.clone(),
+            VersionSelector::Version(version) => format!("{}={}", self.name, version),
+            VersionSelector::Release(release) => format!("{}/{}", self.name, release),
+        }
+    }
+}
+
+/// A version apt can install and the releases that offer it
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvailableVersion {
+    pub version: String,
+    pub releases: Vec<String>,
+}
+
+/// Reads the version table of each package from `apt-cache policy`
+/// output. Versions only known from the dpkg status file are left out,
+/// since apt cannot download them again. Flat repositories (`./` instead
+/// of a suite) offer a version without naming a release.
+pub fn parse_version_table(output: &str) -> HashMap<String, Vec<AvailableVersion>> {
+    let mut tables: HashMap<String, Vec<AvailableVersion>> = HashMap::new();
+    let mut current: Option<String> = None;
+    // The version being read and whether any repository offers it
+    let mut version: Option<(AvailableVersion, bool)> = None;
+
+    let finish = |tables: &mut HashMap<String, Vec<AvailableVersion>>, name: &Option<String>, version: Option<(AvailableVersion, bool)>| {
+        if let (Some(name), Some((version, true))) = (name, version) {
+            tables.entry(name.clone()).or_default().push(version);
+        }
+    };
+
+    for line in output.lines() {
+        if !line.starts_with(' ') && line.ends_with(':') {
+            finish(&mut tables, &current, version.take());
+            current = Some(line.trim_end_matches(':').to_string());
+            continue;
+        }
+        let fields: Vec<&str> = line.trim().trim_start_matches("***").split_whitespace().collect();
+        match fields.as_slice() {
+            [number, priority] if priority.parse::<i32>().is_ok() => {
+                finish(&mut tables, &current, version.take());
+                version = Some((AvailableVersion { version: number.to_string(), releases: Vec::new() }, false));
+            }
+            // "500 http://deb.debian.org/debian bookworm-backports/main amd64 Packages" or
+            // "500 file:/srv/repo ./ Packages"; "100 /var/lib/dpkg/status" is not a repository
+            [priority, uri, suite, ..] if priority.parse::<i32>().is_ok() && !uri.starts_with('/') => {
+                let release = suite.split('/').next().unwrap_or(suite);
+                if let Some((version, offered)) = version.as_mut() {
+                    *offered = true;
+                    if !release.is_empty() && release != "." && !version.releases.iter().any(|r| r == release) {
+                        version.releases.push(release.to_string());
                     }
-                    current_package.clear();
                 }
             }
+            _ => {}
         }
     }
-    
-    // Default value for packages whose size is not found
-    for pkg_name in package_names {
-        if !sizes.contains_key(pkg_name) {
-            sizes.insert(pkg_name.clone(), "N/A".to_string());
+    finish(&mut tables, &current, version);
+    tables
+}
+
+/// Checks that apt can satisfy each request, with a message naming what is
+/// available when it cannot
+pub fn check_available(requests: &[PackageRequest], tables: &HashMap<String, Vec<AvailableVersion>>) -> Result<()> {
+    for request in requests {
+        let versions = tables.get(&request.name).map(Vec::as_slice).unwrap_or(&[]);
+        let available = versions.iter().map(|v| v.version.as_str()).collect::<Vec<_>>().join(", ");
+        match &request.selector {
+            VersionSelector::Candidate => {}
+            VersionSelector::Version(wanted) => {
+                if !versions.iter().any(|v| v.version == *wanted) {
+                    return Err(Error::Apt(if versions.is_empty() {
+                        format!("Version {} of {} is not available: no repository offers {}", wanted, request.name, request.name)
+                    } else {
+                        format!("Version {} of {} is not available. Available versions: {}", wanted, request.name, available)
+                    }));
+                }
+            }
+            VersionSelector::Release(wanted) => {
+                if !versions.iter().any(|v| v.releases.contains(wanted)) {
+                    let mut releases: Vec<&str> = versions.iter().flat_map(|v| v.releases.iter().map(String::as_str)).collect();
+                    releases.sort();
+                    releases.dedup();
+                    return Err(Error::Apt(if versions.is_empty() {
+                        format!("{} is not available from {}: no repository offers {}", request.name, wanted, request.name)
+                    } else if releases.is_empty() {
+                        format!("{} is not available from {}: only repositories without a release name offer it", request.name, wanted)
+                    } else {
+                        format!("{} is not available from {}. It is offered by: {}", request.name, wanted, releases.join(", "))
+                    }));
+                }
+            }
         }
     }
-    
-    sizes
+    Ok(())
+}
+
+/// Runs `apt-cache policy` for the requests that ask for a specific version
+/// or release and checks them with `check_available`
+pub fn verify_available(requests: &[PackageRequest]) -> Result<()> {
+    let names: Vec<String> = requests.iter()
+        .filter(|request| request.selector != VersionSelector::Candidate)
+        .map(|request| request.name.clone())
+        .collect();
+    if names.is_empty() {
+        return Ok(());
+    }
+    let output = Command::new("apt-cache")
+        .arg("policy")
+        .args(&names)
+        .env("LANG", "C")
+        .output()
+        .context("Could not run `apt-cache policy`")?;
+    if !output.status.success() {
+        return Err(Error::from_status("apt-cache policy", &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+    check_available(requests, &parse_version_table(&String::from_utf8_lossy(&output.stdout)))
 }
 
 /// Function that parses the apt list output
 pub fn parse_apt_list_output(s: &str) -> Vec<PackageUpdate> {
     let mut packages = Vec::new();
-    let mut package_names = Vec::new();
 
     // First collect all package information
     for (i, line) in s.lines().enumerate() {
@@ -222,31 +508,28 @@ pub fn parse_apt_list_output(s: &str) -> Vec<PackageUpdate> {
         let update_type = determine_update_type(&name, repo);
 
         packages.push(PackageUpdate {
-            name: name.clone(),
+            name,
             current_version,
             new_version,
             update_type,
-            size: String::new(), 
+            from_security: from_security_archive(repo),
+            size: None,
+            first_seen: None,
         });
-        
-        package_names.push(name);
-    }
-
-    
-    let sizes = get_package_sizes(&package_names);
-    
-    
-    for pkg in &mut packages {
-        if let Some(size) = sizes.get(&pkg.name) {
-            pkg.size = size.clone();
-        } else {
-            pkg.size = "N/A".to_string();
-        }
     }
 
     packages
 }
 
+/// Fills in the download sizes apt knows. Blocks while apt runs.
+pub fn annotate_sizes(packages: &mut [PackageUpdate]) {
+    let names: Vec<String> = packages.iter().map(|pkg| pkg.name.clone()).collect();
+    let sizes = get_package_sizes(&names);
+    for pkg in packages.iter_mut() {
+        pkg.size = sizes.get(&pkg.name).copied();
+    }
+}
+
 
 pub fn get_upgradable_packages() -> Result<Vec<PackageUpdate>> {
     let output = Command::new("apt")
@@ -254,7 +537,981 @@ pub fn get_upgradable_packages() -> Result<Vec<PackageUpdate>> {
         .env("LANG", "C")
         .output()
         .context("Could not run `apt list --upgradable`")?;
+    if !output.status.success() {
+        return Err(Error::from_status("apt list --upgradable", &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut packages = parse_apt_list_output(&stdout);
+    annotate_sizes(&mut packages);
+    mark_security_updates(&mut packages);
+    crate::update_age::annotate_first_seen(&mut packages);
+    Ok(packages)
+}
+
+/// What a running transaction reports to its progress view
+#[derive(Debug)]
+pub enum TransactionEvent {
+    Status(String),
+    /// Overall progress between 0.0 and 1.0
+    Progress(f64),
+    Log(String),
+    /// dpkg asks what to do with a modified config file; the answer is sent back
+    Conffile(String, String, mpsc::Sender<ConffileChoice>),
+    /// A file is being fetched from `host`; the speed once it can be measured
+    Download { host: String, bytes_per_second: Option<u64> },
+}
+
+/// Progress line written to `APT::Status-Fd`
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusLine {
+    /// `dlstatus`: percent of the download and what is being fetched
+    Download(f64, String),
+    /// `pmstatus`: percent of the dpkg run and what dpkg is doing
+    Install(f64, String),
+}
+
+/// Parses `pmstatus:pkg:percent:description` and `dlstatus:n:percent:description`
+pub fn parse_status_line(line: &str) -> Option<StatusLine> {
+    let (kind, rest) = line.split_once(':')?;
+    // Multiarch package names carry their own colon ("libc6:amd64"), so the
+    // percent is the first numeric field after the subject
+    let (_subject, mut rest) = rest.split_once(':')?;
+    let (percent, description) = loop {
+        let (field, tail) = rest.split_once(':')?;
+        if let Ok(percent) = field.parse::<f64>() {
+            break (percent, tail.to_string());
+        }
+        rest = tail;
+    };
+    match kind {
+        "dlstatus" => Some(StatusLine::Download(percent, description)),
+        "pmstatus" => Some(StatusLine::Install(percent, description)),
+        _ => None,
+    }
+}
+
+/// Installed version before and after a planned change
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersionChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// Packages a transaction would change, read from a simulated run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionPlan {
+    pub install: Vec<String>,
+    pub upgrade: Vec<String>,
+    pub remove: Vec<String>,
+    pub versions: HashMap<String, VersionChange>,
+}
+
+impl TransactionPlan {
+    /// Changed packages that were not part of `requested`
+    pub fn extra_packages(&self, requested: &[String]) -> Vec<String> {
+        self.install.iter()
+            .chain(&self.upgrade)
+            .chain(&self.remove)
+            .filter(|name| !requested.contains(name))
+            .cloned()
+            .collect()
+    }
+
+    pub fn summary(&self) -> String {
+        change_summary(self.upgrade.len(), self.install.len(), self.remove.len())
+    }
+}
+
+/// "2 upgraded, 1 newly installed, 1 removed", leaving out zero counts
+pub fn change_summary(upgraded: usize, installed: usize, removed: usize) -> String {
+    let parts: Vec<String> = [(upgraded, "upgraded"), (installed, "newly installed"), (removed, "removed")]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect();
+    if parts.is_empty() {
+        "no changes".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Parses the `Inst`, `Remv` and `Purg` lines of `apt-get -s`
+pub fn parse_simulation(output: &str) -> TransactionPlan {
+    let mut plan = TransactionPlan::default();
+    for line in output.lines() {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            // "Inst bash [5.1-2] (5.1-2+deb11u1 ...)" upgrades an installed version
+            (Some("Inst"), Some(name)) => {
+                let mut next = parts.next();
+                let from = next.filter(|part| part.starts_with('[')).map(|part| part.trim_matches(|c| c == '[' || c == ']').to_string());
+                if from.is_some() {
+                    plan.upgrade.push(name.to_string());
+                    next = parts.next();
+                } else {
+                    plan.install.push(name.to_string());
+                }
+                let to = next.filter(|part| part.starts_with('(')).map(|part| part.trim_start_matches('(').to_string());
+                plan.versions.insert(name.to_string(), VersionChange { from, to });
+            }
+            (Some("Remv") | Some("Purg"), Some(name)) => {
+                plan.remove.push(name.to_string());
+                let from = parts.next()
+                    .filter(|part| part.starts_with('['))
+                    .map(|part| part.trim_matches(|c| c == '[' || c == ']').to_string());
+                plan.versions.insert(name.to_string(), VersionChange { from, to: None });
+            }
+            _ => {}
+        }
+    }
+    plan
+}
+
+const KEPT_BACK_HEADING: &str = "The following packages have been kept back:";
+const PHASING_HEADING: &str = "The following upgrades have been deferred due to phasing:";
 
+/// Package names apt lists in the indented block under `heading`
+fn listed_under(output: &str, heading: &str) -> Vec<String> {
+    output.lines()
+        .skip_while(|line| line.trim() != heading)
+        .skip(1)
+        .take_while(|line| line.starts_with(' '))
+        .flat_map(|line| line.split_whitespace())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Packages `apt-get upgrade` leaves at their old version because the new
+/// one needs packages added or removed
+pub fn parse_kept_back(upgrade_output: &str) -> Vec<String> {
+    listed_under(upgrade_output, KEPT_BACK_HEADING)
+}
+
+/// Packages whose phased update has not reached this machine yet
+pub fn parse_phased(upgrade_output: &str) -> Vec<String> {
+    listed_under(upgrade_output, PHASING_HEADING)
+}
+
+/// The `Depends:`/`Breaks:` problems of a failed `apt-get -s install`
+pub fn parse_unmet_dependencies(output: &str) -> Vec<String> {
+    output.lines()
+        .skip_while(|line| !line.ends_with("have unmet dependencies:"))
+        .skip(1)
+        .take_while(|line| line.starts_with(' '))
+        .map(|line| {
+            let line = line.trim();
+            line.split_once(" : ").map(|(_, problem)| problem).unwrap_or(line).trim().to_string()
+        })
+        .filter(|problem| !problem.is_empty())
+        .collect()
+}
+
+/// Explains why `package` was kept back from the simulated output of
+/// installing it on its own
+pub fn kept_back_reason(package: &str, install_output: &str) -> String {
+    let unmet = parse_unmet_dependencies(install_output);
+    if !unmet.is_empty() {
+        return format!("Its dependencies cannot be met right now:\n{}", unmet.join("\n"));
+    }
+
+    let plan = parse_simulation(install_output);
+    let mut reasons = Vec::new();
+    let new_packages: Vec<&str> = plan.install.iter().filter(|name| *name != package).map(|name| name.as_str()).collect();
+    if !new_packages.is_empty() {
+        reasons.push(format!("The new version needs new packages: {}", new_packages.join(", ")));
+    }
+    if !plan.remove.is_empty() {
+        reasons.push(format!("Upgrading it would remove: {}", plan.remove.join(", ")));
+    }
+    if reasons.is_empty() {
+        "apt kept it back to avoid changing other packages".to_string()
+    } else {
+        reasons.join("\n")
+    }
+}
+
+/// Upgradable packages a plain upgrade would skip, with the reason for each
+pub fn get_kept_back() -> Result<HashMap<String, String>> {
+    let output = Command::new("apt-get")
+        .args(["-s", "upgrade"])
+        .env("LANG", "C")
+        .output()
+        .context("Could not run `apt-get -s upgrade`")?;
+    if !output.status.success() {
+        return Err(Error::from_status("Simulating an upgrade", &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
     let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(parse_apt_list_output(&stdout))
+
+    let held: Vec<String> = Command::new("apt-mark")
+        .arg("showhold")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).lines().map(|line| line.trim().to_string()).collect())
+        .unwrap_or_default();
+
+    let mut reasons = HashMap::new();
+    for package in parse_phased(&stdout) {
+        reasons.insert(package, "This is a phased update that is being rolled out gradually and has not reached this computer yet".to_string());
+    }
+    for package in parse_kept_back(&stdout) {
+        if held.contains(&package) {
+            reasons.insert(package, "The package is on hold (apt-mark hold)".to_string());
+            continue;
+        }
+        let simulated = Command::new("apt-get")
+            .args(["-s", "install", package.as_str()])
+            .env("LANG", "C")
+            .output();
+        let reason = match simulated {
+            Ok(output) => kept_back_reason(&package, &String::from_utf8_lossy(&output.stdout)),
+            Err(_) => "apt kept it back to avoid changing other packages".to_string(),
+        };
+        reasons.insert(package, reason);
+    }
+    Ok(reasons)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransactionKind {
+    Install,
+    Remove,
+    Purge,
+    FullUpgrade,
+}
+
+impl TransactionKind {
+    fn verb(&self) -> &'static str {
+        match self {
+            TransactionKind::Install => "install",
+            TransactionKind::Remove => "remove",
+            TransactionKind::Purge => "purge",
+            TransactionKind::FullUpgrade => "dist-upgrade",
+        }
+    }
+}
+
+/// An apt-get run: plan it with a simulation, then execute it with
+/// progress events. Install, remove and upgrade flows all go through this.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    kind: TransactionKind,
+    packages: Vec<String>,
+    only_upgrade: bool,
+    refresh_lists: bool,
+    autoremove: bool,
+    ensure_installed: Vec<String>,
+    /// Requests for specific versions or releases, checked before apt runs
+    requests: Vec<PackageRequest>,
+    journal: Option<OperationKind>,
+    /// Shown with the packages in the Update History
+    notes: Vec<String>,
+    before: Vec<Vec<String>>,
+    after: Vec<Vec<String>>,
+    cancel: Option<CancelToken>,
+    /// Only downloads the packages, into this archives directory
+    download_to: Option<String>,
+}
+
+impl Transaction {
+    fn new(kind: TransactionKind, packages: &[String]) -> Self {
+        Self {
+            kind,
+            packages: packages.to_vec(),
+            only_upgrade: false,
+            refresh_lists: false,
+            autoremove: false,
+            ensure_installed: Vec::new(),
+            requests: Vec::new(),
+            journal: None,
+            notes: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+            cancel: None,
+            download_to: None,
+        }
+    }
+
+    /// Installs or upgrades packages; `.deb` paths are accepted too
+    pub fn install(packages: &[String]) -> Self {
+        Self::new(TransactionKind::Install, packages)
+    }
+
+    /// Installs the requested versions, e.g. a downgrade with
+    /// `pkg=version` or a backport with `pkg/suite`
+    pub fn install_requests(requests: &[PackageRequest]) -> Self {
+        let packages: Vec<String> = requests.iter().map(PackageRequest::to_arg).collect();
+        let mut transaction = Self::new(TransactionKind::Install, &packages);
+        transaction.requests = requests.to_vec();
+        transaction
+    }
+
+    pub fn remove(packages: &[String]) -> Self {
+        Self::new(TransactionKind::Remove, packages)
+    }
+
+    /// Removes packages together with their configuration files
+    pub fn purge(packages: &[String]) -> Self {
+        Self::new(TransactionKind::Purge, packages)
+    }
+
+    /// Upgrades everything, installing and removing packages where the new
+    /// versions need it (`apt-get dist-upgrade`)
+    pub fn full_upgrade() -> Self {
+        Self::new(TransactionKind::FullUpgrade, &[])
+    }
+
+    /// Only upgrades packages that are already installed
+    pub fn only_upgrade(mut self) -> Self {
+        self.only_upgrade = true;
+        self
+    }
+
+    /// Runs `apt-get update` first unless the lists were refreshed recently
+    pub fn refresh_lists(mut self) -> Self {
+        self.refresh_lists = true;
+        self
+    }
+
+    /// Removes packages that are no longer needed afterwards
+    pub fn then_autoremove(mut self) -> Self {
+        self.autoremove = true;
+        self
+    }
+
+    /// Installs `packages` in the same apt run, so a removal cannot leave
+    /// the system without them (apt's `package+` syntax)
+    pub fn ensure_installed(mut self, packages: &[String]) -> Self {
+        self.ensure_installed.extend(packages.iter().cloned());
+        self
+    }
+
+    /// Records the transaction so an interrupted run can be recovered
+    pub fn journal(mut self, kind: OperationKind) -> Self {
+        self.journal = Some(kind);
+        self
+    }
+
+    /// Adds a line to the history entry, e.g. where a backup was saved
+    pub fn note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    /// Lets `cancel` stop the transaction while it runs
+    pub fn cancellable(mut self, cancel: CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Privileged command run before apt; a failure is only logged
+    pub fn run_before(mut self, argv: &[&str]) -> Self {
+        self.before.push(argv.iter().map(|arg| arg.to_string()).collect());
+        self
+    }
+
+    /// Privileged command run after apt succeeded; a failure is only logged
+    pub fn run_after(mut self, argv: &[&str]) -> Self {
+        self.after.push(argv.iter().map(|arg| arg.to_string()).collect());
+        self
+    }
+
+    /// Downloads the packages into `archives` without installing them.
+    /// apt needs a `partial` directory there.
+    pub fn download_only(mut self, archives: &str) -> Self {
+        self.download_to = Some(archives.to_string());
+        self
+    }
+
+    pub fn packages(&self) -> &[String] {
+        &self.packages
+    }
+
+    /// Short description such as "install firefox-esr"
+    pub fn summary(&self) -> String {
+        match self.kind {
+            TransactionKind::FullUpgrade => "run a full upgrade".to_string(),
+            _ => format!("{} {}", self.kind.verb(), self.packages.join(", ")),
+        }
+    }
+
+    /// The apt-get command line that performs the transaction
+    pub fn argv(&self) -> Vec<String> {
+        let mut argv: Vec<String> = ["apt-get", self.kind.verb(), "-y", "-o", "APT::Status-Fd=1"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        if self.only_upgrade {
+            argv.push("--only-upgrade".to_string());
+        }
+        // Without it `-y` refuses to install an older version
+        if self.requests.iter().any(|request| matches!(request.selector, VersionSelector::Version(_))) {
+            argv.push("--allow-downgrades".to_string());
+        }
+        if let Some(archives) = &self.download_to {
+            argv.extend(["--download-only".to_string(), "-o".to_string(), format!("Dir::Cache::archives={}", archives)]);
+        }
+        argv.extend(self.package_args());
+        argv
+    }
+
+    fn package_args(&self) -> Vec<String> {
+        self.packages.iter()
+            .cloned()
+            .chain(self.ensure_installed.iter().map(|package| format!("{}+", package)))
+            .collect()
+    }
+
+    /// Asks apt what the transaction would change without touching the system
+    pub fn plan(&self) -> Result<TransactionPlan> {
+        verify_available(&self.requests)?;
+        let output = Command::new("apt-get")
+            .arg("-s")
+            .arg(self.kind.verb())
+            .args(self.only_upgrade.then_some("--only-upgrade"))
+            // Also lists what the autoremove step will take away
+            .args(self.autoremove.then_some("--autoremove"))
+            .args(self.package_args())
+            .env("LANG", "C")
+            .output()
+            .context("Could not run `apt-get -s`")?;
+        if !output.status.success() {
+            return Err(Error::from_status(&format!("Simulating `{}`", self.summary()), &output.status, &String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(parse_simulation(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Runs the transaction, blocking until apt is done. `report` receives
+    /// progress events and may be called from this thread only.
+    pub fn execute(&self, report: &dyn Fn(TransactionEvent)) -> Result<()> {
+        let _journal = self.journal.map(|kind| journal::begin(kind, &self.packages.join(" ")));
+
+        let packagekit = self.packagekit_action().filter(|_| packagekit::is_available());
+        // Stale lists are refreshed in the same privileged run as the
+        // changes, except where the versions must be checked against the
+        // fresh lists first or PackageKit does the work
+        let refresh_in_script = self.refresh_lists && packagekit.is_none() && self.requests.is_empty() && needs_apt_update();
+        let start = if self.refresh_lists { 0.2 } else { 0.0 };
+        if self.refresh_lists && !refresh_in_script {
+            report(TransactionEvent::Status("Checking the package list...".to_string()));
+            match packagekit {
+                Some(_) if needs_apt_update() => {
+                    packagekit::refresh_cache(report, 0.0, start, self.cancel.as_ref())?;
+                    mark_apt_updated();
+                }
+                _ => update_lists_if_needed(report)?,
+            }
+        }
+        verify_available(&self.requests)?;
+
+        // The plan tells upgrades, new packages and removals apart in the history
+        let plan = self.journal.and_then(|_| self.plan().ok());
+        let result = match packagekit {
+            Some(action) => {
+                report(TransactionEvent::Status(self.status_text().to_string()));
+                report(TransactionEvent::Progress(start));
+                report(TransactionEvent::Log(format!("Command: PackageKit {} {}", action.method(), self.packages.join(" "))));
+                packagekit::run(action, &self.packages, report, start, 0.95, self.cancel.as_ref())
+            }
+            None => self.run_privileged(report, refresh_in_script, start),
+        };
+        if result.is_ok() {
+            report(TransactionEvent::Progress(1.0));
+        }
+        if let (Some(kind), Some(plan)) = (self.journal, plan) {
+            history::record(kind, &plan, &self.notes, result.is_ok());
+        }
+        result
+    }
+
+    fn status_text(&self) -> &'static str {
+        if self.download_to.is_some() {
+            return "Downloading packages...";
+        }
+        match self.kind {
+            TransactionKind::Install => "Installing packages...",
+            TransactionKind::FullUpgrade => "Upgrading packages...",
+            TransactionKind::Remove | TransactionKind::Purge => "Removing packages...",
+        }
+    }
+
+    /// Every privileged command of the transaction in order, with its share
+    /// of the progress bar
+    fn steps(&self, refresh_lists: bool, start: f64) -> Vec<ScriptStep> {
+        let mut steps = Vec::new();
+        if refresh_lists {
+            steps.push(ScriptStep::new(vec!["apt-get".to_string(), "update".to_string()], "Refreshing the package list...", 0.0, start));
+        }
+        for argv in &self.before {
+            steps.push(ScriptStep::optional(argv, start));
+        }
+        let end = if self.autoremove { 0.8 } else { 0.95 };
+        steps.push(ScriptStep::new(self.argv(), self.status_text(), start, end));
+        if self.autoremove {
+            let argv: Vec<String> = ["apt-get", "autoremove", "-y", "-o", "APT::Status-Fd=1"]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+            steps.push(ScriptStep::new(argv, "Removing packages that are no longer needed...", end, 0.95));
+        }
+        for argv in &self.after {
+            steps.push(ScriptStep::optional(argv, 0.95));
+        }
+        steps
+    }
+
+    /// The commands that run as root, refreshing the lists first when
+    /// `refresh_lists` is set. They run as one script, so a single password
+    /// prompt covers all of them.
+    pub fn commands(&self, refresh_lists: bool) -> Vec<String> {
+        self.steps(refresh_lists, 0.0).iter().map(|step| step.argv.join(" ")).collect()
+    }
+
+    fn run_privileged(&self, report: &dyn Fn(TransactionEvent), refresh_lists: bool, start: f64) -> Result<()> {
+        let steps = self.steps(refresh_lists, start);
+        report(TransactionEvent::Log(format!("Command: {} {}", privilege::method_name(), self.commands(refresh_lists).join(" && "))));
+        let run = run_steps(&steps, report, self.cancel.as_ref());
+        if refresh_lists {
+            repo_status::record_update(&run.outputs[0]);
+            // Later steps only start once the update went through
+            if run.reached > 0 {
+                mark_apt_updated();
+            }
+        }
+        run.result
+    }
+
+    /// The PackageKit action that does the same, if there is one. Version
+    /// requests, `.deb` files, purges, full upgrades, extra steps, plain
+    /// downloads and architecture qualified names still need apt-get.
+    pub fn packagekit_action(&self) -> Option<packagekit::Action> {
+        let plain_names = !self.packages.is_empty()
+            && self.packages.iter().all(|package| is_package_name(package) && !package.contains(':'));
+        if !plain_names || !self.requests.is_empty() || !self.ensure_installed.is_empty() || !self.before.is_empty() || !self.after.is_empty() || self.download_to.is_some() {
+            return None;
+        }
+        match self.kind {
+            TransactionKind::Install if self.autoremove => None,
+            TransactionKind::Install if self.only_upgrade => Some(packagekit::Action::Update),
+            TransactionKind::Install => Some(packagekit::Action::Install),
+            TransactionKind::Remove => Some(packagekit::Action::Remove { autoremove: self.autoremove }),
+            TransactionKind::Purge | TransactionKind::FullUpgrade => None,
+        }
+    }
+
+    /// Runs the transaction without a progress view
+    pub fn run(&self) -> Result<()> {
+        self.execute(&|event| {
+            if let TransactionEvent::Log(line) = event {
+                println!("{}", line);
+            }
+        })
+    }
+}
+
+fn needs_apt_update() -> bool {
+    if !std::path::Path::new("/var/lib/apt/lists").exists() {
+        return true;
+    }
+
+    let current_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    match LAST_APT_UPDATE.lock().ok().and_then(|last| *last) {
+        Some(last_update) => current_time.saturating_sub(last_update) > APT_UPDATE_CACHE_DURATION,
+        None => true,
+    }
+}
+
+fn mark_apt_updated() {
+    if let Ok(mut last_update) = LAST_APT_UPDATE.lock() {
+        *last_update = Some(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        );
+    }
+}
+
+/// Runs `apt-get update` unless it already ran in the last five minutes
+pub fn update_lists_if_needed(report: &dyn Fn(TransactionEvent)) -> Result<()> {
+    if !needs_apt_update() {
+        report(TransactionEvent::Log("Apt cache is up to date, skipping apt update...".to_string()));
+        return Ok(());
+    }
+
+    report(TransactionEvent::Log("Running the apt update command...".to_string()));
+    let step = ScriptStep::new(vec!["apt-get".to_string(), "update".to_string()], "Refreshing the package list...", 0.0, 0.0);
+    let run = run_steps(&[step], report, None);
+    // The output tells which repositories could be refreshed
+    repo_status::record_update(&run.outputs[0]);
+    run.result?;
+    mark_apt_updated();
+    report(TransactionEvent::Log("apt update completed successfully.".to_string()));
+    Ok(())
+}
+
+/// Parses apt's own size notation such as "1,512 kB" or "2.5 MB" (SI units)
+pub fn parse_apt_size(text: &str) -> Option<u64> {
+    let (number, unit) = text.trim().split_once(' ')?;
+    let factor = util::si_unit_factor(unit.trim())?;
+    let number: f64 = number.replace(',', "").parse().ok()?;
+    Some((number * factor) as u64)
+}
+
+/// Mirror host and size of a `Get:` line, e.g.
+/// "Get:1 http://deb.debian.org/debian bookworm/main amd64 bash amd64 5.2.15-2+b7 [1,512 kB]"
+pub fn parse_fetch_line(line: &str) -> Option<(String, Option<u64>)> {
+    let rest = line.strip_prefix("Get:")?;
+    let uri = rest.split_whitespace().nth(1)?;
+    let host = repo_manager::uri_host(uri);
+    if host.is_empty() {
+        return None;
+    }
+    let size = line.rfind('[')
+        .and_then(|start| line[start + 1..].strip_suffix(']'))
+        .and_then(parse_apt_size);
+    Some((host.to_string(), size))
+}
+
+/// The average rate of a "Fetched 12.3 MB in 3s (4,100 kB/s)" summary line
+pub fn parse_fetched_summary(line: &str) -> Option<u64> {
+    let rest = line.strip_prefix("Fetched ")?;
+    let rate = rest.rsplit_once('(')?.1.strip_suffix(")")?.strip_suffix("/s")?;
+    parse_apt_size(rate)
+}
+
+/// Download speed over the last few seconds, from the sizes of the files
+/// apt started fetching
+#[derive(Debug, Default)]
+pub struct DownloadMeter {
+    /// Milliseconds since the start and bytes of each fetched file
+    samples: Vec<(u64, u64)>,
+}
+
+impl DownloadMeter {
+    const WINDOW_MS: u64 = 5_000;
+
+    pub fn add(&mut self, at_ms: u64, bytes: u64) {
+        self.samples.push((at_ms, bytes));
+        self.samples.retain(|(ms, _)| at_ms.saturating_sub(*ms) <= Self::WINDOW_MS);
+    }
+
+    /// Bytes per second, once the samples span at least a second
+    pub fn speed(&self) -> Option<u64> {
+        let first = self.samples.first()?.0;
+        let last = self.samples.last()?.0;
+        let span = last.saturating_sub(first);
+        if span < 1_000 {
+            return None;
+        }
+        // The newest file is still downloading, so it does not count yet
+        let bytes: u64 = self.samples[..self.samples.len() - 1].iter().map(|(_, bytes)| bytes).sum();
+        Some(bytes * 1_000 / span)
+    }
+}
+
+/// Estimates the time a transaction still needs from how fast its progress
+/// moved lately, so the estimate follows the switch from downloading to
+/// unpacking
+#[derive(Debug, Default)]
+pub struct ProgressClock {
+    /// Milliseconds since the start and the progress at that time
+    samples: Vec<(u64, f64)>,
+}
+
+impl ProgressClock {
+    const WINDOW_MS: u64 = 30_000;
+    const MIN_SPAN_MS: u64 = 3_000;
+
+    pub fn add(&mut self, at_ms: u64, fraction: f64) {
+        // Going back, e.g. to the start of a step, makes older samples useless
+        if self.samples.last().is_some_and(|(_, last)| fraction < *last) {
+            self.samples.clear();
+        }
+        self.samples.push((at_ms, fraction));
+        self.samples.retain(|(ms, _)| at_ms.saturating_sub(*ms) <= Self::WINDOW_MS);
+    }
+
+    /// Seconds left, once the progress has moved for a few seconds
+    pub fn remaining(&self) -> Option<u64> {
+        let (first_ms, first) = *self.samples.first()?;
+        let (last_ms, last) = *self.samples.last()?;
+        let span = last_ms.saturating_sub(first_ms);
+        if span < Self::MIN_SPAN_MS || last <= first {
+            return None;
+        }
+        let per_second = (last - first) * 1_000.0 / span as f64;
+        Some(((1.0 - last).max(0.0) / per_second).ceil() as u64)
+    }
+}
+
+static NEXT_CANCEL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Stops a running transaction on request. A user process cannot signal
+/// apt-get running as root, so cancelling also drops a request file that
+/// the privileged script watches for.
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    requested: Arc<AtomicBool>,
+    file: Option<PathBuf>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        let id = NEXT_CANCEL_ID.fetch_add(1, Ordering::Relaxed);
+        Self {
+            requested: Arc::new(AtomicBool::new(false)),
+            file: paths::runtime_file(&format!("cancel-{}-{}", std::process::id(), id)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        if let Some(file) = &self.file {
+            if let Err(e) = paths::ensure_parent(file).and_then(|()| fs::write(file, "")) {
+                eprintln!("Could not request cancelling at {}: {}", file.display(), e);
+            }
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// `Error::Cancelled` once cancelling was requested, for checks between steps
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() { Err(Error::Cancelled) } else { Ok(()) }
+    }
+
+    /// Removes the request file once nothing watches it anymore
+    fn clean_up(&self) {
+        if let Some(file) = &self.file {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether cancelling can stop a running transaction. The host helper
+/// cannot see the request file in the sandbox and runs each step to its end.
+pub fn can_cancel() -> bool {
+    !matches!(privilege::elevation(), privilege::Elevation::Helper)
+}
+
+/// Echoed before each command of a privileged script, with its index
+const STEP_MARKER: &str = "@@meaupdater-step ";
+/// Echoed when an optional command failed, with its index
+const FAILED_MARKER: &str = "@@meaupdater-failed ";
+
+/// One command of a privileged script and its share of the progress bar
+struct ScriptStep {
+    argv: Vec<String>,
+    status: Option<&'static str>,
+    start: f64,
+    end: f64,
+    /// Failures are only logged, as with `run_before` and `run_after`
+    optional: bool,
+}
+
+impl ScriptStep {
+    fn new(argv: Vec<String>, status: &'static str, start: f64, end: f64) -> Self {
+        Self { argv, status: Some(status), start, end, optional: false }
+    }
+
+    fn optional(argv: &[String], at: f64) -> Self {
+        Self { argv: argv.to_vec(), status: None, start: at, end: at, optional: true }
+    }
+}
+
+/// What a privileged script did
+struct ScriptRun {
+    result: Result<()>,
+    /// Output of each step
+    outputs: Vec<String>,
+    /// Index of the last step that started
+    reached: usize,
+}
+
+fn build_script(steps: &[ScriptStep], cancel_file: Option<&str>) -> String {
+    let mut script = String::from("set -e\n");
+    if let Some(file) = cancel_file {
+        // Interrupts the running command once the request file shows up.
+        // dpkg may have been stopped halfway, so it finishes configuring.
+        script.push_str(&format!("cancel_file={}\n", util::shell_quote(file)));
+        script.push_str("( while [ ! -e \"$cancel_file\" ]; do sleep 1; done; pkill -INT -P $$ ) >/dev/null 2>&1 &\n");
+        script.push_str("watcher=$!\n");
+        script.push_str("trap 'kill $watcher 2>/dev/null; if [ -e \"$cancel_file\" ]; then dpkg --configure -a; fi' EXIT\n");
+    }
+    for (index, step) in steps.iter().enumerate() {
+        let command: Vec<String> = step.argv.iter().map(|arg| util::shell_quote(arg)).collect();
+        if cancel_file.is_some() {
+            // Optional steps swallow the interruption, so check before each one
+            script.push_str("if [ -e \"$cancel_file\" ]; then exit 130; fi\n");
+        }
+        script.push_str(&format!("echo '{}{}'\n", STEP_MARKER, index));
+        if step.optional {
+            script.push_str(&format!("{} || echo '{}{}'\n", command.join(" "), FAILED_MARKER, index));
+        } else {
+            script.push_str(&command.join(" "));
+            script.push('\n');
+        }
+    }
+    script
+}
+
+/// Runs the steps as root in one shell, so one authentication covers all
+/// of them. Status lines become progress within the range of the running
+/// step, and conffile prompts are answered through the progress view.
+fn run_script(steps: &[ScriptStep], report: &dyn Fn(TransactionEvent), cancel: Option<&CancelToken>) -> ScriptRun {
+    let mut outputs = vec![String::new(); steps.len()];
+    let mut current = 0;
+    let result = (|| {
+        if let Some(cancel) = cancel {
+            cancel.check()?;
+        }
+        let cancel_file = cancel.and_then(|cancel| cancel.file.as_ref()).map(|file| file.display().to_string());
+        let mut command = privilege::elevated_command();
+        if matches!(privilege::elevation(), privilege::Elevation::Helper) {
+            command.args(&steps[0].argv);
+        } else {
+            command.args(["sh", "-c", &build_script(steps, cancel_file.as_deref())]);
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Could not start `{}`", steps[0].argv.join(" ")))?;
+
+        // Read stderr on its own thread so a full pipe cannot stall apt
+        let stderr = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut collected = String::new();
+                let _ = stderr.read_to_string(&mut collected);
+                collected
+            })
+        });
+
+        let mut stdin = child.stdin.take();
+        let mut pending_conffile: Option<String> = None;
+        let started = Instant::now();
+        let mut meter = DownloadMeter::default();
+        let mut mirror: Option<String> = None;
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                if let Some(index) = line.strip_prefix(STEP_MARKER).and_then(|index| index.parse::<usize>().ok()).filter(|index| *index < steps.len()) {
+                    current = index;
+                    let step = &steps[index];
+                    if let Some(status) = step.status {
+                        report(TransactionEvent::Status(status.to_string()));
+                    }
+                    report(TransactionEvent::Progress(step.start));
+                    report(TransactionEvent::Log(format!("$ {}", step.argv.join(" "))));
+                    continue;
+                }
+                if let Some(step) = line.strip_prefix(FAILED_MARKER).and_then(|index| index.parse::<usize>().ok()).and_then(|index| steps.get(index)) {
+                    report(TransactionEvent::Log(format!("⚠️ `{}` failed", step.argv.join(" "))));
+                    continue;
+                }
+
+                if let Some((host, size)) = parse_fetch_line(&line) {
+                    meter.add(started.elapsed().as_millis() as u64, size.unwrap_or(0));
+                    report(TransactionEvent::Download { host: host.clone(), bytes_per_second: meter.speed() });
+                    mirror = Some(host);
+                } else if let (Some(rate), Some(host)) = (parse_fetched_summary(&line), &mirror) {
+                    report(TransactionEvent::Download { host: host.clone(), bytes_per_second: Some(rate) });
+                }
+
+                if let Some(status) = parse_status_line(&line) {
+                    let (percent, description) = match status {
+                        StatusLine::Download(percent, description) => (percent / 2.0, description),
+                        StatusLine::Install(percent, description) => (50.0 + percent / 2.0, description),
+                    };
+                    let step = &steps[current];
+                    report(TransactionEvent::Progress(step.start + (step.end - step.start) * percent / 100.0));
+                    report(TransactionEvent::Status(description));
+                    continue;
+                }
+
+                outputs[current].push_str(&line);
+                outputs[current].push('\n');
+                report(TransactionEvent::Log(line.clone()));
+
+                // dpkg is asking about a modified config file
+                if let Some(path) = conffile::parse_conffile_line(&line) {
+                    pending_conffile = Some(path);
+                } else if conffile::is_prompt_end(&line) {
+                    if let Some(path) = pending_conffile.take() {
+                        let (reply_tx, reply_rx) = mpsc::channel();
+                        let diff = conffile::side_by_side_diff(&path);
+                        report(TransactionEvent::Conffile(path.clone(), diff, reply_tx));
+
+                        let choice = reply_rx.recv().unwrap_or(ConffileChoice::KeepCurrent);
+                        report(TransactionEvent::Log(format!("Conffile {}: {:?}", path, choice)));
+                        if let Some(stdin) = stdin.as_mut() {
+                            let _ = stdin.write_all(choice.answer().as_bytes());
+                            let _ = stdin.flush();
+                        }
+                    }
+                }
+            }
+        }
+
+        let argv = &steps[current].argv;
+        let status = child.wait().with_context(|| format!("`{}` did not finish", argv.join(" ")))?;
+        let stderr = stderr.and_then(|handle| handle.join().ok()).unwrap_or_default();
+        for line in stderr.lines() {
+            report(TransactionEvent::Log(line.to_string()));
+        }
+        outputs[current].push_str(&stderr);
+        if let Some(cancel) = cancel {
+            cancel.clean_up();
+            cancel.check()?;
+        }
+        if !status.success() {
+            return Err(Error::from_status(&argv[..argv.len().min(2)].join(" "), &status, &stderr));
+        }
+        Ok(())
+    })();
+    ScriptRun { result, outputs, reached: current }
+}
+
+/// Runs the steps as one script, or one after another through the host
+/// helper, which only takes single apt-get operations and no scripts
+fn run_steps(steps: &[ScriptStep], report: &dyn Fn(TransactionEvent), cancel: Option<&CancelToken>) -> ScriptRun {
+    if !matches!(privilege::elevation(), privilege::Elevation::Helper) {
+        return run_script(steps, report, cancel);
+    }
+
+    let mut outputs = vec![String::new(); steps.len()];
+    for (index, step) in steps.iter().enumerate() {
+        // Optional extras such as loading a module are left to the user
+        if step.optional && Operation::from_argv(&step.argv).is_none() {
+            report(TransactionEvent::Log(format!("⏭️ Skipped `{}`: the host helper only runs package operations", step.argv.join(" "))));
+            continue;
+        }
+        if let Some(status) = step.status {
+            report(TransactionEvent::Status(status.to_string()));
+        }
+        report(TransactionEvent::Progress(step.start));
+        report(TransactionEvent::Log(format!("$ {}", step.argv.join(" "))));
+        let run = run_script(std::slice::from_ref(step), report, cancel);
+        outputs[index] = run.outputs.concat();
+        match run.result {
+            Err(Error::Cancelled) => return ScriptRun { result: Err(Error::Cancelled), outputs, reached: index },
+            Err(_) if step.optional => report(TransactionEvent::Log(format!("⚠️ `{}` failed", step.argv.join(" ")))),
+            Err(e) => return ScriptRun { result: Err(e), outputs, reached: index },
+            Ok(()) => {}
+        }
+    }
+    ScriptRun { result: Ok(()), outputs, reached: steps.len().saturating_sub(1) }
 }
@@ -0,0 +1,143 @@
+// src/bin/meaupdater-helper.rs
+//
+// Host-side helper for sandboxed builds. Runs as root on the system bus and
+// performs a fixed set of package operations for the GUI after checking polkit.
+
+use gio::prelude::*;
+use meaupdater::helper_protocol::{self, Operation, BUS_NAME, OBJECT_PATH, INTERFACE};
+use std::fs;
+use std::process::Command;
+
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="io.github.mthakan.MeaUpdater.Helper">
+    <method name="Update">
+      <arg type="i" name="exit_code" direction="out"/>
+      <arg type="s" name="output" direction="out"/>
+    </method>
+    <method name="Install">
+      <arg type="as" name="packages" direction="in"/>
+      <arg type="b" name="only_upgrade" direction="in"/>
+      <arg type="i" name="exit_code" direction="out"/>
+      <arg type="s" name="output" direction="out"/>
+    </method>
+    <method name="Downgrade">
+      <arg type="as" name="packages" direction="in"/>
+      <arg type="i" name="exit_code" direction="out"/>
+      <arg type="s" name="output" direction="out"/>
+    </method>
+    <method name="FullUpgrade">
+      <arg type="i" name="exit_code" direction="out"/>
+      <arg type="s" name="output" direction="out"/>
+    </method>
+    <method name="Remove">
+      <arg type="as" name="packages" direction="in"/>
+      <arg type="b" name="purge" direction="in"/>
+      <arg type="i" name="exit_code" direction="out"/>
+      <arg type="s" name="output" direction="out"/>
+    </method>
+    <method name="Autoremove">
+      <arg type="i" name="exit_code" direction="out"/>
+      <arg type="s" name="output" direction="out"/>
+    </method>
+    <method name="WriteSources">
+      <arg type="s" name="path" direction="in"/>
+      <arg type="s" name="content" direction="in"/>
+      <arg type="i" name="exit_code" direction="out"/>
+      <arg type="s" name="output" direction="out"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+/// Asks polkit about the caller's bus name. Unlike a PID, the unique name
+/// cannot be taken over by another process while the check runs.
+fn authorized(action: &str, sender: &str) -> bool {
+    Command::new("pkcheck")
+        .args(["--action-id", action, "--system-bus-name", sender, "--allow-user-interaction"])
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+fn run(sender: &str, operation: Operation) -> (i32, String) {
+    if let Err(reason) = operation.validate() {
+        eprintln!("❌ Refused request from {}: {:?}", sender, operation);
+        return (helper_protocol::EXIT_UNAVAILABLE, reason);
+    }
+
+    let action = operation.polkit_action();
+    if !authorized(action, sender) {
+        println!("🔒 Not authorized: {} for {}", action, sender);
+        return (helper_protocol::EXIT_NOT_AUTHORIZED, "Authentication was cancelled or not authorized".to_string());
+    }
+
+    if let Operation::WriteSources { path, content } = &operation {
+        println!("📝 Writing {} for {}", path, sender);
+        return match fs::write(path, content) {
+            Ok(()) => (0, String::new()),
+            Err(e) => (1, format!("Could not write {}: {}", path, e)),
+        };
+    }
+
+    let argv = match operation.argv() {
+        Some(argv) => argv,
+        None => return (helper_protocol::EXIT_UNAVAILABLE, "Nothing to run".to_string()),
+    };
+
+    println!("▶️ Running for {}: {}", sender, argv.join(" "));
+
+    match Command::new(&argv[0]).args(&argv[1..]).output() {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            (output.status.code().unwrap_or(-1), text)
+        }
+        Err(e) => (helper_protocol::EXIT_UNAVAILABLE, format!("Failed to start {}: {}", argv[0], e)),
+    }
+}
+
+fn main() {
+    let node = gio::DBusNodeInfo::for_xml(INTROSPECTION_XML)
+        .expect("invalid introspection data");
+    let interface = node.lookup_interface(INTERFACE)
+        .expect("interface missing from introspection data");
+
+    let main_loop = glib::MainLoop::new(None, false);
+    let main_loop_lost = main_loop.clone();
+
+    let _owner = gio::bus_own_name(
+        gio::BusType::System,
+        BUS_NAME,
+        gio::BusNameOwnerFlags::NONE,
+        move |connection, _| {
+            let registration = connection.register_object(
+                OBJECT_PATH,
+                &interface,
+                |_, sender, _, _, method, params, invocation| {
+                    let operation = match Operation::from_call(method, &params) {
+                        Some(operation) => operation,
+                        None => {
+                            invocation.return_dbus_error("org.freedesktop.DBus.Error.UnknownMethod", method);
+                            return;
+                        }
+                    };
+                    let (code, output) = run(sender, operation);
+                    invocation.return_value(Some(&(code, output).to_variant()));
+                },
+                |_, _, _, _, _| glib::Variant::from(""),
+                |_, _, _, _, _, _| false,
+            );
+            if let Err(e) = registration {
+                eprintln!("❌ Could not register helper object: {}", e);
+            }
+        },
+        |_, name| println!("✅ Helper ready as {}", name),
+        move |_, name| {
+            eprintln!("❌ Lost bus name {}", name);
+            main_loop_lost.quit();
+        },
+    );
+
+    main_loop.run();
+}
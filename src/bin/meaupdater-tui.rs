@@ -0,0 +1,284 @@
+// src/bin/meaupdater-tui.rs
+//
+// Terminal frontend for servers without X or Wayland. Checks, selects and
+// installs updates and lists the repositories through the same code as the
+// GUI and `meaupdater --install-updates`.
+
+use meaupdater::cli;
+use meaupdater::config;
+use meaupdater::format;
+use meaupdater::model::{PackageUpdate, UpdateType};
+use meaupdater::policy;
+use meaupdater::privilege;
+use meaupdater::repo_manager::{self, Repository};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashSet;
+use std::io;
+
+const HELP: &str = "↑↓ move · Space select · a all · s security · i install · r reload · Tab repositories · q quit";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tab {
+    Updates,
+    Repositories,
+}
+
+struct App {
+    tab: Tab,
+    updates: Vec<PackageUpdate>,
+    /// Names of the ticked updates
+    selected: HashSet<String>,
+    update_list: ListState,
+    repositories: Vec<Repository>,
+    repo_list: ListState,
+    status: String,
+}
+
+/// Keeps the cursor on a row when the list changed length
+fn clamp(state: &mut ListState, len: usize) {
+    state.select(match len {
+        0 => None,
+        len => Some(state.selected().unwrap_or(0).min(len - 1)),
+    });
+}
+
+impl App {
+    fn new() -> Self {
+        let mut app = Self {
+            tab: Tab::Updates,
+            updates: Vec::new(),
+            selected: HashSet::new(),
+            update_list: ListState::default(),
+            repositories: Vec::new(),
+            repo_list: ListState::default(),
+            status: String::new(),
+        };
+        app.reload();
+        app
+    }
+
+    /// Reads the pending updates and the repositories again
+    fn reload(&mut self) {
+        match cli::pending_updates() {
+            Ok(updates) => {
+                self.status = match updates.len() {
+                    0 => "✅ The system is up to date".to_string(),
+                    count => format!("{} updates available", count),
+                };
+                self.selected.retain(|name| updates.iter().any(|pkg| pkg.name == *name));
+                self.updates = updates;
+            }
+            Err(e) => {
+                self.status = format!("❌ {}", e);
+                self.updates.clear();
+                self.selected.clear();
+            }
+        }
+        match repo_manager::get_repositories() {
+            Ok(repositories) => self.repositories = repositories,
+            Err(e) => self.status = format!("❌ Repositories could not be read: {}", e),
+        }
+        clamp(&mut self.update_list, self.updates.len());
+        clamp(&mut self.repo_list, self.repositories.len());
+    }
+
+    fn move_cursor(&mut self, down: bool) {
+        let (state, len) = match self.tab {
+            Tab::Updates => (&mut self.update_list, self.updates.len()),
+            Tab::Repositories => (&mut self.repo_list, self.repositories.len()),
+        };
+        if len == 0 {
+            return;
+        }
+        let current = state.selected().unwrap_or(0);
+        state.select(Some(if down { (current + 1).min(len - 1) } else { current.saturating_sub(1) }));
+    }
+
+    fn toggle_current(&mut self) {
+        let Some(pkg) = self.update_list.selected().and_then(|index| self.updates.get(index)) else {
+            return;
+        };
+        if !self.selected.remove(&pkg.name) {
+            self.selected.insert(pkg.name.clone());
+        }
+    }
+
+    /// Ticks every update, or unticks them all when all are ticked
+    fn toggle_all(&mut self) {
+        if self.selected.len() == self.updates.len() {
+            self.selected.clear();
+        } else {
+            self.selected = self.updates.iter().map(|pkg| pkg.name.clone()).collect();
+        }
+    }
+
+    fn select_security(&mut self) {
+        self.selected = self.updates.iter()
+            .filter(|pkg| pkg.from_security)
+            .map(|pkg| pkg.name.clone())
+            .collect();
+        if self.selected.is_empty() {
+            self.status = "✅ No security updates are pending".to_string();
+        }
+    }
+
+    /// The ticked updates in list order
+    fn selected_names(&self) -> Vec<String> {
+        self.updates.iter()
+            .filter(|pkg| self.selected.contains(&pkg.name))
+            .map(|pkg| pkg.name.clone())
+            .collect()
+    }
+}
+
+fn update_item(pkg: &PackageUpdate, ticked: bool) -> ListItem<'static> {
+    let line = format!(
+        "{} {:<36} {:<9} {} → {}  {}",
+        if ticked { "[x]" } else { "[ ]" },
+        pkg.name,
+        cli::type_name(&pkg.update_type),
+        if pkg.current_version.is_empty() { "-" } else { &pkg.current_version },
+        pkg.new_version,
+        format::optional_size(pkg.size),
+    );
+    let style = match pkg.update_type {
+        UpdateType::Security => Style::default().fg(Color::Red),
+        UpdateType::Kernel | UpdateType::Firmware => Style::default().fg(Color::Yellow),
+        _ => Style::default(),
+    };
+    ListItem::new(line).style(style)
+}
+
+fn repository_item(repo: &Repository) -> ListItem<'static> {
+    let mut line = format!(
+        "{} {}{} {} {}",
+        if repo.enabled { "✔" } else { "✘" },
+        if repo.is_source { "deb-src " } else { "" },
+        repo.uri,
+        repo.distribution,
+        repo.components,
+    );
+    if let Some(tool) = &repo.managed_by {
+        line.push_str(&format!("  (managed by {})", tool));
+    }
+    let style = if repo.enabled { Style::default() } else { Style::default().add_modifier(Modifier::DIM) };
+    ListItem::new(line).style(style)
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [tabs_area, list_area, footer_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(2),
+    ]).areas(frame.area());
+
+    let titles = vec![
+        format!(" 📦 Updates ({}) ", app.updates.len()),
+        format!(" 🗂️ Repositories ({}) ", app.repositories.len()),
+    ];
+    let tabs = Tabs::new(titles)
+        .select(if app.tab == Tab::Updates { 0 } else { 1 })
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
+    frame.render_widget(tabs, tabs_area);
+
+    let highlight = Style::default().add_modifier(Modifier::REVERSED);
+    match app.tab {
+        Tab::Updates => {
+            let items: Vec<ListItem> = app.updates.iter().map(|pkg| update_item(pkg, app.selected.contains(&pkg.name))).collect();
+            let title = format!("MeaUpdater · {} selected", app.selected.len());
+            let list = List::new(items).block(Block::bordered().title(title)).highlight_style(highlight);
+            frame.render_stateful_widget(list, list_area, &mut app.update_list);
+        }
+        Tab::Repositories => {
+            let items: Vec<ListItem> = app.repositories.iter().map(repository_item).collect();
+            let list = List::new(items).block(Block::bordered().title("Repositories")).highlight_style(highlight);
+            frame.render_stateful_widget(list, list_area, &mut app.repo_list);
+        }
+    }
+
+    let footer = Paragraph::new(vec![Line::from(app.status.clone()), Line::from(HELP)]);
+    frame.render_widget(footer, footer_area);
+}
+
+/// Leaves the full-screen view while apt runs, so its output and a
+/// password prompt show up as in a plain terminal
+fn install(terminal: &mut DefaultTerminal, app: &mut App) {
+    if config::check_only_enforced() {
+        app.status = "❌ Your administrator allows checking for updates only".to_string();
+        return;
+    }
+    let names = app.selected_names();
+    if names.is_empty() {
+        app.status = "Select updates with Space first".to_string();
+        return;
+    }
+    let names = cli::with_kernel_set(&app.updates, names);
+
+    ratatui::restore();
+    println!("⬇️ Installing {}", names.join(", "));
+    let installed = match policy::install_packages(&names) {
+        Ok(()) => {
+            println!("✅ Updates installed successfully!");
+            true
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            false
+        }
+    };
+    println!("Press Enter to return to MeaUpdater");
+    let _ = io::stdin().read_line(&mut String::new());
+    *terminal = ratatui::init();
+
+    if installed {
+        app.selected.clear();
+    }
+    app.reload();
+}
+
+fn run(terminal: &mut DefaultTerminal) -> io::Result<()> {
+    let mut app = App::new();
+    loop {
+        terminal.draw(|frame| draw(frame, &mut app))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Tab | KeyCode::BackTab => {
+                app.tab = if app.tab == Tab::Updates { Tab::Repositories } else { Tab::Updates };
+            }
+            KeyCode::Down | KeyCode::Char('j') => app.move_cursor(true),
+            KeyCode::Up | KeyCode::Char('k') => app.move_cursor(false),
+            KeyCode::Char(' ') if app.tab == Tab::Updates => app.toggle_current(),
+            KeyCode::Char('a') if app.tab == Tab::Updates => app.toggle_all(),
+            KeyCode::Char('s') if app.tab == Tab::Updates => app.select_security(),
+            KeyCode::Char('i') if app.tab == Tab::Updates => install(terminal, &mut app),
+            KeyCode::Char('r') => {
+                app.status = "Reloading...".to_string();
+                terminal.draw(|frame| draw(frame, &mut app))?;
+                app.reload();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal);
+    ratatui::restore();
+    privilege::restore_home_ownership();
+    if let Err(e) = result {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,198 @@
+// src/boot_layout.rs
+
+use crate::boot_platform::{self, BootPlatform};
+use crate::format;
+use crate::sysroot;
+use std::fs;
+use std::process::Command;
+
+/// Assumed for a new kernel when the running one's files cannot be measured
+pub const FALLBACK_KERNEL_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Kernel Manager operations that write to /boot or regenerate grub.cfg
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelOperation {
+    Install,
+    Remove,
+    SetDefault,
+}
+
+/// Where /boot lives and what it takes to boot from it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootLayout {
+    /// What boots the kernels; the GRUB checks only apply to GRUB
+    pub platform: BootPlatform,
+    /// /boot is its own file system
+    pub separate_boot: bool,
+    /// /boot is in /etc/fstab but not mounted
+    pub boot_unmounted: bool,
+    /// The system boots through EFI and /boot/efi is in /etc/fstab but not mounted
+    pub esp_unmounted: bool,
+    /// Device types below /boot, from `lsblk -s`, e.g. lvm, crypt, part, disk
+    pub boot_stack: Vec<String>,
+    /// Device types below `/`
+    pub root_stack: Vec<String>,
+    /// `GRUB_ENABLE_CRYPTODISK=y` in /etc/default/grub
+    pub grub_cryptodisk: bool,
+    /// The initramfs hook of cryptsetup-initramfs is installed
+    pub initramfs_cryptsetup: bool,
+    /// Free space on the file system holding /boot, or on the firmware
+    /// partition the kernels are copied to
+    pub boot_free: Option<u64>,
+}
+
+impl BootLayout {
+    /// GRUB itself has to unlock the device with the kernels
+    pub fn encrypted_boot(&self) -> bool {
+        self.boot_stack.iter().any(|kind| kind == "crypt")
+    }
+
+    /// The initramfs has to unlock the root file system
+    pub fn encrypted_root(&self) -> bool {
+        self.root_stack.iter().any(|kind| kind == "crypt")
+    }
+
+    /// GRUB only writes its environment block to plain partitions, so
+    /// `grub-reboot` cannot clear its one-time entry on LUKS, LVM or RAID
+    pub fn grubenv_writable(&self) -> bool {
+        !self.boot_stack.iter().any(|kind| kind == "crypt" || kind == "lvm" || kind.starts_with("raid"))
+    }
+
+    /// What could leave the system unbootable if `operation` went ahead.
+    /// `needed` is the space a new kernel takes in /boot.
+    pub fn warnings(&self, operation: KernelOperation, needed: u64) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.boot_unmounted {
+            warnings.push("/boot is listed in /etc/fstab but not mounted. Kernels and the GRUB menu would be written to the root \
+                file system, where the boot loader does not look. Mount /boot first.".to_string());
+        }
+        let grub = self.platform.uses_grub();
+        if grub && self.esp_unmounted {
+            warnings.push("The EFI system partition (/boot/efi) is not mounted, so GRUB on it is not updated.".to_string());
+        }
+        if grub && self.encrypted_boot() && !self.grub_cryptodisk {
+            warnings.push("/boot is on an encrypted device, but GRUB_ENABLE_CRYPTODISK=y is missing from /etc/default/grub. \
+                A regenerated GRUB could not unlock /boot.".to_string());
+        }
+        if operation == KernelOperation::Install {
+            if self.encrypted_root() && !self.initramfs_cryptsetup {
+                warnings.push("The root file system is encrypted, but cryptsetup-initramfs is not installed. The initramfs of \
+                    the new kernel could not unlock it.".to_string());
+            }
+            if let Some(free) = self.boot_free.filter(|free| *free < needed) {
+                let place = match self.platform.firmware_dir() {
+                    Some(dir) => dir,
+                    None if self.separate_boot => "/boot",
+                    None => "The file system holding /boot",
+                };
+                warnings.push(format!(
+                    "{} has {} free, but a new kernel needs about {}. A cut-off initramfs does not boot; remove an old kernel first.",
+                    place,
+                    format::size(free),
+                    format::size(needed),
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// Space to keep free for a kernel whose running sibling takes
+/// `current_kernel` bytes in /boot. The initramfs is rebuilt beside the
+/// old image, so twice that.
+pub fn needed_for_install(current_kernel: u64) -> u64 {
+    match current_kernel {
+        0 => FALLBACK_KERNEL_BYTES,
+        bytes => bytes * 2,
+    }
+}
+
+/// Device and file system type of the last mount on `mount_point` in
+/// `/proc/mounts` output
+pub fn mount_source(mounts: &str, mount_point: &str) -> Option<(String, String)> {
+    mounts.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [device, point, fstype, ..] if *point == mount_point => Some((device.to_string(), fstype.to_string())),
+                _ => None,
+            }
+        })
+        .last()
+}
+
+/// Whether /etc/fstab mounts something on `mount_point` at boot
+pub fn fstab_mounts(fstab: &str, mount_point: &str) -> bool {
+    fstab.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .any(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            fields.len() >= 3 && fields[1] == mount_point && !fields.get(3).is_some_and(|options| options.split(',').any(|option| option == "noauto"))
+        })
+}
+
+/// Device types from `lsblk -s -n -o TYPE`, the device first
+pub fn parse_lsblk_types(output: &str) -> Vec<String> {
+    output.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string).collect()
+}
+
+/// Whether /etc/default/grub enables cryptodisk support
+pub fn grub_cryptodisk(grub_default: &str) -> bool {
+    grub_default.lines()
+        .filter_map(|line| line.trim().strip_prefix("GRUB_ENABLE_CRYPTODISK="))
+        .last()
+        .is_some_and(|value| value.trim_matches(|c| c == '"' || c == '\'') == "y")
+}
+
+/// Available bytes from `df -B1 --output=avail`
+pub fn parse_df_available(output: &str) -> Option<u64> {
+    output.lines().nth(1)?.trim().parse().ok()
+}
+
+fn device_stack(device: &str) -> Vec<String> {
+    if !device.starts_with("/dev/") {
+        return Vec::new();
+    }
+    Command::new("lsblk")
+        .args(["-s", "-n", "-o", "TYPE", device])
+        .output()
+        .map(|output| parse_lsblk_types(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+fn free_space(path: &str) -> Option<u64> {
+    let output = Command::new("df").args(["-B1", "--output=avail", path]).env("LANG", "C").output().ok()?;
+    parse_df_available(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Looks at the mounts, block devices and GRUB settings of this system
+pub fn detect() -> BootLayout {
+    let mounts = fs::read_to_string(sysroot::path("/proc/mounts")).unwrap_or_default();
+    let fstab = fs::read_to_string(sysroot::path("/etc/fstab")).unwrap_or_default();
+    let grub_default = fs::read_to_string(sysroot::path("/etc/default/grub")).unwrap_or_default();
+
+    let boot = mount_source(&mounts, "/boot");
+    let root = mount_source(&mounts, "/");
+    let efi = sysroot::path("/sys/firmware/efi").exists();
+    // A Raspberry Pi also copies the kernels to the smaller firmware partition
+    let platform = boot_platform::detect();
+    let boot_free = free_space(platform.firmware_dir().unwrap_or("/boot"));
+    let root_stack = root.as_ref().map(|(device, _)| device_stack(device)).unwrap_or_default();
+    let boot_stack = match &boot {
+        Some((device, _)) => device_stack(device),
+        None => root_stack.clone(),
+    };
+
+    BootLayout {
+        platform,
+        separate_boot: boot.is_some(),
+        boot_unmounted: boot.is_none() && fstab_mounts(&fstab, "/boot"),
+        esp_unmounted: efi && mount_source(&mounts, "/boot/efi").is_none() && fstab_mounts(&fstab, "/boot/efi"),
+        boot_stack,
+        root_stack,
+        grub_cryptodisk: grub_cryptodisk(&grub_default),
+        initramfs_cryptsetup: sysroot::path("/usr/share/initramfs-tools/hooks/cryptroot").exists(),
+        boot_free,
+    }
+}
@@ -0,0 +1,70 @@
+// src/boot_platform.rs
+
+use crate::sysroot;
+use std::fs;
+
+/// Explains why the default kernel is left alone on boards without GRUB
+pub const SET_DEFAULT_UNSUPPORTED: &str = "This board does not boot through GRUB, so MeaUpdater does not change \
+    /etc/default/grub here. The firmware or flash-kernel boots the newest installed kernel.";
+
+/// How the installed kernels get booted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum BootPlatform {
+    /// GRUB reads /etc/default/grub and grub.cfg
+    #[default]
+    Grub,
+    /// The Raspberry Pi firmware boots what raspi-firmware or flash-kernel
+    /// copied to `firmware_dir`, as set up in its config.txt
+    RaspberryPi { firmware_dir: String },
+    /// Another ARM board, booted by U-Boot or flash-kernel
+    OtherArm,
+}
+
+impl BootPlatform {
+    pub fn uses_grub(&self) -> bool {
+        *self == BootPlatform::Grub
+    }
+
+    /// Partition the kernels are copied to, when it is not /boot
+    pub fn firmware_dir(&self) -> Option<&str> {
+        match self {
+            BootPlatform::RaspberryPi { firmware_dir } if firmware_dir != "/boot" => Some(firmware_dir),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BootPlatform::Grub => "GRUB",
+            BootPlatform::RaspberryPi { .. } => "Raspberry Pi firmware",
+            BootPlatform::OtherArm => "U-Boot or flash-kernel",
+        }
+    }
+}
+
+/// Works out the platform from the device tree model, the CPU architecture,
+/// whether GRUB is installed and whether /boot/firmware has a config.txt.
+/// An ARM server with GRUB and EFI is treated like a PC.
+pub fn detect_from(model: Option<&str>, arch: &str, grub_installed: bool, firmware_config: bool) -> BootPlatform {
+    if model.is_some_and(|model| model.contains("Raspberry Pi")) {
+        let firmware_dir = if firmware_config { "/boot/firmware" } else { "/boot" };
+        return BootPlatform::RaspberryPi { firmware_dir: firmware_dir.to_string() };
+    }
+    if !grub_installed && (arch.starts_with("arm") || arch == "aarch64") {
+        return BootPlatform::OtherArm;
+    }
+    BootPlatform::Grub
+}
+
+/// The boot platform of this system
+pub fn detect() -> BootPlatform {
+    // The model string ends with a NUL byte
+    let model = fs::read_to_string(sysroot::path("/proc/device-tree/model"))
+        .ok()
+        .map(|model| model.trim_end_matches('\0').to_string());
+    let grub_installed = ["/usr/sbin/update-grub", "/usr/sbin/grub-mkconfig", "/usr/sbin/grub2-mkconfig"]
+        .iter()
+        .any(|path| sysroot::path(path).exists());
+    let firmware_config = sysroot::path("/boot/firmware/config.txt").exists();
+    detect_from(model.as_deref(), std::env::consts::ARCH, grub_installed, firmware_config)
+}
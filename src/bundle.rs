@@ -0,0 +1,124 @@
+// src/bundle.rs
+
+use crate::download_plan::{DownloadUri, BUNDLE_MANIFEST};
+use crate::error::{Context, Error, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Outcome of checking one `.deb` of an imported bundle
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verification {
+    /// Size and checksum match the manifest
+    Verified,
+    /// Readable package, but not listed in the manifest
+    Unlisted,
+    /// Size or checksum differs from the manifest
+    Mismatch(String),
+    /// dpkg-deb could not read the archive
+    Corrupt(String),
+}
+
+impl Verification {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Verification::Verified | Verification::Unlisted)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BundleFile {
+    pub path: PathBuf,
+    pub filename: String,
+    pub verification: Verification,
+}
+
+/// Parses the `filename size hash` lines written by the export script
+pub fn parse_manifest(content: &str) -> HashMap<String, DownloadUri> {
+    let mut entries = HashMap::new();
+
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 2 {
+            continue;
+        }
+        entries.insert(fields[0].to_string(), DownloadUri {
+            uri: String::new(),
+            filename: fields[0].to_string(),
+            size: fields[1].parse().unwrap_or(0),
+            hash: fields.get(2).unwrap_or(&"").to_string(),
+        });
+    }
+
+    entries
+}
+
+/// Compares a file against the size and checksum apt reported for it
+pub fn verify_against(path: &Path, expected: &DownloadUri) -> Verification {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if expected.size != 0 && size != expected.size {
+        return Verification::Mismatch(format!("size is {} bytes, expected {}", size, expected.size));
+    }
+
+    let (tool, digest) = match expected.checksum() {
+        Some(checksum) => checksum,
+        None => return Verification::Verified,
+    };
+
+    match Command::new(tool).arg(path).output() {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let actual = stdout.split_whitespace().next().unwrap_or("");
+            if actual.eq_ignore_ascii_case(digest) {
+                Verification::Verified
+            } else {
+                Verification::Mismatch(format!("{} does not match", tool))
+            }
+        }
+        _ => Verification::Mismatch(format!("{} could not be run", tool)),
+    }
+}
+
+fn check_archive(path: &Path) -> Option<String> {
+    match Command::new("dpkg-deb").arg("--info").arg(path).output() {
+        Ok(output) if output.status.success() => None,
+        Ok(output) => Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Some(format!("dpkg-deb could not be run: {}", e)),
+    }
+}
+
+/// Finds and verifies every `.deb` in `dir`
+pub fn scan_bundle(dir: &Path) -> Result<Vec<BundleFile>> {
+    let manifest = fs::read_to_string(dir.join(BUNDLE_MANIFEST))
+        .map(|content| parse_manifest(&content))
+        .unwrap_or_default();
+
+    let mut files = Vec::new();
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("{} could not be read", dir.display()))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("deb") {
+            continue;
+        }
+        let filename = entry.file_name().to_string_lossy().to_string();
+
+        let verification = match check_archive(&path) {
+            Some(error) => Verification::Corrupt(error),
+            None => match manifest.get(&filename) {
+                Some(expected) => verify_against(&path, expected),
+                None => Verification::Unlisted,
+            },
+        };
+
+        files.push(BundleFile { path, filename, verification });
+    }
+
+    if files.is_empty() {
+        return Err(Error::Other(format!("No .deb files were found in {}", dir.display())));
+    }
+
+    files.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(files)
+}
@@ -0,0 +1,177 @@
+// src/bundle_window.rs
+
+use crate::app_state::{BusyState, Operation};
+use crate::bundle::{self, BundleFile, Verification};
+use crate::progress::ProgressWindow;
+use gtk::prelude::*;
+use gtk::{
+    ApplicationWindow, Box as GtkBox, Button, ListBox, ListBoxRow, ScrolledWindow,
+    Orientation, HeaderBar, Label, Dialog,
+    Separator, FileChooserAction, FileChooserNative, ResponseType, glib,
+};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Verifies and installs `.deb` files from a directory or USB stick
+pub struct BundleWindow {
+    window: Dialog,
+}
+
+impl BundleWindow {
+    /// Asks for the bundle directory, then opens the import window
+    pub fn choose(parent: &ApplicationWindow, busy: Rc<BusyState>, on_installed: Rc<dyn Fn()>) {
+        let chooser = FileChooserNative::new(
+            Some("Import Update Bundle"),
+            Some(parent),
+            FileChooserAction::SelectFolder,
+            Some("Open"),
+            Some("Cancel"),
+        );
+
+        let parent = parent.clone();
+        chooser.connect_response(move |chooser, response| {
+            if response == ResponseType::Accept {
+                if let Some(dir) = chooser.file().and_then(|file| file.path()) {
+                    let bundle_window = BundleWindow::new(&parent, &dir, busy.clone(), on_installed.clone());
+                    bundle_window.show();
+                }
+            }
+            chooser.destroy();
+        });
+        chooser.show();
+    }
+
+    pub fn new(parent: &ApplicationWindow, dir: &Path, busy: Rc<BusyState>, on_installed: Rc<dyn Fn()>) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Import Update Bundle")
+            .default_width(800)
+            .default_height(500)
+            .build();
+
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some("📥 Import Update Bundle"))));
+        window.set_titlebar(Some(&header_bar));
+
+        let main_vbox = GtkBox::new(Orientation::Vertical, 12);
+        main_vbox.set_margin_top(16);
+        main_vbox.set_margin_bottom(16);
+        main_vbox.set_margin_start(16);
+        main_vbox.set_margin_end(16);
+
+        let files = match bundle::scan_bundle(dir) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("Bundle scan error: {}", e);
+                Vec::new()
+            }
+        };
+        let failed = files.iter().filter(|f| !f.verification.is_ok()).count();
+        let unlisted = files.iter().filter(|f| f.verification == Verification::Unlisted).count();
+
+        let summary = if files.is_empty() {
+            format!("❌ No .deb files were found in {}", dir.display())
+        } else if failed > 0 {
+            format!("❌ {} of {} files failed verification. Download the bundle again before installing.", failed, files.len())
+        } else if unlisted > 0 {
+            format!("⚠️ {} files are ready, {} of them could not be checked against a download manifest.", files.len(), unlisted)
+        } else {
+            format!("✅ All {} files were verified.", files.len())
+        };
+
+        let summary_label = Label::new(Some(&summary));
+        summary_label.set_halign(gtk::Align::Start);
+        summary_label.set_wrap(true);
+        main_vbox.append(&summary_label);
+
+        main_vbox.append(&Separator::new(Orientation::Horizontal));
+
+        let listbox = ListBox::new();
+        listbox.set_selection_mode(gtk::SelectionMode::None);
+        for file in &files {
+            listbox.append(&Self::create_file_row(file));
+        }
+
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .build();
+        scrolled_window.set_child(Some(&listbox));
+        main_vbox.append(&scrolled_window);
+
+        let button_panel = GtkBox::new(Orientation::Horizontal, 12);
+        button_panel.set_halign(gtk::Align::End);
+
+        let install_btn = Button::with_label("⬇️ Install Bundle");
+        install_btn.add_css_class("install-button");
+        install_btn.set_sensitive(!files.is_empty() && failed == 0);
+        button_panel.append(&install_btn);
+        main_vbox.append(&button_panel);
+
+        window.content_area().append(&main_vbox);
+
+        let debs: Vec<String> = files.iter()
+            .map(|f| f.path.to_string_lossy().to_string())
+            .collect();
+        let parent = parent.clone();
+        let window_install = window.clone();
+        install_btn.connect_clicked(move |_| {
+            if !busy.try_begin(Operation::Installing) {
+                return;
+            }
+            window_install.close();
+
+            let progress_window = ProgressWindow::new(&parent);
+            progress_window.show();
+
+            let debs = debs.clone();
+            let busy = busy.clone();
+            let on_installed = on_installed.clone();
+            glib::spawn_future_local(async move {
+                let result = progress_window.install_local_debs_with_progress(&debs).await;
+                busy.finish();
+                if result.is_ok() {
+                    on_installed();
+                }
+            });
+        });
+
+        Self { window }
+    }
+
+    fn create_file_row(file: &BundleFile) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        let hbox = GtkBox::new(Orientation::Horizontal, 12);
+        hbox.set_margin_top(6);
+        hbox.set_margin_bottom(6);
+        hbox.set_margin_start(8);
+        hbox.set_margin_end(8);
+
+        let (icon, detail) = match &file.verification {
+            Verification::Verified => ("✅", "Verified".to_string()),
+            Verification::Unlisted => ("⚠️", "Not in manifest".to_string()),
+            Verification::Mismatch(reason) => ("❌", format!("Mismatch: {}", reason)),
+            Verification::Corrupt(reason) => ("❌", format!("Unreadable: {}", reason)),
+        };
+
+        let icon_label = Label::new(Some(icon));
+        let name_label = Label::new(Some(&file.filename));
+        name_label.set_hexpand(true);
+        name_label.set_halign(gtk::Align::Start);
+        name_label.add_css_class("package-name");
+        let detail_label = Label::new(Some(&detail));
+        detail_label.set_ellipsize(pango::EllipsizeMode::End);
+        detail_label.add_css_class("dim-label");
+
+        hbox.append(&icon_label);
+        hbox.append(&name_label);
+        hbox.append(&detail_label);
+        row.set_child(Some(&hbox));
+        row
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
@@ -0,0 +1,154 @@
+// src/changelog.rs
+
+use crate::error::{Context, Error, Result};
+use crate::model::UpdateType;
+use crate::paths;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// `apt-get changelog` downloads from the distribution's changelog server,
+/// so only a few run at once
+const PREFETCH_CONCURRENCY: usize = 3;
+
+/// Changelogs being downloaded right now, as cache keys
+static IN_FLIGHT: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn cache_dir() -> Option<PathBuf> {
+    Some(paths::cache_dir()?.join("changelogs"))
+}
+
+/// File name of a cached changelog. Characters outside the Debian version
+/// alphabet, such as the `:` of epochs, become `%`.
+pub fn cache_key(package: &str, version: &str) -> String {
+    format!("{}_{}", package, version)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "+-._~".contains(c) { c } else { '%' })
+        .collect()
+}
+
+pub fn cached(package: &str, version: &str) -> Option<String> {
+    fs::read_to_string(cache_dir()?.join(cache_key(package, version))).ok()
+}
+
+fn store(package: &str, version: &str, text: &str) {
+    if let Some(dir) = cache_dir() {
+        let _ = paths::ensure_dir(&dir);
+        if let Err(e) = fs::write(dir.join(cache_key(package, version)), text) {
+            eprintln!("Could not cache the changelog of {}: {}", package, e);
+        }
+    }
+}
+
+fn changelog_args(package: &str, version: &str) -> Vec<String> {
+    vec!["changelog".to_string(), "-qq".to_string(), format!("{}={}", package, version)]
+}
+
+/// The changelog of `package` at `version`, from the cache if it was
+/// fetched before. Blocks while downloading.
+pub fn fetch(package: &str, version: &str) -> Result<String> {
+    if let Some(text) = cached(package, version) {
+        return Ok(text);
+    }
+    let output = std::process::Command::new("apt-get")
+        .args(changelog_args(package, version))
+        .env("LANG", "C")
+        .stdin(Stdio::null())
+        .output()
+        .context("apt-get changelog could not be run")?;
+    if !output.status.success() {
+        return Err(Error::from_status("apt-get changelog", &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+    let text = String::from_utf8_lossy(&output.stdout).into_owned();
+    store(package, version, &text);
+    Ok(text)
+}
+
+/// Downloads the changelogs of `packages` (name and new version) that are
+/// not cached yet on a background thread, a few at a time
+pub fn prefetch(packages: Vec<(String, String)>) {
+    let wanted: Vec<(String, String)> = {
+        let mut in_flight = match IN_FLIGHT.lock() {
+            Ok(in_flight) => in_flight,
+            Err(_) => return,
+        };
+        packages.into_iter()
+            .filter(|(name, version)| {
+                let key = cache_key(name, version);
+                if in_flight.contains(&key) || cached(name, version).is_some() {
+                    return false;
+                }
+                in_flight.push(key);
+                true
+            })
+            .collect()
+    };
+    if wanted.is_empty() {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(_) => return,
+        };
+        runtime.block_on(async {
+            let limit = Arc::new(Semaphore::new(PREFETCH_CONCURRENCY));
+            let mut downloads = JoinSet::new();
+            for (name, version) in wanted {
+                downloads.spawn(download(limit.clone(), name, version));
+            }
+            while downloads.join_next().await.is_some() {}
+        });
+    });
+}
+
+async fn download(limit: Arc<Semaphore>, package: String, version: String) {
+    if let Ok(_permit) = limit.acquire().await {
+        let output = tokio::process::Command::new("apt-get")
+            .args(changelog_args(&package, &version))
+            .env("LANG", "C")
+            .stdin(Stdio::null())
+            .output()
+            .await;
+        if let Ok(output) = output {
+            if output.status.success() {
+                store(&package, &version, &String::from_utf8_lossy(&output.stdout));
+            }
+        }
+    }
+    if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+        let key = cache_key(&package, &version);
+        in_flight.retain(|k| *k != key);
+    }
+}
+
+/// Whether apt has a changelog for this kind of update. Flatpak
+/// applications and snaps have none.
+pub fn has_changelog(update_type: &UpdateType) -> bool {
+    !matches!(update_type, UpdateType::Flatpak | UpdateType::Snap)
+}
+
+/// The part of a Debian changelog that is newer than `installed_version`.
+/// Falls back to the whole text when that version is not mentioned.
+pub fn entries_since(changelog: &str, installed_version: &str) -> String {
+    if installed_version.is_empty() {
+        return changelog.to_string();
+    }
+    let mut kept = Vec::new();
+    for line in changelog.lines() {
+        // Entry headers look like "bash (5.2.15-2+b7) bookworm; urgency=medium"
+        let is_header = !line.starts_with(' ') && line.contains(" (") && line.contains(';');
+        if is_header {
+            let version = line.split_once(" (").and_then(|(_, rest)| rest.split_once(')')).map(|(version, _)| version);
+            if version == Some(installed_version) {
+                return kept.join("\n").trim_end().to_string();
+            }
+        }
+        kept.push(line);
+    }
+    changelog.to_string()
+}
@@ -0,0 +1,151 @@
+// src/changelog_window.rs
+
+use crate::changelog;
+use crate::kev;
+use crate::model::{PackageUpdate, UpdateType};
+use crate::security_news::FeedSource;
+use gtk::prelude::*;
+use gtk::{gio, glib, Box as GtkBox, CheckButton, Dialog, FlowBox, HeaderBar, Label, LinkButton, Orientation, ScrolledWindow, SelectionMode, TextView, WrapMode};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Shows what changed in a pending update, straight from the cache when
+/// the changelog was prefetched
+pub struct ChangelogWindow {
+    window: Dialog,
+}
+
+impl ChangelogWindow {
+    pub fn new(parent: &impl IsA<gtk::Window>, pkg: &PackageUpdate) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title(format!("Changelog of {}", pkg.name))
+            .default_width(720)
+            .default_height(520)
+            .build();
+
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some(&format!("📜 {} {}", pkg.name, pkg.new_version)))));
+        window.set_titlebar(Some(&header_bar));
+
+        // Security updates list the CVEs they fix, linked to the tracker
+        let cve_pane = GtkBox::new(Orientation::Vertical, 4);
+        cve_pane.set_margin_top(16);
+        cve_pane.set_margin_start(16);
+        cve_pane.set_margin_end(16);
+        cve_pane.set_visible(pkg.update_type == UpdateType::Security);
+        let cve_title = Label::new(Some("🛡️ Looking for fixed vulnerabilities..."));
+        cve_title.set_halign(gtk::Align::Start);
+        cve_title.add_css_class("package-name");
+        cve_pane.append(&cve_title);
+        let cve_links = FlowBox::new();
+        cve_links.set_selection_mode(SelectionMode::None);
+        cve_links.set_max_children_per_line(6);
+        cve_pane.append(&cve_links);
+        window.content_area().append(&cve_pane);
+
+        let text_view = TextView::new();
+        text_view.set_editable(false);
+        text_view.set_monospace(true);
+        text_view.set_wrap_mode(WrapMode::WordChar);
+
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .margin_top(16)
+            .margin_bottom(16)
+            .margin_start(16)
+            .margin_end(16)
+            .child(&text_view)
+            .build();
+        window.content_area().append(&scrolled_window);
+
+        // Only the entries newer than the installed version, unless asked
+        let show_all = CheckButton::with_label("Show older entries too");
+        show_all.set_margin_start(16);
+        show_all.set_margin_bottom(12);
+        show_all.set_sensitive(false);
+        window.content_area().append(&show_all);
+
+        let buffer = text_view.buffer();
+        let current_version = pkg.current_version.clone();
+        let full_text: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let list_cves = {
+            let current_version = pkg.current_version.clone();
+            move |text: &str| show_fixed_cves(&cve_title, &cve_links, &kev::fixed_cves(text, &current_version))
+        };
+
+        let show = {
+            let buffer = buffer.clone();
+            let show_all = show_all.clone();
+            let full_text = full_text.clone();
+            move || {
+                if let Some(text) = full_text.borrow().as_deref() {
+                    if show_all.is_active() {
+                        buffer.set_text(text);
+                    } else {
+                        buffer.set_text(&changelog::entries_since(text, &current_version));
+                    }
+                }
+            }
+        };
+        let show = Rc::new(show);
+        let show_on_toggle = show.clone();
+        show_all.connect_toggled(move |_| show_on_toggle());
+
+        match changelog::cached(&pkg.name, &pkg.new_version) {
+            Some(text) => {
+                list_cves(&text);
+                full_text.replace(Some(text));
+                show_all.set_sensitive(true);
+                show();
+            }
+            None => {
+                buffer.set_text("⏳ Downloading the changelog...");
+                let name = pkg.name.clone();
+                let version = pkg.new_version.clone();
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(move || changelog::fetch(&name, &version)).await;
+                    match result {
+                        Ok(Ok(text)) => {
+                            list_cves(&text);
+                            full_text.replace(Some(text));
+                            show_all.set_sensitive(true);
+                            show();
+                        }
+                        Ok(Err(e)) => {
+                            cve_pane.set_visible(false);
+                            buffer.set_text(&format!("❌ The changelog could not be downloaded:\n\n{}", e));
+                        }
+                        Err(_) => {
+                            cve_pane.set_visible(false);
+                            buffer.set_text("❌ The changelog could not be downloaded");
+                        }
+                    }
+                });
+            }
+        }
+
+        Self { window }
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
+
+/// Fills the vulnerabilities pane with one tracker link per CVE
+fn show_fixed_cves(title: &Label, links: &FlowBox, cves: &[String]) {
+    if cves.is_empty() {
+        title.set_text("🛡️ The changelog names no CVE for this update");
+        return;
+    }
+    title.set_text(&format!("🛡️ Fixes {} {}", cves.len(), if cves.len() == 1 { "vulnerability" } else { "vulnerabilities" }));
+    let tracker = FeedSource::detect().unwrap_or(FeedSource::Debian);
+    for cve in cves {
+        let link = LinkButton::with_label(&tracker.cve_url(cve), cve);
+        link.set_tooltip_text(Some("Open in the security tracker"));
+        links.insert(&link, -1);
+    }
+}
@@ -0,0 +1,244 @@
+// src/cli.rs
+
+use crate::apt::{self, PackageRequest, VersionSelector};
+use crate::config::{self, AdminPolicy};
+use crate::error::{Context, Error, Result};
+use crate::format;
+use crate::kernel_manager;
+use crate::model::{PackageUpdate, UpdateType};
+use crate::policy;
+use crate::util::json_string;
+
+/// Lists the pending updates on stdout without starting the GUI
+pub const CHECK_ARG: &str = "--check";
+/// Installs pending updates without starting the GUI. `--install` already
+/// installs MeaUpdater itself.
+pub const INSTALL_UPDATES_ARG: &str = "--install-updates";
+
+/// Exit status of `--check` when updates are pending, as with `yum check-update`
+pub const UPDATES_PENDING_STATUS: i32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOptions {
+    pub format: OutputFormat,
+    /// Install every pending update instead of the named ones
+    pub all: bool,
+    pub packages: Vec<String>,
+}
+
+/// Parses `--json`, `--format table|json`, `--all` and package names
+pub fn parse_options(args: &[String]) -> Result<CliOptions> {
+    let mut options = CliOptions { format: OutputFormat::Table, all: false, packages: Vec::new() };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let format = match arg.as_str() {
+            "--json" => Some("json"),
+            "--format" => Some(args.next().context("--format needs table or json")?.as_str()),
+            other => other.strip_prefix("--format="),
+        };
+        if let Some(format) = format {
+            options.format = match format {
+                "table" => OutputFormat::Table,
+                "json" => OutputFormat::Json,
+                other => return Err(Error::Other(format!("Unknown output format: {}", other))),
+            };
+            continue;
+        }
+
+        match arg.as_str() {
+            "--all" => options.all = true,
+            other if other.starts_with('-') => return Err(Error::Other(format!("Unknown option: {}", other))),
+            name => options.packages.push(name.to_string()),
+        }
+    }
+    Ok(options)
+}
+
+pub fn type_name(update_type: &UpdateType) -> &'static str {
+    match update_type {
+        UpdateType::Security => "security",
+        UpdateType::Software => "software",
+        UpdateType::Kernel => "kernel",
+        UpdateType::Firmware => "firmware",
+        UpdateType::Flatpak => "flatpak",
+        UpdateType::Snap => "snap",
+    }
+}
+
+/// Aligned columns for reading in a terminal
+pub fn format_table(packages: &[PackageUpdate]) -> String {
+    if packages.is_empty() {
+        return "✅ The system is up to date\n".to_string();
+    }
+
+    let mut rows = vec![["PACKAGE".to_string(), "TYPE".to_string(), "CURRENT".to_string(), "NEW".to_string(), "SIZE".to_string()]];
+    for pkg in packages {
+        rows.push([
+            pkg.name.clone(),
+            type_name(&pkg.update_type).to_string(),
+            if pkg.current_version.is_empty() { "-".to_string() } else { pkg.current_version.clone() },
+            pkg.new_version.clone(),
+            format::optional_size(pkg.size),
+        ]);
+    }
+
+    let mut widths = [0; 5];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in &rows {
+        let cells: Vec<String> = row.iter().zip(widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+        table.push_str(cells.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// One object per update, sizes in bytes, for scripts and monitoring
+pub fn format_json(packages: &[PackageUpdate]) -> String {
+    let items: Vec<String> = packages.iter()
+        .map(|pkg| format!(
+            "  {{\"name\": {}, \"type\": {}, \"current_version\": {}, \"new_version\": {}, \"size\": {}}}",
+            json_string(&pkg.name),
+            json_string(type_name(&pkg.update_type)),
+            json_string(&pkg.current_version),
+            json_string(&pkg.new_version),
+            pkg.size.map(|size| size.to_string()).unwrap_or_else(|| "null".to_string())
+        ))
+        .collect();
+    if items.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", items.join(",\n"))
+    }
+}
+
+/// The packages `options` asks to install, all of them pending updates
+pub fn select(packages: &[PackageUpdate], options: &CliOptions) -> Result<Vec<String>> {
+    if options.all {
+        return Ok(packages.iter().map(|pkg| pkg.name.clone()).collect());
+    }
+    if options.packages.is_empty() {
+        return Err(Error::Other("Name the packages to install, or pass --all".to_string()));
+    }
+    let unknown: Vec<&str> = options.packages.iter()
+        .filter(|name| !packages.iter().any(|pkg| pkg.name == **name))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(Error::Other(format!("No update is pending for: {}", unknown.join(", "))));
+    }
+    Ok(options.packages.clone())
+}
+
+/// Splits the named packages into plain names, which must be pending
+/// updates, and `pkg=version` or `pkg/suite` requests, which need not be
+pub fn pinned_requests(options: &CliOptions) -> Result<(Vec<String>, Vec<PackageRequest>)> {
+    let mut names = Vec::new();
+    let mut pinned = Vec::new();
+    for package in &options.packages {
+        let request = PackageRequest::parse(package)?;
+        if request.selector == VersionSelector::Candidate {
+            names.push(request.name);
+        } else {
+            pinned.push(request);
+        }
+    }
+    Ok((names, pinned))
+}
+
+/// Adds the rest of the kernel set when one of its packages is selected,
+/// since its packages are only installed together
+pub fn with_kernel_set(pending: &[PackageUpdate], selected: Vec<String>) -> Vec<String> {
+    let kernel_set = kernel_manager::kernel_update_set(pending);
+    if selected.iter().any(|name| kernel_set.contains(name)) {
+        kernel_manager::expand_kernel_set(kernel_set.first().cloned().into_iter().chain(selected).collect(), &kernel_set)
+    } else {
+        selected
+    }
+}
+
+/// Pending updates, limited to security updates when the policy says so
+pub fn pending_updates() -> Result<Vec<PackageUpdate>> {
+    let mut packages = apt::get_upgradable_packages()?;
+    if AdminPolicy::load().security_only {
+        packages.retain(|pkg| pkg.from_security);
+    }
+    Ok(packages)
+}
+
+/// Entry point for `meaupdater --check`: 0 when up to date,
+/// `UPDATES_PENDING_STATUS` when updates are pending, 1 on errors
+pub fn run_check(args: &[String]) -> i32 {
+    let result = parse_options(args).and_then(|options| Ok((options, pending_updates()?)));
+    match result {
+        Ok((options, packages)) => {
+            match options.format {
+                OutputFormat::Table => print!("{}", format_table(&packages)),
+                OutputFormat::Json => print!("{}", format_json(&packages)),
+            }
+            if packages.is_empty() { 0 } else { UPDATES_PENDING_STATUS }
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            1
+        }
+    }
+}
+
+/// Entry point for `meaupdater --install-updates [--all | PACKAGE...]`
+pub fn run_install(args: &[String]) -> i32 {
+    if config::check_only_enforced() {
+        eprintln!("❌ Your administrator allows checking for updates only");
+        return 1;
+    }
+
+    let result = parse_options(args).and_then(|options| {
+        let (names, pinned) = pinned_requests(&options)?;
+        if !pinned.is_empty() && AdminPolicy::load().security_only {
+            return Err(Error::Other("Your administrator allows installing security updates only".to_string()));
+        }
+        let plain = CliOptions { packages: names, ..options };
+        let selected = if pinned.is_empty() || plain.all || !plain.packages.is_empty() {
+            // Naming one package of the kernel set installs all of it
+            let pending = pending_updates()?;
+            let selected = select(&pending, &plain)?;
+            with_kernel_set(&pending, selected)
+        } else {
+            Vec::new()
+        };
+        if selected.is_empty() && pinned.is_empty() {
+            println!("✅ The system is up to date");
+            return Ok(());
+        }
+        if !selected.is_empty() {
+            println!("⬇️ Installing {}", selected.join(", "));
+            policy::install_packages(&selected)?;
+        }
+        if !pinned.is_empty() {
+            let args: Vec<String> = pinned.iter().map(PackageRequest::to_arg).collect();
+            println!("⬇️ Installing {}", args.join(", "));
+            policy::install_requests(&pinned)?;
+        }
+        println!("✅ Updates installed successfully!");
+        Ok(())
+    });
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// src/conffile.rs
+
+use std::path::Path;
+use std::process::Command;
+
+/// Answer given to dpkg's conffile prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConffileChoice {
+    /// Keep the locally modified file (dpkg's default)
+    KeepCurrent,
+    /// Replace it with the package maintainer's version
+    InstallNew,
+}
+
+impl ConffileChoice {
+    /// Text written to dpkg's stdin
+    pub fn answer(&self) -> &'static str {
+        match self {
+            ConffileChoice::KeepCurrent => "N\n",
+            ConffileChoice::InstallNew => "Y\n",
+        }
+    }
+}
+
+/// Extracts the path from dpkg's `Configuration file '/etc/foo'` line
+pub fn parse_conffile_line(line: &str) -> Option<String> {
+    let rest = line.trim().strip_prefix("Configuration file '")?;
+    let path = rest.strip_suffix('\'')?;
+    if path.is_empty() {
+        None
+    } else {
+        Some(path.to_string())
+    }
+}
+
+/// The last full line dpkg prints before waiting for an answer. The actual
+/// `*** foo (Y/I/N/O/D/Z) [default=N] ?` question has no trailing newline,
+/// so it never shows up as a line while dpkg is waiting.
+pub fn is_prompt_end(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("The default action is to keep your current version")
+        || line.starts_with("The default action is to install the new version")
+}
+
+/// Side-by-side diff of the current file and dpkg's `.dpkg-new` copy, with
+/// unchanged lines left out
+pub fn side_by_side_diff(path: &str) -> String {
+    let new_path = format!("{}.dpkg-new", path);
+    if !Path::new(&new_path).exists() {
+        return format!("The new version ({}) could not be found.", new_path);
+    }
+
+    match Command::new("diff")
+        .args(&["--side-by-side", "--suppress-common-lines", "--expand-tabs", "--width=140", path, &new_path])
+        .output()
+    {
+        // diff exits with 1 when the files differ
+        Ok(output) if output.status.code() == Some(0) || output.status.code() == Some(1) => {
+            let diff = String::from_utf8_lossy(&output.stdout).to_string();
+            if diff.trim().is_empty() {
+                "The files only differ in whitespace.".to_string()
+            } else {
+                diff
+            }
+        }
+        Ok(output) => format!(
+            "The files could not be compared:\n{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => format!("The files could not be compared: {}", e),
+    }
+}
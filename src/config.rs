@@ -0,0 +1,359 @@
+// src/config.rs
+
+use gio::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+use crate::install;
+use crate::paths;
+use crate::policy::{self, AutoInstallPolicy, Schedule, ScheduleFrequency, UpdateClass};
+use crate::sysroot;
+
+/// Choices offered for the automatic update check, in minutes
+pub const CHECK_INTERVALS: &[(u32, &str)] = &[
+    (15, "Every 15 minutes"),
+    (30, "Every 30 minutes"),
+    (60, "Every hour"),
+    (180, "Every 3 hours"),
+    (360, "Every 6 hours"),
+    (720, "Every 12 hours"),
+    (1440, "Daily"),
+];
+
+const MIN_INTERVAL: u32 = 15;
+const MAX_INTERVAL: u32 = 1440;
+
+/// Main window size on the first start
+pub const DEFAULT_WIDTH: i32 = 950;
+pub const DEFAULT_HEIGHT: i32 = 625;
+/// Remembered sizes below this are ignored, so the window cannot get lost
+const MIN_WINDOW_SIZE: i32 = 400;
+
+/// System-wide lockdown written by the administrator
+pub const POLICY_FILE: &str = "/etc/meaupdater/policy.toml";
+
+/// User settings stored in `~/.config/meaupdater/config.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub automatic_checks: bool,
+    pub check_interval_minutes: u32,
+    /// Update classes the background check installs without asking
+    pub auto_install: AutoInstallPolicy,
+    /// When the background check runs and whether it may install
+    pub schedule: Schedule,
+    /// Weekly summary as a desktop notification
+    pub weekly_digest: bool,
+    /// Weekly summary as an HTML report in the Documents folder
+    pub weekly_report: bool,
+    /// Sections of the grouped update list the user collapsed, e.g. "software"
+    pub collapsed_groups: Vec<String>,
+    /// Desktop notification when new updates are found
+    pub notify_updates: bool,
+    pub sort_by_type: bool,
+    pub sort_by_size: bool,
+    /// Size of the main window when it was last closed
+    pub window_width: i32,
+    pub window_height: i32,
+    /// Save the package and module state before installing or removing a driver
+    pub driver_backup: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            automatic_checks: true,
+            check_interval_minutes: 30,
+            auto_install: AutoInstallPolicy::default(),
+            schedule: Schedule::default(),
+            weekly_digest: false,
+            weekly_report: false,
+            collapsed_groups: Vec::new(),
+            notify_updates: true,
+            sort_by_type: false,
+            sort_by_size: false,
+            window_width: DEFAULT_WIDTH,
+            window_height: DEFAULT_HEIGHT,
+            driver_backup: true,
+        }
+    }
+}
+
+impl Config {
+    /// Reads `key = value` lines; unknown keys and bad values keep the default
+    pub fn parse(content: &str, defaults: Config) -> Self {
+        let mut config = defaults;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim().trim_matches('"')),
+                None => continue,
+            };
+
+            match key {
+                "automatic_checks" => {
+                    if let Ok(enabled) = value.parse() {
+                        config.automatic_checks = enabled;
+                    }
+                }
+                "check_interval_minutes" => {
+                    if let Ok(minutes) = value.parse::<u32>() {
+                        config.check_interval_minutes = minutes.clamp(MIN_INTERVAL, MAX_INTERVAL);
+                    }
+                }
+                "schedule" => {
+                    if let Some(frequency) = ScheduleFrequency::parse(value) {
+                        config.schedule.frequency = frequency;
+                    }
+                }
+                "schedule_time" => {
+                    if let Some(minutes) = policy::parse_time_of_day(value) {
+                        config.schedule.time_of_day = minutes;
+                    }
+                }
+                "schedule_weekday" => {
+                    if let Some(weekday) = policy::parse_weekday(value) {
+                        config.schedule.weekday = weekday;
+                    }
+                }
+                "notify_only" => {
+                    if let Ok(enabled) = value.parse() {
+                        config.schedule.notify_only = enabled;
+                    }
+                }
+                "weekly_digest" => {
+                    if let Ok(enabled) = value.parse() {
+                        config.weekly_digest = enabled;
+                    }
+                }
+                "notify_updates" => {
+                    if let Ok(enabled) = value.parse() {
+                        config.notify_updates = enabled;
+                    }
+                }
+                "sort_by_type" => {
+                    if let Ok(enabled) = value.parse() {
+                        config.sort_by_type = enabled;
+                    }
+                }
+                "sort_by_size" => {
+                    if let Ok(enabled) = value.parse() {
+                        config.sort_by_size = enabled;
+                    }
+                }
+                "driver_backup" => {
+                    if let Ok(enabled) = value.parse() {
+                        config.driver_backup = enabled;
+                    }
+                }
+                "window_width" => {
+                    if let Some(size) = value.parse::<i32>().ok().filter(|size| *size >= MIN_WINDOW_SIZE) {
+                        config.window_width = size;
+                    }
+                }
+                "window_height" => {
+                    if let Some(size) = value.parse::<i32>().ok().filter(|size| *size >= MIN_WINDOW_SIZE) {
+                        config.window_height = size;
+                    }
+                }
+                "collapsed_groups" => {
+                    config.collapsed_groups = value.split(',')
+                        .map(str::trim)
+                        .filter(|group| !group.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+                "weekly_report" => {
+                    if let Ok(enabled) = value.parse() {
+                        config.weekly_report = enabled;
+                    }
+                }
+                _ => {
+                    let class = UpdateClass::ALL.iter().find(|class| class.config_key() == key);
+                    if let (Some(class), Ok(allowed)) = (class, value.parse()) {
+                        config.auto_install.set(*class, allowed);
+                    }
+                }
+            }
+        }
+
+        config
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut toml = format!(
+            "# MeaUpdater settings\nautomatic_checks = {}\ncheck_interval_minutes = {}\nweekly_digest = {}\nweekly_report = {}\n",
+            self.automatic_checks, self.check_interval_minutes, self.weekly_digest, self.weekly_report
+        );
+        toml.push_str(&format!(
+            "schedule = \"{}\"\nschedule_time = \"{}\"\nschedule_weekday = \"{}\"\nnotify_only = {}\n",
+            self.schedule.frequency.as_str(),
+            policy::format_time_of_day(self.schedule.time_of_day),
+            policy::WEEKDAYS[(self.schedule.weekday.clamp(1, 7) - 1) as usize],
+            self.schedule.notify_only
+        ));
+        for class in UpdateClass::ALL {
+            toml.push_str(&format!("{} = {}\n", class.config_key(), self.auto_install.allows(class)));
+        }
+        toml.push_str(&format!(
+            "notify_updates = {}\nsort_by_type = {}\nsort_by_size = {}\nwindow_width = {}\nwindow_height = {}\ndriver_backup = {}\n",
+            self.notify_updates, self.sort_by_type, self.sort_by_size, self.window_width, self.window_height, self.driver_backup
+        ));
+        toml.push_str(&format!("collapsed_groups = \"{}\"\n", self.collapsed_groups.join(",")));
+        toml
+    }
+
+    pub fn is_group_collapsed(&self, group: &str) -> bool {
+        self.collapsed_groups.iter().any(|collapsed| collapsed == group)
+    }
+
+    pub fn set_group_collapsed(&mut self, group: &str, collapsed: bool) {
+        self.collapsed_groups.retain(|existing| existing != group);
+        if collapsed {
+            self.collapsed_groups.push(group.to_string());
+        }
+    }
+
+    pub fn check_interval_seconds(&self) -> u32 {
+        self.check_interval_minutes * 60
+    }
+
+    /// Settings from the config file on top of the system defaults, with
+    /// the values pinned by the administrator's policy
+    pub fn load() -> Self {
+        let defaults = system_defaults();
+        let config = match config_file().and_then(|path| fs::read_to_string(path).ok()) {
+            Some(content) => Self::parse(&content, defaults),
+            None => defaults,
+        };
+        AdminPolicy::load().apply(config)
+    }
+
+    pub fn save(&self) {
+        let path = match config_file() {
+            Some(path) => path,
+            None => return,
+        };
+        if let Some(parent) = path.parent() {
+            let _ = paths::ensure_dir(parent);
+        }
+        if let Err(e) = fs::write(&path, self.to_toml()) {
+            eprintln!("Could not save settings: {}", e);
+        }
+    }
+}
+
+/// Restrictions from `/etc/meaupdater/policy.toml`. They override the
+/// user's settings and cannot be changed from the Preferences window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AdminPolicy {
+    pub hide_kernel_manager: bool,
+    pub hide_driver_manager: bool,
+    pub hide_repository_manager: bool,
+    /// Only security updates are listed and installed
+    pub security_only: bool,
+    pub automatic_checks: Option<bool>,
+    pub check_interval_minutes: Option<u32>,
+}
+
+impl AdminPolicy {
+    /// Reads the same `key = value` lines as the user's config file
+    pub fn parse(content: &str) -> Self {
+        let mut policy = AdminPolicy::default();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim().trim_matches('"')),
+                None => continue,
+            };
+            let flag = value.parse::<bool>().ok();
+
+            match key {
+                "hide_kernel_manager" => policy.hide_kernel_manager = flag.unwrap_or(false),
+                "hide_driver_manager" => policy.hide_driver_manager = flag.unwrap_or(false),
+                "hide_repository_manager" => policy.hide_repository_manager = flag.unwrap_or(false),
+                "security_only" => policy.security_only = flag.unwrap_or(false),
+                "automatic_checks" => policy.automatic_checks = flag,
+                "check_interval_minutes" => {
+                    policy.check_interval_minutes = value.parse::<u32>().ok()
+                        .map(|minutes| minutes.clamp(MIN_INTERVAL, MAX_INTERVAL));
+                }
+                _ => eprintln!("Unknown key in {}: {}", POLICY_FILE, key),
+            }
+        }
+
+        policy
+    }
+
+    pub fn load() -> Self {
+        fs::read_to_string(sysroot::path(POLICY_FILE))
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// `config` with the pinned values put in
+    pub fn apply(&self, mut config: Config) -> Config {
+        if let Some(enabled) = self.automatic_checks {
+            config.automatic_checks = enabled;
+        }
+        // A pinned interval rules out daily and weekly schedules
+        if let Some(minutes) = self.check_interval_minutes {
+            config.check_interval_minutes = minutes;
+            config.schedule.frequency = ScheduleFrequency::Interval;
+        }
+        config
+    }
+}
+
+/// Defaults from the installed GSettings schema, so administrators can
+/// change them with a vendor override
+fn system_defaults() -> Config {
+    let installed = gio::SettingsSchemaSource::default()
+        .and_then(|source| source.lookup(install::APP_ID, true))
+        .is_some();
+    if !installed {
+        return Config::default();
+    }
+
+    let settings = gio::Settings::new(install::APP_ID);
+    Config {
+        automatic_checks: settings.boolean("automatic-checks"),
+        check_interval_minutes: settings.uint("check-interval-minutes").clamp(MIN_INTERVAL, MAX_INTERVAL),
+        auto_install: AutoInstallPolicy::default(),
+        schedule: Schedule::default(),
+        weekly_digest: false,
+        weekly_report: false,
+        ..Config::default()
+    }
+}
+
+/// Starts MeaUpdater in check-only mode, where nothing can be installed,
+/// removed or reconfigured
+pub const CHECK_ONLY_ARG: &str = "--check-only";
+
+/// Whether the administrator locked MeaUpdater to check-only mode with the
+/// `check-only` GSettings key. It is not read from the user's config file.
+pub fn check_only_enforced() -> bool {
+    let installed = gio::SettingsSchemaSource::default()
+        .and_then(|source| source.lookup(install::APP_ID, true))
+        .map_or(false, |schema| schema.has_key("check-only"));
+    installed && gio::Settings::new(install::APP_ID).boolean("check-only")
+}
+
+/// Check-only mode from the command line or the administrator's setting.
+/// Returns the remaining arguments for GApplication, which rejects unknown options.
+pub fn check_only_mode(args: &[String], enforced: bool) -> (bool, Vec<String>) {
+    let requested = args.iter().skip(1).any(|arg| arg == CHECK_ONLY_ARG);
+    let remaining = args.iter().filter(|arg| *arg != CHECK_ONLY_ARG).cloned().collect();
+    (requested || enforced, remaining)
+}
+
+fn config_file() -> Option<PathBuf> {
+    paths::config_file("config.toml")
+}
@@ -0,0 +1,253 @@
+// src/digest.rs
+
+use crate::apt;
+use crate::error::{Context, Result};
+use crate::format;
+use crate::history::{self, ChangeAction, HistoryEntry};
+use crate::install;
+use crate::model::PackageUpdate;
+use crate::paths;
+use gio::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// How often the digest is sent
+pub const DIGEST_INTERVAL_SECS: u64 = 7 * 24 * 60 * 60;
+
+const NOTIFICATION_ID: &str = "weekly-digest";
+
+/// What happened to the system during one digest period
+#[derive(Debug, Clone, PartialEq)]
+pub struct Digest {
+    pub since: u64,
+    pub until: u64,
+    /// Successful operations of the period, oldest first
+    pub operations: Vec<HistoryEntry>,
+    pub failed_operations: usize,
+    pub pending: usize,
+    pub pending_security: usize,
+    /// Boots during the period, when the journal could tell
+    pub reboots: Option<usize>,
+}
+
+impl Digest {
+    pub fn upgraded(&self) -> usize {
+        self.operations.iter().map(|entry| entry.changes_of(ChangeAction::Upgraded).count()).sum()
+    }
+
+    pub fn installed(&self) -> usize {
+        self.operations.iter().map(|entry| entry.changes_of(ChangeAction::Installed).count()).sum()
+    }
+
+    pub fn removed(&self) -> usize {
+        self.operations.iter().map(|entry| entry.changes_of(ChangeAction::Removed).count()).sum()
+    }
+}
+
+/// Builds the digest of the week before `now` from the history (newest
+/// first, as `history::load` returns it), the pending updates and boot times
+pub fn build(history: &[HistoryEntry], pending: &[PackageUpdate], boots: Option<&[u64]>, now: u64) -> Digest {
+    let since = now.saturating_sub(DIGEST_INTERVAL_SECS);
+    let in_period: Vec<&HistoryEntry> = history.iter()
+        .filter(|entry| entry.timestamp >= since && entry.timestamp <= now)
+        .collect();
+
+    let mut operations: Vec<HistoryEntry> = in_period.iter().filter(|entry| entry.succeeded).map(|entry| (*entry).clone()).collect();
+    operations.sort_by_key(|entry| entry.timestamp);
+
+    Digest {
+        since,
+        until: now,
+        operations,
+        failed_operations: in_period.iter().filter(|entry| !entry.succeeded).count(),
+        pending: pending.len(),
+        pending_security: pending.iter().filter(|pkg| pkg.from_security).count(),
+        reboots: boots.map(|boots| boots.iter().filter(|time| **time >= since && **time <= now).count()),
+    }
+}
+
+/// Whether a week has passed since the last digest
+pub fn is_due(last_sent: Option<u64>, now: u64) -> bool {
+    match last_sent {
+        Some(last) => now.saturating_sub(last) >= DIGEST_INTERVAL_SECS,
+        None => true,
+    }
+}
+
+/// "2024-11-05" for a Unix time, in UTC
+pub fn date(timestamp: u64) -> String {
+    // Civil-from-days, after Howard Hinnant's date algorithms
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Short text for the desktop notification
+pub fn notification_body(digest: &Digest) -> String {
+    let mut lines = vec![format!("📦 This week: {}", apt::change_summary(digest.upgraded(), digest.installed(), digest.removed()))];
+    if digest.failed_operations > 0 {
+        lines.push(format!("❌ {} failed", format::plural(digest.failed_operations as u64, "operation", "operations")));
+    }
+    if let Some(reboots) = digest.reboots {
+        lines.push(format!("🔁 {}", format::plural(reboots as u64, "restart", "restarts")));
+    }
+    lines.push(match (digest.pending, digest.pending_security) {
+        (0, _) => "✅ No updates pending".to_string(),
+        (pending, 0) => format!("⏳ {} pending", format::plural(pending as u64, "update", "updates")),
+        (pending, security) => format!("⏳ {} pending, {} of them security", format::plural(pending as u64, "update", "updates"), security),
+    });
+    lines.join("\n")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Standalone HTML page listing every change of the period
+pub fn format_html(digest: &Digest) -> String {
+    let mut rows = String::new();
+    for entry in &digest.operations {
+        for change in &entry.changes {
+            rows.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                date(entry.timestamp),
+                escape_html(change.action.as_str()),
+                escape_html(&change.name),
+                escape_html(change.from.as_deref().unwrap_or("")),
+                escape_html(change.to.as_deref().unwrap_or(""))
+            ));
+        }
+    }
+    if rows.is_empty() {
+        rows.push_str("<tr><td colspan=\"5\">No packages were changed.</td></tr>\n");
+    }
+
+    let reboots = digest.reboots
+        .map(|reboots| format!("<li>Restarts: {}</li>\n", reboots))
+        .unwrap_or_default();
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>MeaUpdater weekly summary</title>\n\
+         <style>body {{ font-family: sans-serif; margin: 2em; }} table {{ border-collapse: collapse; }} \
+         td, th {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}</style>\n</head>\n<body>\n\
+         <h1>MeaUpdater weekly summary</h1>\n<p>{} to {}</p>\n<ul>\n\
+         <li>Upgraded: {}</li>\n<li>Newly installed: {}</li>\n<li>Removed: {}</li>\n<li>Failed operations: {}</li>\n\
+         {}<li>Pending updates: {} ({} security)</li>\n</ul>\n\
+         <table>\n<tr><th>Date</th><th>Change</th><th>Package</th><th>From</th><th>To</th></tr>\n{}</table>\n</body>\n</html>\n",
+        date(digest.since),
+        date(digest.until),
+        digest.upgraded(),
+        digest.installed(),
+        digest.removed(),
+        digest.failed_operations,
+        reboots,
+        digest.pending,
+        digest.pending_security,
+        rows
+    )
+}
+
+/// Boot times from `journalctl --list-boots -o json`, in Unix seconds
+pub fn parse_boot_times(json: &str) -> Vec<u64> {
+    let key = "\"first_entry\"";
+    let mut times = Vec::new();
+    let mut rest = json;
+    while let Some(start) = rest.find(key) {
+        rest = rest[start + key.len()..].trim_start().trim_start_matches(':').trim_start();
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if let Ok(micros) = digits.parse::<u64>() {
+            times.push(micros / 1_000_000);
+        }
+    }
+    times
+}
+
+fn boot_times() -> Option<Vec<u64>> {
+    let output = Command::new("journalctl")
+        .args(["--list-boots", "--no-pager", "-o", "json"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Older journalctl versions print a table instead
+    let json = String::from_utf8_lossy(&output.stdout);
+    if !json.contains("first_entry") {
+        return None;
+    }
+    Some(parse_boot_times(&json))
+}
+
+fn state_file() -> Option<PathBuf> {
+    paths::state_file("last_digest")
+}
+
+fn last_sent() -> Option<u64> {
+    fs::read_to_string(state_file()?).ok()?.trim().parse().ok()
+}
+
+fn mark_sent(now: u64) {
+    if let Some(path) = state_file() {
+        if let Some(parent) = path.parent() {
+            let _ = paths::ensure_dir(parent);
+        }
+        let _ = fs::write(path, now.to_string());
+    }
+}
+
+/// Writes the HTML report to the Documents folder
+pub fn write_report(digest: &Digest) -> Result<PathBuf> {
+    let dir = glib::user_special_dir(glib::UserDirectory::Documents)
+        .or_else(|| std::env::var("HOME").ok().map(|home| PathBuf::from(home).join("Documents")))
+        .context("The Documents folder is not known")?;
+    fs::create_dir_all(&dir).context("Could not create the Documents folder")?;
+    let path = dir.join(format!("meaupdater-weekly-{}.html", date(digest.until)));
+    fs::write(&path, format_html(digest)).context("Could not save the weekly summary")?;
+    Ok(path)
+}
+
+/// Sends the notification and/or writes the report once a week. The
+/// first call only starts the clock, so a new install does not report an
+/// empty week.
+pub fn send_if_due(pending: &[PackageUpdate], notify: bool, report: bool) {
+    if !notify && !report {
+        return;
+    }
+    let now = format::now();
+    let last = last_sent();
+    if last.is_none() {
+        mark_sent(now);
+        return;
+    }
+    if !is_due(last, now) {
+        return;
+    }
+    mark_sent(now);
+
+    let boots = boot_times();
+    let digest = build(&history::load(), pending, boots.as_deref(), now);
+
+    let mut body = notification_body(&digest);
+    if report {
+        match write_report(&digest) {
+            Ok(path) => body.push_str(&format!("\n📄 Report saved to {}", path.display())),
+            Err(e) => eprintln!("Weekly report could not be written: {}", e),
+        }
+    }
+    if notify {
+        if let Some(app) = gio::Application::default() {
+            let notification = gio::Notification::new("MeaUpdater weekly summary");
+            notification.set_icon(&install::app_icon());
+            notification.set_body(Some(&body));
+            app.send_notification(Some(NOTIFICATION_ID), &notification);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// src/download_plan.rs
+
+use crate::error::{Context, Error, Result};
+use std::process::Command;
+use crate::util::shell_quote;
+
+/// Written next to the downloaded files so the bundle can be verified on import
+pub const BUNDLE_MANIFEST: &str = "meaupdater-bundle.txt";
+
+/// One file apt would download for a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadUri {
+    pub uri: String,
+    pub filename: String,
+    pub size: u64,
+    /// Checksum as printed by apt, e.g. `SHA256:abc...`
+    pub hash: String,
+}
+
+impl DownloadUri {
+    /// Splits `hash` into the checksum tool and the hex digest
+    pub fn checksum(&self) -> Option<(&'static str, &str)> {
+        let (kind, digest) = self.hash.split_once(':')?;
+        let tool = match kind.to_ascii_uppercase().as_str() {
+            "SHA512" => "sha512sum",
+            "SHA256" => "sha256sum",
+            "SHA1" => "sha1sum",
+            "MD5SUM" | "MD5" => "md5sum",
+            _ => return None,
+        };
+        Some((tool, digest))
+    }
+}
+
+/// Parses the output of `apt-get --print-uris`. Each download is printed as
+/// `'uri' filename size hash`; everything else is ignored.
+pub fn parse_print_uris(output: &str) -> Vec<DownloadUri> {
+    let mut uris = Vec::new();
+
+    for line in output.lines() {
+        let line = line.trim();
+        if !line.starts_with('\'') {
+            continue;
+        }
+
+        let end = match line[1..].find('\'') {
+            Some(end) => end + 1,
+            None => continue,
+        };
+        let uri = &line[1..end];
+        let fields: Vec<&str> = line[end + 1..].split_whitespace().collect();
+        if fields.len() < 2 {
+            continue;
+        }
+
+        uris.push(DownloadUri {
+            uri: uri.to_string(),
+            filename: fields[0].to_string(),
+            size: fields[1].parse().unwrap_or(0),
+            hash: fields.get(2).unwrap_or(&"").to_string(),
+        });
+    }
+
+    uris
+}
+
+/// Resolves everything installing `packages` would download, without
+/// downloading or installing anything
+pub fn get_download_plan(packages: &[String]) -> Result<Vec<DownloadUri>> {
+    let output = Command::new("apt-get")
+        .args(&["install", "--print-uris", "-qq", "-y", "-o", "Debug::NoLocking=1"])
+        .args(packages)
+        .output()
+        .context("apt-get could not be run")?;
+
+    if !output.status.success() {
+        return Err(Error::from_status("Resolving the transaction", &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+
+    Ok(parse_print_uris(&String::from_utf8_lossy(&output.stdout)))
+}
+
+pub fn total_size(plan: &[DownloadUri]) -> u64 {
+    plan.iter().map(|uri| uri.size).sum()
+}
+
+/// Shell script that downloads and verifies every file of the plan on a
+/// machine with internet access
+pub fn export_script(packages: &[String], plan: &[DownloadUri]) -> String {
+    let mut script = String::new();
+    script.push_str("#!/bin/sh\n");
+    script.push_str("# Generated by MeaUpdater\n");
+    script.push_str(&format!("# Packages: {}\n", packages.join(" ")));
+    script.push_str(&format!("# Files: {}, total size: {} bytes\n", plan.len(), total_size(plan)));
+    script.push_str("#\n");
+    script.push_str("# Run this on a machine with internet access, copy the resulting\n");
+    script.push_str("# directory back and import it with \"Import Update Bundle\".\n");
+    script.push_str("set -e\n\n");
+    script.push_str(&format!("cat > {} <<'EOF'\n", BUNDLE_MANIFEST));
+    for uri in plan {
+        script.push_str(&format!("{} {} {}\n", uri.filename, uri.size, uri.hash));
+    }
+    script.push_str("EOF\n\n");
+    script.push_str("if command -v curl >/dev/null 2>&1; then\n");
+    script.push_str("    fetch() { curl -fL -o \"$2\" \"$1\"; }\n");
+    script.push_str("else\n");
+    script.push_str("    fetch() { wget -O \"$2\" \"$1\"; }\n");
+    script.push_str("fi\n\n");
+
+    for uri in plan {
+        script.push_str(&format!("fetch {} {}\n", shell_quote(&uri.uri), shell_quote(&uri.filename)));
+        if let Some((tool, digest)) = uri.checksum() {
+            script.push_str(&format!("echo {} | {} -c -\n", shell_quote(&format!("{}  {}", digest, uri.filename)), tool));
+        }
+    }
+
+    script.push_str("\necho \"All files downloaded and verified.\"\n");
+    script
+}
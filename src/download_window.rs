@@ -0,0 +1,193 @@
+// src/download_window.rs
+
+use crate::format;
+use crate::download_plan::{self, DownloadUri};
+use gtk::prelude::*;
+use gtk::{
+    ApplicationWindow, Box as GtkBox, Button, ListBox, ListBoxRow, ScrolledWindow,
+    Orientation, HeaderBar, Label, Dialog, MessageDialog, ButtonsType, MessageType,
+    Separator, FileChooserAction, FileChooserNative, ResponseType,
+};
+use std::fs;
+use std::rc::Rc;
+
+/// Lists what a transaction would download, for machines without internet
+pub struct DownloadPlanWindow {
+    window: Dialog,
+}
+
+impl DownloadPlanWindow {
+    pub fn new(parent: &ApplicationWindow, packages: Vec<String>) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Download Plan")
+            .default_width(900)
+            .default_height(550)
+            .build();
+
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some("🧪 Download Plan"))));
+        window.set_titlebar(Some(&header_bar));
+
+        let main_vbox = GtkBox::new(Orientation::Vertical, 12);
+        main_vbox.set_margin_top(16);
+        main_vbox.set_margin_bottom(16);
+        main_vbox.set_margin_start(16);
+        main_vbox.set_margin_end(16);
+
+        let (plan, summary) = match download_plan::get_download_plan(&packages) {
+            Ok(plan) if plan.is_empty() => (plan, "✅ Everything needed is already downloaded.".to_string()),
+            Ok(plan) => {
+                let summary = format!(
+                    "<b>{} files, {} in total</b> for {} selected packages. Nothing has been downloaded or installed.",
+                    plan.len(),
+                    format::size(download_plan::total_size(&plan)),
+                    packages.len()
+                );
+                (plan, summary)
+            }
+            Err(e) => (Vec::new(), format!("❌ {}", gtk::glib::markup_escape_text(&e.to_string()))),
+        };
+
+        let summary_label = Label::new(None);
+        summary_label.set_markup(&summary);
+        summary_label.set_halign(gtk::Align::Start);
+        summary_label.set_wrap(true);
+        main_vbox.append(&summary_label);
+
+        main_vbox.append(&Separator::new(Orientation::Horizontal));
+
+        let listbox = ListBox::new();
+        listbox.set_selection_mode(gtk::SelectionMode::None);
+        for uri in &plan {
+            listbox.append(&Self::create_uri_row(uri));
+        }
+
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .build();
+        scrolled_window.set_child(Some(&listbox));
+        main_vbox.append(&scrolled_window);
+
+        let button_panel = GtkBox::new(Orientation::Horizontal, 12);
+        button_panel.set_halign(gtk::Align::End);
+
+        let copy_btn = Button::with_label("📋 Copy URIs");
+        let export_btn = Button::with_label("💾 Export Script");
+        export_btn.add_css_class("install-button");
+        copy_btn.set_sensitive(!plan.is_empty());
+        export_btn.set_sensitive(!plan.is_empty());
+
+        button_panel.append(&copy_btn);
+        button_panel.append(&export_btn);
+        main_vbox.append(&button_panel);
+
+        window.content_area().append(&main_vbox);
+
+        let plan = Rc::new(plan);
+
+        let plan_copy = plan.clone();
+        copy_btn.connect_clicked(move |btn| {
+            let uris: Vec<&str> = plan_copy.iter().map(|uri| uri.uri.as_str()).collect();
+            btn.clipboard().set_text(&uris.join("\n"));
+        });
+
+        let plan_export = plan.clone();
+        let window_export = window.clone();
+        export_btn.connect_clicked(move |_| {
+            Self::export_script(&window_export, &packages, &plan_export);
+        });
+
+        Self { window }
+    }
+
+    fn create_uri_row(uri: &DownloadUri) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        let vbox = GtkBox::new(Orientation::Vertical, 2);
+        vbox.set_margin_top(6);
+        vbox.set_margin_bottom(6);
+        vbox.set_margin_start(8);
+        vbox.set_margin_end(8);
+
+        let top = GtkBox::new(Orientation::Horizontal, 12);
+        let name_label = Label::new(Some(&uri.filename));
+        name_label.set_hexpand(true);
+        name_label.set_halign(gtk::Align::Start);
+        name_label.add_css_class("package-name");
+        let size_label = Label::new(Some(&format::size(uri.size)));
+        size_label.add_css_class("package-size");
+        top.append(&name_label);
+        top.append(&size_label);
+        vbox.append(&top);
+
+        let uri_label = Label::new(Some(&uri.uri));
+        uri_label.set_halign(gtk::Align::Start);
+        uri_label.set_selectable(true);
+        uri_label.set_ellipsize(pango::EllipsizeMode::Middle);
+        uri_label.add_css_class("dim-label");
+        vbox.append(&uri_label);
+
+        let hash_label = Label::new(Some(&uri.hash));
+        hash_label.set_halign(gtk::Align::Start);
+        hash_label.set_selectable(true);
+        hash_label.set_ellipsize(pango::EllipsizeMode::End);
+        hash_label.add_css_class("dim-label");
+        vbox.append(&hash_label);
+
+        row.set_child(Some(&vbox));
+        row
+    }
+
+    fn export_script(parent: &Dialog, packages: &[String], plan: &[DownloadUri]) {
+        let chooser = FileChooserNative::new(
+            Some("Export Download Script"),
+            Some(parent),
+            FileChooserAction::Save,
+            Some("Save"),
+            Some("Cancel"),
+        );
+        chooser.set_current_name("meaupdater-download.sh");
+
+        let script = download_plan::export_script(packages, plan);
+        let parent = parent.clone();
+        chooser.connect_response(move |chooser, response| {
+            if response == ResponseType::Accept {
+                if let Some(path) = chooser.file().and_then(|file| file.path()) {
+                    let (message_type, text) = match fs::write(&path, &script) {
+                        Ok(()) => {
+                            Self::make_executable(&path);
+                            (MessageType::Info, format!("✅ Script saved to {}", path.display()))
+                        }
+                        Err(e) => (MessageType::Error, format!("❌ Script could not be saved:\n{}", e)),
+                    };
+                    let dialog = MessageDialog::builder()
+                        .transient_for(&parent)
+                        .modal(true)
+                        .message_type(message_type)
+                        .buttons(ButtonsType::Ok)
+                        .text(&text)
+                        .build();
+                    dialog.connect_response(|dlg, _| dlg.close());
+                    dialog.show();
+                }
+            }
+            chooser.destroy();
+        });
+        chooser.show();
+    }
+
+    fn make_executable(path: &std::path::Path) {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = fs::metadata(path) {
+            let mut permissions = metadata.permissions();
+            permissions.set_mode(permissions.mode() | 0o755);
+            let _ = fs::set_permissions(path, permissions);
+        }
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
@@ -1,10 +1,24 @@
 // src/driver_manager.rs
 
-use anyhow::{Context, Result, bail};
+use crate::error::{Context, Error, Result};
+use crate::paths;
+use crate::sysroot;
 use std::fs;
-use std::process::Command;
-use std::collections::HashMap;
-use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::UNIX_EPOCH;
+use tokio::sync::Semaphore;
+use crate::apt::{self, PolicyCandidate, Transaction};
+use crate::icons::Icon;
+use crate::journal::OperationKind;
+use crate::metadata;
+use crate::repo_manager::{self, ArchiveFamily, Repository};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DriverType {
@@ -39,6 +53,12 @@ pub struct DriverInfo {
     pub is_active: bool,
     pub is_recommended: bool,
     pub modalias: Option<String>,
+    /// Repository the candidate version comes from, for packages not installed yet
+    pub origin: Option<String>,
+    /// Why the package cannot be installed from the enabled repositories
+    pub unavailable: Option<String>,
+    /// Archive components the package needs that no enabled repository has
+    pub missing_components: Vec<String>,
 }
 
 impl DriverInfo {
@@ -65,37 +85,39 @@ impl DriverInfo {
             is_active: false,
             is_recommended: false,
             modalias: None,
+            origin: None,
+            unavailable: None,
+            missing_components: Vec::new(),
         }
     }
 
-    pub fn get_type_icon(&self) -> &'static str {
-        match self.driver_type {
-            DriverType::Graphics => "🎮",
-            DriverType::Network => "🌐",
-            DriverType::Audio => "🔊",
-            DriverType::Bluetooth => "📡",
-            DriverType::Chipset => "🔧",
-            DriverType::Storage => "💾",
-            DriverType::Input => "⌨️",
-            DriverType::Other => "🔌",
+    /// Installed version, or the candidate and where it would come from
+    pub fn version_text(&self) -> String {
+        match &self.origin {
+            Some(origin) if !self.is_installed => format!("{} from {}", self.version, origin),
+            _ => self.version.clone(),
         }
     }
 
-    pub fn get_license_icon(&self) -> &'static str {
+    pub fn type_icon(&self) -> Icon {
+        self.driver_type.icon()
+    }
+
+    pub fn license_icon(&self) -> Icon {
         match self.license {
-            DriverLicense::Free => "🆓",
-            DriverLicense::NonFree => "💰",
-            DriverLicense::Unknown => "❓",
+            DriverLicense::Free => Icon::FreeLicense,
+            DriverLicense::NonFree => Icon::NonFreeLicense,
+            DriverLicense::Unknown => Icon::UnknownLicense,
         }
     }
 
-    pub fn get_status_icon(&self) -> &'static str {
+    pub fn status_icon(&self) -> Icon {
         if self.is_active {
-            "🟢"
+            Icon::Active
         } else if self.is_installed {
-            "🔵"
+            Icon::Installed
         } else {
-            "⚪"
+            Icon::Available
         }
     }
 }
@@ -105,7 +127,7 @@ pub fn detect_hardware() -> Result<Vec<String>> {
     let mut modaliases = Vec::new();
     
     // PCI devices
-    if let Ok(entries) = fs::read_dir("/sys/bus/pci/devices") {
+    if let Ok(entries) = fs::read_dir(sysroot::path("/sys/bus/pci/devices")) {
         for entry in entries.flatten() {
             let modalias_path = entry.path().join("modalias");
             if let Ok(modalias) = fs::read_to_string(modalias_path) {
@@ -115,7 +137,7 @@ pub fn detect_hardware() -> Result<Vec<String>> {
     }
     
     // USB devices
-    if let Ok(entries) = fs::read_dir("/sys/bus/usb/devices") {
+    if let Ok(entries) = fs::read_dir(sysroot::path("/sys/bus/usb/devices")) {
         for entry in entries.flatten() {
             let modalias_path = entry.path().join("modalias");
             if let Ok(modalias) = fs::read_to_string(modalias_path) {
@@ -127,13 +149,8 @@ pub fn detect_hardware() -> Result<Vec<String>> {
     Ok(modaliases)
 }
 
-pub fn parse_lspci_output() -> Result<Vec<(String, String, String)>> {
-    let output = Command::new("lspci")
-        .arg("-nn")
-        .output()
-        .context("lspci command could not be executed")?;
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
+/// `(bus, description, vendor:device)` for each line of `lspci -nn` output
+pub fn parse_lspci_devices(stdout: &str) -> Vec<(String, String, String)> {
     let mut devices = Vec::new();
     
     println!("🔍 Analyzing lspci output...");
@@ -166,16 +183,11 @@ pub fn parse_lspci_output() -> Result<Vec<(String, String, String)>> {
     }
     
     println!("✅ {} hardware device parsed", devices.len());
-    Ok(devices)
+    devices
 }
 
-pub fn get_installed_drivers() -> Result<HashMap<String, String>> {
-    let output = Command::new("dpkg")
-        .args(&["-l"])
-        .output()
-        .context("dpkg command failed to run")?;
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
+/// Installed driver packages and their versions from `dpkg -l` output
+pub fn parse_installed_drivers(stdout: &str) -> HashMap<String, String> {
     let mut installed = HashMap::new();
     
     for line in stdout.lines() {
@@ -190,7 +202,7 @@ pub fn get_installed_drivers() -> Result<HashMap<String, String>> {
         }
     }
     
-    Ok(installed)
+    installed
 }
 
 fn is_driver_package(package_name: &str) -> bool {
@@ -212,8 +224,12 @@ pub fn create_driver_backup() -> Result<String> {
         .unwrap()
         .as_secs();
     
-    let backup_dir = format!("/tmp/meaupdater_driver_backup_{}", timestamp);
-    fs::create_dir_all(&backup_dir)?;
+    let backup_dir = paths::state_dir()
+        .context("HOME is not set")?
+        .join("driver-backups")
+        .join(timestamp.to_string());
+    paths::ensure_dir(&backup_dir).context("Could not create the driver backup folder")?;
+    let backup_dir = backup_dir.display().to_string();
     
     let installed_output = Command::new("dpkg")
         .args(&["-l"])
@@ -248,68 +264,271 @@ pub fn create_driver_backup() -> Result<String> {
     Ok(backup_dir)
 }
 
+/// How many probe commands a driver scan runs at the same time
+const PROBE_CONCURRENCY: usize = 4;
+
+
+/// Everything one driver scan needs to know about the system. The
+/// commands run once and concurrently, the detectors only read the results.
+#[derive(Debug, Default)]
+pub struct HardwareProbe {
+    pub installed: HashMap<String, String>,
+    pub devices: Vec<(String, String, String)>,
+    pub loaded_modules: HashSet<String>,
+    pub running_processes: HashSet<String>,
+    pub available_packages: HashMap<String, PolicyCandidate>,
+    pub nvidia_smi_works: bool,
+    pub intel_cpu: bool,
+    pub bluetooth_present: bool,
+    pub bluetooth_active: bool,
+    /// Whether an enabled Debian repository carries the non-free component
+    pub nonfree_enabled: bool,
+    /// Components of the enabled repositories, `None` if the sources could not be read
+    pub components: Option<HashSet<String>>,
+    pub archive: ArchiveFamily,
+}
+
+impl HardwareProbe {
+    pub fn is_loaded(&self, module: &str) -> bool {
+        self.loaded_modules.contains(module)
+    }
+
+    pub fn is_running(&self, process: &str) -> bool {
+        self.running_processes.contains(process)
+    }
+
+    pub fn is_available(&self, package: &str) -> bool {
+        self.available_packages.contains_key(package)
+    }
+
+    /// Runs all probes with at most `PROBE_CONCURRENCY` commands at once.
+    /// Blocks, so call it from a worker thread.
+    pub fn gather() -> Result<HardwareProbe> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Could not start the hardware probe")?;
+        let mut probe = runtime.block_on(Self::gather_async())?;
+        // Without the sources there is no reason to blame non-free
+        let repos = repo_manager::get_repositories().ok();
+        probe.nonfree_enabled = repos.as_ref().map_or(true, |repos| nonfree_enabled(repos));
+        probe.components = repos.as_ref().map(|repos| repo_manager::enabled_components(repos));
+        probe.archive = ArchiveFamily::detect();
+        Ok(probe)
+    }
+
+    async fn gather_async() -> Result<HardwareProbe> {
+        let limit = Semaphore::new(PROBE_CONCURRENCY);
+        // NVIDIA rows are only listed when their package is available
+        let mut policy_args = vec!["policy", "xserver-xorg-video-nouveau"];
+        policy_args.extend(NVIDIA_BRANCHES.iter().map(|branch| branch.package));
+
+        let (installed, lspci, lsmod, nvidia_smi, lscpu, lsusb, rfkill, bluetooth, pulseaudio, pipewire, policy) = tokio::join!(
+            probe(&limit, "dpkg", &["-l"]),
+            probe(&limit, "lspci", &["-nn"]),
+            probe(&limit, "lsmod", &[]),
+            probe(&limit, "nvidia-smi", &[]),
+            probe(&limit, "lscpu", &[]),
+            probe(&limit, "lsusb", &[]),
+            probe(&limit, "rfkill", &["list"]),
+            probe(&limit, "systemctl", &["is-active", "bluetooth"]),
+            probe(&limit, "pgrep", &["-x", "pulseaudio"]),
+            probe(&limit, "pgrep", &["-x", "pipewire"]),
+            probe(&limit, "apt-cache", &policy_args),
+        );
+
+        let installed = installed.context("dpkg command failed to run")?;
+        let lspci = lspci.context("lspci command could not be executed")?;
+        let devices = parse_lspci_devices(&stdout_of(&lspci));
+
+        let mentions_bluetooth = |output: &Output| stdout_of(output).to_lowercase().contains("bluetooth");
+        let bluetooth_present = mentions_bluetooth(&lspci)
+            || lsusb.as_ref().map_or(false, mentions_bluetooth)
+            || rfkill.as_ref().map_or(false, mentions_bluetooth)
+            || sysroot::path("/sys/class/bluetooth").exists();
+
+        let mut running_processes = HashSet::new();
+        for (name, output) in [("pulseaudio", &pulseaudio), ("pipewire", &pipewire)] {
+            if output.as_ref().map_or(false, |o| o.status.success()) {
+                running_processes.insert(name.to_string());
+            }
+        }
+
+        Ok(HardwareProbe {
+            installed: parse_installed_drivers(&stdout_of(&installed)),
+            devices,
+            loaded_modules: lsmod.map(|o| parse_lsmod(&stdout_of(&o))).unwrap_or_default(),
+            running_processes,
+            available_packages: policy
+                .map(|o| apt::parse_policy(&stdout_of(&o)))
+                .unwrap_or_default(),
+            nvidia_smi_works: nvidia_smi.map_or(false, |o| o.status.success()),
+            intel_cpu: lscpu.map_or(false, |o| stdout_of(&o).to_lowercase().contains("intel")),
+            bluetooth_present,
+            bluetooth_active: bluetooth.map_or(false, |o| stdout_of(&o).trim() == "active"),
+            nonfree_enabled: false,
+            components: None,
+            archive: ArchiveFamily::Debian,
+        })
+    }
+}
+
+/// Runs one probe command once a slot is free. `None` if it could not start.
+async fn probe(limit: &Semaphore, program: &str, args: &[&str]) -> Option<Output> {
+    let _permit = limit.acquire().await.ok()?;
+    tokio::process::Command::new(program)
+        .args(args)
+        .env("LANG", "C")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .ok()
+}
+
+fn stdout_of(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Module names from `lsmod` output
+pub fn parse_lsmod(output: &str) -> HashSet<String> {
+    output.lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether any enabled binary repository has a non-free component
+pub fn nonfree_enabled(repos: &[Repository]) -> bool {
+    repos.iter()
+        .filter(|repo| repo.enabled && !repo.is_source)
+        .any(|repo| repo.components.split_whitespace().any(|component| component == "non-free"))
+}
+
+/// Explains why a driver package has no installation candidate
+pub fn unavailable_reason(license: &DriverLicense, nonfree_enabled: bool) -> String {
+    if *license == DriverLicense::NonFree && !nonfree_enabled {
+        "Requires the non-free repository".to_string()
+    } else {
+        "Not in your enabled repositories".to_string()
+    }
+}
+
+/// Archive components a driver package is published in besides main.
+/// Debian's NVIDIA packages pull helpers from contrib, and since bookworm
+/// firmware has a component of its own.
+pub fn required_components(license: &DriverLicense, package: &str, archive: ArchiveFamily) -> &'static [&'static str] {
+    if *license != DriverLicense::NonFree {
+        return &[];
+    }
+    let firmware = package.starts_with("firmware-") || package.ends_with("-microcode");
+    let nvidia = package.starts_with("nvidia-");
+    match archive {
+        ArchiveFamily::Debian if firmware => &["non-free-firmware"],
+        ArchiveFamily::Debian if nvidia => &["contrib", "non-free"],
+        ArchiveFamily::Debian => &["non-free"],
+        ArchiveFamily::Ubuntu if firmware || nvidia => &["restricted"],
+        ArchiveFamily::Ubuntu => &["multiverse"],
+    }
+}
+
+/// Notes which components each driver not installed yet still needs
+fn annotate_missing_components(drivers: &mut [DriverInfo], probe: &HardwareProbe) {
+    let enabled = match &probe.components {
+        Some(enabled) => enabled,
+        None => return,
+    };
+    for driver in drivers.iter_mut().filter(|d| !d.is_installed) {
+        driver.missing_components = required_components(&driver.license, &driver.package_name, probe.archive).iter()
+            .filter(|component| !enabled.contains(**component))
+            .map(|component| component.to_string())
+            .collect();
+    }
+}
+
+/// Replaces "Available" with the version apt would install and its
+/// repository. Packages the probe did not cover are looked up in one batch.
+fn annotate_candidates(drivers: &mut [DriverInfo], known: &HashMap<String, PolicyCandidate>) {
+    let mut missing: Vec<String> = drivers.iter()
+        .filter(|d| !d.is_installed && d.unavailable.is_none() && !known.contains_key(&d.package_name))
+        .map(|d| d.package_name.clone())
+        .collect();
+    missing.sort();
+    missing.dedup();
+    let looked_up = apt::get_policy_candidates(&missing);
+
+    for driver in drivers.iter_mut().filter(|d| !d.is_installed) {
+        if let Some(candidate) = known.get(&driver.package_name).or_else(|| looked_up.get(&driver.package_name)) {
+            driver.version = candidate.version.clone();
+            driver.origin = candidate.origin.clone();
+        }
+    }
+}
+
 pub fn detect_drivers() -> Result<Vec<DriverInfo>> {
     println!("🔍 Starting driver detection...");
     let mut drivers = Vec::new();
     
-    println!("📦 Checking installed packages...");
-    let installed_packages = get_installed_drivers()?;
+    println!("🖥️ Probing hardware and installed packages...");
+    let probe = HardwareProbe::gather()?;
+    let installed_packages = &probe.installed;
+    let hardware_devices = &probe.devices;
     println!("✅ {} Installed driver package found", installed_packages.len());
-    
-    println!("🖥️ Checking hardware devices...");
-    let hardware_devices = parse_lspci_output()?;
     println!("✅ {} hardware device found", hardware_devices.len());
     
     
-    let nvidia_devices = detect_nvidia_hardware(&hardware_devices)?;
+    let nvidia_devices = detect_nvidia_hardware(hardware_devices)?;
     if !nvidia_devices.is_empty() {
         println!("🎮 {} NVIDIA device detected", nvidia_devices.len());
         for (device_name, device_id) in nvidia_devices {
             println!("  - {}: {}", device_name, device_id);
-            drivers.extend(detect_nvidia_drivers(&installed_packages, &device_name, &device_id)?);
+            drivers.extend(detect_nvidia_drivers(&probe, &device_name, &device_id)?);
         }
     }
     
-    let amd_devices = detect_amd_hardware(&hardware_devices)?;
+    let amd_devices = detect_amd_hardware(hardware_devices)?;
     if !amd_devices.is_empty() {
         println!("🎮 {} AMD device detected", amd_devices.len());
         for (device_name, device_id) in amd_devices {
             println!("  - {}: {}", device_name, device_id);
-            drivers.extend(detect_amd_drivers(&installed_packages, &device_name, &device_id)?);
+            drivers.extend(detect_amd_drivers(&probe, &device_name, &device_id)?);
         }
     }
     
-    let intel_devices = detect_intel_hardware(&hardware_devices)?;
+    let intel_devices = detect_intel_hardware(&probe)?;
     if !intel_devices.is_empty() {
         println!("🎮 {} Intel device detected", intel_devices.len());
         for (device_name, device_id) in intel_devices {
             println!("  - {}: {}", device_name, device_id);
-            drivers.extend(detect_intel_drivers(&installed_packages, &device_name, &device_id)?);
+            drivers.extend(detect_intel_drivers(&probe, &device_name, &device_id)?);
         }
     }
     
-    let network_drivers = detect_network_drivers(&installed_packages, &hardware_devices)?;
+    let network_drivers = detect_network_drivers(&probe)?;
     if !network_drivers.is_empty() {
         println!("🌐 {} network driver found", network_drivers.len());
         drivers.extend(network_drivers);
     }
     
-    if has_audio_hardware(&hardware_devices) {
+    if has_audio_hardware(hardware_devices) {
         println!("🔊 Checking audio drivers...");
-        drivers.extend(detect_audio_drivers(&installed_packages)?);
+        drivers.extend(detect_audio_drivers(&probe)?);
     }
     
-    if has_bluetooth_hardware() {
+    if probe.bluetooth_present {
         println!("📡 Checking Bluetooth drivers...");
-        drivers.extend(detect_bluetooth_drivers(&installed_packages)?);
+        drivers.extend(detect_bluetooth_drivers(&probe)?);
     }
     
-    let firmware_drivers = detect_firmware_packages(&installed_packages, &hardware_devices)?;
+    let firmware_drivers = detect_firmware_packages(installed_packages, hardware_devices)?;
     if !firmware_drivers.is_empty() {
         println!("💾 {} firmware package found", firmware_drivers.len());
         drivers.extend(firmware_drivers);
     }
     
+    annotate_candidates(&mut drivers, &probe.available_packages);
+    annotate_missing_components(&mut drivers, &probe);
+    
     println!("✅ Total {} drivers found", drivers.len());
     Ok(drivers)
 }
@@ -382,10 +601,10 @@ fn detect_amd_hardware(devices: &[(String, String, String)]) -> Result<Vec<(Stri
     Ok(amd_devices)
 }
 
-fn detect_intel_hardware(devices: &[(String, String, String)]) -> Result<Vec<(String, String)>> {
+fn detect_intel_hardware(probe: &HardwareProbe) -> Result<Vec<(String, String)>> {
     let mut intel_devices = Vec::new();
     
-    for (_, desc, device_id) in devices {
+    for (_, desc, device_id) in &probe.devices {
         let desc_lower = desc.to_lowercase();
         if desc_lower.contains("intel") && 
            (desc_lower.contains("vga") || desc_lower.contains("vga compatible") || 
@@ -399,15 +618,7 @@ fn detect_intel_hardware(devices: &[(String, String, String)]) -> Result<Vec<(St
         }
     }
     
-    let intel_cpu = Command::new("lscpu")
-        .output()
-        .map(|output| {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            stdout.lines().any(|line| line.to_lowercase().contains("intel"))
-        })
-        .unwrap_or(false);
-    
-    if intel_cpu {
+    if probe.intel_cpu {
         println!("🔧 Intel CPU Found (For Microcode)");
         intel_devices.push(("Intel CPU (Microcode)".to_string(), "CPU".to_string()));
     }
@@ -421,53 +632,12 @@ fn has_audio_hardware(devices: &[(String, String, String)]) -> bool {
         (desc_lower.contains("audio") || desc_lower.contains("sound")) &&
         !desc_lower.contains("hdmi") 
     }) ||
-    std::path::Path::new("/proc/asound").exists()
-}
-
-fn has_bluetooth_hardware() -> bool {
-    let pci_bluetooth = Command::new("lspci")
-        .output()
-        .map(|output| {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            stdout.lines().any(|line| {
-                line.to_lowercase().contains("bluetooth")
-            })
-        })
-        .unwrap_or(false);
-    
-    let usb_bluetooth = Command::new("lsusb")
-        .output()
-        .map(|output| {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            stdout.lines().any(|line| {
-                line.to_lowercase().contains("bluetooth")
-            })
-        })
-        .unwrap_or(false);
-    
-    let sys_bluetooth = std::path::Path::new("/sys/class/bluetooth").exists();
-    
-    let rfkill_bluetooth = Command::new("rfkill")
-        .arg("list")
-        .output()
-        .map(|output| {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            stdout.lines().any(|line| {
-                line.to_lowercase().contains("bluetooth")
-            })
-        })
-        .unwrap_or(false);
-    
-    pci_bluetooth || usb_bluetooth || sys_bluetooth || rfkill_bluetooth
+    sysroot::path("/proc/asound").exists()
 }
 
 fn detect_available_nvidia_packages() -> Result<Vec<String>> {
-    let output = Command::new("apt")
-        .args(&["search", "nvidia-driver"])
-        .output()
+    let stdout = metadata::query("apt", &["search", "nvidia-driver"])
         .context("Could not run apt search nvidia-driver command")?;
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let mut packages = Vec::new();
     
     for line in stdout.lines() {
@@ -492,82 +662,245 @@ fn detect_available_nvidia_packages() -> Result<Vec<String>> {
     Ok(packages)
 }
 
-fn detect_nvidia_drivers(installed: &HashMap<String, String>, device_name: &str, device_id: &str) -> Result<Vec<DriverInfo>> {
+/// NVIDIA GPU architectures, oldest first. Each driver branch supports a
+/// contiguous range of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NvidiaGeneration {
+    Tesla,
+    Fermi,
+    Kepler,
+    Maxwell,
+    Pascal,
+    Volta,
+    Turing,
+    Ampere,
+    Ada,
+    Blackwell,
+}
+
+/// PCI device ID ranges of each generation. More specific ranges come
+/// first because some families share an ID block.
+const NVIDIA_GENERATIONS: &[(u16, u16, NvidiaGeneration)] = &[
+    (0x1280, 0x12BF, NvidiaGeneration::Kepler),
+    (0x1340, 0x13FF, NvidiaGeneration::Maxwell),
+    (0x15F0, 0x15FF, NvidiaGeneration::Pascal),
+    (0x1D80, 0x1DBF, NvidiaGeneration::Volta),
+    (0x20B0, 0x20FF, NvidiaGeneration::Ampere),
+    (0x0400, 0x06BF, NvidiaGeneration::Tesla),
+    (0x06C0, 0x06FF, NvidiaGeneration::Fermi),
+    (0x0A20, 0x0A7F, NvidiaGeneration::Tesla),
+    (0x0CA0, 0x0CBF, NvidiaGeneration::Tesla),
+    (0x0DC0, 0x0DFF, NvidiaGeneration::Fermi),
+    (0x0E20, 0x0E3F, NvidiaGeneration::Fermi),
+    (0x0FC0, 0x103F, NvidiaGeneration::Kepler),
+    (0x1040, 0x10BF, NvidiaGeneration::Fermi),
+    (0x10C0, 0x10DF, NvidiaGeneration::Tesla),
+    (0x1180, 0x11FF, NvidiaGeneration::Kepler),
+    (0x1200, 0x127F, NvidiaGeneration::Fermi),
+    (0x1380, 0x17FF, NvidiaGeneration::Maxwell),
+    (0x1B00, 0x1DFF, NvidiaGeneration::Pascal),
+    (0x1E00, 0x1FFF, NvidiaGeneration::Turing),
+    (0x2180, 0x21FF, NvidiaGeneration::Turing),
+    (0x2200, 0x25FF, NvidiaGeneration::Ampere),
+    (0x2600, 0x28FF, NvidiaGeneration::Ada),
+    (0x2900, 0x2FFF, NvidiaGeneration::Blackwell),
+];
+
+impl NvidiaGeneration {
+    /// Generation of an lspci `vendor:device` ID such as `10de:1f08`
+    pub fn from_device_id(device_id: &str) -> Option<NvidiaGeneration> {
+        let (vendor, device) = device_id.split_once(':')?;
+        if !vendor.eq_ignore_ascii_case("10de") {
+            return None;
+        }
+        let device = u16::from_str_radix(device.trim(), 16).ok()?;
+        NVIDIA_GENERATIONS.iter()
+            .find(|(first, last, _)| (*first..=*last).contains(&device))
+            .map(|(_, _, generation)| *generation)
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            NvidiaGeneration::Tesla => "Tesla",
+            NvidiaGeneration::Fermi => "Fermi",
+            NvidiaGeneration::Kepler => "Kepler",
+            NvidiaGeneration::Maxwell => "Maxwell",
+            NvidiaGeneration::Pascal => "Pascal",
+            NvidiaGeneration::Volta => "Volta",
+            NvidiaGeneration::Turing => "Turing",
+            NvidiaGeneration::Ampere => "Ampere",
+            NvidiaGeneration::Ada => "Ada Lovelace",
+            NvidiaGeneration::Blackwell => "Blackwell",
+        }
+    }
+}
+
+/// An NVIDIA driver series packaged by Debian
+#[derive(Debug, Clone, PartialEq)]
+pub struct NvidiaBranch {
+    pub package: &'static str,
+    pub label: &'static str,
+    pub license: DriverLicense,
+    pub oldest: NvidiaGeneration,
+    pub newest: NvidiaGeneration,
+}
+
+pub const NVIDIA_BRANCHES: &[NvidiaBranch] = &[
+    NvidiaBranch {
+        package: "nvidia-legacy-390xx-driver",
+        label: "NVIDIA 390.xx Legacy Driver",
+        license: DriverLicense::NonFree,
+        oldest: NvidiaGeneration::Fermi,
+        newest: NvidiaGeneration::Pascal,
+    },
+    NvidiaBranch {
+        package: "nvidia-tesla-470-driver",
+        label: "NVIDIA 470.xx Legacy Driver",
+        license: DriverLicense::NonFree,
+        oldest: NvidiaGeneration::Kepler,
+        newest: NvidiaGeneration::Ampere,
+    },
+    NvidiaBranch {
+        package: "nvidia-driver",
+        label: "NVIDIA Driver (Current)",
+        license: DriverLicense::NonFree,
+        oldest: NvidiaGeneration::Maxwell,
+        newest: NvidiaGeneration::Ada,
+    },
+    NvidiaBranch {
+        package: "nvidia-open-kernel-dkms",
+        label: "NVIDIA Open Kernel Module",
+        license: DriverLicense::Free,
+        oldest: NvidiaGeneration::Turing,
+        newest: NvidiaGeneration::Blackwell,
+    },
+];
+
+impl NvidiaBranch {
+    pub fn supports(&self, generation: NvidiaGeneration) -> bool {
+        (self.oldest..=self.newest).contains(&generation)
+    }
+}
+
+/// Branch to suggest for a GPU: the newest closed driver that still
+/// supports it, or the open module for GPUs only it supports
+pub fn recommended_nvidia_branch(generation: Option<NvidiaGeneration>) -> Option<&'static NvidiaBranch> {
+    let generation = match generation {
+        Some(generation) => generation,
+        // Unknown IDs are most likely newer than the table
+        None => return NVIDIA_BRANCHES.iter().find(|b| b.package == "nvidia-driver"),
+    };
+    NVIDIA_BRANCHES.iter()
+        .filter(|b| b.supports(generation) && b.license == DriverLicense::NonFree)
+        .last()
+        .or_else(|| NVIDIA_BRANCHES.iter().find(|b| b.supports(generation)))
+}
+
+/// Why a branch does or does not fit the GPU, shown next to the driver
+pub fn nvidia_compatibility_hint(branch: &NvidiaBranch, generation: Option<NvidiaGeneration>) -> String {
+    let generation = match generation {
+        Some(generation) => generation,
+        None => return "GPU generation unknown, check NVIDIA's supported products list".to_string(),
+    };
+    if branch.supports(generation) {
+        format!("supports {} GPUs", generation.display_name())
+    } else if generation < branch.oldest {
+        format!("{} GPUs are too old for this driver", generation.display_name())
+    } else {
+        format!("{} GPUs are too new for this driver", generation.display_name())
+    }
+}
+
+fn detect_nvidia_drivers(probe: &HardwareProbe, device_name: &str, device_id: &str) -> Result<Vec<DriverInfo>> {
+    let installed = &probe.installed;
     let mut drivers = Vec::new();
     
-    let debian_nvidia_packages = vec![
-        ("nvidia-driver", "NVIDIA Driver (Metapackage)", DriverLicense::NonFree),
-        ("nvidia-driver-full", "NVIDIA Full Driver Suite", DriverLicense::NonFree),
-        ("xserver-xorg-video-nvidia", "NVIDIA Xorg Driver", DriverLicense::NonFree),
-        ("xserver-xorg-video-nouveau", "Nouveau (Open Source)", DriverLicense::Free),
-    ];
+    let generation = NvidiaGeneration::from_device_id(device_id);
+    let recommended = recommended_nvidia_branch(generation);
+    println!("🎮 NVIDIA generation: {}", generation.map_or("Unknown", |g| g.display_name()));
     
-    for (package, desc, license) in debian_nvidia_packages {
-        let package_installed = installed.contains_key(package) || 
-            installed.iter().any(|(k, _)| k.contains(package) || k.starts_with(package));
+    let nvidia_loaded = (probe.is_loaded("nvidia") && !probe.is_loaded("nouveau")) || probe.nvidia_smi_works;
+    
+    for branch in NVIDIA_BRANCHES {
+        let package = branch.package;
+        if let Some(generation) = generation {
+            if !branch.supports(generation) {
+                println!("⚠️ {} does not support {} GPUs, skipping", package, generation.display_name());
+                continue;
+            }
+        }
         
-        let is_active = if package.contains("nouveau") {
-            Command::new("lsmod")
-                .output()
-                .map(|output| String::from_utf8_lossy(&output.stdout).contains("nouveau"))
-                .unwrap_or(false)
+        let package_installed = installed.contains_key(package) || 
+            installed.keys().any(|k| k.starts_with(&format!("{}-", package)));
+        let is_active = package_installed && nvidia_loaded;
+        // Listed anyway, so the user learns which repository is missing
+        let unavailable = if !package_installed && !probe.is_available(package) {
+            let reason = unavailable_reason(&branch.license, probe.nonfree_enabled);
+            println!("⚠️ Package not available: {} ({})", package, reason);
+            Some(reason)
         } else {
-            Command::new("lsmod")
-                .output()
-                .map(|output| {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    stdout.contains("nvidia") && !stdout.contains("nouveau")
-                })
-                .unwrap_or(false) ||
-            Command::new("nvidia-smi")
-                .output()
-                .map(|output| output.status.success())
-                .unwrap_or(false)
+            None
         };
         
-        let package_available = Command::new("apt")
-            .args(&["show", package])
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false);
-        
-        if !package_available {
-            println!("⚠️ Package not available, skipping: {}", package);
-            continue;
-        }
-        
-        let is_truly_installed = package_installed || is_active;
-        
-        let version = if is_truly_installed {
-            installed.get(package).unwrap_or(&"Active".to_string()).clone()
+        let version = if package_installed {
+            installed.get(package).cloned().unwrap_or_else(|| "Active".to_string())
+        } else if unavailable.is_some() {
+            "Unavailable".to_string()
         } else {
             "Available".to_string()
         };
         
         let mut driver = DriverInfo::new(
             package.to_string(),
-            format!("{} - {}", desc, device_name),
+            format!("{} - {} ({})", branch.label, device_name, nvidia_compatibility_hint(branch, generation)),
             package.to_string(),
             version,
             DriverType::Graphics,
-            license.clone(),
+            branch.license.clone(),
             "NVIDIA".to_string(),
             device_id.to_string(),
         );
         
-        driver.is_installed = is_truly_installed;
+        driver.is_installed = package_installed;
         driver.is_active = is_active;
-        driver.is_recommended = package == "nvidia-driver";
+        driver.is_recommended = recommended == Some(branch);
+        driver.unavailable = unavailable;
         drivers.push(driver);
         
-        println!("🎮 NVIDIA package: {} - Installed: {}, Active: {}, Available: {}", 
-                package, package_installed, is_active, package_available);
+        println!("🎮 NVIDIA package: {} - Installed: {}, Active: {}", package, package_installed, is_active);
+    }
+    
+    let nouveau = "xserver-xorg-video-nouveau";
+    if probe.is_available(nouveau) {
+        let is_active = probe.is_loaded("nouveau");
+        let package_installed = installed.contains_key(nouveau);
+        let is_truly_installed = package_installed || is_active;
+        
+        let mut driver = DriverInfo::new(
+            nouveau.to_string(),
+            format!("Nouveau (Open Source) - {}", device_name),
+            nouveau.to_string(),
+            if is_truly_installed {
+                installed.get(nouveau).cloned().unwrap_or_else(|| "Active".to_string())
+            } else {
+                "Available".to_string()
+            },
+            DriverType::Graphics,
+            DriverLicense::Free,
+            "NVIDIA".to_string(),
+            device_id.to_string(),
+        );
+        driver.is_installed = is_truly_installed;
+        driver.is_active = is_active;
+        driver.is_recommended = recommended.is_none();
+        drivers.push(driver);
     }
     
     Ok(drivers)
 }
 
-fn detect_amd_drivers(installed: &HashMap<String, String>, device_name: &str, device_id: &str) -> Result<Vec<DriverInfo>> {
+fn detect_amd_drivers(probe: &HardwareProbe, device_name: &str, device_id: &str) -> Result<Vec<DriverInfo>> {
+    let installed = &probe.installed;
     let mut drivers = Vec::new();
     
     let amd_packages = [
@@ -576,10 +909,7 @@ fn detect_amd_drivers(installed: &HashMap<String, String>, device_name: &str, de
     ];
     
     for (package, desc, license) in &amd_packages {
-        let is_active = Command::new("lsmod")
-            .output()
-            .map(|output| String::from_utf8_lossy(&output.stdout).contains(package))
-            .unwrap_or(false);
+        let is_active = probe.is_loaded(package);
         
         let package_exists = installed.iter().any(|(k, _)| k.contains(package)) ||
             installed.iter().any(|(k, _)| k.contains(&format!("xserver-xorg-video-{}", package))) ||
@@ -613,12 +943,13 @@ fn detect_amd_drivers(installed: &HashMap<String, String>, device_name: &str, de
     Ok(drivers)
 }
 
-fn detect_intel_drivers(installed: &HashMap<String, String>, device_name: &str, device_id: &str) -> Result<Vec<DriverInfo>> {
+fn detect_intel_drivers(probe: &HardwareProbe, device_name: &str, device_id: &str) -> Result<Vec<DriverInfo>> {
+    let installed = &probe.installed;
     let mut drivers = Vec::new();
     
     if device_id == "CPU" {
         let is_microcode_installed = installed.contains_key("intel-microcode");
-        let is_microcode_active = std::path::Path::new("/sys/devices/system/cpu/microcode").exists();
+        let is_microcode_active = sysroot::path("/sys/devices/system/cpu/microcode").exists();
         let is_truly_installed = is_microcode_installed && is_microcode_active;
         
         let version = if is_truly_installed {
@@ -643,10 +974,7 @@ fn detect_intel_drivers(installed: &HashMap<String, String>, device_name: &str,
         microcode_driver.is_recommended = true;
         drivers.push(microcode_driver);
     } else {
-        let is_active = Command::new("lsmod")
-            .output()
-            .map(|output| String::from_utf8_lossy(&output.stdout).contains("i915"))
-            .unwrap_or(false);
+        let is_active = probe.is_loaded("i915");
         
         let mut driver = DriverInfo::new(
             "intel".to_string(),
@@ -668,7 +996,9 @@ fn detect_intel_drivers(installed: &HashMap<String, String>, device_name: &str,
     Ok(drivers)
 }
 
-fn detect_network_drivers(installed: &HashMap<String, String>, devices: &[(String, String, String)]) -> Result<Vec<DriverInfo>> {
+fn detect_network_drivers(probe: &HardwareProbe) -> Result<Vec<DriverInfo>> {
+    let installed = &probe.installed;
+    let devices = &probe.devices;
     let mut drivers = Vec::new();
     
     for (_, desc, device_id) in devices {
@@ -677,12 +1007,7 @@ fn detect_network_drivers(installed: &HashMap<String, String>, devices: &[(Strin
         if desc_lower.contains("realtek") && desc_lower.contains("ethernet") {
             println!("✅ Realtek Ethernet card detected: {}", desc);
             
-            let is_active = Command::new("lsmod").output()
-                .map(|output| {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    stdout.contains("r8169") || stdout.contains("r8168")
-                })
-                .unwrap_or(false);
+            let is_active = probe.is_loaded("r8169") || probe.is_loaded("r8168");
             
             let mut driver = DriverInfo::new(
                 "r8168-dkms".to_string(),
@@ -713,18 +1038,7 @@ fn detect_network_drivers(installed: &HashMap<String, String>, devices: &[(Strin
             
             for (package, package_desc) in &realtek_wifi_packages {
                 let package_installed = installed.contains_key(*package);
-                let is_active = Command::new("lsmod").output()
-                    .map(|output| {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        if package.contains("rtl8192eu") {
-                            stdout.contains("rtl8192eu")
-                        } else if package.contains("rtl8821ce") {
-                            stdout.contains("rtl8821ce")
-                        } else {
-                            false
-                        }
-                    })
-                    .unwrap_or(false);
+                let is_active = probe.is_loaded(package.trim_end_matches("-dkms"));
                 
                 let is_truly_installed = package_installed && is_active;
                 
@@ -767,13 +1081,9 @@ fn detect_network_drivers(installed: &HashMap<String, String>, devices: &[(Strin
         for (package, desc, license) in &broadcom_packages {
             let package_installed = installed.contains_key(*package);
             let is_active = if *package == "broadcom-sta-dkms" {
-                Command::new("lsmod").output()
-                    .map(|output| String::from_utf8_lossy(&output.stdout).contains("wl"))
-                    .unwrap_or(false)
+                probe.is_loaded("wl")
             } else {
-                Command::new("lsmod").output()
-                    .map(|output| String::from_utf8_lossy(&output.stdout).contains("b43"))
-                    .unwrap_or(false)
+                probe.is_loaded("b43")
             };
             
             let is_truly_installed = package_installed && is_active;
@@ -809,9 +1119,7 @@ fn detect_network_drivers(installed: &HashMap<String, String>, devices: &[(Strin
     
     if has_intel_wifi {
         println!("✅ Intel WiFi card detected");
-        let is_active = Command::new("lsmod").output()
-            .map(|output| String::from_utf8_lossy(&output.stdout).contains("iwlwifi"))
-            .unwrap_or(false);
+        let is_active = probe.is_loaded("iwlwifi");
         
         let mut driver = DriverInfo::new(
             "iwlwifi".to_string(),
@@ -833,7 +1141,8 @@ fn detect_network_drivers(installed: &HashMap<String, String>, devices: &[(Strin
     Ok(drivers)
 }
 
-fn detect_audio_drivers(installed: &HashMap<String, String>) -> Result<Vec<DriverInfo>> {
+fn detect_audio_drivers(probe: &HardwareProbe) -> Result<Vec<DriverInfo>> {
+    let installed = &probe.installed;
     let mut drivers = Vec::new();
     
     let audio_packages = [
@@ -846,24 +1155,11 @@ fn detect_audio_drivers(installed: &HashMap<String, String>) -> Result<Vec<Drive
         let package_installed = installed.contains_key(*package) ||
             installed.iter().any(|(k, _)| k.starts_with(package) || k.contains(&format!("{}-", package)));
         
-        let is_active = if *package == "pulseaudio" {
-            // 2025 mthakan
-            Command::new("pgrep")
-                .arg("-x") 
-                .arg("pulseaudio")
-                .status()
-                .map(|s| s.success())
-                .unwrap_or(false)
-        } else if *package == "pipewire" {
-            Command::new("pgrep")
-                .arg("-x")   
-                .arg("pipewire")
-                .status()
-                .map(|s| s.success())
-                .unwrap_or(false)
+        let is_active = if *package == "pulseaudio" || *package == "pipewire" {
+            probe.is_running(package)
         } else if *package == "alsa-base" {
 
-            std::path::Path::new("/proc/asound").exists()
+            sysroot::path("/proc/asound").exists()
         } else {
             false
         };
@@ -904,7 +1200,8 @@ fn detect_audio_drivers(installed: &HashMap<String, String>) -> Result<Vec<Drive
 }
 
 
-fn detect_bluetooth_drivers(installed: &HashMap<String, String>) -> Result<Vec<DriverInfo>> {
+fn detect_bluetooth_drivers(probe: &HardwareProbe) -> Result<Vec<DriverInfo>> {
+    let installed = &probe.installed;
     let mut drivers = Vec::new();
     
     println!("✅ Bluetooth device detected");
@@ -918,11 +1215,7 @@ fn detect_bluetooth_drivers(installed: &HashMap<String, String>) -> Result<Vec<D
         let package_installed = installed.contains_key(*package);
         
 
-        let is_active = Command::new("systemctl")
-            .args(&["is-active", "bluetooth"])
-            .output()
-            .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "active")
-            .unwrap_or(false);
+        let is_active = probe.bluetooth_active;
         
         let is_truly_installed = package_installed && is_active;
         
@@ -1104,17 +1397,13 @@ pub fn install_driver(package_name: &str) -> Result<()> {
     if package_name.contains("nvidia") {
         println!("🔍 Checking non-free repository for NVIDIA driver...");
         
-        let sources_check = Command::new("grep")
-            .args(&["-r", "non-free", "/etc/apt/sources.list", "/etc/apt/sources.list.d/"])
-            .output();
-        
-        let has_nonfree = sources_check
-            .map(|output| !output.stdout.is_empty())
-            .unwrap_or(false);
+        // Unreadable sources are left for apt to complain about
+        let has_nonfree = repo_manager::get_repositories()
+            .map_or(true, |repos| nonfree_enabled(&repos));
         
         if !has_nonfree {
             println!("❌ Non-free repository is not active!");
-            bail!("Non-free repository required for NVIDIA drivers.\n\nTo enable:\nsudo apt edit-sources\n\nAnd add 'non-free' at the end of the line.");
+            return Err(Error::Other("Non-free repository required for NVIDIA drivers.\n\nTo enable:\nsudo apt edit-sources\n\nAnd add 'non-free' at the end of the line.".to_string()));
         }
         
         println!("✅ Non-free repository active");
@@ -1122,43 +1411,26 @@ pub fn install_driver(package_name: &str) -> Result<()> {
     
 
     println!("🔍 Checking package availability: {}", package_name);
-    let search_result = Command::new("apt")
-        .args(&["search", package_name])
-        .output()
+    let search_output = metadata::query("apt", &["search", package_name])
         .context("Could not run apt search command")?;
-    
-    let search_output = String::from_utf8_lossy(&search_result.stdout);
     if !search_output.contains(package_name) {
-        bail!("Package not found: {}\n\nCheck available packages:\napt search nvidia-driver", package_name);
+        return Err(Error::Other(format!("Package not found: {}\n\nCheck available packages:\napt search nvidia-driver", package_name)));
     }
     
     println!("✅ Package found: {}", package_name);
     
 
     println!("💾 Creating driver backup...");
-    let _backup_dir = create_driver_backup()?;
-    
-
-    println!("🔄 Updating package database...");
-    let update_status = Command::new("pkexec")
-        .args(&["apt", "update"])
-        .status()
-        .context("apt update command could not be executed")?;
-    
-    if !update_status.success() {
-        println!("⚠️ apt update failed, continuing...");
-    }
+    let backup_dir = create_driver_backup()?;
     
 
     println!("📦 Driver is being installed: {}", package_name);
-    let status = Command::new("pkexec")
-        .args(&["apt", "install", "-y", package_name])
-        .status()
-        .context("Driver install command failed to execute")?;
-    
-    if !status.success() {
-        bail!("Driver installation failed. Check the package name or repository settings.");
-    }
+    Transaction::install(&[package_name.to_string()])
+        .refresh_lists()
+        .journal(OperationKind::DriverInstall)
+        .note(&format!("Backup: {}", backup_dir))
+        .run()
+        .context("Driver installation")?;
     
     println!("✅ Driver successfully installed: {}", package_name);
     Ok(())
@@ -1167,20 +1439,55 @@ pub fn install_driver(package_name: &str) -> Result<()> {
 
 pub fn remove_driver(package_name: &str) -> Result<()> {
 
-    let _backup_dir = create_driver_backup()?;
-    
-    let status = Command::new("pkexec")
-        .args(&["apt", "remove", "--purge", "-y", package_name])
-        .status()
-        .context("Uninstall driver command failed to execute")?;
+    let backup_dir = create_driver_backup()?;
     
-    if !status.success() {
-        bail!("Driver uninstall failed");
+    Transaction::purge(&[package_name.to_string()])
+        .journal(OperationKind::DriverRemove)
+        .note(&format!("Backup: {}", backup_dir))
+        .run()
+        .context("Driver uninstall")
+}
+
+
+/// Whether `driver` is the graphics driver currently driving the display
+pub fn is_active_display_driver(driver: &DriverInfo) -> bool {
+    driver.driver_type == DriverType::Graphics && driver.is_active && driver.is_installed
+}
+
+/// Packages that keep a working display once `driver` is removed. The
+/// modesetting driver ships with xserver-xorg-core.
+pub fn display_fallback_packages(driver: &DriverInfo) -> Vec<&'static str> {
+    let mut packages = vec!["xserver-xorg-core"];
+    if driver.vendor == "NVIDIA" && !driver.package_name.contains("nouveau") {
+        packages.push("xserver-xorg-video-nouveau");
     }
-    
-    Ok(())
+    packages
+}
+
+/// Packages reported as fully installed by
+/// `dpkg-query -W -f '${Package} ${db:Status-Abbrev}\n'`
+pub fn parse_installed_status(output: &str) -> HashSet<String> {
+    output.lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(_, status)| status.trim() == "ii")
+        .map(|(package, _)| package.to_string())
+        .collect()
 }
 
+/// The subset of `packages` that is not installed
+pub fn missing_packages(packages: &[&str]) -> Vec<String> {
+    let installed = Command::new("dpkg-query")
+        .arg("-W")
+        .arg("-f=${Package} ${db:Status-Abbrev}\n")
+        .args(packages)
+        .output()
+        .map(|output| parse_installed_status(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default();
+    packages.iter()
+        .filter(|package| !installed.contains(**package))
+        .map(|package| package.to_string())
+        .collect()
+}
 
 pub fn group_drivers_by_type(drivers: Vec<DriverInfo>) -> HashMap<DriverType, Vec<DriverInfo>> {
     let mut groups: HashMap<DriverType, Vec<DriverInfo>> = HashMap::new();
@@ -1258,6 +1565,19 @@ pub fn rescan_hardware() -> Result<()> {
 
 
 impl DriverType {
+    pub fn icon(&self) -> Icon {
+        match self {
+            DriverType::Graphics => Icon::Graphics,
+            DriverType::Network => Icon::Network,
+            DriverType::Audio => Icon::Audio,
+            DriverType::Bluetooth => Icon::Bluetooth,
+            DriverType::Chipset => Icon::Chipset,
+            DriverType::Storage => Icon::Storage,
+            DriverType::Input => Icon::Input,
+            DriverType::Other => Icon::Device,
+        }
+    }
+
     pub fn display_name(&self) -> &'static str {
         match self {
             DriverType::Graphics => "GPU",
@@ -1271,3 +1591,243 @@ impl DriverType {
         }
     }
 }
+
+impl DriverType {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            DriverType::Graphics => "graphics",
+            DriverType::Network => "network",
+            DriverType::Audio => "audio",
+            DriverType::Bluetooth => "bluetooth",
+            DriverType::Chipset => "chipset",
+            DriverType::Storage => "storage",
+            DriverType::Input => "input",
+            DriverType::Other => "other",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<DriverType> {
+        match value {
+            "graphics" => Some(DriverType::Graphics),
+            "network" => Some(DriverType::Network),
+            "audio" => Some(DriverType::Audio),
+            "bluetooth" => Some(DriverType::Bluetooth),
+            "chipset" => Some(DriverType::Chipset),
+            "storage" => Some(DriverType::Storage),
+            "input" => Some(DriverType::Input),
+            "other" => Some(DriverType::Other),
+            _ => None,
+        }
+    }
+}
+
+impl DriverLicense {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            DriverLicense::Free => "free",
+            DriverLicense::NonFree => "non-free",
+            DriverLicense::Unknown => "unknown",
+        }
+    }
+
+    fn from_str(value: &str) -> DriverLicense {
+        match value {
+            "free" => DriverLicense::Free,
+            "non-free" => DriverLicense::NonFree,
+            _ => DriverLicense::Unknown,
+        }
+    }
+}
+
+static DRIVER_CACHE: Mutex<Option<(String, Vec<DriverInfo>)>> = Mutex::new(None);
+static HOTPLUG_WATCH: AtomicBool = AtomicBool::new(false);
+
+fn driver_cache_file() -> Option<PathBuf> {
+    paths::cache_file("driver_scan")
+}
+
+/// Identifies the current hardware and package state. Plugging a device,
+/// installing a package, updating the package lists or booting another
+/// kernel changes it.
+pub fn hardware_fingerprint() -> String {
+    let mut modaliases = detect_hardware().unwrap_or_default();
+    modaliases.sort();
+
+    let mut hasher = DefaultHasher::new();
+    modaliases.hash(&mut hasher);
+    fs::read_to_string(sysroot::path("/proc/sys/kernel/osrelease")).unwrap_or_default().hash(&mut hasher);
+    for path in ["/var/lib/dpkg/status", "/var/lib/apt/lists"] {
+        fs::metadata(sysroot::path(path))
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|age| age.as_secs())
+            .hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+fn cache_field(value: &str) -> String {
+    value.replace(['\t', '\n'], " ")
+}
+
+/// Serializes a scan: a `fingerprint=` line, then one tab separated line per driver
+pub fn format_driver_cache(fingerprint: &str, drivers: &[DriverInfo]) -> String {
+    let mut content = format!("fingerprint={}\n", fingerprint);
+    for driver in drivers {
+        let fields = [
+            cache_field(&driver.name),
+            cache_field(&driver.description),
+            cache_field(&driver.package_name),
+            cache_field(&driver.version),
+            driver.driver_type.as_str().to_string(),
+            driver.license.as_str().to_string(),
+            cache_field(&driver.vendor),
+            cache_field(&driver.device_id),
+            (driver.is_installed as u8).to_string(),
+            (driver.is_active as u8).to_string(),
+            (driver.is_recommended as u8).to_string(),
+            cache_field(driver.modalias.as_deref().unwrap_or("")),
+            cache_field(driver.origin.as_deref().unwrap_or("")),
+            cache_field(driver.unavailable.as_deref().unwrap_or("")),
+            cache_field(&driver.missing_components.join(" ")),
+        ];
+        content.push_str(&fields.join("\t"));
+        content.push('\n');
+    }
+    content
+}
+
+/// Reads a cached scan, or `None` when it was made for another fingerprint
+pub fn parse_driver_cache(content: &str, fingerprint: &str) -> Option<Vec<DriverInfo>> {
+    let mut lines = content.lines();
+    if lines.next()?.strip_prefix("fingerprint=")? != fingerprint {
+        return None;
+    }
+
+    let mut drivers = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 15 {
+            return None;
+        }
+        let mut driver = DriverInfo::new(
+            fields[0].to_string(),
+            fields[1].to_string(),
+            fields[2].to_string(),
+            fields[3].to_string(),
+            DriverType::from_str(fields[4])?,
+            DriverLicense::from_str(fields[5]),
+            fields[6].to_string(),
+            fields[7].to_string(),
+        );
+        driver.is_installed = fields[8] == "1";
+        driver.is_active = fields[9] == "1";
+        driver.is_recommended = fields[10] == "1";
+        driver.modalias = Some(fields[11].to_string()).filter(|m| !m.is_empty());
+        driver.origin = Some(fields[12].to_string()).filter(|o| !o.is_empty());
+        driver.unavailable = Some(fields[13].to_string()).filter(|u| !u.is_empty());
+        driver.missing_components = fields[14].split_whitespace().map(str::to_string).collect();
+        drivers.push(driver);
+    }
+    Some(drivers)
+}
+
+/// The last scan, if the hardware and packages have not changed since
+pub fn get_cached_drivers() -> Option<Vec<DriverInfo>> {
+    let fingerprint = hardware_fingerprint();
+
+    if let Ok(cache_guard) = DRIVER_CACHE.lock() {
+        if let Some((cached_fingerprint, drivers)) = cache_guard.as_ref() {
+            if *cached_fingerprint == fingerprint {
+                return Some(drivers.clone());
+            }
+        }
+    }
+
+    let content = fs::read_to_string(driver_cache_file()?).ok()?;
+    let drivers = parse_driver_cache(&content, &fingerprint)?;
+    if let Ok(mut cache_guard) = DRIVER_CACHE.lock() {
+        *cache_guard = Some((fingerprint, drivers.clone()));
+    }
+    Some(drivers)
+}
+
+pub fn set_driver_cache(drivers: Vec<DriverInfo>) {
+    let fingerprint = hardware_fingerprint();
+
+    if let Some(path) = driver_cache_file() {
+        if let Some(parent) = path.parent() {
+            let _ = paths::ensure_dir(parent);
+        }
+        if let Err(e) = fs::write(&path, format_driver_cache(&fingerprint, &drivers)) {
+            eprintln!("Could not save the driver scan: {}", e);
+        }
+    }
+
+    if let Ok(mut cache_guard) = DRIVER_CACHE.lock() {
+        *cache_guard = Some((fingerprint, drivers));
+    }
+}
+
+pub fn invalidate_driver_cache() {
+    if let Ok(mut cache_guard) = DRIVER_CACHE.lock() {
+        *cache_guard = None;
+    }
+    if let Some(path) = driver_cache_file() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Cached scan when still valid, a fresh one otherwise
+pub fn detect_drivers_cached() -> Result<Vec<DriverInfo>> {
+    if let Some(drivers) = get_cached_drivers() {
+        println!("📋 Using cached driver scan ({} drivers)", drivers.len());
+        return Ok(drivers);
+    }
+    let drivers = detect_drivers()?;
+    set_driver_cache(drivers.clone());
+    Ok(drivers)
+}
+
+/// Whether a `udevadm monitor` line reports a device being added or removed
+pub fn is_hotplug_event(line: &str) -> bool {
+    let mut fields = line.split_whitespace();
+    fields.next() == Some("UDEV")
+        && fields.nth(1).map_or(false, |action| matches!(action, "add" | "remove" | "bind" | "unbind"))
+}
+
+/// Drops the driver cache whenever udev reports a PCI or USB device
+/// coming or going. Started once per process.
+pub fn watch_hotplug_events() {
+    if HOTPLUG_WATCH.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        let child = Command::new("udevadm")
+            .args(["monitor", "--udev", "--subsystem-match=pci", "--subsystem-match=usb"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Could not watch for hardware changes: {}", e);
+                HOTPLUG_WATCH.store(false, Ordering::SeqCst);
+                return;
+            }
+        };
+
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                if is_hotplug_event(&line) {
+                    println!("🔌 Hardware change detected, driver cache cleared");
+                    invalidate_driver_cache();
+                }
+            }
+        }
+        let _ = child.wait();
+        HOTPLUG_WATCH.store(false, Ordering::SeqCst);
+    });
+}
@@ -1,17 +1,28 @@
 // src/driver_window.rs
 
+use crate::error;
+use crate::format;
 use crate::driver_manager::{self, DriverInfo, DriverType, DriverLicense};
-use crate::driver_progress::DriverProgressWindow;
+use crate::apt::Transaction;
+use crate::config::Config;
+use crate::driver_progress::{self, DriverProgressWindow};
+use crate::progress;
 use gtk::prelude::*;
 use gtk::{
     ApplicationWindow, Box as GtkBox, Button, ListBox, ListBoxRow, ScrolledWindow,
     Orientation, HeaderBar, Label, Dialog, MessageDialog, CheckButton,
     ButtonsType, MessageType, Separator, ComboBoxText, Switch,
-    ResponseType, Frame, Expander, Grid,
+    ResponseType, Frame, Expander, Grid, FileChooserAction, FileChooserNative,
 };
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use crate::gpu_power;
+use crate::hardware_profile;
+use crate::icons;
+use crate::privilege;
+use crate::repo_window::RepoWindow;
+use crate::xorg_config;
 
 pub struct DriverWindow {
     window: Dialog,
@@ -91,13 +102,22 @@ impl DriverWindow {
         let advanced_btn = Button::with_label("⚙️ Advanced");
         advanced_btn.add_css_class("header-button");
 
+        let export_btn = Button::with_label("📋 Export Profile");
+        export_btn.add_css_class("header-button");
+        export_btn.set_tooltip_text(Some("Save an anonymized hardware and driver report to attach to forum posts or bug reports"));
+
         button_box.append(&detect_btn);
         button_box.append(&refresh_btn);
         button_box.append(&advanced_btn);
+        button_box.append(&export_btn);
 
         control_panel.append(&button_box);
         main_vbox.append(&control_panel);
 
+        if let Some(power_bar) = Self::gpu_power_bar(&window) {
+            main_vbox.append(&power_bar);
+        }
+
         let separator = Separator::new(Orientation::Horizontal);
         main_vbox.append(&separator);
 
@@ -151,7 +171,7 @@ impl DriverWindow {
         let category_listbox_clone = category_listbox.clone();
         let window_clone = window.clone();
         detect_btn.connect_clicked(move |_| {
-            Self::detect_drivers_with_progress(&window_clone, &drivers_clone, &filtered_drivers_clone, &driver_listbox_clone, &category_listbox_clone);
+            Self::detect_drivers_with_progress(&window_clone, &drivers_clone, &filtered_drivers_clone, &driver_listbox_clone, &category_listbox_clone, false);
         });
 
         let drivers_refresh = drivers.clone();
@@ -160,7 +180,7 @@ impl DriverWindow {
         let category_listbox_refresh = category_listbox.clone();
         let window_refresh = window.clone();
         refresh_btn.connect_clicked(move |_| {
-            Self::detect_drivers_with_progress(&window_refresh, &drivers_refresh, &filtered_drivers_refresh, &driver_listbox_refresh, &category_listbox_refresh);
+            Self::detect_drivers_with_progress(&window_refresh, &drivers_refresh, &filtered_drivers_refresh, &driver_listbox_refresh, &category_listbox_refresh, false);
         });
 
         let drivers_filter = drivers.clone();
@@ -217,7 +237,14 @@ impl DriverWindow {
             Self::show_advanced_options(&window_advanced, &drivers_advanced, &filtered_drivers_advanced, &driver_listbox_advanced, &category_listbox_advanced);
         });
 
-        Self::detect_drivers_with_progress(&window, &drivers, &filtered_drivers, &driver_listbox, &category_listbox);
+        let window_export = window.clone();
+        let drivers_export = drivers.clone();
+        export_btn.connect_clicked(move |_| {
+            Self::export_hardware_profile(&window_export, drivers_export.borrow().clone());
+        });
+
+        driver_manager::watch_hotplug_events();
+        Self::detect_drivers_with_progress(&window, &drivers, &filtered_drivers, &driver_listbox, &category_listbox, true);
 
         Self { window }
     }
@@ -226,6 +253,123 @@ impl DriverWindow {
         self.window.show();
     }
 
+    /// Power state of the discrete GPU on hybrid graphics laptops, with the
+    /// runtime power management switch. `None` on single-GPU systems.
+    fn gpu_power_bar(window: &Dialog) -> Option<GtkBox> {
+        let gpu = gpu_power::discrete_gpu()?;
+
+        let power_bar = GtkBox::new(Orientation::Horizontal, 12);
+        power_bar.set_margin_bottom(12);
+        power_bar.set_margin_start(16);
+        power_bar.set_margin_end(16);
+
+        let state_label = Label::new(None);
+        state_label.set_hexpand(true);
+        state_label.set_halign(gtk::Align::Start);
+
+        let pm_label = Label::new(Some("Suspend when idle"));
+        let pm_switch = Switch::new();
+        pm_switch.set_valign(gtk::Align::Center);
+        pm_switch.set_active(gpu.runtime_pm);
+        pm_switch.set_sensitive(gpu.runtime_pm_supported && privilege::can_run_commands());
+        let pm_tooltip = if !gpu.runtime_pm_supported {
+            "The GPU is switched by bbswitch, which does not support runtime power management"
+        } else if !privilege::can_run_commands() {
+            privilege::HELPER_UNSUPPORTED
+        } else {
+            "Runtime power management lets the kernel power the GPU down while no application uses it, which saves battery"
+        };
+        pm_label.set_tooltip_text(Some(pm_tooltip));
+        pm_switch.set_tooltip_text(Some(pm_tooltip));
+
+        power_bar.append(&state_label);
+        power_bar.append(&pm_label);
+        power_bar.append(&pm_switch);
+
+        let show_state = move |gpu: &gpu_power::DiscreteGpu| {
+            state_label.set_text(&format!("🎮 Discrete {} GPU ({}): {}", gpu.vendor, gpu.address, gpu.state.label()));
+        };
+        show_state(&gpu);
+
+        // Set while the switch is put back after a failure
+        let reverting = Rc::new(std::cell::Cell::new(false));
+        let window_pm = window.clone();
+        let address = gpu.address.clone();
+        pm_switch.connect_state_set(move |switch, enabled| {
+            if reverting.get() {
+                return glib::Propagation::Proceed;
+            }
+            match gpu_power::set_runtime_pm(&address, enabled) {
+                Ok(()) => glib::Propagation::Proceed,
+                Err(e) => {
+                    error::show_error_dialog(&window_pm, "❌ The GPU power management could not be changed", &e, None);
+                    reverting.set(true);
+                    switch.set_active(!enabled);
+                    reverting.set(false);
+                    glib::Propagation::Stop
+                }
+            }
+        });
+
+        // The GPU suspends and wakes on its own, so keep the state current
+        let bar_weak = power_bar.downgrade();
+        glib::timeout_add_seconds_local(5, move || {
+            if bar_weak.upgrade().is_none() {
+                return glib::ControlFlow::Break;
+            }
+            if let Some(gpu) = gpu_power::discrete_gpu() {
+                show_state(&gpu);
+            }
+            glib::ControlFlow::Continue
+        });
+
+        Some(power_bar)
+    }
+
+    /// Asks where to save the hardware profile; a `.json` name selects JSON
+    fn export_hardware_profile(parent: &Dialog, drivers: Vec<DriverInfo>) {
+        let chooser = FileChooserNative::new(
+            Some("Export Hardware Profile"),
+            Some(parent),
+            FileChooserAction::Save,
+            Some("Save"),
+            Some("Cancel"),
+        );
+        chooser.set_current_name("hardware-profile.txt");
+
+        let parent = parent.clone();
+        chooser.connect_response(move |chooser, response| {
+            let path = chooser.file().and_then(|file| file.path());
+            chooser.destroy();
+            let path = match path {
+                Some(path) if response == ResponseType::Accept => path,
+                _ => return,
+            };
+
+            let parent = parent.clone();
+            let drivers = drivers.clone();
+            glib::spawn_future_local(async move {
+                let format = hardware_profile::ProfileFormat::from_path(&path);
+                let sections = gio::spawn_blocking(hardware_profile::collect).await.unwrap_or_default();
+                let report = hardware_profile::format_report(format, &sections, &drivers);
+                let (message_type, text) = match std::fs::write(&path, report) {
+                    Ok(()) => (MessageType::Info, format!("✅ Hardware profile saved to {}\n\nMAC addresses, the host name and your user name were removed.", path.display())),
+                    Err(e) => (MessageType::Error, format!("❌ Hardware profile could not be saved:\n{}", e)),
+                };
+                let dialog = MessageDialog::builder()
+                    .transient_for(&parent)
+                    .modal(true)
+                    .message_type(message_type)
+                    .buttons(ButtonsType::Ok)
+                    .text(&text)
+                    .build();
+                dialog.connect_response(|dlg, _| dlg.close());
+                dialog.show();
+            });
+        });
+        chooser.show();
+    }
+
     fn show_warning_dialog(parent: &Dialog) {
         let warning_dialog = MessageDialog::builder()
             .transient_for(parent)
@@ -257,7 +401,20 @@ impl DriverWindow {
         filtered_drivers: &Rc<RefCell<Vec<DriverInfo>>>,
         driver_listbox: &ListBox,
         category_listbox: &ListBox,
+        use_cache: bool,
     ) {
+        if use_cache {
+            if let Some(cached) = driver_manager::get_cached_drivers() {
+                *drivers.borrow_mut() = cached.clone();
+                *filtered_drivers.borrow_mut() = cached.clone();
+                Self::populate_category_list(category_listbox, &cached);
+                Self::populate_driver_list(driver_listbox, cached);
+                return;
+            }
+        } else {
+            driver_manager::invalidate_driver_cache();
+        }
+
         let app_window = if let Ok(app_win) = parent.clone().upcast::<gtk::Window>().downcast::<ApplicationWindow>() {
             app_win
         } else {
@@ -277,49 +434,37 @@ impl DriverWindow {
 
         glib::spawn_future_local(async move {
             progress_window.set_progress(0.3, "30%");
-            progress_window.append_log("Scanning PCI/USB devices...");
-            
-            glib::timeout_add_local(std::time::Duration::from_millis(500), {
-                let progress_window = progress_window.clone();
-                let drivers_clone = drivers_clone.clone();
-                let filtered_drivers_clone = filtered_drivers_clone.clone();
-                let driver_listbox_clone = driver_listbox_clone.clone();
-                let category_listbox_clone = category_listbox_clone.clone();
-                
-                move || {
-                    progress_window.set_progress(0.6, "60%");
-                    progress_window.append_log("Checking installed drivers...");
-                    
-                    match driver_manager::detect_drivers() {
-                        Ok(detected_drivers) => {
-                            progress_window.set_progress(0.9, "90%");
-                            progress_window.append_log(&format!("{} driver found", detected_drivers.len()));
-                            
-                            *drivers_clone.borrow_mut() = detected_drivers.clone();
-                            *filtered_drivers_clone.borrow_mut() = detected_drivers.clone();
-                            Self::populate_category_list(&category_listbox_clone, &detected_drivers);
-                            Self::populate_driver_list(&driver_listbox_clone, detected_drivers);
-                            
-                            progress_window.set_progress(1.0, "100%");
-                            progress_window.set_status("✅ Driver scan completed!");
-                            
-                            glib::timeout_add_seconds_local(2, {
-                                let window = progress_window.window.clone();
-                                move || {
-                                    window.close();
-                                    glib::ControlFlow::Break
-                                }
-                            });
-                        }
-                        Err(e) => {
-                            progress_window.set_status(&format!("❌ Error: {}", e));
-                            progress_window.append_log(&format!("Driver detection error: {}", e));
+            progress_window.append_log("Probing PCI/USB devices, modules and packages...");
+
+            match gio::spawn_blocking(driver_manager::detect_drivers_cached).await {
+                Ok(Ok(detected_drivers)) => {
+                    progress_window.set_progress(0.9, "90%");
+                    progress_window.append_log(&format!("{} driver found", detected_drivers.len()));
+
+                    *drivers_clone.borrow_mut() = detected_drivers.clone();
+                    *filtered_drivers_clone.borrow_mut() = detected_drivers.clone();
+                    Self::populate_category_list(&category_listbox_clone, &detected_drivers);
+                    Self::populate_driver_list(&driver_listbox_clone, detected_drivers);
+
+                    progress_window.set_progress(1.0, "100%");
+                    progress_window.set_status("✅ Driver scan completed!");
+
+                    glib::timeout_add_seconds_local(2, {
+                        let progress_window = progress_window.clone();
+                        move || {
+                            progress_window.close();
+                            glib::ControlFlow::Break
                         }
-                    }
-                    
-                    glib::ControlFlow::Break
+                    });
                 }
-            });
+                Ok(Err(e)) => {
+                    progress_window.set_status(&format!("❌ Error: {}", e));
+                    progress_window.append_log(&format!("Driver detection error: {}", e));
+                }
+                Err(_) => {
+                    progress_window.set_status("❌ Error: the driver scan stopped unexpectedly");
+                }
+            }
         });
     }
 
@@ -340,16 +485,7 @@ impl DriverWindow {
             hbox.set_margin_start(12);
             hbox.set_margin_end(12);
 
-            let icon_label = Label::new(Some(match driver_type {
-                DriverType::Graphics => "🎮",
-                DriverType::Network => "🌐",
-                DriverType::Audio => "🔊",
-                DriverType::Bluetooth => "📡",
-                DriverType::Chipset => "🔧",
-                DriverType::Storage => "💾",
-                DriverType::Input => "⌨️",
-                DriverType::Other => "🔌",
-            }));
+            let icon_label = icons::widget(driver_type.icon());
 
             let name_label = Label::new(Some(&format!("{} ({})", driver_type.display_name(), type_drivers.len())));
             name_label.set_halign(gtk::Align::Start);
@@ -392,9 +528,9 @@ impl DriverWindow {
 
             let icon_box = GtkBox::new(Orientation::Horizontal, 4);
             
-            let status_icon = Label::new(Some(driver.get_status_icon()));
-            let type_icon = Label::new(Some(driver.get_type_icon()));
-            let license_icon = Label::new(Some(driver.get_license_icon()));
+            let status_icon = icons::widget(driver.status_icon());
+            let type_icon = icons::widget(driver.type_icon());
+            let license_icon = icons::widget(driver.license_icon());
 
             icon_box.append(&status_icon);
             icon_box.append(&type_icon);
@@ -413,7 +549,7 @@ impl DriverWindow {
 
             let details_label = Label::new(Some(&format!("{} • {} • {}", 
                 driver.vendor, 
-                driver.version,
+                driver.version_text(),
                 match driver.license {
                     DriverLicense::Free => "Free",
                     DriverLicense::NonFree => "Closed Source",
@@ -427,6 +563,19 @@ impl DriverWindow {
             info_box.append(&desc_label);
             info_box.append(&details_label);
 
+            let missing_components = if driver.is_installed { Vec::new() } else { driver.missing_components.clone() };
+            if !missing_components.is_empty() {
+                let missing_label = Label::new(Some(&format!(
+                    "⚠️ Needs the {} {}, which no enabled repository has",
+                    missing_components.join(" and "),
+                    if missing_components.len() == 1 { "component" } else { "components" }
+                )));
+                missing_label.set_halign(gtk::Align::Start);
+                missing_label.set_wrap(true);
+                missing_label.add_css_class("size-info");
+                info_box.append(&missing_label);
+            }
+
             let button_box = GtkBox::new(Orientation::Horizontal, 8);
             button_box.set_halign(gtk::Align::End);
             button_box.set_valign(gtk::Align::Center);
@@ -438,7 +587,16 @@ impl DriverWindow {
                 button_box.append(&recommended_label);
             }
 
-            if driver.is_installed {
+            if let Some(reason) = &driver.unavailable {
+                icon_box.set_sensitive(false);
+                info_box.set_sensitive(false);
+
+                let reason_label = Label::new(Some(&format!("🚫 {}", reason)));
+                reason_label.add_css_class("size-info");
+                button_box.append(&reason_label);
+
+                button_box.append(&Self::fix_repositories_button(&missing_components, &driver.license));
+            } else if driver.is_installed {
                 if driver.is_active {
                     let active_btn = Button::with_label("🟢 Active");
                     active_btn.set_sensitive(false);
@@ -470,6 +628,11 @@ impl DriverWindow {
                 
                 button_box.append(&remove_btn);
             } else {
+                // Firmware is listed without asking apt, so offer the fix next to Install
+                if !missing_components.is_empty() {
+                    button_box.append(&Self::fix_repositories_button(&missing_components, &driver.license));
+                }
+
                 let install_btn = Button::with_label("⬇️ Install");
                 install_btn.add_css_class("suggested-action");
                 
@@ -493,6 +656,33 @@ impl DriverWindow {
         }
     }
 
+    /// Opens the Repository Manager at the entry that lacks `missing_components`,
+    /// or just the manager when the components are not known
+    fn fix_repositories_button(missing_components: &[String], license: &DriverLicense) -> Button {
+        let fix_btn = Button::with_label("🗂️ Fix Repositories");
+        let tooltip = if !missing_components.is_empty() {
+            format!("Add {} to your distribution's repository entry, then update the package lists", missing_components.join(" and "))
+        } else if *license == DriverLicense::NonFree {
+            "Add the non-free and non-free-firmware components to your Debian repository, then update the package lists".to_string()
+        } else {
+            "Enable a repository that provides this driver, then update the package lists".to_string()
+        };
+        fix_btn.set_tooltip_text(Some(&tooltip));
+
+        let missing_components = missing_components.to_vec();
+        fix_btn.connect_clicked(move |btn| {
+            if let Some(window) = btn.root().and_downcast::<gtk::Window>() {
+                let repo_window = RepoWindow::new(&window);
+                if missing_components.is_empty() {
+                    repo_window.show();
+                } else {
+                    repo_window.show_adding_components(&missing_components);
+                }
+            }
+        });
+        fix_btn
+    }
+
     fn show_category_drivers(
         drivers: &Rc<RefCell<Vec<DriverInfo>>>,
         listbox: &ListBox,
@@ -527,25 +717,25 @@ impl DriverWindow {
         dialog.connect_response(move |dialog, response| {
             if response == ResponseType::Yes {
                 let success = if driver_clone.package_name.contains("nvidia") {
-                    std::process::Command::new("pkexec")
+                    privilege::elevated_command()
                         .args(&["modprobe", "nvidia"])
                         .status()
                         .map(|s| s.success())
                         .unwrap_or(false)
                 } else if driver_clone.package_name.contains("nouveau") {
-                    std::process::Command::new("pkexec")
+                    privilege::elevated_command()
                         .args(&["modprobe", "nouveau"])
                         .status()
                         .map(|s| s.success())
                         .unwrap_or(false)
                 } else if driver_clone.package_name == "bluez" {
-                    std::process::Command::new("pkexec")
+                    privilege::elevated_command()
                         .args(&["systemctl", "start", "bluetooth"])
                         .status()
                         .map(|s| s.success())
                         .unwrap_or(false)
                 } else {
-                    std::process::Command::new("pkexec")
+                    privilege::elevated_command()
                         .args(&["modprobe", &driver_clone.package_name])
                         .status()
                         .map(|s| s.success())
@@ -575,28 +765,93 @@ impl DriverWindow {
     }
 
     fn install_driver_with_progress(parent: &gtk::Window, driver: &DriverInfo) {
+        let transaction = driver_progress::install_transaction(&driver.package_name);
+        // Moving the files aside needs a script the host helper does not run
+        let stale = if driver.driver_type == DriverType::Graphics && privilege::can_run_commands() {
+            xorg_config::conflicting_files(&driver.package_name)
+        } else {
+            Vec::new()
+        };
+        if stale.is_empty() {
+            Self::run_driver_transaction(parent, transaction, false);
+            return;
+        }
+
+        let dialog = MessageDialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .message_type(MessageType::Warning)
+            .buttons(ButtonsType::None)
+            .text("🖥️ Old X Configuration Found")
+            .secondary_text(&format!(
+                "These files select a different graphics driver and often keep '{}' from starting:\n\n    {}\n\nThey can be moved to a backup before installing. Removing the driver again puts them back.",
+                driver.package_name,
+                stale.join("\n    ")
+            ))
+            .build();
+        dialog.add_button("Cancel", ResponseType::Cancel);
+        dialog.add_button("Keep Them", ResponseType::Reject);
+        let move_btn = dialog.add_button("Back Up and Remove", ResponseType::Accept);
+        move_btn.add_css_class("suggested-action");
+
+        let package_name = driver.package_name.clone();
+        let parent_clone = parent.clone();
+        dialog.connect_response(move |dialog, response| {
+            dialog.close();
+            let transaction = match response {
+                ResponseType::Accept => {
+                    let backup = xorg_config::backup_dir(format::now());
+                    xorg_config::record_backup(&package_name, &backup);
+                    transaction.clone().run_before(&["sh", "-c", &xorg_config::move_aside_script(&backup, &stale)])
+                }
+                ResponseType::Reject => transaction.clone(),
+                _ => return,
+            };
+            Self::run_driver_transaction(&parent_clone, transaction, false);
+        });
+        dialog.show();
+    }
+
+    /// Confirms the planned changes, then runs `transaction` in a progress window
+    fn run_driver_transaction(parent: &gtk::Window, transaction: Transaction, removal: bool) {
         let app_window = if let Ok(app_win) = parent.clone().downcast::<ApplicationWindow>() {
             app_win
         } else {
             ApplicationWindow::builder().build()
         };
-        
-        let progress_window = DriverProgressWindow::new(&app_window);
-        progress_window.show();
-        
-        let driver_clone = driver.clone();
-        
-        glib::spawn_future_local(async move {
-            match progress_window.install_driver_with_progress(&driver_clone.package_name).await {
-                Ok(_) => {},
-                Err(e) => {
-                    eprintln!("Driver installation error: {}", e);
+
+        progress::confirm_transaction(parent, transaction, move |transaction| {
+            let packages = transaction.packages().to_vec();
+            let progress_window = DriverProgressWindow::new(&app_window);
+            progress_window.show();
+
+            glib::spawn_future_local(async move {
+                let result = if removal {
+                    progress_window.remove_driver_with_progress(transaction).await
+                } else {
+                    progress_window.install_driver_with_progress(transaction).await
+                };
+                driver_manager::invalidate_driver_cache();
+                match result {
+                    // The X configuration backup was restored by the removal
+                    Ok(()) if removal => {
+                        for package in &packages {
+                            xorg_config::forget_backup(package);
+                        }
+                    }
+                    Ok(()) => {}
+                    Err(e) => eprintln!("Driver operation error: {}", e),
                 }
-            }
+            });
         });
     }
 
     fn show_remove_confirmation(parent: &gtk::Window, driver: &DriverInfo) {
+        if driver_manager::is_active_display_driver(driver) {
+            Self::show_display_driver_removal(parent, driver);
+            return;
+        }
+
         let dialog = MessageDialog::builder()
             .transient_for(parent)
             .modal(true)
@@ -613,24 +868,7 @@ impl DriverWindow {
         let parent_clone = parent.clone();
         dialog.connect_response(move |dialog, response| {
             if response == ResponseType::Yes {
-                let app_window = if let Ok(app_win) = parent_clone.clone().downcast::<ApplicationWindow>() {
-                    app_win
-                } else {
-                    ApplicationWindow::builder().build()
-                };
-                
-                let progress_window = DriverProgressWindow::new(&app_window);
-                progress_window.show();
-                
-                let driver_pkg = driver_clone.package_name.clone();
-                glib::spawn_future_local(async move {
-                    match progress_window.remove_driver_with_progress(&driver_pkg).await {
-                        Ok(_) => {},
-                        Err(e) => {
-                            eprintln!("Driver uninstall error: {}", e);
-                        }
-                    }
-                });
+                Self::run_driver_transaction(&parent_clone, driver_progress::remove_transaction(&driver_clone.package_name), true);
             }
             dialog.close();
         });
@@ -638,6 +876,57 @@ impl DriverWindow {
         dialog.show();
     }
 
+    /// Removing the driver that drives the screen needs a second, explicit
+    /// confirmation and installs a fallback driver in the same apt run
+    fn show_display_driver_removal(parent: &gtk::Window, driver: &DriverInfo) {
+        let fallback = driver_manager::missing_packages(&driver_manager::display_fallback_packages(driver));
+        let fallback_note = if fallback.is_empty() {
+            "A fallback driver (nouveau or modesetting) is already installed.".to_string()
+        } else {
+            format!("These fallback packages will be installed in the same step:\n    {}", fallback.join("\n    "))
+        };
+
+        let dialog = MessageDialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .message_type(MessageType::Warning)
+            .buttons(ButtonsType::None)
+            .text("⚠️ Removing the Active Display Driver")
+            .secondary_text(&format!(
+                "'{}' is currently driving your display.\n\nAfter removing it the graphical session may freeze or drop to a text console, and the desktop only comes back after a reboot.\n\n{}",
+                driver.package_name, fallback_note
+            ))
+            .build();
+
+        let understood = CheckButton::with_label("I understand that my session may drop to a text console");
+        understood.set_margin_top(8);
+        if let Ok(message_area) = dialog.message_area().downcast::<GtkBox>() {
+            message_area.append(&understood);
+        }
+
+        dialog.add_button("Cancel", ResponseType::Cancel);
+        let remove_btn = dialog.add_button("Remove Anyway", ResponseType::Accept);
+        remove_btn.add_css_class("destructive-action");
+        remove_btn.set_sensitive(false);
+        understood.connect_toggled(move |check| remove_btn.set_sensitive(check.is_active()));
+
+        let package_name = driver.package_name.clone();
+        let parent_clone = parent.clone();
+        dialog.connect_response(move |dialog, response| {
+            dialog.close();
+            if response != ResponseType::Accept {
+                return;
+            }
+            let mut transaction = driver_progress::remove_transaction(&package_name);
+            if !fallback.is_empty() {
+                transaction = transaction.ensure_installed(&fallback).refresh_lists();
+            }
+            Self::run_driver_transaction(&parent_clone, transaction, true);
+        });
+
+        dialog.show();
+    }
+
     fn show_advanced_options(
         parent: &Dialog,
         drivers: &Rc<RefCell<Vec<DriverInfo>>>,
@@ -679,17 +968,9 @@ impl DriverWindow {
             if let Err(e) = driver_manager::rescan_hardware() {
                 eprintln!("Hardware scan error: {}", e);
                 
-                let error_dialog = MessageDialog::builder()
-                    .transient_for(&parent_rescan)
-                    .modal(true)
-                    .message_type(MessageType::Error)
-                    .buttons(ButtonsType::Ok)
-                    .text(&format!("❌ Hardware rescan error:\n{}", e))
-                    .build();
-                error_dialog.connect_response(|dlg, _| dlg.close());
-                error_dialog.show();
+                error::show_error_dialog(&parent_rescan, "❌ Hardware rescan error", &e, None);
             } else {
-                Self::detect_drivers_with_progress(&parent_rescan, &drivers_rescan, &filtered_drivers_rescan, &driver_listbox_rescan, &category_listbox_rescan);
+                Self::detect_drivers_with_progress(&parent_rescan, &drivers_rescan, &filtered_drivers_rescan, &driver_listbox_rescan, &category_listbox_rescan, false);
                 
                 let success_dialog = MessageDialog::builder()
                     .transient_for(&parent_rescan)
@@ -722,7 +1003,12 @@ impl DriverWindow {
         management_box.set_margin_end(12);
 
         let backup_switch = Switch::new();
-        backup_switch.set_active(true);
+        backup_switch.set_active(Config::load().driver_backup);
+        backup_switch.connect_active_notify(|switch| {
+            let mut config = Config::load();
+            config.driver_backup = switch.is_active();
+            config.save();
+        });
         let backup_box = GtkBox::new(Orientation::Horizontal, 8);
         backup_box.append(&Label::new(Some("Take backup before changing drivers:")));
         backup_box.append(&backup_switch);
@@ -743,15 +1029,7 @@ impl DriverWindow {
                     success_dialog.show();
                 }
                 Err(e) => {
-                    let error_dialog = MessageDialog::builder()
-                        .transient_for(&parent_backup)
-                        .modal(true)
-                        .message_type(MessageType::Error)
-                        .buttons(ButtonsType::Ok)
-                        .text(&format!("❌ Backup creation error:\n{}", e))
-                        .build();
-                    error_dialog.connect_response(|dlg, _| dlg.close());
-                    error_dialog.show();
+                    error::show_error_dialog(&parent_backup, "❌ Backup creation error", &e, None);
                 }
             }
         });
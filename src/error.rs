@@ -0,0 +1,235 @@
+// src/error.rs
+
+#[cfg(feature = "gui")]
+use gtk::prelude::*;
+#[cfg(feature = "gui")]
+use gtk::{ButtonsType, MessageDialog, MessageType, ResponseType};
+use std::fmt;
+use std::io;
+use std::process::ExitStatus;
+use crate::privilege;
+
+/// Errors of the package management code. The UI matches on the kind to
+/// show a targeted hint instead of only the raw message.
+#[derive(Debug)]
+pub enum Error {
+    /// apt or dpkg ran but reported a failure
+    Apt(String),
+    /// Administrator rights were refused or are not available
+    PermissionDenied(String),
+    /// Another package manager holds the apt or dpkg lock
+    LockHeld(String),
+    /// A repository or download server could not be reached
+    Network(String),
+    /// Command output or a file could not be understood
+    Parse(String),
+    /// A program could not be started or a file could not be accessed
+    Io { context: String, source: io::Error },
+    /// The user stopped the operation
+    Cancelled,
+    Other(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const LOCK_MARKERS: &[&str] = &[
+    "could not get lock",
+    "unable to acquire the dpkg frontend lock",
+    "unable to lock the administration directory",
+    "unable to lock directory",
+    "is another process using it",
+];
+
+const NETWORK_MARKERS: &[&str] = &[
+    "temporary failure resolving",
+    "could not resolve",
+    "failed to fetch",
+    "network is unreachable",
+    "connection timed out",
+    "could not connect to",
+    "connection failed",
+];
+
+const PERMISSION_MARKERS: &[&str] = &[
+    "permission denied",
+    "are you root?",
+    "not authorized",
+];
+
+impl Error {
+    /// Classifies the error output of apt, apt-get or dpkg
+    pub fn from_apt_output(output: &str) -> Error {
+        let lower = output.to_lowercase();
+        let message = output.trim().to_string();
+
+        // Running apt without root also fails to take the lock, so the
+        // permission markers are checked first
+        if PERMISSION_MARKERS.iter().any(|marker| lower.contains(marker)) {
+            Error::PermissionDenied(message)
+        } else if LOCK_MARKERS.iter().any(|marker| lower.contains(marker)) {
+            Error::LockHeld(message)
+        } else if NETWORK_MARKERS.iter().any(|marker| lower.contains(marker)) {
+            Error::Network(message)
+        } else {
+            Error::Apt(message)
+        }
+    }
+
+    /// Error for a command that exited with `status`. `what` names the
+    /// command and `stderr` is its error output, if it was captured.
+    pub fn from_status(what: &str, status: &ExitStatus, stderr: &str) -> Error {
+        if let Some(reason) = privilege::describe_exit_status(status) {
+            return Error::PermissionDenied(format!("{} failed: {}", what, reason));
+        }
+        if stderr.trim().is_empty() {
+            return Error::Apt(format!("{} failed ({})", what, status));
+        }
+        match Error::from_apt_output(stderr) {
+            Error::Apt(message) => Error::Apt(format!("{} failed:\n{}", what, message)),
+            other => other,
+        }
+    }
+
+    /// Adds what was being done when the error happened
+    pub fn with_context(self, context: &str) -> Error {
+        match self {
+            Error::Io { source, .. } => Error::Io { context: context.to_string(), source },
+            Error::Apt(message) => Error::Apt(format!("{}: {}", context, message)),
+            Error::PermissionDenied(message) => Error::PermissionDenied(format!("{}: {}", context, message)),
+            Error::LockHeld(message) => Error::LockHeld(format!("{}: {}", context, message)),
+            Error::Network(message) => Error::Network(format!("{}: {}", context, message)),
+            Error::Parse(message) => Error::Parse(format!("{}: {}", context, message)),
+            Error::Other(message) => Error::Other(format!("{}: {}", context, message)),
+            Error::Cancelled => Error::Cancelled,
+        }
+    }
+
+    /// Lock and network problems usually go away on their own
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Error::LockHeld(_) | Error::Network(_))
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Error::Apt(_) => "❌ Package Manager Error",
+            Error::PermissionDenied(_) => "🔐 Permission Denied",
+            Error::LockHeld(_) => "🔒 Package System Busy",
+            Error::Network(_) => "🌐 Network Problem",
+            Error::Parse(_) => "❌ Unexpected Output",
+            Error::Cancelled => "⏹️ Cancelled",
+            Error::Io { .. } | Error::Other(_) => "❌ Error",
+        }
+    }
+
+    /// What the user can do about the error
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Error::Apt(_) => Some("If an earlier installation was interrupted, running\n    sudo dpkg --configure -a\n    sudo apt -f install\nrepairs most problems.".to_string()),
+            Error::PermissionDenied(_) => Some(privilege::manual_instructions()),
+            Error::LockHeld(_) => Some("Another program (a software center, unattended-upgrades or a terminal) is installing packages. Wait for it to finish and try again.".to_string()),
+            Error::Network(_) => Some("Check your internet connection and proxy settings, then try again.".to_string()),
+            Error::Parse(_) | Error::Io { .. } | Error::Cancelled | Error::Other(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Apt(message)
+            | Error::PermissionDenied(message)
+            | Error::LockHeld(message)
+            | Error::Network(message)
+            | Error::Parse(message)
+            | Error::Other(message) => write!(f, "{}", message),
+            Error::Io { context, source } if context.is_empty() => write!(f, "{}", source),
+            Error::Io { context, source } => write!(f, "{}: {}", context, source),
+            Error::Cancelled => write!(f, "The operation was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::PermissionDenied {
+            return Error::PermissionDenied(error.to_string());
+        }
+        Error::Io { context: String::new(), source: error }
+    }
+}
+
+impl From<glib::Error> for Error {
+    fn from(error: glib::Error) -> Self {
+        Error::Other(error.to_string())
+    }
+}
+
+/// `anyhow`-style `.context()` for results and options
+pub trait Context<T> {
+    fn context(self, context: &str) -> Result<T>;
+
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for std::result::Result<T, E> {
+    fn context(self, context: &str) -> Result<T> {
+        self.map_err(|e| e.into().with_context(context))
+    }
+
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T> {
+        self.map_err(|e| e.into().with_context(&context()))
+    }
+}
+
+impl<T> Context<T> for Option<T> {
+    fn context(self, context: &str) -> Result<T> {
+        self.ok_or_else(|| Error::Other(context.to_string()))
+    }
+
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T> {
+        self.ok_or_else(|| Error::Other(context()))
+    }
+}
+
+/// Shows `error` with a hint for its kind. Lock and network errors get a
+/// "Try Again" button when `retry` is given.
+#[cfg(feature = "gui")]
+pub fn show_error_dialog(parent: &impl IsA<gtk::Window>, heading: &str, error: &Error, retry: Option<Box<dyn Fn()>>) {
+    let secondary = match error.hint() {
+        Some(hint) => format!("{}\n\n{}\n\n💡 {}", heading, error, hint),
+        None => format!("{}\n\n{}", heading, error),
+    };
+    let message_type = if error.is_transient() { MessageType::Warning } else { MessageType::Error };
+
+    let dialog = MessageDialog::builder()
+        .transient_for(parent)
+        .modal(true)
+        .message_type(message_type)
+        .buttons(ButtonsType::Close)
+        .text(error.title())
+        .secondary_text(&secondary)
+        .build();
+
+    let retry = retry.filter(|_| error.is_transient());
+    if retry.is_some() {
+        dialog.add_button("Try Again", ResponseType::Accept);
+    }
+    dialog.connect_response(move |dlg, response| {
+        dlg.close();
+        if response == ResponseType::Accept {
+            if let Some(retry) = &retry {
+                retry();
+            }
+        }
+    });
+    dialog.show();
+}
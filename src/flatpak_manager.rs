@@ -0,0 +1,183 @@
+// src/flatpak_manager.rs
+
+use crate::apt::TransactionEvent;
+use crate::error::{Context, Error, Result};
+use crate::model::{PackageUpdate, UpdateType};
+use crate::util;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+/// Whether the flatpak command is installed
+pub fn is_available() -> bool {
+    Command::new("flatpak")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Parses sizes as GLib formats them, e.g. "12.3 MB" or "512 bytes"
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.replace('\u{a0}', " ");
+    let (number, unit) = text.trim().split_once(' ')?;
+    let factor = util::si_unit_factor(unit.trim())?;
+    let number: f64 = number.replace(',', ".").parse().ok()?;
+    Some((number * factor) as u64)
+}
+
+/// Parses `flatpak list --columns=application,version,branch` into
+/// application → installed version
+pub fn parse_installed(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let application = fields.first()?.trim();
+            if application.is_empty() {
+                return None;
+            }
+            let version = fields.get(1).map(|v| v.trim()).filter(|v| !v.is_empty())
+                .or_else(|| fields.get(2).map(|b| b.trim()))
+                .unwrap_or("");
+            Some((application.to_string(), version.to_string()))
+        })
+        .collect()
+}
+
+/// Parses `flatpak remote-ls --updates --columns=application,version,branch,download-size`.
+/// Branches of the same application are updated together, so each appears once.
+pub fn parse_updates(output: &str, installed: &HashMap<String, String>) -> Vec<PackageUpdate> {
+    let mut updates: Vec<PackageUpdate> = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+        let application = match fields.first() {
+            Some(application) if !application.is_empty() => application.to_string(),
+            _ => continue,
+        };
+        if updates.iter().any(|update| update.name == application) {
+            continue;
+        }
+        let version = fields.get(1).copied().filter(|v| !v.is_empty())
+            .or_else(|| fields.get(2).copied())
+            .unwrap_or("")
+            .to_string();
+        let current_version = installed.get(&application)
+            .filter(|current| **current != version)
+            .cloned()
+            .unwrap_or_default();
+
+        updates.push(PackageUpdate {
+            name: application,
+            current_version,
+            new_version: version,
+            update_type: UpdateType::Flatpak,
+            from_security: false,
+            size: fields.get(3).and_then(|size| parse_size(size)),
+            first_seen: None,
+        });
+    }
+    updates
+}
+
+fn flatpak_output(args: &[&str]) -> Result<String> {
+    let output = Command::new("flatpak")
+        .args(args)
+        .env("LANG", "C")
+        .output()
+        .context("flatpak could not be run")?;
+    if !output.status.success() {
+        return Err(Error::from_status(&format!("flatpak {}", args[0]), &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Pending updates of installed Flatpak applications and runtimes
+pub fn get_updates() -> Result<Vec<PackageUpdate>> {
+    let installed = parse_installed(&flatpak_output(&["list", "--columns=application,version,branch"])?);
+    let updates = flatpak_output(&["remote-ls", "--updates", "--columns=application,version,branch,download-size"])?;
+    Ok(parse_updates(&updates, &installed))
+}
+
+/// Adds the Flatpak updates to an APT update list. A broken Flatpak setup
+/// must not hide the APT updates, so errors are only logged.
+pub fn append_updates(packages: &mut Vec<PackageUpdate>) {
+    if !is_available() {
+        return;
+    }
+    match get_updates() {
+        Ok(updates) => {
+            let mut updates = updates;
+            crate::update_age::annotate_first_seen(&mut updates);
+            packages.extend(updates);
+        }
+        Err(e) => eprintln!("Flatpak updates could not be listed: {}", e),
+    }
+}
+
+/// Reads "Updating 2/5…" progress lines of `flatpak update`
+pub fn parse_progress_line(line: &str) -> Option<(usize, usize)> {
+    let rest = line.trim().strip_prefix("Updating ")?;
+    let counter = rest.split(|c: char| !c.is_ascii_digit() && c != '/').next()?;
+    let (done, total) = counter.split_once('/')?;
+    let (done, total) = (done.parse().ok()?, total.parse::<usize>().ok()?);
+    if total == 0 { None } else { Some((done, total)) }
+}
+
+/// Names of the Flatpak updates among `names`
+pub fn flatpak_names(packages: &[PackageUpdate], names: &[String]) -> Vec<String> {
+    names.iter()
+        .filter(|name| packages.iter().any(|pkg| pkg.name == **name && pkg.update_type == UpdateType::Flatpak))
+        .cloned()
+        .collect()
+}
+
+/// Updates the given applications, reporting progress like an APT
+/// transaction. Flatpak asks polkit itself for system installations.
+pub fn update(applications: &[String], report: &dyn Fn(TransactionEvent)) -> Result<()> {
+    if applications.is_empty() {
+        return Ok(());
+    }
+    report(TransactionEvent::Status("Updating Flatpak applications...".to_string()));
+    report(TransactionEvent::Log(format!("Command: flatpak update -y --noninteractive {}", applications.join(" "))));
+
+    let mut child = Command::new("flatpak")
+        .args(["update", "-y", "--noninteractive"])
+        .args(applications)
+        .env("LANG", "C")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("flatpak could not be run")?;
+
+    let stderr = child.stderr.take();
+    let stderr_reader = std::thread::spawn(move || {
+        let mut collected = String::new();
+        if let Some(stderr) = stderr {
+            for line in BufReader::new(stderr).lines().map_while(std::result::Result::ok) {
+                collected.push_str(&line);
+                collected.push('\n');
+            }
+        }
+        collected
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(std::result::Result::ok) {
+            if let Some((done, total)) = parse_progress_line(&line) {
+                report(TransactionEvent::Progress(done.saturating_sub(1) as f64 / total as f64));
+            }
+            report(TransactionEvent::Log(line));
+        }
+    }
+
+    let status = child.wait().context("flatpak update did not finish")?;
+    let stderr = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        return Err(Error::from_status("flatpak update", &status, &stderr));
+    }
+    report(TransactionEvent::Progress(1.0));
+    Ok(())
+}
@@ -0,0 +1,104 @@
+// src/format.rs
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// "1 day" / "3 days". Every unit word shown to the user goes through
+/// here, so translations only need to hook in at one place.
+pub fn plural(count: u64, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+/// "just now", "5 minutes ago", "2 hours ago", "3 days ago" for `timestamp`
+/// seen from `now`
+pub fn relative_time(timestamp: u64, now: u64) -> String {
+    let seconds = now.saturating_sub(timestamp);
+    let amount = match seconds {
+        0..=59 => return "just now".to_string(),
+        s if s < HOUR => plural(s / MINUTE, "minute", "minutes"),
+        s if s < DAY => plural(s / HOUR, "hour", "hours"),
+        s => plural(s / DAY, "day", "days"),
+    };
+    format!("{} ago", amount)
+}
+
+/// `relative_time` as seen from the current time
+pub fn time_ago(timestamp: u64) -> String {
+    relative_time(timestamp, now())
+}
+
+/// "less than a minute left", "about 5 minutes left", "about 2 hours left"
+pub fn time_left(seconds: u64) -> String {
+    let amount = match seconds {
+        0..=59 => return "less than a minute left".to_string(),
+        s if s < HOUR => plural(s.div_ceil(MINUTE), "minute", "minutes"),
+        s => plural((s + HOUR / 2) / HOUR, "hour", "hours"),
+    };
+    format!("about {} left", amount)
+}
+
+/// Age in whole days, "New today" for less than a day
+pub fn days(days: u64) -> String {
+    match days {
+        0 => "New today".to_string(),
+        n => plural(n, "day", "days"),
+    }
+}
+
+/// The decimal separator of the user's locale, from the same variables
+/// the C library reads
+pub fn decimal_separator() -> char {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    decimal_separator_for(&locale)
+}
+
+/// Languages that write 1,5 instead of 1.5
+const COMMA_LANGUAGES: &[&str] = &[
+    "bg", "ca", "cs", "da", "de", "el", "es", "et", "fi", "fr", "hr", "hu", "id", "it", "lt", "lv",
+    "nb", "nl", "nn", "pl", "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr", "uk", "vi",
+];
+
+pub fn decimal_separator_for(locale: &str) -> char {
+    let language = locale.split(|c| c == '_' || c == '.' || c == '@').next().unwrap_or("");
+    if COMMA_LANGUAGES.contains(&language) { ',' } else { '.' }
+}
+
+/// Size in binary units with the locale's decimal separator
+pub fn size(bytes: u64) -> String {
+    size_with_separator(bytes, decimal_separator())
+}
+
+/// `size`, or "N/A" when the size is not known
+pub fn optional_size(bytes: Option<u64>) -> String {
+    bytes.map(size).unwrap_or_else(|| "N/A".to_string())
+}
+
+pub fn size_with_separator(bytes: u64, separator: char) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    if unit_index == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit_index]).replacen('.', &separator.to_string(), 1)
+    }
+}
@@ -0,0 +1,156 @@
+// src/gpu_power.rs
+
+use crate::error::{Context, Error, Result};
+use crate::privilege;
+use crate::sysroot;
+use std::fs;
+use std::path::Path;
+
+const DRM_DIR: &str = "/sys/class/drm";
+const PCI_DEVICES_DIR: &str = "/sys/bus/pci/devices";
+const BBSWITCH: &str = "/proc/acpi/bbswitch";
+
+/// Power state of a discrete GPU
+#[derive(Debug, Clone, PartialEq)]
+pub enum PowerState {
+    Active,
+    Suspended,
+    /// Any other runtime PM state, such as "suspending"
+    Other(String),
+}
+
+impl PowerState {
+    pub fn parse(value: &str) -> PowerState {
+        match value.trim() {
+            "active" | "ON" => PowerState::Active,
+            "suspended" | "OFF" => PowerState::Suspended,
+            other => PowerState::Other(other.to_string()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            PowerState::Active => "⚡ Powered on".to_string(),
+            PowerState::Suspended => "💤 Suspended".to_string(),
+            PowerState::Other(state) => format!("⏳ {}", state),
+        }
+    }
+}
+
+/// The second GPU of a hybrid graphics laptop
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscreteGpu {
+    /// PCI address such as "0000:01:00.0"
+    pub address: String,
+    pub vendor: &'static str,
+    pub state: PowerState,
+    /// Whether the kernel may suspend it while idle (`power/control` is "auto")
+    pub runtime_pm: bool,
+    /// Whether runtime PM can be changed, which it cannot under bbswitch
+    pub runtime_pm_supported: bool,
+}
+
+pub fn vendor_name(vendor_id: &str) -> &'static str {
+    match vendor_id.trim() {
+        "0x10de" => "NVIDIA",
+        "0x1002" => "AMD",
+        "0x8086" => "Intel",
+        _ => "Unknown",
+    }
+}
+
+/// Parses `/proc/acpi/bbswitch`, e.g. "0000:01:00.0 OFF"
+pub fn parse_bbswitch(content: &str) -> Option<(String, PowerState)> {
+    let mut words = content.split_whitespace();
+    let address = words.next()?.to_string();
+    let state = PowerState::parse(words.next()?);
+    Some((address, state))
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
+
+/// PCI addresses of the GPUs behind the DRM cards and whether each one
+/// is the boot (integrated) GPU
+fn drm_gpus() -> Vec<(String, bool)> {
+    let mut gpus: Vec<(String, bool)> = Vec::new();
+    let entries = match fs::read_dir(sysroot::path(DRM_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return gpus,
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // card0, not card0-eDP-1 connectors
+        if !name.starts_with("card") || name.contains('-') {
+            continue;
+        }
+        let device = match fs::canonicalize(entry.path().join("device")) {
+            Ok(device) => device,
+            Err(_) => continue,
+        };
+        let address = match device.file_name() {
+            Some(address) => address.to_string_lossy().to_string(),
+            None => continue,
+        };
+        let boot_vga = read_trimmed(&device.join("boot_vga")).as_deref() == Some("1");
+        if !gpus.iter().any(|(known, _)| *known == address) {
+            gpus.push((address, boot_vga));
+        }
+    }
+    gpus.sort();
+    gpus
+}
+
+/// The discrete GPU of a hybrid graphics system. Reads sysfs and
+/// bbswitch only: querying nvidia-smi would wake a suspended GPU.
+pub fn discrete_gpu() -> Option<DiscreteGpu> {
+    if let Some((address, state)) = fs::read_to_string(sysroot::path(BBSWITCH)).ok().as_deref().and_then(parse_bbswitch) {
+        let vendor = read_trimmed(&sysroot::path(PCI_DEVICES_DIR).join(&address).join("vendor"))
+            .map(|id| vendor_name(&id))
+            .unwrap_or("NVIDIA");
+        return Some(DiscreteGpu { address, vendor, state, runtime_pm: false, runtime_pm_supported: false });
+    }
+
+    let gpus = drm_gpus();
+    if gpus.len() < 2 {
+        return None;
+    }
+    let (address, _) = gpus.into_iter().find(|(_, boot_vga)| !boot_vga)?;
+    let device = sysroot::path(PCI_DEVICES_DIR).join(&address);
+    let control = read_trimmed(&device.join("power/control"));
+    Some(DiscreteGpu {
+        vendor: read_trimmed(&device.join("vendor")).map(|id| vendor_name(&id)).unwrap_or("Unknown"),
+        state: read_trimmed(&device.join("power/runtime_status"))
+            .map(|status| PowerState::parse(&status))
+            .unwrap_or_else(|| PowerState::Other("unknown".to_string())),
+        runtime_pm: control.as_deref() == Some("auto"),
+        runtime_pm_supported: control.is_some(),
+        address,
+    })
+}
+
+/// Lets the kernel suspend the GPU at `address` while idle, or keeps it on
+pub fn set_runtime_pm(address: &str, enabled: bool) -> Result<()> {
+    if !privilege::can_run_commands() {
+        return Err(Error::Other(privilege::HELPER_UNSUPPORTED.to_string()));
+    }
+    // The address comes from sysfs, but it still ends up in a root shell
+    if !address.chars().all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.') {
+        return Err(Error::Other(format!("Invalid PCI address: {}", address)));
+    }
+    let script = format!(
+        "echo {} > {}/{}/power/control",
+        if enabled { "auto" } else { "on" },
+        PCI_DEVICES_DIR,
+        address
+    );
+    let status = privilege::elevated_command()
+        .args(["sh", "-c", &script])
+        .status()
+        .context("Could not change the GPU power management")?;
+    if !status.success() {
+        return Err(Error::from_status("Changing the GPU power management", &status, ""));
+    }
+    Ok(())
+}
@@ -0,0 +1,187 @@
+// src/hardware_profile.rs
+
+use crate::driver_manager::DriverInfo;
+use crate::sysroot;
+use crate::util::json_string;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Commands whose output goes into the profile
+const COMMANDS: &[(&str, &[&str])] = &[
+    ("uname", &["-srm"]),
+    ("lspci", &["-nnk"]),
+    ("lsusb", &[]),
+    ("lsmod", &[]),
+    ("dkms", &["status"]),
+];
+
+/// Words shorter than this are too likely to appear by chance to be masked
+const MIN_PRIVATE_LEN: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileFormat {
+    Text,
+    Json,
+}
+
+impl ProfileFormat {
+    /// JSON for `.json` files, plain text otherwise
+    pub fn from_path(path: &Path) -> ProfileFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => ProfileFormat::Json,
+            _ => ProfileFormat::Text,
+        }
+    }
+}
+
+/// Output of one command, already anonymized
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub command: String,
+    pub output: String,
+}
+
+fn is_mac_address(candidate: &[u8]) -> bool {
+    candidate.len() == 17
+        && candidate.iter().enumerate().all(|(i, byte)| {
+            if i % 3 == 2 { *byte == b':' } else { byte.is_ascii_hexdigit() }
+        })
+}
+
+/// Masks MAC addresses and the given private words (host and user name)
+pub fn anonymize(text: &str, private: &[(&str, &str)]) -> String {
+    let bytes = text.as_bytes();
+    let mut masked = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let boundary = i == 0 || !bytes[i - 1].is_ascii_alphanumeric() && bytes[i - 1] != b':';
+        if boundary && i + 17 <= bytes.len() && is_mac_address(&bytes[i..i + 17])
+            && bytes.get(i + 17).map_or(true, |next| !next.is_ascii_alphanumeric() && *next != b':')
+        {
+            masked.push_str("xx:xx:xx:xx:xx:xx");
+            i += 17;
+            continue;
+        }
+        let ch = text[i..].chars().next().unwrap_or_default();
+        masked.push(ch);
+        i += ch.len_utf8().max(1);
+    }
+
+    for (word, replacement) in private {
+        if word.len() >= MIN_PRIVATE_LEN {
+            masked = masked.replace(word, replacement);
+        }
+    }
+    masked
+}
+
+/// The host and user name, with what replaces them in the report
+fn private_words() -> Vec<(String, &'static str)> {
+    let mut words = Vec::new();
+    if let Ok(host) = fs::read_to_string(sysroot::path("/proc/sys/kernel/hostname")) {
+        words.push((host.trim().to_string(), "<hostname>"));
+    }
+    if let Ok(user) = std::env::var("USER") {
+        words.push((user, "<user>"));
+    }
+    words
+}
+
+/// Runs the profile commands; ones that are not installed are noted
+/// instead of failing the export. Blocks, so call it from a worker thread.
+pub fn collect() -> Vec<Section> {
+    let private = private_words();
+    let private: Vec<(&str, &str)> = private.iter().map(|(word, replacement)| (word.as_str(), *replacement)).collect();
+
+    COMMANDS.iter()
+        .map(|(program, args)| {
+            let output = match Command::new(program).args(*args).env("LANG", "C").output() {
+                Ok(output) => {
+                    let stdout = String::from_utf8_lossy(&output.stdout).trim_end().to_string();
+                    if stdout.is_empty() {
+                        String::from_utf8_lossy(&output.stderr).trim_end().to_string()
+                    } else {
+                        stdout
+                    }
+                }
+                Err(e) => format!("(not available: {})", e),
+            };
+            Section {
+                command: std::iter::once(*program).chain(args.iter().copied()).collect::<Vec<_>>().join(" "),
+                output: anonymize(&output, &private),
+            }
+        })
+        .collect()
+}
+
+fn driver_state(driver: &DriverInfo) -> &'static str {
+    if driver.is_active {
+        "active"
+    } else if driver.is_installed {
+        "installed"
+    } else if driver.unavailable.is_some() {
+        "unavailable"
+    } else {
+        "available"
+    }
+}
+
+/// Plain text report for forum posts
+pub fn format_text(sections: &[Section], drivers: &[DriverInfo]) -> String {
+    let mut report = String::from("MeaUpdater hardware profile\n===========================\n");
+    for section in sections {
+        report.push_str(&format!("\n## {}\n{}\n", section.command, section.output));
+    }
+
+    report.push_str("\n## Drivers\n");
+    if drivers.is_empty() {
+        report.push_str("(no driver scan)\n");
+    }
+    for driver in drivers {
+        report.push_str(&format!(
+            "{} [{}] {} {} ({}, {})\n",
+            driver.package_name,
+            driver_state(driver),
+            driver.version,
+            driver.device_id,
+            driver.driver_type.as_str(),
+            driver.license.as_str()
+        ));
+    }
+    report
+}
+
+/// JSON report for issue trackers and scripts
+pub fn format_json(sections: &[Section], drivers: &[DriverInfo]) -> String {
+    let sections: Vec<String> = sections.iter()
+        .map(|section| format!(
+            "    {{\"command\": {}, \"output\": {}}}",
+            json_string(&section.command),
+            json_string(&section.output)
+        ))
+        .collect();
+    let drivers: Vec<String> = drivers.iter()
+        .map(|driver| format!(
+            "    {{\"package\": {}, \"state\": {}, \"version\": {}, \"device_id\": {}, \"type\": {}, \"license\": {}}}",
+            json_string(&driver.package_name),
+            json_string(driver_state(driver)),
+            json_string(&driver.version),
+            json_string(&driver.device_id),
+            json_string(driver.driver_type.as_str()),
+            json_string(driver.license.as_str())
+        ))
+        .collect();
+    format!(
+        "{{\n  \"sections\": [\n{}\n  ],\n  \"drivers\": [\n{}\n  ]\n}}\n",
+        sections.join(",\n"),
+        drivers.join(",\n")
+    )
+}
+
+pub fn format_report(format: ProfileFormat, sections: &[Section], drivers: &[DriverInfo]) -> String {
+    match format {
+        ProfileFormat::Text => format_text(sections, drivers),
+        ProfileFormat::Json => format_json(sections, drivers),
+    }
+}
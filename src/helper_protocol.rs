@@ -0,0 +1,219 @@
+// src/helper_protocol.rs
+
+use glib::prelude::*;
+use std::path::Path;
+
+/// Well-known name of the host-side helper on the system bus
+pub const BUS_NAME: &str = "io.github.mthakan.MeaUpdater.Helper";
+pub const OBJECT_PATH: &str = "/io/github/mthakan/MeaUpdater/Helper";
+pub const INTERFACE: &str = "io.github.mthakan.MeaUpdater.Helper";
+
+/// Exit code returned when polkit denied the request
+pub const EXIT_NOT_AUTHORIZED: i32 = 126;
+/// Exit code returned when the helper could not be reached or refused the command
+pub const EXIT_UNAVAILABLE: i32 = 127;
+
+/// polkit action for installing and upgrading packages from the configured
+/// repositories; the password is kept for a few minutes
+pub const ACTION_INSTALL: &str = "io.github.mthakan.MeaUpdater.install";
+/// polkit action for removals, downgrades, list refreshes and sources
+/// changes, which always ask for the password
+pub const ACTION_MANAGE: &str = "io.github.mthakan.MeaUpdater.manage";
+
+/// Only files apt reads its repositories from may be written
+const SOURCES_LIST: &str = "/etc/apt/sources.list";
+const SOURCES_DIR: &str = "/etc/apt/sources.list.d/";
+
+/// Something the host helper does as root. The helper builds the command
+/// line itself, so callers cannot pass options to apt or dpkg.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// `apt-get update`
+    Update,
+    /// `apt-get install`, limited to installed packages with `only_upgrade`
+    Install { packages: Vec<String>, only_upgrade: bool },
+    /// `apt-get install --allow-downgrades` of exact `pkg=version` requests
+    Downgrade { packages: Vec<String> },
+    /// `apt-get dist-upgrade`
+    FullUpgrade,
+    /// `apt-get remove`, or `purge`
+    Remove { packages: Vec<String>, purge: bool },
+    /// `apt-get autoremove`
+    Autoremove,
+    /// Replaces `/etc/apt/sources.list` or a file in `sources.list.d`
+    WriteSources { path: String, content: String },
+}
+
+/// A package argument as apt-get takes it: a name with an optional
+/// architecture, then an optional `=version` or `/release`. A trailing `+`
+/// keeps the package installed during a removal.
+pub fn is_package_arg(arg: &str) -> bool {
+    let arg = arg.strip_suffix('+').unwrap_or(arg);
+    let (name, selector) = match arg.find(['=', '/']) {
+        Some(index) => (&arg[..index], Some(&arg[index + 1..])),
+        None => (arg, None),
+    };
+    let (package, arch) = name.split_once(':').unwrap_or((name, "amd64"));
+    let selector_ok = selector.is_none_or(|selector| {
+        selector.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && selector.chars().all(|c| c.is_ascii_alphanumeric() || ".+-~:".contains(c))
+    });
+    package.len() >= 2
+        && package.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && package.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.".contains(c))
+        && !arch.is_empty()
+        && arch.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && selector_ok
+}
+
+/// Whether `path` is a sources file the helper may replace
+pub fn is_sources_path(path: &str) -> bool {
+    if path == SOURCES_LIST {
+        return true;
+    }
+    let name = match path.strip_prefix(SOURCES_DIR) {
+        Some(name) => name,
+        None => return false,
+    };
+    let stem = name.strip_suffix(".list").or_else(|| name.strip_suffix(".sources"));
+    stem.is_some_and(|stem| {
+        !stem.is_empty()
+            && stem.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && stem.chars().all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c))
+    })
+}
+
+impl Operation {
+    /// Reads one of the apt-get command lines MeaUpdater builds, such as
+    /// `apt-get install -y -o APT::Status-Fd=1 firefox-esr`. Any other
+    /// program or option has no operation and is not sent to the helper.
+    pub fn from_argv(argv: &[String]) -> Option<Operation> {
+        let program = argv.first()?;
+        if !matches!(program.as_str(), "apt-get" | "/usr/bin/apt-get") {
+            return None;
+        }
+        let verb = argv.get(1)?.as_str();
+
+        let mut packages = Vec::new();
+        let mut only_upgrade = false;
+        let mut allow_downgrades = false;
+        let mut args = argv[2..].iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-y" => {}
+                "-o" if args.next().map(String::as_str) == Some("APT::Status-Fd=1") => {}
+                "--only-upgrade" if verb == "install" => only_upgrade = true,
+                "--allow-downgrades" if verb == "install" => allow_downgrades = true,
+                arg if arg.starts_with('-') => return None,
+                arg => packages.push(arg.to_string()),
+            }
+        }
+
+        match verb {
+            "update" if packages.is_empty() => Some(Operation::Update),
+            "install" if allow_downgrades => (!only_upgrade).then_some(Operation::Downgrade { packages }),
+            "install" => Some(Operation::Install { packages, only_upgrade }),
+            "dist-upgrade" if packages.is_empty() => Some(Operation::FullUpgrade),
+            "remove" | "purge" => Some(Operation::Remove { packages, purge: verb == "purge" }),
+            "autoremove" if packages.is_empty() => Some(Operation::Autoremove),
+            _ => None,
+        }
+    }
+
+    /// Checks the arguments again on the helper's side of the bus
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Operation::Downgrade { packages } if packages.iter().any(|package| !package.contains('=')) => {
+                Err("A downgrade needs an exact version for every package".to_string())
+            }
+            Operation::Install { packages, .. } | Operation::Downgrade { packages } | Operation::Remove { packages, .. } => {
+                if packages.is_empty() {
+                    return Err("No packages given".to_string());
+                }
+                match packages.iter().find(|package| !is_package_arg(package)) {
+                    Some(package) => Err(format!("Not a package name: {}", package)),
+                    None => Ok(()),
+                }
+            }
+            Operation::WriteSources { path, content } => {
+                if !is_sources_path(path) {
+                    Err(format!("Not an apt sources file: {}", path))
+                } else if content.contains('\0') {
+                    Err("The sources file contains binary data".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+            Operation::Update | Operation::FullUpgrade | Operation::Autoremove => Ok(()),
+        }
+    }
+
+    /// D-Bus method name and parameters for the call
+    pub fn to_call(&self) -> (&'static str, glib::Variant) {
+        match self {
+            Operation::Update => ("Update", ().to_variant()),
+            Operation::Install { packages, only_upgrade } => ("Install", (packages.clone(), *only_upgrade).to_variant()),
+            Operation::Downgrade { packages } => ("Downgrade", (packages.clone(),).to_variant()),
+            Operation::FullUpgrade => ("FullUpgrade", ().to_variant()),
+            Operation::Remove { packages, purge } => ("Remove", (packages.clone(), *purge).to_variant()),
+            Operation::Autoremove => ("Autoremove", ().to_variant()),
+            Operation::WriteSources { path, content } => ("WriteSources", (path.clone(), content.clone()).to_variant()),
+        }
+    }
+}
+
+/// The helper's side of the protocol; the GUI only sends operations
+#[allow(dead_code)]
+impl Operation {
+    /// The polkit action that must allow the caller
+    pub fn polkit_action(&self) -> &'static str {
+        match self {
+            Operation::Install { .. } | Operation::FullUpgrade => ACTION_INSTALL,
+            _ => ACTION_MANAGE,
+        }
+    }
+
+    /// The command the helper runs, or `None` for writing a file
+    pub fn argv(&self) -> Option<Vec<String>> {
+        let (verb, packages, option) = match self {
+            Operation::Update => return Some(vec!["apt-get".to_string(), "update".to_string()]),
+            Operation::Install { packages, only_upgrade } => ("install", packages.as_slice(), only_upgrade.then_some("--only-upgrade")),
+            Operation::Downgrade { packages } => ("install", packages.as_slice(), Some("--allow-downgrades")),
+            Operation::FullUpgrade => ("dist-upgrade", &[][..], None),
+            Operation::Remove { packages, purge } => (if *purge { "purge" } else { "remove" }, packages.as_slice(), None),
+            Operation::Autoremove => ("autoremove", &[][..], None),
+            Operation::WriteSources { .. } => return None,
+        };
+        let mut argv: Vec<String> = ["apt-get", verb, "-y", "-o", "APT::Status-Fd=1"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        argv.extend(option.map(str::to_string));
+        argv.push("--".to_string());
+        argv.extend(packages.iter().cloned());
+        Some(argv)
+    }
+
+    /// Reads a call made with `to_call`; `None` for unknown methods or
+    /// parameters of the wrong type
+    pub fn from_call(method: &str, params: &glib::Variant) -> Option<Operation> {
+        match method {
+            "Update" => Some(Operation::Update),
+            "Install" => params.get::<(Vec<String>, bool)>()
+                .map(|(packages, only_upgrade)| Operation::Install { packages, only_upgrade }),
+            "Downgrade" => params.get::<(Vec<String>,)>().map(|(packages,)| Operation::Downgrade { packages }),
+            "FullUpgrade" => Some(Operation::FullUpgrade),
+            "Remove" => params.get::<(Vec<String>, bool)>()
+                .map(|(packages, purge)| Operation::Remove { packages, purge }),
+            "Autoremove" => Some(Operation::Autoremove),
+            "WriteSources" => params.get::<(String, String)>()
+                .map(|(path, content)| Operation::WriteSources { path, content }),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the GUI itself is running inside a Flatpak sandbox
+pub fn running_in_flatpak() -> bool {
+    Path::new("/.flatpak-info").exists()
+}
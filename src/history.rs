@@ -0,0 +1,298 @@
+// src/history.rs
+
+use crate::apt::{self, PackageRequest, TransactionPlan};
+use crate::journal::OperationKind;
+use crate::paths;
+use crate::util;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeAction {
+    Upgraded,
+    /// Replaced by an older version
+    Downgraded,
+    Installed,
+    Removed,
+}
+
+impl ChangeAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeAction::Upgraded => "upgraded",
+            ChangeAction::Downgraded => "downgraded",
+            ChangeAction::Installed => "installed",
+            ChangeAction::Removed => "removed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "upgraded" => Some(ChangeAction::Upgraded),
+            "downgraded" => Some(ChangeAction::Downgraded),
+            "installed" => Some(ChangeAction::Installed),
+            "removed" => Some(ChangeAction::Removed),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChangeAction::Upgraded => "⬆️ Upgraded",
+            ChangeAction::Downgraded => "⬇️ Downgraded",
+            ChangeAction::Installed => "📦 Newly installed",
+            ChangeAction::Removed => "🗑️ Removed",
+        }
+    }
+}
+
+/// One package changed by a recorded operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageChange {
+    pub name: String,
+    pub action: ChangeAction,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// A finished package, kernel or driver operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub kind: OperationKind,
+    pub succeeded: bool,
+    pub changes: Vec<PackageChange>,
+    /// What else the operation did, such as the GRUB entry it chose or
+    /// the backup it saved
+    pub notes: Vec<String>,
+}
+
+impl HistoryEntry {
+    pub fn from_plan(kind: OperationKind, plan: &TransactionPlan, succeeded: bool, timestamp: u64) -> HistoryEntry {
+        let groups = [
+            (ChangeAction::Upgraded, &plan.upgrade),
+            (ChangeAction::Installed, &plan.install),
+            (ChangeAction::Removed, &plan.remove),
+        ];
+        let changes = groups.iter()
+            .flat_map(|(action, names)| names.iter().map(move |name| {
+                let versions = plan.versions.get(name).cloned().unwrap_or_default();
+                // apt lists a downgrade like an upgrade
+                let action = match (&versions.from, &versions.to) {
+                    (Some(from), Some(to)) if *action == ChangeAction::Upgraded && util::compare_versions(to, from).is_lt() => ChangeAction::Downgraded,
+                    _ => *action,
+                };
+                PackageChange { name: name.clone(), action, from: versions.from, to: versions.to }
+            }))
+            .collect();
+        HistoryEntry { timestamp, kind, succeeded, changes, notes: Vec::new() }
+    }
+
+    pub fn changes_of(&self, action: ChangeAction) -> impl Iterator<Item = &PackageChange> {
+        self.changes.iter().filter(move |change| change.action == action)
+    }
+
+    pub fn summary(&self) -> String {
+        // Changing the default kernel touches no package
+        if let (true, Some(note)) = (self.changes.is_empty(), self.notes.first()) {
+            return note.clone();
+        }
+        let summary = apt::change_summary(
+            self.changes_of(ChangeAction::Upgraded).count(),
+            self.changes_of(ChangeAction::Installed).count(),
+            self.changes_of(ChangeAction::Removed).count(),
+        );
+        match self.changes_of(ChangeAction::Downgraded).count() {
+            0 => summary,
+            downgraded if summary == "no changes" => format!("{} downgraded", downgraded),
+            downgraded => format!("{}, {} downgraded", summary, downgraded),
+        }
+    }
+}
+
+/// The request that puts `change` back to the version it replaced, for
+/// upgrades with a known previous version
+pub fn downgrade_request(change: &PackageChange) -> Option<PackageRequest> {
+    match (change.action, &change.from) {
+        (ChangeAction::Upgraded, Some(from)) => Some(PackageRequest::version(&change.name, from)),
+        _ => None,
+    }
+}
+
+/// The GRUB settings a successful default kernel change or restore
+/// replaced, which can be put back
+pub fn grub_backup(entry: &HistoryEntry) -> Option<&str> {
+    if entry.kind != OperationKind::GrubUpdate || !entry.succeeded {
+        return None;
+    }
+    entry.notes.iter().find_map(|note| note.strip_prefix("Backup: "))
+}
+
+/// Whether the entry at `timestamp` holds the latest successful change of
+/// `package` in `entries` (newest first). Only that one can be undone in
+/// a single step.
+pub fn is_latest_change(entries: &[HistoryEntry], timestamp: u64, package: &str) -> bool {
+    entries.iter()
+        .filter(|entry| entry.succeeded)
+        .find(|entry| entry.changes.iter().any(|change| change.name == package))
+        .map_or(false, |entry| entry.timestamp == timestamp)
+}
+
+fn history_file() -> Option<PathBuf> {
+    paths::state_file("history")
+}
+
+/// Repository each package was last installed from, one `name<TAB>origin` per line
+fn origins_file() -> Option<PathBuf> {
+    Some(history_file()?.with_file_name("origins"))
+}
+
+pub fn parse_origins(content: &str) -> HashMap<String, String> {
+    content.lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, origin)| (name.to_string(), origin.to_string()))
+        .collect()
+}
+
+pub fn format_origins(origins: &HashMap<String, String>) -> String {
+    let mut lines: Vec<String> = origins.iter().map(|(name, origin)| format!("{}\t{}\n", name, origin)).collect();
+    lines.sort();
+    lines.concat()
+}
+
+/// Where each package came from the last time MeaUpdater installed it
+pub fn known_origins() -> HashMap<String, String> {
+    origins_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| parse_origins(&content))
+        .unwrap_or_default()
+}
+
+/// Remembers the origin of the packages a successful operation installed
+fn record_origins(plan: &TransactionPlan) {
+    let path = match origins_file() {
+        Some(path) => path,
+        None => return,
+    };
+    let names: Vec<String> = plan.upgrade.iter().chain(&plan.install).cloned().collect();
+    let mut origins = known_origins();
+    for (name, candidate) in apt::get_policy_candidates(&names) {
+        if let Some(origin) = candidate.origin {
+            origins.insert(name, origin);
+        }
+    }
+    for name in &plan.remove {
+        origins.remove(name);
+    }
+    if let Err(e) = fs::write(&path, format_origins(&origins)) {
+        eprintln!("Could not save package origins: {}", e);
+    }
+}
+
+/// One line per entry: `timestamp<TAB>kind<TAB>ok|failed<TAB>changes`, the
+/// changes being comma separated `action name from to` with `-` for no
+/// version, followed by one field per note
+pub fn format_entry(entry: &HistoryEntry) -> String {
+    let changes: Vec<String> = entry.changes.iter()
+        .map(|change| format!(
+            "{} {} {} {}",
+            change.action.as_str(),
+            change.name,
+            change.from.as_deref().unwrap_or("-"),
+            change.to.as_deref().unwrap_or("-")
+        ))
+        .collect();
+    let mut line = format!(
+        "{}\t{}\t{}\t{}",
+        entry.timestamp,
+        entry.kind.as_str(),
+        if entry.succeeded { "ok" } else { "failed" },
+        changes.join(",")
+    );
+    for note in &entry.notes {
+        line.push('\t');
+        line.push_str(&note.replace(['\t', '\n'], " "));
+    }
+    line
+}
+
+pub fn parse_entry(line: &str) -> Option<HistoryEntry> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() < 4 {
+        return None;
+    }
+    let version = |value: &str| Some(value.to_string()).filter(|value| value != "-");
+    let changes = fields[3].split(',')
+        .filter(|change| !change.is_empty())
+        .filter_map(|change| {
+            let parts: Vec<&str> = change.split(' ').collect();
+            match parts.as_slice() {
+                [action, name, from, to] => Some(PackageChange {
+                    name: name.to_string(),
+                    action: ChangeAction::parse(action)?,
+                    from: version(from),
+                    to: version(to),
+                }),
+                _ => None,
+            }
+        })
+        .collect();
+
+    Some(HistoryEntry {
+        timestamp: fields[0].parse().ok()?,
+        kind: OperationKind::parse(fields[1])?,
+        succeeded: fields[2] == "ok",
+        changes,
+        notes: fields[4..].iter().map(|note| note.to_string()).collect(),
+    })
+}
+
+/// Appends the outcome of an operation planned as `plan`
+pub fn record(kind: OperationKind, plan: &TransactionPlan, notes: &[String], succeeded: bool) {
+    let mut entry = HistoryEntry::from_plan(kind, plan, succeeded, timestamp_now());
+    entry.notes = notes.to_vec();
+    append(&entry);
+    if succeeded {
+        record_origins(plan);
+    }
+}
+
+/// Appends an operation that changed no package, such as a new default kernel
+pub fn record_event(kind: OperationKind, notes: &[String], succeeded: bool) {
+    append(&HistoryEntry { timestamp: timestamp_now(), kind, succeeded, changes: Vec::new(), notes: notes.to_vec() });
+}
+
+fn timestamp_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn append(entry: &HistoryEntry) {
+    let path = match history_file() {
+        Some(path) => path,
+        None => return,
+    };
+    if let Some(parent) = path.parent() {
+        let _ = paths::ensure_dir(parent);
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", format_entry(entry)));
+    if let Err(e) = result {
+        eprintln!("Could not save the update history: {}", e);
+    }
+}
+
+/// All recorded operations, newest first
+pub fn load() -> Vec<HistoryEntry> {
+    let content = history_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    let mut entries: Vec<HistoryEntry> = content.lines().filter_map(parse_entry).collect();
+    entries.reverse();
+    entries
+}
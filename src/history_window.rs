@@ -0,0 +1,266 @@
+// src/history_window.rs
+
+use crate::apt::{PackageRequest, Transaction};
+use crate::app_state::{BusyState, Operation};
+use crate::error;
+use crate::format;
+use crate::history::{self, ChangeAction, HistoryEntry};
+use crate::journal::OperationKind;
+use crate::kernel_manager;
+use crate::privilege;
+use crate::progress::{self, ProgressWindow};
+use gtk::prelude::*;
+use gtk::{
+    gio, glib, ApplicationWindow, Box as GtkBox, Button, ButtonsType, Dialog, Expander, HeaderBar, Label, ListBox, ListBoxRow,
+    MessageDialog, MessageType, Orientation, ScrolledWindow,
+};
+use std::rc::Rc;
+
+/// Lists past package, kernel and driver operations with what each one
+/// upgraded, newly installed and removed
+pub struct HistoryWindow {
+    window: Dialog,
+}
+
+/// Runs a downgrade offered next to an upgrade
+type DowngradeAction = Rc<dyn Fn(PackageRequest)>;
+
+/// Puts back the GRUB settings saved before a default kernel change
+type RestoreAction = Rc<dyn Fn(String)>;
+
+impl HistoryWindow {
+    /// With `busy`, the latest upgrade of each package can be undone and
+    /// GRUB settings restored; `on_changed` runs after a successful downgrade
+    pub fn new(parent: &ApplicationWindow, busy: Option<Rc<BusyState>>, on_changed: Rc<dyn Fn()>) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Update History")
+            .default_width(760)
+            .default_height(560)
+            .build();
+
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some("🕘 Update History"))));
+        window.set_titlebar(Some(&header_bar));
+
+        let listbox = ListBox::new();
+        listbox.set_selection_mode(gtk::SelectionMode::None);
+
+        let entries = history::load();
+        // The host helper cannot write the GRUB settings
+        let restore: Option<RestoreAction> = busy.clone().filter(|_| privilege::can_run_commands()).map(|busy| {
+            let window = window.clone();
+            Rc::new(move |backup: String| Self::restore_grub(&window, busy.clone(), backup)) as RestoreAction
+        });
+        let downgrade: Option<DowngradeAction> = busy.map(|busy| {
+            let parent = parent.clone();
+            let window = window.clone();
+            Rc::new(move |request: PackageRequest| {
+                Self::downgrade(&parent, &window, busy.clone(), on_changed.clone(), request);
+            }) as DowngradeAction
+        });
+        if entries.is_empty() {
+            let empty_label = Label::new(Some("📂 No operations recorded yet"));
+            empty_label.set_margin_top(20);
+            empty_label.set_margin_bottom(20);
+            listbox.append(&empty_label);
+        }
+        for entry in &entries {
+            listbox.append(&Self::entry_row(entry, &entries, downgrade.as_ref(), restore.as_ref()));
+        }
+
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .margin_top(16)
+            .margin_bottom(16)
+            .margin_start(16)
+            .margin_end(16)
+            .child(&listbox)
+            .build();
+        window.content_area().append(&scrolled_window);
+
+        Self { window }
+    }
+
+    fn entry_row(entry: &HistoryEntry, entries: &[HistoryEntry], downgrade: Option<&DowngradeAction>, restore: Option<&RestoreAction>) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        row.add_css_class("package-row");
+
+        let outcome = if entry.succeeded { "✅" } else { "❌" };
+        let expander = Expander::new(Some(&format!(
+            "{} {}  {} — {}",
+            outcome,
+            format::time_ago(entry.timestamp),
+            entry.kind.description(),
+            entry.summary()
+        )));
+        let when = glib::DateTime::from_unix_local(entry.timestamp as i64)
+            .and_then(|dt| dt.format("%x %H:%M"))
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        expander.set_tooltip_text(Some(&when));
+        expander.set_margin_top(6);
+        expander.set_margin_bottom(6);
+        expander.set_margin_start(8);
+        expander.set_margin_end(8);
+
+        let details = GtkBox::new(Orientation::Vertical, 6);
+        details.set_margin_start(24);
+        details.set_margin_top(6);
+        for action in [ChangeAction::Upgraded, ChangeAction::Downgraded, ChangeAction::Installed, ChangeAction::Removed] {
+            let lines: Vec<String> = entry.changes_of(action)
+                .map(|change| match (&change.from, &change.to) {
+                    (Some(from), Some(to)) => format!("{}  {} → {}", change.name, from, to),
+                    (None, Some(version)) | (Some(version), None) => format!("{}  {}", change.name, version),
+                    (None, None) => change.name.clone(),
+                })
+                .collect();
+            if lines.is_empty() {
+                continue;
+            }
+            let label = Label::new(Some(&format!("{} ({})\n{}", action.label(), lines.len(), lines.join("\n"))));
+            label.set_halign(gtk::Align::Start);
+            label.set_selectable(true);
+            label.add_css_class("version-info");
+            details.append(&label);
+        }
+
+        if !entry.notes.is_empty() {
+            let notes = Label::new(Some(&entry.notes.join("\n")));
+            notes.set_halign(gtk::Align::Start);
+            notes.set_selectable(true);
+            notes.set_wrap(true);
+            notes.add_css_class("size-info");
+            details.append(&notes);
+        }
+        if let (Some(restore), Some(backup)) = (restore, history::grub_backup(entry)) {
+            let button = Button::with_label("↩️ Restore previous GRUB configuration");
+            button.add_css_class("flat");
+            button.set_halign(gtk::Align::Start);
+            button.set_tooltip_text(Some(backup));
+            let restore = restore.clone();
+            let backup = backup.to_string();
+            button.connect_clicked(move |_| restore(backup.clone()));
+            details.append(&button);
+        }
+
+        // Undoing an older upgrade would skip the versions installed since
+        let revertible: Vec<PackageRequest> = match downgrade {
+            Some(_) if entry.succeeded => entry.changes_of(ChangeAction::Upgraded)
+                .filter(|change| history::is_latest_change(entries, entry.timestamp, &change.name))
+                .filter_map(history::downgrade_request)
+                .collect(),
+            _ => Vec::new(),
+        };
+        if let (Some(downgrade), false) = (downgrade, revertible.is_empty()) {
+            let buttons = GtkBox::new(Orientation::Vertical, 4);
+            buttons.set_halign(gtk::Align::Start);
+            for request in revertible {
+                let button = Button::with_label(&format!("↩️ Downgrade {}", request.to_arg().replacen('=', " to ", 1)));
+                button.add_css_class("flat");
+                button.set_tooltip_text(Some("Install the version this update replaced again"));
+                let downgrade = downgrade.clone();
+                button.connect_clicked(move |_| downgrade(request.clone()));
+                buttons.append(&button);
+            }
+            details.append(&buttons);
+        }
+        if !entry.succeeded {
+            let failed = Label::new(Some("The operation failed, so some of these changes may not have been made."));
+            failed.set_halign(gtk::Align::Start);
+            failed.add_css_class("size-info");
+            details.append(&failed);
+        }
+        expander.set_child(Some(&details));
+
+        row.set_child(Some(&expander));
+        row
+    }
+
+    /// Confirms and runs a downgrade in a progress window
+    fn downgrade(parent: &ApplicationWindow, window: &Dialog, busy: Rc<BusyState>, on_changed: Rc<dyn Fn()>, request: PackageRequest) {
+        let transaction = Transaction::install_requests(&[request.clone()]).journal(OperationKind::Downgrade);
+        let parent = parent.clone();
+        let history_window = window.clone();
+        progress::confirm_transaction(window, transaction, move |transaction| {
+            if !busy.try_begin(Operation::Installing) {
+                return;
+            }
+            // Reopening the history shows the downgrade
+            history_window.close();
+
+            let progress_window = ProgressWindow::new(&parent);
+            progress_window.show();
+
+            let busy = busy.clone();
+            let on_changed = on_changed.clone();
+            let message = format!("✅ {} was downgraded", request.name);
+            glib::spawn_future_local(async move {
+                let result = progress_window.run_transaction(transaction, &message).await;
+                busy.finish();
+                if result.is_ok() {
+                    on_changed();
+                }
+            });
+        });
+    }
+
+    /// Confirms, then puts back `backup` and regenerates grub.cfg
+    fn restore_grub(window: &Dialog, busy: Rc<BusyState>, backup: String) {
+        let confirmation = MessageDialog::builder()
+            .transient_for(window)
+            .modal(true)
+            .message_type(MessageType::Question)
+            .buttons(ButtonsType::YesNo)
+            .text("↩️ Restore previous GRUB configuration")
+            .secondary_text(&format!(
+                "The GRUB settings saved in {} will replace the current ones and the boot menu will be regenerated.\n\n\
+                The current settings are backed up first.",
+                backup
+            ))
+            .build();
+        let window = window.clone();
+        confirmation.connect_response(move |dialog, response| {
+            dialog.close();
+            if response != gtk::ResponseType::Yes || !busy.try_begin(Operation::Installing) {
+                return;
+            }
+            let window = window.clone();
+            let busy = busy.clone();
+            let backup = backup.clone();
+            glib::spawn_future_local(async move {
+                let restored = backup.clone();
+                let result = gio::spawn_blocking(move || kernel_manager::restore_grub_backup(&restored)).await;
+                busy.finish();
+                match result {
+                    Ok(Ok(())) => {
+                        let done = MessageDialog::builder()
+                            .transient_for(&window)
+                            .modal(true)
+                            .message_type(MessageType::Info)
+                            .buttons(ButtonsType::Ok)
+                            .text("✅ GRUB configuration restored")
+                            .secondary_text("The previous boot settings take effect on the next restart.")
+                            .build();
+                        // Reopening the history shows the restore
+                        let history_window = window.clone();
+                        done.connect_response(move |dlg, _| {
+                            dlg.close();
+                            history_window.close();
+                        });
+                        done.show();
+                    }
+                    Ok(Err(e)) => error::show_error_dialog(&window, "❌ GRUB configuration could not be restored", &e, None),
+                    Err(_) => eprintln!("The GRUB restore task panicked"),
+                }
+            });
+        });
+        confirmation.show();
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
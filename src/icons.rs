@@ -0,0 +1,174 @@
+// src/icons.rs
+
+#[cfg(feature = "gui")]
+use gtk::prelude::*;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Forces the icon style: "emoji" or "symbolic"
+pub const ICON_STYLE_ENV: &str = "MEAUPDATER_ICONS";
+
+/// Codepoint looked up to find a color emoji font (📦)
+const EMOJI_PROBE_CHARSET: &str = ":charset=1f4e6";
+
+/// States and categories shown as an icon. Each has the emoji the views
+/// always used and a themed symbolic icon for systems without an emoji font.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    Security,
+    Software,
+    Kernel,
+    Firmware,
+    Flatpak,
+    Snap,
+    Active,
+    Installed,
+    Available,
+    FreeLicense,
+    NonFreeLicense,
+    UnknownLicense,
+    Graphics,
+    Network,
+    Audio,
+    Bluetooth,
+    Chipset,
+    Storage,
+    Input,
+    Device,
+}
+
+impl Icon {
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Icon::Security => "🔒",
+            Icon::Software => "📦",
+            Icon::Kernel => "⚡",
+            Icon::Firmware => "🔌",
+            Icon::Flatpak => "🧩",
+            Icon::Snap => "🔸",
+            Icon::Active => "🟢",
+            Icon::Installed => "🔵",
+            Icon::Available => "⚪",
+            Icon::FreeLicense => "🆓",
+            Icon::NonFreeLicense => "💰",
+            Icon::UnknownLicense => "❓",
+            Icon::Graphics => "🎮",
+            Icon::Network => "🌐",
+            Icon::Audio => "🔊",
+            Icon::Bluetooth => "📡",
+            Icon::Chipset => "🔧",
+            Icon::Storage => "💾",
+            Icon::Input => "⌨️",
+            Icon::Device => "🔌",
+        }
+    }
+
+    /// Symbolic icon from the icon theme
+    pub fn icon_name(self) -> &'static str {
+        match self {
+            Icon::Security => "security-high-symbolic",
+            Icon::Software => "package-x-generic-symbolic",
+            Icon::Kernel => "system-run-symbolic",
+            Icon::Firmware => "media-flash-symbolic",
+            Icon::Flatpak => "application-x-addon-symbolic",
+            Icon::Snap => "application-x-executable-symbolic",
+            Icon::Active => "emblem-ok-symbolic",
+            Icon::Installed => "object-select-symbolic",
+            Icon::Available => "folder-download-symbolic",
+            Icon::FreeLicense => "emblem-shared-symbolic",
+            Icon::NonFreeLicense => "channel-secure-symbolic",
+            Icon::UnknownLicense => "dialog-question-symbolic",
+            Icon::Graphics => "video-display-symbolic",
+            Icon::Network => "network-wired-symbolic",
+            Icon::Audio => "audio-card-symbolic",
+            Icon::Bluetooth => "bluetooth-active-symbolic",
+            Icon::Chipset => "preferences-system-symbolic",
+            Icon::Storage => "drive-harddisk-symbolic",
+            Icon::Input => "input-keyboard-symbolic",
+            Icon::Device => "preferences-other-symbolic",
+        }
+    }
+
+    /// Tooltip and accessible name
+    pub fn description(self) -> &'static str {
+        match self {
+            Icon::Security => "Security update",
+            Icon::Software => "Software update",
+            Icon::Kernel => "Kernel update",
+            Icon::Firmware => "Firmware update",
+            Icon::Flatpak => "Flatpak update",
+            Icon::Snap => "Snap update",
+            Icon::Active => "Active",
+            Icon::Installed => "Installed",
+            Icon::Available => "Available",
+            Icon::FreeLicense => "Free software",
+            Icon::NonFreeLicense => "Closed source",
+            Icon::UnknownLicense => "Unknown license",
+            Icon::Graphics => "Graphics card",
+            Icon::Network => "Network card",
+            Icon::Audio => "Audio card",
+            Icon::Bluetooth => "Bluetooth",
+            Icon::Chipset => "Chipset",
+            Icon::Storage => "Storage",
+            Icon::Input => "Input device",
+            Icon::Device => "Other device",
+        }
+    }
+}
+
+/// Reads `MEAUPDATER_ICONS`; `None` lets the font check decide
+pub fn style_override(value: Option<&str>) -> Option<bool> {
+    match value?.trim() {
+        "emoji" => Some(true),
+        "symbolic" => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether `fc-list :charset=1f4e6 family` found a font with emoji
+pub fn has_color_emoji(fc_list_output: &str) -> bool {
+    fc_list_output.lines().any(|family| !family.trim().is_empty())
+}
+
+/// Whether emoji render here. Checked once per run.
+pub fn emoji_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        if let Some(forced) = style_override(std::env::var(ICON_STYLE_ENV).ok().as_deref()) {
+            return forced;
+        }
+        // Without fontconfig there is nothing to go by, keep the emoji
+        Command::new("fc-list")
+            .args([EMOJI_PROBE_CHARSET, "family"])
+            .output()
+            .map_or(true, |output| has_color_emoji(&String::from_utf8_lossy(&output.stdout)))
+    })
+}
+
+/// The emoji, or the themed icon without an emoji font. Carries the
+/// description as tooltip and accessible label.
+#[cfg(feature = "gui")]
+pub fn widget(icon: Icon) -> gtk::Widget {
+    let widget: gtk::Widget = if emoji_available() {
+        gtk::Label::new(Some(icon.emoji())).upcast()
+    } else {
+        gtk::Image::from_icon_name(icon.icon_name()).upcast()
+    };
+    widget.set_tooltip_text(Some(icon.description()));
+    widget.update_property(&[gtk::accessible::Property::Label(icon.description())]);
+    widget
+}
+
+/// Like `widget`, as wide as `chars` characters so it lines up with a column header
+#[cfg(feature = "gui")]
+pub fn column_widget(icon: Icon, chars: i32) -> gtk::Widget {
+    let widget = widget(icon);
+    match widget.downcast_ref::<gtk::Label>() {
+        Some(label) => label.set_width_chars(chars),
+        None => {
+            let char_width = widget.pango_context().metrics(None, None).approximate_char_width();
+            widget.set_size_request(chars * char_width / pango::SCALE, -1);
+        }
+    }
+    widget
+}
@@ -0,0 +1,266 @@
+// src/install.rs
+
+use crate::error::{Context, Error, Result};
+#[cfg(feature = "gui")]
+use crate::paths;
+use gdk_pixbuf::{InterpType, Pixbuf};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Application id; also the name of the desktop file, icon and GSettings schema
+pub const APP_ID: &str = "io.github.mthakan.MeaUpdater";
+
+pub const INSTALL_ARG: &str = "--install";
+pub const UNINSTALL_ARG: &str = "--uninstall";
+
+const DEFAULT_PREFIX: &str = "/usr/local";
+const ICON_SIZES: &[i32] = &[48, 128, 256, 512];
+
+pub const LOGO_PNG: &[u8] = include_bytes!("../assets/logo.png");
+const DESKTOP_FILE: &str = include_str!("../data/io.github.mthakan.MeaUpdater.desktop");
+const GSCHEMA_FILE: &str = include_str!("../data/io.github.mthakan.MeaUpdater.gschema.xml");
+const HELPER_POLICY: &str = include_str!("../data/helper/io.github.mthakan.MeaUpdater.policy");
+const HELPER_DBUS_CONF: &str = include_str!("../data/helper/io.github.mthakan.MeaUpdater.Helper.conf");
+const HELPER_DBUS_SERVICE: &str = include_str!("../data/helper/io.github.mthakan.MeaUpdater.Helper.service");
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallSource {
+    Text(String),
+    Copy(PathBuf),
+    /// The embedded logo scaled to a square icon of this size
+    Icon(i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallEntry {
+    pub path: PathBuf,
+    pub mode: u32,
+    pub source: InstallSource,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallOptions {
+    pub prefix: PathBuf,
+    /// Staging root for packagers; post-install hooks are skipped when set
+    pub destdir: Option<PathBuf>,
+}
+
+/// Parses `--prefix DIR` and `--destdir DIR` following the install switch
+pub fn parse_options(args: &[String]) -> Result<InstallOptions> {
+    let mut options = InstallOptions { prefix: PathBuf::from(DEFAULT_PREFIX), destdir: None };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--prefix" => options.prefix = PathBuf::from(args.next().context("--prefix needs a directory")?),
+            "--destdir" => options.destdir = Some(PathBuf::from(args.next().context("--destdir needs a directory")?)),
+            other => return Err(Error::Other(format!("Unknown install option: {}", other))),
+        }
+    }
+
+    if !options.prefix.is_absolute() {
+        return Err(Error::Other("The prefix must be an absolute path".to_string()));
+    }
+    Ok(options)
+}
+
+/// Every file installed for `prefix`. The helper files are only included when
+/// a helper binary was built next to the application.
+pub fn install_plan(prefix: &Path, executable: &Path, helper: Option<&Path>) -> Vec<InstallEntry> {
+    let share = prefix.join("share");
+    let bindir = prefix.join("bin");
+    let libexecdir = prefix.join("libexec");
+
+    let mut plan = vec![
+        InstallEntry { path: bindir.join("meaupdater"), mode: 0o755, source: InstallSource::Copy(executable.to_path_buf()) },
+        InstallEntry {
+            path: share.join("applications").join(format!("{}.desktop", APP_ID)),
+            mode: 0o644,
+            source: InstallSource::Text(DESKTOP_FILE.replace("@BINDIR@", &bindir.to_string_lossy())),
+        },
+        InstallEntry {
+            path: share.join("glib-2.0/schemas").join(format!("{}.gschema.xml", APP_ID)),
+            mode: 0o644,
+            source: InstallSource::Text(GSCHEMA_FILE.to_string()),
+        },
+    ];
+
+    for size in ICON_SIZES {
+        plan.push(InstallEntry {
+            path: share.join(format!("icons/hicolor/{0}x{0}/apps", size)).join(format!("{}.png", APP_ID)),
+            mode: 0o644,
+            source: InstallSource::Icon(*size),
+        });
+    }
+
+    if let Some(helper) = helper {
+        let helper_path = libexecdir.join("meaupdater-helper");
+        plan.push(InstallEntry { path: helper_path.clone(), mode: 0o755, source: InstallSource::Copy(helper.to_path_buf()) });
+        // D-Bus and polkit only read these from /usr/share, whatever the prefix
+        plan.push(InstallEntry {
+            path: PathBuf::from("/usr/share/dbus-1/system-services/io.github.mthakan.MeaUpdater.Helper.service"),
+            mode: 0o644,
+            source: InstallSource::Text(HELPER_DBUS_SERVICE.replace("/usr/libexec/meaupdater-helper", &helper_path.to_string_lossy())),
+        });
+        plan.push(InstallEntry {
+            path: PathBuf::from("/usr/share/dbus-1/system.d/io.github.mthakan.MeaUpdater.Helper.conf"),
+            mode: 0o644,
+            source: InstallSource::Text(HELPER_DBUS_CONF.to_string()),
+        });
+        plan.push(InstallEntry {
+            path: PathBuf::from("/usr/share/polkit-1/actions/io.github.mthakan.MeaUpdater.policy"),
+            mode: 0o644,
+            source: InstallSource::Text(HELPER_POLICY.to_string()),
+        });
+    }
+
+    plan
+}
+
+/// Places an absolute path below the staging root, if any
+pub fn staged_path(destdir: Option<&Path>, path: &Path) -> PathBuf {
+    match destdir {
+        Some(root) => root.join(path.strip_prefix("/").unwrap_or(path)),
+        None => path.to_path_buf(),
+    }
+}
+
+fn icon_png(size: i32) -> Result<Vec<u8>> {
+    let pixbuf = Pixbuf::from_read(std::io::Cursor::new(LOGO_PNG)).context("Embedded logo could not be decoded")?;
+    let scaled = pixbuf
+        .scale_simple(size, size, InterpType::Bilinear)
+        .context("Logo could not be scaled")?;
+    Ok(scaled.save_to_bufferv("png", &[])?)
+}
+
+fn write_entry(entry: &InstallEntry, target: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Could not create {}", parent.display()))?;
+    }
+    match &entry.source {
+        InstallSource::Text(text) => fs::write(target, text).map(|_| ()),
+        InstallSource::Copy(source) => fs::copy(source, target).map(|_| ()),
+        InstallSource::Icon(size) => fs::write(target, icon_png(*size)?),
+    }
+    .with_context(|| format!("Could not write {}", target.display()))?;
+
+    fs::set_permissions(target, fs::Permissions::from_mode(entry.mode))?;
+    Ok(())
+}
+
+/// Whether `prefix` belongs to root; the nearest existing directory decides
+/// while the prefix is not created yet
+fn root_owned_prefix(prefix: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    prefix.ancestors()
+        .find_map(|dir| fs::metadata(dir).ok())
+        .is_some_and(|metadata| metadata.uid() == 0)
+}
+
+fn current_plan(options: &InstallOptions) -> Result<Vec<InstallEntry>> {
+    let executable = std::env::current_exe().context("Could not locate the MeaUpdater executable")?;
+    let helper = executable.with_file_name("meaupdater-helper");
+    let mut helper = helper.exists().then_some(helper.as_path());
+
+    // The helper's D-Bus and polkit files go to /usr/share whatever the
+    // prefix, which a per-user install must not touch
+    if helper.is_some() && options.destdir.is_none() && !root_owned_prefix(&options.prefix) {
+        println!("ℹ️ Skipping meaupdater-helper: D-Bus and polkit only read its files from /usr/share. Use a system prefix or --destdir to include it.");
+        helper = None;
+    }
+    Ok(install_plan(&options.prefix, &executable, helper))
+}
+
+/// Refreshes the caches that desktops read the installed files from
+fn run_post_install(prefix: &Path) {
+    let share = prefix.join("share");
+    let hooks: [(&str, Vec<PathBuf>); 3] = [
+        ("glib-compile-schemas", vec![share.join("glib-2.0/schemas")]),
+        ("gtk-update-icon-cache", vec![PathBuf::from("-qtf"), share.join("icons/hicolor")]),
+        ("update-desktop-database", vec![share.join("applications")]),
+    ];
+
+    for (program, args) in hooks {
+        match Command::new(program).args(&args).status() {
+            Ok(status) if status.success() => println!("✅ {}", program),
+            _ => println!("⚠️ {} could not be run, skipping", program),
+        }
+    }
+}
+
+pub fn install(options: &InstallOptions) -> Result<()> {
+    for entry in current_plan(options)? {
+        let target = staged_path(options.destdir.as_deref(), &entry.path);
+        write_entry(&entry, &target)?;
+        println!("📦 {}", target.display());
+    }
+
+    if options.destdir.is_none() {
+        run_post_install(&options.prefix);
+    }
+    Ok(())
+}
+
+pub fn uninstall(options: &InstallOptions) -> Result<()> {
+    for entry in current_plan(options)? {
+        let target = staged_path(options.destdir.as_deref(), &entry.path);
+        match fs::remove_file(&target) {
+            Ok(()) => println!("🗑️ {}", target.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(Error::from(e).with_context(&format!("Could not remove {}", target.display()))),
+        }
+    }
+
+    if options.destdir.is_none() {
+        run_post_install(&options.prefix);
+    }
+    Ok(())
+}
+
+/// Entry point for `meaupdater --install` / `--uninstall`
+pub fn run_cli(uninstalling: bool, args: &[String]) -> i32 {
+    let result = parse_options(args).and_then(|options| {
+        if uninstalling { uninstall(&options) } else { install(&options) }
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            if let Error::PermissionDenied(_) = e {
+                eprintln!("Run the command with sudo, or pass --prefix ~/.local");
+            }
+            1
+        }
+    }
+}
+
+/// The embedded logo as a notification icon, so no installed theme is needed
+pub fn app_icon() -> gio::BytesIcon {
+    gio::BytesIcon::new(&glib::Bytes::from_static(LOGO_PNG))
+}
+
+/// Makes the icon name resolvable when running from a build tree
+#[cfg(feature = "gui")]
+pub fn register_icon(display: &gtk::gdk::Display) {
+    let icons_dir = match paths::cache_dir() {
+        Some(dir) => dir.join("icons"),
+        None => return,
+    };
+    let icon_path = icons_dir.join("hicolor/256x256/apps").join(format!("{}.png", APP_ID));
+
+    if !icon_path.exists() {
+        let entry = InstallEntry { path: icon_path.clone(), mode: 0o644, source: InstallSource::Icon(256) };
+        if let Err(e) = write_entry(&entry, &icon_path) {
+            eprintln!("Could not prepare the application icon: {:#}", e);
+            return;
+        }
+    }
+
+    gtk::IconTheme::for_display(display).add_search_path(&icons_dir);
+    gtk::Window::set_default_icon_name(APP_ID);
+}
@@ -0,0 +1,47 @@
+// src/install_queue.rs
+
+use crate::paths;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+fn queue_file() -> Option<PathBuf> {
+    paths::state_file("install_queue")
+}
+
+/// Saves the packages selected for installation so they survive a restart
+pub fn save(packages: &[String]) {
+    let path = match queue_file() {
+        Some(path) => path,
+        None => return,
+    };
+    if let Some(parent) = path.parent() {
+        let _ = paths::ensure_dir(parent);
+    }
+    if let Err(e) = fs::write(&path, packages.join("\n")) {
+        eprintln!("Could not save install queue: {}", e);
+    }
+}
+
+pub fn load() -> Vec<String> {
+    queue_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| content.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// Forgets the saved selection, called once an install starts
+pub fn clear() {
+    if let Some(path) = queue_file() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Queued packages that still have an update available, in queue order
+pub fn resumable(queue: &[String], available: &[String]) -> Vec<String> {
+    let available: HashSet<&String> = available.iter().collect();
+    queue.iter()
+        .filter(|name| available.contains(name))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,84 @@
+// src/jobs.rs
+
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// A running operation whose progress window was sent to the background
+#[derive(Clone)]
+pub struct Job {
+    pub id: u64,
+    pub title: String,
+    /// Status line, as Pango markup
+    pub status: String,
+    pub fraction: f64,
+    window: gtk::Window,
+}
+
+thread_local! {
+    static JOBS: RefCell<Vec<Job>> = RefCell::new(Vec::new());
+    static OBSERVERS: RefCell<Vec<Rc<dyn Fn(&[Job])>>> = RefCell::new(Vec::new());
+    static NEXT_ID: Cell<u64> = Cell::new(1);
+}
+
+fn notify() {
+    let jobs = JOBS.with(|jobs| jobs.borrow().clone());
+    let observers = OBSERVERS.with(|observers| observers.borrow().clone());
+    for observer in observers {
+        observer(&jobs);
+    }
+}
+
+/// Calls `f` with the background jobs whenever they change
+pub fn connect_changed<F: Fn(&[Job]) + 'static>(f: F) {
+    OBSERVERS.with(|observers| observers.borrow_mut().push(Rc::new(f)));
+}
+
+/// Hides `window` and keeps its operation listed until it finishes or the
+/// user brings the window back
+pub fn detach(window: &gtk::Window, title: &str, status: &str, fraction: f64) -> u64 {
+    let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+    window.set_visible(false);
+    JOBS.with(|jobs| jobs.borrow_mut().push(Job {
+        id,
+        title: title.to_string(),
+        status: status.to_string(),
+        fraction,
+        window: window.clone(),
+    }));
+    notify();
+    id
+}
+
+pub fn update(id: u64, status: Option<&str>, fraction: Option<f64>) {
+    let changed = JOBS.with(|jobs| {
+        let mut jobs = jobs.borrow_mut();
+        let job = match jobs.iter_mut().find(|job| job.id == id) {
+            Some(job) => job,
+            None => return false,
+        };
+        if let Some(status) = status {
+            job.status = status.to_string();
+        }
+        if let Some(fraction) = fraction {
+            job.fraction = fraction;
+        }
+        true
+    });
+    if changed {
+        notify();
+    }
+}
+
+/// Shows the job's window again and drops it from the list
+pub fn reattach(id: u64) {
+    let job = JOBS.with(|jobs| {
+        let mut jobs = jobs.borrow_mut();
+        let index = jobs.iter().position(|job| job.id == id)?;
+        Some(jobs.remove(index))
+    });
+    if let Some(job) = job {
+        job.window.present();
+        notify();
+    }
+}
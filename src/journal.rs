@@ -0,0 +1,201 @@
+// src/journal.rs
+
+use crate::boot_platform::BootPlatform;
+use crate::paths;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Privileged operation that can leave the system half-configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    Install,
+    KernelInstall,
+    KernelRemove,
+    DriverInstall,
+    DriverRemove,
+    GrubUpdate,
+    Downgrade,
+}
+
+impl OperationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationKind::Install => "install",
+            OperationKind::KernelInstall => "kernel-install",
+            OperationKind::KernelRemove => "kernel-remove",
+            OperationKind::DriverInstall => "driver-install",
+            OperationKind::DriverRemove => "driver-remove",
+            OperationKind::GrubUpdate => "grub-update",
+            OperationKind::Downgrade => "downgrade",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "install" => Some(OperationKind::Install),
+            "kernel-install" => Some(OperationKind::KernelInstall),
+            "kernel-remove" => Some(OperationKind::KernelRemove),
+            "driver-install" => Some(OperationKind::DriverInstall),
+            "driver-remove" => Some(OperationKind::DriverRemove),
+            "grub-update" => Some(OperationKind::GrubUpdate),
+            "downgrade" => Some(OperationKind::Downgrade),
+            _ => None,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            OperationKind::Install => "Installing updates",
+            OperationKind::KernelInstall => "Installing a kernel",
+            OperationKind::KernelRemove => "Removing a kernel",
+            OperationKind::DriverInstall => "Installing a driver",
+            OperationKind::DriverRemove => "Removing a driver",
+            OperationKind::GrubUpdate => "Changing the default kernel",
+            OperationKind::Downgrade => "Downgrading a package",
+        }
+    }
+}
+
+/// Commands that bring the system back to a consistent state after all of
+/// `kinds`, each run once and in an order that suits every operation.
+/// Boards that do not boot through GRUB have no menu to regenerate.
+pub fn recovery_steps(kinds: &[OperationKind], platform: &BootPlatform) -> Vec<RecoveryStep> {
+    let mut steps = Vec::new();
+    let any = |f: fn(&OperationKind) -> bool| kinds.iter().any(f);
+
+    if any(|kind| *kind != OperationKind::GrubUpdate) {
+        steps.push(RecoveryStep::new("Finish configuring unpacked packages", &["dpkg", "--configure", "-a"]));
+        steps.push(RecoveryStep::new("Fix broken dependencies", &["apt-get", "install", "-f", "-y"]));
+    }
+    if any(|kind| matches!(kind, OperationKind::KernelRemove | OperationKind::DriverRemove)) {
+        steps.push(RecoveryStep::new("Remove leftover dependencies", &["apt-get", "autoremove", "-y"]));
+    }
+    if platform.uses_grub() && any(|kind| matches!(kind, OperationKind::KernelInstall | OperationKind::KernelRemove | OperationKind::GrubUpdate)) {
+        steps.push(RecoveryStep::new("Regenerate the GRUB menu", &["update-grub"]));
+    }
+
+    steps
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryStep {
+    pub description: &'static str,
+    pub argv: Vec<&'static str>,
+}
+
+impl RecoveryStep {
+    fn new(description: &'static str, argv: &[&'static str]) -> Self {
+        Self { description, argv: argv.to_vec() }
+    }
+}
+
+/// An operation that was running when the app last stopped
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub kind: OperationKind,
+    pub detail: String,
+    pub started: u64,
+}
+
+/// Removes the operation's journal entry when it ends, whatever the outcome.
+/// Only a crash or power loss leaves the entry behind.
+pub struct OperationGuard {
+    path: Option<PathBuf>,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// One file per running operation, so operations that overlap, such as a
+/// GRUB update during a kernel install, keep their own entries
+fn journal_dir() -> Option<PathBuf> {
+    paths::state_file("operation_journal.d")
+}
+
+/// Name of the next entry file, unique within this process and across runs
+fn entry_file_name(started: u64) -> String {
+    static NEXT: AtomicU32 = AtomicU32::new(0);
+    format!("{}-{}-{}", started, std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+pub fn format_entry(entry: &JournalEntry) -> String {
+    format!("kind={}\ndetail={}\nstarted={}\n", entry.kind.as_str(), entry.detail.replace('\n', " "), entry.started)
+}
+
+pub fn parse_entry(content: &str) -> Option<JournalEntry> {
+    let mut kind = None;
+    let mut detail = String::new();
+    let mut started = 0;
+
+    for line in content.lines() {
+        match line.split_once('=') {
+            Some(("kind", value)) => kind = OperationKind::parse(value),
+            Some(("detail", value)) => detail = value.to_string(),
+            Some(("started", value)) => started = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    Some(JournalEntry { kind: kind?, detail, started })
+}
+
+/// Records that `kind` is starting; keep the guard alive until it finishes
+pub fn begin(kind: OperationKind, detail: &str) -> OperationGuard {
+    let entry = JournalEntry {
+        kind,
+        detail: detail.to_string(),
+        started: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+    };
+
+    let path = journal_dir().map(|dir| dir.join(entry_file_name(entry.started)));
+    if let Some(path) = &path {
+        let _ = paths::ensure_parent(path);
+        if let Err(e) = fs::write(path, format_entry(&entry)) {
+            eprintln!("Could not write operation journal: {}", e);
+        }
+    }
+
+    OperationGuard { path }
+}
+
+/// Operations left unfinished by a previous run, oldest first, with the
+/// files that record them
+pub fn interrupted() -> Vec<(PathBuf, JournalEntry)> {
+    let entries = match journal_dir().and_then(|dir| fs::read_dir(dir).ok()) {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+    let mut interrupted: Vec<(PathBuf, JournalEntry)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let content = fs::read_to_string(entry.path()).ok()?;
+            Some((entry.path(), parse_entry(&content)?))
+        })
+        .collect();
+    interrupted.sort_by_key(|(_, entry)| entry.started);
+    interrupted
+}
+
+/// Forgets the entries `interrupted` returned
+pub fn dismiss(files: &[PathBuf]) {
+    for file in files {
+        let _ = fs::remove_file(file);
+    }
+}
+
+/// Whether dpkg reports half-installed or unconfigured packages
+pub fn dpkg_needs_repair() -> bool {
+    Command::new("dpkg")
+        .arg("--audit")
+        .output()
+        .map(|output| !output.stdout.is_empty())
+        .unwrap_or(false)
+}
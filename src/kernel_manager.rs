@@ -1,11 +1,24 @@
 // src/kernel_manager.rs
 
-use anyhow::{Context, Result, bail};
+use crate::error::{Context, Error, Result};
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::sync::Mutex;
+use crate::apt::Transaction;
+use crate::boot_layout;
+use crate::boot_platform;
+use crate::model::{PackageUpdate, UpdateType};
+use crate::privilege;
+use crate::sysroot;
+use crate::util;
+use crate::format;
+use crate::history;
+use crate::journal::{self, OperationKind};
+use crate::metadata;
+use crate::zfs;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum KernelType {
@@ -23,7 +36,8 @@ pub struct KernelInfo {
     pub is_current: bool,
     pub major_version: String, 
     pub package_name: String,
-    pub size: String,
+    /// Download size in bytes, if apt knows it
+    pub size: Option<u64>,
 }
 
 impl KernelInfo {
@@ -40,7 +54,7 @@ impl KernelInfo {
             is_current: false,
             major_version,
             package_name: package_name.to_string(),
-            size: "N/A".to_string(),
+            size: None,
         }
     }
     
@@ -108,6 +122,11 @@ pub fn update_kernel_check_time() {
     }
 }
 
+/// When the kernel list was last checked
+pub fn last_kernel_check() -> Option<u64> {
+    LAST_KERNEL_CHECK.lock().ok().and_then(|last_check| *last_check)
+}
+
 pub fn get_cached_kernels() -> Option<Vec<KernelInfo>> {
     if let Ok(cache_guard) = KERNEL_CACHE.lock() {
         cache_guard.clone()
@@ -129,7 +148,7 @@ pub fn get_current_kernel() -> Result<String> {
         .output()
         .context("Could not get current kernel version")?;
     if !output.status.success() {
-        bail!("uname command failed");
+        return Err(Error::from_status("uname", &output.status, &String::from_utf8_lossy(&output.stderr)));
     }
     
     let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -212,17 +231,8 @@ pub fn get_installed_kernels() -> Result<Vec<KernelInfo>> {
 
 
 pub fn get_available_kernels() -> Result<Vec<KernelInfo>> {
-    let output = Command::new("apt")
-        .args(&["search", "^linux-image-[0-9]"])
-        .env("LANG", "C")
-        .output()
+    let stdout = metadata::query("apt", &["search", "^linux-image-[0-9]"])
         .context("Could not get available kernel list")?;
-        
-    if !output.status.success() {
-        return Ok(Vec::new());
-    }
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
     let mut kernels = Vec::new();
     let installed_kernels = get_installed_kernels().unwrap_or_default();
     let current_kernel = get_current_kernel().unwrap_or_default();
@@ -336,7 +346,7 @@ pub fn group_kernels_by_major_version(kernels: Vec<KernelInfo>) -> HashMap<Strin
     for group in groups.values_mut() {
         group.sort_by(|a, b| {
 
-            version_compare(&b.version, &a.version) 
+            util::compare_versions(&b.version, &a.version) 
         });
     }
     
@@ -344,124 +354,27 @@ pub fn group_kernels_by_major_version(kernels: Vec<KernelInfo>) -> HashMap<Strin
 }
 
 
-fn version_compare(a: &str, b: &str) -> std::cmp::Ordering {
-    let a_parts: Vec<&str> = a.split(&['.', '-'][..]).collect();
-    let b_parts: Vec<&str> = b.split(&['.', '-'][..]).collect();
-    
-    let max_len = a_parts.len().max(b_parts.len());
-    
-    for i in 0..max_len {
-        let a_part = a_parts.get(i).unwrap_or(&"0");
-        let b_part = b_parts.get(i).unwrap_or(&"0");
-        
-
-        match (a_part.parse::<i32>(), b_part.parse::<i32>()) {
-            (Ok(a_num), Ok(b_num)) => {
-                match a_num.cmp(&b_num) {
-                    std::cmp::Ordering::Equal => continue,
-                    other => return other,
-                }
-            }
-            _ => {
-
-                match a_part.cmp(b_part) {
-                    std::cmp::Ordering::Equal => continue,
-                    other => return other,
-                }
-            }
-        }
-    }
-    
-    std::cmp::Ordering::Equal
+pub fn install_kernel(package_name: &str) -> Result<()> {
+    Transaction::install(&[package_name.to_string()])
+        .journal(OperationKind::KernelInstall)
+        .run()
+        .context("Kernel installation")
 }
 
 
-pub fn get_kernel_sizes(package_names: &[String]) -> HashMap<String, String> {
-    let mut sizes = HashMap::new();
-    
-    if package_names.is_empty() {
-        return sizes;
-    }
-    
-
-    let output = Command::new("apt")
-        .arg("show")
-        .args(package_names)
-        .env("LANG", "C")
-        .output();
-    
-    if let Ok(output) = output {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut current_package = String::new();
-        
-        for line in stdout.lines() {
-            let line = line.trim();
-            
-            if line.starts_with("Package:") {
-                if let Some(pkg_name) = line.split_whitespace().nth(1) {
-                    current_package = pkg_name.to_string();
-                }
-            } else if line.starts_with("Size:") && !current_package.is_empty() {
-                if let Some(size_str) = line.split_whitespace().nth(1) {
-                    if let Ok(size_bytes) = size_str.parse::<u64>() {
-                        sizes.insert(current_package.clone(), format_size(size_bytes));
-                    }
-                }
-                current_package.clear();
-            }
-        }
-    }
-    
-
-    for pkg_name in package_names {
-        if !sizes.contains_key(pkg_name) {
-            sizes.insert(pkg_name.clone(), "~50 MB".to_string()); 
+/// Refuses to remove the kernel the system is running on
+pub fn ensure_removable(package_name: &str, current_kernel: &str) -> Result<()> {
+    if let Some(kernel_version) = extract_kernel_version_from_package(package_name) {
+        if kernels_match(current_kernel, &kernel_version) {
+            return Err(Error::Other("The current running kernel cannot be removed.".to_string()));
         }
     }
-    
-    sizes
-}
-
-
-fn format_size(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
-    let mut size = bytes as f64;
-    let mut unit_index = 0;
-    
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
-    }
-    
-    if unit_index == 0 {
-        format!("{:.0} {}", size, UNITS[unit_index])
-    } else {
-        format!("{:.1} {}", size, UNITS[unit_index])
-    }
-}
-
-
-pub fn install_kernel(package_name: &str) -> Result<()> {
-    let status = Command::new("pkexec")
-        .args(&["apt", "install", "-y", package_name])
-        .status()
-        .context("Kernel install command could not be executed")?;
-        
-    if !status.success() {
-        bail!("Kernel installation failed");
-    }
-    
     Ok(())
 }
 
 
 pub fn remove_kernel_with_autoremove(package_name: &str, current_kernel: &str) -> Result<()> {
-
-    if let Some(kernel_version) = extract_kernel_version_from_package(package_name) {
-        if kernels_match(current_kernel, &kernel_version) {
-            bail!("The current running kernel cannot be removed.");
-        }
-    }
+    ensure_removable(package_name, current_kernel)?;
     
     println!("🗑️ Kernel is being removed: {}", package_name);
     
@@ -470,36 +383,33 @@ pub fn remove_kernel_with_autoremove(package_name: &str, current_kernel: &str) -
     
     println!("📋 Packages to remove: {:?}", kernel_packages);
     
-
-    for package in &kernel_packages {
-        println!("🗑️ Removing: {}", package);
-        
-        let status = Command::new("pkexec")
-            .args(&["apt", "remove", "--purge", "-y", package])
-            .status()
-            .context("Uninstall kernel command failed to execute")?;
-            
-        if !status.success() {
-            println!("⚠️ {} package removal failed, continuing...", package);
-        }
-    }
-    
-
-    println!("🧹 Orphaned packages are being cleaned...");
-    let autoremove_status = Command::new("pkexec")
-        .args(&["apt", "autoremove", "-y"])
-        .status()
-        .context("Autoremove command failed to execute")?;
-        
-    if !autoremove_status.success() {
-        println!("⚠️ Autoremove operation failed");
-    }
+    removal_transaction(&kernel_packages)
+        .run()
+        .context("Kernel removal")?;
     
     println!("✅ Kernel removal completed");
     Ok(())
 }
 
 
+/// Purges kernel packages and what only they needed. On zsys systems the
+/// whole system is saved as a state first, so the removal can be reverted.
+pub fn removal_transaction(packages: &[String]) -> Transaction {
+    let transaction = Transaction::purge(packages)
+        .then_autoremove()
+        .journal(OperationKind::KernelRemove);
+    match zfs::detect() {
+        Some(root) if root.zsys => {
+            let id = zfs::state_id(format::now());
+            let save = zfs::save_state_command(&id);
+            let save: Vec<&str> = save.iter().map(String::as_str).collect();
+            transaction.run_before(&save).note(&format!("zsys system state: {}", id))
+        }
+        _ => transaction,
+    }
+}
+
+
 fn find_related_kernel_packages(main_package: &str) -> Result<Vec<String>> {
     let mut packages = Vec::new();
     
@@ -550,6 +460,290 @@ fn find_related_kernel_packages(main_package: &str) -> Result<Vec<String>> {
 }
 
 
+/// Newest installed kernel when it is newer than the running one, i.e. a
+/// reboot is needed to use it
+pub fn newer_installed_kernel(installed: &[KernelInfo], running: &str) -> Option<String> {
+    let newest = installed.iter()
+        .filter(|kernel| kernel.is_installed)
+        .max_by(|a, b| util::compare_versions(&a.version, &b.version))?;
+    if kernels_match(&newest.version, running) {
+        return None;
+    }
+    (util::compare_versions(&newest.version, running) == std::cmp::Ordering::Greater).then(|| newest.version.clone())
+}
+
+/// `newer_installed_kernel` for this system
+pub fn pending_kernel_reboot() -> Option<String> {
+    let running = get_current_kernel().ok()?;
+    newer_installed_kernel(&get_installed_kernels().ok()?, &running)
+}
+
+
+/// Packages a kernel upgrade has to move together: the image and headers
+/// meta packages, the versioned packages they depend on and the firmware
+fn is_kernel_set_member(package_name: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "linux-image-",
+        "linux-headers-",
+        "linux-modules-",
+        "linux-kbuild-",
+        "linux-signed-",
+        "linux-generic",
+        "linux-lowlatency",
+        "linux-virtual",
+        "linux-oem-",
+        "linux-hwe-",
+        "firmware-linux",
+    ];
+    package_name == "linux-firmware" || PREFIXES.iter().any(|prefix| package_name.starts_with(prefix))
+}
+
+/// The pending kernel packages that are only installed together, so a
+/// partial selection cannot leave an image without its headers or modules.
+/// The image meta package leads. Empty unless two or more are pending.
+pub fn kernel_update_set(packages: &[PackageUpdate]) -> Vec<String> {
+    let mut members: Vec<String> = packages.iter()
+        .filter(|pkg| is_kernel_set_member(&pkg.name))
+        .map(|pkg| pkg.name.clone())
+        .collect();
+    if members.len() < 2 {
+        return Vec::new();
+    }
+    // "linux-image-amd64" sorts before "linux-image-6.1.0-18-amd64"
+    let not_image = |name: &str| !name.starts_with("linux-image-");
+    members.sort_by(|a, b| not_image(a).cmp(&not_image(b)).then(a.len().cmp(&b.len())).then(a.cmp(b)));
+    members.dedup();
+    members
+}
+
+/// Folds the kernel set into its lead for the update list: the total
+/// download size, and listed as a security update if any member is one
+pub fn merge_kernel_set(packages: Vec<PackageUpdate>, set: &[String]) -> Vec<PackageUpdate> {
+    let lead = match set.first() {
+        Some(lead) => lead,
+        None => return packages,
+    };
+    let members: Vec<&PackageUpdate> = packages.iter().filter(|pkg| set.contains(&pkg.name)).collect();
+    let sizes: Vec<u64> = members.iter().filter_map(|pkg| pkg.size).collect();
+    let size = (!sizes.is_empty()).then(|| sizes.iter().sum());
+    let security = members.iter().any(|pkg| pkg.update_type == UpdateType::Security);
+    let from_security = members.iter().any(|pkg| pkg.from_security);
+
+    packages.into_iter()
+        .filter(|pkg| pkg.name == *lead || !set.contains(&pkg.name))
+        .map(|mut pkg| {
+            if pkg.name == *lead {
+                pkg.size = size;
+                pkg.from_security = from_security;
+                if security {
+                    pkg.update_type = UpdateType::Security;
+                }
+            }
+            pkg
+        })
+        .collect()
+}
+
+/// Adds the rest of the kernel set to a selection that has its lead
+pub fn expand_kernel_set(mut selected: Vec<String>, set: &[String]) -> Vec<String> {
+    if let Some(lead) = set.first() {
+        if selected.contains(lead) {
+            for member in &set[1..] {
+                if !selected.contains(member) {
+                    selected.push(member.clone());
+                }
+            }
+        }
+    }
+    selected
+}
+
+/// Disk space taken by the installed kernels
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KernelDiskUsage {
+    pub installed: usize,
+    /// Kernel images, initramfs images and their companions in /boot
+    pub boot_bytes: u64,
+    pub modules_bytes: u64,
+    /// Installed size of the kernels a cleanup would remove
+    pub reclaimable_bytes: u64,
+}
+
+const BOOT_FILE_PREFIXES: &[&str] = &["vmlinuz-", "initrd.img-", "System.map-", "config-"];
+
+/// Installed kernels a cleanup removes: all but the running and the newest one
+pub fn cleanup_candidates(kernels: &[KernelInfo]) -> Vec<&KernelInfo> {
+    let newest = kernels.iter()
+        .filter(|kernel| kernel.is_installed)
+        .max_by(|a, b| util::compare_versions(&a.version, &b.version))
+        .map(|kernel| kernel.version.clone());
+
+    kernels.iter()
+        .filter(|kernel| kernel.is_installed && !kernel.is_current)
+        .filter(|kernel| Some(&kernel.version) != newest.as_ref())
+        .collect()
+}
+
+/// Total size of the files below `path`; symlinks are not followed
+pub fn dir_size(path: &Path) -> u64 {
+    let mut total = 0;
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            if let Ok(metadata) = entry.metadata() {
+                if metadata.is_dir() {
+                    total += dir_size(&entry.path());
+                } else if metadata.is_file() {
+                    total += metadata.len();
+                }
+            }
+        }
+    }
+    total
+}
+
+/// Sum of the `Installed-Size` fields of the installed `packages`, in bytes
+fn installed_size(packages: &[String]) -> u64 {
+    if packages.is_empty() {
+        return 0;
+    }
+    Command::new("dpkg-query")
+        .arg("-W")
+        .arg("-f=${Installed-Size}\n")
+        .args(packages)
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.trim().parse::<u64>().ok())
+                .sum::<u64>() * 1024
+        })
+        .unwrap_or(0)
+}
+
+/// Space the kernel image, initramfs and companions of `version` take in /boot
+pub fn kernel_boot_bytes(version: &str) -> u64 {
+    BOOT_FILE_PREFIXES.iter()
+        .filter_map(|prefix| fs::metadata(sysroot::path(&format!("/boot/{}{}", prefix, version))).ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Measures /boot and /lib/modules and what removing old kernels would free
+pub fn kernel_disk_usage(kernels: &[KernelInfo]) -> KernelDiskUsage {
+    let boot_bytes = fs::read_dir(sysroot::path("/boot"))
+        .map(|entries| {
+            entries.flatten()
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    BOOT_FILE_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+                })
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or(0);
+
+    let reclaimable_packages: Vec<String> = cleanup_candidates(kernels).iter()
+        .flat_map(|kernel| find_related_kernel_packages(&kernel.package_name).unwrap_or_default())
+        .collect();
+
+    KernelDiskUsage {
+        installed: kernels.iter().filter(|kernel| kernel.is_installed).count(),
+        boot_bytes,
+        modules_bytes: dir_size(Path::new("/lib/modules")),
+        reclaimable_bytes: installed_size(&reclaimable_packages),
+    }
+}
+
+
+/// /lib/modules directory left behind by a kernel whose packages are gone
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanModules {
+    pub version: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+const MODULE_ROOTS: &[&str] = &["/lib/modules", "/usr/lib/modules"];
+
+/// Paths reported by `dpkg-query -S`, whose lines read "pkg1, pkg2: /path"
+pub fn parse_dpkg_search(output: &str) -> HashSet<String> {
+    output.lines()
+        .filter(|line| !line.starts_with("diversion by"))
+        .filter_map(|line| line.split_once(": "))
+        .map(|(_, path)| path.trim().to_string())
+        .collect()
+}
+
+/// Kernel release strings never contain anything else, which keeps the
+/// directory name safe to hand to a shell
+pub fn is_safe_kernel_version(version: &str) -> bool {
+    !version.is_empty()
+        && version != "."
+        && version != ".."
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | '_' | '~'))
+}
+
+/// Module directories no installed package owns, except the running kernel's
+pub fn find_orphan_module_dirs() -> Vec<OrphanModules> {
+    let running = get_current_kernel().unwrap_or_default();
+    let versions: Vec<String> = fs::read_dir("/lib/modules")
+        .map(|entries| {
+            entries.flatten()
+                .filter(|entry| entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false))
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|version| is_safe_kernel_version(version) && !kernels_match(version, &running))
+                .collect()
+        })
+        .unwrap_or_default();
+    if versions.is_empty() {
+        return Vec::new();
+    }
+
+    // Merged-/usr systems may register the files under either prefix
+    let queried: Vec<String> = versions.iter()
+        .flat_map(|version| MODULE_ROOTS.iter().map(move |root| format!("{}/{}", root, version)))
+        .collect();
+    let owned = match Command::new("dpkg-query").arg("-S").args(&queried).env("LANG", "C").output() {
+        Ok(output) => parse_dpkg_search(&String::from_utf8_lossy(&output.stdout)),
+        Err(_) => return Vec::new(),
+    };
+
+    versions.into_iter()
+        .filter(|version| !MODULE_ROOTS.iter().any(|root| owned.contains(&format!("{}/{}", root, version))))
+        .map(|version| {
+            let path = Path::new("/lib/modules").join(&version);
+            let size = dir_size(&path);
+            OrphanModules { version, path, size }
+        })
+        .collect()
+}
+
+/// Deletes an orphaned module directory after checking again that no
+/// package owns it and that it is not the running kernel's
+pub fn remove_orphan_module_dir(orphan: &OrphanModules) -> Result<()> {
+    if !is_safe_kernel_version(&orphan.version) {
+        return Err(Error::Other(format!("Refusing to delete unexpected directory {}", orphan.path.display())));
+    }
+    if !find_orphan_module_dirs().iter().any(|current| current.version == orphan.version) {
+        return Err(Error::Other(format!("{} belongs to an installed package or the running kernel", orphan.path.display())));
+    }
+
+    let status = privilege::elevated_command()
+        .args(["rm", "-rf", "--"])
+        .arg(format!("/lib/modules/{}", orphan.version))
+        .status()
+        .context("Module directory could not be deleted")?;
+    if !status.success() {
+        return Err(Error::from_status(&format!("Deleting {}", orphan.path.display()), &status, ""));
+    }
+    Ok(())
+}
+
+
 fn detect_linux_mint_de() -> Option<String> {
 
     if let Ok(current_desktop) = std::env::var("XDG_CURRENT_DESKTOP") {
@@ -620,33 +814,36 @@ fn detect_linux_mint_de() -> Option<String> {
 }
 
 
-fn detect_distribution_name() -> String {
+const GRUB_CFG_PATHS: [&str; 4] = [
+    "/boot/grub/grub.cfg",
+    "/boot/grub2/grub.cfg",
+    "/boot/efi/EFI/debian/grub.cfg",
+    "/boot/efi/EFI/ubuntu/grub.cfg",
+];
 
-    let grub_cfg_paths = [
-        "/boot/grub/grub.cfg",
-        "/boot/grub2/grub.cfg", 
-        "/boot/efi/EFI/debian/grub.cfg",
-        "/boot/efi/EFI/ubuntu/grub.cfg"
-    ];
-    
+/// The GRUB menu files present, most common location first
+fn grub_cfg_files() -> impl Iterator<Item = PathBuf> {
+    GRUB_CFG_PATHS.iter().map(|path| sysroot::path(path)).filter(|path| path.exists())
+}
 
-    for path in &grub_cfg_paths {
-        if std::path::Path::new(path).exists() {
-            if let Ok(grub_content) = fs::read_to_string(path) {
-                for line in grub_content.lines() {
-                    let line = line.trim();
-                    
+/// Distribution name as GRUB's "Advanced options for ..." submenu shows it
+pub fn detect_distribution_name() -> String {
 
-                    if line.starts_with("submenu ") && line.contains("Advanced options for") {
-                        if let Some(title_start) = line.find("'") {
-                            if let Some(title_end) = line.rfind("'") {
-                                let submenu_title = line[title_start+1..title_end].to_string();
-                                
+    for path in grub_cfg_files() {
+        if let Ok(grub_content) = fs::read_to_string(&path) {
+            for line in grub_content.lines() {
+                let line = line.trim();
+                
 
-                                if let Some(distro_part) = submenu_title.strip_prefix("Advanced options for ") {
-                                    println!("🎯 Distribution detected from GRUB: {}", distro_part);
-                                    return distro_part.to_string();
-                                }
+                if line.starts_with("submenu ") && line.contains("Advanced options for") {
+                    if let Some(title_start) = line.find("'") {
+                        if let Some(title_end) = line.rfind("'") {
+                            let submenu_title = line[title_start+1..title_end].to_string();
+                            
+
+                            if let Some(distro_part) = submenu_title.strip_prefix("Advanced options for ") {
+                                println!("🎯 Distribution detected from GRUB: {}", distro_part);
+                                return distro_part.to_string();
                             }
                         }
                     }
@@ -656,7 +853,7 @@ fn detect_distribution_name() -> String {
     }
     
 
-    if let Ok(content) = fs::read_to_string("/etc/os-release") {
+    if let Ok(content) = fs::read_to_string(sysroot::path("/etc/os-release")) {
         let mut name = String::new();
         let mut id = String::new();
         let mut version = String::new();
@@ -727,7 +924,7 @@ fn detect_distribution_name() -> String {
     }
     
 
-    if std::path::Path::new("/etc/debian_version").exists() {
+    if sysroot::path("/etc/debian_version").exists() {
         return "Debian GNU/Linux".to_string();
     }
     
@@ -774,7 +971,7 @@ fn normalize_distro_name(name: &str) -> String {
 
 fn is_lmde_system() -> bool {
 
-    if let Ok(content) = fs::read_to_string("/etc/os-release") {
+    if let Ok(content) = fs::read_to_string(sysroot::path("/etc/os-release")) {
         for line in content.lines() {
             if line.starts_with("ID=") {
                 let id = line.trim_start_matches("ID=").trim_matches('"').to_string();
@@ -807,7 +1004,18 @@ fn is_lmde_system() -> bool {
 
 pub fn set_default_kernel(kernel_version: &str) -> Result<()> {
 
+    if !privilege::can_run_commands() {
+        return Err(Error::Other(privilege::HELPER_UNSUPPORTED.to_string()));
+    }
+    if zfs::detect().is_some() {
+        return Err(Error::Other(zfs::SET_DEFAULT_UNSUPPORTED.to_string()));
+    }
+    // Writing GRUB settings on a board that boots without GRUB does nothing good
+    if !boot_platform::detect().uses_grub() {
+        return Err(Error::Other(boot_platform::SET_DEFAULT_UNSUPPORTED.to_string()));
+    }
     let clean_version = kernel_version.split('/').next().unwrap_or(kernel_version);
+    let _journal = journal::begin(OperationKind::GrubUpdate, clean_version);
     
     println!("🔍 Updating GRUB settings...");
     println!("📋 Target kernel: {}", clean_version);
@@ -824,24 +1032,13 @@ pub fn set_default_kernel(kernel_version: &str) -> Result<()> {
     println!("📋 Target kernel: {}", clean_version);
     
 
-    let grub_cfg_paths = [
-        "/boot/grub/grub.cfg",
-        "/boot/grub2/grub.cfg", 
-        "/boot/efi/EFI/debian/grub.cfg",
-        "/boot/efi/EFI/ubuntu/grub.cfg"
-    ];
-    
-    let mut grub_cfg_path = None;
-    for path in &grub_cfg_paths {
-        if std::path::Path::new(path).exists() {
-            grub_cfg_path = Some(*path);
-            println!("📁 GRUB config found: {}", path);
-            break;
-        }
+    let grub_cfg_path = grub_cfg_files().next();
+    if let Some(path) = &grub_cfg_path {
+        println!("📁 GRUB config found: {}", path.display());
     }
     
 
-    if let Some(cfg_path) = grub_cfg_path {
+    if let Some(cfg_path) = &grub_cfg_path {
         if let Ok(grub_content) = fs::read_to_string(cfg_path) {
             println!("🔍 Analyzing GRUB menu structure...");
             
@@ -900,7 +1097,7 @@ pub fn set_default_kernel(kernel_version: &str) -> Result<()> {
 
     println!("⚠️ Automatic analysis failed, scanning submenu names from GRUB config...");
     
-    if let Some(cfg_path) = grub_cfg_path {
+    if let Some(cfg_path) = &grub_cfg_path {
         if let Ok(grub_content) = fs::read_to_string(cfg_path) {
 
             let mut real_distro_name = None;
@@ -982,15 +1179,111 @@ pub fn set_default_kernel(kernel_version: &str) -> Result<()> {
     set_grub_default_via_config(&manual_entry, clean_version)
 }
 
+/// Exit code of the GRUB script when the settings were written but no
+/// command could regenerate grub.cfg
+const GRUB_REGENERATE_FAILED: i32 = 3;
+
+/// The GRUB settings that `set_default_kernel` changes
+const GRUB_FILE: &str = "/etc/default/grub";
+
+/// Where the GRUB settings are saved before each change
+const GRUB_BACKUP_PREFIX: &str = "/etc/default/grub.backup-";
+
+fn new_grub_backup_path() -> String {
+    let stamp = glib::DateTime::now_local()
+        .and_then(|now| now.format("%Y%m%d-%H%M%S"))
+        .map(|stamp| stamp.to_string())
+        .unwrap_or_else(|_| format::now().to_string());
+    format!("{}{}", GRUB_BACKUP_PREFIX, stamp)
+}
+
+/// Whether `path` is a backup made before changing the GRUB settings. The
+/// history is writable by the user, so nothing else may be copied over them.
+pub fn is_grub_backup(path: &str) -> bool {
+    path.strip_prefix(GRUB_BACKUP_PREFIX)
+        .is_some_and(|stamp| !stamp.is_empty() && stamp.chars().all(|c| c.is_ascii_digit() || c == '-'))
+}
+
+/// Shell commands that regenerate grub.cfg with the first tool found,
+/// exiting with `GRUB_REGENERATE_FAILED` when none works
+fn regenerate_grub_script() -> String {
+    format!(r#"for cmd in "update-grub" "grub-mkconfig -o /boot/grub/grub.cfg" "grub2-mkconfig -o /boot/grub2/grub.cfg"; do
+    if command -v ${{cmd%% *}} >/dev/null 2>&1; then
+        echo "   Trying: $cmd"
+        if $cmd; then
+            echo "   ✅ Successful: $cmd"
+            exit 0
+        fi
+    fi
+done
+exit {regenerate_failed}
+"#, regenerate_failed = GRUB_REGENERATE_FAILED)
+}
+
+/// Puts back GRUB settings saved by an earlier change and regenerates
+/// grub.cfg. The current settings are saved first, so this can be undone
+/// the same way.
+pub fn restore_grub_backup(backup: &str) -> Result<()> {
+    if !is_grub_backup(backup) {
+        return Err(Error::Other(format!("{} is not a backup of the GRUB settings", backup)));
+    }
+    if !sysroot::path(backup).exists() {
+        return Err(Error::Other(format!("The backup {} no longer exists", backup)));
+    }
+    if !boot_platform::detect().uses_grub() {
+        return Err(Error::Other(boot_platform::SET_DEFAULT_UNSUPPORTED.to_string()));
+    }
+    if !privilege::can_run_commands() {
+        return Err(Error::Other(privilege::HELPER_UNSUPPORTED.to_string()));
+    }
+    let _journal = journal::begin(OperationKind::GrubUpdate, backup);
+    let current_backup = new_grub_backup_path();
+
+    println!("↩️ Restoring GRUB settings from {}", backup);
+    let script = format!(
+        "set -e\ncp {grub_file} {current_backup}\ncp {backup} {grub_file}\nset +e\n{regenerate}",
+        grub_file = GRUB_FILE,
+        current_backup = current_backup,
+        backup = backup,
+        regenerate = regenerate_grub_script(),
+    );
+    let result = privilege::elevated_command()
+        .args(&["sh", "-c", &script])
+        .status()
+        .context("GRUB settings restore")
+        .and_then(|status| match status.code() {
+            Some(0) => Ok(()),
+            Some(GRUB_REGENERATE_FAILED) => Err(Error::Other(
+                "The GRUB settings were restored, but grub.cfg could not be regenerated.\n\nRun sudo update-grub by hand.".to_string(),
+            )),
+            _ => Err(Error::from_status("GRUB settings restore", &status, "")),
+        });
+
+    let notes = [format!("Restored GRUB settings from {}", backup), format!("Backup: {}", current_backup)];
+    history::record_event(OperationKind::GrubUpdate, &notes, result.is_ok());
+    result
+}
+
+/// Makes `entry` the default boot entry and records it in the Update History
 fn set_grub_default_via_config(entry: &str, kernel_version: &str) -> Result<()> {
-    let grub_file = "/etc/default/grub";
-    
+    let backup = new_grub_backup_path();
+    let result = write_grub_default(GRUB_FILE, &backup, entry, kernel_version);
+    let notes = [
+        format!("Default kernel: {}", kernel_version),
+        format!("GRUB entry: {}", entry),
+        format!("Backup: {}", backup),
+    ];
+    history::record_event(OperationKind::GrubUpdate, &notes, result.is_ok());
+    result
+}
+
+fn write_grub_default(grub_file: &str, backup: &str, entry: &str, kernel_version: &str) -> Result<()> {
     println!("🔧 Updating /etc/default/grub file...");
     
 
     let combined_script = format!(r#"
 # Create backup
-cp {grub_file} {grub_file}.backup-$(date +%Y%m%d-%H%M%S)
+cp {grub_file} {backup}
 
 # Delete old GRUB_DEFAULT lines
 sed -i '/^GRUB_DEFAULT=/d' {grub_file}
@@ -1007,72 +1300,33 @@ echo 'GRUB_TIMEOUT=10' >> {grub_file}
 echo 'GRUB_TIMEOUT_STYLE=menu' >> {grub_file}
 
 echo "✅ GRUB settings updated"
-"#, grub_file = grub_file, entry = entry);
+
+# Regenerate grub.cfg in the same run, so one password prompt is enough
+{regenerate}"#, grub_file = grub_file, backup = backup, entry = entry, regenerate = regenerate_grub_script());
 
 
-    let status = Command::new("pkexec")
+    let status = privilege::elevated_command()
         .args(&["sh", "-c", &combined_script])
-        .status();
-        
-    match status {
-        Ok(exit_status) => {
-            if exit_status.success() {
-                println!("🔧 GRUB_DEFAULT is set: {}", entry);
-            } else {
-                bail!("GRUB config update failed");
-            }
-        }
-        Err(e) => {
-            bail!("GRUB config update error: {}", e);
-        }
+        .status()
+        .context("GRUB config update error")?;
+    let update_success = status.success();
+    if !update_success && status.code() != Some(GRUB_REGENERATE_FAILED) {
+        return Err(Error::from_status("GRUB config update", &status, ""));
     }
-    
-
-    println!("🔄 Updating GRUB config...");
-    
-    let update_commands = [
-        "update-grub",
-        "/usr/sbin/update-grub", 
-        "/usr/bin/update-grub",
-        "grub-mkconfig -o /boot/grub/grub.cfg",
-        "/usr/sbin/grub-mkconfig -o /boot/grub/grub.cfg",
-        "grub2-mkconfig -o /boot/grub2/grub.cfg",
-        "/usr/sbin/grub2-mkconfig -o /boot/grub2/grub.cfg",
-    ];
-    
-    let mut update_success = false;
-    
-    for cmd_str in &update_commands {
+    println!("🔧 GRUB_DEFAULT is set: {}", entry);
+    // GRUB cannot clear a one-time entry it cannot write back
+    let grubenv_writable = boot_layout::detect().grubenv_writable();
 
-        let first_part = cmd_str.split_whitespace().next().unwrap_or("");
-        if let Ok(which_output) = Command::new("which").arg(first_part).output() {
-            if which_output.status.success() {
-                println!("   Trying: {}", cmd_str);
-                
-                let cmd_parts: Vec<&str> = cmd_str.split_whitespace().collect();
-                let status = Command::new("pkexec")
-                    .args(&cmd_parts)
-                    .status();
-                    
-                if let Ok(status) = status {
-                    if status.success() {
-                        println!("   ✅ Successful: {}", cmd_str);
-                        update_success = true;
-                        break;
-                    }
-                }
-            }
-        }
-    }
-    
     if update_success {
         println!("✅ Default kernel set successfully!");
         println!("🔄 Kernel '{}' will be selected automatically when the system is rebooted", kernel_version);
         
 
-        println!("\n🧪 Test command:");
-        println!("   sudo grub-reboot \"{}\"", entry);
-        println!("   sudo reboot");
+        if grubenv_writable {
+            println!("\n🧪 Test command:");
+            println!("   sudo grub-reboot \"{}\"", entry);
+            println!("   sudo reboot");
+        }
         
         Ok(())
     } else {
@@ -1085,9 +1339,13 @@ echo "✅ GRUB settings updated"
         println!("   # veya");
         println!("   sudo grub-mkconfig -o /boot/grub/grub.cfg");
         println!();
-        println!("3. One-time test:");
-        println!("   sudo grub-reboot \"{}\"", entry);
+        if grubenv_writable {
+            println!("3. One-time test:");
+            println!("   sudo grub-reboot \"{}\"", entry);
+        } else {
+            println!("3. Pick the entry in the boot menu: grub-reboot does not work while /boot is on LUKS, LVM or RAID");
+        }
         
-        bail!("GRUB config update failed")
+        Err(Error::Other("GRUB config update failed".to_string()))
     }
 }
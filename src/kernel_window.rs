@@ -1,7 +1,13 @@
 // src/kernel_window.rs
 
-use crate::kernel_manager::{self, KernelInfo, KernelType};
-use crate::progress::ProgressWindow;
+use crate::error::{self, Error};
+use crate::format;
+use crate::icons::{self, Icon};
+use crate::kernel_manager::{self, KernelDiskUsage, KernelInfo, KernelType, OrphanModules};
+use crate::apt::{self, Transaction};
+use crate::boot_layout::{self, KernelOperation};
+use crate::boot_platform::{self, BootPlatform};
+use crate::progress::{self, ProgressWindow};
 use gtk::prelude::*;
 use gtk::{
     ApplicationWindow, Box as GtkBox, Button, ListBox, ListBoxRow, ScrolledWindow,
@@ -10,8 +16,12 @@ use gtk::{
     ResponseType,
 };
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use crate::journal::OperationKind;
+use crate::privilege;
+use crate::sessions;
+use crate::zfs;
 
 pub struct KernelWindow {
     window: Dialog,
@@ -126,6 +136,17 @@ impl KernelWindow {
         let default_btn = Button::with_label("⭐ Make default");
         default_btn.add_css_class("kernel-default-btn");
         default_btn.set_sensitive(false);
+        let platform = boot_platform::detect();
+        let zfs_root = zfs::detect();
+        if !platform.uses_grub() {
+            // The GRUB settings do not exist on such boards
+            default_btn.set_visible(false);
+        } else if zfs_root.is_some() {
+            default_btn.set_tooltip_text(Some(zfs::SET_DEFAULT_UNSUPPORTED));
+        } else if !privilege::can_run_commands() {
+            default_btn.set_tooltip_text(Some(privilege::HELPER_UNSUPPORTED));
+        }
+        let can_set_default = platform.uses_grub() && zfs_root.is_none() && privilege::can_run_commands();
         
         let refresh_btn = Button::with_label("🔄 Refresh list");
         refresh_btn.add_css_class("refresh-button");
@@ -136,6 +157,12 @@ impl KernelWindow {
         action_box.append(&refresh_btn);
         
         right_vbox.append(&action_box);
+
+        let stats_pane = KernelStatsPane::new(&platform);
+        right_vbox.append(&stats_pane.frame);
+
+        let orphans_pane = OrphanModulesPane::new(&window);
+        right_vbox.append(&orphans_pane.frame);
         
         right_frame.set_child(Some(&right_vbox));
         
@@ -161,26 +188,22 @@ impl KernelWindow {
         let selected_kernel_install = selected_kernel.clone();
         let kernels_install = kernels.clone();
         let listbox_install = version_listbox.clone();
-        let current_label_install = current_kernel_label.clone();
         let window_install = window.clone();
         install_btn.connect_clicked(move |_| {
             if let Some(kernel) = selected_kernel_install.borrow().as_ref() {
                 let kernel_clone = kernel.clone();
                 let kernels_clone = kernels_install.clone();
                 let listbox_clone = listbox_install.clone();
-                let current_label_clone = current_label_install.clone();
                 let window_clone = window_install.clone(); 
-                glib::spawn_future_local(async move {
-                    let app_window = window_clone.clone().upcast::<gtk::Window>().downcast::<ApplicationWindow>().unwrap_or_else(|_| ApplicationWindow::builder().build());
-                    match Self::install_kernel_with_progress_impl(crate::progress::ProgressWindow::new(&app_window), &kernel_clone).await {
-                        Ok(_) => {
-                            kernel_manager::set_kernel_cache(vec![]);
-                            Self::refresh_kernels_with_progress(&window_clone, &kernels_clone, &listbox_clone, &current_label_clone);
-                        }
-                        Err(e) => {
-                            eprintln!("Kernel loading error: {}", e);
-                        }
-                    }
+                let action = format!("install kernel {}", kernel_clone.version);
+                Self::confirm_boot_layout(window_install.upcast_ref(), KernelOperation::Install, move || {
+                    let kernel_clone = kernel_clone.clone();
+                    let kernels_clone = kernels_clone.clone();
+                    let listbox_clone = listbox_clone.clone();
+                    let window_clone = window_clone.clone();
+                    sessions::confirm_disruptive_action(&window_clone.clone().upcast(), &action, move || {
+                        Self::install_kernel_with_progress(window_clone.upcast_ref(), &kernel_clone, &kernels_clone, &listbox_clone);
+                    });
                 });
             }
         });
@@ -200,7 +223,11 @@ impl KernelWindow {
         let window_default = window.clone();
         default_btn.connect_clicked(move |_| {
             if let Some(kernel) = selected_kernel_default.borrow().as_ref() {
-                Self::set_default_kernel_action(&window_default.clone().upcast(), kernel);
+                let kernel = kernel.clone();
+                let parent: gtk::Window = window_default.clone().upcast();
+                Self::confirm_boot_layout(&parent.clone(), KernelOperation::SetDefault, move || {
+                    Self::set_default_kernel_action(&parent, &kernel);
+                });
             }
         });
 
@@ -239,11 +266,11 @@ impl KernelWindow {
                         };
                         selected_type_clone.set_markup(type_text);
                         
-                        selected_size_clone.set_markup(&format!("<b>Size:</b> {}", kernel.size));
+                        selected_size_clone.set_markup(&format!("<b>Size:</b> {}", format::optional_size(kernel.size)));
                         
                         install_btn_clone.set_sensitive(!kernel.is_installed && !kernel.is_current);
                         remove_btn_clone.set_sensitive(kernel.is_installed && !kernel.is_current);
-                        default_btn_clone.set_sensitive(kernel.is_installed && !kernel.is_current);
+                        default_btn_clone.set_sensitive(kernel.is_installed && !kernel.is_current && can_set_default);
                     }
                 }
             } else {
@@ -258,6 +285,24 @@ impl KernelWindow {
             }
         });
 
+        // Every refresh rebuilds the list, including those after installs and
+        // removals, so the stats follow the rows instead of each caller
+        let stats_pending = Rc::new(Cell::new(false));
+        let kernels_stats = kernels.clone();
+        version_listbox.observe_children().connect_items_changed(move |_, _, _, _| {
+            if stats_pending.replace(true) {
+                return;
+            }
+            let stats_pending = stats_pending.clone();
+            let kernels_stats = kernels_stats.clone();
+            let stats_pane = stats_pane.clone();
+            let orphans_pane = orphans_pane.clone();
+            glib::idle_add_local_once(move || {
+                stats_pending.set(false);
+                stats_pane.refresh(kernels_stats.borrow().clone());
+                orphans_pane.refresh();
+            });
+        });
         
         Self::refresh_kernels_with_progress(&window, &kernels, &version_listbox, &current_kernel_label);
 
@@ -317,155 +362,41 @@ impl KernelWindow {
         });
     }
 
-    async fn check_kernels_with_progress(progress_window: ProgressWindow) -> Result<Vec<KernelInfo>, anyhow::Error> {
-        use std::sync::{Arc, Mutex, mpsc};
-        use std::thread;
-        use std::time::Duration;
+    async fn check_kernels_with_progress(progress_window: ProgressWindow) -> Result<Vec<KernelInfo>, Error> {
         use crate::progress::ProgressMessage;
 
-        let (tx, rx) = mpsc::channel::<ProgressMessage>();
-        let (result_tx, result_rx) = mpsc::channel::<Result<Vec<KernelInfo>, anyhow::Error>>();
-
-        let progress_bar = progress_window.progress_bar.clone();
-        let status_label = progress_window.status_label.clone();
-        let log_buffer = progress_window.log_buffer.clone();
-        let log_view = progress_window.log_view.clone();
-
-        let rx = Arc::new(Mutex::new(rx));
-        let rx_clone = rx.clone();
-
-        thread::spawn(move || {
+        progress_window.run_check(|tx| {
             let _ = tx.send(ProgressMessage::Status("Checking kernel list...".to_string()));
             let _ = tx.send(ProgressMessage::Progress(0.1, "10%".to_string()));
             let _ = tx.send(ProgressMessage::Log("Searching for available kernels...".to_string()));
 
-            match kernel_manager::get_available_kernels() {
-                Ok(mut kernels) => {
-                    let _ = tx.send(ProgressMessage::Progress(0.5, "50%".to_string()));
-                    let _ = tx.send(ProgressMessage::Log(format!("{} Kernel found", kernels.len())));
-
-                    
-                    let package_names: Vec<String> = kernels.iter()
-                        .map(|k| k.package_name.clone())
-                        .collect();
-                    
-                    let _ = tx.send(ProgressMessage::Status("Getting kernel sizes...".to_string()));
-                    let _ = tx.send(ProgressMessage::Progress(0.8, "80%".to_string()));
-                    
-                    let sizes = kernel_manager::get_kernel_sizes(&package_names);
-                    
-                    
-                    for kernel in &mut kernels {
-                        if let Some(size) = sizes.get(&kernel.package_name) {
-                            kernel.size = size.clone();
-                        }
-                    }
-
-                    let _ = tx.send(ProgressMessage::Progress(1.0, "100%".to_string()));
-                    let _ = tx.send(ProgressMessage::Status("✅ Kernel list is ready!".to_string()));
-                    let _ = tx.send(ProgressMessage::CheckComplete);
-                    let _ = result_tx.send(Ok(kernels));
-                }
+            let mut kernels = match kernel_manager::get_available_kernels() {
+                Ok(kernels) => kernels,
                 Err(e) => {
-                    let _ = tx.send(ProgressMessage::Error(format!("Could not get kernel list: {}", e)));
-                    let _ = result_tx.send(Err(anyhow::anyhow!("Kernel list error: {}", e)));
+                    let _ = tx.send(ProgressMessage::Log(format!("Could not get kernel list: {}", e)));
+                    return Err(e.with_context("Kernel list error"));
                 }
-            }
-        });
+            };
+            let _ = tx.send(ProgressMessage::Progress(0.5, "50%".to_string()));
+            let _ = tx.send(ProgressMessage::Log(format!("{} Kernel found", kernels.len())));
 
-        let result_kernels = Arc::new(Mutex::new(Vec::new()));
-        let result_error = Arc::new(Mutex::new(None::<anyhow::Error>));
-        let is_complete = Arc::new(Mutex::new(false));
+            let package_names: Vec<String> = kernels.iter()
+                .map(|k| k.package_name.clone())
+                .collect();
 
-        let result_kernels_clone = result_kernels.clone();
-        let result_error_clone = result_error.clone();
-        let is_complete_clone = is_complete.clone();
+            let _ = tx.send(ProgressMessage::Status("Getting kernel sizes...".to_string()));
+            let _ = tx.send(ProgressMessage::Progress(0.8, "80%".to_string()));
 
-        
-        glib::timeout_add_local(Duration::from_millis(100), move || {
-            let mut messages_to_process = Vec::new();
-            
-            if let Ok(rx_guard) = rx_clone.try_lock() {
-                while let Ok(msg) = rx_guard.try_recv() {
-                    messages_to_process.push(msg);
-                }
+            let sizes = apt::get_package_sizes(&package_names);
+            for kernel in &mut kernels {
+                kernel.size = sizes.get(&kernel.package_name).copied();
             }
 
-            
-            if let Ok(res) = result_rx.try_recv() {
-                match res {
-                    Ok(kernels) => {
-                        if let Ok(mut kernels_guard) = result_kernels_clone.try_lock() {
-                            *kernels_guard = kernels;
-                        }
-                    }
-                    Err(error) => {
-                        if let Ok(mut error_guard) = result_error_clone.try_lock() {
-                            *error_guard = Some(error);
-                        }
-                    }
-                }
-                if let Ok(mut complete_guard) = is_complete_clone.try_lock() {
-                    *complete_guard = true;
-                }
-            }
-
-            
-            for msg in messages_to_process {
-                match msg {
-                    ProgressMessage::Status(status) => {
-                        status_label.set_markup(&format!("<b>{}</b>", status));
-                    }
-                    ProgressMessage::Progress(fraction, text) => {
-                        progress_bar.set_fraction(fraction);
-                        progress_bar.set_text(Some(&text));
-                    }
-                    ProgressMessage::Log(log) => {
-                        let mut end_iter = log_buffer.end_iter();
-                        log_buffer.insert(&mut end_iter, &format!("{}\n", log));
-                        
-                        let mark = log_buffer.create_mark(None, &end_iter, false);
-                        log_view.scroll_mark_onscreen(&mark);
-                    }
-                    ProgressMessage::Error(_) => {
-                        return glib::ControlFlow::Break;
-                    }
-                    ProgressMessage::CheckComplete => {
-                        glib::timeout_add_seconds_local(2, {
-                            let window = progress_window.window.clone();
-                            move || {
-                                window.close();
-                                glib::ControlFlow::Break
-                            }
-                        });
-                        return glib::ControlFlow::Break;
-                    }
-                    _ => {}
-                }
-            }
-            
-            glib::ControlFlow::Continue
-        });
-
-        
-        loop {
-            glib::MainContext::default().iteration(false);
-            thread::sleep(Duration::from_millis(50));
-            
-            if let Ok(complete_guard) = is_complete.try_lock() {
-                if *complete_guard {
-                    if let Ok(error_guard) = result_error.try_lock() {
-                        if let Some(error) = error_guard.as_ref() {
-                            return Err(anyhow::anyhow!("{}", error));
-                        }
-                    }
-                    
-                    if let Ok(kernels_guard) = result_kernels.try_lock() {
-                        return Ok(kernels_guard.clone());
-                    }
-                }
-            }
-        }
+            let _ = tx.send(ProgressMessage::Progress(1.0, "100%".to_string()));
+            let _ = tx.send(ProgressMessage::Status("✅ Kernel list is ready!".to_string()));
+            let _ = tx.send(ProgressMessage::CheckComplete);
+            Ok(kernels)
+        }).await
     }
 
     fn update_current_kernel_label(label: &Label) {
@@ -477,6 +408,9 @@ impl KernelWindow {
                 label.set_markup("<b>❌ Current Kernel: Undetermined</b>");
             }
         }
+        let checked = kernel_manager::last_kernel_check()
+            .map(|ts| format!("Kernel list checked {}", format::time_ago(ts)));
+        label.set_tooltip_text(checked.as_deref());
     }
 
     fn populate_kernel_list(listbox: &ListBox, kernels: Vec<KernelInfo>, kernels_ref: &Rc<RefCell<Vec<KernelInfo>>>) {
@@ -543,14 +477,14 @@ impl KernelWindow {
             let version_box = GtkBox::new(Orientation::Horizontal, 6);
             
             let status_icon = if kernel.is_current {
-                "🟢"
+                Icon::Active
             } else if kernel.is_installed {
-                "🔵" 
+                Icon::Installed
             } else {
-                "⚪"
+                Icon::Available
             };
             
-            let icon_label = Label::new(Some(status_icon));
+            let icon_label = icons::widget(status_icon);
             
             let version_label = Label::new(Some(&kernel.version));
             version_label.set_halign(gtk::Align::Start);
@@ -606,19 +540,24 @@ impl KernelWindow {
             if response == gtk::ResponseType::Yes {
                 match kernel_manager::get_current_kernel() {
                     Ok(current_kernel) => {
-                        
-                        Self::remove_kernel_with_progress(&parent_clone, &kernel_clone, &kernels_ref_clone, &listbox_clone, &current_kernel);
+                        let parent = parent_clone.clone();
+                        let kernel = kernel_clone.clone();
+                        let kernels_ref = kernels_ref_clone.clone();
+                        let listbox = listbox_clone.clone();
+                        let action = format!("remove kernel {}", kernel_clone.version);
+                        Self::confirm_boot_layout(&parent_clone, KernelOperation::Remove, move || {
+                            let parent = parent.clone();
+                            let kernel = kernel.clone();
+                            let kernels_ref = kernels_ref.clone();
+                            let listbox = listbox.clone();
+                            let current_kernel = current_kernel.clone();
+                            sessions::confirm_disruptive_action(&parent.clone(), &action, move || {
+                                Self::remove_kernel_with_progress(&parent, &kernel, &kernels_ref, &listbox, &current_kernel);
+                            });
+                        });
                     }
                     Err(e) => {
-                        let error_dialog = MessageDialog::builder()
-                            .transient_for(&parent_clone)
-                            .modal(true)
-                            .message_type(MessageType::Error)
-                            .buttons(ButtonsType::Ok)
-                            .text(&format!("❌ Could not get current kernel information:\n{}", e))
-                            .build();
-                        error_dialog.connect_response(|dlg, _| dlg.close());
-                        error_dialog.show();
+                        error::show_error_dialog(&parent_clone, "❌ Could not get current kernel information", &e, None);
                     }
                 }
             }
@@ -635,251 +574,89 @@ impl KernelWindow {
         listbox: &ListBox,
         current_kernel: &str
     ) {
-        
-        let app_window = if let Ok(app_win) = parent.clone().upcast::<gtk::Window>().downcast::<ApplicationWindow>() {
+        if let Err(e) = kernel_manager::ensure_removable(&kernel.package_name, current_kernel) {
+            error::show_error_dialog(parent, "❌ Kernel removal error", &e, None);
+            return;
+        }
+
+        let transaction = kernel_manager::removal_transaction(&[kernel.package_name.clone()]);
+        Self::run_kernel_transaction(parent, kernels_ref, listbox, transaction, "✅ Kernel removed successfully!\n\nKernel and all related packages were cleaned from the system.");
+    }
+
+    /// Confirms the planned changes, then runs `transaction` in a progress
+    /// window and reloads the kernel list when it succeeded
+    fn run_kernel_transaction(
+        parent: &gtk::Window,
+        kernels_ref: &Rc<RefCell<Vec<KernelInfo>>>,
+        listbox: &ListBox,
+        transaction: Transaction,
+        success_message: &'static str
+    ) {
+        let app_window = if let Ok(app_win) = parent.clone().downcast::<ApplicationWindow>() {
             app_win
         } else {
             ApplicationWindow::builder().build()
         };
-        
-        let progress_window = ProgressWindow::new(&app_window);
-        progress_window.show();
-        
-        let kernel_clone = kernel.clone();
-        let kernels_ref_clone = kernels_ref.clone();
-        let listbox_clone = listbox.clone();
+
+        let kernels_ref = kernels_ref.clone();
+        let listbox = listbox.clone();
         let parent_clone = parent.clone();
-        let current_kernel_clone = current_kernel.to_string();
-        
-        
-        glib::spawn_future_local(async move {
-            match Self::remove_kernel_with_progress_impl(progress_window, &kernel_clone, &current_kernel_clone).await {
-                Ok(_) => {
-                    //2025 mthakan
+        progress::confirm_transaction(parent, transaction, move |transaction| {
+            let progress_window = ProgressWindow::titled(&app_window, "Kernel Manager", "🐧 Kernel Manager - Process Progress");
+            progress_window.show();
+
+            let kernels_ref = kernels_ref.clone();
+            let listbox = listbox.clone();
+            let parent = parent_clone.clone();
+            glib::spawn_future_local(async move {
+                if progress_window.run_transaction(transaction, success_message).await.is_ok() {
                     kernel_manager::set_kernel_cache(vec![]);
-                    
-                    let success_dialog = MessageDialog::builder()
-                        .transient_for(&parent_clone)
-                        .modal(true)
-                        .message_type(MessageType::Info)
-                        .buttons(ButtonsType::Ok)
-                        .text("✅ Kernel removed successfully!")
-                        .secondary_text("Kernel and all related packages were cleaned from the system.")
-                        .build();
-                    success_dialog.connect_response(|dlg, _| dlg.close());
-                    success_dialog.show();
-                    
-                    
-                    if let Some(parent_dialog) = parent_clone.downcast_ref::<Dialog>() {
-                        let current_label = Label::new(Some(""));  
-                        Self::refresh_kernels_with_progress(&parent_dialog, &kernels_ref_clone, &listbox_clone, &current_label);
+                    if let Some(parent_dialog) = parent.downcast_ref::<Dialog>() {
+                        let current_label = Label::new(Some(""));  // 2025 mthakan
+                        Self::refresh_kernels_with_progress(&parent_dialog, &kernels_ref, &listbox, &current_label);
                     }
                 }
-                Err(e) => {
-                    let error_dialog = MessageDialog::builder()
-                        .transient_for(&parent_clone)
-                        .modal(true)
-                        .message_type(MessageType::Error)
-                        .buttons(ButtonsType::Ok)
-                        .text(&format!("❌ Kernel removal error:\n{}", e))
-                        .build();
-                    error_dialog.connect_response(|dlg, _| dlg.close());
-                    error_dialog.show();
-                }
-            }
+            });
         });
     }
 
-    async fn remove_kernel_with_progress_impl(
-        progress_window: ProgressWindow,
-        kernel: &KernelInfo,
-        current_kernel: &str
-    ) -> Result<(), anyhow::Error> {
-        use std::sync::{Arc, Mutex, mpsc};
-        use std::thread;
-        use std::time::Duration;
-        use std::process::{Command, Stdio};
-        use std::io::{BufRead, BufReader};
-        use crate::progress::ProgressMessage;
-
-        let (tx, rx) = mpsc::channel::<ProgressMessage>();
-
-        let progress_bar = progress_window.progress_bar.clone();
-        let status_label = progress_window.status_label.clone();
-        let log_buffer = progress_window.log_buffer.clone();
-        let log_view = progress_window.log_view.clone();
-        let window = progress_window.window.clone();
-
-        let rx = Arc::new(Mutex::new(rx));
-        let rx_clone = rx.clone();
-
-        let kernel_clone = kernel.clone();
-        let current_kernel_clone = current_kernel.to_string();
-
-        
-        thread::spawn(move || {
-            let _ = tx.send(ProgressMessage::Status("Kernel is being removed...".to_string()));
-            let _ = tx.send(ProgressMessage::Progress(0.1, "10%".to_string()));
-            let _ = tx.send(ProgressMessage::Log(format!("Removing kernel {}...", kernel_clone.version)));
-
-            
-            match Command::new("pkexec")
-                .args(&["apt", "remove", "--purge", "-y", &kernel_clone.package_name])
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-            {
-                Ok(mut child) => {
-                    if let Some(stdout) = child.stdout.take() {
-                        let reader = BufReader::new(stdout);
-                        for line in reader.lines() {
-                            if let Ok(line) = line {
-                                let _ = tx.send(ProgressMessage::Log(line));
-                            }
-                        }
-                    }
-
-                    match child.wait() {
-                        Ok(status) => {
-                            if status.success() {
-                                let _ = tx.send(ProgressMessage::Progress(0.6, "60%".to_string()));
-                                let _ = tx.send(ProgressMessage::Status("Orphaned packages are being cleaned...".to_string()));
-                                let _ = tx.send(ProgressMessage::Log("Cleaning up orphaned packages with autoremove...".to_string()));
-                                
-                                
-                                match Command::new("pkexec")
-                                    .args(&["apt", "autoremove", "-y"])
-                                    .stdout(Stdio::piped())
-                                    .stderr(Stdio::piped())
-                                    .spawn()
-                                {
-                                    Ok(mut autoremove_child) => {
-                                        if let Some(stdout) = autoremove_child.stdout.take() {
-                                            let reader = BufReader::new(stdout);
-                                            for line in reader.lines() {
-                                                if let Ok(line) = line {
-                                                    let _ = tx.send(ProgressMessage::Log(line));
-                                                }
-                                            }
-                                        }
-                                        
-                                        match autoremove_child.wait() {
-                                            Ok(autoremove_status) => {
-                                                if autoremove_status.success() {
-                                                    let _ = tx.send(ProgressMessage::Progress(1.0, "100%".to_string()));
-                                                    let _ = tx.send(ProgressMessage::Status("✅ Kernel removed successfully!".to_string()));
-                                                    let _ = tx.send(ProgressMessage::Log("Kernel and related packages completely removed.".to_string()));
-                                                    let _ = tx.send(ProgressMessage::Success);
-                                                } else {
-                                                    let _ = tx.send(ProgressMessage::Error("Autoremove operation failed".to_string()));
-                                                }
-                                            }
-                                            Err(e) => {
-                                                let _ = tx.send(ProgressMessage::Error(format!("Autoremove error: {}", e)));
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        let _ = tx.send(ProgressMessage::Error(format!("Autoremove startup error: {}", e)));
-                                    }
-                                }
-                            } else {
-                                let _ = tx.send(ProgressMessage::Error("Kernel removal failed".to_string()));
-                            }
-                        }
-                        Err(e) => {
-                            let _ = tx.send(ProgressMessage::Error(format!("Command error: {}", e)));
-                        }
-                    }
-                }
-                Err(e) => {
-                    let _ = tx.send(ProgressMessage::Error(format!("Uninstall initialization error: {}", e)));
-                }
+    /// Looks for a boot setup `operation` could leave unbootable, such as
+    /// an unmounted or full /boot, and asks before going on
+    fn confirm_boot_layout<F: Fn() + 'static>(parent: &gtk::Window, operation: KernelOperation, proceed: F) {
+        let parent = parent.clone();
+        glib::spawn_future_local(async move {
+            let warnings = gio::spawn_blocking(move || {
+                let current = kernel_manager::get_current_kernel()
+                    .map(|version| kernel_manager::kernel_boot_bytes(&version))
+                    .unwrap_or(0);
+                boot_layout::detect().warnings(operation, boot_layout::needed_for_install(current))
+            }).await.unwrap_or_default();
+            if warnings.is_empty() {
+                proceed();
+                return;
             }
-        });
-
-        let is_complete = Arc::new(Mutex::new(false));
-        let has_error = Arc::new(Mutex::new(false));
-        let is_complete_clone = is_complete.clone();
-        let has_error_clone = has_error.clone();
 
-        
-        glib::timeout_add_local(Duration::from_millis(100), move || {
-            let mut messages_to_process = Vec::new();
-            
-            if let Ok(rx_guard) = rx_clone.try_lock() {
-                while let Ok(msg) = rx_guard.try_recv() {
-                    messages_to_process.push(msg);
+            let details: Vec<String> = warnings.iter().map(|warning| format!("• {}", warning)).collect();
+            let dialog = MessageDialog::builder()
+                .transient_for(&parent)
+                .modal(true)
+                .message_type(MessageType::Warning)
+                .buttons(ButtonsType::None)
+                .text("⚠️ Check the Boot Setup First")
+                .secondary_text(format!("{}\n\nThe system might not start again if you go on.", details.join("\n\n")))
+                .build();
+            dialog.add_button("Cancel", ResponseType::Cancel);
+            let continue_btn = dialog.add_button("Continue Anyway", ResponseType::Accept);
+            continue_btn.add_css_class("destructive-action");
+            dialog.set_default_response(ResponseType::Cancel);
+            dialog.connect_response(move |dlg, response| {
+                dlg.close();
+                if response == ResponseType::Accept {
+                    proceed();
                 }
-            }
-
-            for msg in messages_to_process {
-                match msg {
-                    ProgressMessage::Status(status) => {
-                        status_label.set_markup(&format!("<b>{}</b>", status));
-                    }
-                    ProgressMessage::Progress(fraction, text) => {
-                        progress_bar.set_fraction(fraction);
-                        progress_bar.set_text(Some(&text));
-                    }
-                    ProgressMessage::Log(log) => {
-                        let mut end_iter = log_buffer.end_iter();
-                        log_buffer.insert(&mut end_iter, &format!("{}\n", log));
-                        
-                        let mark = log_buffer.create_mark(None, &end_iter, false);
-                        log_view.scroll_mark_onscreen(&mark);
-                    }
-                    ProgressMessage::Error(error) => {
-                        status_label.set_markup(&format!("<b><span color='red'>❌ Error: {}</span></b>", error));
-                        
-                        if let Ok(mut error_guard) = has_error_clone.try_lock() {
-                            *error_guard = true;
-                        }
-                        if let Ok(mut complete_guard) = is_complete_clone.try_lock() {
-                            *complete_guard = true;
-                        }
-                        
-                        return glib::ControlFlow::Break;
-                    }
-                    ProgressMessage::Success => {
-                        
-                        glib::timeout_add_seconds_local(2, {
-                            let window = window.clone();
-                            move || {
-                                window.close();
-                                glib::ControlFlow::Break
-                            }
-                        });
-                        
-                        if let Ok(mut complete_guard) = is_complete_clone.try_lock() {
-                            *complete_guard = true;
-                        }
-                        
-                        return glib::ControlFlow::Break;
-                    }
-                    _ => {}
-                }
-            }
-            
-            glib::ControlFlow::Continue
+            });
+            dialog.show();
         });
-
-        
-        loop {
-            glib::MainContext::default().iteration(false);
-            thread::sleep(Duration::from_millis(50));
-            
-            if let Ok(complete_guard) = is_complete.try_lock() {
-                if *complete_guard {
-                    if let Ok(error_guard) = has_error.try_lock() {
-                        if *error_guard {
-                            return Err(anyhow::anyhow!("An error occurred during kernel removal."));
-                        }
-                    }
-                    return Ok(());
-                }
-            }
-        }
     }
 
     fn set_default_kernel_action(
@@ -921,15 +698,7 @@ impl KernelWindow {
                         success_dialog.show();
                     }
                     Err(e) => {
-                        let error_dialog = MessageDialog::builder()
-                            .transient_for(&parent_clone)
-                            .modal(true)
-                            .message_type(MessageType::Error)
-                            .buttons(ButtonsType::Ok)
-                            .text(&format!("❌ Default kernel configuration error:\n{}", e))
-                            .build();
-                        error_dialog.connect_response(|dlg, _| dlg.close());
-                        error_dialog.show();
+                        error::show_error_dialog(&parent_clone, "❌ Default kernel configuration error", &e, None);
                     }
                 }
             }
@@ -1049,7 +818,7 @@ impl KernelWindow {
         }
 
         
-        let size_label = Label::new(Some(&kernel.size));
+        let size_label = Label::new(Some(&format::optional_size(kernel.size)));
         size_label.set_width_chars(12);
         size_label.set_halign(gtk::Align::Center);
         size_label.add_css_class("size-info");
@@ -1157,28 +926,12 @@ impl KernelWindow {
                                 }
                             }
                             Err(e) => {
-                                let error_dialog = MessageDialog::builder()
-                                    .transient_for(&parent_clone)
-                                    .modal(true)
-                                    .message_type(MessageType::Error)
-                                    .buttons(ButtonsType::Ok)
-                                    .text(&format!("❌ Kernel removal error:\n{}", e))
-                                    .build();
-                                error_dialog.connect_response(|dlg, _| dlg.close());
-                                error_dialog.show();
+                                error::show_error_dialog(&parent_clone, "❌ Kernel removal error", &e, None);
                             }
                         }
                     }
                     Err(e) => {
-                        let error_dialog = MessageDialog::builder()
-                            .transient_for(&parent_clone)
-                            .modal(true)
-                            .message_type(MessageType::Error)
-                            .buttons(ButtonsType::Ok)
-                            .text(&format!("❌ Could not get current kernel information:\n{}", e))
-                            .build();
-                        error_dialog.connect_response(|dlg, _| dlg.close());
-                        error_dialog.show();
+                        error::show_error_dialog(&parent_clone, "❌ Could not get current kernel information", &e, None);
                     }
                 }
             }
@@ -1194,211 +947,184 @@ impl KernelWindow {
         kernels_ref: &Rc<RefCell<Vec<KernelInfo>>>,
         listbox: &ListBox
     ) {
+        let transaction = Transaction::install(&[kernel.package_name.clone()])
+            .journal(OperationKind::KernelInstall);
+        Self::run_kernel_transaction(parent, kernels_ref, listbox, transaction, "✅ Kernel successfully installed!");
+    }
+}
 
-        let app_window = if let Ok(app_win) = parent.clone().upcast::<gtk::Window>().downcast::<ApplicationWindow>() {
-            app_win
-        } else {
-            ApplicationWindow::builder().build()
-        };
-        
-        let progress_window = ProgressWindow::new(&app_window);
-        progress_window.show();
-        
-        let kernel_clone = kernel.clone();
-        let kernels_ref_clone = kernels_ref.clone();
-        let listbox_clone = listbox.clone();
-        let parent_clone = parent.clone();
-        
+/// Installed kernel count and disk usage, shown below the kernel actions
+#[derive(Clone)]
+struct KernelStatsPane {
+    frame: gtk::Frame,
+    installed_label: Label,
+    boot_label: Label,
+    modules_label: Label,
+    reclaimable_label: Label,
+}
+
+impl KernelStatsPane {
+    fn new(platform: &BootPlatform) -> Self {
+        let frame = gtk::Frame::new(Some("📊 Overview"));
+        let stats_box = GtkBox::new(Orientation::Vertical, 6);
+        stats_box.set_margin_top(8);
+        stats_box.set_margin_bottom(8);
+        stats_box.set_margin_start(8);
+        stats_box.set_margin_end(8);
+
+        let platform_label = Label::new(None);
+        platform_label.set_markup(&format!("<b>Boots with:</b> {}", platform.name()));
+        if !platform.uses_grub() {
+            platform_label.set_tooltip_text(Some(boot_platform::SET_DEFAULT_UNSUPPORTED));
+        }
+        let installed_label = Label::new(Some("Calculating..."));
+        let boot_label = Label::new(None);
+        let modules_label = Label::new(None);
+        let reclaimable_label = Label::new(None);
+        reclaimable_label.set_wrap(true);
+        for label in [&platform_label, &installed_label, &boot_label, &modules_label, &reclaimable_label] {
+            label.set_halign(gtk::Align::Start);
+            stats_box.append(label);
+        }
+        frame.set_child(Some(&stats_box));
 
+        Self { frame, installed_label, boot_label, modules_label, reclaimable_label }
+    }
+
+    /// Measures in the background since walking /lib/modules takes a moment
+    fn refresh(&self, kernels: Vec<KernelInfo>) {
+        let pane = self.clone();
         glib::spawn_future_local(async move {
-            match Self::install_kernel_with_progress_impl(progress_window, &kernel_clone).await {
-                Ok(_) => {
-                    kernel_manager::set_kernel_cache(vec![]);
-                    
-                    let success_dialog = MessageDialog::builder()
-                        .transient_for(&parent_clone)
-                        .modal(true)
-                        .message_type(MessageType::Info)
-                        .buttons(ButtonsType::Ok)
-                        .text("✅ Kernel successfully installed!")
-                        .build();
-                    success_dialog.connect_response(|dlg, _| dlg.close());
-                    success_dialog.show();
-                    
-                    if let Some(parent_dialog) = parent_clone.downcast_ref::<Dialog>() {
-                        let current_label = Label::new(Some(""));  // Dummy label
-                        Self::refresh_kernels_with_progress(&parent_dialog, &kernels_ref_clone, &listbox_clone, &current_label);
-                    }
-                }
-                Err(e) => {
-                    let error_dialog = MessageDialog::builder()
-                        .transient_for(&parent_clone)
-                        .modal(true)
-                        .message_type(MessageType::Error)
-                        .buttons(ButtonsType::Ok)
-                        .text(&format!("❌ Kernel installation error:\n{}", e))
-                        .build();
-                    error_dialog.connect_response(|dlg, _| dlg.close());
-                    error_dialog.show();
-                }
+            if let Ok(usage) = gio::spawn_blocking(move || kernel_manager::kernel_disk_usage(&kernels)).await {
+                pane.set_usage(&usage);
             }
         });
     }
 
-    async fn install_kernel_with_progress_impl(
-        progress_window: ProgressWindow,
-        kernel: &KernelInfo
-    ) -> Result<(), anyhow::Error> {
-        use std::sync::{Arc, Mutex, mpsc};
-        use std::thread;
-        use std::time::Duration;
-        use std::process::{Command, Stdio};
-        use std::io::{BufRead, BufReader};
-        use crate::progress::ProgressMessage;
-
-        let (tx, rx) = mpsc::channel::<ProgressMessage>();
-
-        let progress_bar = progress_window.progress_bar.clone();
-        let status_label = progress_window.status_label.clone();
-        let log_buffer = progress_window.log_buffer.clone();
-        let log_view = progress_window.log_view.clone();
-        let window = progress_window.window.clone();
-
-        let rx = Arc::new(Mutex::new(rx));
-        let rx_clone = rx.clone();
+    fn set_usage(&self, usage: &KernelDiskUsage) {
+        self.installed_label.set_markup(&format!("<b>Installed kernels:</b> {}", usage.installed));
+        self.boot_label.set_markup(&format!("<b>/boot:</b> {}", format::size(usage.boot_bytes)));
+        self.modules_label.set_markup(&format!("<b>/lib/modules:</b> {}", format::size(usage.modules_bytes)));
+        if usage.reclaimable_bytes == 0 {
+            self.reclaimable_label.set_markup("<b>Reclaimable by cleanup:</b> nothing, only the running and newest kernels are installed");
+        } else {
+            self.reclaimable_label.set_markup(&format!(
+                "<b>Reclaimable by cleanup:</b> <span color='#e65100'>{}</span>",
+                format::size(usage.reclaimable_bytes)
+            ));
+        }
+    }
+}
 
-        let kernel_clone = kernel.clone();
+/// Module directories of kernels whose packages are gone, with a button to
+/// delete each. Hidden while there are none.
+#[derive(Clone)]
+struct OrphanModulesPane {
+    frame: gtk::Frame,
+    listbox: ListBox,
+    parent: Dialog,
+}
 
-        
-        thread::spawn(move || {
-            let _ = tx.send(ProgressMessage::Status("Kernel is being installed...".to_string()));
-            let _ = tx.send(ProgressMessage::Progress(0.1, "10%".to_string()));
-            let _ = tx.send(ProgressMessage::Log(format!("Kernel {} is being installed...", kernel_clone.version)));
-//2025 mthakan
-            match Command::new("pkexec")
-                .args(&["apt", "install", "-y", &kernel_clone.package_name])
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-            {
-                Ok(mut child) => {
-                    if let Some(stdout) = child.stdout.take() {
-                        let reader = BufReader::new(stdout);
-                        let mut progress = 0.3;
-                        
-                        for line in reader.lines() {
-                            if let Ok(line) = line {
-                                let _ = tx.send(ProgressMessage::Log(line.clone()));
-                                
-                                if line.contains("Unpacking") || line.contains("Setting up") || line.contains("Processing") {
-                                    progress += 0.1;
-                                    if progress > 0.9 { progress = 0.9; }
-                                    let percent = (progress * 100.0) as i32;
-                                    let _ = tx.send(ProgressMessage::Progress(progress, format!("{}%", percent)));
-                                }
-                            }
-                        }
-                    }
+impl OrphanModulesPane {
+    fn new(parent: &Dialog) -> Self {
+        let frame = gtk::Frame::new(Some("🧹 Leftover Module Directories"));
+        frame.set_visible(false);
+
+        let vbox = GtkBox::new(Orientation::Vertical, 6);
+        vbox.set_margin_top(8);
+        vbox.set_margin_bottom(8);
+        vbox.set_margin_start(8);
+        vbox.set_margin_end(8);
+
+        let hint = Label::new(Some("These kernels were removed but their /lib/modules directories were left behind."));
+        hint.set_wrap(true);
+        hint.set_halign(gtk::Align::Start);
+        hint.add_css_class("dim-label");
+        vbox.append(&hint);
+
+        let listbox = ListBox::new();
+        listbox.set_selection_mode(gtk::SelectionMode::None);
+        vbox.append(&listbox);
+        frame.set_child(Some(&vbox));
+
+        Self { frame, listbox, parent: parent.clone() }
+    }
 
-                    match child.wait() {
-                        Ok(status) => {
-                            if status.success() {
-                                let _ = tx.send(ProgressMessage::Progress(1.0, "100%".to_string()));
-                                let _ = tx.send(ProgressMessage::Status("✅ Kernel successfully loaded!".to_string()));
-                                let _ = tx.send(ProgressMessage::Success);
-                            } else {
-                                let _ = tx.send(ProgressMessage::Error("Kernel installation failed".to_string()));
-                            }
-                        }
-                        Err(e) => {
-                            let _ = tx.send(ProgressMessage::Error(format!("Command error: {}", e)));
-                        }
-                    }
-                }
-                Err(e) => {
-                    let _ = tx.send(ProgressMessage::Error(format!("Installation initialization error: {}", e)));
-                }
+    fn refresh(&self) {
+        let pane = self.clone();
+        glib::spawn_future_local(async move {
+            if let Ok(orphans) = gio::spawn_blocking(kernel_manager::find_orphan_module_dirs).await {
+                pane.populate(orphans);
             }
         });
+    }
 
-        let is_complete = Arc::new(Mutex::new(false));
-        let has_error = Arc::new(Mutex::new(false));
-        let is_complete_clone = is_complete.clone();
-        let has_error_clone = has_error.clone();
+    fn populate(&self, orphans: Vec<OrphanModules>) {
+        while let Some(child) = self.listbox.first_child() {
+            self.listbox.remove(&child);
+        }
+        self.frame.set_visible(!orphans.is_empty());
 
-        glib::timeout_add_local(Duration::from_millis(100), move || {
-            let mut messages_to_process = Vec::new();
-            
-            if let Ok(rx_guard) = rx_clone.try_lock() {
-                while let Ok(msg) = rx_guard.try_recv() {
-                    messages_to_process.push(msg);
-                }
-            }
+        for orphan in orphans {
+            let hbox = GtkBox::new(Orientation::Horizontal, 8);
+            hbox.set_margin_top(4);
+            hbox.set_margin_bottom(4);
+
+            let label = Label::new(None);
+            label.set_markup(&format!(
+                "<b>{}</b>  <span color='#666'>{}</span>",
+                glib::markup_escape_text(&orphan.version),
+                format::size(orphan.size)
+            ));
+            label.set_halign(gtk::Align::Start);
+            label.set_hexpand(true);
+            hbox.append(&label);
+
+            let delete_btn = Button::with_label("🗑️ Delete");
+            delete_btn.add_css_class("destructive-action");
+            let pane = self.clone();
+            delete_btn.connect_clicked(move |_| pane.confirm_delete(&orphan));
+            hbox.append(&delete_btn);
 
-            for msg in messages_to_process {
-                match msg {
-                    ProgressMessage::Status(status) => {
-                        status_label.set_markup(&format!("<b>{}</b>", status));
-                    }
-                    ProgressMessage::Progress(fraction, text) => {
-                        progress_bar.set_fraction(fraction);
-                        progress_bar.set_text(Some(&text));
-                    }
-                    ProgressMessage::Log(log) => {
-                        let mut end_iter = log_buffer.end_iter();
-                        log_buffer.insert(&mut end_iter, &format!("{}\n", log));
-                        
-                        let mark = log_buffer.create_mark(None, &end_iter, false);
-                        log_view.scroll_mark_onscreen(&mark);
-                    }
-                    ProgressMessage::Error(error) => {
-                        status_label.set_markup(&format!("<b><span color='red'>❌ Error: {}</span></b>", error));
-                        
-                        if let Ok(mut error_guard) = has_error_clone.try_lock() {
-                            *error_guard = true;
-                        }
-                        if let Ok(mut complete_guard) = is_complete_clone.try_lock() {
-                            *complete_guard = true;
-                        }
-                        
-                        return glib::ControlFlow::Break;
-                    }
-                    ProgressMessage::Success => {
-                        glib::timeout_add_seconds_local(2, {
-                            let window = window.clone();
-                            move || {
-                                window.close();
-                                glib::ControlFlow::Break
-                            }
-                        });
-                        
-                        if let Ok(mut complete_guard) = is_complete_clone.try_lock() {
-                            *complete_guard = true;
-                        }
-                        
-                        return glib::ControlFlow::Break;
-                    }
-                    _ => {}
-                }
-            }
-            
-            glib::ControlFlow::Continue
-        });
+            let row = ListBoxRow::new();
+            row.set_child(Some(&hbox));
+            self.listbox.append(&row);
+        }
+    }
 
+    fn confirm_delete(&self, orphan: &OrphanModules) {
+        let dialog = MessageDialog::builder()
+            .transient_for(&self.parent)
+            .modal(true)
+            .message_type(MessageType::Question)
+            .buttons(ButtonsType::YesNo)
+            .text("🧹 Delete Leftover Modules")
+            .secondary_text(&format!(
+                "Delete {} ({})?\n\nNo installed package owns this directory and it does not belong to the running kernel.",
+                orphan.path.display(),
+                format::size(orphan.size)
+            ))
+            .build();
 
-        loop {
-            glib::MainContext::default().iteration(false);
-            thread::sleep(Duration::from_millis(50));
-            if let Ok(complete_guard) = is_complete.try_lock() {
-                if *complete_guard {
-                    if let Ok(error_guard) = has_error.try_lock() {
-                        if *error_guard {
-                            return Err(anyhow::anyhow!("An error occurred while loading the kernel"));
-                        }
-                    }
-                    return Ok(());
-                }
+        let pane = self.clone();
+        let orphan = orphan.clone();
+        dialog.connect_response(move |dlg, response| {
+            dlg.close();
+            if response != ResponseType::Yes {
+                return;
             }
-        }
+            let pane = pane.clone();
+            let orphan = orphan.clone();
+            glib::spawn_future_local(async move {
+                let target = orphan.clone();
+                match gio::spawn_blocking(move || kernel_manager::remove_orphan_module_dir(&target)).await {
+                    Ok(Ok(())) => pane.refresh(),
+                    Ok(Err(e)) => error::show_error_dialog(&pane.parent, "❌ Could not delete the module directory", &e, None),
+                    Err(_) => {}
+                }
+            });
+        });
+        dialog.show();
     }
 }
+
@@ -0,0 +1,120 @@
+// src/kev.rs
+
+use crate::changelog;
+use crate::error::{Error, Result};
+use crate::model::PackageUpdate;
+use crate::paths;
+use crate::util::{self, json_string_fields};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// CISA's catalog of vulnerabilities known to be exploited in the wild
+pub const CATALOG_URL: &str = "https://www.cisa.gov/sites/default/files/feeds/known_exploited_vulnerabilities.json";
+
+/// The catalog changes a few times a week, so one download a day is plenty
+const CATALOG_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn cache_file() -> Option<PathBuf> {
+    paths::cache_file("known_exploited_vulnerabilities.json")
+}
+
+/// CVE ids listed in the catalog JSON
+pub fn parse_catalog(json: &str) -> Result<HashSet<String>> {
+    // A login or error page arrives with a success status, too
+    if !json.contains("\"vulnerabilities\"") {
+        return Err(Error::Parse("The exploited vulnerabilities catalog is not valid".to_string()));
+    }
+    Ok(json_string_fields(json, "cveID").into_iter().map(|id| id.to_uppercase()).collect())
+}
+
+/// CVE ids mentioned in `text`, such as the entries of a changelog, sorted
+pub fn cve_ids(text: &str) -> Vec<String> {
+    let upper = text.to_uppercase();
+    let mut ids = Vec::new();
+    for (start, _) in upper.match_indices("CVE-") {
+        let rest = &upper[start + 4..];
+        let year: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if year.len() != 4 || !rest[4..].starts_with('-') {
+            continue;
+        }
+        let number: String = rest[5..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        if number.len() >= 4 {
+            ids.push(format!("CVE-{}-{}", year, number));
+        }
+    }
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// The CVEs a pending update fixes, read from its changelog entries newer
+/// than `installed_version`
+pub fn fixed_cves(changelog_text: &str, installed_version: &str) -> Vec<String> {
+    cve_ids(&changelog::entries_since(changelog_text, installed_version))
+}
+
+/// The CVEs in `text` that are known to be exploited
+pub fn exploited_cves(text: &str, catalog: &HashSet<String>) -> Vec<String> {
+    cve_ids(text).into_iter().filter(|id| catalog.contains(id)).collect()
+}
+
+/// The catalog, downloaded again once the cached copy is a day old.
+/// Offline, a stale copy is still better than none.
+pub fn load_catalog() -> Result<HashSet<String>> {
+    let cache = cache_file();
+    let cached = cache.as_ref().and_then(|path| fs::read_to_string(path).ok());
+    let fresh = cache.as_ref()
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|meta| meta.modified().ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map_or(false, |age| age < CATALOG_MAX_AGE);
+
+    if let (true, Some(json)) = (fresh, &cached) {
+        return parse_catalog(json);
+    }
+
+    let downloaded = util::fetch_url(CATALOG_URL, 30)
+        .map(|json| String::from_utf8_lossy(&json).to_string())
+        .and_then(|json| parse_catalog(&json).map(|ids| (json, ids)));
+    match downloaded {
+        Ok((json, ids)) => {
+            if let Some(path) = &cache {
+                if let Some(dir) = path.parent() {
+                    let _ = paths::ensure_dir(dir);
+                }
+                if let Err(e) = fs::write(path, &json) {
+                    eprintln!("Could not cache the exploited vulnerabilities catalog: {}", e);
+                }
+            }
+            Ok(ids)
+        }
+        Err(e) => match cached {
+            Some(json) => parse_catalog(&json),
+            None => Err(e),
+        },
+    }
+}
+
+/// Pending security update → the exploited CVEs it fixes, read from the
+/// changelog entries newer than the installed version. Blocks while
+/// changelogs are downloaded.
+pub fn exploited_by_package(packages: &[PackageUpdate]) -> Result<HashMap<String, Vec<String>>> {
+    let catalog = load_catalog()?;
+    let mut exploited = HashMap::new();
+    for pkg in packages.iter().filter(|pkg| pkg.from_security) {
+        let text = match changelog::fetch(&pkg.name, &pkg.new_version) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Changelog of {} could not be read: {}", pkg.name, e);
+                continue;
+            }
+        };
+        let cves = exploited_cves(&changelog::entries_since(&text, &pkg.current_version), &catalog);
+        if !cves.is_empty() {
+            exploited.insert(pkg.name.clone(), cves);
+        }
+    }
+    Ok(exploited)
+}
@@ -1,13 +1,88 @@
 // src/lib.rs
 
+pub mod anomalies;
+pub mod app_state;
 pub mod apt;
+pub mod conffile;
+pub mod error;
+pub mod flatpak_manager;
+pub mod format;
+pub mod gpu_power;
+pub mod hardware_profile;
+pub mod icons;
+#[cfg(feature = "gui")]
+pub mod log_filter;
 pub mod model;
 pub mod policy;
+pub mod privilege;
+#[cfg(feature = "gui")]
 pub mod progress;
 pub mod repo_manager;
+pub mod repo_status;
+#[cfg(feature = "gui")]
 pub mod repo_window;
+pub mod sessions;
 pub mod kernel_manager;
+#[cfg(feature = "gui")]
 pub mod kernel_window;
 pub mod driver_manager;
+#[cfg(feature = "gui")]
 pub mod driver_window;
+#[cfg(feature = "gui")]
 pub mod driver_progress;
+pub mod package_search;
+#[cfg(feature = "gui")]
+pub mod package_search_window;
+pub mod packagekit;
+pub mod paths;
+pub mod obsolete_manager;
+pub mod offline_update;
+#[cfg(feature = "gui")]
+pub mod obsolete_window;
+pub mod boot_layout;
+pub mod boot_platform;
+pub mod bundle;
+#[cfg(feature = "gui")]
+pub mod bundle_window;
+pub mod cli;
+pub mod changelog;
+#[cfg(feature = "gui")]
+pub mod changelog_window;
+pub mod download_plan;
+#[cfg(feature = "gui")]
+pub mod download_window;
+pub mod helper_protocol;
+pub mod history;
+#[cfg(feature = "gui")]
+pub mod history_window;
+pub mod install_queue;
+#[cfg(feature = "gui")]
+pub mod jobs;
+pub mod journal;
+pub mod kev;
+pub mod metadata;
+pub mod motd;
+#[cfg(feature = "gui")]
+pub mod motd_window;
+pub mod security_news;
+#[cfg(feature = "gui")]
+pub mod security_news_window;
+pub mod config;
+pub mod digest;
+#[cfg(feature = "gui")]
+pub mod preferences_window;
+pub mod notifications;
+pub mod install;
+#[cfg(feature = "gui")]
+pub mod resources;
+pub mod self_update;
+pub mod services;
+pub mod snap_manager;
+pub mod sysroot;
+#[cfg(feature = "gui")]
+pub mod main_window;
+pub mod update_age;
+pub mod update_list;
+pub mod util;
+pub mod xorg_config;
+pub mod zfs;
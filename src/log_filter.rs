@@ -0,0 +1,156 @@
+// src/log_filter.rs
+
+use gtk::prelude::*;
+use gtk::{Box as GtkBox, CheckButton, Label, Orientation, SearchEntry, TextBuffer, TextTag};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+const MATCH_TAG: &str = "log-match";
+const HIDDEN_TAG: &str = "log-hidden";
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Case-insensitive, non-overlapping matches of `query` in `line` as
+/// character offsets
+pub fn match_ranges(line: &str, query: &str) -> Vec<(usize, usize)> {
+    let needle: Vec<char> = query.chars().map(lower).collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let haystack: Vec<char> = line.chars().map(lower).collect();
+
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i + needle.len() <= haystack.len() {
+        if haystack[i..i + needle.len()] == needle[..] {
+            ranges.push((i, i + needle.len()));
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    ranges
+}
+
+struct FilterState {
+    query: RefCell<String>,
+    only_matching: Cell<bool>,
+    /// Lines before this one are already tagged
+    processed: Cell<i32>,
+    matches: Cell<usize>,
+}
+
+fn apply_lines(buffer: &TextBuffer, state: &FilterState, from: i32) {
+    let query = state.query.borrow();
+    let last_line = buffer.line_count() - 1;
+
+    for line_no in from..last_line {
+        let (start, end) = match (buffer.iter_at_line(line_no), buffer.iter_at_line(line_no + 1)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => continue,
+        };
+        let text = buffer.text(&start, &end, true);
+        let ranges = match_ranges(&text, &query);
+
+        for (from, to) in &ranges {
+            let mut match_start = start;
+            match_start.forward_chars(*from as i32);
+            let mut match_end = start;
+            match_end.forward_chars(*to as i32);
+            buffer.apply_tag_by_name(MATCH_TAG, &match_start, &match_end);
+        }
+        if !ranges.is_empty() {
+            state.matches.set(state.matches.get() + 1);
+        } else if state.only_matching.get() && !query.is_empty() {
+            buffer.apply_tag_by_name(HIDDEN_TAG, &start, &end);
+        }
+    }
+
+    state.processed.set(last_line.max(0));
+}
+
+fn reapply(buffer: &TextBuffer, state: &FilterState) {
+    let (start, end) = buffer.bounds();
+    buffer.remove_tag_by_name(MATCH_TAG, &start, &end);
+    buffer.remove_tag_by_name(HIDDEN_TAG, &start, &end);
+    state.matches.set(0);
+    apply_lines(buffer, state, 0);
+}
+
+/// Search bar for a log view. Matching text is highlighted while the log is
+/// being written; "Only matching lines" hides everything else.
+pub fn filter_bar(buffer: &TextBuffer) -> GtkBox {
+    let match_tag = TextTag::builder()
+        .name(MATCH_TAG)
+        .background("#f9e79f")
+        .foreground("#1c1c1c")
+        .weight(700)
+        .build();
+    let hidden_tag = TextTag::builder()
+        .name(HIDDEN_TAG)
+        .invisible(true)
+        .build();
+    buffer.tag_table().add(&match_tag);
+    buffer.tag_table().add(&hidden_tag);
+
+    let hbox = GtkBox::new(Orientation::Horizontal, 8);
+
+    let entry = SearchEntry::new();
+    entry.set_hexpand(true);
+    entry.set_placeholder_text(Some("Filter log, e.g. a package name"));
+
+    let only_matching = CheckButton::with_label("Only matching lines");
+    let count_label = Label::new(None);
+    count_label.add_css_class("dim-label");
+
+    hbox.append(&entry);
+    hbox.append(&only_matching);
+    hbox.append(&count_label);
+
+    let state = Rc::new(FilterState {
+        query: RefCell::new(String::new()),
+        only_matching: Cell::new(false),
+        processed: Cell::new(0),
+        matches: Cell::new(0),
+    });
+
+    let update_count = {
+        let state = state.clone();
+        let count_label = count_label.clone();
+        move || {
+            if state.query.borrow().is_empty() {
+                count_label.set_text("");
+            } else {
+                count_label.set_text(&format!("{} lines", state.matches.get()));
+            }
+        }
+    };
+    let update_count = Rc::new(update_count);
+
+    let state_changed = state.clone();
+    let count_changed = update_count.clone();
+    buffer.connect_changed(move |buffer| {
+        apply_lines(buffer, &state_changed, state_changed.processed.get());
+        count_changed();
+    });
+
+    let state_entry = state.clone();
+    let buffer_entry = buffer.clone();
+    let count_entry = update_count.clone();
+    entry.connect_search_changed(move |entry| {
+        *state_entry.query.borrow_mut() = entry.text().to_string();
+        reapply(&buffer_entry, &state_entry);
+        count_entry();
+    });
+
+    let buffer_toggle = buffer.clone();
+    only_matching.connect_toggled(move |check| {
+        state.only_matching.set(check.is_active());
+        reapply(&buffer_toggle, &state);
+        update_count();
+    });
+
+    hbox
+}
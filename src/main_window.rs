@@ -0,0 +1,1447 @@
+// src/main_window.rs
+
+use crate::app_state::{AppState, BusyState, Operation};
+use crate::apt::{self, CancelToken, Transaction, TransactionEvent};
+use crate::changelog;
+use crate::changelog_window::ChangelogWindow;
+use crate::config::Config;
+use crate::digest;
+use crate::error;
+use crate::flatpak_manager;
+use crate::format;
+use crate::icons::{self, Icon};
+use crate::install_queue;
+use crate::jobs;
+use crate::journal::OperationKind;
+use crate::kernel_manager;
+use crate::kev;
+use crate::model::{self, PackageUpdate};
+use crate::notifications;
+use crate::offline_update::{self, OfflineStatus};
+use crate::policy::{self, ScheduleFrequency};
+use crate::privilege;
+use crate::progress::{self, ProgressWindow};
+use crate::services::{NotificationService, UpdateService, UpdateStatus};
+use crate::self_update;
+use crate::sessions;
+use crate::snap_manager;
+use crate::update_age;
+use crate::update_list::{self, SelectionSummary};
+use crate::resources;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{
+    glib, gio, Application, ApplicationWindow, Box as GtkBox, Button, CheckButton, ListBox,
+    ListBoxRow, MessageDialog, Orientation, ButtonsType, MessageType, Label,
+    ResponseType, Expander,
+};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+mod imp {
+    use super::*;
+
+    /// Widgets of data/resources/main_window.ui that the code fills in
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[cfg_attr(meaupdater_gresources, template(resource = "/io/github/mthakan/MeaUpdater/main_window.ui"))]
+    #[cfg_attr(not(meaupdater_gresources), template(file = "../data/resources/main_window.ui"))]
+    pub struct MainWindowWidget {
+        #[template_child]
+        pub title_label: TemplateChild<Label>,
+        #[template_child]
+        pub menu_button: TemplateChild<gtk::MenuButton>,
+        #[template_child]
+        pub offline_btn: TemplateChild<Button>,
+        #[template_child]
+        pub reboot_banner: TemplateChild<GtkBox>,
+        #[template_child]
+        pub reboot_label: TemplateChild<Label>,
+        #[template_child]
+        pub dismiss_btn: TemplateChild<Button>,
+        #[template_child]
+        pub jobs_bar: TemplateChild<GtkBox>,
+        #[template_child]
+        pub refresh_btn: TemplateChild<Button>,
+        #[template_child]
+        pub select_all_btn: TemplateChild<Button>,
+        #[template_child]
+        pub install_btn: TemplateChild<Button>,
+        #[template_child]
+        pub security_btn: TemplateChild<Button>,
+        #[template_child]
+        pub listbox: TemplateChild<ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MainWindowWidget {
+        const NAME: &'static str = "MeaUpdaterMainWindow";
+        type Type = super::MainWindowWidget;
+        type ParentType = ApplicationWindow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MainWindowWidget {}
+    impl WidgetImpl for MainWindowWidget {}
+    impl WindowImpl for MainWindowWidget {}
+    impl ApplicationWindowImpl for MainWindowWidget {}
+}
+
+glib::wrapper! {
+    /// The window built from the template; `MainWindow` holds it
+    pub struct MainWindowWidget(ObjectSubclass<imp::MainWindowWidget>)
+        @extends ApplicationWindow, gtk::Window, gtk::Widget,
+        @implements gio::ActionGroup, gio::ActionMap, gtk::Accessible, gtk::Buildable,
+            gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+/// The update list window. Other views are added to its menu with
+/// `add_pane` and talk to it through the methods below.
+#[derive(Clone)]
+pub struct MainWindow {
+    window: ApplicationWindow,
+    menu: gio::Menu,
+    listbox: ListBox,
+    refresh_btn: Button,
+    select_all_btn: Button,
+    install_btn: Button,
+    /// Selects exactly the security updates and installs them
+    security_btn: Button,
+    /// Reminder shown while a newer kernel waits for a reboot
+    reboot_banner: GtkBox,
+    reboot_label: Label,
+    /// Header bar indicator of updates staged for the next restart
+    offline_btn: Button,
+    state: Rc<AppState>,
+    busy: Rc<BusyState>,
+    /// Actions that are disabled while an operation is running
+    idle_actions: Rc<RefCell<Vec<gio::SimpleAction>>>,
+    periodic_check: Rc<RefCell<Option<glib::SourceId>>>,
+    updates: Rc<dyn UpdateService>,
+    notifier: Rc<dyn NotificationService>,
+}
+
+impl MainWindow {
+    pub fn new(app: &Application, state: Rc<AppState>, updates: Rc<dyn UpdateService>, notifier: Rc<dyn NotificationService>) -> Self {
+        let config = Config::load();
+        // The template may come from the resource bundle
+        resources::register();
+        let widget: MainWindowWidget = glib::Object::builder()
+            .property("application", app)
+            .property("default-width", config.window_width)
+            .property("default-height", config.window_height)
+            .build();
+        let ui = widget.imp();
+
+        if state.is_check_only() {
+            ui.title_label.set_label("📦 MeaUpdater — 👁️ Check Only");
+        }
+        if state.is_security_only() {
+            ui.title_label.set_tooltip_text(Some("🔒 Your administrator only allows security updates"));
+        }
+
+        let menu = gio::Menu::new();
+        menu.append(Some("Sort by Type"), Some("win.sort_by_type"));
+        menu.append(Some("Sort by Size"), Some("win.sort_by_size"));
+        // Staging runs a script, which the host helper does not run
+        if !state.is_check_only() && privilege::can_run_commands() {
+            menu.append(Some("Install Selected at Next Restart"), Some("win.stage_offline"));
+        }
+        ui.menu_button.set_menu_model(Some(&menu));
+
+        let banner = ui.reboot_banner.get();
+        ui.dismiss_btn.connect_clicked(move |_| banner.set_visible(false));
+
+        let jobs_bar = ui.jobs_bar.get();
+        jobs::connect_changed(move |jobs| show_jobs(&jobs_bar, jobs));
+
+        // Check-only mode shows the updates without offering to install them
+        if state.is_check_only() {
+            ui.select_all_btn.set_visible(false);
+            ui.install_btn.set_visible(false);
+            ui.security_btn.set_visible(false);
+            ui.refresh_btn.set_tooltip_text(Some("Reads the package lists as they are; refreshing them needs administrator rights"));
+        }
+
+        let main_window = Self {
+            window: widget.clone().upcast(),
+            menu,
+            listbox: ui.listbox.get(),
+            refresh_btn: ui.refresh_btn.get(),
+            select_all_btn: ui.select_all_btn.get(),
+            install_btn: ui.install_btn.get(),
+            security_btn: ui.security_btn.get(),
+            reboot_banner: ui.reboot_banner.get(),
+            reboot_label: ui.reboot_label.get(),
+            offline_btn: ui.offline_btn.get(),
+            busy: state.busy(),
+            state,
+            idle_actions: Rc::new(RefCell::new(Vec::new())),
+            periodic_check: Rc::new(RefCell::new(None)),
+            updates,
+            notifier,
+        };
+        main_window.connect_signals(app);
+        main_window.update_reboot_reminder();
+        main_window.update_offline_indicator();
+        main_window
+    }
+
+    fn connect_signals(&self, app: &Application) {
+        let config = Config::load();
+        let sort_modes: [(&str, bool, fn(&mut Config) -> &mut bool); 2] = [
+            ("sort_by_type", config.sort_by_type, |config| &mut config.sort_by_type),
+            ("sort_by_size", config.sort_by_size, |config| &mut config.sort_by_size),
+        ];
+        for (name, initial, field) in sort_modes {
+            let sort_action = gio::SimpleAction::new_stateful(name, None, &initial.to_variant());
+            let this = self.clone();
+            sort_action.connect_activate(move |action, _| {
+                let enabled = !action.state().and_then(|s| s.get::<bool>()).unwrap_or(false);
+                action.set_state(&enabled.to_variant());
+                let mut config = Config::load();
+                *field(&mut config) = enabled;
+                config.save();
+                this.show_packages(this.state.packages());
+            });
+            self.window.add_action(&sort_action);
+        }
+
+        let this = self.clone();
+        self.listbox.connect_row_activated(move |_, row| {
+            let Some(name) = row_package_name(row) else { return };
+            if let Some(pkg) = this.state.packages().into_iter().find(|pkg| pkg.name == name) {
+                if changelog::has_changelog(&pkg.update_type) {
+                    ChangelogWindow::new(&this.window, &pkg).show();
+                }
+            }
+        });
+
+        // Activated by the package checkboxes
+        let selection_action = gio::SimpleAction::new("selection_changed", None);
+        let this = self.clone();
+        selection_action.connect_activate(move |_, _| this.update_selection_size());
+        self.window.add_action(&selection_action);
+
+        // Offered next to the kept-back packages
+        let full_upgrade_action = gio::SimpleAction::new("full_upgrade", None);
+        let this = self.clone();
+        full_upgrade_action.connect_activate(move |_, _| this.full_upgrade());
+        self.window.add_action(&full_upgrade_action);
+        // A full upgrade would install more than the security updates
+        if self.state.is_check_only() || self.state.is_security_only() {
+            full_upgrade_action.set_enabled(false);
+        } else {
+            self.idle_actions.borrow_mut().push(full_upgrade_action);
+        }
+
+        // Downloads now, installs on the next restart
+        let stage_action = gio::SimpleAction::new("stage_offline", None);
+        let this = self.clone();
+        stage_action.connect_activate(move |_, _| this.stage_selected());
+        self.window.add_action(&stage_action);
+        if self.state.is_check_only() || !privilege::can_run_commands() {
+            stage_action.set_enabled(false);
+        } else {
+            self.idle_actions.borrow_mut().push(stage_action);
+        }
+
+        let this = self.clone();
+        self.offline_btn.connect_clicked(move |_| this.show_offline_update());
+
+        let this = self.clone();
+        self.security_btn.connect_clicked(move |_| this.install_security_updates());
+
+        // Offered by the update notification
+        let install_security_action = gio::SimpleAction::new("install-security", None);
+        let this = self.clone();
+        install_security_action.connect_activate(move |_, _| {
+            this.present();
+            this.install_security_updates();
+        });
+        install_security_action.set_enabled(!self.state.is_check_only());
+        app.add_action(&install_security_action);
+
+        // Used by the queue resume dialog
+        let install_selected_action = gio::SimpleAction::new("install_selected", None);
+        let this = self.clone();
+        install_selected_action.connect_activate(move |_, _| this.install_selected());
+        self.window.add_action(&install_selected_action);
+
+        // Keep buttons and menu actions in sync with the busy state
+        let this = self.clone();
+        self.busy.connect_changed(move |operation| this.on_busy_changed(operation));
+
+        let this = self.clone();
+        self.select_all_btn.connect_clicked(move |_| this.toggle_all());
+
+        let this = self.clone();
+        self.refresh_btn.connect_clicked(move |_| {
+            if this.state.is_check_only() {
+                this.check_in_background();
+            } else {
+                this.check_with_progress();
+            }
+        });
+
+        let this = self.clone();
+        self.install_btn.connect_clicked(move |_| this.install_selected());
+
+        let app = app.clone();
+        self.window.connect_close_request(move |window| {
+            // A maximized window would come back maximized-sized but not maximized
+            if !window.is_maximized() {
+                let (width, height) = window.default_size();
+                let mut config = Config::load();
+                config.window_width = width;
+                config.window_height = height;
+                config.save();
+            }
+            app.quit();
+            glib::Propagation::Proceed
+        });
+    }
+
+    pub fn window(&self) -> &ApplicationWindow {
+        &self.window
+    }
+
+    pub fn busy(&self) -> Rc<BusyState> {
+        self.busy.clone()
+    }
+
+    /// Adds a menu entry that opens another view. Entries with `needs_idle`
+    /// are disabled while an operation is running.
+    pub fn add_pane<F: Fn(&MainWindow) + 'static>(&self, name: &str, label: &str, needs_idle: bool, activate: F) {
+        let action = gio::SimpleAction::new(name, None);
+        let this = self.clone();
+        action.connect_activate(move |_, _| activate(&this));
+        self.window.add_action(&action);
+        self.menu.append(Some(label), Some(&format!("win.{}", name)));
+
+        if needs_idle {
+            action.set_enabled(!self.busy.is_busy());
+            self.idle_actions.borrow_mut().push(action);
+        }
+    }
+
+    /// Callback for views that change the installed packages
+    pub fn refresh_callback(&self) -> Rc<dyn Fn()> {
+        let refresh_btn = self.refresh_btn.clone();
+        Rc::new(move || refresh_btn.emit_clicked())
+    }
+
+    /// Ticked packages, with the whole kernel set when its row is ticked
+    pub fn selected_packages(&self) -> Vec<String> {
+        update_list::expand_selection(&self.state, selected_packages(&self.listbox))
+    }
+
+    /// Ticked APT packages, leaving out Flatpak applications and snaps
+    pub fn selected_apt_packages(&self) -> Vec<String> {
+        update_list::apt_only(&self.state.packages(), self.selected_packages())
+    }
+
+    pub fn pending_packages(&self) -> Vec<String> {
+        self.state.package_names()
+    }
+
+    /// Highlights pending packages that fix a published advisory
+    pub fn mark_advisory_fixes(&self, fixes: HashMap<String, Vec<String>>) {
+        self.state.set_advisory_fixes(fixes);
+        mark_advisory_rows(&self.listbox, &self.state);
+    }
+
+    pub fn present(&self) {
+        self.window.present();
+    }
+
+    fn on_busy_changed(&self, operation: Operation) {
+        let idle = operation == Operation::Idle;
+        self.refresh_btn.set_sensitive(idle);
+        self.select_all_btn.set_sensitive(idle);
+        self.install_btn.set_sensitive(idle);
+        self.update_security_button();
+
+        for action in self.idle_actions.borrow().iter() {
+            action.set_enabled(idle);
+        }
+
+        if idle {
+            self.window.set_title(Some("MeaUpdater"));
+        } else {
+            self.window.set_title(Some(&format!("MeaUpdater — {}...", operation.description())));
+        }
+    }
+
+    fn is_toggled(&self, action: &str) -> bool {
+        self.window
+            .lookup_action(action)
+            .and_then(|action| action.state())
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or(false)
+    }
+
+    fn show_packages(&self, mut packages: Vec<PackageUpdate>) {
+        let by_size = self.is_toggled("sort_by_size");
+        if by_size {
+            model::sort_by_size(&mut packages);
+        }
+        populate_package_list(&self.listbox, &self.state, packages, self.is_toggled("sort_by_type"), by_size);
+        self.update_selection_size();
+        self.update_security_button();
+    }
+
+    fn update_security_button(&self) {
+        let count = update_list::security_updates(&self.state).len();
+        self.security_btn.set_sensitive(count > 0 && !self.busy.is_busy());
+        if count == 0 {
+            self.security_btn.set_label("🔒 Install Security Updates");
+        } else {
+            self.security_btn.set_label(&format!("🔒 Install Security Updates ({})", count));
+        }
+    }
+
+    /// Ticks exactly the security updates and runs the usual install
+    /// pipeline, which shows the plan for confirmation first
+    fn install_security_updates(&self) {
+        if self.state.is_check_only() || self.busy.is_busy() {
+            return;
+        }
+        let security = update_list::security_rows(&self.state);
+        if security.is_empty() {
+            return;
+        }
+        let mut child = self.listbox.first_child();
+        while let Some(widget) = child {
+            child = widget.next_sibling();
+            let Ok(row) = widget.downcast::<ListBoxRow>() else { continue };
+            if let (Some(name), Some(check)) = (row_package_name(&row), row_check(&row)) {
+                check.set_active(security.contains(&name));
+            }
+        }
+        self.install_selected();
+    }
+
+    /// Shows the download size of the ticked packages on the install button
+    fn update_selection_size(&self) {
+        let selected = self.selected_packages();
+        let summary = SelectionSummary::new(&self.state.packages(), &selected);
+        self.install_btn.set_label(&summary.install_label());
+        self.install_btn.set_tooltip_text(summary.tooltip().as_deref());
+        self.prefetch_changelogs(&selected);
+    }
+
+    /// Downloads the changelogs of the ticked packages in the background so
+    /// they open instantly
+    fn prefetch_changelogs(&self, selected: &[String]) {
+        let wanted = self.state.packages().into_iter()
+            .filter(|pkg| selected.contains(&pkg.name) && changelog::has_changelog(&pkg.update_type))
+            .map(|pkg| (pkg.name, pkg.new_version))
+            .collect();
+        changelog::prefetch(wanted);
+    }
+
+    /// Stores the result of a check and shows it
+    fn apply_check_result(&self, packages: Vec<PackageUpdate>) {
+        let packages = update_list::offered(&self.state, packages);
+        self.state.set_packages(packages.clone());
+        self.show_packages(packages);
+        self.update_reboot_reminder();
+        self.update_kept_back();
+        self.update_exploited();
+    }
+
+    /// Looks up which pending security updates fix a vulnerability that is
+    /// known to be exploited and badges them
+    fn update_exploited(&self) {
+        let packages = self.state.packages();
+        if !packages.iter().any(|pkg| pkg.from_security) {
+            self.state.set_exploited(HashMap::new());
+            return;
+        }
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let exploited = match gio::spawn_blocking(move || kev::exploited_by_package(&packages)).await {
+                Ok(Ok(exploited)) => exploited,
+                Ok(Err(e)) => {
+                    eprintln!("Exploited vulnerabilities could not be checked: {}", e);
+                    return;
+                }
+                Err(_) => return,
+            };
+            if exploited.is_empty() && this.state.packages().iter().all(|pkg| this.state.exploited_cves(&pkg.name).is_none()) {
+                return;
+            }
+            let selected = this.selected_packages();
+            this.state.set_exploited(exploited);
+            this.show_packages(this.state.packages());
+            set_selected_packages(&this.listbox, &selected);
+        });
+    }
+
+    /// Asks apt which pending updates a plain upgrade would skip and moves
+    /// them to their own section, keeping the user's ticks on the others
+    fn update_kept_back(&self) {
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let reasons = match gio::spawn_blocking(apt::get_kept_back).await {
+                Ok(Ok(reasons)) => reasons,
+                Ok(Err(e)) => {
+                    eprintln!("Kept-back packages could not be determined: {}", e);
+                    return;
+                }
+                Err(_) => return,
+            };
+            if reasons.is_empty() && this.state.packages().iter().all(|pkg| this.state.kept_back_reason(&pkg.name).is_none()) {
+                return;
+            }
+            let mut selected = this.selected_packages();
+            this.state.set_kept_back(reasons);
+            selected.retain(|name| this.state.kept_back_reason(name).is_none());
+            this.show_packages(this.state.packages());
+            set_selected_packages(&this.listbox, &selected);
+        });
+    }
+
+    /// Shows the banner while a newer kernel is installed than the one running
+    fn update_reboot_reminder(&self) {
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let pending = gio::spawn_blocking(kernel_manager::pending_kernel_reboot).await.ok().flatten();
+            match pending {
+                Some(version) => {
+                    let running = kernel_manager::get_current_kernel().unwrap_or_default();
+                    this.reboot_label.set_markup(&format!(
+                        "🔁 Kernel <b>{}</b> is installed, but <b>{}</b> is still running. Restart the computer to use the updated kernel.",
+                        glib::markup_escape_text(&version),
+                        glib::markup_escape_text(&running)
+                    ));
+                    this.reboot_banner.set_visible(true);
+                }
+                None => this.reboot_banner.set_visible(false),
+            }
+        });
+    }
+
+    fn announce_result(&self) {
+        // Only announce updates that were not in the last notification
+        if notifications::should_notify(&self.state.package_names()) && Config::load().notify_updates {
+            let security = update_list::security_updates(&self.state).len();
+            let status = if security > 0 && !self.state.is_check_only() {
+                UpdateStatus::SecurityAvailable { updates: self.state.update_count(), security }
+            } else {
+                UpdateStatus::Complete(self.state.update_count())
+            };
+            self.notifier.notify(status);
+        } else {
+            self.notifier.withdraw();
+        }
+    }
+
+    /// Quiet check used at startup and by the periodic timer
+    pub fn check_in_background(&self) {
+        // Skip the check if the user already started something
+        if !self.busy.try_begin(Operation::Checking) {
+            return;
+        }
+        self.notifier.notify(UpdateStatus::Checking);
+
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let result = this.updates.upgradable_packages();
+            this.busy.finish();
+            match result {
+                Ok(packages) => {
+                    this.apply_check_result(packages);
+                    if this.state.take_queue_resume_offer() && !this.state.is_check_only() {
+                        offer_queue_resume(&this.listbox, &this.state.package_names());
+                    }
+                    if !this.install_automatically() {
+                        this.announce_result();
+                    }
+                    let config = Config::load();
+                    digest::send_if_due(&this.state.packages(), config.weekly_digest, config.weekly_report);
+                }
+                Err(e) => {
+                    this.notifier.notify(UpdateStatus::Failed);
+                    eprintln!("Update checking error: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Installs the pending updates whose class the user allowed to be
+    /// installed unattended; kernels and drivers wait for confirmation
+    /// unless allowed too. Returns whether an installation was started.
+    fn install_automatically(&self) -> bool {
+        if self.state.is_check_only() {
+            return false;
+        }
+        let config = Config::load();
+        if config.schedule.notify_only {
+            return false;
+        }
+        let policy = config.auto_install;
+        let packages = self.state.packages();
+        let (automatic, manual) = policy.split(&packages);
+        if automatic.is_empty() || !self.busy.try_begin(Operation::Installing) {
+            return false;
+        }
+        println!("🤖 Installing automatically: {}", automatic.join(", "));
+
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let flatpaks = flatpak_manager::flatpak_names(&packages, &automatic);
+            let snaps = snap_manager::snap_names(&packages, &automatic);
+            let debs: Vec<String> = automatic.iter().filter(|name| !flatpaks.contains(name) && !snaps.contains(name)).cloned().collect();
+            let result = gio::spawn_blocking(move || {
+                if !debs.is_empty() {
+                    policy::install_packages(&debs)?;
+                }
+                flatpak_manager::update(&flatpaks, &|_| {})?;
+                snap_manager::refresh(&snaps, &|_| {})
+            }).await;
+            this.busy.finish();
+            match result {
+                Ok(Ok(())) => {
+                    let mut remaining = this.state.packages();
+                    remaining.retain(|pkg| !automatic.contains(&pkg.name));
+                    this.state.set_packages(remaining.clone());
+                    this.show_packages(remaining);
+                    this.notifier.notify(UpdateStatus::AutoInstalled { installed: automatic.len(), waiting: manual.len() });
+                    if self_update::needs_restart(&automatic) {
+                        offer_restart(&this.window);
+                    }
+                }
+                Ok(Err(e)) => {
+                    eprintln!("Automatic installation failed: {}", e);
+                    this.announce_result();
+                }
+                Err(_) => this.announce_result(),
+            }
+        });
+        true
+    }
+
+    /// Refreshes the package lists with a progress window
+    fn check_with_progress(&self) {
+        if !self.busy.try_begin(Operation::Checking) {
+            return;
+        }
+
+        let progress_window = ProgressWindow::new(&self.window);
+        progress_window.show();
+        self.notifier.notify(UpdateStatus::Checking);
+
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let result = progress_window.check_updates_with_progress().await;
+            this.busy.finish();
+            match result {
+                Ok(packages) => {
+                    this.apply_check_result(packages);
+                    this.announce_result();
+                }
+                Err(e) => {
+                    this.notifier.notify(UpdateStatus::Failed);
+
+                    let retry = this.clone();
+                    error::show_error_dialog(
+                        &this.window,
+                        "An error occurred while checking for updates:",
+                        &e,
+                        Some(Box::new(move || retry.check_with_progress())),
+                    );
+                }
+            }
+        });
+    }
+
+    fn install_selected(&self) {
+        if self.state.is_check_only() {
+            return;
+        }
+        if self.busy.is_busy() {
+            eprintln!("Install ignored, busy: {}", self.busy.current().description());
+            return;
+        }
+
+        let selected = self.selected_packages();
+        if selected.is_empty() {
+            let dialog = MessageDialog::builder()
+                .transient_for(&self.window)
+                .modal(true)
+                .message_type(MessageType::Error)
+                .buttons(ButtonsType::Ok)
+                .text("⚠️ Please select at least one package.")
+                .build();
+            dialog.connect_response(|dlg, _| dlg.close());
+            dialog.show();
+            return;
+        }
+
+        // Flatpak applications and snaps are updated after the APT packages
+        let packages = self.state.packages();
+        let flatpaks = flatpak_manager::flatpak_names(&packages, &selected);
+        let snaps = snap_manager::snap_names(&packages, &selected);
+        let debs: Vec<String> = selected.into_iter().filter(|name| !flatpaks.contains(name) && !snaps.contains(name)).collect();
+        if debs.is_empty() {
+            self.start_upgrade(None, flatpaks, snaps);
+            return;
+        }
+
+        let transaction = Transaction::install(&debs)
+            .only_upgrade()
+            .refresh_lists()
+            .journal(OperationKind::Install);
+        self.run_upgrade(transaction, "install updates", flatpaks, snaps);
+    }
+
+    /// Upgrades everything, kept-back packages included, after showing
+    /// which packages that adds or removes
+    fn full_upgrade(&self) {
+        if self.state.is_check_only() || self.busy.is_busy() {
+            return;
+        }
+        let packages = self.state.packages();
+        let all: Vec<String> = packages.iter().map(|pkg| pkg.name.clone()).collect();
+        let transaction = Transaction::full_upgrade()
+            .refresh_lists()
+            .journal(OperationKind::Install);
+        self.run_upgrade(
+            transaction,
+            "run a full upgrade",
+            flatpak_manager::flatpak_names(&packages, &all),
+            snap_manager::snap_names(&packages, &all),
+        );
+    }
+
+    fn run_upgrade(&self, transaction: Transaction, what: &str, flatpaks: Vec<String>, snaps: Vec<String>) {
+        let parent = self.window.clone().upcast::<gtk::Window>();
+        let this = self.clone();
+        sessions::confirm_disruptive_action(&parent, what, move || {
+            let transaction = transaction.clone();
+            let this = this.clone();
+            let flatpaks = flatpaks.clone();
+            let snaps = snaps.clone();
+            progress::confirm_transaction(&this.window.clone(), transaction, move |transaction| {
+                this.start_upgrade(Some(transaction), flatpaks.clone(), snaps.clone());
+            });
+        });
+    }
+
+    /// Runs the confirmed APT transaction, if any, and then updates the
+    /// Flatpak applications and snaps in the same progress window
+    fn start_upgrade(&self, transaction: Option<Transaction>, flatpaks: Vec<String>, snaps: Vec<String>) {
+        if !self.busy.try_begin(Operation::Installing) {
+            return;
+        }
+        install_queue::clear();
+
+        let progress_window = ProgressWindow::new(&self.window);
+        progress_window.show();
+
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let packages = transaction.as_ref().map(|t| t.packages().to_vec()).unwrap_or_default();
+            let summary = match &transaction {
+                Some(transaction) => transaction.summary(),
+                None => "update Flatpak applications and snaps".to_string(),
+            };
+            let work = move |report: &dyn Fn(TransactionEvent), cancel: &CancelToken| {
+                if let Some(transaction) = transaction {
+                    transaction.cancellable(cancel.clone()).execute(report)?;
+                }
+                cancel.check()?;
+                flatpak_manager::update(&flatpaks, report)?;
+                cancel.check()?;
+                snap_manager::refresh(&snaps, report)
+            };
+            let result = progress_window.run_operation(&summary, work, "✅ Updates installed successfully!").await;
+            this.busy.finish();
+            if result.is_ok() {
+                if self_update::needs_restart(&packages) {
+                    offer_restart(&this.window);
+                }
+                let refresh_btn = this.refresh_btn.clone();
+                glib::timeout_add_seconds_local(3, move || {
+                    refresh_btn.emit_clicked();
+                    glib::ControlFlow::Break
+                });
+            }
+        });
+    }
+
+    /// Downloads the ticked APT updates and stages them, so they are
+    /// installed while the computer restarts instead of while it is in use
+    fn stage_selected(&self) {
+        if self.state.is_check_only() || !privilege::can_run_commands() || self.busy.is_busy() {
+            return;
+        }
+        let debs = self.selected_apt_packages();
+        if debs.is_empty() {
+            let dialog = MessageDialog::builder()
+                .transient_for(&self.window)
+                .modal(true)
+                .message_type(MessageType::Error)
+                .buttons(ButtonsType::Ok)
+                .text("⚠️ Please select at least one package.")
+                .secondary_text("Flatpak applications and snaps are updated right away, not at the next restart.")
+                .build();
+            dialog.connect_response(|dlg, _| dlg.close());
+            dialog.show();
+            return;
+        }
+
+        let this = self.clone();
+        progress::confirm_transaction(&self.window.clone(), offline_update::stage_transaction(&debs), move |transaction| {
+            this.start_staging(transaction);
+        });
+    }
+
+    fn start_staging(&self, transaction: Transaction) {
+        if !self.busy.try_begin(Operation::Installing) {
+            return;
+        }
+        let progress_window = ProgressWindow::new(&self.window);
+        progress_window.show();
+
+        let this = self.clone();
+        glib::spawn_future_local(async move {
+            let work = move |report: &dyn Fn(TransactionEvent), cancel: &CancelToken| {
+                transaction.cancellable(cancel.clone()).execute(report)?;
+                offline_update::verify_staged().map(|_| ())
+            };
+            let _ = progress_window.run_operation(
+                "stage the updates for the next restart",
+                work,
+                "✅ The updates will be installed when the computer restarts",
+            ).await;
+            this.busy.finish();
+            this.update_offline_indicator();
+        });
+    }
+
+    /// Shows or hides the header bar indicator of staged updates
+    fn update_offline_indicator(&self) {
+        let status = offline_update::status();
+        match status.indicator_label() {
+            Some(label) => {
+                self.offline_btn.set_label(&label);
+                self.offline_btn.set_visible(true);
+            }
+            None => self.offline_btn.set_visible(false),
+        }
+    }
+
+    /// Offers to restart into the staged updates or to drop them
+    fn show_offline_update(&self) {
+        let status = offline_update::status();
+        let dialog = match &status {
+            OfflineStatus::Idle => {
+                self.update_offline_indicator();
+                return;
+            }
+            OfflineStatus::Staged(packages) => {
+                let dialog = MessageDialog::builder()
+                    .transient_for(&self.window)
+                    .modal(true)
+                    .message_type(MessageType::Info)
+                    .buttons(ButtonsType::None)
+                    .text("⏳ Updates Staged for Restart")
+                    .secondary_text(format!(
+                        "These updates are downloaded and will be installed the next time the computer restarts:\n\n{}",
+                        packages.join(", "),
+                    ))
+                    .build();
+                dialog.add_button("Discard", ResponseType::Reject);
+                dialog.add_button("Shut Down and Install", ResponseType::Other(1));
+                dialog.add_button("Restart and Install", ResponseType::Accept);
+                dialog.set_default_response(ResponseType::Accept);
+                dialog
+            }
+            OfflineStatus::Failed { log } => {
+                let dialog = MessageDialog::builder()
+                    .transient_for(&self.window)
+                    .modal(true)
+                    .message_type(MessageType::Warning)
+                    .buttons(ButtonsType::None)
+                    .text("⚠️ Offline Update Failed")
+                    .secondary_text(format!(
+                        "The staged updates could not be installed during the last restart. The apt output is in {}.\n\nCheck for updates to try again.",
+                        log,
+                    ))
+                    .build();
+                dialog.add_button("Dismiss", ResponseType::Reject);
+                dialog.add_button("Close", ResponseType::Close);
+                dialog
+            }
+        };
+
+        let this = self.clone();
+        dialog.connect_response(move |dlg, response| {
+            dlg.close();
+            let (heading, work): (&str, fn() -> error::Result<()>) = match response {
+                ResponseType::Accept => ("❌ Could not restart", || offline_update::restart(false)),
+                ResponseType::Other(1) => ("❌ Could not restart", || offline_update::restart(true)),
+                ResponseType::Reject => ("❌ The staged updates could not be discarded", offline_update::discard),
+                _ => return,
+            };
+            let this = this.clone();
+            glib::spawn_future_local(async move {
+                match gio::spawn_blocking(work).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => error::show_error_dialog(&this.window, heading, &e, None),
+                    Err(_) => eprintln!("The offline update task panicked"),
+                }
+                this.update_offline_indicator();
+            });
+        });
+        dialog.show();
+    }
+
+    /// Ticks every shown package, or unticks them all when all are ticked
+    fn toggle_all(&self) {
+        // Rows of collapsed sections keep their ticks
+        let checks: Vec<CheckButton> = row_checks(&self.listbox).into_iter()
+            .filter(|check| check.ancestor(ListBoxRow::static_type()).map_or(true, |row| row.is_visible()))
+            .collect();
+        let ticks: Vec<bool> = checks.iter().map(|check| check.is_active()).collect();
+        let active = update_list::select_all_ticks(&ticks);
+        for check in checks {
+            check.set_active(active);
+        }
+    }
+
+    /// Replaces the periodic check timer after the settings changed
+    pub fn reschedule_checks(&self, config: &Config) {
+        if let Some(source) = self.periodic_check.borrow_mut().take() {
+            source.remove();
+        }
+        if !config.automatic_checks {
+            return;
+        }
+
+        let this = self.clone();
+        if config.schedule.frequency == ScheduleFrequency::Interval {
+            let source = glib::timeout_add_seconds_local(config.check_interval_seconds(), move || {
+                this.check_in_background();
+                glib::ControlFlow::Continue
+            });
+            *self.periodic_check.borrow_mut() = Some(source);
+            return;
+        }
+
+        // Daily and weekly checks wait for the next wall-clock time, so
+        // the wait is worked out again after every check
+        let now = glib::DateTime::now_local();
+        let delay = match &now {
+            Ok(now) => config.schedule.seconds_until_next(
+                config.check_interval_seconds(),
+                now.day_of_week() as u32,
+                (now.hour() * 3600 + now.minute() * 60 + now.second()) as u32,
+            ),
+            Err(_) => config.check_interval_seconds(),
+        };
+        println!("⏰ Next update check in {} minutes ({})", delay / 60, config.schedule.describe(config.check_interval_minutes));
+        let source = glib::timeout_add_seconds_local(delay, move || {
+            // The source is gone once this returns Break
+            this.periodic_check.borrow_mut().take();
+            this.check_in_background();
+            this.reschedule_checks(&Config::load());
+            glib::ControlFlow::Break
+        });
+        *self.periodic_check.borrow_mut() = Some(source);
+    }
+}
+
+/// One compact row per background job, with a button to bring its window back
+fn show_jobs(bar: &GtkBox, jobs: &[jobs::Job]) {
+    while let Some(child) = bar.first_child() {
+        bar.remove(&child);
+    }
+    bar.set_visible(!jobs.is_empty());
+
+    for job in jobs {
+        let row = GtkBox::new(Orientation::Horizontal, 12);
+        let title = Label::new(Some(&format!("⏳ {}", job.title)));
+        title.set_halign(gtk::Align::Start);
+        let status = Label::new(None);
+        status.set_markup(&job.status);
+        status.set_hexpand(true);
+        status.set_halign(gtk::Align::Start);
+        status.set_ellipsize(pango::EllipsizeMode::End);
+        let progress = gtk::ProgressBar::new();
+        progress.set_fraction(job.fraction);
+        progress.set_valign(gtk::Align::Center);
+        progress.set_size_request(160, -1);
+        let show_btn = Button::with_label("Show");
+        show_btn.add_css_class("flat");
+        show_btn.set_tooltip_text(Some("Open the progress window again"));
+        let id = job.id;
+        show_btn.connect_clicked(move |_| jobs::reattach(id));
+
+        row.append(&title);
+        row.append(&status);
+        row.append(&progress);
+        row.append(&show_btn);
+        bar.append(&row);
+    }
+}
+
+/// Offers to restart into the MeaUpdater version that was just installed
+pub fn offer_restart(window: &ApplicationWindow) {
+    let dialog = MessageDialog::builder()
+        .transient_for(window)
+        .modal(true)
+        .message_type(MessageType::Info)
+        .buttons(ButtonsType::None)
+        .text("🔄 MeaUpdater Was Updated")
+        .secondary_text("A new version of MeaUpdater was installed. Restart it now so you are not running the old version?")
+        .build();
+    dialog.add_button("Later", ResponseType::Reject);
+    dialog.add_button("Restart Now", ResponseType::Accept);
+    dialog.set_default_response(ResponseType::Accept);
+
+    let window = window.clone();
+    dialog.connect_response(move |dlg, response| {
+        dlg.close();
+        if response == ResponseType::Accept {
+            let e = self_update::restart();
+            error::show_error_dialog(&window, "❌ MeaUpdater could not be restarted", &e, None);
+        }
+    });
+    dialog.show();
+}
+
+/// Checkboxes of the package rows
+fn row_checks(listbox: &ListBox) -> Vec<CheckButton> {
+    let mut checks = Vec::new();
+    let mut child = listbox.first_child();
+    while let Some(row_widget) = child {
+        child = row_widget.next_sibling();
+        if let Some(check) = row_widget.downcast::<ListBoxRow>().ok().and_then(|row| row_check(&row)) {
+            checks.push(check);
+        }
+    }
+    checks
+}
+
+/// Checkbox of a package row
+fn row_check(row: &ListBoxRow) -> Option<CheckButton> {
+    let hbox = row.child()?.downcast::<GtkBox>().ok()?;
+    let mut widget_child = hbox.first_child();
+    while let Some(widget) = widget_child {
+        widget_child = widget.next_sibling();
+        if let Ok(check) = widget.downcast::<CheckButton>() {
+            return Some(check);
+        }
+    }
+    None
+}
+
+fn populate_package_list(listbox: &ListBox, state: &AppState, packages: Vec<model::PackageUpdate>, group_by_type: bool, by_size: bool) {
+
+    while let Some(child) = listbox.first_child() {
+        listbox.remove(&child);
+    }
+    
+
+    if packages.is_empty() {
+        let row = ListBoxRow::new();
+        row.set_activatable(false);
+        let empty_box = GtkBox::new(Orientation::Horizontal, 12);
+        empty_box.set_margin_top(20);
+        empty_box.set_margin_bottom(20);
+        empty_box.set_halign(gtk::Align::Center);
+        
+        let empty_label = Label::new(Some("✅ All packages are up to date! No updates required."));
+        empty_label.set_markup("<big><b>✅ All packages are up to date!</b></big>");
+        empty_box.append(&empty_label);
+        
+        row.set_child(Some(&empty_box));
+        listbox.append(&row);
+        return;
+    }
+
+    // Used to flag updates to the running desktop session
+    let processes = policy::running_processes();
+
+    let config = Config::load();
+    for section in update_list::sections(state, packages, group_by_type, by_size) {
+        let Some(key) = section.key else {
+            for pkg in section.packages {
+                add_package_row(listbox, state, &processes, pkg);
+            }
+            continue;
+        };
+        let expanded = !config.is_group_collapsed(key);
+        let header = if section.is_kept_back() {
+            add_kept_back_header(listbox, section.packages.len(), state.is_check_only() || state.is_security_only(), expanded)
+        } else {
+            add_group_header(listbox, key, section.title, section.packages.len(), expanded)
+        };
+        for pkg in section.packages {
+            add_package_row(listbox, state, &processes, pkg);
+        }
+        show_group_rows(&header, expanded);
+    }
+}
+
+
+/// Header of the kept-back section, with the way to include them anyway
+fn add_kept_back_header(listbox: &ListBox, count: usize, hide_full_upgrade: bool, expanded: bool) -> ListBoxRow {
+    let header_row = add_group_header(listbox, update_list::KEPT_BACK_SECTION, "⏸️ Kept Back", count, expanded);
+    if let Some(header_box) = header_row.child().and_downcast::<GtkBox>() {
+        let hint = Label::new(Some("A plain upgrade skips these because they need packages added or removed"));
+        hint.add_css_class("size-info");
+        hint.set_hexpand(true);
+        hint.set_halign(gtk::Align::Start);
+        let full_upgrade_btn = Button::with_label("🔓 Full Upgrade");
+        full_upgrade_btn.set_tooltip_text(Some("Upgrade everything including these, after reviewing what gets installed or removed"));
+        full_upgrade_btn.set_action_name(Some("win.full_upgrade"));
+        full_upgrade_btn.set_visible(!hide_full_upgrade);
+        header_box.append(&hint);
+        header_box.append(&full_upgrade_btn);
+    }
+    header_row
+}
+
+/// Section header that collapses the rows below it up to the next header.
+/// The state is saved under `key`, so collapsed sections stay collapsed.
+fn add_group_header(listbox: &ListBox, key: &str, title: &str, count: usize, expanded: bool) -> ListBoxRow {
+    let header_row = ListBoxRow::new();
+    header_row.set_selectable(false);
+    header_row.set_activatable(false);
+    header_row.add_css_class("kernel-header-row");
+    
+    let header_box = GtkBox::new(Orientation::Horizontal, 12);
+    header_box.set_margin_top(12);
+    header_box.set_margin_bottom(8);
+    header_box.set_margin_start(8);
+    header_box.set_margin_end(8);
+    
+    let header_label = Label::new(None);
+    header_label.set_markup(&format!("<b>{} ({})</b>", title, count));
+    let expander = Expander::new(None);
+    expander.set_label_widget(Some(&header_label));
+    expander.set_expanded(expanded);
+    expander.set_tooltip_text(Some("Collapsed sections stay collapsed until you expand them again"));
+
+    let row = header_row.clone();
+    let key = key.to_string();
+    expander.connect_expanded_notify(move |expander| {
+        show_group_rows(&row, expander.is_expanded());
+        let mut config = Config::load();
+        config.set_group_collapsed(&key, !expander.is_expanded());
+        config.save();
+    });
+    
+    header_box.append(&expander);
+    header_row.set_child(Some(&header_box));
+    listbox.append(&header_row);
+    header_row
+}
+
+/// Shows or hides the package rows between `header` and the next header
+fn show_group_rows(header: &ListBoxRow, visible: bool) {
+    let mut next = header.next_sibling();
+    while let Some(row) = next {
+        if row.has_css_class("kernel-header-row") {
+            break;
+        }
+        row.set_visible(visible);
+        next = row.next_sibling();
+    }
+}
+
+fn add_package_row(listbox: &ListBox, state: &AppState, processes: &HashSet<String>, pkg: model::PackageUpdate) {
+    let row = ListBoxRow::new();
+    row.add_css_class("package-row");
+    
+    let hbox = GtkBox::new(Orientation::Horizontal, 12);
+    hbox.set_margin_top(8);
+    hbox.set_margin_bottom(8);
+    hbox.set_margin_start(8);
+    hbox.set_margin_end(8);
+    
+    
+    let (type_icon, type_class) = match pkg.update_type {
+        model::UpdateType::Security => (Icon::Security, "security-update"),
+        model::UpdateType::Software => (Icon::Software, "software-update"),
+        model::UpdateType::Kernel => (Icon::Kernel, "kernel-update"),
+        model::UpdateType::Firmware => (Icon::Firmware, "firmware-update"),
+        model::UpdateType::Flatpak => (Icon::Flatpak, "flatpak-update"),
+        model::UpdateType::Snap => (Icon::Snap, "snap-update"),
+    };
+    let type_label = icons::column_widget(type_icon, 6);
+    type_label.set_halign(gtk::Align::Center);
+    type_label.add_css_class(type_class);
+    if pkg.update_type == model::UpdateType::Firmware {
+        type_label.set_tooltip_text(Some("Firmware update: it only affects specific devices and usually needs a reboot to take effect"));
+    }
+    
+    
+    let kept_back = state.kept_back_reason(&pkg.name);
+    let check = CheckButton::new();
+    check.set_active(kept_back.is_none());
+    check.set_sensitive(!state.is_check_only());
+    check.set_halign(gtk::Align::Center);
+    check.connect_toggled(|check| {
+        // Remember the selection so it can be resumed after a restart
+        if let Some(listbox) = check.ancestor(ListBox::static_type()).and_downcast::<ListBox>() {
+            install_queue::save(&selected_packages(&listbox));
+        }
+        WidgetExt::activate_action(check, "win.selection_changed", None).ok();
+    });
+    
+    
+    let name_label = Label::new(Some(&pkg.name));
+    name_label.set_hexpand(true);
+    name_label.set_halign(gtk::Align::Start);
+    name_label.add_css_class("package-name");
+    mark_advisory_fix(&name_label, state, &pkg.name);
+    if let Some(reason) = &kept_back {
+        name_label.set_tooltip_text(Some(&format!("⏸️ Kept back: {}", reason)));
+    }
+
+    let exploited_badge = Label::new(None);
+    if let Some(cves) = state.exploited_cves(&pkg.name) {
+        exploited_badge.set_text("🚨 Actively exploited");
+        exploited_badge.add_css_class("exploited");
+        exploited_badge.set_tooltip_text(Some(&format!(
+            "Fixes {}, listed in the CISA Known Exploited Vulnerabilities catalog. Attackers are using it now: install this update first.",
+            cves.join(", ")
+        )));
+    }
+
+    let kernel_set_badge = Label::new(None);
+    let kernel_set = state.kernel_set();
+    if kernel_set.first() == Some(&pkg.name) {
+        kernel_set_badge.set_text(&format!("🔗 +{}", format::plural((kernel_set.len() - 1) as u64, "package", "packages")));
+        kernel_set_badge.add_css_class("size-info");
+        kernel_set_badge.set_tooltip_text(Some(&format!(
+            "Installed together so the kernel, its headers, modules and firmware stay in step:\n{}",
+            kernel_set.join("\n")
+        )));
+    }
+
+    let session_badge = Label::new(None);
+    if let Some(component) = policy::session_restart_component(&pkg.name, processes) {
+        session_badge.set_text("🔁 Session restart recommended");
+        session_badge.add_css_class("session-restart");
+        session_badge.set_tooltip_text(Some(&format!(
+            "This updates {}, which is running now. Log out and back in (or restart) after installing so the new version is used.",
+            component.name()
+        )));
+    }
+    
+    
+    let version_text = if !pkg.current_version.is_empty() {
+        format!("{} → {}", pkg.current_version, pkg.new_version)
+    } else {
+        pkg.new_version.clone()
+    };
+    let version_label = Label::new(Some(&version_text));
+    version_label.set_width_chars(25);
+    version_label.set_halign(gtk::Align::Center);
+    version_label.add_css_class("version-info");
+    
+    
+    let size_label = Label::new(Some(&format::optional_size(pkg.size)));
+    size_label.set_width_chars(12);
+    size_label.set_halign(gtk::Align::Center);
+    size_label.add_css_class("size-info");
+    
+    
+    let age_label = Label::new(None);
+    age_label.set_width_chars(12);
+    age_label.set_halign(gtk::Align::Center);
+    age_label.add_css_class("size-info");
+    if let Some(days) = update_age::pending_days(&pkg) {
+        age_label.set_text(&format::days(days));
+        if let Some(first_seen) = pkg.first_seen {
+            age_label.set_tooltip_text(Some(&format!(
+                "Available since {}",
+                glib::DateTime::from_unix_local(first_seen as i64)
+                    .and_then(|dt| dt.format("%x"))
+                    .map(|s| s.to_string())
+                    .unwrap_or_default()
+            )));
+        }
+        if pkg.update_type == model::UpdateType::Security && days >= update_age::STALE_SECURITY_DAYS {
+            age_label.add_css_class("stale-update");
+        }
+    }
+    
+    hbox.append(&type_label);
+    hbox.append(&check);
+    hbox.append(&name_label);
+    hbox.append(&exploited_badge);
+    hbox.append(&kernel_set_badge);
+    hbox.append(&session_badge);
+    hbox.append(&version_label);
+    hbox.append(&size_label);
+    hbox.append(&age_label);
+
+    // Kept last: the selection helpers find the checkbox and name by position
+    let changelog_btn = Button::with_label("📜");
+    changelog_btn.set_visible(changelog::has_changelog(&pkg.update_type));
+    changelog_btn.add_css_class("flat");
+    changelog_btn.set_tooltip_text(Some("Show what changed in this update (or double-click the row)"));
+    changelog_btn.connect_clicked(move |btn| {
+        if let Some(window) = btn.root().and_downcast::<gtk::Window>() {
+            ChangelogWindow::new(&window, &pkg).show();
+        }
+    });
+    hbox.append(&changelog_btn);
+    
+    row.set_child(Some(&hbox));
+    listbox.append(&row);
+}
+
+/// Ticks exactly the packages in `names`
+fn set_selected_packages(listbox: &ListBox, names: &[String]) {
+    let mut child = listbox.first_child();
+    while let Some(row_widget) = child {
+        child = row_widget.next_sibling();
+        if let Some(hbox) = row_widget
+            .downcast::<ListBoxRow>().ok()
+            .and_then(|r| r.child())
+            .and_then(|c| c.downcast::<GtkBox>().ok())
+        {
+            let check = hbox.first_child().and_then(|w| w.next_sibling()).and_downcast::<CheckButton>();
+            let name = check.as_ref().and_then(|c| c.next_sibling()).and_downcast::<Label>();
+            if let (Some(check), Some(name)) = (check, name) {
+                check.set_active(names.iter().any(|n| n.as_str() == name.text().as_str()));
+            }
+        }
+    }
+}
+
+/// Offers to restore a selection that was never installed
+fn offer_queue_resume(listbox: &ListBox, available: &[String]) {
+    let queued = install_queue::resumable(&install_queue::load(), available);
+    if queued.is_empty() {
+        install_queue::clear();
+        return;
+    }
+    let window = match listbox.root().and_downcast::<ApplicationWindow>() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let dialog = MessageDialog::builder()
+        .transient_for(&window)
+        .modal(true)
+        .message_type(MessageType::Question)
+        .buttons(ButtonsType::None)
+        .text("📋 Resume Previous Selection?")
+        .secondary_text(&format!(
+            "Last time you selected {} updates that were not installed:\n\n{}",
+            queued.len(),
+            queued.join(", ")
+        ))
+        .build();
+    dialog.add_button("Discard", ResponseType::Reject);
+    dialog.add_button("Select Again", ResponseType::Apply);
+    dialog.add_button("Install Now", ResponseType::Accept);
+
+    let listbox = listbox.clone();
+    dialog.connect_response(move |dlg, response| {
+        dlg.close();
+        match response {
+            ResponseType::Apply => set_selected_packages(&listbox, &queued),
+            ResponseType::Accept => {
+                set_selected_packages(&listbox, &queued);
+                WidgetExt::activate_action(&window, "win.install_selected", None).ok();
+            }
+            _ => install_queue::clear(),
+        }
+    });
+    dialog.show();
+}
+
+/// Highlights a package whose pending update fixes a published advisory
+fn mark_advisory_fix(name_label: &Label, state: &AppState, package: &str) {
+    if let Some(ids) = state.advisories_fixed_by(package) {
+        name_label.add_css_class("advisory-fix");
+        name_label.set_tooltip_text(Some(&format!("🛡️ Fixes {}", ids.join(", "))));
+    }
+}
+
+/// Re-applies the advisory highlight to the rows already in the list
+fn mark_advisory_rows(listbox: &ListBox, state: &AppState) {
+    let mut child = listbox.first_child();
+    while let Some(row_widget) = child {
+        child = row_widget.next_sibling();
+        let name_label = row_widget
+            .downcast::<ListBoxRow>().ok()
+            .and_then(|r| r.child())
+            .and_then(|c| c.downcast::<GtkBox>().ok())
+            .and_then(|hbox| hbox.first_child())
+            .and_then(|w| w.next_sibling())
+            .and_then(|w| w.next_sibling())
+            .and_then(|w| w.downcast::<Label>().ok());
+        if let Some(label) = name_label {
+            mark_advisory_fix(&label, state, &label.text());
+        }
+    }
+}
+
+/// Names of the packages whose checkbox is ticked
+/// Name of the package shown in a row, from its name label
+fn row_package_name(row: &ListBoxRow) -> Option<String> {
+    let hbox = row.child()?.downcast::<GtkBox>().ok()?;
+    let name_label = hbox.first_child()?.next_sibling()?.next_sibling()?.downcast::<Label>().ok()?;
+    Some(name_label.text().to_string())
+}
+
+fn selected_packages(listbox: &ListBox) -> Vec<String> {
+    let mut selected = Vec::new();
+    let mut child = listbox.first_child();
+    while let Some(row_widget) = child {
+        child = row_widget.next_sibling();
+        if let Some(hbox) = row_widget
+            .downcast::<ListBoxRow>().ok()
+            .and_then(|r| r.child())
+            .and_then(|c| c.downcast::<GtkBox>().ok())
+        {
+            let mut checkbox_found = false;
+            let mut package_name = String::new();
+
+            let mut widget_child = hbox.first_child();
+            let mut widget_count = 0;
+            
+            while let Some(widget) = widget_child {
+                widget_child = widget.next_sibling();
+                widget_count += 1;
+
+
+                if let Ok(check) = widget.clone().downcast::<CheckButton>() {
+                    checkbox_found = check.is_active();
+                }
+
+
+                if widget_count == 3 {
+                    if let Ok(label) = widget.clone().downcast::<Label>() {
+                        package_name = label.text().to_string();
+                    }
+                }
+            }
+
+            if checkbox_found && !package_name.is_empty() {
+                selected.push(package_name);
+            }
+        }
+    }
+
+    selected
+}
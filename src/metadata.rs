@@ -0,0 +1,80 @@
+// src/metadata.rs
+
+use crate::sysroot;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long the answer of a query is reused. The Kernel Manager, Driver
+/// Manager and the update check often ask the same within a minute.
+pub const TTL: Duration = Duration::from_secs(5 * 60);
+
+static CACHE: Mutex<Option<MetadataCache>> = Mutex::new(None);
+
+/// Modification times of the package lists and of the dpkg status file.
+/// An entry taken before `apt-get update` or an install no longer applies.
+pub type Stamp = (Option<SystemTime>, Option<SystemTime>);
+
+struct Entry {
+    stdout: String,
+    fetched: Instant,
+    stamp: Stamp,
+}
+
+/// Output of read-only apt queries, by command line
+pub struct MetadataCache {
+    entries: HashMap<Vec<String>, Entry>,
+    ttl: Duration,
+}
+
+impl MetadataCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { entries: HashMap::new(), ttl }
+    }
+
+    /// The stored output of `argv`, if it is younger than the TTL and was
+    /// taken with the same package lists
+    pub fn get(&self, argv: &[String], now: Instant, stamp: Stamp) -> Option<String> {
+        self.entries.get(argv)
+            .filter(|entry| entry.stamp == stamp && now.saturating_duration_since(entry.fetched) < self.ttl)
+            .map(|entry| entry.stdout.clone())
+    }
+
+    pub fn insert(&mut self, argv: Vec<String>, stdout: String, now: Instant, stamp: Stamp) {
+        self.entries.retain(|_, entry| entry.stamp == stamp && now.saturating_duration_since(entry.fetched) < self.ttl);
+        self.entries.insert(argv, Entry { stdout, fetched: now, stamp });
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(sysroot::path(path)).and_then(|meta| meta.modified()).ok()
+}
+
+fn current_stamp() -> Stamp {
+    (modified("/var/lib/apt/lists"), modified("/var/lib/dpkg/status"))
+}
+
+/// Stdout of `program args` with `LANG=C`, shared by every window. Only
+/// successful runs are kept. The lock is held while apt runs, so a second
+/// window asking the same waits for the first answer instead of starting
+/// its own.
+pub fn query(program: &str, args: &[&str]) -> io::Result<String> {
+    let argv: Vec<String> = std::iter::once(program).chain(args.iter().copied()).map(str::to_string).collect();
+    let mut cache = CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let cache = cache.get_or_insert_with(|| MetadataCache::new(TTL));
+
+    let stamp = current_stamp();
+    if let Some(stdout) = cache.get(&argv, Instant::now(), stamp) {
+        return Ok(stdout);
+    }
+
+    let output = Command::new(program).args(args).env("LANG", "C").output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if output.status.success() {
+        cache.insert(argv, stdout.clone(), Instant::now(), stamp);
+    }
+    Ok(stdout)
+}
@@ -5,6 +5,11 @@ pub enum UpdateType {
     Security,
     Software,
     Kernel,
+    Firmware,
+    /// A Flatpak application or runtime rather than an APT package
+    Flatpak,
+    /// A snap refreshed through snapd
+    Snap,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -13,5 +18,26 @@ pub struct PackageUpdate {
     pub current_version: String,
     pub new_version: String,
     pub update_type: UpdateType,
-    pub size: String, // Paket boyutu
+    /// Offered by a security archive, also when it is listed as a kernel
+    /// or firmware update
+    pub from_security: bool,
+    /// Download size in bytes, if apt knows it
+    pub size: Option<u64>,
+    pub first_seen: Option<u64>, // Unix time the update was first noticed
+}
+
+/// Largest downloads first; packages of unknown size go last
+pub fn sort_by_size(packages: &mut [PackageUpdate]) {
+    packages.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+}
+
+/// Total download size of the packages in `names` and how many of them
+/// have no known size
+pub fn total_size(packages: &[PackageUpdate], names: &[String]) -> (u64, usize) {
+    packages.iter()
+        .filter(|pkg| names.contains(&pkg.name))
+        .fold((0, 0), |(total, unknown), pkg| match pkg.size {
+            Some(size) => (total + size, unknown),
+            None => (total, unknown + 1),
+        })
 }
@@ -0,0 +1,229 @@
+// src/motd.rs
+
+use crate::error::{Context, Error, Result};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::apt;
+use crate::install;
+use crate::model::UpdateType;
+use crate::paths;
+use crate::privilege;
+use crate::util::shell_quote;
+
+/// Command line switch that prints the login summary and exits
+pub const MOTD_ARG: &str = "--motd";
+
+/// Script run by pam_motd through update-motd(5) on every login
+pub const SYSTEM_SCRIPT_PATH: &str = "/etc/update-motd.d/92-meaupdater";
+
+const GREETING_START: &str = "# >>> meaupdater login summary >>>";
+const GREETING_END: &str = "# <<< meaupdater login summary <<<";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct UpdateSummary {
+    pub total: usize,
+    pub security: usize,
+    pub kernel: usize,
+}
+
+/// Counts the upgradable packages in `apt list --upgradable` output
+pub fn summarize(apt_list_output: &str) -> UpdateSummary {
+    let mut summary = UpdateSummary::default();
+
+    for line in apt_list_output.lines() {
+        if !line.contains("[upgradable from:") {
+            continue;
+        }
+        let repo = line.split_whitespace().next().unwrap_or("");
+        let name = repo.split('/').next().unwrap_or("");
+
+        summary.total += 1;
+        match apt::determine_update_type(name, repo) {
+            UpdateType::Security => summary.security += 1,
+            UpdateType::Kernel => summary.kernel += 1,
+            UpdateType::Software | UpdateType::Firmware | UpdateType::Flatpak | UpdateType::Snap => {}
+        }
+    }
+
+    summary
+}
+
+pub fn format_summary(summary: &UpdateSummary) -> String {
+    if summary.total == 0 {
+        return "MeaUpdater: the system is up to date.".to_string();
+    }
+
+    let mut details = Vec::new();
+    if summary.security > 0 {
+        details.push(format!("{} security", summary.security));
+    }
+    if summary.kernel > 0 {
+        details.push(format!("{} kernel", summary.kernel));
+    }
+
+    let noun = if summary.total == 1 { "update" } else { "updates" };
+    if details.is_empty() {
+        format!("MeaUpdater: {} {} can be installed.", summary.total, noun)
+    } else {
+        format!("MeaUpdater: {} {} can be installed ({}).", summary.total, noun, details.join(", "))
+    }
+}
+
+/// Headless check shared by the login hooks and the dialog preview; it
+/// only reads the current package lists and never refreshes them
+pub fn current_summary() -> Result<String> {
+    let output = Command::new("apt")
+        .args(&["list", "--upgradable"])
+        .env("LANG", "C")
+        .output()
+        .context("Could not run `apt list --upgradable`")?;
+
+    Ok(format_summary(&summarize(&String::from_utf8_lossy(&output.stdout))))
+}
+
+pub fn print_summary() -> i32 {
+    match current_summary() {
+        Ok(summary) => {
+            println!("{}", summary);
+            0
+        }
+        Err(_) => 1,
+    }
+}
+
+fn executable() -> Result<String> {
+    let exe = std::env::current_exe().context("Could not locate the MeaUpdater executable")?;
+    Ok(exe.to_string_lossy().to_string())
+}
+
+pub fn system_script(executable: &str) -> String {
+    format!(
+        "#!/bin/sh\n# Managed by MeaUpdater, remove it from the Login Summary dialog.\n[ -x {exe} ] && {exe} {arg} 2>/dev/null\nexit 0\n",
+        exe = shell_quote(executable),
+        arg = MOTD_ARG
+    )
+}
+
+/// Checks that only root can change `path` or any directory above it, as
+/// the login hook runs it as root. A copy installed with `--prefix ~/.local`
+/// would let any program of the user run code as root at the next login.
+pub fn check_root_owned(path: &Path) -> Result<()> {
+    let path = path.canonicalize().with_context(|| format!("Could not resolve {}", path.display()))?;
+    for entry in path.ancestors() {
+        let metadata = fs::metadata(entry).with_context(|| format!("Could not read {}", entry.display()))?;
+        if metadata.uid() != 0 || metadata.mode() & 0o022 != 0 {
+            return Err(Error::Other(format!(
+                "{} can be changed by users other than root, so it is not run at login for all users. Install MeaUpdater system-wide first, e.g. with `sudo meaupdater {}`.",
+                entry.display(),
+                install::INSTALL_ARG
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Appends the greeting block to a shell startup file, replacing an old one
+pub fn add_greeting(content: &str, executable: &str) -> String {
+    let mut result = remove_greeting(content);
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(&format!(
+        "{}\n[ -x {exe} ] && {exe} {arg} 2>/dev/null\n{}\n",
+        GREETING_START,
+        GREETING_END,
+        exe = shell_quote(executable),
+        arg = MOTD_ARG
+    ));
+    result
+}
+
+pub fn remove_greeting(content: &str) -> String {
+    let mut result = String::new();
+    let mut inside = false;
+
+    for line in content.lines() {
+        if line.trim() == GREETING_START {
+            inside = true;
+        } else if line.trim() == GREETING_END {
+            inside = false;
+        } else if !inside {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+
+    result
+}
+
+pub fn has_greeting(content: &str) -> bool {
+    content.lines().any(|line| line.trim() == GREETING_START)
+}
+
+fn shell_rc_file() -> Option<PathBuf> {
+    Some(PathBuf::from(std::env::var("HOME").ok()?).join(".bashrc"))
+}
+
+pub fn system_script_installed() -> bool {
+    fs::metadata(SYSTEM_SCRIPT_PATH).is_ok()
+}
+
+pub fn shell_greeting_installed() -> bool {
+    shell_rc_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| has_greeting(&content))
+        .unwrap_or(false)
+}
+
+pub fn install_system_script() -> Result<()> {
+    let executable = executable()?;
+    check_root_owned(Path::new(&executable))?;
+    let temp_file = paths::write_runtime_file("motd-script", system_script(&executable).as_bytes())?;
+
+    let status = privilege::elevated_command()
+        .arg("install")
+        .arg("-m")
+        .arg("755")
+        .arg(&temp_file)
+        .arg(SYSTEM_SCRIPT_PATH)
+        .status()
+        .context("Login summary script could not be installed")?;
+    let _ = fs::remove_file(&temp_file);
+
+    if !status.success() {
+        return Err(Error::from_status("Login summary script installation", &status, ""));
+    }
+    Ok(())
+}
+
+pub fn remove_system_script() -> Result<()> {
+    let status = privilege::elevated_command()
+        .arg("sh")
+        .arg("-c")
+        .arg(&format!("rm -f {}", SYSTEM_SCRIPT_PATH))
+        .status()
+        .context("Login summary script could not be removed")?;
+
+    if !status.success() {
+        return Err(Error::from_status("Login summary script removal", &status, ""));
+    }
+    Ok(())
+}
+
+pub fn set_shell_greeting(enabled: bool) -> Result<()> {
+    let path = shell_rc_file().context("HOME is not set")?;
+    let content = fs::read_to_string(&path).unwrap_or_default();
+
+    let updated = if enabled {
+        add_greeting(&content, &executable()?)
+    } else {
+        remove_greeting(&content)
+    };
+
+    if updated != content {
+        fs::write(&path, updated).with_context(|| format!("Could not update {}", path.display()))?;
+    }
+    Ok(())
+}
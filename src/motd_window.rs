@@ -0,0 +1,110 @@
+// src/motd_window.rs
+
+use crate::motd;
+use gtk::prelude::*;
+use gtk::{
+    ApplicationWindow, Box as GtkBox, CheckButton, Orientation, HeaderBar, Label, Dialog,
+    MessageDialog, ButtonsType, MessageType, Separator,
+};
+use crate::error::Result;
+
+/// Lets the user show pending update counts at login on headless machines
+pub struct MotdWindow {
+    window: Dialog,
+}
+
+impl MotdWindow {
+    /// In `check_only` mode the system-wide message, which is installed as
+    /// root, is not offered
+    pub fn new(parent: &ApplicationWindow, check_only: bool) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Login Summary")
+            .default_width(560)
+            .build();
+
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some("🖥️ Login Summary"))));
+        window.set_titlebar(Some(&header_bar));
+
+        let main_vbox = GtkBox::new(Orientation::Vertical, 12);
+        main_vbox.set_margin_top(16);
+        main_vbox.set_margin_bottom(16);
+        main_vbox.set_margin_start(16);
+        main_vbox.set_margin_end(16);
+
+        let intro = Label::new(Some(
+            "Show the number of pending updates when someone logs in, for example over SSH. \
+             The summary only reads the current package lists and never refreshes them.",
+        ));
+        intro.set_wrap(true);
+        intro.set_halign(gtk::Align::Start);
+        main_vbox.append(&intro);
+
+        let summary = motd::current_summary().unwrap_or_else(|e| format!("unavailable ({})", e));
+        let preview = Label::new(Some(&format!("Preview: {}", summary)));
+        preview.set_halign(gtk::Align::Start);
+        preview.set_selectable(true);
+        preview.add_css_class("dim-label");
+        main_vbox.append(&preview);
+
+        main_vbox.append(&Separator::new(Orientation::Horizontal));
+
+        let system_check = CheckButton::with_label(&format!(
+            "Message of the day for all users ({})",
+            motd::SYSTEM_SCRIPT_PATH
+        ));
+        system_check.set_active(motd::system_script_installed());
+        system_check.set_visible(!check_only);
+        main_vbox.append(&system_check);
+
+        let shell_check = CheckButton::with_label("Greeting in my shell (~/.bashrc)");
+        shell_check.set_active(motd::shell_greeting_installed());
+        main_vbox.append(&shell_check);
+
+        window.content_area().append(&main_vbox);
+
+        let window_system = window.clone();
+        system_check.connect_toggled(move |check| {
+            let result = if check.is_active() {
+                motd::install_system_script()
+            } else {
+                motd::remove_system_script()
+            };
+            Self::handle_result(&window_system, check, motd::system_script_installed(), result);
+        });
+
+        let window_shell = window.clone();
+        shell_check.connect_toggled(move |check| {
+            let result = motd::set_shell_greeting(check.is_active());
+            Self::handle_result(&window_shell, check, motd::shell_greeting_installed(), result);
+        });
+
+        Self { window }
+    }
+
+    /// Shows the error and puts the checkbox back to the real state
+    fn handle_result(parent: &Dialog, check: &CheckButton, installed: bool, result: Result<()>) {
+        if let Err(e) = result {
+            let dialog = MessageDialog::builder()
+                .transient_for(parent)
+                .modal(true)
+                .message_type(MessageType::Error)
+                .buttons(ButtonsType::Ok)
+                .text("❌ Login summary could not be changed")
+                .secondary_text(&e.to_string())
+                .build();
+            dialog.connect_response(|dlg, _| dlg.close());
+            dialog.show();
+        }
+
+        if check.is_active() != installed {
+            check.set_active(installed);
+        }
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
@@ -0,0 +1,48 @@
+// src/notifications.rs
+
+use crate::paths;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+fn notified_file() -> Option<PathBuf> {
+    paths::state_file("notified_updates")
+}
+
+/// Packages in `current` that were not part of the last notification
+pub fn new_packages(previous: &HashSet<String>, current: &[String]) -> Vec<String> {
+    current.iter()
+        .filter(|name| !previous.contains(*name))
+        .cloned()
+        .collect()
+}
+
+pub fn load_notified() -> HashSet<String> {
+    notified_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| content.lines().map(str::to_string).filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// Remembers the current set, so packages that go away and come back count as new
+pub fn save_notified(current: &[String]) {
+    let path = match notified_file() {
+        Some(path) => path,
+        None => return,
+    };
+    if let Some(parent) = path.parent() {
+        let _ = paths::ensure_dir(parent);
+    }
+    let mut names = current.to_vec();
+    names.sort();
+    if let Err(e) = fs::write(&path, names.join("\n")) {
+        eprintln!("Could not save notification state: {}", e);
+    }
+}
+
+/// Updates the remembered set and tells whether a notification is warranted
+pub fn should_notify(current: &[String]) -> bool {
+    let fresh = new_packages(&load_notified(), current);
+    save_notified(current);
+    !fresh.is_empty()
+}
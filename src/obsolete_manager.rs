@@ -0,0 +1,183 @@
+// src/obsolete_manager.rs
+
+use crate::error::{Context, Error, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use crate::apt::{self, Transaction};
+use crate::paths;
+
+/// Installed package whose installed version did not come from any
+/// configured repository (local .debs, removed PPAs, old releases)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalPackage {
+    pub name: String,
+    pub version: String,
+    pub architecture: String,
+    /// Newest version a repository offers, if any
+    pub candidate: Option<String>,
+}
+
+impl LocalPackage {
+    /// No enabled repository provides this package at all
+    pub fn is_obsolete(&self) -> bool {
+        self.candidate.is_none()
+    }
+
+    pub fn origin_description(&self) -> String {
+        match &self.candidate {
+            None => "Obsolete: no repository provides it".to_string(),
+            Some(candidate) => format!("Origin unknown: repositories offer {}", candidate),
+        }
+    }
+}
+
+fn kept_file() -> Option<PathBuf> {
+    paths::state_file("kept_obsolete")
+}
+
+/// Parses the `[installed,local]` entries of `apt list --installed`, e.g.
+/// `libssl1.1/now 1.1.1n-0+deb11u5 amd64 [installed,local]`
+pub fn parse_local_list(output: &str) -> Vec<LocalPackage> {
+    output.lines()
+        .filter(|line| line.contains('/') && line.contains("[installed") && line.contains("local]"))
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 3 {
+                return None;
+            }
+            Some(LocalPackage {
+                name: parts[0].split('/').next()?.to_string(),
+                version: parts[1].to_string(),
+                architecture: parts[2].to_string(),
+                candidate: None,
+            })
+        })
+        .collect()
+}
+
+/// Reads the newest version each package has in a repository from the
+/// version tables of `apt-cache policy` output. The candidate alone is not
+/// enough: a pinned or newer local version is the candidate itself and
+/// would hide what the repositories offer. Missing entries mean no
+/// repository has the package.
+pub fn parse_repository_versions(output: &str) -> HashMap<String, String> {
+    apt::parse_version_table(output)
+        .into_iter()
+        // The table lists the newest version first
+        .filter_map(|(name, versions)| versions.into_iter().next().map(|newest| (name, newest.version)))
+        .collect()
+}
+
+/// Name without its trailing version, used to look for a successor:
+/// `libssl1.1` becomes `libssl`, `python3.9-minimal` becomes `python3`
+pub fn replacement_stem(name: &str) -> String {
+    let base = name.split('-').next().unwrap_or(name);
+    let stem = base.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if stem.len() < 3 {
+        base.to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+/// `apt-cache search` pattern for names starting with `stem`. Package
+/// names may contain `+` and `.`, which are special in a regex.
+pub fn replacement_pattern(stem: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in stem.chars() {
+        if !c.is_ascii_alphanumeric() && c != '-' {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern
+}
+
+/// Packages the user chose to keep; they are hidden from the list
+pub fn load_kept() -> HashSet<String> {
+    kept_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| content.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+pub fn keep_package(name: &str) -> Result<()> {
+    let path = kept_file().context("Home directory not found")?;
+    let mut kept = load_kept();
+    kept.insert(name.to_string());
+
+    let mut names: Vec<String> = kept.into_iter().collect();
+    names.sort();
+    if let Some(parent) = path.parent() {
+        paths::ensure_dir(parent)?;
+    }
+    fs::write(&path, names.join("\n")).context("Kept packages could not be saved")?;
+    Ok(())
+}
+
+/// Foreign and obsolete packages, minus the ones the user chose to keep
+pub fn get_local_packages() -> Result<Vec<LocalPackage>> {
+    let output = Command::new("apt")
+        .args(&["list", "--installed"])
+        .env("LANG", "C")
+        .output()
+        .context("apt list could not be run")?;
+
+    if !output.status.success() {
+        return Err(Error::from_status("apt list", &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let kept = load_kept();
+    let mut packages: Vec<LocalPackage> = parse_local_list(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter(|pkg| !kept.contains(&pkg.name))
+        .collect();
+
+    if packages.is_empty() {
+        return Ok(packages);
+    }
+
+    let names: Vec<&str> = packages.iter().map(|pkg| pkg.name.as_str()).collect();
+    let policy = Command::new("apt-cache")
+        .arg("policy")
+        .args(&names)
+        .env("LANG", "C")
+        .output()
+        .context("apt-cache policy could not be run")?;
+    if !policy.status.success() {
+        return Err(Error::from_status("apt-cache policy", &policy.status, &String::from_utf8_lossy(&policy.stderr)));
+    }
+    let offered = parse_repository_versions(&String::from_utf8_lossy(&policy.stdout));
+
+    for pkg in packages.iter_mut() {
+        pkg.candidate = offered.get(&pkg.name).cloned();
+    }
+
+    // Obsolete packages first, they are the ones that never get updates
+    packages.sort_by(|a, b| b.is_obsolete().cmp(&a.is_obsolete()).then(a.name.cmp(&b.name)));
+    Ok(packages)
+}
+
+/// Candidate successors from the enabled repositories
+pub fn find_replacements(pkg: &LocalPackage) -> Result<Vec<String>> {
+    let stem = replacement_stem(&pkg.name);
+    let output = Command::new("apt-cache")
+        .args(&["search", "--names-only", &replacement_pattern(&stem)])
+        .output()
+        .context("apt-cache could not be run")?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.starts_with(&format!("{} ", pkg.name)))
+        .map(|line| line.to_string())
+        .take(20)
+        .collect())
+}
+
+pub fn remove_package(name: &str) -> Result<()> {
+    Transaction::remove(&[name.to_string()])
+        .run()
+        .with_context(|| format!("Removing {}", name))
+}
@@ -0,0 +1,244 @@
+// src/obsolete_window.rs
+
+use crate::error;
+use crate::obsolete_manager::{self, LocalPackage};
+use crate::sessions;
+use gtk::prelude::*;
+use gtk::{
+    ApplicationWindow, Box as GtkBox, Button, ListBox, ListBoxRow, ScrolledWindow,
+    Orientation, HeaderBar, Label, Dialog, MessageDialog, ButtonsType, MessageType,
+    Separator,
+};
+
+pub struct ObsoleteWindow {
+    window: Dialog,
+}
+
+impl ObsoleteWindow {
+    pub fn new(parent: &ApplicationWindow) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Foreign & Obsolete Packages")
+            .default_width(800)
+            .default_height(500)
+            .build();
+
+        // Header bar
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some("🧹 Foreign & Obsolete Packages"))));
+        window.set_titlebar(Some(&header_bar));
+
+        let main_vbox = GtkBox::new(Orientation::Vertical, 0);
+
+        let info_label = Label::new(Some(
+            "These packages were not installed from any of your configured repositories, \
+            for example local .deb files or packages from removed PPAs. They will not receive updates."
+        ));
+        info_label.set_wrap(true);
+        info_label.set_halign(gtk::Align::Start);
+        info_label.set_margin_top(16);
+        info_label.set_margin_bottom(12);
+        info_label.set_margin_start(16);
+        info_label.set_margin_end(16);
+        main_vbox.append(&info_label);
+
+        let refresh_btn = Button::with_label("🔄 Refresh");
+        refresh_btn.add_css_class("header-button");
+        refresh_btn.add_css_class("refresh-button");
+        refresh_btn.set_halign(gtk::Align::Center);
+        refresh_btn.set_margin_bottom(12);
+        main_vbox.append(&refresh_btn);
+
+        main_vbox.append(&Separator::new(Orientation::Horizontal));
+
+        // Main list
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .margin_top(8)
+            .margin_bottom(16)
+            .margin_start(16)
+            .margin_end(16)
+            .build();
+
+        let listbox = ListBox::new();
+        listbox.set_selection_mode(gtk::SelectionMode::None);
+        scrolled_window.set_child(Some(&listbox));
+        main_vbox.append(&scrolled_window);
+
+        window.content_area().append(&main_vbox);
+
+        let listbox_clone = listbox.clone();
+        refresh_btn.connect_clicked(move |_| {
+            Self::refresh_packages(&listbox_clone);
+        });
+
+        Self::refresh_packages(&listbox);
+
+        Self { window }
+    }
+
+    fn refresh_packages(listbox: &ListBox) {
+        while let Some(child) = listbox.first_child() {
+            listbox.remove(&child);
+        }
+
+        match obsolete_manager::get_local_packages() {
+            Ok(packages) if packages.is_empty() => {
+                let label = Label::new(Some("✅ Every installed package comes from a configured repository."));
+                label.set_margin_top(24);
+                listbox.append(&label);
+            }
+            Ok(packages) => {
+                for pkg in packages {
+                    listbox.append(&Self::create_package_row(listbox, pkg));
+                }
+            }
+            Err(e) => {
+                eprintln!("Obsolete package check error: {}", e);
+                let label = Label::new(Some(&format!("❌ {}", e)));
+                label.set_margin_top(24);
+                listbox.append(&label);
+            }
+        }
+    }
+
+    fn create_package_row(listbox: &ListBox, pkg: LocalPackage) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        row.add_css_class("package-row");
+
+        let hbox = GtkBox::new(Orientation::Horizontal, 12);
+        hbox.set_margin_top(8);
+        hbox.set_margin_bottom(8);
+        hbox.set_margin_start(8);
+        hbox.set_margin_end(8);
+
+        let name_box = GtkBox::new(Orientation::Vertical, 2);
+        name_box.set_hexpand(true);
+
+        let name_label = Label::new(Some(&pkg.name));
+        name_label.set_halign(gtk::Align::Start);
+        name_label.add_css_class("package-name");
+
+        let origin_label = Label::new(Some(&pkg.origin_description()));
+        origin_label.set_halign(gtk::Align::Start);
+        origin_label.add_css_class("dim-label");
+
+        name_box.append(&name_label);
+        name_box.append(&origin_label);
+
+        let version_label = Label::new(Some(&format!("{} ({})", pkg.version, pkg.architecture)));
+        version_label.set_width_chars(25);
+        version_label.add_css_class("version-info");
+
+        let keep_btn = Button::with_label("Keep");
+        keep_btn.set_tooltip_text(Some("Hide this package from the list"));
+        keep_btn.add_css_class("flat");
+
+        let replace_btn = Button::from_icon_name("system-search-symbolic");
+        replace_btn.set_tooltip_text(Some("Find replacements"));
+        replace_btn.add_css_class("flat");
+
+        let remove_btn = Button::from_icon_name("user-trash-symbolic");
+        remove_btn.set_tooltip_text(Some("Remove"));
+        remove_btn.add_css_class("flat");
+        remove_btn.add_css_class("destructive-action");
+
+        let pkg_keep = pkg.clone();
+        let listbox_keep = listbox.clone();
+        keep_btn.connect_clicked(move |_| {
+            if let Err(e) = obsolete_manager::keep_package(&pkg_keep.name) {
+                eprintln!("Could not keep {}: {}", pkg_keep.name, e);
+            }
+            Self::refresh_packages(&listbox_keep);
+        });
+
+        let pkg_replace = pkg.clone();
+        replace_btn.connect_clicked(move |btn| {
+            if let Some(window) = btn.root().and_downcast::<gtk::Window>() {
+                Self::show_replacements(&window, &pkg_replace);
+            }
+        });
+
+        let pkg_remove = pkg.clone();
+        let listbox_remove = listbox.clone();
+        remove_btn.connect_clicked(move |btn| {
+            if let Some(window) = btn.root().and_downcast::<gtk::Window>() {
+                Self::show_remove_confirmation(&window, &pkg_remove, &listbox_remove);
+            }
+        });
+
+        hbox.append(&name_box);
+        hbox.append(&version_label);
+        hbox.append(&keep_btn);
+        hbox.append(&replace_btn);
+        hbox.append(&remove_btn);
+
+        row.set_child(Some(&hbox));
+        row
+    }
+
+    fn show_replacements(parent: &gtk::Window, pkg: &LocalPackage) {
+        let text = match obsolete_manager::find_replacements(pkg) {
+            Ok(candidates) if candidates.is_empty() => "No similarly named packages were found.".to_string(),
+            Ok(candidates) => candidates.join("\n"),
+            Err(e) => format!("❌ {}", e),
+        };
+
+        let dialog = MessageDialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .message_type(MessageType::Info)
+            .buttons(ButtonsType::Ok)
+            .text(&format!("🔍 Possible replacements for {}", pkg.name))
+            .secondary_text(&text)
+            .build();
+        dialog.connect_response(|dlg, _| dlg.close());
+        dialog.show();
+    }
+
+    fn show_remove_confirmation(parent: &gtk::Window, pkg: &LocalPackage, listbox: &ListBox) {
+        let dialog = MessageDialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .message_type(MessageType::Warning)
+            .buttons(ButtonsType::YesNo)
+            .text("🗑️ Remove Package")
+            .secondary_text(&format!(
+                "Remove '{}'?\n\n{}",
+                pkg.name,
+                if pkg.is_obsolete() {
+                    "It can not be reinstalled from your current repositories."
+                } else {
+                    "Reinstalling it later will use the repository version instead."
+                }
+            ))
+            .build();
+
+        let pkg_clone = pkg.clone();
+        let listbox_clone = listbox.clone();
+        let parent_clone = parent.clone();
+        dialog.connect_response(move |dialog, response| {
+            dialog.close();
+            if response != gtk::ResponseType::Yes {
+                return;
+            }
+            let pkg = pkg_clone.clone();
+            let listbox = listbox_clone.clone();
+            let parent = parent_clone.clone();
+            let action = format!("remove {}", pkg.name);
+            sessions::confirm_disruptive_action(&parent_clone, &action, move || {
+                if let Err(e) = obsolete_manager::remove_package(&pkg.name) {
+                    error::show_error_dialog(&parent, "❌ Package could not be removed", &e, None);
+                }
+                Self::refresh_packages(&listbox);
+            });
+        });
+        dialog.show();
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
@@ -0,0 +1,193 @@
+// src/offline_update.rs
+
+use crate::apt::Transaction;
+use crate::error::{Context, Error, Result};
+use crate::format;
+use crate::privilege;
+use crate::sysroot;
+use std::fs;
+
+/// Holds the downloaded packages and the list of updates to install
+pub const STAGING_DIR: &str = "/var/lib/meaupdater/offline-update";
+/// While this link points to the staging directory, systemd boots into
+/// `system-update.target` and the staged updates are installed
+pub const TRIGGER: &str = "/system-update";
+
+const UNIT_NAME: &str = "meaupdater-offline-update.service";
+const UNIT_DIR: &str = "/etc/systemd/system";
+const MARKER: &str = "MEAUPDATER_EOF";
+
+/// What the header bar shows about staged updates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OfflineStatus {
+    Idle,
+    /// Waiting for the next restart
+    Staged(Vec<String>),
+    /// The last offline update failed; its apt output is in `log`
+    Failed { log: String },
+}
+
+impl OfflineStatus {
+    /// Text of the header bar indicator, `None` hides it
+    pub fn indicator_label(&self) -> Option<String> {
+        match self {
+            OfflineStatus::Idle => None,
+            OfflineStatus::Staged(packages) => Some(format!("⏳ {} staged for restart", format::plural(packages.len() as u64, "update", "updates"))),
+            OfflineStatus::Failed { .. } => Some("⚠️ Offline update failed".to_string()),
+        }
+    }
+}
+
+fn staged_file(name: &str) -> String {
+    format!("{}/{}", STAGING_DIR, name)
+}
+
+/// Where `apt-get --download-only` puts the staged packages
+pub fn archives_dir() -> String {
+    staged_file("archives")
+}
+
+/// One package name per line
+pub fn manifest_text(packages: &[String]) -> String {
+    packages.iter().map(|name| format!("{}\n", name)).collect()
+}
+
+pub fn parse_manifest(text: &str) -> Vec<String> {
+    text.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string).collect()
+}
+
+/// Works out the status from the target of the trigger link, the staged
+/// package list and the exit code the last offline update left behind
+pub fn parse_status(trigger_target: Option<&str>, manifest: Option<&str>, result: Option<&str>) -> OfflineStatus {
+    let staged = manifest.map(parse_manifest).unwrap_or_default();
+    if trigger_target == Some(STAGING_DIR) && !staged.is_empty() {
+        return OfflineStatus::Staged(staged);
+    }
+    match result.map(str::trim) {
+        Some(code) if !code.is_empty() && code != "0" => OfflineStatus::Failed { log: staged_file("apply.log") },
+        _ => OfflineStatus::Idle,
+    }
+}
+
+pub fn status() -> OfflineStatus {
+    let target = fs::read_link(sysroot::path(TRIGGER)).ok().map(|target| target.display().to_string());
+    let manifest = fs::read_to_string(sysroot::path(&staged_file("packages"))).ok();
+    let result = fs::read_to_string(sysroot::path(&staged_file("result"))).ok();
+    parse_status(target.as_deref(), manifest.as_deref(), result.as_deref())
+}
+
+/// Runs in `system-update.target`. The trigger goes first, so a failing
+/// update cannot keep the machine rebooting into it.
+pub fn apply_script() -> String {
+    format!(
+        r#"#!/bin/sh
+dir={dir}
+[ "$(readlink {trigger})" = "$dir" ] || exit 0
+rm -f {trigger}
+export DEBIAN_FRONTEND=noninteractive
+plymouth display-message --text="Installing updates, do not turn off the computer..." 2>/dev/null
+apt-get install -y --only-upgrade --no-download -o Dir::Cache::archives="$dir/archives" -o Dpkg::Options::=--force-confdef -o Dpkg::Options::=--force-confold $(cat "$dir/packages") >"$dir/apply.log" 2>&1
+result=$?
+[ $result -eq 0 ] || dpkg --configure -a >>"$dir/apply.log" 2>&1
+echo $result >"$dir/result"
+rm -rf "$dir/archives" "$dir/packages"
+action=$(cat "$dir/action" 2>/dev/null)
+rm -f "$dir/action"
+[ "$action" = poweroff ] || action=reboot
+systemctl "$action"
+"#,
+        dir = STAGING_DIR,
+        trigger = TRIGGER,
+    )
+}
+
+/// The service `system-update.target` starts to install the updates
+pub fn unit_file() -> String {
+    format!(
+        "[Unit]\n\
+         Description=Install the updates staged by MeaUpdater\n\
+         DefaultDependencies=no\n\
+         Requires=sysinit.target\n\
+         After=sysinit.target system-update-pre.target\n\
+         Before=system-update.target\n\
+         ConditionPathExists={trigger}\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart=/bin/sh {script}\n",
+        trigger = TRIGGER,
+        script = staged_file("apply.sh"),
+    )
+}
+
+fn write_file(path: &str, content: &str) -> String {
+    format!("cat > {} <<'{marker}'\n{}{marker}\n", path, content, marker = MARKER)
+}
+
+/// Stages the downloaded packages: writes the package list, the apply
+/// script and its service, then sets the trigger
+pub fn stage_script(packages: &[String]) -> String {
+    let mut script = String::from("set -e\n");
+    script.push_str(&write_file(&staged_file("packages"), &manifest_text(packages)));
+    script.push_str(&write_file(&staged_file("apply.sh"), &apply_script()));
+    script.push_str(&write_file(&format!("{}/{}", UNIT_DIR, UNIT_NAME), &unit_file()));
+    script.push_str(&format!("mkdir -p {}/system-update.target.wants\n", UNIT_DIR));
+    script.push_str(&format!("ln -sf ../{name} {}/system-update.target.wants/{name}\n", UNIT_DIR, name = UNIT_NAME));
+    script.push_str(&format!("rm -f {} {}\n", staged_file("result"), staged_file("action")));
+    script.push_str(&format!("ln -sfn {} {}\n", STAGING_DIR, TRIGGER));
+    script
+}
+
+/// Downloads `packages` into the staging directory and stages them for
+/// the next restart, in one privileged run
+pub fn stage_transaction(packages: &[String]) -> Transaction {
+    let script = stage_script(packages);
+    Transaction::install(packages)
+        .only_upgrade()
+        .refresh_lists()
+        .download_only(&archives_dir())
+        .run_before(&["mkdir", "-p", &format!("{}/partial", archives_dir())])
+        .run_after(&["sh", "-c", &script])
+}
+
+/// Checks that the staging went through; the last step only logs failures
+pub fn verify_staged() -> Result<Vec<String>> {
+    match status() {
+        OfflineStatus::Staged(packages) => Ok(packages),
+        _ => Err(Error::Other("The updates were downloaded, but could not be staged for the next restart.".to_string())),
+    }
+}
+
+fn run_elevated(what: &str, script: &str) -> Result<()> {
+    if !privilege::can_run_commands() {
+        return Err(Error::Other(privilege::HELPER_UNSUPPORTED.to_string()));
+    }
+    let output = privilege::elevated_command()
+        .args(["sh", "-c", script])
+        .output()
+        .with_context(|| format!("Could not start {}", what))?;
+    if !output.status.success() {
+        return Err(Error::from_status(what, &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(())
+}
+
+/// Drops the staged updates and the result of the last offline update
+pub fn discard() -> Result<()> {
+    let script = format!(
+        "rm -f {trigger} {unit_dir}/system-update.target.wants/{name} && rm -rf {dir}",
+        trigger = TRIGGER,
+        unit_dir = UNIT_DIR,
+        name = UNIT_NAME,
+        dir = STAGING_DIR,
+    );
+    run_elevated("Discarding the staged updates", &script)
+}
+
+/// Restarts into the offline update. With `power_off` the computer shuts
+/// down once the updates are installed.
+pub fn restart(power_off: bool) -> Result<()> {
+    let action = if power_off { "poweroff" } else { "reboot" };
+    let script = format!("echo {} > {} && systemctl reboot", action, staged_file("action"));
+    run_elevated("Restarting to install the updates", &script)
+}
@@ -0,0 +1,128 @@
+// src/package_search.rs
+
+use crate::error::{Context, Error, Result};
+use std::process::Command;
+use crate::privilege;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub package: String,
+    /// Short description for name searches, matching file path for file searches
+    pub detail: String,
+}
+
+/// Where file search results came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileSearchSource {
+    /// apt-file: every package in the enabled repositories
+    AptFile,
+    /// dpkg -S: installed packages only
+    Installed,
+}
+
+fn apt_file_available() -> bool {
+    Command::new("which")
+        .arg("apt-file")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Parses `apt-cache search` output (`name - description`)
+pub fn parse_name_results(output: &str) -> Vec<SearchResult> {
+    output.lines()
+        .filter_map(|line| line.split_once(" - "))
+        .map(|(name, description)| SearchResult {
+            package: name.trim().to_string(),
+            detail: description.trim().to_string(),
+        })
+        .collect()
+}
+
+/// Parses `package: /path` lines from apt-file or dpkg -S. dpkg lists
+/// several packages as `pkg1, pkg2: /path`.
+pub fn parse_file_results(output: &str) -> Vec<SearchResult> {
+    let mut results = Vec::new();
+
+    for line in output.lines() {
+        let (packages, path) = match line.split_once(": ") {
+            Some(parts) => parts,
+            None => continue,
+        };
+        for package in packages.split(", ") {
+            if package.contains(' ') {
+                continue;
+            }
+            results.push(SearchResult {
+                package: package.to_string(),
+                detail: path.trim().to_string(),
+            });
+        }
+    }
+
+    results
+}
+
+/// Pattern for apt-file. A bare word is treated as a command name and only
+/// matched in the bin directories; anything with a slash is a path fragment.
+pub fn file_pattern(query: &str) -> (bool, String) {
+    if query.contains('/') {
+        (false, query.to_string())
+    } else {
+        let escaped: String = query.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c.to_string() } else { format!("\\{}", c) })
+            .collect();
+        (true, format!("/s?bin/{}$", escaped))
+    }
+}
+
+pub fn search_by_name(query: &str) -> Result<Vec<SearchResult>> {
+    let output = Command::new("apt-cache")
+        // "--" keeps a query such as "-o..." from being read as an option
+        .args(&["search", "--names-only", "--", query])
+        .output()
+        .context("apt-cache could not be run")?;
+
+    let mut results = parse_name_results(&String::from_utf8_lossy(&output.stdout));
+    results.truncate(200);
+    Ok(results)
+}
+
+/// Finds the packages that ship a file or command
+pub fn search_by_file(query: &str) -> Result<(FileSearchSource, Vec<SearchResult>)> {
+    let (source, output) = if apt_file_available() {
+        let (regexp, pattern) = file_pattern(query);
+        let mut command = Command::new("apt-file");
+        command.arg("search");
+        if regexp {
+            command.arg("--regexp");
+        }
+        let output = command.arg("--").arg(&pattern).output().context("apt-file could not be run")?;
+        (FileSearchSource::AptFile, output)
+    } else {
+        let pattern = if query.contains('/') { query.to_string() } else { format!("bin/{}", query) };
+        let output = Command::new("dpkg").args(&["-S", "--", &pattern]).output().context("dpkg could not be run")?;
+        (FileSearchSource::Installed, output)
+    };
+
+    let mut results = parse_file_results(&String::from_utf8_lossy(&output.stdout));
+    results.truncate(200);
+    Ok((source, results))
+}
+
+/// Downloads the Contents indexes apt-file searches in
+pub fn update_file_index() -> Result<()> {
+    if !apt_file_available() {
+        return Err(Error::Other("apt-file is not installed. Install it with: sudo apt install apt-file".to_string()));
+    }
+
+    let status = privilege::elevated_command()
+        .args(&["apt-file", "update"])
+        .status()
+        .context("apt-file update could not be run")?;
+
+    if !status.success() {
+        return Err(Error::from_status("apt-file update", &status, ""));
+    }
+    Ok(())
+}
@@ -0,0 +1,225 @@
+// src/package_search_window.rs
+
+use crate::app_state::{BusyState, Operation};
+use crate::package_search::{self, FileSearchSource, SearchResult};
+use crate::progress::ProgressWindow;
+use gtk::prelude::*;
+use gtk::{
+    ApplicationWindow, Box as GtkBox, Button, CheckButton, Entry, ListBox, ListBoxRow,
+    ScrolledWindow, Orientation, HeaderBar, Label, Dialog, MessageDialog, ButtonsType,
+    MessageType, Separator, glib,
+};
+use std::rc::Rc;
+
+/// Search packages by name, or by a file or command they provide
+pub struct PackageSearchWindow {
+    window: Dialog,
+}
+
+impl PackageSearchWindow {
+    pub fn new(parent: &ApplicationWindow, busy: Rc<BusyState>, on_installed: Rc<dyn Fn()>) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Package Search")
+            .default_width(850)
+            .default_height(550)
+            .build();
+
+        // Header bar
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some("🔍 Package Search"))));
+        window.set_titlebar(Some(&header_bar));
+
+        let main_vbox = GtkBox::new(Orientation::Vertical, 0);
+
+        // Search bar
+        let search_box = GtkBox::new(Orientation::Horizontal, 12);
+        search_box.set_margin_top(16);
+        search_box.set_margin_bottom(12);
+        search_box.set_margin_start(16);
+        search_box.set_margin_end(16);
+
+        let search_entry = Entry::new();
+        search_entry.set_hexpand(true);
+        search_entry.set_placeholder_text(Some("Package name, command or file path"));
+
+        let file_check = CheckButton::with_label("Search by file or command");
+        file_check.set_tooltip_text(Some("Find the package that provides a command like 'convert' or a file like '/usr/lib/libfoo.so'"));
+
+        let search_btn = Button::with_label("🔍 Search");
+        search_btn.add_css_class("refresh-button");
+
+        let index_btn = Button::with_label("🔄 Update File Index");
+        index_btn.set_tooltip_text(Some("Download the file lists used for file searches (apt-file update)"));
+
+        search_box.append(&search_entry);
+        search_box.append(&file_check);
+        search_box.append(&search_btn);
+        search_box.append(&index_btn);
+        main_vbox.append(&search_box);
+
+        let status_label = Label::new(None);
+        status_label.set_halign(gtk::Align::Start);
+        status_label.set_margin_start(16);
+        status_label.set_margin_bottom(8);
+        status_label.add_css_class("dim-label");
+        main_vbox.append(&status_label);
+
+        main_vbox.append(&Separator::new(Orientation::Horizontal));
+
+        // Results
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .margin_top(8)
+            .margin_bottom(16)
+            .margin_start(16)
+            .margin_end(16)
+            .build();
+
+        let listbox = ListBox::new();
+        listbox.set_selection_mode(gtk::SelectionMode::None);
+        scrolled_window.set_child(Some(&listbox));
+        main_vbox.append(&scrolled_window);
+
+        window.content_area().append(&main_vbox);
+
+        let install: Rc<dyn Fn(String)> = {
+            let parent = parent.clone();
+            let window = window.clone();
+            Rc::new(move |package: String| {
+                Self::install_package(&parent, &window, &busy, &on_installed, package);
+            })
+        };
+
+        let do_search = {
+            let entry = search_entry.clone();
+            let file_check = file_check.clone();
+            let listbox = listbox.clone();
+            let status_label = status_label.clone();
+            Rc::new(move || {
+                let query = entry.text().trim().to_string();
+                if query.is_empty() {
+                    return;
+                }
+                Self::run_search(&query, file_check.is_active(), &listbox, &status_label, &install);
+            })
+        };
+
+        let search_clicked = do_search.clone();
+        search_btn.connect_clicked(move |_| search_clicked());
+        search_entry.connect_activate(move |_| do_search());
+
+        let window_index = window.clone();
+        let status_index = status_label.clone();
+        index_btn.connect_clicked(move |_| {
+            status_index.set_text("Updating the file index, this can take a while...");
+            let (message_type, text) = match package_search::update_file_index() {
+                Ok(()) => (MessageType::Info, "✅ File index updated.".to_string()),
+                Err(e) => (MessageType::Error, format!("❌ {}", e)),
+            };
+            status_index.set_text("");
+            let dialog = MessageDialog::builder()
+                .transient_for(&window_index)
+                .modal(true)
+                .message_type(message_type)
+                .buttons(ButtonsType::Ok)
+                .text(&text)
+                .build();
+            dialog.connect_response(|dlg, _| dlg.close());
+            dialog.show();
+        });
+
+        Self { window }
+    }
+
+    fn run_search(query: &str, by_file: bool, listbox: &ListBox, status_label: &Label, install: &Rc<dyn Fn(String)>) {
+        while let Some(child) = listbox.first_child() {
+            listbox.remove(&child);
+        }
+
+        let result = if by_file {
+            package_search::search_by_file(query).map(|(source, results)| {
+                let note = match source {
+                    FileSearchSource::AptFile => "",
+                    FileSearchSource::Installed => " (installed packages only, install apt-file to search all packages)",
+                };
+                (results, note)
+            })
+        } else {
+            package_search::search_by_name(query).map(|results| (results, ""))
+        };
+
+        match result {
+            Ok((results, note)) => {
+                status_label.set_text(&format!("{} results{}", results.len(), note));
+                for result in results {
+                    listbox.append(&Self::create_result_row(&result, install));
+                }
+            }
+            Err(e) => status_label.set_text(&format!("❌ {}", e)),
+        }
+    }
+
+    fn create_result_row(result: &SearchResult, install: &Rc<dyn Fn(String)>) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        row.add_css_class("package-row");
+
+        let hbox = GtkBox::new(Orientation::Horizontal, 12);
+        hbox.set_margin_top(6);
+        hbox.set_margin_bottom(6);
+        hbox.set_margin_start(8);
+        hbox.set_margin_end(8);
+
+        let name_label = Label::new(Some(&result.package));
+        name_label.set_width_chars(30);
+        name_label.set_halign(gtk::Align::Start);
+        name_label.set_xalign(0.0);
+        name_label.add_css_class("package-name");
+
+        let detail_label = Label::new(Some(&result.detail));
+        detail_label.set_hexpand(true);
+        detail_label.set_halign(gtk::Align::Start);
+        detail_label.set_ellipsize(pango::EllipsizeMode::End);
+        detail_label.add_css_class("version-info");
+
+        let install_btn = Button::from_icon_name("list-add-symbolic");
+        install_btn.set_tooltip_text(Some("Install"));
+        install_btn.add_css_class("flat");
+
+        let install = install.clone();
+        let package = result.package.clone();
+        install_btn.connect_clicked(move |_| install(package.clone()));
+
+        hbox.append(&name_label);
+        hbox.append(&detail_label);
+        hbox.append(&install_btn);
+        row.set_child(Some(&hbox));
+        row
+    }
+
+    fn install_package(parent: &ApplicationWindow, window: &Dialog, busy: &Rc<BusyState>, on_installed: &Rc<dyn Fn()>, package: String) {
+        if !busy.try_begin(Operation::Installing) {
+            return;
+        }
+        window.close();
+
+        let progress_window = ProgressWindow::new(parent);
+        progress_window.show();
+
+        let busy = busy.clone();
+        let on_installed = on_installed.clone();
+        glib::spawn_future_local(async move {
+            let result = progress_window.install_packages_with_progress(&[package]).await;
+            busy.finish();
+            if result.is_ok() {
+                on_installed();
+            }
+        });
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
@@ -0,0 +1,424 @@
+// src/packagekit.rs
+
+use crate::apt::{CancelToken, TransactionEvent};
+use crate::error::{Error, Result};
+use gio::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const BUS_NAME: &str = "org.freedesktop.PackageKit";
+const OBJECT_PATH: &str = "/org/freedesktop/PackageKit";
+const INTERFACE: &str = "org.freedesktop.PackageKit";
+const TRANSACTION_INTERFACE: &str = "org.freedesktop.PackageKit.Transaction";
+
+/// `PK_TRANSACTION_FLAG_ENUM_ONLY_TRUSTED`
+const FLAG_ONLY_TRUSTED: u64 = 1 << 1;
+/// Bits of `PK_FILTER_ENUM_INSTALLED`, `_NEWEST` and `_ARCH`
+const FILTER_INSTALLED: u64 = 1 << 2;
+const FILTER_NEWEST: u64 = 1 << 16;
+const FILTER_ARCH: u64 = 1 << 18;
+
+const INFO_INSTALLED: u32 = 1;
+const EXIT_SUCCESS: u32 = 1;
+const EXIT_CANCELLED: u32 = 3;
+/// `Percentage` while the backend cannot tell
+const PERCENTAGE_UNKNOWN: u32 = 101;
+
+const ERROR_NO_NETWORK: u32 = 2;
+const ERROR_CANNOT_GET_LOCK: u32 = 26;
+const ERROR_NOT_AUTHORIZED: u32 = 48;
+
+static AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+/// What a PackageKit transaction does with the resolved packages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Install,
+    /// Upgrades installed packages only
+    Update,
+    /// Removes the packages and what depends on them; `autoremove` also
+    /// takes away dependencies nothing needs any more
+    Remove { autoremove: bool },
+}
+
+impl Action {
+    /// D-Bus method of the transaction
+    pub fn method(&self) -> &'static str {
+        match self {
+            Action::Install => "InstallPackages",
+            Action::Update => "UpdatePackages",
+            Action::Remove { .. } => "RemovePackages",
+        }
+    }
+
+    /// Filter of the `Resolve` call that turns names into package ids
+    pub fn resolve_filter(&self) -> u64 {
+        match self {
+            Action::Install | Action::Update => FILTER_NEWEST | FILTER_ARCH,
+            Action::Remove { .. } => FILTER_INSTALLED,
+        }
+    }
+}
+
+/// The parts of a package id such as "bash;5.2.15-2+b7;amd64;installed:debian-bookworm-main"
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageId {
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub data: String,
+}
+
+pub fn parse_package_id(id: &str) -> Option<PackageId> {
+    let mut parts = id.split(';');
+    let name = parts.next().filter(|name| !name.is_empty())?;
+    let version = parts.next()?;
+    let arch = parts.next()?;
+    let data = parts.next()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(PackageId { name: name.to_string(), version: version.to_string(), arch: arch.to_string(), data: data.to_string() })
+}
+
+/// The ids `action` applies to, from the `(info, id)` pairs `Resolve`
+/// emitted for `names`. Packages that are already current or not
+/// installed are left out, as apt-get would; unknown names are an error.
+pub fn select_ids(action: Action, names: &[String], resolved: &[(u32, String)]) -> Result<Vec<String>> {
+    let resolved_name = |id: &str| parse_package_id(id).map(|id| id.name);
+    if let Action::Install = action {
+        let unknown: Vec<&str> = names.iter()
+            .filter(|name| !resolved.iter().any(|(_, id)| resolved_name(id).as_deref() == Some(name.as_str())))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(Error::Apt(format!("Unable to locate package {}", unknown.join(", "))));
+        }
+    }
+
+    let wanted = |info: u32| match action {
+        Action::Install | Action::Update => info != INFO_INSTALLED,
+        Action::Remove { .. } => info == INFO_INSTALLED,
+    };
+    let mut ids: Vec<String> = Vec::new();
+    for (info, id) in resolved {
+        if wanted(*info) && !ids.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+    Ok(ids)
+}
+
+/// Progress view text for a transaction `Status`
+pub fn status_text(status: u32) -> Option<&'static str> {
+    match status {
+        1 => Some("Waiting for other transactions..."),
+        2 => Some("Preparing..."),
+        4 => Some("Looking up packages..."),
+        6 => Some("Removing packages..."),
+        7 => Some("Refreshing the package list..."),
+        8 => Some("Downloading packages..."),
+        9 => Some("Installing packages..."),
+        10 => Some("Upgrading packages..."),
+        11 => Some("Cleaning up..."),
+        13 => Some("Resolving dependencies..."),
+        14 => Some("Checking signatures..."),
+        15 => Some("Testing changes..."),
+        16 => Some("Applying changes..."),
+        19 => Some("Cancelling..."),
+        30 => Some("Waiting for another package manager..."),
+        31 => Some("Waiting for authentication..."),
+        _ => None,
+    }
+}
+
+/// Log verb for a `Package` signal sent while the transaction runs
+pub fn info_verb(info: u32) -> Option<&'static str> {
+    match info {
+        10 => Some("Downloading"),
+        11 => Some("Upgrading"),
+        12 => Some("Installing"),
+        13 => Some("Removing"),
+        14 => Some("Cleaning up"),
+        19 => Some("Reinstalling"),
+        20 => Some("Downgrading"),
+        21 => Some("Preparing"),
+        22 => Some("Unpacking"),
+        _ => None,
+    }
+}
+
+/// Overall progress for a `Percentage` between `start` and `end`
+pub fn progress(percentage: u32, start: f64, end: f64) -> Option<f64> {
+    if percentage >= PERCENTAGE_UNKNOWN {
+        return None;
+    }
+    Some(start + (end - start) * f64::from(percentage) / 100.0)
+}
+
+/// Turns an `ErrorCode` signal into the error kind the UI knows
+pub fn error_for(code: u32, details: &str) -> Error {
+    let message = details.trim().to_string();
+    match code {
+        ERROR_NO_NETWORK => Error::Network(message),
+        ERROR_CANNOT_GET_LOCK => Error::LockHeld(message),
+        ERROR_NOT_AUTHORIZED => Error::PermissionDenied(message),
+        _ => Error::from_apt_output(&message),
+    }
+}
+
+/// The result of a transaction from its `Finished` exit code and the
+/// `ErrorCode` signals before it
+pub fn finish_result(exit: u32, errors: &[(u32, String)]) -> Result<()> {
+    if exit == EXIT_SUCCESS {
+        return Ok(());
+    }
+    match errors.first() {
+        Some((code, details)) => Err(error_for(*code, details)),
+        None if exit == EXIT_CANCELLED => Err(Error::Other("The PackageKit transaction was cancelled".to_string())),
+        None => Err(Error::Apt(format!("The PackageKit transaction failed (exit code {})", exit))),
+    }
+}
+
+fn system_bus() -> Option<gio::DBusConnection> {
+    gio::bus_get_sync(gio::BusType::System, gio::Cancellable::NONE)
+        .map_err(|e| eprintln!("❌ System bus unavailable: {}", e))
+        .ok()
+}
+
+fn backend_name(connection: &gio::DBusConnection) -> Option<String> {
+    let reply = connection.call_sync(
+        Some(BUS_NAME),
+        OBJECT_PATH,
+        "org.freedesktop.DBus.Properties",
+        "Get",
+        Some(&(INTERFACE, "BackendName").to_variant()),
+        Some(glib::VariantTy::new("(v)").unwrap()),
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+    ).ok()?;
+    reply.child_value(0).as_variant()?.get::<String>()
+}
+
+/// Whether PackageKit runs, or can be started, with an apt backend. Probed
+/// once per process.
+pub fn is_available() -> bool {
+    *AVAILABLE.get_or_init(|| {
+        let backend = system_bus().and_then(|connection| backend_name(&connection));
+        match backend {
+            Some(backend) if backend.starts_with("apt") => {
+                println!("📦 Package backend: PackageKit ({})", backend);
+                true
+            }
+            Some(backend) => {
+                println!("⚠️ PackageKit uses the {} backend, falling back to apt-get", backend);
+                false
+            }
+            None => {
+                println!("📦 Package backend: apt-get (PackageKit not available)");
+                false
+            }
+        }
+    })
+}
+
+/// What happened to a running transaction so far
+#[derive(Default)]
+struct TransactionState {
+    packages: Vec<(u32, String)>,
+    errors: Vec<(u32, String)>,
+    finished: Option<u32>,
+    destroyed: bool,
+    events: Vec<TransactionEvent>,
+}
+
+impl TransactionState {
+    fn handle(&mut self, member: &str, parameters: &glib::Variant, start: f64, end: f64) {
+        match member {
+            "Package" => {
+                if let Some((info, id, _summary)) = parameters.get::<(u32, String, String)>() {
+                    if let (Some(verb), Some(package)) = (info_verb(info), parse_package_id(&id)) {
+                        self.events.push(TransactionEvent::Log(format!("{} {} {}", verb, package.name, package.version)));
+                    }
+                    self.packages.push((info, id));
+                }
+            }
+            "ErrorCode" => {
+                if let Some((code, details)) = parameters.get::<(u32, String)>() {
+                    self.events.push(TransactionEvent::Log(format!("⚠️ {}", details.trim())));
+                    self.errors.push((code, details));
+                }
+            }
+            "Finished" => {
+                if let Some((exit, _runtime)) = parameters.get::<(u32, u32)>() {
+                    self.finished = Some(exit);
+                }
+            }
+            "Destroy" => self.destroyed = true,
+            "PropertiesChanged" => {
+                let changed = glib::VariantDict::new(Some(&parameters.child_value(1)));
+                let value = |key: &str| changed.lookup::<u32>(key).ok().flatten();
+                if let Some(fraction) = value("Percentage").and_then(|percentage| progress(percentage, start, end)) {
+                    self.events.push(TransactionEvent::Progress(fraction));
+                }
+                if let Some(text) = value("Status").and_then(status_text) {
+                    self.events.push(TransactionEvent::Status(text.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Runs one transaction method and waits for it to finish, forwarding its
+/// progress. Returns the packages it emitted. Must run on a thread whose
+/// default main context is `context`.
+fn run_transaction(
+    connection: &gio::DBusConnection,
+    context: &glib::MainContext,
+    method: &str,
+    parameters: glib::Variant,
+    report: &dyn Fn(TransactionEvent),
+    (start, end): (f64, f64),
+    cancel: Option<&CancelToken>,
+) -> Result<Vec<(u32, String)>> {
+    let reply = connection.call_sync(
+        Some(BUS_NAME),
+        OBJECT_PATH,
+        INTERFACE,
+        "CreateTransaction",
+        None,
+        Some(glib::VariantTy::new("(o)").unwrap()),
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+    ).map_err(|e| Error::Other(format!("PackageKit could not create a transaction: {}", e)))?;
+    let path = reply.child_value(0).str().unwrap_or_default().to_string();
+
+    let state = Rc::new(RefCell::new(TransactionState::default()));
+    let subscription = {
+        let state = state.clone();
+        connection.signal_subscribe(
+            Some(BUS_NAME),
+            None,
+            None,
+            Some(&path),
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |_, _, _, _, member, parameters| state.borrow_mut().handle(member, parameters, start, end),
+        )
+    };
+
+    // Lets polkit ask for the password instead of refusing outright
+    let hints = vec!["interactive=true".to_string(), "background=false".to_string()];
+    let _ = connection.call_sync(
+        Some(BUS_NAME),
+        &path,
+        TRANSACTION_INTERFACE,
+        "SetHints",
+        Some(&(hints,).to_variant()),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+    );
+
+    let call = connection.call_sync(
+        Some(BUS_NAME),
+        &path,
+        TRANSACTION_INTERFACE,
+        method,
+        Some(&parameters),
+        None,
+        gio::DBusCallFlags::ALLOW_INTERACTIVE_AUTHORIZATION,
+        -1,
+        gio::Cancellable::NONE,
+    );
+
+    // Wakes the loop regularly to notice a cancel request
+    let ticker = glib::timeout_source_new(Duration::from_millis(250), None, glib::Priority::DEFAULT, || glib::ControlFlow::Continue);
+    ticker.attach(Some(context));
+    let mut cancel_sent = false;
+
+    let result = match call {
+        Err(e) => Err(Error::from_apt_output(&format!("PackageKit refused {}: {}", method, e))),
+        Ok(_) => loop {
+            context.iteration(true);
+            if !cancel_sent && cancel.is_some_and(CancelToken::is_cancelled) {
+                cancel_sent = true;
+                report(TransactionEvent::Log(format!("Asking PackageKit to cancel {}...", method)));
+                let _ = connection.call_sync(
+                    Some(BUS_NAME),
+                    &path,
+                    TRANSACTION_INTERFACE,
+                    "Cancel",
+                    None,
+                    None,
+                    gio::DBusCallFlags::NONE,
+                    -1,
+                    gio::Cancellable::NONE,
+                );
+            }
+            let mut state = state.borrow_mut();
+            for event in state.events.drain(..) {
+                report(event);
+            }
+            if let Some(exit) = state.finished {
+                if cancel_sent && exit == EXIT_CANCELLED {
+                    break Err(Error::Cancelled);
+                }
+                break finish_result(exit, &state.errors).map(|()| state.packages.clone());
+            }
+            if state.destroyed {
+                break Err(Error::Other(format!("PackageKit ended {} without a result", method)));
+            }
+        },
+    };
+    ticker.destroy();
+    connection.signal_unsubscribe(subscription);
+    result
+}
+
+/// Runs `f` with the system bus on a main context of its own, which the
+/// transaction signals are delivered to
+fn with_bus<T>(f: impl FnOnce(&gio::DBusConnection, &glib::MainContext) -> Result<T>) -> Result<T> {
+    let connection = system_bus().ok_or_else(|| Error::Other("The system bus is not available".to_string()))?;
+    let context = glib::MainContext::new();
+    context.with_thread_default(|| f(&connection, &context))
+        .map_err(|e| Error::Other(format!("PackageKit could not be used: {}", e)))?
+}
+
+/// Runs `action` on the named packages through PackageKit, reporting like
+/// apt-get. PackageKit keeps modified config files instead of asking.
+pub fn run(action: Action, packages: &[String], report: &dyn Fn(TransactionEvent), start: f64, end: f64, cancel: Option<&CancelToken>) -> Result<()> {
+    with_bus(|connection, context| {
+        report(TransactionEvent::Status("Looking up packages...".to_string()));
+        let parameters = (action.resolve_filter(), packages.to_vec()).to_variant();
+        let resolved = run_transaction(connection, context, "Resolve", parameters, report, (start, start), cancel)?;
+        let ids = select_ids(action, packages, &resolved)?;
+        if ids.is_empty() {
+            report(TransactionEvent::Log("Nothing to do, the packages are already as requested.".to_string()));
+            return Ok(());
+        }
+
+        let parameters = match action {
+            Action::Install | Action::Update => (FLAG_ONLY_TRUSTED, ids).to_variant(),
+            Action::Remove { autoremove } => (0u64, ids, true, autoremove).to_variant(),
+        };
+        if let Some(cancel) = cancel {
+            cancel.check()?;
+        }
+        run_transaction(connection, context, action.method(), parameters, report, (start, end), cancel).map(|_| ())
+    })
+}
+
+/// Refreshes the package lists, which polkit usually allows without a
+/// password, so it does not add a prompt of its own
+pub fn refresh_cache(report: &dyn Fn(TransactionEvent), start: f64, end: f64, cancel: Option<&CancelToken>) -> Result<()> {
+    with_bus(|connection, context| {
+        run_transaction(connection, context, "RefreshCache", (false,).to_variant(), report, (start, end), cancel).map(|_| ())
+    })
+}
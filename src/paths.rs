@@ -0,0 +1,117 @@
+// src/paths.rs
+
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+/// Subdirectory of every base directory that belongs to MeaUpdater
+const APP_DIR: &str = "meaupdater";
+
+fn non_empty<F: Fn(&str) -> Option<String>>(lookup: &F, key: &str) -> Option<String> {
+    lookup(key).filter(|value| !value.is_empty())
+}
+
+/// `$var/meaupdater`, or `$HOME/default/meaupdater` when `var` is unset or empty
+fn app_dir_from<F: Fn(&str) -> Option<String>>(lookup: &F, var: &str, default: &str) -> Option<PathBuf> {
+    let base = match non_empty(lookup, var) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(non_empty(lookup, "HOME")?).join(default),
+    };
+    Some(base.join(APP_DIR))
+}
+
+/// Settings the user may edit, e.g. config.toml (`XDG_CONFIG_HOME`)
+pub fn config_dir_from<F: Fn(&str) -> Option<String>>(lookup: F) -> Option<PathBuf> {
+    app_dir_from(&lookup, "XDG_CONFIG_HOME", ".config")
+}
+
+/// Downloads and scan results that can be fetched again (`XDG_CACHE_HOME`)
+pub fn cache_dir_from<F: Fn(&str) -> Option<String>>(lookup: F) -> Option<PathBuf> {
+    app_dir_from(&lookup, "XDG_CACHE_HOME", ".cache")
+}
+
+/// History, journals and backups that should survive a restart (`XDG_STATE_HOME`)
+pub fn state_dir_from<F: Fn(&str) -> Option<String>>(lookup: F) -> Option<PathBuf> {
+    app_dir_from(&lookup, "XDG_STATE_HOME", ".local/state")
+}
+
+/// Short-lived files such as locks and hand-over copies, which are gone
+/// after logging out (`XDG_RUNTIME_DIR`). Falls back to the cache.
+pub fn runtime_dir_from<F: Fn(&str) -> Option<String>>(lookup: F) -> Option<PathBuf> {
+    match non_empty(&lookup, "XDG_RUNTIME_DIR") {
+        Some(dir) => Some(PathBuf::from(dir).join(APP_DIR)),
+        None => cache_dir_from(lookup).map(|dir| dir.join("run")),
+    }
+}
+
+fn env(key: &str) -> Option<String> {
+    std::env::var(key).ok()
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    config_dir_from(env)
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    cache_dir_from(env)
+}
+
+pub fn state_dir() -> Option<PathBuf> {
+    state_dir_from(env)
+}
+
+pub fn runtime_dir() -> Option<PathBuf> {
+    runtime_dir_from(env)
+}
+
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    Some(config_dir()?.join(name))
+}
+
+pub fn cache_file(name: &str) -> Option<PathBuf> {
+    Some(cache_dir()?.join(name))
+}
+
+pub fn state_file(name: &str) -> Option<PathBuf> {
+    Some(state_dir()?.join(name))
+}
+
+pub fn runtime_file(name: &str) -> Option<PathBuf> {
+    Some(runtime_dir()?.join(name))
+}
+
+/// Every directory MeaUpdater writes to in the user's home
+pub fn user_dirs_from<F: Fn(&str) -> Option<String>>(lookup: F) -> Vec<PathBuf> {
+    [config_dir_from(&lookup), cache_dir_from(&lookup), state_dir_from(&lookup)]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Creates `dir` and its parents. New directories are private to the
+/// user, as the spec asks for the runtime dir and history deserves too.
+pub fn ensure_dir(dir: &Path) -> io::Result<()> {
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)
+}
+
+/// Creates the directory a file is about to be written to
+pub fn ensure_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => ensure_dir(parent),
+        None => Ok(()),
+    }
+}
+
+/// Writes `content` to a new file in the runtime dir that only the user may
+/// read, to hand it to an elevated command. Unlike a fixed name in /tmp,
+/// nothing another user placed at the path can be followed or read.
+pub fn write_runtime_file(name: &str, content: &[u8]) -> io::Result<PathBuf> {
+    let path = runtime_file(name).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))?;
+    ensure_parent(&path)?;
+    // A leftover from a crashed run; the directory is the user's own
+    let _ = fs::remove_file(&path);
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path)?;
+    file.write_all(content)?;
+    Ok(path)
+}
@@ -1,26 +1,350 @@
 // src/policy.rs
-use anyhow::{bail, Context, Result};
-use std::process::Command;
+use crate::error::{Context, Error, Result};
+use crate::apt::{PackageRequest, Transaction};
+use crate::journal::OperationKind;
+use crate::model::{PackageUpdate, UpdateType};
+use std::collections::HashSet;
+use std::fs;
 
 /// Testable command generator
 pub fn build_install_command(pkgs: &[String]) -> String {
     format!("apt update && apt install --only-upgrade -y {}", pkgs.join(" "))
 }
 
-/// Installs selected packages with `pkexec`.
+/// Upgrades the selected packages without installing new ones
 pub fn install_packages(pkgs: &[String]) -> Result<()> {
     if pkgs.is_empty() {
-        bail!("No package selected");
-    }
-    let cmd = build_install_command(pkgs);
-    let status = Command::new("pkexec")
-        .arg("sh")
-        .arg("-c")
-        .arg(&cmd)
-        .status()
-        .context("`pkexec` failed to start")?;
-    if !status.success() {
-        bail!("`apt install` error code {}", status);
-    }
-    Ok(())
+        return Err(Error::Other("No package selected".to_string()));
+    }
+    Transaction::install(pkgs)
+        .only_upgrade()
+        .refresh_lists()
+        .journal(OperationKind::Install)
+        .run()
+        .context("`apt install`")
+}
+
+/// Installs specific versions or releases, e.g. `pkg=version` for a
+/// downgrade or `pkg/suite` for a backport
+pub fn install_requests(requests: &[PackageRequest]) -> Result<()> {
+    if requests.is_empty() {
+        return Err(Error::Other("No package selected".to_string()));
+    }
+    Transaction::install_requests(requests)
+        .refresh_lists()
+        .journal(OperationKind::Install)
+        .run()
+        .context("`apt install`")
+}
+
+/// Groups of updates that may each be installed without asking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateClass {
+    Security,
+    Applications,
+    Kernel,
+    Drivers,
+}
+
+impl UpdateClass {
+    pub const ALL: [UpdateClass; 4] = [UpdateClass::Security, UpdateClass::Applications, UpdateClass::Kernel, UpdateClass::Drivers];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpdateClass::Security => "Security updates",
+            UpdateClass::Applications => "Application updates",
+            UpdateClass::Kernel => "Kernels",
+            UpdateClass::Drivers => "Graphics drivers and firmware",
+        }
+    }
+
+    /// Key in the config file
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            UpdateClass::Security => "auto_install_security",
+            UpdateClass::Applications => "auto_install_applications",
+            UpdateClass::Kernel => "auto_install_kernel",
+            UpdateClass::Drivers => "auto_install_drivers",
+        }
+    }
+}
+
+/// Name prefixes of graphics driver packages
+const DRIVER_PREFIXES: &[&str] = &["nvidia-", "libnvidia-", "xserver-xorg-video-", "firmware-amd-graphics", "amdgpu-"];
+
+/// The class of a pending update. Kernels and drivers win over security so
+/// that a security fix for them still waits for confirmation.
+pub fn update_class(pkg: &PackageUpdate) -> UpdateClass {
+    if pkg.update_type == UpdateType::Kernel {
+        UpdateClass::Kernel
+    } else if pkg.update_type == UpdateType::Firmware || DRIVER_PREFIXES.iter().any(|prefix| pkg.name.starts_with(prefix)) {
+        UpdateClass::Drivers
+    } else if pkg.update_type == UpdateType::Security {
+        UpdateClass::Security
+    } else {
+        UpdateClass::Applications
+    }
+}
+
+/// Which classes the background installer may install on its own. Nothing
+/// is installed without confirmation unless the user turns it on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AutoInstallPolicy {
+    pub security: bool,
+    pub applications: bool,
+    pub kernel: bool,
+    pub drivers: bool,
+}
+
+impl AutoInstallPolicy {
+    pub fn allows(&self, class: UpdateClass) -> bool {
+        match class {
+            UpdateClass::Security => self.security,
+            UpdateClass::Applications => self.applications,
+            UpdateClass::Kernel => self.kernel,
+            UpdateClass::Drivers => self.drivers,
+        }
+    }
+
+    pub fn set(&mut self, class: UpdateClass, allowed: bool) {
+        match class {
+            UpdateClass::Security => self.security = allowed,
+            UpdateClass::Applications => self.applications = allowed,
+            UpdateClass::Kernel => self.kernel = allowed,
+            UpdateClass::Drivers => self.drivers = allowed,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        UpdateClass::ALL.iter().any(|class| self.allows(*class))
+    }
+
+    /// Splits `packages` into the names that may be installed automatically
+    /// and those that need the user's confirmation
+    pub fn split(&self, packages: &[PackageUpdate]) -> (Vec<String>, Vec<String>) {
+        let mut automatic = Vec::new();
+        let mut manual = Vec::new();
+        for pkg in packages {
+            if self.allows(update_class(pkg)) {
+                automatic.push(pkg.name.clone());
+            } else {
+                manual.push(pkg.name.clone());
+            }
+        }
+        (automatic, manual)
+    }
+}
+
+/// How often the background check runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleFrequency {
+    /// Every `check_interval_minutes`
+    Interval,
+    Daily,
+    Weekly,
+}
+
+impl ScheduleFrequency {
+    pub const ALL: [ScheduleFrequency; 3] = [ScheduleFrequency::Interval, ScheduleFrequency::Daily, ScheduleFrequency::Weekly];
+
+    /// Value in the config file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduleFrequency::Interval => "interval",
+            ScheduleFrequency::Daily => "daily",
+            ScheduleFrequency::Weekly => "weekly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        ScheduleFrequency::ALL.into_iter().find(|frequency| frequency.as_str() == value)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScheduleFrequency::Interval => "At a fixed interval",
+            ScheduleFrequency::Daily => "Once a day",
+            ScheduleFrequency::Weekly => "Once a week",
+        }
+    }
+}
+
+/// Day names in the config file, Monday first as in ISO 8601
+pub const WEEKDAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+/// When the background check runs and whether it may install anything,
+/// e.g. security updates daily at 03:00 or only a notification weekly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    pub frequency: ScheduleFrequency,
+    /// Minutes after local midnight, for daily and weekly checks
+    pub time_of_day: u32,
+    /// 1 for Monday to 7 for Sunday, for weekly checks
+    pub weekday: u32,
+    /// Only notify about updates, even those `AutoInstallPolicy` allows
+    pub notify_only: bool,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self { frequency: ScheduleFrequency::Interval, time_of_day: 3 * 60, weekday: 1, notify_only: false }
+    }
+}
+
+/// Parses "03:00" into minutes after midnight
+pub fn parse_time_of_day(value: &str) -> Option<u32> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+pub fn format_time_of_day(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60 % 24, minutes % 60)
+}
+
+/// 1 for "monday" or "mon", up to 7 for Sunday
+pub fn parse_weekday(value: &str) -> Option<u32> {
+    let value = value.trim().to_lowercase();
+    if value.len() < 3 {
+        return None;
+    }
+    WEEKDAYS.iter().position(|day| day.starts_with(&value)).map(|index| index as u32 + 1)
+}
+
+impl Schedule {
+    /// Seconds from now until the next check. `weekday` (1 to 7) and
+    /// `seconds_of_day` describe the local time now.
+    pub fn seconds_until_next(&self, interval_secs: u32, weekday: u32, seconds_of_day: u32) -> u32 {
+        const DAY: u32 = 24 * 60 * 60;
+        let target = self.time_of_day * 60;
+        match self.frequency {
+            ScheduleFrequency::Interval => interval_secs,
+            ScheduleFrequency::Daily => {
+                if target > seconds_of_day { target - seconds_of_day } else { DAY - seconds_of_day + target }
+            }
+            ScheduleFrequency::Weekly => {
+                let days_ahead = (self.weekday + 7 - weekday) % 7;
+                let wait = days_ahead * DAY + target;
+                if wait > seconds_of_day { wait - seconds_of_day } else { 7 * DAY - seconds_of_day + wait }
+            }
+        }
+    }
+
+    /// "Daily at 03:00", for the Preferences window and logs
+    pub fn describe(&self, interval_minutes: u32) -> String {
+        let when = match self.frequency {
+            ScheduleFrequency::Interval => format!("Every {} minutes", interval_minutes),
+            ScheduleFrequency::Daily => format!("Daily at {}", format_time_of_day(self.time_of_day)),
+            ScheduleFrequency::Weekly => {
+                let day = WEEKDAYS[(self.weekday.clamp(1, 7) - 1) as usize];
+                format!("Every {}{} at {}", day[..1].to_uppercase(), &day[1..], format_time_of_day(self.time_of_day))
+            }
+        };
+        if self.notify_only { format!("{}, notify only", when) } else { when }
+    }
+}
+
+/// Parts of the running desktop session that only pick up an update
+/// after logging out or restarting them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DesktopComponent {
+    GnomeShell,
+    Cinnamon,
+    Plasma,
+    Xfce,
+    Mate,
+    Xorg,
+    DisplayManager,
+}
+
+impl DesktopComponent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DesktopComponent::GnomeShell => "GNOME Shell",
+            DesktopComponent::Cinnamon => "Cinnamon",
+            DesktopComponent::Plasma => "KDE Plasma",
+            DesktopComponent::Xfce => "Xfce",
+            DesktopComponent::Mate => "MATE",
+            DesktopComponent::Xorg => "the X server",
+            DesktopComponent::DisplayManager => "the login screen",
+        }
+    }
+
+    /// Process names that show the component is running
+    fn processes(&self) -> &'static [&'static str] {
+        match self {
+            DesktopComponent::GnomeShell => &["gnome-shell"],
+            DesktopComponent::Cinnamon => &["cinnamon"],
+            DesktopComponent::Plasma => &["plasmashell", "kwin_x11", "kwin_wayland"],
+            DesktopComponent::Xfce => &["xfce4-session", "xfwm4"],
+            DesktopComponent::Mate => &["mate-session", "marco"],
+            DesktopComponent::Xorg => &["Xorg", "Xwayland"],
+            DesktopComponent::DisplayManager => &["gdm", "gdm3", "lightdm", "sddm"],
+        }
+    }
+
+    /// Whether the component is running according to `processes`
+    pub fn is_running(&self, processes: &HashSet<String>) -> bool {
+        self.processes().iter().any(|process| processes.contains(*process))
+    }
+}
+
+/// Package names, or prefixes ending in `-`, of each desktop component
+const COMPONENT_PACKAGES: &[(&str, DesktopComponent)] = &[
+    ("gnome-shell", DesktopComponent::GnomeShell),
+    ("gnome-shell-common", DesktopComponent::GnomeShell),
+    ("mutter", DesktopComponent::GnomeShell),
+    ("mutter-common", DesktopComponent::GnomeShell),
+    ("libmutter-", DesktopComponent::GnomeShell),
+    ("cinnamon", DesktopComponent::Cinnamon),
+    ("cinnamon-common", DesktopComponent::Cinnamon),
+    ("muffin", DesktopComponent::Cinnamon),
+    ("libmuffin", DesktopComponent::Cinnamon),
+    ("plasma-workspace", DesktopComponent::Plasma),
+    ("plasma-desktop", DesktopComponent::Plasma),
+    ("kwin-", DesktopComponent::Plasma),
+    ("xfwm4", DesktopComponent::Xfce),
+    ("xfce4-session", DesktopComponent::Xfce),
+    ("xfce4-panel", DesktopComponent::Xfce),
+    ("xfdesktop4", DesktopComponent::Xfce),
+    ("mate-session-manager", DesktopComponent::Mate),
+    ("mate-panel", DesktopComponent::Mate),
+    ("marco", DesktopComponent::Mate),
+    ("xserver-xorg-core", DesktopComponent::Xorg),
+    ("xwayland", DesktopComponent::Xorg),
+    ("gdm3", DesktopComponent::DisplayManager),
+    ("lightdm", DesktopComponent::DisplayManager),
+    ("sddm", DesktopComponent::DisplayManager),
+];
+
+/// The desktop component `package` belongs to, if any
+pub fn desktop_component(package: &str) -> Option<DesktopComponent> {
+    COMPONENT_PACKAGES.iter()
+        .find(|(name, _)| {
+            if name.ends_with('-') {
+                package.starts_with(name)
+            } else {
+                package == *name
+            }
+        })
+        .map(|(_, component)| *component)
+}
+
+/// The running component an update of `package` only takes effect in after
+/// the session (or the component) is restarted
+pub fn session_restart_component(package: &str, processes: &HashSet<String>) -> Option<DesktopComponent> {
+    desktop_component(package).filter(|component| component.is_running(processes))
+}
+
+/// Names of all running processes, from `/proc/<pid>/comm`
+pub fn running_processes() -> HashSet<String> {
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return HashSet::new(),
+    };
+    entries.flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+        .filter_map(|entry| fs::read_to_string(entry.path().join("comm")).ok())
+        .map(|comm| comm.trim().to_string())
+        .collect()
 }
@@ -0,0 +1,329 @@
+// src/preferences_window.rs
+
+use crate::config::{self, AdminPolicy, Config};
+use crate::policy::{self, ScheduleFrequency, UpdateClass, WEEKDAYS};
+use gtk::prelude::*;
+use gtk::{
+    ApplicationWindow, Box as GtkBox, ComboBoxText, Orientation, HeaderBar, Label, Dialog,
+    Switch, Grid,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Edits the persistent settings; `on_changed` runs after every saved change
+pub struct PreferencesWindow {
+    window: Dialog,
+}
+
+impl PreferencesWindow {
+    pub fn new(parent: &ApplicationWindow, on_changed: Rc<dyn Fn(&Config)>) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Preferences")
+            .default_width(480)
+            .build();
+
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some("⚙️ Preferences"))));
+        window.set_titlebar(Some(&header_bar));
+
+        let grid = Grid::new();
+        grid.set_row_spacing(12);
+        grid.set_column_spacing(16);
+        grid.set_margin_top(16);
+        grid.set_margin_bottom(16);
+        grid.set_margin_start(16);
+        grid.set_margin_end(16);
+
+        let config = Rc::new(RefCell::new(Config::load()));
+        let policy = AdminPolicy::load();
+        let locked_tooltip = format!("🔒 Set by your administrator in {}", config::POLICY_FILE);
+
+        let auto_label = Label::new(Some("Check for updates automatically"));
+        auto_label.set_halign(gtk::Align::Start);
+        auto_label.set_hexpand(true);
+        let auto_switch = Switch::new();
+        auto_switch.set_active(config.borrow().automatic_checks);
+        auto_switch.set_halign(gtk::Align::End);
+        if policy.automatic_checks.is_some() {
+            auto_switch.set_sensitive(false);
+            auto_switch.set_tooltip_text(Some(&locked_tooltip));
+        }
+        grid.attach(&auto_label, 0, 0, 1, 1);
+        grid.attach(&auto_switch, 1, 0, 1, 1);
+
+        let interval_label = Label::new(Some("Check interval"));
+        interval_label.set_halign(gtk::Align::Start);
+        let interval_combo = ComboBoxText::new();
+        for (minutes, label) in config::CHECK_INTERVALS {
+            interval_combo.append(Some(&minutes.to_string()), label);
+        }
+        let current = config.borrow().check_interval_minutes.to_string();
+        if !interval_combo.set_active_id(Some(&current)) {
+            interval_combo.append(Some(&current), &format!("Every {} minutes", current));
+            interval_combo.set_active_id(Some(&current));
+        }
+        let interval_locked = policy.check_interval_minutes.is_some();
+        if interval_locked {
+            interval_combo.set_tooltip_text(Some(&locked_tooltip));
+        }
+
+        let schedule_label = Label::new(Some("Check"));
+        schedule_label.set_halign(gtk::Align::Start);
+        let schedule_combo = ComboBoxText::new();
+        for frequency in ScheduleFrequency::ALL {
+            schedule_combo.append(Some(frequency.as_str()), frequency.name());
+        }
+        schedule_combo.set_active_id(Some(config.borrow().schedule.frequency.as_str()));
+        grid.attach(&schedule_label, 0, 1, 1, 1);
+        grid.attach(&schedule_combo, 1, 1, 1, 1);
+
+        grid.attach(&interval_label, 0, 2, 1, 1);
+        grid.attach(&interval_combo, 1, 2, 1, 1);
+
+        let time_label = Label::new(Some("Time of day"));
+        time_label.set_halign(gtk::Align::Start);
+        let time_combo = ComboBoxText::new();
+        for hour in 0..24 {
+            time_combo.append(Some(&(hour * 60).to_string()), &policy::format_time_of_day(hour * 60));
+        }
+        let current_time = config.borrow().schedule.time_of_day;
+        if !time_combo.set_active_id(Some(&current_time.to_string())) {
+            time_combo.append(Some(&current_time.to_string()), &policy::format_time_of_day(current_time));
+            time_combo.set_active_id(Some(&current_time.to_string()));
+        }
+        grid.attach(&time_label, 0, 3, 1, 1);
+        grid.attach(&time_combo, 1, 3, 1, 1);
+
+        let weekday_label = Label::new(Some("Day of the week"));
+        weekday_label.set_halign(gtk::Align::Start);
+        let weekday_combo = ComboBoxText::new();
+        for (index, day) in WEEKDAYS.iter().enumerate() {
+            weekday_combo.append(Some(&(index + 1).to_string()), &format!("{}{}", day[..1].to_uppercase(), &day[1..]));
+        }
+        weekday_combo.set_active_id(Some(&config.borrow().schedule.weekday.to_string()));
+        grid.attach(&weekday_label, 0, 4, 1, 1);
+        grid.attach(&weekday_combo, 1, 4, 1, 1);
+
+        let notify_label = Label::new(Some("Only notify, never install"));
+        notify_label.set_halign(gtk::Align::Start);
+        let notify_switch = Switch::new();
+        notify_switch.set_active(config.borrow().schedule.notify_only);
+        notify_switch.set_halign(gtk::Align::End);
+        notify_switch.set_tooltip_text(Some("Scheduled checks only tell you about updates, whatever is allowed below"));
+        grid.attach(&notify_label, 0, 5, 1, 1);
+        grid.attach(&notify_switch, 1, 5, 1, 1);
+
+        let schedule_widgets = ScheduleWidgets {
+            schedule: schedule_combo.clone(),
+            interval: interval_combo.clone(),
+            time: time_combo.clone(),
+            weekday: weekday_combo.clone(),
+            notify_only: notify_switch.clone(),
+            interval_locked,
+        };
+        schedule_widgets.update_sensitivity(&config.borrow());
+
+        let auto_install_heading = Label::new(None);
+        auto_install_heading.set_markup("<b>Install automatically after a check</b>");
+        auto_install_heading.set_halign(gtk::Align::Start);
+        auto_install_heading.set_margin_top(8);
+        grid.attach(&auto_install_heading, 0, 6, 2, 1);
+
+        for (row, class) in UpdateClass::ALL.into_iter().enumerate() {
+            let label = Label::new(Some(class.name()));
+            label.set_halign(gtk::Align::Start);
+            let switch = Switch::new();
+            switch.set_active(config.borrow().auto_install.allows(class));
+            switch.set_halign(gtk::Align::End);
+            if matches!(class, UpdateClass::Kernel | UpdateClass::Drivers) {
+                switch.set_tooltip_text(Some("These can leave the system unable to boot or show a desktop, so they wait for your confirmation unless turned on"));
+            }
+
+            let config_class = config.clone();
+            let on_changed_class = on_changed.clone();
+            switch.connect_active_notify(move |switch| {
+                let mut config = config_class.borrow_mut();
+                config.auto_install.set(class, switch.is_active());
+                config.save();
+                on_changed_class(&config);
+            });
+
+            grid.attach(&label, 0, 7 + row as i32, 1, 1);
+            grid.attach(&switch, 1, 7 + row as i32, 1, 1);
+        }
+
+        let digest_heading = Label::new(None);
+        digest_heading.set_markup("<b>Weekly summary</b>");
+        digest_heading.set_halign(gtk::Align::Start);
+        digest_heading.set_margin_top(8);
+        let digest_row = 7 + UpdateClass::ALL.len() as i32;
+        grid.attach(&digest_heading, 0, digest_row, 2, 1);
+
+        let digest_options: [(&str, fn(&mut Config) -> &mut bool); 2] = [
+            ("Show a notification", |config| &mut config.weekly_digest),
+            ("Save an HTML report in Documents", |config| &mut config.weekly_report),
+        ];
+        let other_heading = Label::new(None);
+        other_heading.set_markup("<b>Other</b>");
+        other_heading.set_halign(gtk::Align::Start);
+        other_heading.set_margin_top(8);
+        let other_row = digest_row + 1 + digest_options.len() as i32;
+        grid.attach(&other_heading, 0, other_row, 2, 1);
+
+        let other_options: [(&str, &str, fn(&mut Config) -> &mut bool); 2] = [
+            ("Notify about new updates", "A desktop notification when a check finds updates that were not there before", |config| &mut config.notify_updates),
+            ("Back up before changing drivers", "Saves the installed packages and loaded modules to ~/.local/state/meaupdater before a driver is installed or removed", |config| &mut config.driver_backup),
+        ];
+        for (offset, (text, tooltip, field)) in other_options.into_iter().enumerate() {
+            let label = Label::new(Some(text));
+            label.set_halign(gtk::Align::Start);
+            let switch = Switch::new();
+            switch.set_active(*field(&mut config.borrow_mut()));
+            switch.set_halign(gtk::Align::End);
+            switch.set_tooltip_text(Some(tooltip));
+
+            let config_other = config.clone();
+            let on_changed_other = on_changed.clone();
+            switch.connect_active_notify(move |switch| {
+                let mut config = config_other.borrow_mut();
+                *field(&mut config) = switch.is_active();
+                config.save();
+                on_changed_other(&config);
+            });
+
+            grid.attach(&label, 0, other_row + 1 + offset as i32, 1, 1);
+            grid.attach(&switch, 1, other_row + 1 + offset as i32, 1, 1);
+        }
+
+        for (offset, (text, field)) in digest_options.into_iter().enumerate() {
+            let label = Label::new(Some(text));
+            label.set_halign(gtk::Align::Start);
+            let switch = Switch::new();
+            switch.set_active(*field(&mut config.borrow_mut()));
+            switch.set_halign(gtk::Align::End);
+            switch.set_tooltip_text(Some("Summarizes the installed updates, failures, restarts and pending updates once a week"));
+
+            let config_digest = config.clone();
+            let on_changed_digest = on_changed.clone();
+            switch.connect_active_notify(move |switch| {
+                let mut config = config_digest.borrow_mut();
+                *field(&mut config) = switch.is_active();
+                config.save();
+                on_changed_digest(&config);
+            });
+
+            grid.attach(&label, 0, digest_row + 1 + offset as i32, 1, 1);
+            grid.attach(&switch, 1, digest_row + 1 + offset as i32, 1, 1);
+        }
+
+        let content = GtkBox::new(Orientation::Vertical, 0);
+        content.append(&grid);
+        window.content_area().append(&content);
+
+        let config_auto = config.clone();
+        let on_changed_auto = on_changed.clone();
+        let widgets_auto = schedule_widgets.clone();
+        auto_switch.connect_active_notify(move |switch| {
+            let mut config = config_auto.borrow_mut();
+            config.automatic_checks = switch.is_active();
+            config.save();
+            widgets_auto.update_sensitivity(&config);
+            on_changed_auto(&config);
+        });
+
+        let config_schedule = config.clone();
+        let on_changed_schedule = on_changed.clone();
+        schedule_combo.connect_changed(move |combo| {
+            let frequency = match combo.active_id().and_then(|id| ScheduleFrequency::parse(&id)) {
+                Some(frequency) => frequency,
+                None => return,
+            };
+            let mut config = config_schedule.borrow_mut();
+            config.schedule.frequency = frequency;
+            config.save();
+            schedule_widgets.update_sensitivity(&config);
+            on_changed_schedule(&config);
+        });
+
+        let config_time = config.clone();
+        let on_changed_time = on_changed.clone();
+        time_combo.connect_changed(move |combo| {
+            let minutes = match combo.active_id().and_then(|id| id.parse::<u32>().ok()) {
+                Some(minutes) => minutes,
+                None => return,
+            };
+            let mut config = config_time.borrow_mut();
+            config.schedule.time_of_day = minutes;
+            config.save();
+            on_changed_time(&config);
+        });
+
+        let config_weekday = config.clone();
+        let on_changed_weekday = on_changed.clone();
+        weekday_combo.connect_changed(move |combo| {
+            let weekday = match combo.active_id().and_then(|id| id.parse::<u32>().ok()) {
+                Some(weekday) => weekday,
+                None => return,
+            };
+            let mut config = config_weekday.borrow_mut();
+            config.schedule.weekday = weekday;
+            config.save();
+            on_changed_weekday(&config);
+        });
+
+        let config_notify = config.clone();
+        let on_changed_notify = on_changed.clone();
+        notify_switch.connect_active_notify(move |switch| {
+            let mut config = config_notify.borrow_mut();
+            config.schedule.notify_only = switch.is_active();
+            config.save();
+            on_changed_notify(&config);
+        });
+
+        let config_interval = config.clone();
+        interval_combo.connect_changed(move |combo| {
+            let minutes = match combo.active_id().and_then(|id| id.parse::<u32>().ok()) {
+                Some(minutes) => minutes,
+                None => return,
+            };
+            let mut config = config_interval.borrow_mut();
+            if config.check_interval_minutes == minutes {
+                return;
+            }
+            config.check_interval_minutes = minutes;
+            config.save();
+            on_changed(&config);
+        });
+
+        Self { window }
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
+
+/// The schedule controls, which only apply to the chosen kind of schedule
+#[derive(Clone)]
+struct ScheduleWidgets {
+    schedule: ComboBoxText,
+    interval: ComboBoxText,
+    time: ComboBoxText,
+    weekday: ComboBoxText,
+    notify_only: Switch,
+    interval_locked: bool,
+}
+
+impl ScheduleWidgets {
+    fn update_sensitivity(&self, config: &Config) {
+        let enabled = config.automatic_checks;
+        let frequency = config.schedule.frequency;
+        self.schedule.set_sensitive(enabled && !self.interval_locked);
+        self.interval.set_sensitive(enabled && !self.interval_locked && frequency == ScheduleFrequency::Interval);
+        self.time.set_sensitive(enabled && frequency != ScheduleFrequency::Interval);
+        self.weekday.set_sensitive(enabled && frequency == ScheduleFrequency::Weekly);
+        self.notify_only.set_sensitive(enabled);
+    }
+}
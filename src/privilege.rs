@@ -0,0 +1,454 @@
+// src/privilege.rs
+
+use crate::helper_protocol::{self, Operation, BUS_NAME, OBJECT_PATH, INTERFACE};
+use crate::paths;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::OnceLock;
+
+/// How privileged commands are run on this system
+#[derive(Debug, Clone, PartialEq)]
+pub enum Elevation {
+    /// pkexec is installed and a polkit authentication agent is running
+    Pkexec,
+    /// No usable polkit agent, but sudo can prompt through an askpass helper
+    SudoAskpass(String),
+    /// Sandboxed build; commands are run by the host helper over D-Bus
+    Helper,
+    /// Started as root, e.g. with `sudo meaupdater`; commands run directly
+    AlreadyRoot,
+    /// Neither method can prompt for a password from the GUI
+    Unavailable,
+}
+
+static ELEVATION: OnceLock<Elevation> = OnceLock::new();
+static SESSION_ENV: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Graphical session the application is running in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayServer {
+    Wayland,
+    X11,
+    /// No display, e.g. started over SSH without X forwarding
+    Headless,
+}
+
+/// Variables forwarded to privileged commands. pkexec and sudo both start
+/// from a clean environment, which breaks helpers that need the display,
+/// the runtime dir or the session bus.
+const SESSION_VARIABLES: &[&str] = &[
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XDG_RUNTIME_DIR",
+    "XAUTHORITY",
+    "XDG_SESSION_TYPE",
+    "XDG_CURRENT_DESKTOP",
+    "DBUS_SESSION_BUS_ADDRESS",
+    "LANG",
+];
+
+/// Known polkit authentication agents shipped by the common desktops
+const POLKIT_AGENTS: &[&str] = &[
+    "polkit-gnome-authentication-agent-1",
+    "polkit-kde-authentication-agent-1",
+    "polkit-mate-authentication-agent-1",
+    "lxpolkit",
+    "lxqt-policykit-agent",
+    "xfce-polkit",
+    "mate-polkit",
+    "polkit-agent-helper",
+    "gnome-shell",
+    "cinnamon",
+    "budgie-polkit-dialog",
+];
+
+/// Askpass helpers that sudo -A can use
+const ASKPASS_HELPERS: &[&str] = &[
+    "/usr/bin/ssh-askpass",
+    "/usr/bin/ksshaskpass",
+    "/usr/bin/lxqt-openssh-askpass",
+    "/usr/lib/ssh/x11-ssh-askpass",
+    "/usr/lib/openssh/gnome-ssh-askpass",
+    "/usr/libexec/openssh/gnome-ssh-askpass",
+];
+
+fn command_exists(program: &str) -> bool {
+    Command::new("which")
+        .arg(program)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn polkit_agent_running() -> bool {
+    POLKIT_AGENTS.iter().any(|agent| {
+        Command::new("pgrep")
+            .args(&["-f", agent])
+            .output()
+            .map(|output| output.status.success() && !output.stdout.is_empty())
+            .unwrap_or(false)
+    })
+}
+
+fn find_askpass_helper() -> Option<String> {
+    if let Ok(askpass) = std::env::var("SUDO_ASKPASS") {
+        if !askpass.is_empty() && Path::new(&askpass).exists() {
+            return Some(askpass);
+        }
+    }
+
+    ASKPASS_HELPERS.iter()
+        .find(|helper| Path::new(helper).exists())
+        .map(|helper| helper.to_string())
+}
+
+fn detect_elevation() -> Elevation {
+    if running_as_root() {
+        println!("⚠️ Running as root, privileged commands run without a password prompt");
+        return Elevation::AlreadyRoot;
+    }
+
+    if helper_protocol::running_in_flatpak() {
+        println!("🔐 Privilege escalation: host helper ({})", BUS_NAME);
+        return Elevation::Helper;
+    }
+
+    let has_pkexec = command_exists("pkexec");
+
+    if has_pkexec && polkit_agent_running() {
+        println!("🔐 Privilege escalation: pkexec (polkit agent found)");
+        return Elevation::Pkexec;
+    }
+
+    if command_exists("sudo") {
+        if let Some(helper) = find_askpass_helper() {
+            println!("🔐 Privilege escalation: sudo -A ({})", helper);
+            return Elevation::SudoAskpass(helper);
+        }
+    }
+
+    if has_pkexec {
+        // pkexec may still work through an agent we don't know about
+        println!("⚠️ No polkit agent detected, falling back to plain pkexec");
+        return Elevation::Pkexec;
+    }
+
+    println!("❌ No usable privilege escalation method found");
+    Elevation::Unavailable
+}
+
+fn non_empty<F: Fn(&str) -> Option<String>>(lookup: &F, key: &str) -> Option<String> {
+    lookup(key).filter(|value| !value.is_empty())
+}
+
+/// Works out the display server from environment lookups
+pub fn display_server_from<F: Fn(&str) -> Option<String>>(lookup: F) -> DisplayServer {
+    let session_type = non_empty(&lookup, "XDG_SESSION_TYPE").unwrap_or_default();
+
+    if non_empty(&lookup, "WAYLAND_DISPLAY").is_some() || session_type == "wayland" {
+        DisplayServer::Wayland
+    } else if non_empty(&lookup, "DISPLAY").is_some() || session_type == "x11" {
+        DisplayServer::X11
+    } else {
+        DisplayServer::Headless
+    }
+}
+
+/// Builds the `KEY=value` pairs passed on to privileged commands. Missing
+/// values that the session still needs are derived from the user's uid and
+/// home directory.
+pub fn session_environment_from<F: Fn(&str) -> Option<String>>(
+    lookup: F,
+    uid: Option<u32>,
+    home: Option<&str>,
+) -> Vec<(String, String)> {
+    let mut env: Vec<(String, String)> = SESSION_VARIABLES.iter()
+        .filter_map(|key| non_empty(&lookup, key).map(|value| (key.to_string(), value)))
+        .collect();
+    let has = |env: &Vec<(String, String)>, key: &str| env.iter().any(|(k, _)| k == key);
+
+    let display_server = display_server_from(&lookup);
+
+    if !has(&env, "XDG_RUNTIME_DIR") {
+        if let Some(uid) = uid {
+            env.push(("XDG_RUNTIME_DIR".to_string(), format!("/run/user/{}", uid)));
+        }
+    }
+
+    if display_server == DisplayServer::Wayland && !has(&env, "WAYLAND_DISPLAY") {
+        env.push(("WAYLAND_DISPLAY".to_string(), "wayland-0".to_string()));
+    }
+
+    if display_server == DisplayServer::X11 && !has(&env, "XAUTHORITY") {
+        if let Some(home) = home {
+            env.push(("XAUTHORITY".to_string(), format!("{}/.Xauthority", home)));
+        }
+    }
+
+    env
+}
+
+fn current_uid() -> Option<u32> {
+    Command::new("id")
+        .arg("-u")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok())
+}
+
+/// Whether the process runs as root
+pub fn running_as_root() -> bool {
+    current_uid() == Some(0)
+}
+
+/// MeaUpdater's config, cache and state folders, from the XDG variables or
+/// their defaults below `HOME`
+pub fn user_data_dirs<F: Fn(&str) -> Option<String>>(lookup: F) -> Vec<PathBuf> {
+    paths::user_dirs_from(lookup)
+}
+
+/// Gives `path` and everything below it to `uid`:`gid`, returning how many
+/// entries had another owner
+fn chown_tree(path: &Path, uid: u32, gid: u32) -> usize {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    let mut changed = 0;
+    if metadata.uid() != uid && std::os::unix::fs::lchown(path, Some(uid), Some(gid)).is_ok() {
+        changed += 1;
+    }
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+            changed += chown_tree(&entry.path(), uid, gid);
+        }
+    }
+    changed
+}
+
+/// Hands files MeaUpdater created while running as root back to the owner
+/// of `HOME`, so `sudo meaupdater` does not leave root-owned files in a
+/// user's home folder
+pub fn restore_home_ownership() {
+    if !running_as_root() {
+        return;
+    }
+    let home = match std::env::var("HOME") {
+        Ok(home) if !home.is_empty() => PathBuf::from(home),
+        _ => return,
+    };
+    let owner = match fs::metadata(&home) {
+        Ok(metadata) if metadata.uid() != 0 => (metadata.uid(), metadata.gid()),
+        _ => return,
+    };
+
+    let mut changed = 0;
+    for dir in user_data_dirs(|key| std::env::var(key).ok()) {
+        if !dir.starts_with(&home) {
+            continue;
+        }
+        changed += chown_tree(&dir, owner.0, owner.1);
+        // Parents such as ~/.cache may have been created as well
+        let mut parent = dir.parent();
+        while let Some(path) = parent.filter(|path| *path != home) {
+            if fs::metadata(path).map(|metadata| metadata.uid() == 0).unwrap_or(false)
+                && std::os::unix::fs::lchown(path, Some(owner.0), Some(owner.1)).is_ok()
+            {
+                changed += 1;
+            }
+            parent = path.parent();
+        }
+    }
+    if changed > 0 {
+        println!("🔧 Gave {} files in {} back to uid {}", changed, home.display(), owner.0);
+    }
+}
+
+/// Session environment of the calling user, collected once per process
+pub fn session_environment() -> &'static [(String, String)] {
+    SESSION_ENV.get_or_init(|| {
+        let home = std::env::var("HOME").ok();
+        let env = session_environment_from(|key| std::env::var(key).ok(), current_uid(), home.as_deref());
+
+        match display_server_from(|key| std::env::var(key).ok()) {
+            DisplayServer::Wayland => println!("🖥️ Wayland session, forwarding {} variables to privileged commands", env.len()),
+            DisplayServer::X11 => println!("🖥️ X11 session, forwarding {} variables to privileged commands", env.len()),
+            DisplayServer::Headless => println!("⚠️ No display found, privileged helpers will run without GUI access"),
+        }
+
+        env
+    })
+}
+
+/// Detected elevation method, probed once per process
+pub fn elevation() -> &'static Elevation {
+    ELEVATION.get_or_init(detect_elevation)
+}
+
+/// Explains why GRUB, GPU and X configuration changes are missing
+pub const HELPER_UNSUPPORTED: &str = "The Flatpak build changes the system through meaupdater-helper on the host, \
+    which only installs, upgrades, downgrades and removes packages and edits apt sources. \
+    Use MeaUpdater installed on the host for this.";
+
+/// Whether root commands other than package operations can run. The host
+/// helper refuses scripts and other programs.
+pub fn can_run_commands() -> bool {
+    !matches!(elevation(), Elevation::Helper)
+}
+
+/// Whether the startup checks found a working polkit setup
+pub fn polkit_ready() -> bool {
+    command_exists("pkexec") && polkit_agent_running()
+}
+
+/// Creates the command used to run something as root. Callers add the
+/// program and its arguments exactly as they would after `pkexec`; the
+/// user's session environment is passed through `env` so GRUB and driver
+/// scripts still see the display and runtime dir.
+pub fn elevated_command() -> Command {
+    let mut command = match elevation() {
+        Elevation::SudoAskpass(helper) => {
+            let mut command = Command::new("sudo");
+            command.env("SUDO_ASKPASS", helper).arg("-A");
+            command
+        }
+        Elevation::Helper => {
+            // Re-run ourselves in client mode; the helper has its own environment
+            let exe = std::env::current_exe()
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "meaupdater".to_string());
+            let mut command = Command::new(exe);
+            command.arg(HELPER_CALL_ARG);
+            return command;
+        }
+        // Same session, so the environment is already right
+        Elevation::AlreadyRoot => return Command::new("env"),
+        Elevation::Pkexec | Elevation::Unavailable => Command::new("pkexec"),
+    };
+
+    command.arg("env");
+    for (key, value) in session_environment() {
+        command.arg(format!("{}={}", key, value));
+    }
+    command
+}
+
+/// Short name of the elevation method for log output
+pub fn method_name() -> &'static str {
+    match elevation() {
+        Elevation::Pkexec => "pkexec",
+        Elevation::SudoAskpass(_) => "sudo -A",
+        Elevation::Helper => "host helper",
+        Elevation::AlreadyRoot => "root",
+        Elevation::Unavailable => "pkexec (unavailable)",
+    }
+}
+
+/// Explains pkexec's special exit codes; `None` for ordinary failures
+pub fn describe_exit_status(status: &ExitStatus) -> Option<&'static str> {
+    match (elevation(), status.code()) {
+        (Elevation::Pkexec | Elevation::Helper, Some(helper_protocol::EXIT_NOT_AUTHORIZED)) => Some("Authentication was cancelled or not authorized."),
+        (Elevation::Pkexec, Some(127)) => Some("pkexec could not ask for a password. Is a polkit authentication agent running?"),
+        (Elevation::Helper, Some(127)) => Some("The MeaUpdater helper is not installed on the host or refused the command."),
+        _ => None,
+    }
+}
+
+/// Command line flag that turns the binary into a one-shot helper client
+pub const HELPER_CALL_ARG: &str = "--helper-call";
+
+fn system_bus() -> Option<gio::DBusConnection> {
+    gio::bus_get_sync(gio::BusType::System, gio::Cancellable::NONE)
+        .map_err(|e| eprintln!("❌ System bus unavailable: {}", e))
+        .ok()
+}
+
+/// Whether the host helper is running or can be activated
+pub fn helper_available() -> bool {
+    let connection = match system_bus() {
+        Some(connection) => connection,
+        None => return false,
+    };
+
+    connection.call_sync(
+        Some("org.freedesktop.DBus"),
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+        "ListActivatableNames",
+        None,
+        Some(glib::VariantTy::new("(as)").unwrap()),
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+    )
+    .ok()
+    .and_then(|reply| reply.get::<(Vec<String>,)>())
+    .map(|(names,)| names.iter().any(|name| name == BUS_NAME))
+    .unwrap_or(false)
+}
+
+/// Sends `operation` to the host helper and returns the exit code of the
+/// command and its output
+pub fn call_helper(operation: &Operation) -> (i32, String) {
+    let connection = match system_bus() {
+        Some(connection) => connection,
+        None => return (helper_protocol::EXIT_UNAVAILABLE, String::new()),
+    };
+
+    let (method, params) = operation.to_call();
+    let reply = connection.call_sync(
+        Some(BUS_NAME),
+        OBJECT_PATH,
+        INTERFACE,
+        method,
+        Some(&params),
+        Some(glib::VariantTy::new("(is)").unwrap()),
+        gio::DBusCallFlags::NONE,
+        // apt runs can take a long time; don't let D-Bus time out first
+        i32::MAX,
+        gio::Cancellable::NONE,
+    );
+
+    match reply.ok().and_then(|reply| reply.get::<(i32, String)>()) {
+        Some(reply) => reply,
+        None => {
+            eprintln!("❌ Could not reach {}. Is meaupdater-helper installed on the host?", BUS_NAME);
+            (helper_protocol::EXIT_UNAVAILABLE, String::new())
+        }
+    }
+}
+
+/// Sends the operation `argv` stands for to the host helper, prints its
+/// output and returns the exit code of the command. The helper only knows
+/// MeaUpdater's apt-get operations; scripts and other programs are refused.
+pub fn run_helper_call(argv: &[String]) -> i32 {
+    let operation = match Operation::from_argv(argv) {
+        Some(operation) => operation,
+        None => {
+            eprintln!("❌ The helper does not run `{}`", argv.join(" "));
+            return helper_protocol::EXIT_UNAVAILABLE;
+        }
+    };
+    // The helper checks again, this only gives a clearer message
+    if let Err(reason) = operation.validate() {
+        eprintln!("❌ {}", reason);
+        return helper_protocol::EXIT_UNAVAILABLE;
+    }
+
+    let (code, output) = call_helper(&operation);
+    print!("{}", output);
+    code
+}
+
+/// Help text shown when no GUI password prompt is possible
+pub fn manual_instructions() -> String {
+    "MeaUpdater needs administrator rights to install updates, but no way to ask for your password was found.\n\n\
+    To fix this, do one of the following:\n\
+    • Install and start a polkit authentication agent:\n    sudo apt install policykit-1-gnome\n\
+    • Or install an askpass helper so sudo can be used:\n    sudo apt install ssh-askpass\n\
+    • Or run the update from a terminal:\n    sudo apt update && sudo apt upgrade\n\n\
+    Checking for updates will keep working in the meantime."
+        .to_string()
+}
@@ -3,16 +3,28 @@ use gtk::prelude::*;
 use gtk::{
     Window, ApplicationWindow, Box as GtkBox, ScrolledWindow, TextView,
     TextBuffer, Orientation, HeaderBar, ButtonsType, MessageDialog,
-    MessageType, Label, ProgressBar, glib
+    MessageType, Label, ProgressBar, Dialog, ResponseType, Button, glib
 };
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
 use std::io::{BufRead, BufReader};
 use std::thread;
-use std::sync::{Arc, Mutex, mpsc};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::fs;
-
-use anyhow::Error;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::anomalies;
+use crate::apt::{self, CancelToken, ProgressClock, Transaction, TransactionEvent};
+use crate::conffile::ConffileChoice;
+use crate::error::{self, Context, Error};
+use crate::flatpak_manager;
+use crate::format;
+use crate::install;
+use crate::jobs;
+use crate::log_filter;
+use crate::snap_manager;
+use crate::journal::OperationKind;
 
 #[derive(Clone)]
 pub struct ProgressWindow {
@@ -21,25 +33,39 @@ pub struct ProgressWindow {
     pub log_view: TextView,
     pub log_buffer: TextBuffer,
     pub status_label: Label,
+    /// Mirror and speed of the running download
+    pub download_label: Label,
+    /// Stops the running operation; only shown while one runs
+    cancel_button: Button,
+    cancel: Rc<RefCell<Option<CancelToken>>>,
+    /// Set while apt or dpkg changes packages; closing then only hides the window
+    critical: Rc<Cell<bool>>,
+    /// Background job while the window is hidden
+    job: Rc<Cell<Option<u64>>>,
 }
 
+/// Notification shown while a hidden progress window keeps working
+const BACKGROUND_NOTIFICATION_ID: &str = "transaction-in-background";
 
-static LAST_APT_UPDATE: Mutex<Option<u64>> = Mutex::new(None);
-const APT_UPDATE_CACHE_DURATION: u64 = 300;
 
 impl ProgressWindow {
     pub fn new(parent: &ApplicationWindow) -> Self {
+        Self::titled(parent, "Güncelleme İlerlemesi", "📦 Update Progress")
+    }
+
+    /// Progress window with its own window title and header text
+    pub fn titled(parent: &ApplicationWindow, title: &str, heading: &str) -> Self {
         let window = Window::builder()
             .transient_for(parent)
             .modal(true)
-            .title("Güncelleme İlerlemesi")
+            .title(title)
             .default_width(600)
             .default_height(400)
             .build();
 
         
         let header_bar = HeaderBar::new();
-        header_bar.set_title_widget(Some(&Label::new(Some("📦 Update Progress"))));
+        header_bar.set_title_widget(Some(&Label::new(Some(heading))));
         window.set_titlebar(Some(&header_bar));
 
         let main_vbox = GtkBox::new(Orientation::Vertical, 12);
@@ -60,6 +86,12 @@ impl ProgressWindow {
         progress_bar.set_text(Some("0%"));
         main_vbox.append(&progress_bar);
 
+        let download_label = Label::new(None);
+        download_label.set_halign(gtk::Align::Start);
+        download_label.add_css_class("size-info");
+        download_label.set_visible(false);
+        main_vbox.append(&download_label);
+
         
         let log_buffer = TextBuffer::new(None::<&gtk::TextTagTable>);
         let log_view = TextView::with_buffer(&log_buffer);
@@ -71,17 +103,99 @@ impl ProgressWindow {
             .vexpand(true)
             .hexpand(true)
             .build();
+        main_vbox.append(&log_filter::filter_bar(&log_buffer));
+
         scrolled_window.set_child(Some(&log_view));
         main_vbox.append(&scrolled_window);
 
+        let cancel_button = Button::with_label("⏹️ Cancel");
+        cancel_button.set_halign(gtk::Align::End);
+        cancel_button.set_tooltip_text(Some("Stop the running operation. Downloads stop right away; a running dpkg step is finished first."));
+        cancel_button.set_visible(false);
+        main_vbox.append(&cancel_button);
+
         window.set_child(Some(&main_vbox));
 
-        Self {
+        let progress = Self {
             window,
             progress_bar,
             log_view,
             log_buffer,
             status_label,
+            download_label,
+            cancel_button,
+            cancel: Rc::new(RefCell::new(None)),
+            critical: Rc::new(Cell::new(false)),
+            job: Rc::new(Cell::new(None)),
+        };
+
+        let this = progress.clone();
+        progress.cancel_button.connect_clicked(move |button| {
+            if let Some(cancel) = this.cancel.borrow().as_ref() {
+                button.set_sensitive(false);
+                this.set_status("⏹️ Cancelling...");
+                this.append_log("Cancelling, waiting for the running command to stop...");
+                cancel.cancel();
+            }
+        });
+
+        let this = progress.clone();
+        progress.window.connect_close_request(move |_| {
+            if !this.critical.get() {
+                return glib::Propagation::Proceed;
+            }
+            this.confirm_close();
+            glib::Propagation::Stop
+        });
+        progress
+    }
+
+    /// Interrupting dpkg can leave packages half configured, so closing the
+    /// window during a transaction only offers to hide it. The main window
+    /// lists the hidden operation and can bring it back.
+    fn confirm_close(&self) {
+        let window = &self.window;
+        let dialog = MessageDialog::builder()
+            .transient_for(window)
+            .modal(true)
+            .message_type(MessageType::Warning)
+            .buttons(ButtonsType::None)
+            .text("⚠️ Packages Are Being Changed")
+            .secondary_text("Stopping now could leave your system with half-installed packages. The operation can continue in the background; MeaUpdater shows the result when it is done.")
+            .build();
+        dialog.add_button("Keep Window Open", ResponseType::Cancel);
+        dialog.add_button("Continue in Background", ResponseType::Accept);
+        dialog.set_default_response(ResponseType::Cancel);
+
+        let this = self.clone();
+        dialog.connect_response(move |dlg, response| {
+            dlg.close();
+            if response != ResponseType::Accept {
+                return;
+            }
+            let title = this.window.title().map(|title| title.to_string()).unwrap_or_default();
+            let status = this.status_label.label().to_string();
+            let id = jobs::detach(&this.window, &title, &status, this.progress_bar.fraction());
+            this.job.set(Some(id));
+            if let Some(app) = gio::Application::default() {
+                let notification = gio::Notification::new("MeaUpdater");
+                notification.set_icon(&install::app_icon());
+                notification.set_body(Some("📦 Packages are being changed in the background. Do not shut down until MeaUpdater reports the result."));
+                app.send_notification(Some(BACKGROUND_NOTIFICATION_ID), &notification);
+            }
+        });
+        dialog.show();
+    }
+
+    /// Shows the window again after it was sent to the background
+    fn return_from_background(&self) {
+        if let Some(app) = gio::Application::default() {
+            app.withdraw_notification(BACKGROUND_NOTIFICATION_ID);
+        }
+        match self.job.take() {
+            Some(id) => jobs::reattach(id),
+            None if !self.window.is_visible() => self.window.present(),
+            None => {}
         }
     }
 
@@ -96,10 +210,16 @@ impl ProgressWindow {
     pub fn set_progress(&self, fraction: f64, text: &str) {
         self.progress_bar.set_fraction(fraction);
         self.progress_bar.set_text(Some(text));
+        if let Some(id) = self.job.get() {
+            jobs::update(id, None, Some(fraction));
+        }
     }
 
     pub fn set_status(&self, status: &str) {
         self.status_label.set_markup(&format!("<b>{}</b>", status));
+        if let Some(id) = self.job.get() {
+            jobs::update(id, Some(status), None);
+        }
     }
 
     pub fn append_log(&self, text: &str) {
@@ -112,483 +232,393 @@ impl ProgressWindow {
     }
 
     
-    fn needs_apt_update() -> bool {
-        
-        if !std::path::Path::new("/var/lib/apt/lists").exists() {
-            return true;
-        }
-
-        
-        if let Ok(last_update_guard) = LAST_APT_UPDATE.lock() {
-            if let Some(last_update) = *last_update_guard {
-                let current_time = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-                
-                return current_time - last_update > APT_UPDATE_CACHE_DURATION;
+    /// Reads the child's stderr on its own thread so a full pipe cannot
+    /// stall it; the lines are logged and returned for error classification.
+    fn collect_stderr(child: &mut Child, tx: &UnboundedSender<ProgressMessage>) -> thread::JoinHandle<String> {
+        let stderr = child.stderr.take();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let mut collected = String::new();
+            if let Some(stderr) = stderr {
+                for line in BufReader::new(stderr).lines().map_while(|line| line.ok()) {
+                    let _ = tx.send(ProgressMessage::Log(line.clone()));
+                    collected.push_str(&line);
+                    collected.push('\n');
+                }
             }
-        }
-        
-        true
+            collected
+        })
     }
 
-    
-    fn update_apt_update_time() {
-        if let Ok(mut last_update_guard) = LAST_APT_UPDATE.lock() {
-            let current_time = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-            *last_update_guard = Some(current_time);
-        }
-    }
+    /// Runs `work` on its own thread, showing the messages it sends, and
+    /// resolves with its result once it is done. The window closes shortly
+    /// after `ProgressMessage::CheckComplete`.
+    pub async fn run_check<T, F>(&self, work: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(UnboundedSender<ProgressMessage>) -> Result<T, Error> + Send + 'static,
+    {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ProgressMessage>();
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel::<Result<T, Error>>();
 
-    
-    fn run_apt_update_if_needed(tx: &mpsc::Sender<ProgressMessage>) -> Result<(), String> {
-        if !Self::needs_apt_update() {
-            let _ = tx.send(ProgressMessage::Log("Apt cache is up to date, skipping apt update...".to_string()));
-            return Ok(());
-        }
+        thread::spawn(move || {
+            let _ = result_tx.send(work(tx));
+        });
 
-        let _ = tx.send(ProgressMessage::Log("Running the apt update command...".to_string()));
-
-        match Command::new("pkexec")
-            .args(&["apt", "update"])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-        {
-            Ok(mut child) => {
-                if let Some(stdout) = child.stdout.take() {
-                    let reader = BufReader::new(stdout);
-                    for line in reader.lines() {
-                        if let Ok(line) = line {
-                            let _ = tx.send(ProgressMessage::Log(line));
-                        }
-                    }
-                }
-                match child.wait() {
-                    Ok(status) => {
-                        if !status.success() {
-                            return Err("apt-update failed.".to_string());
-                        }
-                        
-                        Self::update_apt_update_time();
-                        let _ = tx.send(ProgressMessage::Log("apt update completed successfully.".to_string()));
-                        Ok(())
-                    }
-                    Err(e) => {
-                        Err(format!("apt update error: {}", e))
-                    }
+        // The channel closes when the worker thread and its readers are done
+        while let Some(message) = rx.recv().await {
+            match message {
+                ProgressMessage::Status(status) => self.set_status(&status),
+                ProgressMessage::Progress(fraction, text) => self.set_progress(fraction, &text),
+                ProgressMessage::Log(line) => self.append_log(&line),
+                ProgressMessage::CheckComplete => {
+                    let window = self.window.clone();
+                    glib::timeout_add_seconds_local(2, move || {
+                        window.close();
+                        glib::ControlFlow::Break
+                    });
                 }
             }
-            Err(e) => {
-                Err(format!("apt update initialization error: {}", e))
-            }
         }
+
+        let result = result_rx.await
+            .unwrap_or_else(|_| Err(Error::Other("The check stopped unexpectedly".to_string())));
+        if let Err(error) = &result {
+            // The caller shows the error with a hint for its kind
+            self.status_label.set_markup(&format!("<b><span color='red'>❌ Error: {}</span></b>", glib::markup_escape_text(&error.to_string())));
+        }
+        result
     }
 
     pub async fn check_updates_with_progress(&self) -> Result<Vec<crate::model::PackageUpdate>, Error> {
-        let (tx, rx) = mpsc::channel::<ProgressMessage>();
-        let (result_tx, result_rx) = mpsc::channel::<Result<Vec<crate::model::PackageUpdate>, Error>>();
-
-        
-        let progress_bar = self.progress_bar.clone();
-        let status_label = self.status_label.clone();
-        let log_buffer = self.log_buffer.clone();
-        let log_view = self.log_view.clone();
-        let window = self.window.clone();
-
-        
-        let rx = Arc::new(Mutex::new(rx));
-        let rx_clone = rx.clone();
-
-        
-        thread::spawn(move || {
+        self.run_check(|tx| {
             let _ = tx.send(ProgressMessage::Status("Checking the package list...".to_string()));
             let _ = tx.send(ProgressMessage::Progress(0.1, "10%".to_string()));
 
-            
-            if let Err(e) = Self::run_apt_update_if_needed(&tx) {
-                let _ = tx.send(ProgressMessage::Error(e));
-                let _ = result_tx.send(Err(anyhow::anyhow!("apt update error")));
-                return;
-            }
+            let forward = |event: TransactionEvent| {
+                let message = match event {
+                    TransactionEvent::Status(status) => ProgressMessage::Status(status),
+                    TransactionEvent::Log(line) => ProgressMessage::Log(line),
+                    TransactionEvent::Progress(_) | TransactionEvent::Conffile(..) | TransactionEvent::Download { .. } => return,
+                };
+                let _ = tx.send(message);
+            };
+            apt::update_lists_if_needed(&forward)?;
 
             let _ = tx.send(ProgressMessage::Status("Checking for updatable packages...".to_string()));
             let _ = tx.send(ProgressMessage::Progress(0.6, "60%".to_string()));
             let _ = tx.send(ProgressMessage::Log("Running apt list --upgradable command...".to_string()));
 
-            
-            match Command::new("apt")
-                .args(&["list", "--upgradable"])
+            let mut child = Command::new("apt")
+                .args(["list", "--upgradable"])
                 .env("LANG", "C")
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
                 .spawn()
-            {
-                Ok(mut child) => {
-                    let mut output_text = String::new();
-                    if let Some(stdout) = child.stdout.take() {
-                        let reader = BufReader::new(stdout);
-                        for line in reader.lines() {
-                            if let Ok(line) = line {
-                                let _ = tx.send(ProgressMessage::Log(line.clone()));
-                                output_text.push_str(&line);
-                                output_text.push('\n');
-                            }
-                        }
-                    }
-
-                    match child.wait() {
-                        Ok(status) => {
-                            if status.success() {
-                                let _ = tx.send(ProgressMessage::Progress(0.9, "90%".to_string()));
-                                let _ = tx.send(ProgressMessage::Status("Packing list is being processed...".to_string()));
-                                
-                                let packages = crate::apt::parse_apt_list_output(&output_text);
-                                let package_count = packages.len();
-                                
-                                let _ = tx.send(ProgressMessage::Progress(1.0, "100%".to_string()));
-                                if package_count == 0 {
-                                    let _ = tx.send(ProgressMessage::Status("✅ All packages are up to date!".to_string()));
-                                    let _ = tx.send(ProgressMessage::Log("The package required to be updated was not found.".to_string()));
-                                } else {
-                                    let _ = tx.send(ProgressMessage::Status(format!("✅ {} update found!", package_count)));
-                                    let _ = tx.send(ProgressMessage::Log(format!("{} updatable package found.", package_count)));
-                                }
-                                let _ = tx.send(ProgressMessage::CheckComplete);
-                                let _ = result_tx.send(Ok(packages));
-                            } else {
-                                let _ = tx.send(ProgressMessage::Error("Unable to retrieve package list.".to_string()));
-                                let _ = result_tx.send(Err(anyhow::anyhow!("apt list command failed")));
-                            }
-                        }
-                        Err(e) => {
-                            let _ = tx.send(ProgressMessage::Error(format!("Command error: {}", e)));
-                            let _ = result_tx.send(Err(anyhow::anyhow!("Command error: {}", e)));
-                        }
-                    }
-                }
-                Err(e) => {
-                    let _ = tx.send(ProgressMessage::Error(format!("apt list initialization error: {}", e)));
-                    let _ = result_tx.send(Err(anyhow::anyhow!("apt list initialization error: {}", e)));
+                .context("apt list initialization error")?;
+            let stderr = Self::collect_stderr(&mut child, &tx);
+            let mut output_text = String::new();
+            if let Some(stdout) = child.stdout.take() {
+                for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                    let _ = tx.send(ProgressMessage::Log(line.clone()));
+                    output_text.push_str(&line);
+                    output_text.push('\n');
                 }
             }
-        });
-
-        
-        let result_packages = Arc::new(Mutex::new(Vec::new()));
-        let result_error = Arc::new(Mutex::new(None::<Error>));
-        let is_complete = Arc::new(Mutex::new(false));
-
-        let result_packages_clone = result_packages.clone();
-        let result_error_clone = result_error.clone();
-        let is_complete_clone = is_complete.clone();
 
-        
-        glib::timeout_add_local(Duration::from_millis(100), move || {
-            let mut messages_to_process = Vec::new();
-            
-            
-            if let Ok(rx_guard) = rx_clone.try_lock() {
-                while let Ok(msg) = rx_guard.try_recv() {
-                    messages_to_process.push(msg);
-                }
+            let status = child.wait().context("Command error")?;
+            if !status.success() {
+                let stderr = stderr.join().unwrap_or_default();
+                return Err(Error::from_status("apt list --upgradable", &status, &stderr));
             }
 
-            
-            if let Ok(res) = result_rx.try_recv() {
-                match res {
-                    Ok(packages) => {
-                        if let Ok(mut packages_guard) = result_packages_clone.try_lock() {
-                            *packages_guard = packages;
-                        }
-                    }
-                    Err(error) => {
-                        if let Ok(mut error_guard) = result_error_clone.try_lock() {
-                            *error_guard = Some(error);
-                        }
-                    }
-                }
-                if let Ok(mut complete_guard) = is_complete_clone.try_lock() {
-                    *complete_guard = true;
-                }
+            let _ = tx.send(ProgressMessage::Progress(0.9, "90%".to_string()));
+            let _ = tx.send(ProgressMessage::Status("Packing list is being processed...".to_string()));
+
+            let mut packages = crate::apt::parse_apt_list_output(&output_text);
+            crate::apt::annotate_sizes(&mut packages);
+            crate::apt::mark_security_updates(&mut packages);
+            crate::update_age::annotate_first_seen(&mut packages);
+            let _ = tx.send(ProgressMessage::Log("Checking for Flatpak updates...".to_string()));
+            flatpak_manager::append_updates(&mut packages);
+            let _ = tx.send(ProgressMessage::Log("Checking for snap updates...".to_string()));
+            snap_manager::append_updates(&mut packages);
+            let package_count = packages.len();
+
+            let _ = tx.send(ProgressMessage::Progress(1.0, "100%".to_string()));
+            if package_count == 0 {
+                let _ = tx.send(ProgressMessage::Status("✅ All packages are up to date!".to_string()));
+                let _ = tx.send(ProgressMessage::Log("The package required to be updated was not found.".to_string()));
+            } else {
+                let _ = tx.send(ProgressMessage::Status(format!("✅ {} update found!", package_count)));
+                let _ = tx.send(ProgressMessage::Log(format!("{} updatable package found.", package_count)));
             }
+            let _ = tx.send(ProgressMessage::CheckComplete);
+            Ok(packages)
+        }).await
+    }
 
-            
-            for msg in messages_to_process {
-                match msg {
-                    ProgressMessage::Status(status) => {
-                        status_label.set_markup(&format!("<b>{}</b>", status));
-                    }
-                    ProgressMessage::Progress(fraction, text) => {
-                        progress_bar.set_fraction(fraction);
-                        progress_bar.set_text(Some(&text));
-                    }
-                    ProgressMessage::Log(log) => {
-                        let mut end_iter = log_buffer.end_iter();
-                        log_buffer.insert(&mut end_iter, &format!("{}\n", log));
-                        
-                        
-                        let mark = log_buffer.create_mark(None, &end_iter, false);
-                        log_view.scroll_mark_onscreen(&mark);
-                    }
-                    ProgressMessage::Error(error) => {
-                        status_label.set_markup(&format!("<b><span color='red'>❌ Error: {}</span></b>", error));
-                        
-                        
-                        let dialog = MessageDialog::builder()
-                            .transient_for(&window)
-                            .modal(true)
-                            .message_type(MessageType::Error)
-                            .buttons(ButtonsType::Ok)
-                            .text(&format!("❌ Update Check Error:\n{}", error))
-                            .build();
-                        dialog.connect_response(|dlg, _| dlg.close());
-                        dialog.show();
-                        
-                        return glib::ControlFlow::Break;
-                    }
-                    ProgressMessage::CheckComplete => {
-                        
-                        glib::timeout_add_seconds_local(2, {
-                            let window = window.clone();
-                            move || {
-                                window.close();
-                                glib::ControlFlow::Break
-                            }
-                        });
-                        
-                        return glib::ControlFlow::Break;
-                    }
-                    _ => {} 
-                }
-            }
-            
-            glib::ControlFlow::Continue
+    fn show_conffile_dialog(parent: &Window, path: &str, diff: &str, reply: mpsc::Sender<ConffileChoice>) {
+        let dialog = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Configuration File Changed")
+            .default_width(900)
+            .default_height(500)
+            .build();
+
+        dialog.add_button("Keep Current Version", ResponseType::Reject);
+        dialog.add_button("Install Package Maintainer's Version", ResponseType::Accept);
+        dialog.set_default_response(ResponseType::Reject);
+
+        let content_area = dialog.content_area();
+        let main_vbox = GtkBox::new(Orientation::Vertical, 12);
+        main_vbox.set_margin_top(16);
+        main_vbox.set_margin_bottom(16);
+        main_vbox.set_margin_start(16);
+        main_vbox.set_margin_end(16);
+
+        let title_label = Label::new(None);
+        title_label.set_markup(&format!(
+            "<b>⚙️ {}</b> was modified on this system, and the update ships a new version.",
+            glib::markup_escape_text(path)
+        ));
+        title_label.set_halign(gtk::Align::Start);
+        title_label.set_wrap(true);
+        main_vbox.append(&title_label);
+
+        let columns_label = Label::new(Some("Current version (left) · Package maintainer's version (right)"));
+        columns_label.set_halign(gtk::Align::Start);
+        columns_label.add_css_class("dim-label");
+        main_vbox.append(&columns_label);
+
+        let diff_buffer = TextBuffer::new(None::<&gtk::TextTagTable>);
+        diff_buffer.set_text(diff);
+        let diff_view = TextView::with_buffer(&diff_buffer);
+        diff_view.set_editable(false);
+        diff_view.set_cursor_visible(false);
+        diff_view.set_monospace(true);
+
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .build();
+        scrolled_window.set_child(Some(&diff_view));
+        main_vbox.append(&scrolled_window);
+
+        content_area.append(&main_vbox);
+
+        dialog.connect_response(move |dlg, response| {
+            let choice = if response == ResponseType::Accept {
+                ConffileChoice::InstallNew
+            } else {
+                ConffileChoice::KeepCurrent
+            };
+            let _ = reply.send(choice);
+            dlg.close();
         });
 
-        
-        loop {
-            glib::MainContext::default().iteration(false);
-            thread::sleep(Duration::from_millis(50));
-            
-            if let Ok(complete_guard) = is_complete.try_lock() {
-                if *complete_guard {
-                    
-                    if let Ok(error_guard) = result_error.try_lock() {
-                        if let Some(error) = error_guard.as_ref() {
-                            return Err(anyhow::anyhow!("{}", error));
-                        }
-                    }
-                    
-                    
-                    if let Ok(packages_guard) = result_packages.try_lock() {
-                        return Ok(packages_guard.clone());
-                    }
-                }
-            }
-        }
+        dialog.show();
     }
 
     pub async fn install_packages_with_progress(&self, packages: &[String]) -> Result<(), Error> {
-        let (tx, rx) = mpsc::channel::<ProgressMessage>();
+        self.install_with_progress(packages, true).await
+    }
 
-        
-        let packages_clone = packages.to_vec();
-        
-        
-        let progress_bar = self.progress_bar.clone();
-        let status_label = self.status_label.clone();
-        let log_buffer = self.log_buffer.clone();
-        let log_view = self.log_view.clone();
-        let window = self.window.clone();
+    /// Installs `.deb` files from a local directory. The package lists are
+    /// not refreshed since the machine may be offline.
+    pub async fn install_local_debs_with_progress(&self, debs: &[String]) -> Result<(), Error> {
+        self.install_with_progress(debs, false).await
+    }
 
-        
-        let rx = Arc::new(Mutex::new(rx));
-        let rx_clone = rx.clone();
+    async fn install_with_progress(&self, packages: &[String], refresh_lists: bool) -> Result<(), Error> {
+        let mut transaction = Transaction::install(packages).journal(OperationKind::Install);
+        if refresh_lists {
+            transaction = transaction.refresh_lists();
+        }
+        self.run_transaction(transaction, "✅ Updates installed successfully!").await
+    }
 
-        
-        let is_complete = Arc::new(Mutex::new(false));
-        let has_error = Arc::new(Mutex::new(false));
-        let is_complete_clone = is_complete.clone();
-        let has_error_clone = has_error.clone();
+    /// Executes `transaction` while showing its progress, conffile questions
+    /// and outcome. The window closes once the success message is dismissed.
+    /// Failures are explained in the window itself, so callers of this and
+    /// the other install methods only need to check for success.
+    pub async fn run_transaction(&self, transaction: Transaction, success_message: &str) -> Result<(), Error> {
+        let summary = transaction.summary();
+        self.run_operation(&summary, move |report, cancel| transaction.cancellable(cancel.clone()).execute(report), success_message).await
+    }
+
+    /// Like `run_transaction`, for any work that reports `TransactionEvent`s,
+    /// such as an APT transaction followed by Flatpak updates. `summary`
+    /// completes "Could not …" in the error dialog. The Cancel button
+    /// triggers the token `work` gets.
+    pub async fn run_operation<F>(&self, summary: &str, work: F, success_message: &str) -> Result<(), Error>
+    where
+        F: FnOnce(&dyn Fn(TransactionEvent), &CancelToken) -> Result<(), Error> + Send + 'static,
+    {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<TransactionEvent>();
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel::<Result<(), Error>>();
+        self.critical.set(true);
+
+        let cancel = CancelToken::new();
+        self.cancel.replace(Some(cancel.clone()));
+        self.cancel_button.set_sensitive(true);
+        self.cancel_button.set_visible(apt::can_cancel());
 
-        
         thread::spawn(move || {
-            let _ = tx.send(ProgressMessage::Status("Checking the package list...".to_string()));
-            let _ = tx.send(ProgressMessage::Progress(0.1, "10%".to_string()));
+            let result = work(&|event| {
+                let _ = tx.send(event);
+            }, &cancel);
+            let _ = result_tx.send(result);
+        });
 
-            
-            if let Err(e) = Self::run_apt_update_if_needed(&tx) {
-                let _ = tx.send(ProgressMessage::Error(e));
-                return;
+        // The channel closes when the worker thread is done
+        let started = Instant::now();
+        let mut clock = ProgressClock::default();
+        while let Some(event) = rx.recv().await {
+            match event {
+                TransactionEvent::Status(status) => self.set_status(&glib::markup_escape_text(&status)),
+                TransactionEvent::Progress(fraction) => {
+                    clock.add(started.elapsed().as_millis() as u64, fraction);
+                    let text = match clock.remaining() {
+                        Some(seconds) => format!("{}% · {}", (fraction * 100.0) as u32, format::time_left(seconds)),
+                        None => format!("{}%", (fraction * 100.0) as u32),
+                    };
+                    self.set_progress(fraction, &text);
+                }
+                TransactionEvent::Log(line) => self.append_log(&line),
+                TransactionEvent::Conffile(path, diff, reply) => {
+                    self.set_status(&format!("⚙️ Waiting for your decision about {}", glib::markup_escape_text(&path)));
+                    Self::show_conffile_dialog(&self.window, &path, &diff, reply);
+                }
+                TransactionEvent::Download { host, bytes_per_second } => {
+                    let speed = bytes_per_second
+                        .map(|speed| format!(" · {}/s", format::size(speed)))
+                        .unwrap_or_default();
+                    self.download_label.set_text(&format!("🌐 Downloading from {}{}", host, speed));
+                    self.download_label.set_visible(true);
+                }
             }
+        }
 
-            let _ = tx.send(ProgressMessage::Status("Installing packages...".to_string()));
-            let _ = tx.send(ProgressMessage::Progress(0.3, "30%".to_string()));
+        let result = result_rx.await
+            .unwrap_or_else(|_| Err(Error::Other("The package manager thread stopped unexpectedly".to_string())));
+        self.critical.set(false);
+        self.cancel.replace(None);
+        self.cancel_button.set_visible(false);
+        self.return_from_background();
+        match &result {
+            Err(Error::Cancelled) => {
+                self.download_label.set_visible(false);
+                self.set_status("⏹️ Cancelled");
+                self.append_log("Cancelled. Packages that were already changed stay changed.");
+            }
+            Ok(()) => {
+                self.set_progress(1.0, "100%");
+                self.set_status(success_message);
+                self.append_log("Completed.");
+
+                let dialog = MessageDialog::builder()
+                    .transient_for(&self.window)
+                    .modal(true)
+                    .message_type(MessageType::Info)
+                    .buttons(ButtonsType::Ok)
+                    .text(success_message)
+                    .build();
+                let window = self.window.clone();
+                dialog.connect_response(move |dlg, _| {
+                    dlg.close();
+                    window.close();
+                });
+                dialog.show();
+            }
+            Err(error) => {
+                self.status_label.set_markup(&format!("<b><span color='red'>❌ Error: {}</span></b>", glib::markup_escape_text(&error.to_string())));
+                error::show_error_dialog(&self.window, &format!("❌ Could not {}", summary), error, None);
+            }
+        }
+        result
+    }
+}
 
-            
-            let mut args = vec!["apt", "install", "-y"];
-            for pkg in &packages_clone {
-                args.push(pkg);
+/// Simulates `transaction` and asks before running it when apt would change
+/// more than the requested packages, e.g. pull in dependencies or remove
+/// packages that depend on them, or when a change looks unusual, like a
+/// downgrade or a switch of repository. Otherwise `proceed` runs right away.
+pub fn confirm_transaction<F: Fn(Transaction) + 'static>(parent: &impl IsA<Window>, transaction: Transaction, proceed: F) {
+    let parent = parent.clone().upcast::<Window>();
+    glib::spawn_future_local(async move {
+        let simulated = transaction.clone();
+        let planned = gio::spawn_blocking(move || {
+            simulated.plan().map(|plan| {
+                let anomalies = anomalies::find(&plan);
+                (plan, anomalies)
+            })
+        }).await;
+        let (plan, anomalies) = match planned {
+            Ok(Ok(planned)) => planned,
+            Ok(Err(e)) => {
+                error::show_error_dialog(&parent, &format!("❌ Could not {}", transaction.summary()), &e, None);
+                return;
+            }
+            Err(_) => {
+                proceed(transaction);
+                return;
             }
+        };
 
-            let _ = tx.send(ProgressMessage::Log(format!("Command: pkexec {}", args.join(" "))));
+        let extra = plan.extra_packages(transaction.packages());
+        if extra.is_empty() && anomalies.is_empty() {
+            proceed(transaction);
+            return;
+        }
 
-            match Command::new("pkexec")
-                .args(&args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-            {
-                Ok(mut child) => {
-                    let total_packages = packages_clone.len() as f64;
-                    let mut installed_count = 0.0;
-
-                    if let Some(stdout) = child.stdout.take() {
-                        let reader = BufReader::new(stdout);
-                        for line in reader.lines() {
-                            if let Ok(line) = line {
-                                let _ = tx.send(ProgressMessage::Log(line.clone()));
-                                
-                                
-                                if line.contains("Setting up") || line.contains("Processing") {
-                                    installed_count += 1.0;
-                                    let progress = 0.3 + (installed_count / total_packages) * 0.6;
-                                    let percent = (progress * 100.0) as i32;
-                                    let _ = tx.send(ProgressMessage::Progress(progress, format!("{}%", percent)));
-                                }
-                            }
-                        }
-                    }
-
-                    match child.wait() {
-                        Ok(status) => {
-                            if status.success() {
-                                let _ = tx.send(ProgressMessage::Progress(1.0, "100%".to_string()));
-                                let _ = tx.send(ProgressMessage::Status("✅ All updates installed successfully!".to_string()));
-                                let _ = tx.send(ProgressMessage::Log("Installation completed.".to_string()));
-                                let _ = tx.send(ProgressMessage::Success);
-                            } else {
-                                let _ = tx.send(ProgressMessage::Error("Installation failed.".to_string()));
-                            }
-                        }
-                        Err(e) => {
-                            let _ = tx.send(ProgressMessage::Error(format!("Command error: {}", e)));
-                        }
-                    }
-                }
-                Err(e) => {
-                    let _ = tx.send(ProgressMessage::Error(format!("Installation initialization error: {}", e)));
-                }
+        let mut changes = Vec::new();
+        let new_packages: Vec<&String> = plan.install.iter().filter(|name| extra.contains(name)).collect();
+        let upgrades: Vec<&String> = plan.upgrade.iter().filter(|name| extra.contains(name)).collect();
+        let removals: Vec<&String> = plan.remove.iter().filter(|name| extra.contains(name)).collect();
+        for (label, names) in [("📦 Newly installed", new_packages), ("⬆️ Also upgraded", upgrades), ("🗑️ Removed", removals)] {
+            if !names.is_empty() {
+                let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+                changes.push(format!("{} ({}): {}", label, names.len(), names.join(", ")));
             }
-        });
+        }
 
-       
-        glib::timeout_add_local(Duration::from_millis(100), move || {
-            let mut messages_to_process = Vec::new();
-            
-            
-            if let Ok(rx_guard) = rx_clone.try_lock() {
-                while let Ok(msg) = rx_guard.try_recv() {
-                    messages_to_process.push(msg);
-                }
-            }
+        let mut details = String::new();
+        if !changes.is_empty() {
+            details.push_str(&format!("Beyond what you selected:\n\n{}\n\n", changes.join("\n\n")));
+        }
+        if !anomalies.is_empty() {
+            let lines: Vec<String> = anomalies.iter()
+                .map(|(name, anomaly)| format!("• {} {}", name, anomaly.message()))
+                .collect();
+            details.push_str(&format!(
+                "⚠️ Unusual changes, which can mean a misconfigured or compromised repository:\n\n{}\n\n",
+                lines.join("\n")
+            ));
+        }
 
-            
-            for msg in messages_to_process {
-                match msg {
-                    ProgressMessage::Status(status) => {
-                        status_label.set_markup(&format!("<b>{}</b>", status));
-                    }
-                    ProgressMessage::Progress(fraction, text) => {
-                        progress_bar.set_fraction(fraction);
-                        progress_bar.set_text(Some(&text));
-                    }
-                    ProgressMessage::Log(log) => {
-                        let mut end_iter = log_buffer.end_iter();
-                        log_buffer.insert(&mut end_iter, &format!("{}\n", log));
-                        
-                        
-                        let mark = log_buffer.create_mark(None, &end_iter, false);
-                        log_view.scroll_mark_onscreen(&mark);
-                    }
-                    ProgressMessage::Error(error) => {
-                        status_label.set_markup(&format!("<b><span color='red'>❌ Error: {}</span></b>", error));
-                        
-                        
-                        let dialog = MessageDialog::builder()
-                            .transient_for(&window)
-                            .modal(true)
-                            .message_type(MessageType::Error)
-                            .buttons(ButtonsType::Ok)
-                            .text(&format!("❌ Installation Error:\n{}", error))
-                            .build();
-                        dialog.connect_response(|dlg, _| dlg.close());
-                        dialog.show();
-                        
-                        if let Ok(mut error_guard) = has_error_clone.try_lock() {
-                            *error_guard = true;
-                        }
-                        if let Ok(mut complete_guard) = is_complete_clone.try_lock() {
-                            *complete_guard = true;
-                        }
-                        
-                        return glib::ControlFlow::Break;
-                    }
-                    ProgressMessage::Success => {
-                        
-                        let dialog = MessageDialog::builder()
-                            .transient_for(&window)
-                            .modal(true)
-                            .message_type(MessageType::Info)
-                            .buttons(ButtonsType::Ok)
-                            .text("✅ Updates installed successfully!")
-                            .build();
-                        
-                        let window_clone = window.clone();
-                        dialog.connect_response(move |dlg, _| {
-                            dlg.close();
-                            window_clone.close();
-                        });
-                        dialog.show();
-                        
-                        if let Ok(mut complete_guard) = is_complete_clone.try_lock() {
-                            *complete_guard = true;
-                        }
-                        
-                        return glib::ControlFlow::Break;
-                    }
-                    ProgressMessage::CheckComplete => {
-                        
-                        
-                    }
-                }
+        let dialog = MessageDialog::builder()
+            .transient_for(&parent)
+            .modal(true)
+            .message_type(if plan.remove.is_empty() && anomalies.is_empty() { MessageType::Question } else { MessageType::Warning })
+            .buttons(ButtonsType::YesNo)
+            .text("📋 Review Changes")
+            .secondary_text(&format!(
+                "To {}, apt will make these changes: {}.\n\n{}Do you want to continue?",
+                transaction.summary(),
+                plan.summary(),
+                details
+            ))
+            .build();
+        dialog.connect_response(move |dlg, response| {
+            dlg.close();
+            if response == ResponseType::Yes {
+                proceed(transaction.clone());
             }
-            
-            glib::ControlFlow::Continue
         });
-
-        
-        loop {
-            glib::MainContext::default().iteration(false);
-            thread::sleep(Duration::from_millis(50));
-            
-            if let Ok(complete_guard) = is_complete.try_lock() {
-                if *complete_guard {
-                    if let Ok(error_guard) = has_error.try_lock() {
-                        if *error_guard {
-                            return Err(anyhow::anyhow!("An error occurred during installation"));
-                        }
-                    }
-                    return Ok(());
-                }
-            }
-        }
-    }
+        dialog.show();
+    });
 }
 
 #[derive(Debug)]
@@ -596,7 +626,5 @@ pub enum ProgressMessage {
     Status(String),
     Progress(f64, String),
     Log(String),
-    Error(String),
-    Success,
     CheckComplete,
 }
@@ -1,8 +1,17 @@
 // src/repo_manager.rs
 
-use anyhow::{Context, Result, bail};
+use crate::error::{Context, Error, Result};
+use crate::helper_protocol::{self, Operation};
+use crate::paths;
+use crate::sysroot;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::privilege;
+use crate::repo_status;
+use crate::util::{self, shell_quote};
 
 #[derive(Debug, Clone)]
 pub struct Repository {
@@ -12,247 +21,1100 @@ pub struct Repository {
     pub components: String,
     pub enabled: bool,
     pub is_source: bool,
+    /// Architectures from `arch=`; empty means all
+    pub architectures: Vec<String>,
+    /// Keyring from `signed-by=`
+    pub signed_by: Option<String>,
     pub file_path: Option<String>, // To keep track of which file it came from
     pub line_number: Option<usize>, // To keep track of which line it is on
+    /// Tool or package that maintains the file; such entries are read-only
+    pub managed_by: Option<String>,
+}
+
+/// Short name for the list: the last path segment of the URI, e.g.
+/// "debian" or "local-repo" for `file:/srv/local-repo/`
+pub fn repository_name(uri: &str) -> String {
+    if let Some(label) = uri.strip_prefix("cdrom:[").and_then(|rest| rest.split_once(']')).map(|(label, _)| label) {
+        return label.to_string();
+    }
+    let location = match uri.split_once("://") {
+        Some((_, location)) => location,
+        // file:/srv/repo and copy:/srv/repo have no authority part
+        None => uri.split_once(':').map(|(_, path)| path).unwrap_or(uri),
+    };
+    match location.trim_end_matches('/').rsplit('/').next() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => uri.to_string(),
+    }
 }
 
 impl Repository {
-    pub fn to_sources_list_line(&self) -> String {
-        let repo_type = if self.is_source { "deb-src" } else { "deb" };
-        let comment = if self.enabled { "" } else { "# " };
-        format!("{}{} {} {} {}", comment, repo_type, self.uri, self.distribution, self.components)
+    fn from_entry(entry: &SourceEntry) -> Repository {
+        Repository {
+            name: repository_name(&entry.uri),
+            uri: entry.uri.clone(),
+            distribution: entry.distribution.clone(),
+            components: entry.components.join(" "),
+            enabled: entry.enabled,
+            is_source: entry.is_source,
+            architectures: entry.option("arch")
+                .map(|archs| archs.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            signed_by: entry.option("signed-by").map(str::to_string),
+            file_path: None,
+            line_number: None,
+            managed_by: None,
+        }
     }
 
-    pub fn from_sources_list_line(line: &str) -> Option<Repository> {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') && !line.contains("deb") {
-            return None;
+    /// Flat repositories keep their indexes in one directory, given as a
+    /// path ending in "/" (usually "./") instead of a suite and components
+    pub fn is_flat(&self) -> bool {
+        self.distribution.ends_with('/')
+    }
+
+    /// Whether the URI points at this machine rather than a server
+    pub fn is_local(&self) -> bool {
+        self.uri.starts_with("file:") || self.uri.starts_with("copy:")
+    }
+
+    /// Text of the suite column
+    pub fn suite_label(&self) -> String {
+        if self.distribution == "./" {
+            "(flat)".to_string()
+        } else if self.is_flat() {
+            format!("{} (flat)", self.distribution)
+        } else {
+            self.distribution.clone()
         }
+    }
+}
 
-        let enabled = !line.starts_with('#');
-        let clean_line = if line.starts_with('#') {
-            line.trim_start_matches('#').trim()
+/// One `deb`/`deb-src` line of a sources file. Unchanged entries are
+/// written back byte for byte; edited ones keep their options and comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceEntry {
+    pub enabled: bool,
+    pub is_source: bool,
+    /// Option words from the `[...]` block, e.g. `arch=amd64`
+    pub options: Vec<String>,
+    pub uri: String,
+    pub distribution: String,
+    pub components: Vec<String>,
+    /// Trailing comment after the components, without the `#`
+    pub comment: Option<String>,
+    indent: String,
+    disabled_marker: String,
+    original: Option<String>,
+}
+
+impl SourceEntry {
+    /// Parses a one-line style entry. Commented lines only count as disabled
+    /// entries when the rest is a valid entry, so prose comments stay prose.
+    pub fn parse(line: &str) -> Option<SourceEntry> {
+        let indent_len = line.len() - line.trim_start().len();
+        let indent = &line[..indent_len];
+        let mut rest = &line[indent_len..];
+
+        let mut disabled_marker = String::new();
+        if rest.starts_with('#') {
+            let body = rest.trim_start_matches('#').trim_start();
+            disabled_marker = rest[..rest.len() - body.len()].to_string();
+            rest = body;
+        }
+
+        let (kind, after_kind) = split_word(rest);
+        let is_source = match kind {
+            "deb" => false,
+            "deb-src" => true,
+            _ => return None,
+        };
+        let mut rest = after_kind.trim_start();
+
+        let mut options = Vec::new();
+        if let Some(inner) = rest.strip_prefix('[') {
+            let end = inner.find(']')?;
+            options = inner[..end].split_whitespace().map(str::to_string).collect();
+            rest = inner[end + 1..].trim_start();
+        }
+
+        // cdrom URIs carry the disc label in brackets, spaces included
+        let uri_len = if rest.starts_with("cdrom:[") {
+            let close = rest.find(']')? + 1;
+            close + rest[close..].find(char::is_whitespace).unwrap_or(rest.len() - close)
         } else {
-            line
+            rest.find(char::is_whitespace).unwrap_or(rest.len())
         };
+        let uri = &rest[..uri_len];
+        if !uri.contains(':') {
+            return None;
+        }
+        let rest = &rest[uri_len..];
 
-        let parts: Vec<&str> = clean_line.split_whitespace().collect();
-        if parts.len() < 4 {
+        let (fields, comment) = match rest.find('#') {
+            Some(pos) => (&rest[..pos], Some(rest[pos + 1..].trim().to_string())),
+            None => (rest, None),
+        };
+        let mut fields = fields.split_whitespace();
+        let distribution = fields.next()?.to_string();
+        let components: Vec<String> = fields.map(str::to_string).collect();
+        // Only flat repositories ("./") may omit the components
+        if components.is_empty() && !distribution.ends_with('/') {
             return None;
         }
 
-        let is_source = parts[0] == "deb-src";
-        let uri = parts[1].to_string();
-        let distribution = parts[2].to_string();
-        let components = parts[3..].join(" ");
-        
-        
-        let name = uri.split('/').last()
-            .unwrap_or(&uri)
-            .replace("http://", "")
-            .replace("https://", "");
-
-        Some(Repository {
-            name,
-            uri,
+        Some(SourceEntry {
+            enabled: disabled_marker.is_empty(),
+            is_source,
+            options,
+            uri: uri.to_string(),
             distribution,
             components,
-            enabled,
-            is_source,
-            file_path: None,
-            line_number: None,
+            comment,
+            indent: indent.to_string(),
+            disabled_marker,
+            original: Some(line.to_string()),
         })
     }
-}
 
-pub fn get_repositories() -> Result<Vec<Repository>> {
-    let mut repositories = Vec::new();
-    
-    // Read the /etc/apt/sources.list file
-    if let Ok(content) = fs::read_to_string("/etc/apt/sources.list") {
-        for (line_num, line) in content.lines().enumerate() {
-            if let Some(mut repo) = Repository::from_sources_list_line(line) {
-                repo.file_path = Some("/etc/apt/sources.list".to_string());
-                repo.line_number = Some(line_num);
-                repositories.push(repo);
+    /// Value of an option such as `arch` or `signed-by`
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options.iter()
+            .filter_map(|option| option.split_once('='))
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Sets or, with `None`, removes an option, keeping the others in place
+    pub fn set_option(&mut self, key: &str, value: Option<&str>) {
+        let position = self.options.iter().position(|option| option.split_once('=').map(|(name, _)| name) == Some(key));
+        match (position, value) {
+            (Some(index), Some(value)) => self.options[index] = format!("{}={}", key, value),
+            (Some(index), None) => {
+                self.options.remove(index);
             }
+            (None, Some(value)) => self.options.push(format!("{}={}", key, value)),
+            (None, None) => {}
         }
     }
-    
-    // Read the files in the /etc/apt/sources.list.d/ directory.
-    if let Ok(entries) = fs::read_dir("/etc/apt/sources.list.d/") {
-        for entry in entries.flatten() {
-            if let Some(path) = entry.path().to_str() {
-                if path.ends_with(".list") {
-                    if let Ok(content) = fs::read_to_string(&entry.path()) {
-                        for (line_num, line) in content.lines().enumerate() {
-                            if let Some(mut repo) = Repository::from_sources_list_line(line) {
-                                repo.file_path = Some(path.to_string());
-                                repo.line_number = Some(line_num);
-                                repositories.push(repo);
-                            }
-                        }
+
+    /// The line as it should be written to the file
+    pub fn render(&self) -> String {
+        if let Some(original) = &self.original {
+            if SourceEntry::parse(original).as_ref() == Some(self) {
+                return original.clone();
+            }
+        }
+
+        let marker = match (self.enabled, self.disabled_marker.is_empty()) {
+            (true, _) => "",
+            (false, true) => "# ",
+            (false, false) => self.disabled_marker.as_str(),
+        };
+        let mut line = format!("{}{}{}", self.indent, marker, if self.is_source { "deb-src" } else { "deb" });
+        if !self.options.is_empty() {
+            line.push_str(&format!(" [{}]", self.options.join(" ")));
+        }
+        line.push_str(&format!(" {} {}", self.uri, self.distribution));
+        for component in &self.components {
+            line.push(' ');
+            line.push_str(component);
+        }
+        if let Some(comment) = &self.comment {
+            line.push_str(&format!(" # {}", comment));
+        }
+        line
+    }
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    match text.find(char::is_whitespace) {
+        Some(pos) => (&text[..pos], &text[pos..]),
+        None => (text, ""),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SourceLine {
+    Entry(SourceEntry),
+    /// Comments, blank lines and anything else, kept verbatim
+    Other(String),
+}
+
+/// A one-line style sources file that can be edited without losing
+/// comments, blank lines or the order of entries
+#[derive(Debug, Clone)]
+pub struct SourcesFile {
+    pub lines: Vec<SourceLine>,
+    trailing_newline: bool,
+}
+
+impl SourcesFile {
+    pub fn parse(content: &str) -> SourcesFile {
+        let lines = content.lines()
+            .map(|line| match SourceEntry::parse(line) {
+                Some(entry) => SourceLine::Entry(entry),
+                None => SourceLine::Other(line.to_string()),
+            })
+            .collect();
+        SourcesFile { lines, trailing_newline: content.ends_with('\n') }
+    }
+
+    pub fn render(&self) -> String {
+        let mut content = self.lines.iter()
+            .map(|line| match line {
+                SourceLine::Entry(entry) => entry.render(),
+                SourceLine::Other(text) => text.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if self.trailing_newline {
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Entries with their zero-based line numbers
+    pub fn entries(&self) -> impl Iterator<Item = (usize, &SourceEntry)> {
+        self.lines.iter().enumerate().filter_map(|(number, line)| match line {
+            SourceLine::Entry(entry) => Some((number, entry)),
+            SourceLine::Other(_) => None,
+        })
+    }
+
+    pub fn entry_mut(&mut self, line_number: usize) -> Option<&mut SourceEntry> {
+        match self.lines.get_mut(line_number) {
+            Some(SourceLine::Entry(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// Removes the entry on `line_number`; other lines are left alone
+    pub fn remove_entry(&mut self, line_number: usize) -> bool {
+        if matches!(self.lines.get(line_number), Some(SourceLine::Entry(_))) {
+            self.lines.remove(line_number);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Comment markers of configuration management tools and installers that
+/// regenerate their sources files
+const MANAGED_MARKERS: &[(&str, &str)] = &[
+    ("this file is automatically configured", "the package's cron job"),
+    ("managed by puppet", "Puppet"),
+    ("managed by ansible", "Ansible"),
+    ("ansible managed", "Ansible"),
+    ("managed by chef", "Chef"),
+    ("managed by salt", "Salt"),
+    ("generated by cloud-init", "cloud-init"),
+    ("created by extrepo", "extrepo"),
+];
+
+/// Names what maintains a sources file, so MeaUpdater leaves it alone.
+/// `symlink_target` is where the file points if it is a symlink and
+/// `owner` the package that ships it, if any.
+pub fn detect_manager(file_name: &str, content: &str, symlink_target: Option<&str>, owner: Option<&str>) -> Option<String> {
+    if file_name.starts_with("extrepo_") {
+        return Some("extrepo".to_string());
+    }
+    if let Some(owner) = owner {
+        return Some(format!("the {} package", owner));
+    }
+    // Markers are in the header comment
+    let header = content.lines()
+        .take_while(|line| line.trim().is_empty() || line.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase();
+    if let Some((_, tool)) = MANAGED_MARKERS.iter().find(|(marker, _)| header.contains(marker)) {
+        return Some(tool.to_string());
+    }
+    symlink_target.map(|target| format!("a symlink to {}", target))
+}
+
+/// Reads `dpkg-query -S` output into file path → package
+pub fn parse_dpkg_search(output: &str) -> HashMap<String, String> {
+    output.lines()
+        .filter_map(|line| line.split_once(": "))
+        // "diversion by ..." lines are not ownership
+        .filter(|(packages, _)| !packages.starts_with("diversion by"))
+        .map(|(packages, path)| (path.trim().to_string(), packages.split(", ").next().unwrap_or(packages).to_string()))
+        .collect()
+}
+
+/// Packages that ship any of `paths`
+fn owning_packages(paths: &[String]) -> HashMap<String, String> {
+    Command::new("dpkg-query")
+        .arg("-S")
+        .args(paths)
+        .env("LANG", "C")
+        .output()
+        .map(|output| parse_dpkg_search(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+/// Reads the stanzas of a deb822 `.sources` file, one repository per type
+/// and suite. These are only listed, never rewritten.
+pub fn parse_deb822_sources(content: &str) -> Vec<Repository> {
+    let mut repositories = Vec::new();
+    for stanza in content.split("\n\n") {
+        let field = |name: &str| {
+            stanza.lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+                .find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    key.trim().eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+                })
+        };
+        let (types, uris, suites) = match (field("Types"), field("URIs"), field("Suites")) {
+            (Some(types), Some(uris), Some(suites)) => (types, uris, suites),
+            _ => continue,
+        };
+        let enabled = field("Enabled").map(|value| value != "no").unwrap_or(true);
+        let components = field("Components").unwrap_or_default();
+
+        for kind in types.split_whitespace() {
+            for uri in uris.split_whitespace() {
+                for suite in suites.split_whitespace() {
+                    let line = format!("{} {} {} {}", kind, uri, suite, components);
+                    if let Some(entry) = SourceEntry::parse(line.trim()) {
+                        let mut repo = Repository::from_entry(&entry);
+                        repo.enabled = enabled;
+                        repo.architectures = field("Architectures")
+                            .map(|archs| archs.split_whitespace().map(str::to_string).collect())
+                            .unwrap_or_default();
+                        repo.signed_by = field("Signed-By").filter(|value| !value.is_empty());
+                        repositories.push(repo);
                     }
                 }
             }
         }
     }
-    
-    Ok(repositories)
+    repositories
 }
 
-pub fn add_repository(uri: &str, distribution: &str, components: &str) -> Result<()> {
-    let repo = Repository {
-        name: "Custom Repository".to_string(),
-        uri: uri.to_string(),
-        distribution: distribution.to_string(),
-        components: components.to_string(),
-        enabled: true,
-        is_source: false,
-        file_path: None,
-        line_number: None,
+fn read_repositories(path: &str, owner: Option<&str>, repositories: &mut Vec<Repository>) {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return,
     };
+    if path.ends_with(".sources") {
+        for mut repo in parse_deb822_sources(&content) {
+            repo.file_path = Some(path.to_string());
+            repo.managed_by = Some("extrepo".to_string());
+            repositories.push(repo);
+        }
+        return;
+    }
+    let symlink_target = fs::read_link(path).ok().map(|target| target.to_string_lossy().into_owned());
+    let file_name = Path::new(path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let managed_by = detect_manager(&file_name, &content, symlink_target.as_deref(), owner);
+
+    for (line_num, entry) in SourcesFile::parse(&content).entries() {
+        let mut repo = Repository::from_entry(entry);
+        repo.file_path = Some(path.to_string());
+        repo.line_number = Some(line_num);
+        repo.managed_by = managed_by.clone();
+        repositories.push(repo);
+    }
+}
+
+pub fn get_repositories() -> Result<Vec<Repository>> {
+    let mut repositories = Vec::new();
+    let mut paths = vec![sysroot::path_string("/etc/apt/sources.list")];
+
+    // Read the files in the /etc/apt/sources.list.d/ directory.
+    if let Ok(entries) = fs::read_dir(sysroot::path("/etc/apt/sources.list.d")) {
+        let mut list_files: Vec<String> = entries.flatten()
+            .filter_map(|entry| entry.path().to_str().map(str::to_string))
+            // extrepo writes deb822 files; those are listed read-only
+            .filter(|path| path.ends_with(".list") || (path.contains("/extrepo_") && path.ends_with(".sources")))
+            .collect();
+        list_files.sort();
+        paths.extend(list_files);
+    }
+
+    let owners = owning_packages(&paths);
+    for path in &paths {
+        read_repositories(path, owners.get(path).map(String::as_str), &mut repositories);
+    }
     
-    let line = format!("{}\n", repo.to_sources_list_line());
-    let temp_file = "/tmp/new_repo.list";
-    fs::write(temp_file, line)?;
-    
-    let status = Command::new("pkexec")
+    Ok(repositories)
+}
+
+/// Replaces `path` with `content` as root. Copying over the existing file
+/// keeps its owner and mode.
+fn write_sources_file(path: &str, content: &str, what: &str) -> Result<()> {
+    write_sources_files(&[(path.to_string(), content.to_string())], what)
+}
+
+/// Replaces several sources files with a single elevated call, so bulk
+/// changes only ask for the password once
+fn write_sources_files(files: &[(String, String)], what: &str) -> Result<()> {
+    // Files written in the sandbox are not visible on the host, so the
+    // helper gets the content itself
+    if matches!(privilege::elevation(), privilege::Elevation::Helper) {
+        for (path, content) in files {
+            let (code, output) = privilege::call_helper(&Operation::WriteSources { path: path.clone(), content: content.clone() });
+            match code {
+                0 => {}
+                helper_protocol::EXIT_NOT_AUTHORIZED => return Err(Error::PermissionDenied(format!("{} failed: {}", what, output.trim()))),
+                _ => return Err(Error::Other(format!("{} failed: {}", what, output.trim()))),
+            }
+        }
+        return Ok(());
+    }
+
+    let mut temp_files = Vec::new();
+    let mut commands = Vec::new();
+    for (index, (path, content)) in files.iter().enumerate() {
+        let temp_file = paths::write_runtime_file(&format!("sources-{}-{}", std::process::id(), index), content.as_bytes())?;
+        commands.push(format!("cp -- {} {}", shell_quote(&temp_file.to_string_lossy()), shell_quote(path)));
+        temp_files.push(temp_file);
+    }
+
+    let status = privilege::elevated_command()
         .arg("sh")
         .arg("-c")
-        .arg(&format!("mv {} /etc/apt/sources.list.d/", temp_file))
-        .status()
-        .context("Repository could not be added")?;
-        
+        .arg(&commands.join(" && "))
+        .status();
+    for temp_file in &temp_files {
+        let _ = fs::remove_file(temp_file);
+    }
+
+    let status = status.with_context(|| format!("{} could not be run", what))?;
     if !status.success() {
-        bail!("Repository add operation failed");
+        return Err(Error::from_status(what, &status, ""));
     }
-    
     Ok(())
 }
 
-pub fn remove_repository(repo: &Repository) -> Result<()> {
-    if let Some(file_path) = &repo.file_path {
-        let content = fs::read_to_string(file_path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        
-        if let Some(line_num) = repo.line_number {
-            if line_num < lines.len() {
-                let mut new_lines = lines.clone();
-                new_lines.remove(line_num);
-                let new_content = new_lines.join("\n");
-                
-                let temp_file = "/tmp/temp_sources.list";
-                fs::write(temp_file, new_content)?;
-                
-                let status = Command::new("pkexec")
-                    .arg("sh")
-                    .arg("-c")
-                    .arg(&format!("mv {} {}", temp_file, file_path))
-                    .status()
-                    .context("Repository could not be deleted")?;
-                    
-                if !status.success() {
-                    bail!("Repository deletion failed");
-                }
+/// Loads the file `repo` came from, lets `change` edit its entry and
+/// writes the file back with everything else untouched
+fn modify_entry<F>(repo: &Repository, what: &str, change: F) -> Result<()>
+where
+    F: FnOnce(&mut SourcesFile, usize) -> bool,
+{
+    let (file_path, line_num) = match (&repo.file_path, repo.line_number) {
+        (Some(file_path), Some(line_num)) => (file_path, line_num),
+        _ => return Ok(()),
+    };
+    if let Some(manager) = &repo.managed_by {
+        return Err(Error::Other(format!("{}: {} is maintained by {} and would be overwritten or broken", what, file_path, manager)));
+    }
+
+    let content = fs::read_to_string(file_path)?;
+    let mut sources = SourcesFile::parse(&content);
+    if !change(&mut sources, line_num) {
+        return Err(Error::Other(format!("{}: line {} of {} is no longer a repository entry", what, line_num + 1, file_path)));
+    }
+    write_sources_file(file_path, &sources.render(), what)
+}
+
+/// What a repository's Release file says about a suite
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReleaseInfo {
+    pub suite: Option<String>,
+    pub codename: Option<String>,
+    pub components: Vec<String>,
+    pub architectures: Vec<String>,
+}
+
+/// Reads the fields of a Release or InRelease file
+pub fn parse_release(content: &str) -> ReleaseInfo {
+    let mut info = ReleaseInfo::default();
+    for line in content.lines() {
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) if !field.starts_with(' ') => (field, value.trim()),
+            _ => continue,
+        };
+        let words = || value.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        match field {
+            "Suite" => info.suite = Some(value.to_string()),
+            "Codename" => info.codename = Some(value.to_string()),
+            "Components" => info.components = words(),
+            "Architectures" => info.architectures = words(),
+            _ => {}
+        }
+    }
+    info
+}
+
+/// Suites to try when the user did not type one: the codenames from
+/// os-release, `stable`, and finally a flat repository
+pub fn suite_candidates(os_release: &str) -> Vec<String> {
+    let mut candidates = Vec::new();
+    for line in os_release.lines() {
+        if let Some(value) = line.strip_prefix("VERSION_CODENAME=").or_else(|| line.strip_prefix("UBUNTU_CODENAME=")) {
+            let codename = value.trim_matches('"').to_string();
+            if !codename.is_empty() && !candidates.contains(&codename) {
+                candidates.push(codename);
             }
         }
     }
-    
-    Ok(())
+    candidates.push("stable".to_string());
+    candidates.push("./".to_string());
+    candidates
+}
+
+/// Where the Release files of `suite` live below `uri`
+pub fn release_urls(uri: &str, suite: &str) -> Vec<String> {
+    let base = uri.trim_end_matches('/');
+    let dir = if suite.ends_with('/') {
+        format!("{}/{}", base, suite.trim_start_matches("./"))
+    } else {
+        format!("{}/dists/{}/", base, suite)
+    };
+    vec![format!("{}InRelease", dir), format!("{}Release", dir)]
+}
+
+/// Path of a `file:` URL such as file:/srv/repo or file:///srv/repo
+fn local_path(url: &str) -> Option<&str> {
+    let path = url.strip_prefix("file:")?;
+    Some(match path.strip_prefix("//") {
+        Some(rest) => rest.strip_prefix("localhost").unwrap_or(rest),
+        None => path,
+    })
 }
 
-pub fn toggle_repository(repo: &Repository) -> Result<()> {
-    if let Some(file_path) = &repo.file_path {
-        let content = fs::read_to_string(file_path)?;
-        let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-        
-        if let Some(line_num) = repo.line_number {
-            if line_num < lines.len() {
-                let line = &lines[line_num];
-                
-                // Toggle the row
-                let new_line = if line.trim_start().starts_with('#') {
-                    // Remove comment (enable)
-                    line.trim_start_matches('#').trim_start().to_string()
-                } else {
-                    // Add comment (disable)
-                    format!("# {}", line)
-                };
-                
-                lines[line_num] = new_line;
-                let new_content = lines.join("\n");
-                
-                let temp_file = "/tmp/temp_sources.list";
-                fs::write(temp_file, new_content)?;
-                
-                let status = Command::new("pkexec")
-                    .arg("sh")
-                    .arg("-c")
-                    .arg(&format!("mv {} {}", temp_file, file_path))
-                    .status()
-                    .context("Repository status could not be changed")?;
-                    
-                if !status.success() {
-                    bail!("Repository state change operation failed");
+/// Reads `file:` repositories from disk and downloads everything else
+fn fetch_url(url: &str) -> Result<Vec<u8>> {
+    if let Some(path) = local_path(url) {
+        return fs::read(path).with_context(|| format!("Could not read {}", path));
+    }
+    util::fetch_url(url, 20)
+}
+
+/// Finds the suite of a repository by downloading its Release file. With
+/// `suite` given only that one is tried.
+pub fn detect_release(uri: &str, suite: Option<&str>) -> Result<(String, ReleaseInfo)> {
+    let candidates = match suite {
+        Some(suite) => vec![suite.to_string()],
+        None => suite_candidates(&fs::read_to_string(sysroot::path("/etc/os-release")).unwrap_or_default()),
+    };
+
+    let mut last_error = None;
+    for candidate in candidates {
+        for url in release_urls(uri, &candidate) {
+            match fetch_url(&url) {
+                Ok(content) => {
+                    let info = parse_release(&String::from_utf8_lossy(&content));
+                    return Ok((candidate, info));
                 }
+                Err(e) => last_error = Some(e),
             }
         }
     }
-    
-    Ok(())
+    Err(last_error.unwrap_or_else(|| Error::Network(format!("No Release file found below {}", uri))))
 }
 
-pub fn edit_repository(old_repo: &Repository, new_uri: &str, new_distribution: &str, new_components: &str) -> Result<()> {
-    if let Some(file_path) = &old_repo.file_path {
-        let content = fs::read_to_string(file_path)?;
-        let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-        
-        if let Some(line_num) = old_repo.line_number {
-            if line_num < lines.len() {
-                let new_repo = Repository {
-                    name: old_repo.name.clone(),
-                    uri: new_uri.to_string(),
-                    distribution: new_distribution.to_string(),
-                    components: new_components.to_string(),
-                    enabled: old_repo.enabled,
-                    is_source: old_repo.is_source,
-                    file_path: old_repo.file_path.clone(),
-                    line_number: old_repo.line_number,
-                };
-                
-                lines[line_num] = new_repo.to_sources_list_line();
-                let new_content = lines.join("\n");
-                
-                let temp_file = "/tmp/temp_sources.list";
-                fs::write(temp_file, new_content)?;
-                
-                let status = Command::new("pkexec")
-                    .arg("sh")
-                    .arg("-c")
-                    .arg(&format!("mv {} {}", temp_file, file_path))
-                    .status()
-                    .context("Repository could not be edited")?;
-                    
-                if !status.success() {
-                    bail!("Repository edit operation failed");
-                }
+const KEYRING_DIR: &str = "/usr/share/keyrings";
+const SOURCES_DIR: &str = "/etc/apt/sources.list.d";
+
+/// File name stem for a repository's list file and keyring, e.g.
+/// `packages.microsoft.com-repos-code`
+pub fn repository_slug(uri: &str) -> String {
+    let location = uri.split("://").nth(1).unwrap_or(uri);
+    let slug: String = location.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '_' { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let slug = slug.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-");
+    if slug.is_empty() { "custom-repository".to_string() } else { slug }
+}
+
+pub fn is_armored_key(key: &[u8]) -> bool {
+    String::from_utf8_lossy(key).contains("-----BEGIN PGP PUBLIC KEY BLOCK-----")
+}
+
+/// Whether binary key data starts with an OpenPGP public key packet
+pub fn is_public_key_packet(key: &[u8]) -> bool {
+    match key.first() {
+        // New packet format: tag in the low six bits
+        Some(&byte) if byte & 0xC0 == 0xC0 => byte & 0x3F == 6,
+        // Old packet format: tag in bits 2-5
+        Some(&byte) if byte & 0x80 != 0 => (byte >> 2) & 0x0F == 6,
+        _ => false,
+    }
+}
+
+/// Converts an ASCII-armored key to the binary form apt expects
+fn dearmor(key: &[u8]) -> Result<Vec<u8>> {
+    let mut child = Command::new("gpg")
+        .args(["--dearmor", "--batch"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .context("gpg could not be run to convert the key")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        std::io::Write::write_all(&mut stdin, key)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Error::Parse(format!("The signing key could not be read: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(output.stdout)
+}
+
+/// Downloads the repository's signing key to /usr/share/keyrings/<slug>.gpg
+/// so it only vouches for this repository, unlike apt-key's global trust
+pub fn install_signing_key(key_url: &str, slug: &str) -> Result<String> {
+    let downloaded = fetch_url(key_url)?;
+    let key = if is_armored_key(&downloaded) { dearmor(&downloaded)? } else { downloaded };
+    if !is_public_key_packet(&key) {
+        return Err(Error::Parse(format!("{} does not contain an OpenPGP public key", key_url)));
+    }
+
+    let keyring = format!("{}/{}.gpg", KEYRING_DIR, slug);
+    let temp_file = paths::write_runtime_file(&format!("key-{}", std::process::id()), &key)?;
+    let status = privilege::elevated_command()
+        .arg("sh")
+        .arg("-c")
+        .arg(&format!("install -D -m 644 -- {} {}", shell_quote(&temp_file.to_string_lossy()), shell_quote(&keyring)))
+        .status();
+    let _ = fs::remove_file(&temp_file);
+
+    let status = status.context("The signing key could not be installed")?;
+    if !status.success() {
+        return Err(Error::from_status("Installing the signing key", &status, ""));
+    }
+    Ok(keyring)
+}
+
+/// A list file name in sources.list.d that is not taken yet
+fn unused_list_file(slug: &str) -> String {
+    let mut path = format!("{}/{}.list", SOURCES_DIR, slug);
+    let mut counter = 2;
+    while Path::new(&path).exists() {
+        path = format!("{}/{}-{}.list", SOURCES_DIR, slug, counter);
+        counter += 1;
+    }
+    path
+}
+
+/// Writes the repository to its own list file. With `key_url` its signing
+/// key is installed first and referenced with `signed-by=`.
+pub fn add_repository(edit: &RepositoryEdit, key_url: Option<&str>) -> Result<()> {
+    edit.validate()?;
+
+    let slug = repository_slug(&edit.uri);
+    let signed_by = match key_url {
+        Some(url) => Some(install_signing_key(url, &slug)?),
+        None => edit.signed_by.clone(),
+    };
+
+    let mut entry = SourceEntry::parse(&format!("deb {} {} {}", edit.uri, edit.distribution, edit.components.join(" ")))
+        .context("The repository line could not be built")?;
+    let archs = edit.architectures.join(",");
+    entry.set_option("arch", Some(archs.as_str()).filter(|a| !a.is_empty()));
+    entry.set_option("signed-by", signed_by.as_deref());
+
+    write_sources_file(&unused_list_file(&slug), &format!("{}\n", entry.render()), "Repository add operation")
+}
+
+pub fn remove_repository(repo: &Repository) -> Result<()> {
+    modify_entry(repo, "Repository deletion", |sources, line_num| sources.remove_entry(line_num))
+}
+
+/// Enables or disables all of `repos`, one rewrite per file. Returns how
+/// many entries actually changed.
+pub fn set_repositories_enabled(repos: &[Repository], enabled: bool) -> Result<usize> {
+    let changes: Vec<(Repository, bool)> = repos.iter().map(|repo| (repo.clone(), enabled)).collect();
+    apply_repository_changes(&changes)
+}
+
+/// Repositories whose wanted state in `enabled` (one flag per repository,
+/// in the same order) differs from their current one
+pub fn pending_changes(repos: &[Repository], enabled: &[bool]) -> Vec<(Repository, bool)> {
+    repos.iter()
+        .zip(enabled)
+        .filter(|(repo, enabled)| repo.enabled != **enabled && repo.managed_by.is_none())
+        .map(|(repo, enabled)| (repo.clone(), *enabled))
+        .collect()
+}
+
+/// Sets every repository in `changes` to its paired state with a single
+/// privileged write. Returns how many entries actually changed.
+pub fn apply_repository_changes(changes: &[(Repository, bool)]) -> Result<usize> {
+    let mut files: Vec<(String, SourcesFile)> = Vec::new();
+    let mut changed = 0;
+
+    // Files maintained by other tools are never rewritten
+    for (repo, enabled) in changes.iter().filter(|(repo, enabled)| repo.enabled != *enabled && repo.managed_by.is_none()) {
+        let (file_path, line_num) = match (&repo.file_path, repo.line_number) {
+            (Some(file_path), Some(line_num)) => (file_path, line_num),
+            _ => continue,
+        };
+        let index = match files.iter().position(|(path, _)| path == file_path) {
+            Some(index) => index,
+            None => {
+                let content = fs::read_to_string(file_path)?;
+                files.push((file_path.clone(), SourcesFile::parse(&content)));
+                files.len() - 1
+            }
+        };
+        if let Some(entry) = files[index].1.entry_mut(line_num) {
+            if entry.enabled != *enabled {
+                entry.enabled = *enabled;
+                changed += 1;
             }
         }
     }
-    
+
+    if changed > 0 {
+        let rendered: Vec<(String, String)> = files.into_iter()
+            .map(|(path, sources)| (path, sources.render()))
+            .collect();
+        write_sources_files(&rendered, "Repository state change operation")?;
+    }
+    Ok(changed)
+}
+
+/// Hosts of the distribution's own archives
+const OFFICIAL_HOSTS: &[&str] = &[
+    "deb.debian.org",
+    "security.debian.org",
+    "ftp.debian.org",
+    "archive.ubuntu.com",
+    "security.ubuntu.com",
+    "ports.ubuntu.com",
+];
+
+pub fn uri_host(uri: &str) -> &str {
+    let uri = uri.rsplit('+').next().unwrap_or(uri);
+    let location = uri.split("://").nth(1).unwrap_or("");
+    let host = location.split('/').next().unwrap_or("");
+    let host = host.rsplit('@').next().unwrap_or(host);
+    host.split(':').next().unwrap_or(host)
+}
+
+/// Whether `uri` points at a Debian or Ubuntu archive or mirror, or at
+/// local installation media
+pub fn is_official_uri(uri: &str) -> bool {
+    if uri.starts_with("cdrom:") || uri.starts_with("file:") {
+        return true;
+    }
+    let host = uri_host(uri).to_lowercase();
+    OFFICIAL_HOSTS.iter().any(|official| host == *official || host.ends_with(&format!(".{}", official)))
+        // Country mirrors such as ftp.de.debian.org
+        || (host.starts_with("ftp.") && host.ends_with(".debian.org"))
+}
+
+/// Which naming the distribution uses for the components of its archive:
+/// contrib/non-free/non-free-firmware or universe/restricted/multiverse
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArchiveFamily {
+    #[default]
+    Debian,
+    Ubuntu,
+}
+
+impl ArchiveFamily {
+    /// Ubuntu and its derivatives by `ID` or `ID_LIKE`, Debian otherwise
+    pub fn from_os_release(content: &str) -> Self {
+        let ubuntu = content.lines()
+            .filter_map(|line| line.strip_prefix("ID=").or_else(|| line.strip_prefix("ID_LIKE=")))
+            .any(|value| value.trim_matches('"').split_whitespace().any(|id| id == "ubuntu"));
+        if ubuntu { ArchiveFamily::Ubuntu } else { ArchiveFamily::Debian }
+    }
+
+    pub fn detect() -> Self {
+        Self::from_os_release(&fs::read_to_string(sysroot::path("/etc/os-release")).unwrap_or_default())
+    }
+}
+
+/// Components of the enabled binary entries
+pub fn enabled_components(repos: &[Repository]) -> HashSet<String> {
+    repos.iter()
+        .filter(|repo| repo.enabled && !repo.is_source)
+        .flat_map(|repo| repo.components.split_whitespace().map(str::to_string))
+        .collect()
+}
+
+/// The entry a missing archive `component` should be added to: the
+/// enabled binary entry of the distribution's archive carrying "main",
+/// the release suite before its -updates and -security suites. `None` if
+/// some entry already has the component, or none is suitable.
+pub fn component_target<'a>(repos: &'a [Repository], component: &str) -> Option<&'a Repository> {
+    if enabled_components(repos).contains(component) {
+        return None;
+    }
+    repos.iter()
+        .filter(|repo| repo.enabled && !repo.is_source && repo.managed_by.is_none() && !repo.is_flat())
+        .filter(|repo| is_official_uri(&repo.uri) && repo.components.split_whitespace().any(|c| c == "main"))
+        .min_by_key(|repo| repo.distribution.contains('-'))
+}
+
+fn third_party_state_file() -> Option<PathBuf> {
+    Some(state_dir()?.join("disabled-third-party"))
+}
+
+/// Identifies an entry across rewrites of its file, whose line numbers may shift
+pub fn repository_key(repo: &Repository) -> String {
+    format!(
+        "{}\t{}\t{}\t{}",
+        repo.file_path.as_deref().unwrap_or(""),
+        if repo.is_source { "deb-src" } else { "deb" },
+        repo.uri,
+        repo.distribution
+    )
+}
+
+/// Whether third-party repositories are currently switched off by
+/// [`disable_third_party`]
+pub fn third_party_disabled() -> bool {
+    third_party_state_file().map(|file| file.exists()).unwrap_or(false)
+}
+
+/// Disables every enabled third-party repository and remembers which ones,
+/// so [`restore_third_party`] turns back on only those
+pub fn disable_third_party() -> Result<usize> {
+    let state_file = third_party_state_file().context("HOME is not set")?;
+    let repos: Vec<Repository> = get_repositories()?.into_iter()
+        .filter(|repo| repo.enabled && repo.managed_by.is_none() && !is_official_uri(&repo.uri))
+        .collect();
+
+    let keys: Vec<String> = repos.iter().map(repository_key).collect();
+    if let Some(dir) = state_file.parent() {
+        paths::ensure_dir(dir)?;
+    }
+    fs::write(&state_file, keys.join("\n"))?;
+
+    match set_repositories_enabled(&repos, false) {
+        Ok(changed) => Ok(changed),
+        Err(e) => {
+            let _ = fs::remove_file(&state_file);
+            Err(e)
+        }
+    }
+}
+
+/// Re-enables the repositories switched off by [`disable_third_party`]
+pub fn restore_third_party() -> Result<usize> {
+    let state_file = third_party_state_file().context("HOME is not set")?;
+    let keys = fs::read_to_string(&state_file).unwrap_or_default();
+    let keys: Vec<&str> = keys.lines().collect();
+
+    let repos: Vec<Repository> = get_repositories()?.into_iter()
+        .filter(|repo| !repo.enabled && keys.contains(&repository_key(repo).as_str()))
+        .collect();
+    let changed = set_repositories_enabled(&repos, true)?;
+    let _ = fs::remove_file(&state_file);
+    Ok(changed)
+}
+
+/// A repository from extrepo's curated catalog
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtrepoEntry {
+    pub name: String,
+    pub description: String,
+    /// Licensing policy of the repository: main, contrib or non-free
+    pub policy: String,
+    pub uri: Option<String>,
+}
+
+pub fn extrepo_installed() -> bool {
+    ["/usr/bin/extrepo", "/usr/sbin/extrepo"].iter().any(|path| Path::new(path).exists())
+}
+
+/// Parses `extrepo search` output, a "Found <name>:" line followed by the
+/// YAML description of each repository
+pub fn parse_extrepo_search(output: &str) -> Vec<ExtrepoEntry> {
+    let mut entries: Vec<ExtrepoEntry> = Vec::new();
+    let unquote = |value: &str| value.trim().trim_matches(|c| c == '\'' || c == '"').to_string();
+
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("Found ").and_then(|rest| rest.strip_suffix(':')) {
+            entries.push(ExtrepoEntry {
+                name: name.trim().to_string(),
+                description: String::new(),
+                policy: "main".to_string(),
+                uri: None,
+            });
+            continue;
+        }
+        let entry = match entries.last_mut() {
+            Some(entry) => entry,
+            None => continue,
+        };
+        if let Some(description) = line.strip_prefix("description:") {
+            entry.description = unquote(description);
+        } else if let Some(policy) = line.strip_prefix("policy:") {
+            entry.policy = unquote(policy);
+        } else if let Some(uri) = line.trim_start().strip_prefix("URIs:") {
+            if line.starts_with(' ') && entry.uri.is_none() {
+                entry.uri = Some(unquote(uri));
+            }
+        }
+    }
+    entries
+}
+
+/// Policies extrepo accepts, from the `enabled_policies` list of its
+/// config. extrepo only enables `main` repositories unless told otherwise.
+pub fn parse_extrepo_policies(config: &str) -> Vec<String> {
+    let mut policies = Vec::new();
+    let mut in_list = false;
+    for line in config.lines() {
+        if line.starts_with("enabled_policies:") {
+            in_list = true;
+        } else if in_list {
+            match line.trim().strip_prefix("- ") {
+                Some(policy) => policies.push(policy.trim().to_string()),
+                None if line.trim().is_empty() || line.trim_start().starts_with('#') => {}
+                None => break,
+            }
+        }
+    }
+    if policies.is_empty() {
+        policies.push("main".to_string());
+    }
+    policies
+}
+
+pub fn extrepo_policies() -> Vec<String> {
+    parse_extrepo_policies(&fs::read_to_string(sysroot::path("/etc/extrepo/config.yaml")).unwrap_or_default())
+}
+
+/// The curated repositories extrepo offers
+pub fn extrepo_catalog() -> Result<Vec<ExtrepoEntry>> {
+    let output = Command::new("extrepo")
+        .args(["search", "."])
+        .env("LANG", "C")
+        .output()
+        .context("extrepo could not be run")?;
+    if !output.status.success() {
+        return Err(Error::from_status("extrepo search", &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+    let mut entries = parse_extrepo_search(&String::from_utf8_lossy(&output.stdout));
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Adds a catalog repository together with the signing key extrepo ships for it
+pub fn enable_extrepo(name: &str) -> Result<()> {
+    let output = privilege::elevated_command()
+        .args(["extrepo", "enable", name])
+        .output()
+        .context("extrepo could not be run")?;
+    if !output.status.success() {
+        return Err(Error::from_status("extrepo enable", &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
     Ok(())
 }
 
+/// New values for an existing entry, as typed into the edit dialog
+#[derive(Debug, Clone, Default)]
+pub struct RepositoryEdit {
+    pub uri: String,
+    pub distribution: String,
+    pub components: Vec<String>,
+    pub architectures: Vec<String>,
+    pub signed_by: Option<String>,
+}
+
+const URI_SCHEMES: &[&str] = &["http://", "https://", "ftp://", "file:", "cdrom:", "copy:", "mirror+", "tor+"];
+
+const ARCHITECTURES: &[&str] = &[
+    "amd64", "arm64", "armel", "armhf", "i386", "mips64el", "mipsel", "ppc64el", "riscv64", "s390x", "all",
+];
+
+impl RepositoryEdit {
+    /// Catches the typos that would otherwise only show up as apt errors
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(Error::Parse(message));
+
+        if !URI_SCHEMES.iter().any(|scheme| self.uri.starts_with(scheme)) || self.uri.contains(char::is_whitespace) {
+            return invalid(format!("'{}' is not a repository URI. It should start with http://, https:// or file:", self.uri));
+        }
+        if self.distribution.is_empty() || self.distribution.contains(char::is_whitespace) {
+            return invalid("The suite must be a single word such as 'bookworm' or 'stable'.".to_string());
+        }
+        if self.components.is_empty() && !self.distribution.ends_with('/') {
+            return invalid("At least one component such as 'main' is needed.".to_string());
+        }
+        if let Some(component) = self.components.iter()
+            .find(|c| !c.chars().all(|ch| ch.is_ascii_alphanumeric() || "-_./".contains(ch)))
+        {
+            return invalid(format!("'{}' is not a valid component name.", component));
+        }
+        if let Some(arch) = self.architectures.iter().find(|arch| !ARCHITECTURES.contains(&arch.as_str())) {
+            return invalid(format!("'{}' is not a known architecture. Known architectures: {}", arch, ARCHITECTURES.join(", ")));
+        }
+        if let Some(keyring) = &self.signed_by {
+            // signed-by also accepts key fingerprints
+            let fingerprint = keyring.chars().all(|c| c.is_ascii_hexdigit()) && keyring.len() >= 16;
+            if !fingerprint && !Path::new(keyring).is_absolute() {
+                return invalid(format!("Signed-By must be an absolute keyring path or a key fingerprint, not '{}'.", keyring));
+            }
+            if !fingerprint && !Path::new(keyring).exists() {
+                return invalid(format!("The keyring {} does not exist.", keyring));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn state_dir() -> Option<PathBuf> {
+    paths::state_dir()
+}
+
+fn backup_dir() -> Option<PathBuf> {
+    Some(state_dir()?.join("sources-backup"))
+}
+
+/// Copies a sources file to the user's state directory before it is
+/// changed. Backups stay out of sources.list.d, where apt would warn about them.
+fn backup_sources_file(path: &str) -> Result<PathBuf> {
+    let dir = backup_dir().context("HOME is not set")?;
+    paths::ensure_dir(&dir)?;
+    let name = Path::new(path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| "sources".to_string());
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let backup = dir.join(format!("{}.{}", name, stamp));
+    fs::copy(path, &backup).with_context(|| format!("Could not back up {}", path))?;
+    Ok(backup)
+}
+
+/// Rewrites the entry of `old_repo` in place and returns the backup of the
+/// file as it was before
+pub fn edit_repository(old_repo: &Repository, edit: &RepositoryEdit) -> Result<PathBuf> {
+    edit.validate()?;
+    let file_path = old_repo.file_path.as_deref().context("The repository has no source file")?;
+    let backup = backup_sources_file(file_path)?;
+
+    modify_entry(old_repo, "Repository edit operation", |sources, line_num| {
+        match sources.entry_mut(line_num) {
+            Some(entry) => {
+                entry.uri = edit.uri.clone();
+                entry.distribution = edit.distribution.clone();
+                entry.components = edit.components.clone();
+                let archs = edit.architectures.join(",");
+                entry.set_option("arch", Some(archs.as_str()).filter(|a| !a.is_empty()));
+                entry.set_option("signed-by", edit.signed_by.as_deref());
+                true
+            }
+            None => false,
+        }
+    })?;
+    Ok(backup)
+}
+
 pub fn update_repositories() -> Result<()> {
-    let status = Command::new("pkexec")
-        .arg("apt")
+    let output = privilege::elevated_command()
+        .arg("apt-get")
         .arg("update")
-        .status()
+        .output()
         .context("apt update failed to run")?;
-        
-    if !status.success() {
-        bail!("apt update failed");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    repo_status::record_update(&format!("{}\n{}", stdout, stderr));
+
+    if !output.status.success() {
+        return Err(Error::from_status("apt update", &output.status, &stderr));
     }
     
     Ok(())
@@ -0,0 +1,228 @@
+// src/repo_status.rs
+
+use crate::format;
+use crate::paths;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Repositories without a successful refresh for this long are flagged
+pub const STALE_SECONDS: u64 = 7 * 86_400;
+
+/// Outcome of the refreshes of one repository
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoStatus {
+    pub last_attempt: u64,
+    pub last_success: Option<u64>,
+    /// Why the last refresh failed; `None` when it succeeded
+    pub error: Option<String>,
+}
+
+impl RepoStatus {
+    pub fn is_stale(&self, now: u64) -> bool {
+        self.last_success.map(|ts| now.saturating_sub(ts) > STALE_SECONDS).unwrap_or(true)
+    }
+}
+
+fn status_file() -> Option<PathBuf> {
+    paths::state_file("repo_status")
+}
+
+/// Key of a repository, as apt names it in its update output
+pub fn status_key(uri: &str, suite: &str) -> String {
+    format!("{} {}", uri.trim_end_matches('/'), suite)
+}
+
+/// Key from the `uri suite/component ...` part of a Hit/Get/Err line
+fn key_from_words(uri: &str, target: &str) -> String {
+    // Flat repositories are named by their path, e.g. "./" or "binary/"
+    if target.ends_with('/') {
+        return status_key(uri, target);
+    }
+    let suite = match target.split_once('/') {
+        Some((suite, _)) if !suite.is_empty() && suite != "." => suite,
+        _ => target,
+    };
+    status_key(uri, suite)
+}
+
+/// Key from a `.../dists/<suite>/...` URL of a "Failed to fetch" warning
+fn key_from_url(url: &str) -> Option<String> {
+    let (uri, rest) = url.split_once("/dists/")?;
+    let suite = rest.split('/').next()?;
+    Some(status_key(uri, suite))
+}
+
+/// Reads the outcome of every repository from `apt-get update` output.
+/// `None` means the repository was refreshed, `Some` holds the error.
+pub fn parse_update_output(output: &str) -> HashMap<String, Option<String>> {
+    let mut outcomes: HashMap<String, Option<String>> = HashMap::new();
+    let mut failed_key: Option<String> = None;
+
+    let fail = |outcomes: &mut HashMap<String, Option<String>>, key: String, message: String| {
+        let entry = outcomes.entry(key).or_insert(None);
+        if entry.is_none() {
+            *entry = Some(message);
+        }
+    };
+
+    for line in output.lines() {
+        // Indented lines explain the Err: line above them
+        if line.starts_with(' ') {
+            if let Some(key) = failed_key.take() {
+                let message = line.trim().to_string();
+                if !message.is_empty() {
+                    outcomes.insert(key, Some(message));
+                }
+            }
+            continue;
+        }
+        failed_key = None;
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let tag = words.first().map(|word| word.split(':').next().unwrap_or(word)).unwrap_or("");
+        match (tag, words.get(1), words.get(2)) {
+            ("Hit" | "Get", Some(uri), Some(target)) if uri.contains(':') => {
+                outcomes.entry(key_from_words(uri, target)).or_insert(None);
+                continue;
+            }
+            ("Err", Some(uri), Some(target)) if uri.contains(':') => {
+                let key = key_from_words(uri, target);
+                fail(&mut outcomes, key.clone(), "The repository could not be reached".to_string());
+                failed_key = Some(key);
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some(rest) = line.strip_prefix("W: Failed to fetch ").or_else(|| line.strip_prefix("E: Failed to fetch ")) {
+            let (url, reason) = rest.split_once("  ").unwrap_or((rest, ""));
+            if let Some(key) = key_from_url(url.trim()) {
+                fail(&mut outcomes, key, reason.trim().to_string());
+            }
+        } else if let Some(rest) = line.strip_prefix("W: GPG error: ").or_else(|| line.strip_prefix("E: GPG error: ")) {
+            let words: Vec<&str> = rest.split_whitespace().collect();
+            if let (Some(uri), Some(suite)) = (words.first(), words.get(1)) {
+                let reason = rest.split_once(": ").map(|(_, reason)| reason).unwrap_or(rest);
+                // A failed signature check means the new lists were not used
+                outcomes.insert(status_key(uri, suite), Some(format!("GPG error: {}", reason.trim())));
+            }
+        } else if let Some(rest) = line.strip_prefix("E: The repository '") {
+            if let Some((name, reason)) = rest.split_once('\'') {
+                let words: Vec<&str> = name.split_whitespace().collect();
+                if let (Some(uri), Some(suite)) = (words.first(), words.get(1)) {
+                    outcomes.insert(status_key(uri, suite), Some(reason.trim().trim_end_matches('.').to_string()));
+                }
+            }
+        }
+    }
+
+    // An empty reason from a "Failed to fetch" line is no help
+    for outcome in outcomes.values_mut() {
+        if outcome.as_deref() == Some("") {
+            *outcome = Some("The repository could not be reached".to_string());
+        }
+    }
+    outcomes
+}
+
+/// Parses the `key<TAB>attempt<TAB>success<TAB>error` lines of the status file
+pub fn parse_statuses(content: &str) -> HashMap<String, RepoStatus> {
+    let mut statuses = HashMap::new();
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 4 {
+            continue;
+        }
+        let last_attempt = match fields[1].parse() {
+            Ok(ts) => ts,
+            Err(_) => continue,
+        };
+        statuses.insert(fields[0].to_string(), RepoStatus {
+            last_attempt,
+            last_success: fields[2].parse().ok(),
+            error: Some(fields[3].to_string()).filter(|error| !error.is_empty()),
+        });
+    }
+    statuses
+}
+
+fn format_statuses(statuses: &HashMap<String, RepoStatus>) -> String {
+    let mut lines: Vec<String> = statuses.iter()
+        .map(|(key, status)| format!(
+            "{}\t{}\t{}\t{}",
+            key,
+            status.last_attempt,
+            status.last_success.map(|ts| ts.to_string()).unwrap_or_default(),
+            status.error.as_deref().unwrap_or("").replace(['\t', '\n'], " ")
+        ))
+        .collect();
+    lines.sort();
+    lines.join("\n")
+}
+
+/// Applies the outcomes of one update run at `now`. Repositories missing
+/// from the output keep their previous status.
+pub fn merge_statuses(
+    previous: &HashMap<String, RepoStatus>,
+    outcomes: &HashMap<String, Option<String>>,
+    now: u64,
+) -> HashMap<String, RepoStatus> {
+    let mut merged = previous.clone();
+    for (key, error) in outcomes {
+        let last_success = match error {
+            None => Some(now),
+            Some(_) => previous.get(key).and_then(|status| status.last_success),
+        };
+        merged.insert(key.clone(), RepoStatus { last_attempt: now, last_success, error: error.clone() });
+    }
+    merged
+}
+
+/// Records the per-repository result of an `apt-get update` run
+pub fn record_update(output: &str) {
+    let path = match status_file() {
+        Some(path) => path,
+        None => return,
+    };
+    let outcomes = parse_update_output(output);
+    if outcomes.is_empty() {
+        return;
+    }
+
+    let merged = merge_statuses(&load(), &outcomes, format::now());
+    if let Some(parent) = path.parent() {
+        let _ = paths::ensure_dir(parent);
+    }
+    if let Err(e) = fs::write(&path, format_statuses(&merged)) {
+        eprintln!("Could not save the repository status: {}", e);
+    }
+}
+
+pub fn load() -> HashMap<String, RepoStatus> {
+    status_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| parse_statuses(&content))
+        .unwrap_or_default()
+}
+
+/// Text and tooltip of the status column for `status`
+pub fn describe(status: Option<&RepoStatus>) -> (String, String) {
+    let now = format::now();
+    let status = match status {
+        Some(status) => status,
+        None => return ("—".to_string(), "Not refreshed since MeaUpdater started tracking".to_string()),
+    };
+    let last_success = status.last_success
+        .map(|ts| format!("Last successful refresh: {}", format::relative_time(ts, now)))
+        .unwrap_or_else(|| "Never refreshed successfully".to_string());
+
+    match &status.error {
+        Some(error) => (
+            format!("❌ {}", format::relative_time(status.last_attempt, now)),
+            format!("The last refresh failed: {}\n{}", error, last_success),
+        ),
+        None if status.is_stale(now) => (format!("⚠️ {}", format::relative_time(status.last_attempt, now)), last_success),
+        None => (format!("✅ {}", format::relative_time(status.last_attempt, now)), last_success),
+    }
+}
@@ -1,22 +1,27 @@
 // src/repo_window.rs
 
-use crate::repo_manager::{self, Repository};
+use crate::error;
+use crate::format;
+use crate::repo_manager::{self, ReleaseInfo, Repository, RepositoryEdit};
+use crate::repo_status;
 use gtk::prelude::*;
 use gtk::{
-    ApplicationWindow, Box as GtkBox, Button, ListBox, ListBoxRow, ScrolledWindow,
+    gio, Box as GtkBox, Button, ListBox, ListBoxRow, ScrolledWindow,
     Orientation, HeaderBar, Label, Entry, Dialog, MessageDialog,
     ButtonsType, MessageType, CheckButton, Separator, Grid,
-    ResponseType,
+    ResponseType, Switch, SearchEntry,
 };
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 pub struct RepoWindow {
     window: Dialog,
+    repositories: Rc<RefCell<Vec<Repository>>>,
+    listbox: ListBox,
 }
 
 impl RepoWindow {
-    pub fn new(parent: &ApplicationWindow) -> Self {
+    pub fn new(parent: &impl IsA<gtk::Window>) -> Self {
         let window = Dialog::builder()
             .transient_for(parent)
             .modal(true)
@@ -58,6 +63,34 @@ impl RepoWindow {
 
         main_vbox.append(&button_panel);
 
+        // Bulk actions
+        let bulk_panel = GtkBox::new(Orientation::Horizontal, 12);
+        bulk_panel.set_margin_bottom(12);
+        bulk_panel.set_margin_start(16);
+        bulk_panel.set_margin_end(16);
+
+        let enable_selected_btn = Button::with_label("✅ Enable Selected");
+        let disable_selected_btn = Button::with_label("⛔ Disable Selected");
+        let selection_hint = Label::new(Some("Ctrl+click or Shift+click rows to select several"));
+        selection_hint.add_css_class("size-info");
+        selection_hint.set_hexpand(true);
+        selection_hint.set_halign(gtk::Align::Start);
+
+        let third_party_label = Label::new(Some("Temporarily disable all third-party repositories"));
+        third_party_label.set_tooltip_text(Some(
+            "Turns off every repository that is not a Debian or Ubuntu archive, for example while debugging dependency problems or before a release upgrade. Switching it off again re-enables exactly those repositories."
+        ));
+        let third_party_switch = Switch::new();
+        third_party_switch.set_active(repo_manager::third_party_disabled());
+        third_party_switch.set_valign(gtk::Align::Center);
+
+        bulk_panel.append(&enable_selected_btn);
+        bulk_panel.append(&disable_selected_btn);
+        bulk_panel.append(&selection_hint);
+        bulk_panel.append(&third_party_label);
+        bulk_panel.append(&third_party_switch);
+        main_vbox.append(&bulk_panel);
+
         // Separator
         let separator = Separator::new(Orientation::Horizontal);
         main_vbox.append(&separator);
@@ -94,6 +127,11 @@ impl RepoWindow {
         comp_header.set_halign(gtk::Align::Center);
         comp_header.set_markup("<b>Components</b>");
 
+        let updated_header = Label::new(Some("Last Update"));
+        updated_header.set_width_chars(16);
+        updated_header.set_halign(gtk::Align::Center);
+        updated_header.set_markup("<b>Last Update</b>");
+
         let actions_header = Label::new(Some("Actions"));
         actions_header.set_width_chars(15);
         actions_header.set_halign(gtk::Align::Center);
@@ -104,6 +142,7 @@ impl RepoWindow {
         header_row.append(&uri_header);
         header_row.append(&dist_header);
         header_row.append(&comp_header);
+        header_row.append(&updated_header);
         header_row.append(&actions_header);
 
         main_vbox.append(&header_row);
@@ -123,10 +162,30 @@ impl RepoWindow {
             .build();
 
         let listbox = ListBox::new();
-        listbox.set_selection_mode(gtk::SelectionMode::None);
+        listbox.set_selection_mode(gtk::SelectionMode::Multiple);
         scrolled_window.set_child(Some(&listbox));
         main_vbox.append(&scrolled_window);
 
+        // Checkbox changes wait here until they are applied together
+        let pending_bar = GtkBox::new(Orientation::Horizontal, 12);
+        pending_bar.set_margin_bottom(16);
+        pending_bar.set_margin_start(16);
+        pending_bar.set_margin_end(16);
+        pending_bar.set_visible(false);
+
+        let pending_label = Label::new(None);
+        pending_label.set_hexpand(true);
+        pending_label.set_halign(gtk::Align::Start);
+
+        let revert_btn = Button::with_label("↩️ Revert");
+        let apply_btn = Button::with_label("✅ Apply Changes");
+        apply_btn.add_css_class("suggested-action");
+
+        pending_bar.append(&pending_label);
+        pending_bar.append(&revert_btn);
+        pending_bar.append(&apply_btn);
+        main_vbox.append(&pending_bar);
+
         let content_area = window.content_area();
         content_area.append(&main_vbox);
 
@@ -147,20 +206,127 @@ impl RepoWindow {
         });
 
         let window_clone2 = window.clone();
+        let repos_update = repositories.clone();
+        let listbox_update = listbox.clone();
         update_btn.connect_clicked(move |_| {
             Self::update_repositories(&window_clone2);
+            Self::refresh_repositories(&repos_update, &listbox_update);
+        });
+
+        for (button, enabled) in [(&enable_selected_btn, true), (&disable_selected_btn, false)] {
+            let window_bulk = window.clone();
+            let listbox_bulk = listbox.clone();
+            button.connect_clicked(move |_| {
+                Self::set_selected_enabled(&window_bulk, &listbox_bulk, enabled);
+            });
+        }
+
+        // The rows report checkbox changes through this action
+        let actions = gio::SimpleActionGroup::new();
+        let pending_changed = gio::SimpleAction::new("pending_changed", None);
+        let repos_pending = repositories.clone();
+        let listbox_pending = listbox.clone();
+        pending_changed.connect_activate(move |_, _| {
+            let changes = repo_manager::pending_changes(&repos_pending.borrow(), &checkbox_states(&listbox_pending));
+            pending_bar.set_visible(!changes.is_empty());
+            pending_label.set_text(&format!(
+                "✏️ {} not applied yet",
+                format::plural(changes.len() as u64, "change", "changes")
+            ));
+        });
+        actions.add_action(&pending_changed);
+        window.insert_action_group("repos", Some(&actions));
+
+        let repos_revert = repositories.clone();
+        let listbox_revert = listbox.clone();
+        revert_btn.connect_clicked(move |_| {
+            Self::refresh_repositories(&repos_revert, &listbox_revert);
+        });
+
+        let window_apply = window.clone();
+        let repos_apply = repositories.clone();
+        let listbox_apply = listbox.clone();
+        apply_btn.connect_clicked(move |_| {
+            Self::review_pending_changes(&window_apply, &repos_apply, &listbox_apply);
+        });
+
+        // Set while the switch is put back after a failure
+        let reverting = Rc::new(Cell::new(false));
+        let window_switch = window.clone();
+        let repos_switch = repositories.clone();
+        let listbox_switch = listbox.clone();
+        third_party_switch.connect_state_set(move |switch, disable| {
+            if reverting.get() {
+                return glib::Propagation::Proceed;
+            }
+            let result = if disable {
+                repo_manager::disable_third_party()
+            } else {
+                repo_manager::restore_third_party()
+            };
+            Self::refresh_repositories(&repos_switch, &listbox_switch);
+            match result {
+                Ok(count) => {
+                    println!("🔀 {} third-party repositories {}", count, if disable { "disabled" } else { "re-enabled" });
+                    glib::Propagation::Proceed
+                }
+                Err(e) => {
+                    let heading = if disable {
+                        "❌ Third-party repositories could not be disabled"
+                    } else {
+                        "❌ Third-party repositories could not be re-enabled"
+                    };
+                    error::show_error_dialog(&window_switch, heading, &e, None);
+                    reverting.set(true);
+                    switch.set_active(!disable);
+                    reverting.set(false);
+                    glib::Propagation::Stop
+                }
+            }
         });
 
         // First load
         Self::refresh_repositories(&repositories, &listbox);
 
-        Self { window }
+        Self { window, repositories, listbox }
     }
 
     pub fn show(&self) {
         self.window.show();
     }
 
+    /// Shows the window with the entry that should carry `components`
+    /// selected and opened for editing, the components already filled in
+    pub fn show_adding_components(&self, components: &[String]) {
+        self.show();
+        let repos = self.repositories.borrow().clone();
+        let target = components.iter().find_map(|component| repo_manager::component_target(&repos, component));
+        let index = match target.and_then(|target| repos.iter().position(|repo| std::ptr::eq(repo, target))) {
+            Some(index) => index,
+            None => {
+                let dialog = MessageDialog::builder()
+                    .transient_for(&self.window)
+                    .modal(true)
+                    .message_type(MessageType::Warning)
+                    .buttons(ButtonsType::Ok)
+                    .text(&format!("⚠️ No entry to add {} to", components.join(" and ")))
+                    .secondary_text("None of the enabled entries of your distribution's archive carries the main component. Add the archive with the components you need.")
+                    .build();
+                dialog.connect_response(|dlg, _| dlg.close());
+                dialog.show();
+                return;
+            }
+        };
+
+        if let Some(row) = self.listbox.row_at_index(index as i32) {
+            self.listbox.unselect_all();
+            self.listbox.select_row(Some(&row));
+            row.grab_focus();
+        }
+        let parent = self.window.clone().upcast::<gtk::Window>();
+        Self::show_edit_dialog(&parent, &repos[index], &self.repositories, &self.listbox, components);
+    }
+
     fn refresh_repositories(repositories: &Rc<RefCell<Vec<Repository>>>, listbox: &ListBox) {
         // Clear list
         while let Some(child) = listbox.first_child() {
@@ -182,6 +348,8 @@ impl RepoWindow {
                 listbox.append(&error_row);
             }
         }
+        // Rebuilt rows match the files, so nothing is pending any more
+        WidgetExt::activate_action(listbox, "repos.pending_changed", None).ok();
     }
 
     fn populate_repository_list(listbox: &ListBox, repositories: Vec<Repository>, repositories_ref: &Rc<RefCell<Vec<Repository>>>) {
@@ -196,6 +364,7 @@ impl RepoWindow {
             return;
         }
 
+        let statuses = repo_status::load();
         for (index, repo) in repositories.iter().enumerate() {
             let row = ListBoxRow::new();
             row.add_css_class("package-row");
@@ -212,25 +381,9 @@ impl RepoWindow {
             status_check.set_width_request(60);
             status_check.set_halign(gtk::Align::Center);
 
-            // Checkbox event handler - Change the repo status
-            let repo_clone = repo.clone();
-            let repos_ref_clone = repositories_ref.clone();
-            let listbox_clone = listbox.clone();
-            status_check.connect_toggled(move |check| {
-                let is_active = check.is_active();
-                if is_active != repo_clone.enabled {
-                    match repo_manager::toggle_repository(&repo_clone) {
-                        Ok(_) => {
-                            // Refresh list
-                            Self::refresh_repositories(&repos_ref_clone, &listbox_clone);
-                        }
-                        Err(e) => {
-                            eprintln!("The repository status could not be changed: {}", e);
-                            // Revert checkbox to its previous state
-                            check.set_active(repo_clone.enabled);
-                        }
-                    }
-                }
+            // Changes are only written when applied
+            status_check.connect_toggled(|check| {
+                WidgetExt::activate_action(check, "repos.pending_changed", None).ok();
             });
 
             // Repository name
@@ -241,6 +394,11 @@ impl RepoWindow {
             if repo.is_source {
                 name_label.set_markup(&format!("<i>{} (source)</i>", repo.name));
             }
+            if repo.is_local() {
+                name_label.set_tooltip_text(Some("Local repository on this computer"));
+            } else if !repo_manager::is_official_uri(&repo.uri) {
+                name_label.set_tooltip_text(Some("Third-party repository"));
+            }
 
             // URI
             let uri_label = Label::new(Some(&repo.uri));
@@ -250,18 +408,31 @@ impl RepoWindow {
             uri_label.add_css_class("version-info");
 
             // Distro
-            let dist_label = Label::new(Some(&repo.distribution));
+            let dist_label = Label::new(Some(&repo.suite_label()));
+            if repo.is_flat() {
+                dist_label.set_tooltip_text(Some("Flat repository: its package index lies directly in the given directory, without suites or components"));
+            }
             dist_label.set_width_chars(15);
             dist_label.set_halign(gtk::Align::Center);
             dist_label.add_css_class("version-info");
 
             // Components
-            let comp_label = Label::new(Some(&repo.components));
+            let comp_label = Label::new(Some(if repo.components.is_empty() { "—" } else { &repo.components }));
             comp_label.set_width_chars(20);
             comp_label.set_halign(gtk::Align::Center);
             comp_label.set_ellipsize(pango::EllipsizeMode::End);
             comp_label.add_css_class("size-info");
 
+            // Result of the last apt update for this repository
+            let (updated_text, updated_tooltip) = repo_status::describe(
+                statuses.get(&repo_status::status_key(&repo.uri, &repo.distribution))
+            );
+            let updated_label = Label::new(Some(&updated_text));
+            updated_label.set_width_chars(16);
+            updated_label.set_halign(gtk::Align::Center);
+            updated_label.set_tooltip_text(Some(&updated_tooltip));
+            updated_label.add_css_class("size-info");
+
             // Action buttons
             let actions_box = GtkBox::new(Orientation::Horizontal, 4);
             actions_box.set_width_request(120);
@@ -282,7 +453,7 @@ impl RepoWindow {
             let listbox_edit_clone = listbox.clone();
             edit_btn.connect_clicked(move |btn| {
                 if let Some(window) = btn.root().and_downcast::<gtk::Window>() {
-                    Self::show_edit_dialog(&window, &repo_edit_clone, &repos_ref_edit_clone, &listbox_edit_clone);
+                    Self::show_edit_dialog(&window, &repo_edit_clone, &repos_ref_edit_clone, &listbox_edit_clone, &[]);
                 }
             });
 
@@ -299,11 +470,24 @@ impl RepoWindow {
             actions_box.append(&edit_btn);
             actions_box.append(&delete_btn);
 
+            // Files kept up to date by another tool are shown read-only
+            if let Some(manager) = &repo.managed_by {
+                let reason = format!("🔒 Managed by {}. Change it with that tool; edits made here would be overwritten or break it.", manager);
+                for widget in [status_check.upcast_ref::<gtk::Widget>(), edit_btn.upcast_ref(), delete_btn.upcast_ref()] {
+                    widget.set_sensitive(false);
+                }
+                actions_box.set_tooltip_text(Some(&reason));
+                status_check.set_tooltip_text(Some(&reason));
+                name_label.set_label(&format!("🔒 {}", name_label.label()));
+                name_label.set_tooltip_text(Some(&reason));
+            }
+
             hbox.append(&status_check);
             hbox.append(&name_label);
             hbox.append(&uri_label);
             hbox.append(&dist_label);
             hbox.append(&comp_label);
+            hbox.append(&updated_label);
             hbox.append(&actions_box);
 
             row.set_child(Some(&hbox));
@@ -316,8 +500,8 @@ impl RepoWindow {
             .transient_for(parent)
             .modal(true)
             .title("Add New Repository")
-            .default_width(500)
-            .default_height(300)
+            .default_width(600)
+            .default_height(420)
             .build();
 
         dialog.add_button("Cancel", ResponseType::Cancel);
@@ -338,51 +522,150 @@ impl RepoWindow {
         let uri_entry = Entry::new();
         uri_entry.set_placeholder_text(Some("http://deb.debian.org/debian"));
         uri_entry.set_hexpand(true);
+        let detect_btn = Button::with_label("🔍 Detect");
 
-        // Distro
-        let dist_label = Label::new(Some("Distro:"));
+        // Suite
+        let dist_label = Label::new(Some("Suite:"));
         dist_label.set_halign(gtk::Align::Start);
         let dist_entry = Entry::new();
-        dist_entry.set_placeholder_text(Some("bullseye"));
+        dist_entry.set_placeholder_text(Some("Detected from the Release file"));
 
-        // Components
+        let status_label = Label::new(Some("Enter the URI and press Detect to read the suite and its components."));
+        status_label.set_halign(gtk::Align::Start);
+        status_label.set_wrap(true);
+        status_label.add_css_class("size-info");
+
+        // Components and architectures are filled in by the detection
         let comp_label = Label::new(Some("Components:"));
         comp_label.set_halign(gtk::Align::Start);
-        let comp_entry = Entry::new();
-        comp_entry.set_placeholder_text(Some("main contrib non-free"));
+        comp_label.set_valign(gtk::Align::Start);
+        let comp_box = GtkBox::new(Orientation::Horizontal, 8);
+
+        let arch_label = Label::new(Some("Only for:"));
+        arch_label.set_halign(gtk::Align::Start);
+        arch_label.set_valign(gtk::Align::Start);
+        arch_label.set_tooltip_text(Some("Leave all unchecked to use every architecture apt is configured for"));
+        let arch_box = GtkBox::new(Orientation::Horizontal, 8);
+
+        // Signing key, installed to its own keyring
+        let key_label = Label::new(Some("Signing Key URL:"));
+        key_label.set_halign(gtk::Align::Start);
+        let key_entry = Entry::new();
+        key_entry.set_placeholder_text(Some("https://example.com/key.gpg (optional)"));
+        key_entry.set_tooltip_text(Some("The key is stored in /usr/share/keyrings and only trusted for this repository"));
 
         grid.attach(&uri_label, 0, 0, 1, 1);
         grid.attach(&uri_entry, 1, 0, 1, 1);
+        grid.attach(&detect_btn, 2, 0, 1, 1);
         grid.attach(&dist_label, 0, 1, 1, 1);
-        grid.attach(&dist_entry, 1, 1, 1, 1);
-        grid.attach(&comp_label, 0, 2, 1, 1);
-        grid.attach(&comp_entry, 1, 2, 1, 1);
+        grid.attach(&dist_entry, 1, 1, 2, 1);
+        grid.attach(&status_label, 0, 2, 3, 1);
+        grid.attach(&comp_label, 0, 3, 1, 1);
+        grid.attach(&comp_box, 1, 3, 2, 1);
+        grid.attach(&arch_label, 0, 4, 1, 1);
+        grid.attach(&arch_box, 1, 4, 2, 1);
+        grid.attach(&key_label, 0, 5, 1, 1);
+        grid.attach(&key_entry, 1, 5, 2, 1);
+
+        // Curated alternative to typing a URL
+        let extrepo_btn = Button::with_label("📚 Choose from the extrepo catalog instead");
+        extrepo_btn.add_css_class("flat");
+        if repo_manager::extrepo_installed() {
+            extrepo_btn.set_tooltip_text(Some("extrepo only offers repositories whose keys and policies Debian has reviewed"));
+        } else {
+            extrepo_btn.set_sensitive(false);
+            extrepo_btn.set_tooltip_text(Some("Install the extrepo package to add curated repositories:\n    sudo apt install extrepo"));
+        }
+        grid.attach(&extrepo_btn, 0, 6, 3, 1);
 
         content_area.append(&grid);
 
+        let dialog_extrepo = dialog.clone();
+        let parent_extrepo = parent.clone();
+        let repos_extrepo = repositories.clone();
+        let listbox_extrepo = listbox.clone();
+        extrepo_btn.connect_clicked(move |_| {
+            dialog_extrepo.close();
+            Self::show_extrepo_dialog(&parent_extrepo, &repos_extrepo, &listbox_extrepo);
+        });
+
+        let pickers = Rc::new(ReleasePickers {
+            comp_box,
+            arch_box,
+            components: RefCell::new(Vec::new()),
+            architectures: RefCell::new(Vec::new()),
+            manual_components: RefCell::new(None),
+        });
+
+        let uri_detect = uri_entry.clone();
+        let dist_detect = dist_entry.clone();
+        let pickers_detect = pickers.clone();
+        detect_btn.connect_clicked(move |btn| {
+            let uri = uri_detect.text().trim().to_string();
+            if uri.is_empty() {
+                status_label.set_text("⚠️ Enter the repository URI first.");
+                return;
+            }
+            let typed_suite = Some(dist_detect.text().trim().to_string()).filter(|suite| !suite.is_empty());
+
+            btn.set_sensitive(false);
+            status_label.set_text("⏳ Reading the Release file...");
+            let btn = btn.clone();
+            let status_label = status_label.clone();
+            let dist_entry = dist_detect.clone();
+            let pickers = pickers_detect.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || repo_manager::detect_release(&uri, typed_suite.as_deref())).await;
+                btn.set_sensitive(true);
+                match result {
+                    Ok(Ok((suite, info))) => {
+                        dist_entry.set_text(&suite);
+                        status_label.set_text(&format!(
+                            "✅ Found {}{}",
+                            info.codename.as_deref().or(info.suite.as_deref()).unwrap_or(&suite),
+                            if info.components.is_empty() { " (flat repository)" } else { "" }
+                        ));
+                        pickers.populate(&info);
+                    }
+                    Ok(Err(e)) => {
+                        status_label.set_text(&format!("⚠️ No Release file found ({}). Type the components by hand.", e));
+                        pickers.manual();
+                    }
+                    Err(_) => status_label.set_text("⚠️ The detection stopped unexpectedly."),
+                }
+            });
+        });
+
         let repos_clone = repositories.clone();
         let listbox_clone = listbox.clone();
         let parent_clone = parent.clone();
         dialog.connect_response(move |dialog, response| {
             if response == ResponseType::Accept {
-                let uri = uri_entry.text().to_string();
-                let dist = dist_entry.text().to_string();
-                let comp = comp_entry.text().to_string();
-
-                if uri.is_empty() || dist.is_empty() || comp.is_empty() {
+                let edit = RepositoryEdit {
+                    uri: uri_entry.text().trim().to_string(),
+                    distribution: dist_entry.text().trim().to_string(),
+                    components: pickers.selected_components(),
+                    architectures: pickers.selected_architectures(),
+                    signed_by: None,
+                };
+
+                if edit.uri.is_empty() || edit.distribution.is_empty() {
                     let error_dialog = MessageDialog::builder()
                         .transient_for(&parent_clone)
                         .modal(true)
                         .message_type(MessageType::Error)
                         .buttons(ButtonsType::Ok)
                         .text("❌ Please fill in all fields!")
+                        .secondary_text("Press Detect to fill in the suite and pick the components.")
                         .build();
                     error_dialog.connect_response(|dlg, _| dlg.close());
                     error_dialog.show();
                     return;
                 }
 
-                match repo_manager::add_repository(&uri, &dist, &comp) {
+                let key_url = key_entry.text().trim().to_string();
+                let key_url = Some(key_url.as_str()).filter(|url| !url.is_empty());
+                match repo_manager::add_repository(&edit, key_url) {
                     Ok(_) => {
                         Self::refresh_repositories(&repos_clone, &listbox_clone);
                         dialog.close();
@@ -398,15 +681,7 @@ impl RepoWindow {
                         success_dialog.show();
                     }
                     Err(e) => {
-                        let error_dialog = MessageDialog::builder()
-                            .transient_for(&parent_clone)
-                            .modal(true)
-                            .message_type(MessageType::Error)
-                            .buttons(ButtonsType::Ok)
-                            .text(&format!("❌ The repository could not be added:\n{}", e))
-                            .build();
-                        error_dialog.connect_response(|dlg, _| dlg.close());
-                        error_dialog.show();
+                        error::show_error_dialog(&parent_clone, "❌ The repository could not be added", &e, None);
                     }
                 }
             } else {
@@ -417,13 +692,159 @@ impl RepoWindow {
         dialog.show();
     }
 
-    fn show_edit_dialog(parent: &gtk::Window, repo: &Repository, repositories: &Rc<RefCell<Vec<Repository>>>, listbox: &ListBox) {
+    /// Lets the user enable a repository from extrepo's curated catalog
+    fn show_extrepo_dialog(parent: &Dialog, repositories: &Rc<RefCell<Vec<Repository>>>, listbox: &ListBox) {
+        let dialog = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Curated Repositories")
+            .default_width(640)
+            .default_height(520)
+            .build();
+        dialog.add_button("Cancel", ResponseType::Cancel);
+        dialog.add_button("Enable", ResponseType::Accept);
+        dialog.set_response_sensitive(ResponseType::Accept, false);
+
+        let vbox = GtkBox::new(Orientation::Vertical, 8);
+        vbox.set_margin_top(16);
+        vbox.set_margin_bottom(16);
+        vbox.set_margin_start(16);
+        vbox.set_margin_end(16);
+
+        let search_entry = SearchEntry::new();
+        search_entry.set_placeholder_text(Some("Search by name or description"));
+        let status_label = Label::new(Some("⏳ Loading the extrepo catalog..."));
+        status_label.set_halign(gtk::Align::Start);
+        status_label.add_css_class("size-info");
+
+        let catalog_list = ListBox::new();
+        catalog_list.set_selection_mode(gtk::SelectionMode::Single);
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .child(&catalog_list)
+            .build();
+
+        vbox.append(&search_entry);
+        vbox.append(&status_label);
+        vbox.append(&scrolled_window);
+        dialog.content_area().append(&vbox);
+
+        let query = Rc::new(RefCell::new(String::new()));
+        let query_filter = query.clone();
+        catalog_list.set_filter_func(move |row| {
+            let query = query_filter.borrow();
+            query.is_empty() || row.tooltip_text().map(|text| text.to_lowercase().contains(query.as_str())).unwrap_or(false)
+        });
+        let catalog_search = catalog_list.clone();
+        search_entry.connect_search_changed(move |entry| {
+            *query.borrow_mut() = entry.text().to_lowercase();
+            catalog_search.invalidate_filter();
+        });
+
+        let dialog_select = dialog.clone();
+        catalog_list.connect_row_selected(move |_, row| {
+            dialog_select.set_response_sensitive(ResponseType::Accept, row.map(|row| row.is_sensitive()).unwrap_or(false));
+        });
+
+        let catalog_fill = catalog_list.clone();
+        glib::spawn_future_local(async move {
+            let policies = repo_manager::extrepo_policies();
+            match gio::spawn_blocking(repo_manager::extrepo_catalog).await {
+                Ok(Ok(entries)) => {
+                    status_label.set_text(&format!("{} repositories. Only {} ones can be enabled with the current extrepo policy.", entries.len(), policies.join(", ")));
+                    for entry in entries {
+                        catalog_fill.append(&Self::extrepo_row(&entry, policies.contains(&entry.policy)));
+                    }
+                }
+                Ok(Err(e)) => status_label.set_text(&format!("❌ The catalog could not be loaded: {}", e)),
+                Err(_) => status_label.set_text("❌ Loading the catalog stopped unexpectedly."),
+            }
+        });
+
+        let parent_clone = parent.clone();
+        let repos_clone = repositories.clone();
+        let listbox_clone = listbox.clone();
+        dialog.connect_response(move |dialog, response| {
+            if response != ResponseType::Accept {
+                dialog.close();
+                return;
+            }
+            let name = match catalog_list.selected_row() {
+                Some(row) => row.widget_name().to_string(),
+                None => return,
+            };
+            match repo_manager::enable_extrepo(&name) {
+                Ok(()) => {
+                    dialog.close();
+                    Self::refresh_repositories(&repos_clone, &listbox_clone);
+                    let success_dialog = MessageDialog::builder()
+                        .transient_for(&parent_clone)
+                        .modal(true)
+                        .message_type(MessageType::Info)
+                        .buttons(ButtonsType::Ok)
+                        .text(&format!("✅ {} enabled with extrepo!", name))
+                        .secondary_text("Run Update Repositories to fetch its package lists.")
+                        .build();
+                    success_dialog.connect_response(|dlg, _| dlg.close());
+                    success_dialog.show();
+                }
+                Err(e) => error::show_error_dialog(&parent_clone, &format!("❌ {} could not be enabled", name), &e, None),
+            }
+        });
+
+        dialog.show();
+    }
+
+    fn extrepo_row(entry: &repo_manager::ExtrepoEntry, allowed: bool) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        // The name identifies the selection, the tooltip doubles as search text
+        row.set_widget_name(&entry.name);
+        row.set_tooltip_text(Some(&format!("{}\n{}", entry.name, entry.description)));
+
+        let vbox = GtkBox::new(Orientation::Vertical, 2);
+        vbox.set_margin_top(6);
+        vbox.set_margin_bottom(6);
+        vbox.set_margin_start(8);
+        vbox.set_margin_end(8);
+
+        let name_label = Label::new(None);
+        name_label.set_markup(&format!(
+            "<b>{}</b>  <small>({})</small>",
+            glib::markup_escape_text(&entry.name),
+            glib::markup_escape_text(&entry.policy)
+        ));
+        name_label.set_halign(gtk::Align::Start);
+        let description_label = Label::new(Some(&entry.description));
+        description_label.set_halign(gtk::Align::Start);
+        description_label.set_wrap(true);
+        vbox.append(&name_label);
+        vbox.append(&description_label);
+        if let Some(uri) = &entry.uri {
+            let uri_label = Label::new(Some(uri));
+            uri_label.set_halign(gtk::Align::Start);
+            uri_label.add_css_class("version-info");
+            vbox.append(&uri_label);
+        }
+        if !allowed {
+            row.set_sensitive(false);
+            row.set_tooltip_text(Some(&format!(
+                "{}\n{}\n\nThe {} policy is not enabled in /etc/extrepo/config.yaml",
+                entry.name, entry.description, entry.policy
+            )));
+        }
+
+        row.set_child(Some(&vbox));
+        row
+    }
+
+    /// `add_components` are appended to the components, for the user to save
+    fn show_edit_dialog(parent: &gtk::Window, repo: &Repository, repositories: &Rc<RefCell<Vec<Repository>>>, listbox: &ListBox, add_components: &[String]) {
         let dialog = Dialog::builder()
             .transient_for(parent)
             .modal(true)
             .title(&format!("Edit Repository: {}", repo.name))
-            .default_width(500)
-            .default_height(300)
+            .default_width(600)
+            .default_height(380)
             .build();
 
         dialog.add_button("Cancel", ResponseType::Cancel);
@@ -455,7 +876,35 @@ impl RepoWindow {
         let comp_label = Label::new(Some("Components:"));
         comp_label.set_halign(gtk::Align::Start);
         let comp_entry = Entry::new();
-        comp_entry.set_text(&repo.components);
+        let mut components: Vec<&str> = repo.components.split_whitespace().collect();
+        for component in add_components {
+            if !components.contains(&component.as_str()) {
+                components.push(component);
+            }
+        }
+        comp_entry.set_text(&components.join(" "));
+
+        // Architectures
+        let arch_label = Label::new(Some("Architectures:"));
+        arch_label.set_halign(gtk::Align::Start);
+        let arch_entry = Entry::new();
+        arch_entry.set_text(&repo.architectures.join(", "));
+        arch_entry.set_placeholder_text(Some("amd64, i386 (empty for all)"));
+
+        // Signed-By
+        let key_label = Label::new(Some("Signed-By:"));
+        key_label.set_halign(gtk::Align::Start);
+        let key_entry = Entry::new();
+        key_entry.set_text(repo.signed_by.as_deref().unwrap_or(""));
+        key_entry.set_placeholder_text(Some("/usr/share/keyrings/example-archive-keyring.gpg"));
+
+        let file_label = Label::new(Some(&format!(
+            "📄 {} (line {})",
+            repo.file_path.as_deref().unwrap_or("?"),
+            repo.line_number.map_or(0, |n| n + 1)
+        )));
+        file_label.set_halign(gtk::Align::Start);
+        file_label.add_css_class("size-info");
 
         grid.attach(&uri_label, 0, 0, 1, 1);
         grid.attach(&uri_entry, 1, 0, 1, 1);
@@ -463,6 +912,20 @@ impl RepoWindow {
         grid.attach(&dist_entry, 1, 1, 1, 1);
         grid.attach(&comp_label, 0, 2, 1, 1);
         grid.attach(&comp_entry, 1, 2, 1, 1);
+        grid.attach(&arch_label, 0, 3, 1, 1);
+        grid.attach(&arch_entry, 1, 3, 1, 1);
+        grid.attach(&key_label, 0, 4, 1, 1);
+        grid.attach(&key_entry, 1, 4, 1, 1);
+        grid.attach(&file_label, 0, 5, 2, 1);
+        if !add_components.is_empty() {
+            let hint_label = Label::new(Some(&format!(
+                "➕ {} added to the components. Save, then update the package lists.",
+                add_components.join(" and ")
+            )));
+            hint_label.set_halign(gtk::Align::Start);
+            hint_label.set_wrap(true);
+            grid.attach(&hint_label, 0, 6, 2, 1);
+        }
 
         content_area.append(&grid);
 
@@ -472,11 +935,19 @@ impl RepoWindow {
         let parent_clone = parent.clone();
         dialog.connect_response(move |dialog, response| {
             if response == ResponseType::Accept {
-                let uri = uri_entry.text().to_string();
-                let dist = dist_entry.text().to_string();
-                let comp = comp_entry.text().to_string();
-
-                if uri.is_empty() || dist.is_empty() || comp.is_empty() {
+                let edit = RepositoryEdit {
+                    uri: uri_entry.text().trim().to_string(),
+                    distribution: dist_entry.text().trim().to_string(),
+                    components: comp_entry.text().split_whitespace().map(str::to_string).collect(),
+                    architectures: arch_entry.text()
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|arch| !arch.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                    signed_by: Some(key_entry.text().trim().to_string()).filter(|key| !key.is_empty()),
+                };
+
+                if edit.uri.is_empty() || edit.distribution.is_empty() {
                     let error_dialog = MessageDialog::builder()
                         .transient_for(&parent_clone)
                         .modal(true)
@@ -489,8 +960,8 @@ impl RepoWindow {
                     return;
                 }
 
-                match repo_manager::edit_repository(&repo_clone, &uri, &dist, &comp) {
-                    Ok(_) => {
+                match repo_manager::edit_repository(&repo_clone, &edit) {
+                    Ok(backup) => {
                         Self::refresh_repositories(&repos_clone, &listbox_clone);
                         dialog.close();
                         
@@ -500,20 +971,13 @@ impl RepoWindow {
                             .message_type(MessageType::Info)
                             .buttons(ButtonsType::Ok)
                             .text("✅ Repository successfully edited!")
+                            .secondary_text(&format!("A copy of the previous file was saved to\n{}", backup.display()))
                             .build();
                         success_dialog.connect_response(|dlg, _| dlg.close());
                         success_dialog.show();
                     }
                     Err(e) => {
-                        let error_dialog = MessageDialog::builder()
-                            .transient_for(&parent_clone)
-                            .modal(true)
-                            .message_type(MessageType::Error)
-                            .buttons(ButtonsType::Ok)
-                            .text(&format!("❌ Repository could not be edited:\n{}", e))
-                            .build();
-                        error_dialog.connect_response(|dlg, _| dlg.close());
-                        error_dialog.show();
+                        error::show_error_dialog(&parent_clone, "❌ Repository could not be edited", &e, None);
                     }
                 }
             } else {
@@ -555,15 +1019,7 @@ impl RepoWindow {
                         success_dialog.show();
                     }
                     Err(e) => {
-                        let error_dialog = MessageDialog::builder()
-                            .transient_for(&parent_clone)
-                            .modal(true)
-                            .message_type(MessageType::Error)
-                            .buttons(ButtonsType::Ok)
-                            .text(&format!("❌ Repository could not be deleted:\n{}", e))
-                            .build();
-                        error_dialog.connect_response(|dlg, _| dlg.close());
-                        error_dialog.show();
+                        error::show_error_dialog(&parent_clone, "❌ Repository could not be deleted", &e, None);
                     }
                 }
             }
@@ -573,6 +1029,75 @@ impl RepoWindow {
         dialog.show();
     }
 
+    /// Ticks or unticks the repositories of the selected rows. Like single
+    /// toggles, this only becomes a pending change.
+    fn set_selected_enabled(parent: &Dialog, listbox: &ListBox, enabled: bool) {
+        let selected = listbox.selected_rows();
+        if selected.is_empty() {
+            let dialog = MessageDialog::builder()
+                .transient_for(parent)
+                .modal(true)
+                .message_type(MessageType::Info)
+                .buttons(ButtonsType::Ok)
+                .text("⚠️ No repositories selected")
+                .secondary_text("Click the rows to change, holding Ctrl or Shift to select several.")
+                .build();
+            dialog.connect_response(|dlg, _| dlg.close());
+            dialog.show();
+            return;
+        }
+
+        for row in selected {
+            if let Some(check) = row_checkbox(&row).filter(|check| check.is_sensitive()) {
+                check.set_active(enabled);
+            }
+        }
+    }
+
+    /// Lists the pending changes and writes them all at once when confirmed
+    fn review_pending_changes(parent: &Dialog, repositories: &Rc<RefCell<Vec<Repository>>>, listbox: &ListBox) {
+        let changes = repo_manager::pending_changes(&repositories.borrow(), &checkbox_states(listbox));
+        if changes.is_empty() {
+            return;
+        }
+        let lines: Vec<String> = changes.iter()
+            .map(|(repo, enabled)| format!(
+                "{} {} ({} {})",
+                if *enabled { "✅ Enable" } else { "⛔ Disable" },
+                repo.name,
+                repo.uri,
+                repo.distribution
+            ))
+            .collect();
+
+        let dialog = MessageDialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .message_type(MessageType::Question)
+            .buttons(ButtonsType::None)
+            .text("✏️ Apply Repository Changes?")
+            .secondary_text(&format!("{}\n\nRun \"Update Repositories\" afterwards to refresh the package lists.", lines.join("\n")))
+            .build();
+        dialog.add_button("Cancel", ResponseType::Cancel);
+        dialog.add_button("Apply", ResponseType::Accept);
+
+        let parent = parent.clone();
+        let repositories = repositories.clone();
+        let listbox = listbox.clone();
+        dialog.connect_response(move |dlg, response| {
+            dlg.close();
+            if response != ResponseType::Accept {
+                return;
+            }
+            match repo_manager::apply_repository_changes(&changes) {
+                Ok(count) => println!("🔀 {} repository changes applied", count),
+                Err(e) => error::show_error_dialog(&parent, "❌ The repository changes could not be applied", &e, None),
+            }
+            Self::refresh_repositories(&repositories, &listbox);
+        });
+        dialog.show();
+    }
+
     fn update_repositories(parent: &Dialog) {
         match repo_manager::update_repositories() {
             Ok(_) => {
@@ -587,16 +1112,95 @@ impl RepoWindow {
                 success_dialog.show();
             }
             Err(e) => {
-                let error_dialog = MessageDialog::builder()
-                    .transient_for(parent)
-                    .modal(true)
-                    .message_type(MessageType::Error)
-                    .buttons(ButtonsType::Ok)
-                    .text(&format!("❌ Repository update failed:\n{}", e))
-                    .build();
-                error_dialog.connect_response(|dlg, _| dlg.close());
-                error_dialog.show();
+                error::show_error_dialog(parent, "❌ Repository update failed", &e, None);
             }
         }
     }
 }
+
+/// Component and architecture check boxes of the add dialog, built from
+/// the Release file of the repository
+struct ReleasePickers {
+    comp_box: GtkBox,
+    arch_box: GtkBox,
+    components: RefCell<Vec<CheckButton>>,
+    architectures: RefCell<Vec<CheckButton>>,
+    manual_components: RefCell<Option<Entry>>,
+}
+
+impl ReleasePickers {
+    fn clear(&self) {
+        for container in [&self.comp_box, &self.arch_box] {
+            while let Some(child) = container.first_child() {
+                container.remove(&child);
+            }
+        }
+        self.components.borrow_mut().clear();
+        self.architectures.borrow_mut().clear();
+        *self.manual_components.borrow_mut() = None;
+    }
+
+    fn populate(&self, info: &ReleaseInfo) {
+        self.clear();
+        for component in &info.components {
+            let check = CheckButton::with_label(component);
+            // "main" is what nearly every repository is added for
+            check.set_active(component == "main" || info.components.len() == 1);
+            self.comp_box.append(&check);
+            self.components.borrow_mut().push(check);
+        }
+        for arch in info.architectures.iter().filter(|arch| arch.as_str() != "all") {
+            let check = CheckButton::with_label(arch);
+            self.arch_box.append(&check);
+            self.architectures.borrow_mut().push(check);
+        }
+    }
+
+    /// Falls back to typing the components when there is no Release file
+    fn manual(&self) {
+        self.clear();
+        let entry = Entry::new();
+        entry.set_placeholder_text(Some("main contrib non-free"));
+        entry.set_hexpand(true);
+        self.comp_box.append(&entry);
+        *self.manual_components.borrow_mut() = Some(entry);
+    }
+
+    fn selected_components(&self) -> Vec<String> {
+        if let Some(entry) = self.manual_components.borrow().as_ref() {
+            return entry.text().split_whitespace().map(str::to_string).collect();
+        }
+        checked_labels(&self.components.borrow())
+    }
+
+    fn selected_architectures(&self) -> Vec<String> {
+        checked_labels(&self.architectures.borrow())
+    }
+}
+
+fn checked_labels(checks: &[CheckButton]) -> Vec<String> {
+    checks.iter()
+        .filter(|check| check.is_active())
+        .filter_map(|check| check.label())
+        .map(|label| label.to_string())
+        .collect()
+}
+
+fn row_checkbox(row: &ListBoxRow) -> Option<CheckButton> {
+    row.child()
+        .and_then(|hbox| hbox.first_child())
+        .and_downcast::<CheckButton>()
+}
+
+/// The ticked state of every repository row, in list order
+fn checkbox_states(listbox: &ListBox) -> Vec<bool> {
+    let mut states = Vec::new();
+    let mut child = listbox.first_child();
+    while let Some(row_widget) = child {
+        child = row_widget.next_sibling();
+        if let Some(check) = row_widget.downcast_ref::<ListBoxRow>().and_then(row_checkbox) {
+            states.push(check.is_active());
+        }
+    }
+    states
+}
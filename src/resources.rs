@@ -0,0 +1,90 @@
+// src/resources.rs
+
+use crate::paths;
+use gtk::gdk::Display;
+use gtk::prelude::*;
+use gtk::{gio, CssProvider};
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+thread_local! {
+    static CSS_MONITOR: RefCell<Option<gio::FileMonitor>> = RefCell::new(None);
+}
+
+/// Path prefix of the bundled GResources
+#[cfg(meaupdater_gresources)]
+const RESOURCE_PREFIX: &str = "/io/github/mthakan/MeaUpdater";
+
+/// Registers the compiled resource bundle once per process
+pub fn register() {
+    #[cfg(meaupdater_gresources)]
+    {
+        use std::sync::Once;
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| {
+            gio::resources_register_include!("meaupdater.gresource")
+                .expect("Bundled resources could not be registered");
+        });
+    }
+}
+
+fn load_bundled_css(provider: &CssProvider) {
+    #[cfg(meaupdater_gresources)]
+    provider.load_from_resource(&format!("{}/style.css", RESOURCE_PREFIX));
+
+    #[cfg(not(meaupdater_gresources))]
+    provider.load_from_data(include_str!("../data/resources/style.css"));
+}
+
+/// Stylesheet in the config directory that overrides the bundled rules
+pub fn user_css_file() -> Option<PathBuf> {
+    paths::config_file("style.css")
+}
+
+/// Loads the bundled stylesheet and the user's override, reloading the
+/// override whenever the file changes
+pub fn load_css(display: &Display) {
+    register();
+
+    let provider = CssProvider::new();
+    load_bundled_css(&provider);
+    gtk::style_context_add_provider_for_display(display, &provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+
+    let path = match user_css_file() {
+        Some(path) => path,
+        None => return,
+    };
+
+    let user_provider = CssProvider::new();
+    gtk::style_context_add_provider_for_display(display, &user_provider, gtk::STYLE_PROVIDER_PRIORITY_USER);
+
+    let file = gio::File::for_path(&path);
+    let reload = {
+        let user_provider = user_provider.clone();
+        let path = path.clone();
+        move || {
+            if path.exists() {
+                user_provider.load_from_path(&path);
+            } else {
+                user_provider.load_from_data("");
+            }
+        }
+    };
+    reload();
+
+    match file.monitor_file(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+        Ok(monitor) => {
+            monitor.connect_changed(move |_, _, _, event| {
+                if matches!(
+                    event,
+                    gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created | gio::FileMonitorEvent::Deleted
+                ) {
+                    reload();
+                }
+            });
+            // The monitor stops when dropped
+            CSS_MONITOR.with(|slot| *slot.borrow_mut() = Some(monitor));
+        }
+        Err(e) => eprintln!("Could not watch {}: {}", path.display(), e),
+    }
+}
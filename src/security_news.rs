@@ -0,0 +1,225 @@
+// src/security_news.rs
+
+use crate::error::{Context, Error, Result};
+use crate::sysroot;
+use crate::util;
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+
+/// Advisory feed published by the distribution's security team
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedSource {
+    Debian,
+    Ubuntu,
+}
+
+impl FeedSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeedSource::Debian => "Debian Security Advisories (DSA)",
+            FeedSource::Ubuntu => "Ubuntu Security Notices (USN)",
+        }
+    }
+
+    pub fn url(&self) -> &'static str {
+        match self {
+            FeedSource::Debian => "https://www.debian.org/security/dsa",
+            FeedSource::Ubuntu => "https://ubuntu.com/security/notices/rss.xml",
+        }
+    }
+
+    /// Page of the distribution's security tracker about one CVE
+    pub fn cve_url(&self, cve: &str) -> String {
+        match self {
+            FeedSource::Debian => format!("https://security-tracker.debian.org/tracker/{}", cve),
+            FeedSource::Ubuntu => format!("https://ubuntu.com/security/{}", cve),
+        }
+    }
+
+    /// Picks the feed from the ID/ID_LIKE fields of os-release
+    pub fn from_os_release(content: &str) -> Option<Self> {
+        let mut ids = Vec::new();
+        for line in content.lines() {
+            if let Some(value) = line.strip_prefix("ID=").or_else(|| line.strip_prefix("ID_LIKE=")) {
+                ids.extend(value.trim_matches('"').split_whitespace().map(str::to_string));
+            }
+        }
+
+        if ids.iter().any(|id| id == "ubuntu") {
+            Some(FeedSource::Ubuntu)
+        } else if ids.iter().any(|id| id == "debian") {
+            Some(FeedSource::Debian)
+        } else {
+            None
+        }
+    }
+
+    pub fn detect() -> Option<Self> {
+        Self::from_os_release(&fs::read_to_string(sysroot::path("/etc/os-release")).ok()?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advisory {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub date: String,
+    /// Source package names the advisory is about
+    pub packages: Vec<String>,
+}
+
+/// An advisory together with the local packages it concerns
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdvisoryMatch {
+    pub advisory: Advisory,
+    pub installed: Vec<String>,
+    pub pending: Vec<String>,
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn tag_text(item: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}", tag);
+    let start = item.find(&open)?;
+    let content_start = start + item[start..].find('>')? + 1;
+    let end = content_start + item[content_start..].find(&format!("</{}>", tag))?;
+
+    let text = item[content_start..end].trim();
+    let text = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+        .unwrap_or(text);
+    Some(decode_entities(text.trim()))
+}
+
+/// Package names mentioned in an advisory title.
+/// DSA titles name the source package ("DSA-5801-1 firefox-esr - security update");
+/// USN titles name the product ("USN-7100-1: OpenSSL vulnerabilities"), so only
+/// its first word is used.
+pub fn title_packages(title: &str, source: FeedSource) -> Vec<String> {
+    let subject = match source {
+        FeedSource::Debian => title.split_whitespace().nth(1),
+        FeedSource::Ubuntu => title.split_once(':').and_then(|(_, rest)| rest.split_whitespace().next()),
+    };
+
+    subject
+        .map(|name| name.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|name| !name.is_empty())
+        .into_iter()
+        .collect()
+}
+
+/// Parses the items of an RSS or RDF feed
+pub fn parse_feed(xml: &str, source: FeedSource) -> Vec<Advisory> {
+    let mut advisories = Vec::new();
+
+    for chunk in xml.split("<item").skip(1) {
+        let item = match chunk.find("</item>") {
+            Some(end) => &chunk[..end],
+            None => chunk,
+        };
+        let title = match tag_text(item, "title") {
+            Some(title) if !title.is_empty() => title,
+            _ => continue,
+        };
+
+        let id = title
+            .split(|c: char| c.is_whitespace() || c == ':')
+            .next()
+            .unwrap_or("")
+            .to_string();
+        let date = tag_text(item, "dc:date")
+            .or_else(|| tag_text(item, "pubDate"))
+            .unwrap_or_default();
+
+        advisories.push(Advisory {
+            packages: title_packages(&title, source),
+            id,
+            link: tag_text(item, "link").unwrap_or_default(),
+            date,
+            title,
+        });
+    }
+
+    advisories
+}
+
+/// Parses `dpkg-query -W -f '${Package} ${source:Package}\n'` into binary → source
+pub fn parse_source_map(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let binary = parts.next()?.split(':').next()?.to_string();
+            let source = parts.next().unwrap_or(&binary).to_string();
+            Some((binary, source))
+        })
+        .collect()
+}
+
+/// Finds which installed and pending packages each advisory concerns
+pub fn correlate(advisories: &[Advisory], sources: &HashMap<String, String>, pending: &[String]) -> Vec<AdvisoryMatch> {
+    advisories
+        .iter()
+        .map(|advisory| {
+            let mut installed: Vec<String> = sources
+                .iter()
+                .filter(|(binary, source)| advisory.packages.contains(source) || advisory.packages.contains(binary))
+                .map(|(binary, _)| binary.clone())
+                .collect();
+            installed.sort();
+
+            let pending = installed
+                .iter()
+                .filter(|name| pending.contains(name))
+                .cloned()
+                .collect();
+
+            AdvisoryMatch { advisory: advisory.clone(), installed, pending }
+        })
+        .collect()
+}
+
+/// Pending package → advisories it fixes
+pub fn fixes_by_package(matches: &[AdvisoryMatch]) -> HashMap<String, Vec<String>> {
+    let mut fixes: HashMap<String, Vec<String>> = HashMap::new();
+    for m in matches {
+        for name in &m.pending {
+            fixes.entry(name.clone()).or_default().push(m.advisory.id.clone());
+        }
+    }
+    fixes
+}
+
+pub fn fetch_feed(source: FeedSource) -> Result<String> {
+    util::fetch_url(source.url(), 20).map(|feed| String::from_utf8_lossy(&feed).to_string())
+}
+
+pub fn installed_sources() -> HashMap<String, String> {
+    Command::new("dpkg-query")
+        .args(&["-W", "-f", "${Package} ${source:Package}\n"])
+        .output()
+        .map(|output| parse_source_map(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+/// Downloads the feed for this system and correlates it with local packages
+pub fn load_news(pending: &[String]) -> Result<(FeedSource, Vec<AdvisoryMatch>)> {
+    let source = FeedSource::detect().context("No security feed is known for this distribution")?;
+    let feed = fetch_feed(source)?;
+    // Captive portals and proxies answer with an HTML page instead
+    if !feed.contains("<channel") {
+        return Err(Error::Parse(format!("{} did not return an RSS feed", source.url())));
+    }
+    let advisories = parse_feed(&feed, source);
+    Ok((source, correlate(&advisories, &installed_sources(), pending)))
+}
@@ -0,0 +1,182 @@
+// src/security_news_window.rs
+
+use crate::security_news::{self, AdvisoryMatch};
+use gtk::prelude::*;
+use gtk::{
+    ApplicationWindow, Box as GtkBox, Button, CheckButton, ListBox, ListBoxRow, ScrolledWindow,
+    Orientation, HeaderBar, Label, Dialog, Separator, glib,
+};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Recent distribution security advisories, matched against this system
+pub struct SecurityNewsWindow {
+    window: Dialog,
+}
+
+impl SecurityNewsWindow {
+    pub fn new(parent: &ApplicationWindow, pending: Vec<String>, on_loaded: Rc<dyn Fn(HashMap<String, Vec<String>>)>) -> Self {
+        let window = Dialog::builder()
+            .transient_for(parent)
+            .modal(true)
+            .title("Security News")
+            .default_width(900)
+            .default_height(600)
+            .build();
+
+        let header_bar = HeaderBar::new();
+        header_bar.set_title_widget(Some(&Label::new(Some("📰 Security News"))));
+        window.set_titlebar(Some(&header_bar));
+
+        let main_vbox = GtkBox::new(Orientation::Vertical, 12);
+        main_vbox.set_margin_top(16);
+        main_vbox.set_margin_bottom(16);
+        main_vbox.set_margin_start(16);
+        main_vbox.set_margin_end(16);
+
+        let top_box = GtkBox::new(Orientation::Horizontal, 12);
+        let status_label = Label::new(Some("Downloading advisories..."));
+        status_label.set_hexpand(true);
+        status_label.set_halign(gtk::Align::Start);
+        status_label.set_wrap(true);
+
+        let relevant_check = CheckButton::with_label("Only advisories for installed packages");
+        relevant_check.set_active(true);
+
+        top_box.append(&status_label);
+        top_box.append(&relevant_check);
+        main_vbox.append(&top_box);
+
+        main_vbox.append(&Separator::new(Orientation::Horizontal));
+
+        let listbox = ListBox::new();
+        listbox.set_selection_mode(gtk::SelectionMode::None);
+        let scrolled_window = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .build();
+        scrolled_window.set_child(Some(&listbox));
+        main_vbox.append(&scrolled_window);
+
+        window.content_area().append(&main_vbox);
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(security_news::load_news(&pending));
+        });
+
+        let matches: Rc<std::cell::RefCell<Vec<AdvisoryMatch>>> = Rc::new(std::cell::RefCell::new(Vec::new()));
+
+        let listbox_filter = listbox.clone();
+        let matches_filter = matches.clone();
+        relevant_check.connect_toggled(move |check| {
+            Self::populate(&listbox_filter, &matches_filter.borrow(), check.is_active());
+        });
+
+        glib::timeout_add_local(Duration::from_millis(200), move || {
+            let result = match rx.try_recv() {
+                Ok(result) => result,
+                Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                Err(mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+            };
+
+            match result {
+                Ok((source, loaded)) => {
+                    let relevant = loaded.iter().filter(|m| !m.installed.is_empty()).count();
+                    let fixes = security_news::fixes_by_package(&loaded);
+                    status_label.set_markup(&format!(
+                        "<b>{}</b>: {} recent advisories, {} concern installed packages, {} pending updates fix one.",
+                        source.name(),
+                        loaded.len(),
+                        relevant,
+                        fixes.len()
+                    ));
+                    *matches.borrow_mut() = loaded;
+                    Self::populate(&listbox, &matches.borrow(), relevant_check.is_active());
+                    on_loaded(fixes);
+                }
+                Err(e) => status_label.set_text(&format!("❌ {}", e)),
+            }
+            glib::ControlFlow::Break
+        });
+
+        Self { window }
+    }
+
+    fn populate(listbox: &ListBox, matches: &[AdvisoryMatch], only_relevant: bool) {
+        while let Some(child) = listbox.first_child() {
+            listbox.remove(&child);
+        }
+
+        for m in matches.iter().filter(|m| !only_relevant || !m.installed.is_empty()) {
+            listbox.append(&Self::create_advisory_row(m));
+        }
+    }
+
+    fn create_advisory_row(m: &AdvisoryMatch) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        row.add_css_class("package-row");
+
+        let hbox = GtkBox::new(Orientation::Horizontal, 12);
+        hbox.set_margin_top(8);
+        hbox.set_margin_bottom(8);
+        hbox.set_margin_start(8);
+        hbox.set_margin_end(8);
+
+        let vbox = GtkBox::new(Orientation::Vertical, 2);
+        vbox.set_hexpand(true);
+
+        let title_label = Label::new(Some(&m.advisory.title));
+        title_label.set_halign(gtk::Align::Start);
+        title_label.set_wrap(true);
+        title_label.add_css_class("package-name");
+        vbox.append(&title_label);
+
+        let detail = if !m.pending.is_empty() {
+            format!("🛡️ Fixed by pending updates: {}", m.pending.join(", "))
+        } else if !m.installed.is_empty() {
+            format!("✅ Installed and up to date: {}", m.installed.join(", "))
+        } else {
+            "Not installed on this system".to_string()
+        };
+        let detail_label = Label::new(Some(&detail));
+        detail_label.set_halign(gtk::Align::Start);
+        detail_label.set_wrap(true);
+        if m.pending.is_empty() {
+            detail_label.add_css_class("dim-label");
+        } else {
+            detail_label.add_css_class("security-update");
+        }
+        vbox.append(&detail_label);
+
+        if !m.advisory.date.is_empty() {
+            let date_label = Label::new(Some(&m.advisory.date));
+            date_label.set_halign(gtk::Align::Start);
+            date_label.add_css_class("dim-label");
+            vbox.append(&date_label);
+        }
+
+        hbox.append(&vbox);
+
+        if !m.advisory.link.is_empty() {
+            let link = m.advisory.link.clone();
+            let open_btn = Button::with_label("🌐 Open");
+            open_btn.set_valign(gtk::Align::Center);
+            open_btn.connect_clicked(move |_| {
+                if let Err(e) = open::that(&link) {
+                    eprintln!("Could not open {}: {}", link, e);
+                }
+            });
+            hbox.append(&open_btn);
+        }
+
+        row.set_child(Some(&hbox));
+        row
+    }
+
+    pub fn show(&self) {
+        self.window.show();
+    }
+}
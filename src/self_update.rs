@@ -0,0 +1,251 @@
+// src/self_update.rs
+
+use crate::apt;
+use crate::error::{Context, Error, Result};
+use crate::paths;
+use crate::util::{self, json_string_field, json_string_fields};
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The Debian package MeaUpdater itself is installed from
+pub const PACKAGE_NAME: &str = "meaupdater";
+
+/// Set for the new process after a restart, so it checks right away
+pub const RESTARTED_ENV: &str = "MEAUPDATER_RESTARTED";
+
+/// Release packages are a few megabytes, so downloads get longer than feeds
+const DOWNLOAD_MAX_SECS: u32 = 120;
+
+/// What the kernel appends to `/proc/self/exe` once the file is replaced
+const DELETED_SUFFIX: &str = " (deleted)";
+
+/// Whether a transaction on `packages` upgrades MeaUpdater itself
+pub fn updates_self(packages: &[String]) -> bool {
+    packages.iter().any(|package| package.split(':').next() == Some(PACKAGE_NAME))
+}
+
+/// Whether the running executable was replaced on disk, judged from the
+/// `/proc/self/exe` link target. Returns the path of the new executable.
+pub fn replaced_executable(exe_link: &str) -> Option<PathBuf> {
+    exe_link.strip_suffix(DELETED_SUFFIX).map(PathBuf::from)
+}
+
+/// The new executable when the running one is stale
+pub fn stale_executable() -> Option<PathBuf> {
+    let link = std::fs::read_link("/proc/self/exe").ok()?;
+    replaced_executable(&link.to_string_lossy())
+}
+
+/// Whether the transaction that just finished left this process running old code
+pub fn needs_restart(packages: &[String]) -> bool {
+    updates_self(packages) || stale_executable().is_some()
+}
+
+/// Replaces this process with the freshly installed MeaUpdater, keeping
+/// the command line. Only returns if that fails.
+pub fn restart() -> Error {
+    let exe = match stale_executable().or_else(|| std::env::current_exe().ok()) {
+        Some(exe) => exe,
+        None => return Error::Other("The MeaUpdater executable could not be found".to_string()),
+    };
+    println!("🔄 Restarting {}", exe.display());
+    let error = Command::new(&exe)
+        .args(std::env::args().skip(1))
+        .env(RESTARTED_ENV, "1")
+        .exec();
+    Error::Other(format!("MeaUpdater could not be restarted: {}", error))
+}
+
+/// Whether this process was started by `restart`. Clears the marker so
+/// programs started from here do not inherit it.
+pub fn take_restarted() -> bool {
+    let restarted = std::env::var_os(RESTARTED_ENV).is_some();
+    if restarted {
+        std::env::remove_var(RESTARTED_ENV);
+    }
+    restarted
+}
+
+/// Version of the running program
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit the program was built from, recorded by build.rs
+pub const GIT_COMMIT: &str = env!("MEAUPDATER_GIT_COMMIT");
+
+const LATEST_RELEASE_API: &str = "https://api.github.com/repos/mthakan/meaupdater/releases/latest";
+
+/// Checksum file every release publishes next to its packages
+pub const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// Detached signature of the checksum file
+pub const SIGNATURE_ASSET: &str = "SHA256SUMS.asc";
+
+/// Public key the checksum file of every release is signed with, embedded
+/// at build time from `MEAUPDATER_RELEASE_KEY`
+#[cfg(meaupdater_release_key)]
+const RELEASE_KEY: Option<&[u8]> = Some(include_bytes!(concat!(env!("OUT_DIR"), "/release-key.gpg")));
+#[cfg(not(meaupdater_release_key))]
+const RELEASE_KEY: Option<&[u8]> = None;
+
+/// A published MeaUpdater release
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    /// Version without the leading "v" of the tag
+    pub version: String,
+    pub url: String,
+    /// File name and download URL of each attached file
+    pub assets: Vec<(String, String)>,
+}
+
+impl Release {
+    fn asset_url(&self, name: &str) -> Option<&str> {
+        self.assets.iter().find(|(asset, _)| asset == name).map(|(_, url)| url.as_str())
+    }
+}
+
+/// Reads the response of the GitHub "latest release" endpoint
+pub fn parse_release(json: &str) -> Option<Release> {
+    let tag = json_string_field(json, "tag_name")?;
+    let assets = json_string_fields(json, "browser_download_url").into_iter()
+        .filter_map(|url| {
+            let name = url.rsplit('/').next()?.to_string();
+            Some((name, url))
+        })
+        .collect();
+    Some(Release {
+        version: tag.trim_start_matches('v').to_string(),
+        url: json_string_field(json, "html_url").unwrap_or_default(),
+        assets,
+    })
+}
+
+/// The package of a release for `arch`, or the architecture independent one
+pub fn deb_asset<'a>(assets: &'a [(String, String)], arch: &str) -> Option<&'a (String, String)> {
+    let native = format!("_{}.deb", arch);
+    assets.iter().find(|(name, _)| name.ends_with(&native))
+        .or_else(|| assets.iter().find(|(name, _)| name.ends_with("_all.deb")))
+}
+
+/// The SHA-256 of `file_name` in `sha256sum` output
+pub fn checksum_for(sums: &str, file_name: &str) -> Option<String> {
+    sums.lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim().trim_start_matches('*') == file_name)
+        .map(|(hash, _)| hash.to_lowercase())
+}
+
+fn version_parts(version: &str) -> Vec<u64> {
+    version.trim_start_matches('v')
+        .split(|c: char| c == '.' || c == '-')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Whether `candidate` is a later version than `current`, e.g. 0.10.0 > 0.5.0
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    let (mut candidate, mut current) = (version_parts(candidate), version_parts(current));
+    let len = candidate.len().max(current.len());
+    candidate.resize(len, 0);
+    current.resize(len, 0);
+    candidate > current
+}
+
+/// Asks GitHub for the newest MeaUpdater release. Blocks.
+pub fn latest_release() -> Result<Release> {
+    let json = util::fetch_url(LATEST_RELEASE_API, DOWNLOAD_MAX_SECS)?;
+    parse_release(&String::from_utf8_lossy(&json))
+        .ok_or_else(|| Error::Parse("GitHub did not return a release".to_string()))
+}
+
+/// Whether an APT repository provides MeaUpdater, in which case updates
+/// come through the normal update list instead
+pub fn packaged_by_distribution() -> bool {
+    apt::get_policy_candidates(&[PACKAGE_NAME.to_string()])
+        .get(PACKAGE_NAME)
+        .map_or(false, |candidate| candidate.origin.is_some())
+}
+
+fn download_dir() -> Option<PathBuf> {
+    Some(paths::cache_dir()?.join("self-update"))
+}
+
+fn sha256_of(path: &Path) -> Result<String> {
+    let output = Command::new("sha256sum")
+        .arg(path)
+        .output()
+        .context("sha256sum could not be run")?;
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(str::to_lowercase)
+        .ok_or_else(|| Error::Parse(format!("sha256sum printed no checksum for {}", path.display())))
+}
+
+/// Checks the signature of the checksum file against the embedded release
+/// key. A release without a signature is never trusted.
+fn verify_signature(release: &Release, sums_path: &Path) -> Result<()> {
+    let key = RELEASE_KEY.ok_or_else(|| Error::Other(
+        "This build of MeaUpdater has no release signing key, so GitHub releases cannot be verified. Update it through your distribution instead.".to_string()
+    ))?;
+    let signature_url = release.asset_url(SIGNATURE_ASSET)
+        .ok_or_else(|| Error::Other(format!("MeaUpdater {} publishes no {} file, so its package cannot be verified", release.version, SIGNATURE_ASSET)))?;
+
+    let keyring_path = sums_path.with_file_name("release-key.gpg");
+    fs::write(&keyring_path, key).context("Could not save the release key")?;
+    let signature_path = sums_path.with_extension("asc");
+    fs::write(&signature_path, util::fetch_url(signature_url, DOWNLOAD_MAX_SECS)?).context("Could not save the signature")?;
+    let output = Command::new("gpgv")
+        .arg("--keyring")
+        .arg(&keyring_path)
+        .arg(&signature_path)
+        .arg(sums_path)
+        .output()
+        .context("gpgv could not be run")?;
+    if !output.status.success() {
+        return Err(Error::Other(format!(
+            "The signature of the MeaUpdater {} checksums is not valid:\n{}",
+            release.version,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Downloads the package of `release` for this machine and verifies it
+/// against the published checksums and their signature. Returns the path
+/// of the `.deb`. Blocks.
+pub fn download_release(release: &Release) -> Result<PathBuf> {
+    let arch = Command::new("dpkg")
+        .arg("--print-architecture")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .context("dpkg could not be run")?;
+    let (deb_name, deb_url) = deb_asset(&release.assets, &arch)
+        .ok_or_else(|| Error::Other(format!("MeaUpdater {} has no package for {}", release.version, arch)))?;
+    // Never install a package that cannot be verified
+    let sums_url = release.asset_url(CHECKSUMS_ASSET)
+        .ok_or_else(|| Error::Other(format!("MeaUpdater {} publishes no {} file, so its package cannot be verified", release.version, CHECKSUMS_ASSET)))?;
+
+    let dir = download_dir().ok_or_else(|| Error::Other("No cache directory".to_string()))?;
+    paths::ensure_dir(&dir).context("Could not create the download directory")?;
+
+    let sums_path = dir.join(CHECKSUMS_ASSET);
+    let sums = util::fetch_url(sums_url, DOWNLOAD_MAX_SECS)?;
+    fs::write(&sums_path, &sums).context("Could not save the checksums")?;
+    verify_signature(release, &sums_path)?;
+    let expected = checksum_for(&String::from_utf8_lossy(&sums), deb_name)
+        .ok_or_else(|| Error::Other(format!("{} does not list {}", CHECKSUMS_ASSET, deb_name)))?;
+
+    println!("⬇️ Downloading {}", deb_url);
+    let deb_path = dir.join(deb_name);
+    fs::write(&deb_path, util::fetch_url(deb_url, DOWNLOAD_MAX_SECS)?).context("Could not save the package")?;
+    let actual = sha256_of(&deb_path)?;
+    if actual != expected {
+        let _ = fs::remove_file(&deb_path);
+        return Err(Error::Other(format!("The checksum of {} does not match, the download was discarded", deb_name)));
+    }
+    println!("✅ {} verified", deb_name);
+    Ok(deb_path)
+}
@@ -0,0 +1,101 @@
+// src/services.rs
+
+use crate::apt;
+use crate::flatpak_manager;
+use crate::install;
+use crate::model::PackageUpdate;
+use crate::snap_manager;
+use crate::error::Result;
+use gio::prelude::*;
+use gio::Application;
+
+/// Source of the pending update list used by background checks
+pub trait UpdateService {
+    fn upgradable_packages(&self) -> Result<Vec<PackageUpdate>>;
+}
+
+/// Reads the upgradable packages from the local APT lists, followed by the
+/// pending Flatpak and snap updates
+pub struct AptUpdateService;
+
+impl UpdateService for AptUpdateService {
+    fn upgradable_packages(&self) -> Result<Vec<PackageUpdate>> {
+        let mut packages = apt::get_upgradable_packages()?;
+        flatpak_manager::append_updates(&mut packages);
+        snap_manager::append_updates(&mut packages);
+        Ok(packages)
+    }
+}
+
+/// State of an update check as announced to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateStatus {
+    Checking,
+    Failed,
+    Complete(usize),
+    /// A completed check that found security updates among the others
+    SecurityAvailable { updates: usize, security: usize },
+    /// Updates installed by the background installer and those left for
+    /// the user to confirm
+    AutoInstalled { installed: usize, waiting: usize },
+}
+
+impl UpdateStatus {
+    pub fn body(&self) -> String {
+        match self {
+            UpdateStatus::Checking => "Checking for updates...".to_string(),
+            UpdateStatus::Failed => "❌ Checking for updates failed! Please check your internet connection and try again. The system cannot access package repositories.".to_string(),
+            UpdateStatus::Complete(0) => "✅ Your system is up to date".to_string(),
+            UpdateStatus::Complete(count) => format!("{} updates available", count),
+            UpdateStatus::SecurityAvailable { updates, security } => {
+                format!("🔒 {} updates available, {} of them security updates", updates, security)
+            }
+            UpdateStatus::AutoInstalled { installed, waiting: 0 } => format!("🤖 {} updates installed automatically", installed),
+            UpdateStatus::AutoInstalled { installed, waiting } => {
+                format!("🤖 {} updates installed automatically, {} need your confirmation", installed, waiting)
+            }
+        }
+    }
+}
+
+pub trait NotificationService {
+    fn notify(&self, status: UpdateStatus);
+    /// Removes the last notification, e.g. a stale "Checking..." one
+    fn withdraw(&self);
+}
+
+/// Application action behind the notification button, registered by the main window
+pub const INSTALL_SECURITY_ACTION: &str = "app.install-security";
+
+/// Desktop notifications sent through the GApplication
+pub struct DesktopNotifications {
+    app: Application,
+}
+
+impl DesktopNotifications {
+    const ID: &'static str = "update-status";
+
+    pub fn new(app: &Application) -> Self {
+        Self { app: app.clone() }
+    }
+}
+
+impl NotificationService for DesktopNotifications {
+    fn notify(&self, status: UpdateStatus) {
+        let notification = gio::Notification::new("MeaUpdater");
+        notification.set_icon(&install::app_icon());
+        notification.set_body(Some(&status.body()));
+        if status == UpdateStatus::Failed {
+            // Keep the error visible for longer
+            notification.set_priority(gio::NotificationPriority::High);
+        }
+        if let UpdateStatus::SecurityAvailable { .. } = status {
+            notification.add_button("Install Security Updates", INSTALL_SECURITY_ACTION);
+        }
+        self.app.send_notification(Some(Self::ID), &notification);
+    }
+
+    fn withdraw(&self) {
+        self.app.withdraw_notification(Self::ID);
+    }
+}
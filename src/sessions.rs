@@ -0,0 +1,154 @@
+// src/sessions.rs
+
+#[cfg(feature = "gui")]
+use gtk::prelude::*;
+#[cfg(feature = "gui")]
+use gtk::{ButtonsType, MessageDialog, MessageType, ResponseType};
+use std::collections::HashMap;
+use std::process::Command;
+#[cfg(feature = "gui")]
+use std::rc::Rc;
+
+/// A login session as reported by loginctl
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub id: String,
+    pub user: String,
+    pub tty: String,
+    pub remote_host: Option<String>,
+    pub class: String,
+    pub state: String,
+}
+
+impl Session {
+    /// Short description for the warning dialog, e.g. `anna on tty3`
+    pub fn describe(&self) -> String {
+        let place = match &self.remote_host {
+            Some(host) => format!("remotely from {}", host),
+            None if !self.tty.is_empty() => format!("on {}", self.tty),
+            None => "on a graphical seat".to_string(),
+        };
+        format!("{} {}", self.user, place)
+    }
+}
+
+/// Session ids from `loginctl list-sessions --no-legend`
+pub fn parse_session_ids(output: &str) -> Vec<String> {
+    output.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|id| id.to_string())
+        .collect()
+}
+
+/// Builds a session from `loginctl show-session` key=value output
+pub fn parse_session_properties(id: &str, output: &str) -> Session {
+    let properties: HashMap<&str, &str> = output.lines()
+        .filter_map(|line| line.split_once('='))
+        .collect();
+    let get = |key: &str| properties.get(key).unwrap_or(&"").to_string();
+
+    let remote_host = if get("Remote") == "yes" {
+        Some(get("RemoteHost")).filter(|host| !host.is_empty())
+            .or_else(|| Some("another computer".to_string()))
+    } else {
+        None
+    };
+
+    Session {
+        id: id.to_string(),
+        user: get("Name"),
+        tty: get("TTY"),
+        remote_host,
+        class: get("Class"),
+        state: get("State"),
+    }
+}
+
+/// Whether `session` belongs to somebody else who is still logged in
+pub fn is_other_user(session: &Session, current_user: &str) -> bool {
+    session.class == "user"
+        && session.user != current_user
+        && matches!(session.state.as_str(), "active" | "online")
+}
+
+fn current_user() -> String {
+    std::env::var("USER").ok()
+        .filter(|user| !user.is_empty())
+        .or_else(|| {
+            Command::new("id").arg("-un").output().ok()
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .unwrap_or_default()
+}
+
+/// Sessions of other users, empty if loginctl is not available
+pub fn other_user_sessions() -> Vec<Session> {
+    let output = match Command::new("loginctl").args(&["list-sessions", "--no-legend"]).output() {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+    let user = current_user();
+
+    parse_session_ids(&String::from_utf8_lossy(&output.stdout))
+        .iter()
+        .filter_map(|id| {
+            let output = Command::new("loginctl")
+                .args(&["show-session", id, "-p", "Name", "-p", "TTY", "-p", "Remote", "-p", "RemoteHost", "-p", "Class", "-p", "State"])
+                .output()
+                .ok()?;
+            Some(parse_session_properties(id, &String::from_utf8_lossy(&output.stdout)))
+        })
+        .filter(|session| is_other_user(session, &user))
+        .collect()
+}
+
+/// Sends a message to every terminal with `wall`
+pub fn broadcast(message: &str) {
+    match Command::new("wall").arg(message).status() {
+        Ok(status) if status.success() => println!("📢 Sent wall message"),
+        Ok(_) | Err(_) => eprintln!("Could not send wall message"),
+    }
+}
+
+/// Runs `proceed` right away when nobody else is logged in; otherwise lists
+/// the other sessions first and lets the admin cancel or warn them.
+#[cfg(feature = "gui")]
+pub fn confirm_disruptive_action<F: Fn() + 'static>(parent: &gtk::Window, action: &str, proceed: F) {
+    let sessions = other_user_sessions();
+    if sessions.is_empty() {
+        proceed();
+        return;
+    }
+
+    let list: Vec<String> = sessions.iter().map(|s| format!("• {}", s.describe())).collect();
+    let dialog = MessageDialog::builder()
+        .transient_for(parent)
+        .modal(true)
+        .message_type(MessageType::Warning)
+        .buttons(ButtonsType::None)
+        .text("👥 Other Users Are Logged In")
+        .secondary_text(&format!(
+            "You are about to {}. This may interrupt the work of:\n\n{}\n\nYou can send them a message before continuing.",
+            action,
+            list.join("\n")
+        ))
+        .build();
+    dialog.add_button("Cancel", ResponseType::Cancel);
+    dialog.add_button("📢 Notify and Continue", ResponseType::Other(1));
+    dialog.add_button("Continue", ResponseType::Accept);
+
+    let proceed = Rc::new(proceed);
+    let action = action.to_string();
+    dialog.connect_response(move |dlg, response| {
+        dlg.close();
+        match response {
+            ResponseType::Other(1) => {
+                broadcast(&format!("MeaUpdater: the administrator is about to {}. Please save your work.", action));
+                proceed();
+            }
+            ResponseType::Accept => proceed(),
+            _ => {}
+        }
+    });
+    dialog.show();
+}
@@ -0,0 +1,155 @@
+// src/snap_manager.rs
+
+use crate::apt::TransactionEvent;
+use crate::error::{Context, Error, Result};
+use crate::model::{PackageUpdate, UpdateType};
+use crate::privilege;
+use crate::util;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+/// Whether snapd is installed
+pub fn is_available() -> bool {
+    Command::new("snap")
+        .arg("version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Parses the sizes of `snap refresh --list`, e.g. "245MB" or "12kB"
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (number, unit) = text.split_at(split);
+    let factor = util::si_unit_factor(unit)?;
+    let number: f64 = number.parse().ok()?;
+    Some((number * factor) as u64)
+}
+
+/// Rows of a `snap` table below its "Name ..." header
+fn table_rows(output: &str) -> impl Iterator<Item = Vec<&str>> {
+    output.lines()
+        .skip_while(|line| !line.starts_with("Name "))
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        .filter(|fields| fields.len() >= 2)
+}
+
+/// Parses `snap list` into snap → installed version
+pub fn parse_installed(output: &str) -> HashMap<String, String> {
+    table_rows(output)
+        .map(|fields| (fields[0].to_string(), fields[1].to_string()))
+        .collect()
+}
+
+/// Parses `snap refresh --list`:
+/// "Name  Version  Rev  Size  Publisher  Notes"
+pub fn parse_updates(output: &str, installed: &HashMap<String, String>) -> Vec<PackageUpdate> {
+    table_rows(output)
+        .map(|fields| PackageUpdate {
+            name: fields[0].to_string(),
+            current_version: installed.get(fields[0]).cloned().unwrap_or_default(),
+            new_version: fields[1].to_string(),
+            update_type: UpdateType::Snap,
+            from_security: false,
+            size: fields.get(3).and_then(|size| parse_size(size)),
+            first_seen: None,
+        })
+        .collect()
+}
+
+fn snap_output(args: &[&str]) -> Result<String> {
+    let output = Command::new("snap")
+        .args(args)
+        .env("LANG", "C")
+        .output()
+        .context("snap could not be run")?;
+    if !output.status.success() {
+        return Err(Error::from_status(&format!("snap {}", args[0]), &output.status, &String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Pending snap refreshes. "All snaps up to date." goes to stderr, so an
+/// up to date system has no table.
+pub fn get_updates() -> Result<Vec<PackageUpdate>> {
+    let installed = parse_installed(&snap_output(&["list"])?);
+    Ok(parse_updates(&snap_output(&["refresh", "--list"])?, &installed))
+}
+
+/// Adds the snap updates to an APT update list, only logging errors
+pub fn append_updates(packages: &mut Vec<PackageUpdate>) {
+    if !is_available() {
+        return;
+    }
+    match get_updates() {
+        Ok(mut updates) => {
+            crate::update_age::annotate_first_seen(&mut updates);
+            packages.extend(updates);
+        }
+        Err(e) => eprintln!("Snap updates could not be listed: {}", e),
+    }
+}
+
+/// Names of the snap updates among `names`
+pub fn snap_names(packages: &[PackageUpdate], names: &[String]) -> Vec<String> {
+    names.iter()
+        .filter(|name| packages.iter().any(|pkg| pkg.name == **name && pkg.update_type == UpdateType::Snap))
+        .cloned()
+        .collect()
+}
+
+/// Refreshes the given snaps as root, reporting like an APT transaction
+pub fn refresh(snaps: &[String], report: &dyn Fn(TransactionEvent)) -> Result<()> {
+    if snaps.is_empty() {
+        return Ok(());
+    }
+    report(TransactionEvent::Status("Refreshing snaps...".to_string()));
+    report(TransactionEvent::Log(format!("Command: {} snap refresh {}", privilege::method_name(), snaps.join(" "))));
+
+    let mut child = privilege::elevated_command()
+        .arg("snap")
+        .arg("refresh")
+        .args(snaps)
+        .env("LANG", "C")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("snap could not be run")?;
+
+    let stderr = child.stderr.take();
+    let stderr_reader = std::thread::spawn(move || {
+        let mut collected = String::new();
+        if let Some(stderr) = stderr {
+            for line in BufReader::new(stderr).lines().map_while(std::result::Result::ok) {
+                collected.push_str(&line);
+                collected.push('\n');
+            }
+        }
+        collected
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        // One "<name> <version> from <publisher> refreshed" line per snap
+        let mut done = 0;
+        for line in BufReader::new(stdout).lines().map_while(std::result::Result::ok) {
+            if line.contains(" refreshed") {
+                done += 1;
+                report(TransactionEvent::Progress(done as f64 / snaps.len() as f64));
+            }
+            report(TransactionEvent::Log(line));
+        }
+    }
+
+    let status = child.wait().context("snap refresh did not finish")?;
+    let stderr = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        return Err(Error::from_status("snap refresh", &status, &stderr));
+    }
+    report(TransactionEvent::Progress(1.0));
+    Ok(())
+}
@@ -0,0 +1,60 @@
+// src/sysroot.rs
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    static ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// The absolute `path` below `root`
+pub fn resolve_under(root: &Path, path: &str) -> PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
+
+/// With the `fake-root` feature, `MEAUPDATER_SYSROOT` names a root for the
+/// whole process, e.g. to run the GUI against `tests/fixtures/bookworm`
+#[cfg(feature = "fake-root")]
+fn env_root() -> Option<PathBuf> {
+    std::env::var_os("MEAUPDATER_SYSROOT").filter(|root| !root.is_empty()).map(PathBuf::from)
+}
+
+#[cfg(not(feature = "fake-root"))]
+fn env_root() -> Option<PathBuf> {
+    None
+}
+
+/// The fake root in use, if any
+pub fn root() -> Option<PathBuf> {
+    ROOT.with(|root| root.borrow().clone()).or_else(env_root)
+}
+
+/// Where to read the system file `path` from, e.g. "/etc/os-release"
+pub fn path(path: &str) -> PathBuf {
+    match root() {
+        Some(root) => resolve_under(&root, path),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Like `path`, as a string for code that keeps paths as text
+pub fn path_string(path: &str) -> String {
+    self::path(path).to_string_lossy().into_owned()
+}
+
+struct RestoreRoot(Option<PathBuf>);
+
+impl Drop for RestoreRoot {
+    fn drop(&mut self) {
+        ROOT.with(|root| *root.borrow_mut() = self.0.take());
+    }
+}
+
+/// Runs `f` with the system files of this thread read below `root`. Meant
+/// for integration tests against fixture trees.
+#[allow(dead_code)]
+pub fn with_root<T>(root: &Path, f: impl FnOnce() -> T) -> T {
+    let previous = ROOT.with(|current| current.borrow_mut().replace(root.to_path_buf()));
+    let _restore = RestoreRoot(previous);
+    f()
+}
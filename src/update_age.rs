@@ -0,0 +1,86 @@
+// src/update_age.rs
+
+use crate::format;
+use crate::model::PackageUpdate;
+use crate::paths;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Security updates pending longer than this are highlighted in the list
+pub const STALE_SECURITY_DAYS: u64 = 7;
+
+fn first_seen_file() -> Option<PathBuf> {
+    paths::state_file("first_seen")
+}
+
+/// A pending update: package name and the version it would install
+pub type PendingKey = (String, String);
+
+/// Parses the `package<TAB>version<TAB>timestamp` lines of the first-seen
+/// file. Lines written before the version was recorded are skipped.
+pub fn parse_first_seen(content: &str) -> HashMap<PendingKey, u64> {
+    let mut entries = HashMap::new();
+
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if let [name, version, timestamp] = fields.as_slice() {
+            if let Ok(timestamp) = timestamp.trim().parse::<u64>() {
+                entries.insert((name.to_string(), version.to_string()), timestamp);
+            }
+        }
+    }
+
+    entries
+}
+
+/// Keeps the timestamp of updates that were already pending and stamps new
+/// ones with `now`. Updates that are no longer pending are dropped, so a
+/// package whose pending version changes starts counting from zero.
+pub fn merge_first_seen(previous: &HashMap<PendingKey, u64>, pending: &[PendingKey], now: u64) -> HashMap<PendingKey, u64> {
+    pending.iter()
+        .map(|key| (key.clone(), *previous.get(key).unwrap_or(&now)))
+        .collect()
+}
+
+/// Fills `first_seen` for every package and persists the updated table
+pub fn annotate_first_seen(packages: &mut [PackageUpdate]) {
+    let path = match first_seen_file() {
+        Some(path) => path,
+        None => return,
+    };
+
+    let previous = fs::read_to_string(&path)
+        .map(|content| parse_first_seen(&content))
+        .unwrap_or_default();
+
+    let pending: Vec<PendingKey> = packages.iter().map(|p| (p.name.clone(), p.new_version.clone())).collect();
+    let merged = merge_first_seen(&previous, &pending, format::now());
+
+    for pkg in packages.iter_mut() {
+        pkg.first_seen = merged.get(&(pkg.name.clone(), pkg.new_version.clone())).copied();
+    }
+
+    let mut lines: Vec<String> = merged.iter()
+        .map(|((name, version), timestamp)| format!("{}\t{}\t{}", name, version, timestamp))
+        .collect();
+    lines.sort();
+
+    if let Some(parent) = path.parent() {
+        let _ = paths::ensure_dir(parent);
+    }
+    if let Err(e) = fs::write(&path, lines.join("\n")) {
+        eprintln!("Could not save update age information: {}", e);
+    }
+}
+
+/// Number of whole days since `first_seen`
+pub fn days_pending(first_seen: u64, now: u64) -> u64 {
+    now.saturating_sub(first_seen) / SECONDS_PER_DAY
+}
+
+pub fn pending_days(pkg: &PackageUpdate) -> Option<u64> {
+    pkg.first_seen.map(|ts| days_pending(ts, format::now()))
+}
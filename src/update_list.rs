@@ -0,0 +1,148 @@
+// src/update_list.rs
+
+use crate::app_state::AppState;
+use crate::cli;
+use crate::flatpak_manager;
+use crate::format;
+use crate::kernel_manager;
+use crate::model::{self, PackageUpdate, UpdateType};
+use crate::snap_manager;
+
+/// Config key of the kept-back section
+pub const KEPT_BACK_SECTION: &str = "kept-back";
+
+/// Sections of the list grouped by type, in display order
+const GROUPS: [(UpdateType, &str); 6] = [
+    (UpdateType::Kernel, "⚡ Kernel Updates"),
+    (UpdateType::Security, "🔒 Security Updates"),
+    (UpdateType::Firmware, "🔌 Firmware Updates"),
+    (UpdateType::Software, "📦 Software Updates"),
+    (UpdateType::Flatpak, "🧩 Flatpak Updates"),
+    (UpdateType::Snap, "🔸 Snap Updates"),
+];
+
+/// Rows of the update list under one header
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Remembers whether the section is collapsed. `None` for the rows of
+    /// an ungrouped list, which have no header.
+    pub key: Option<&'static str>,
+    pub title: &'static str,
+    pub packages: Vec<PackageUpdate>,
+}
+
+impl Section {
+    pub fn is_kept_back(&self) -> bool {
+        self.key == Some(KEPT_BACK_SECTION)
+    }
+}
+
+/// The updates offered at all: only security updates when the
+/// administrator's policy says so, including kernels and firmware from a
+/// security archive
+pub fn offered(state: &AppState, mut packages: Vec<PackageUpdate>) -> Vec<PackageUpdate> {
+    if state.is_security_only() {
+        packages.retain(|pkg| pkg.from_security);
+    }
+    packages
+}
+
+/// Splits `packages` into the sections of the list. The kernel set is one
+/// row, so it cannot be half selected, and updates a plain upgrade would
+/// skip get their own section at the end.
+pub fn sections(state: &AppState, packages: Vec<PackageUpdate>, group_by_type: bool, by_size: bool) -> Vec<Section> {
+    let packages = kernel_manager::merge_kernel_set(packages, &state.kernel_set());
+    let (kept_back, packages): (Vec<_>, Vec<_>) = packages.into_iter()
+        .partition(|pkg| state.kept_back_reason(&pkg.name).is_some());
+
+    let mut sections = Vec::new();
+    if group_by_type {
+        for (update_type, title) in GROUPS {
+            let mut group: Vec<_> = packages.iter().filter(|pkg| pkg.update_type == update_type).cloned().collect();
+            if group.is_empty() {
+                continue;
+            }
+            if by_size {
+                model::sort_by_size(&mut group);
+            } else {
+                group.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+            sections.push(Section { key: Some(cli::type_name(&update_type)), title, packages: group });
+        }
+    } else if !packages.is_empty() {
+        sections.push(Section { key: None, title: "", packages });
+    }
+
+    if !kept_back.is_empty() {
+        sections.push(Section { key: Some(KEPT_BACK_SECTION), title: "⏸️ Kept Back", packages: kept_back });
+    }
+    sections
+}
+
+/// Pending security updates a plain upgrade installs
+pub fn security_updates(state: &AppState) -> Vec<String> {
+    state.packages().into_iter()
+        .filter(|pkg| pkg.from_security && state.kept_back_reason(&pkg.name).is_none())
+        .map(|pkg| pkg.name)
+        .collect()
+}
+
+/// Rows to tick for installing the security updates. The kernel set has a
+/// single row, under the name of its lead.
+pub fn security_rows(state: &AppState) -> Vec<String> {
+    let mut security = security_updates(state);
+    let kernel_set = state.kernel_set();
+    if kernel_set.iter().any(|member| security.contains(member)) {
+        security.extend(kernel_set.first().cloned());
+    }
+    security
+}
+
+/// Packages to install for the ticked rows, with the whole kernel set when
+/// its row is ticked
+pub fn expand_selection(state: &AppState, ticked: Vec<String>) -> Vec<String> {
+    kernel_manager::expand_kernel_set(ticked, &state.kernel_set())
+}
+
+/// The APT packages among `selected`, leaving out Flatpak applications and snaps
+pub fn apt_only(packages: &[PackageUpdate], selected: Vec<String>) -> Vec<String> {
+    let flatpaks = flatpak_manager::flatpak_names(packages, &selected);
+    let snaps = snap_manager::snap_names(packages, &selected);
+    selected.into_iter().filter(|name| !flatpaks.contains(name) && !snaps.contains(name)).collect()
+}
+
+/// What "Select All" does with the shown rows: tick them, or untick them
+/// all when all are ticked
+pub fn select_all_ticks(ticked: &[bool]) -> bool {
+    !ticked.iter().all(|&ticked| ticked)
+}
+
+/// Download size of the selection, for the install button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionSummary {
+    pub count: usize,
+    pub total: u64,
+    /// Selected packages whose size is not known
+    pub unknown: usize,
+}
+
+impl SelectionSummary {
+    pub fn new(packages: &[PackageUpdate], selected: &[String]) -> Self {
+        let (total, unknown) = model::total_size(packages, selected);
+        Self { count: selected.len(), total, unknown }
+    }
+
+    pub fn install_label(&self) -> String {
+        match self.count {
+            0 => "⬇️ Install Selected".to_string(),
+            _ => format!("⬇️ Install Selected ({})", format::size(self.total)),
+        }
+    }
+
+    pub fn tooltip(&self) -> Option<String> {
+        match self.unknown {
+            0 => None,
+            n => Some(format!("The size of {} is not known", format::plural(n as u64, "package", "packages"))),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// src/util.rs
+
+use crate::error::{Context, Error, Result};
+use std::cmp::Ordering;
+use std::process::Command;
+
+/// Bytes per unit of the SI sizes apt, GLib and snapd print, e.g. "kB"
+pub fn si_unit_factor(unit: &str) -> Option<f64> {
+    match unit {
+        "B" | "byte" | "bytes" => Some(1.0),
+        "kB" => Some(1e3),
+        "MB" => Some(1e6),
+        "GB" => Some(1e9),
+        _ => None,
+    }
+}
+
+/// Sort weight of a character outside digit runs: `~` sorts before
+/// everything, even the end of the string, and letters before other symbols
+fn char_order(c: u8) -> i32 {
+    match c {
+        b'~' => -1,
+        0 => 0,
+        c if c.is_ascii_digit() => 0,
+        c if c.is_ascii_alphabetic() => i32::from(c),
+        c => i32::from(c) + 256,
+    }
+}
+
+/// dpkg's `verrevcmp`: alternating non-digit and digit runs, the digit
+/// runs compared as numbers of any length
+fn compare_part(a: &[u8], b: &[u8]) -> Ordering {
+    let at = |s: &[u8], i: usize| s.get(i).copied().unwrap_or(0);
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let (ac, bc) = (char_order(at(a, i)), char_order(at(b, j)));
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            i += 1;
+            j += 1;
+        }
+
+        while at(a, i) == b'0' {
+            i += 1;
+        }
+        while at(b, j) == b'0' {
+            j += 1;
+        }
+        let mut first_diff = Ordering::Equal;
+        while at(a, i).is_ascii_digit() && at(b, j).is_ascii_digit() {
+            if first_diff == Ordering::Equal {
+                first_diff = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+        // The longer digit run is the larger number
+        if at(a, i).is_ascii_digit() {
+            return Ordering::Greater;
+        }
+        if at(b, j).is_ascii_digit() {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Epoch, upstream version and Debian revision of "1:2.3-4"
+fn split_version(version: &str) -> (u64, &str, &str) {
+    let version = version.trim();
+    let (epoch, rest) = match version.split_once(':') {
+        Some((epoch, rest)) if epoch.chars().all(|c| c.is_ascii_digit()) => (epoch.parse().unwrap_or(0), rest),
+        _ => (0, version),
+    };
+    match rest.rsplit_once('-') {
+        Some((upstream, revision)) => (epoch, upstream, revision),
+        None => (epoch, rest, ""),
+    }
+}
+
+/// Orders Debian versions like `dpkg --compare-versions`, so "6.1.0-18"
+/// comes after "6.1.0-9" and "1.0~rc1" before "1.0"
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_epoch, a_upstream, a_revision) = split_version(a);
+    let (b_epoch, b_upstream, b_revision) = split_version(b);
+    a_epoch.cmp(&b_epoch)
+        .then_with(|| compare_part(a_upstream.as_bytes(), b_upstream.as_bytes()))
+        .then_with(|| compare_part(a_revision.as_bytes(), b_revision.as_bytes()))
+}
+
+/// Quotes `value` as one word for `sh`, including any single quotes in it
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Downloads `url` with curl, or wget where curl is missing, giving up
+/// after `max_secs` seconds
+pub fn fetch_url(url: &str, max_secs: u32) -> Result<Vec<u8>> {
+    let max_secs = max_secs.to_string();
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", &max_secs, url])
+        .output()
+        .or_else(|_| Command::new("wget").args(["-q", "-T", &max_secs, "-O", "-", url]).output())
+        .context("Neither curl nor wget could be run")?;
+
+    if !output.status.success() {
+        return Err(Error::Network(format!("Could not download {}: {}", url, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(output.stdout)
+}
+
+/// Every string value of `key` in a JSON document, in order. Enough for
+/// flat fields such as those of the GitHub release API and the CISA catalog.
+pub fn json_string_fields(json: &str, key: &str) -> Vec<String> {
+    let quoted_key = format!("\"{}\"", key);
+    let mut values = Vec::new();
+    let mut rest = json;
+    while let Some(start) = rest.find(&quoted_key) {
+        rest = &rest[start + quoted_key.len()..];
+        let value = match rest.trim_start().strip_prefix(':').and_then(|after| after.trim_start().strip_prefix('"')) {
+            Some(value) => value,
+            None => continue,
+        };
+        let mut chars = value.chars();
+        let mut text = String::new();
+        let mut closed = false;
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => {
+                    closed = true;
+                    break;
+                }
+                '\\' => match chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some(other) => text.push(other),
+                    None => break,
+                },
+                ch => text.push(ch),
+            }
+        }
+        if closed {
+            values.push(text);
+        }
+        rest = chars.as_str();
+    }
+    values
+}
+
+/// The first string value of `key` in a JSON document
+pub fn json_string_field(json: &str, key: &str) -> Option<String> {
+    json_string_fields(json, key).into_iter().next()
+}
+
+/// `value` as a quoted JSON string
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
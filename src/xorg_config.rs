@@ -0,0 +1,154 @@
+// src/xorg_config.rs
+
+use crate::paths;
+use crate::sysroot;
+use crate::util::shell_quote;
+use std::fs;
+use std::path::PathBuf;
+
+pub const XORG_CONF: &str = "/etc/X11/xorg.conf";
+pub const XORG_CONF_DIR: &str = "/etc/X11/xorg.conf.d";
+const BACKUP_ROOT: &str = "/var/backups/meaupdater";
+
+/// X video drivers a leftover configuration may pin
+const VIDEO_DRIVERS: &[&str] = &[
+    "nvidia", "nouveau", "amdgpu", "radeon", "intel", "modesetting", "fbdev", "vesa",
+];
+
+fn state_file() -> Option<PathBuf> {
+    Some(paths::state_dir()?.join("xorg-backups"))
+}
+
+/// The `Driver "..."` values of an X configuration, comments ignored
+pub fn configured_drivers(content: &str) -> Vec<String> {
+    content.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter_map(|line| {
+            let mut words = line.splitn(2, char::is_whitespace);
+            match (words.next(), words.next()) {
+                (Some(key), Some(value)) if key.eq_ignore_ascii_case("Driver") => {
+                    Some(value.trim().trim_matches('"').to_string())
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The X driver a driver package provides, if it is a graphics driver
+pub fn xorg_driver_of(package: &str) -> Option<&'static str> {
+    let package = package.to_lowercase();
+    if package.contains("nouveau") {
+        Some("nouveau")
+    } else if package.contains("nvidia") {
+        Some("nvidia")
+    } else if package.contains("amdgpu") || package.contains("firmware-amd-graphics") {
+        Some("amdgpu")
+    } else if package.contains("radeon") || package.contains("ati") {
+        Some("radeon")
+    } else if package.contains("intel") {
+        Some("intel")
+    } else {
+        None
+    }
+}
+
+/// Whether a configuration selects another video driver than the one
+/// `package` installs, which usually keeps the new driver from loading
+pub fn conflicts_with(content: &str, package: &str) -> bool {
+    let wanted = match xorg_driver_of(package) {
+        Some(driver) => driver,
+        None => return false,
+    };
+    configured_drivers(content).iter()
+        .any(|driver| VIDEO_DRIVERS.contains(&driver.as_str()) && driver != wanted)
+}
+
+/// xorg.conf and the xorg.conf.d fragments that would get in the way of
+/// the driver from `package`, as paths on the system
+pub fn conflicting_files(package: &str) -> Vec<String> {
+    let mut candidates = vec![XORG_CONF.to_string()];
+    if let Ok(entries) = fs::read_dir(sysroot::path(XORG_CONF_DIR)) {
+        let mut fragments: Vec<String> = entries.flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".conf"))
+            .map(|name| format!("{}/{}", XORG_CONF_DIR, name))
+            .collect();
+        fragments.sort();
+        candidates.extend(fragments);
+    }
+    candidates.into_iter()
+        .filter(|path| fs::read_to_string(sysroot::path(path)).map_or(false, |content| conflicts_with(&content, package)))
+        .collect()
+}
+
+/// Where the files moved aside at `timestamp` are kept
+pub fn backup_dir(timestamp: u64) -> String {
+    format!("{}/xorg-{}", BACKUP_ROOT, timestamp)
+}
+
+/// Copies `paths` below `backup` (keeping their full path) and removes them
+pub fn move_aside_script(backup: &str, paths: &[String]) -> String {
+    let quoted: Vec<String> = paths.iter().map(|path| shell_quote(path)).collect();
+    format!(
+        "mkdir -p {backup} && cp -a --parents {files} {backup} && rm -f {files}",
+        backup = shell_quote(backup),
+        files = quoted.join(" ")
+    )
+}
+
+/// Puts the files saved by `move_aside_script` back in place
+pub fn restore_script(backup: &str) -> String {
+    format!("cp -a {}/etc/. /etc/", shell_quote(backup))
+}
+
+/// Parses the `package<TAB>backup` lines of the state file
+pub fn parse_backups(content: &str) -> Vec<(String, String)> {
+    content.lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(package, backup)| (package.to_string(), backup.to_string()))
+        .collect()
+}
+
+fn load_backups() -> Vec<(String, String)> {
+    state_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| parse_backups(&content))
+        .unwrap_or_default()
+}
+
+fn save_backups(backups: &[(String, String)]) {
+    let path = match state_file() {
+        Some(path) => path,
+        None => return,
+    };
+    if let Some(parent) = path.parent() {
+        let _ = paths::ensure_dir(parent);
+    }
+    let content: String = backups.iter().map(|(package, backup)| format!("{}\t{}\n", package, backup)).collect();
+    if let Err(e) = fs::write(&path, content) {
+        eprintln!("Could not save the X configuration backup list: {}", e);
+    }
+}
+
+/// Remembers that installing `package` moved the X configuration to `backup`
+pub fn record_backup(package: &str, backup: &str) {
+    let mut backups = load_backups();
+    backups.retain(|(p, _)| p != package);
+    backups.push((package.to_string(), backup.to_string()));
+    save_backups(&backups);
+}
+
+/// The backup to restore when `package` is removed again
+pub fn backup_for(package: &str) -> Option<String> {
+    load_backups().into_iter().find(|(p, _)| p == package).map(|(_, backup)| backup)
+}
+
+pub fn forget_backup(package: &str) {
+    let mut backups = load_backups();
+    let before = backups.len();
+    backups.retain(|(p, _)| p != package);
+    if backups.len() != before {
+        save_backups(&backups);
+    }
+}
@@ -0,0 +1,56 @@
+// src/zfs.rs
+
+use crate::sysroot;
+use std::fs;
+
+/// Explains why the default kernel is left alone on a ZFS root
+pub const SET_DEFAULT_UNSUPPORTED: &str = "This system boots from ZFS. GRUB lists boot environments there instead of \
+    plain kernel entries, so MeaUpdater does not change the default entry.\n\n\
+    Pick the kernel under \"Advanced options\" in the boot menu instead.";
+
+/// The root file system is a ZFS dataset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZfsRoot {
+    /// e.g. "rpool/ROOT/ubuntu_6x1q2b"
+    pub dataset: String,
+    /// zsys manages boot environments and can save system states
+    pub zsys: bool,
+}
+
+/// The ZFS dataset mounted on `/` in `/proc/mounts` output. A later mount
+/// over `/` hides the earlier ones.
+pub fn root_dataset(mounts: &str) -> Option<String> {
+    mounts.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [device, "/", fstype, ..] => Some((*device, *fstype)),
+                _ => None,
+            }
+        })
+        .last()
+        .filter(|(_, fstype)| *fstype == "zfs")
+        .map(|(device, _)| device.to_string())
+}
+
+pub fn detect_from(mounts: &str, zsys_installed: bool) -> Option<ZfsRoot> {
+    root_dataset(mounts).map(|dataset| ZfsRoot { dataset, zsys: zsys_installed })
+}
+
+/// Whether `/` is on ZFS, and whether zsys runs it
+pub fn detect() -> Option<ZfsRoot> {
+    let mounts = fs::read_to_string(sysroot::path("/proc/mounts")).ok()?;
+    let zsys = ["/usr/sbin/zsysctl", "/sbin/zsysctl", "/usr/bin/zsysctl"].iter().any(|path| sysroot::path(path).exists());
+    detect_from(&mounts, zsys)
+}
+
+/// Name of the system state saved before MeaUpdater removes a kernel
+pub fn state_id(timestamp: u64) -> String {
+    format!("meaupdater-{}", timestamp)
+}
+
+/// Saves the whole system as a zsys state, so a kernel removal can be
+/// reverted from the "History" entries of the boot menu
+pub fn save_state_command(id: &str) -> Vec<String> {
+    vec!["zsysctl".to_string(), "save".to_string(), id.to_string(), "--system".to_string()]
+}
@@ -0,0 +1,110 @@
+// tests/anomalies_tests.rs
+use meaupdater::anomalies::{detect, list_file_prefix, parse_release_origin, release_signed, same_repository, Anomaly};
+use meaupdater::apt::{PolicyCandidate, VersionChange};
+
+fn change(from: &str, to: &str) -> VersionChange {
+    VersionChange { from: Some(from.to_string()), to: Some(to.to_string()) }
+}
+
+fn candidate(version: &str, origin: &str) -> PolicyCandidate {
+    PolicyCandidate { version: version.to_string(), origin: Some(origin.to_string()) }
+}
+
+fn no_release_files(_: &str) -> Option<String> {
+    None
+}
+
+#[test]
+fn maps_origins_to_list_files() {
+    assert_eq!(
+        list_file_prefix("deb.debian.org/debian bookworm-security/main").as_deref(),
+        Some("deb.debian.org_debian_dists_bookworm-security")
+    );
+    assert_eq!(
+        list_file_prefix("deb.nodesource.com/node_20.x nodistro/main").as_deref(),
+        Some("deb.nodesource.com_node%5f20.x_dists_nodistro")
+    );
+    assert_eq!(list_file_prefix("example.org/repo ./"), None);
+}
+
+#[test]
+fn tells_signed_from_unsigned_indexes() {
+    let files: Vec<String> = [
+        "deb.debian.org_debian_dists_bookworm_InRelease",
+        "mirror.local_debian_dists_stable_Release",
+        "old.example.org_apt_dists_focal_Release",
+        "old.example.org_apt_dists_focal_Release.gpg",
+    ].iter().map(|f| f.to_string()).collect();
+    assert_eq!(release_signed(&files, "deb.debian.org/debian bookworm/main"), Some(true));
+    assert_eq!(release_signed(&files, "mirror.local/debian stable/main"), Some(false));
+    assert_eq!(release_signed(&files, "old.example.org/apt focal/main"), Some(true));
+    assert_eq!(release_signed(&files, "ppa.example.org/x jammy/main"), None);
+}
+
+#[test]
+fn reports_downgrades_origin_changes_and_unsigned_sources() {
+    let ordinary = detect(
+        &change("1.0", "1.1"),
+        Some(&candidate("1.1", "deb.debian.org/debian bookworm/main")),
+        Some("deb.debian.org/debian bookworm/main"),
+        &no_release_files,
+        Some(true),
+        false,
+    );
+    assert!(ordinary.is_empty());
+
+    let suspicious = detect(
+        &change("1.1", "1.0"),
+        Some(&candidate("1.0", "mirror.local/debian stable/main")),
+        Some("deb.debian.org/debian bookworm/main"),
+        &no_release_files,
+        Some(false),
+        true,
+    );
+    assert_eq!(suspicious.len(), 3);
+    assert_eq!(suspicious[0], Anomaly::Downgrade { from: "1.1".to_string(), to: "1.0".to_string() });
+    assert!(matches!(suspicious[1], Anomaly::OriginChanged { .. }));
+    assert!(suspicious[2].message().contains("not signed"));
+}
+
+#[test]
+fn origin_checks_need_the_candidate_version() {
+    // An explicitly requested version may not come from the candidate's repository
+    let anomalies = detect(
+        &change("1.0", "0.9"),
+        Some(&candidate("1.1", "mirror.local/debian stable/main")),
+        Some("deb.debian.org/debian bookworm/main"),
+        &no_release_files,
+        Some(false),
+        false,
+    );
+    assert!(anomalies.is_empty());
+}
+
+#[test]
+fn security_updates_of_the_same_archive_are_not_origin_changes() {
+    // The URI stays and only the suite changes
+    let anomalies = detect(
+        &change("1.0", "1.0+deb12u1"),
+        Some(&candidate("1.0+deb12u1", "deb.debian.org/debian bookworm-security/main")),
+        Some("deb.debian.org/debian bookworm-updates/main"),
+        &no_release_files,
+        Some(true),
+        false,
+    );
+    assert!(anomalies.is_empty());
+
+    // security.debian.org is another host, but its Release file names the same origin
+    let debian = |origin: &str| (!origin.starts_with("mirror.local")).then(|| "Debian".to_string());
+    assert!(same_repository("deb.debian.org/debian bookworm/main", "security.debian.org/debian-security bookworm-security/main", &debian));
+    assert!(!same_repository("deb.debian.org/debian bookworm/main", "security.debian.org/debian-security bookworm-security/main", &no_release_files));
+    let other = |origin: &str| Some(if origin.starts_with("ppa") { "LP-PPA-x" } else { "Debian" }.to_string());
+    assert!(!same_repository("deb.debian.org/debian bookworm/main", "ppa.example.org/x bookworm/main", &other));
+}
+
+#[test]
+fn reads_the_origin_of_release_files() {
+    let release = "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA512\n\nOrigin: Debian\nLabel: Debian-Security\nSuite: stable-security\n";
+    assert_eq!(parse_release_origin(release).as_deref(), Some("Debian"));
+    assert_eq!(parse_release_origin("Label: x\n"), None);
+}
@@ -0,0 +1,94 @@
+// tests/app_state_tests.rs
+mod common;
+
+use meaupdater::app_state::{AppState, BusyState, Operation};
+use meaupdater::model::UpdateType;
+use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use common::update;
+
+#[test]
+fn only_one_operation_at_a_time() {
+    let busy = BusyState::new();
+    assert!(!busy.is_busy());
+
+    assert!(busy.try_begin(Operation::Checking));
+    assert!(!busy.try_begin(Operation::Installing));
+    assert_eq!(busy.current(), Operation::Checking);
+
+    busy.finish();
+    assert!(busy.try_begin(Operation::Installing));
+    assert_eq!(busy.current(), Operation::Installing);
+}
+
+#[test]
+fn idle_is_not_an_operation() {
+    let busy = BusyState::new();
+    assert!(!busy.try_begin(Operation::Idle));
+    assert!(!busy.is_busy());
+}
+
+#[test]
+fn listeners_see_every_transition() {
+    let busy = BusyState::new();
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let seen_clone = seen.clone();
+    busy.connect_changed(move |op| seen_clone.borrow_mut().push(op));
+
+    busy.try_begin(Operation::Checking);
+    busy.try_begin(Operation::Installing);
+    busy.finish();
+    busy.finish();
+
+    assert_eq!(*seen.borrow(), vec![Operation::Idle, Operation::Checking, Operation::Idle]);
+}
+
+#[test]
+fn update_count_follows_the_package_list() {
+    let state = AppState::new();
+    assert_eq!(state.update_count(), 0);
+
+    state.set_packages(vec![update("curl", UpdateType::Software, None), update("vim", UpdateType::Software, None)]);
+    assert_eq!(state.update_count(), 2);
+    assert_eq!(state.package_names(), vec!["curl", "vim"]);
+
+    state.set_packages(vec![update("vim", UpdateType::Software, None)]);
+    assert_eq!(state.update_count(), 1);
+}
+
+#[test]
+fn queue_resume_is_offered_once() {
+    let state = AppState::new();
+    assert!(state.take_queue_resume_offer());
+    assert!(!state.take_queue_resume_offer());
+}
+
+#[test]
+fn advisory_fixes_are_looked_up_by_package() {
+    let state = AppState::new();
+    let mut fixes = HashMap::new();
+    fixes.insert("openssl".to_string(), vec!["DSA-5000-1".to_string()]);
+    state.set_advisory_fixes(fixes);
+
+    assert_eq!(state.advisories_fixed_by("openssl"), Some(vec!["DSA-5000-1".to_string()]));
+    assert_eq!(state.advisories_fixed_by("curl"), None);
+}
+
+#[test]
+fn busy_state_is_shared() {
+    let state = AppState::new();
+    assert!(state.busy().try_begin(Operation::Checking));
+    assert!(state.busy().is_busy());
+}
+
+#[test]
+fn kernel_set_is_kept_back_as_a_whole() {
+    let state = AppState::new();
+    state.set_packages(vec![update("linux-image-amd64", UpdateType::Software, None), update("linux-headers-amd64", UpdateType::Software, None), update("bash", UpdateType::Software, None)]);
+    assert_eq!(state.kernel_set(), vec!["linux-image-amd64", "linux-headers-amd64"]);
+
+    state.set_kept_back(HashMap::from([("linux-headers-amd64".to_string(), "phased".to_string())]));
+    assert_eq!(state.kept_back_reason("linux-image-amd64").as_deref(), Some("phased"));
+    assert_eq!(state.kept_back_reason("bash"), None);
+}
@@ -1,6 +1,9 @@
 // tests/apt_tests.rs
-use meaupdater::apt::parse_apt_list_output;
+mod common;
+
+use meaupdater::apt::{parse_apt_list_output, parse_policy, PolicyCandidate};
 use meaupdater::model::{PackageUpdate, UpdateType};
+use common::update;
 
 const SAMPLE: &str = r#"Listing...
 bash/stable 5.1-2+deb11u1 amd64 [upgradable from: 5.1-2]
@@ -25,6 +28,9 @@ fn parse_sample() {
             current_version: "5.1-2".into(),
             new_version: "5.1-2+deb11u1".into(),
             update_type: UpdateType::Software,
+            from_security: false,
+            size: None,
+            first_seen: None,
         }
     );
     assert_eq!(
@@ -34,6 +40,394 @@ fn parse_sample() {
             current_version: "1.1.1d-0+deb10u1".into(),
             new_version: "1.1.1d-0+deb10u6".into(),
             update_type: UpdateType::Security,
+            from_security: true,
+            size: None,
+            first_seen: None,
         }
     );
 }
+
+#[test]
+fn classify_firmware() {
+    use meaupdater::apt::determine_update_type;
+
+    assert_eq!(determine_update_type("firmware-iwlwifi", "firmware-iwlwifi/stable"), UpdateType::Firmware);
+    assert_eq!(determine_update_type("linux-firmware", "linux-firmware/noble-updates"), UpdateType::Firmware);
+    assert_eq!(determine_update_type("intel-microcode", "intel-microcode/stable-security"), UpdateType::Firmware);
+    assert_eq!(determine_update_type("fwupd", "fwupd/stable"), UpdateType::Firmware);
+    assert_eq!(determine_update_type("linux-image-amd64", "linux-image-amd64/stable"), UpdateType::Kernel);
+}
+
+#[test]
+fn transaction_argv() {
+    use meaupdater::apt::Transaction;
+
+    let pkgs = vec!["linux-image-6.1.0-18-amd64".to_string()];
+    assert_eq!(
+        Transaction::purge(&pkgs).then_autoremove().argv(),
+        vec!["apt-get", "purge", "-y", "-o", "APT::Status-Fd=1", "linux-image-6.1.0-18-amd64"]
+    );
+
+    let pkgs = vec!["bash".to_string(), "openssl".to_string()];
+    let upgrade = Transaction::install(&pkgs).only_upgrade();
+    assert_eq!(upgrade.argv(), vec!["apt-get", "install", "-y", "-o", "APT::Status-Fd=1", "--only-upgrade", "bash", "openssl"]);
+    assert_eq!(upgrade.summary(), "install bash, openssl");
+
+    let pkgs = vec!["nvidia-driver".to_string()];
+    let fallback = vec!["xserver-xorg-video-nouveau".to_string()];
+    assert_eq!(
+        Transaction::purge(&pkgs).ensure_installed(&fallback).argv(),
+        vec!["apt-get", "purge", "-y", "-o", "APT::Status-Fd=1", "nvidia-driver", "xserver-xorg-video-nouveau+"]
+    );
+}
+
+#[test]
+fn parse_simulated_run() {
+    use meaupdater::apt::parse_simulation;
+
+    let output = "NOTE: This is only a simulation!\n\
+                  Reading package lists...\n\
+                  Remv linux-image-amd64 [6.1.76-1]\n\
+                  Purg linux-image-6.1.0-18-amd64 [6.1.76-1]\n\
+                  Inst bash [5.1-2] (5.1-2+deb11u1 Debian:11.9/stable [amd64])\n\
+                  Inst libfoo1 (1.0-1 Debian:11.9/stable [amd64])\n\
+                  Conf bash (5.1-2+deb11u1 Debian:11.9/stable [amd64])\n";
+    let plan = parse_simulation(output);
+    assert_eq!(plan.upgrade, vec!["bash"]);
+    assert_eq!(plan.install, vec!["libfoo1"]);
+    assert_eq!(plan.remove, vec!["linux-image-amd64", "linux-image-6.1.0-18-amd64"]);
+
+    let requested = vec!["linux-image-6.1.0-18-amd64".to_string(), "bash".to_string()];
+    assert_eq!(plan.extra_packages(&requested), vec!["libfoo1", "linux-image-amd64"]);
+}
+
+#[test]
+fn parse_status_fd_lines() {
+    use meaupdater::apt::{parse_status_line, StatusLine};
+
+    assert_eq!(
+        parse_status_line("pmstatus:bash:42.8571:Preparing to configure bash (amd64)"),
+        Some(StatusLine::Install(42.8571, "Preparing to configure bash (amd64)".to_string()))
+    );
+    assert_eq!(
+        parse_status_line("dlstatus:1:9.5:Retrieving file 1 of 3"),
+        Some(StatusLine::Download(9.5, "Retrieving file 1 of 3".to_string()))
+    );
+    // Multiarch names and descriptions contain colons of their own
+    assert_eq!(
+        parse_status_line("pmstatus:libc6:amd64:10:Installing libc6:amd64"),
+        Some(StatusLine::Install(10.0, "Installing libc6:amd64".to_string()))
+    );
+    assert_eq!(parse_status_line("Setting up bash (5.1-2+deb11u1) ..."), None);
+}
+
+#[test]
+fn policy_candidates_with_origin() {
+    let output = "nvidia-driver:\n  Installed: (none)\n  Candidate: 535.183.01-1\n  Version table:\n     535.183.01-1 500\n        500 http://deb.debian.org/debian bookworm/non-free amd64 Packages\n     525.147.05-4~deb12u1 100\n        100 http://deb.debian.org/debian bookworm-backports/non-free amd64 Packages\n\
+firmware-realtek:\n  Installed: 20230210-5\n  Candidate: 20230210-5\n  Version table:\n *** 20230210-5 500\n        500 https://deb.debian.org/debian/ bookworm/non-free-firmware amd64 Packages\n        100 /var/lib/dpkg/status\n\
+local-only:\n  Installed: 1.0\n  Candidate: 1.0\n  Version table:\n *** 1.0 100\n        100 /var/lib/dpkg/status\n\
+gone:\n  Installed: (none)\n  Candidate: (none)\n  Version table:\n";
+    let candidates = parse_policy(output);
+    assert_eq!(candidates["nvidia-driver"], PolicyCandidate {
+        version: "535.183.01-1".to_string(),
+        origin: Some("deb.debian.org/debian bookworm/non-free".to_string()),
+    });
+    assert_eq!(candidates["firmware-realtek"].origin.as_deref(), Some("deb.debian.org/debian bookworm/non-free-firmware"));
+    assert_eq!(candidates["local-only"].origin, None);
+    assert!(!candidates.contains_key("gone"));
+}
+
+#[test]
+fn simulation_keeps_versions_and_summary() {
+    use meaupdater::apt::{change_summary, parse_simulation, VersionChange};
+
+    let output = "Inst bash [5.1-2] (5.1-2+deb11u1 Debian:11.9/stable [amd64])\n\
+                  Inst libfoo1 (1.0-1 Debian:11.9/stable [amd64])\n\
+                  Remv oldlib [0.9-3]\n";
+    let plan = parse_simulation(output);
+    assert_eq!(plan.versions["bash"], VersionChange { from: Some("5.1-2".to_string()), to: Some("5.1-2+deb11u1".to_string()) });
+    assert_eq!(plan.versions["libfoo1"], VersionChange { from: None, to: Some("1.0-1".to_string()) });
+    assert_eq!(plan.versions["oldlib"], VersionChange { from: Some("0.9-3".to_string()), to: None });
+    assert_eq!(plan.summary(), "1 upgraded, 1 newly installed, 1 removed");
+    assert_eq!(change_summary(3, 0, 0), "3 upgraded");
+    assert_eq!(change_summary(0, 0, 0), "no changes");
+}
+
+#[test]
+fn package_sizes_are_bytes() {
+    use meaupdater::apt::parse_package_sizes;
+
+    let output = "Package: bash\nVersion: 5.2.15-2+b7\nInstalled-Size: 7164 kB\nSize: 1490952\n\n\
+                  Package: localpkg\nVersion: 1.0\nInstalled-Size: 12\n\n\
+                  Package: bash\nVersion: 5.2.15-2\nSize: 1\n";
+    let sizes = parse_package_sizes(output);
+    assert_eq!(sizes["bash"], 1_490_952);
+    // No download size, so the installed size is the best estimate
+    assert_eq!(sizes["localpkg"], 12 * 1024);
+    assert_eq!(sizes.len(), 2);
+}
+
+#[test]
+fn full_upgrade_argv() {
+    use meaupdater::apt::Transaction;
+
+    let upgrade = Transaction::full_upgrade();
+    assert_eq!(upgrade.argv(), vec!["apt-get", "dist-upgrade", "-y", "-o", "APT::Status-Fd=1"]);
+    assert_eq!(upgrade.summary(), "run a full upgrade");
+}
+
+const UPGRADE_SIMULATION: &str = "Reading package lists...
+Building dependency tree...
+Calculating upgrade...
+The following packages have been kept back:
+  linux-image-amd64 mesa-vulkan-drivers
+The following upgrades have been deferred due to phasing:
+  gnome-shell
+The following packages will be upgraded:
+  bash
+1 upgraded, 0 newly installed, 0 to remove and 3 not upgraded.
+Inst bash [5.2.15-2+b2] (5.2.15-2+b7 Debian:12.5/stable [amd64])
+";
+
+#[test]
+fn parse_kept_back_and_phased_sections() {
+    use meaupdater::apt::{parse_kept_back, parse_phased};
+
+    assert_eq!(parse_kept_back(UPGRADE_SIMULATION), vec!["linux-image-amd64", "mesa-vulkan-drivers"]);
+    assert_eq!(parse_phased(UPGRADE_SIMULATION), vec!["gnome-shell"]);
+    assert!(parse_kept_back("0 upgraded, 0 newly installed, 0 to remove and 0 not upgraded.\n").is_empty());
+}
+
+#[test]
+fn kept_back_reason_names_new_packages() {
+    use meaupdater::apt::kept_back_reason;
+
+    let output = "Inst linux-image-6.1.0-21-amd64 (6.1.90-1 Debian:12.6/stable [amd64])
+Inst linux-image-amd64 [6.1.85-1] (6.1.90-1 Debian:12.6/stable [amd64])
+";
+    assert_eq!(
+        kept_back_reason("linux-image-amd64", output),
+        "The new version needs new packages: linux-image-6.1.0-21-amd64"
+    );
+}
+
+#[test]
+fn kept_back_reason_explains_unmet_dependencies() {
+    use meaupdater::apt::{kept_back_reason, parse_unmet_dependencies};
+
+    let output = "Some packages could not be installed.
+The following packages have unmet dependencies:
+ mesa-vulkan-drivers : Depends: libllvm15 (>= 1:15.0.6) but it is not going to be installed
+                       Breaks: libdrm2 (< 2.4.114)
+E: Unable to correct problems, you have held broken packages.
+";
+    assert_eq!(
+        parse_unmet_dependencies(output),
+        vec![
+            "Depends: libllvm15 (>= 1:15.0.6) but it is not going to be installed",
+            "Breaks: libdrm2 (< 2.4.114)",
+        ]
+    );
+    assert!(kept_back_reason("mesa-vulkan-drivers", output).starts_with("Its dependencies cannot be met right now:"));
+}
+
+#[test]
+fn parse_fetch_lines() {
+    use meaupdater::apt::{parse_apt_size, parse_fetch_line, parse_fetched_summary};
+
+    assert_eq!(parse_apt_size("1,512 kB"), Some(1_512_000));
+    assert_eq!(parse_apt_size("2.5 MB"), Some(2_500_000));
+    assert_eq!(parse_apt_size("312 B"), Some(312));
+    assert_eq!(parse_apt_size("12 parsecs"), None);
+
+    assert_eq!(
+        parse_fetch_line("Get:1 http://deb.debian.org/debian bookworm/main amd64 bash amd64 5.2.15-2+b7 [1,512 kB]"),
+        Some(("deb.debian.org".to_string(), Some(1_512_000)))
+    );
+    assert_eq!(
+        parse_fetch_line("Get:2 https://mirror.example.org:8443/debian bookworm InRelease"),
+        Some(("mirror.example.org".to_string(), None))
+    );
+    assert_eq!(parse_fetch_line("Hit:1 http://deb.debian.org/debian bookworm InRelease"), None);
+
+    assert_eq!(parse_fetched_summary("Fetched 12.3 MB in 3s (4,100 kB/s)"), Some(4_100_000));
+    assert_eq!(parse_fetched_summary("Fetched 0 B in 0s (0 B/s)"), Some(0));
+}
+
+#[test]
+fn download_meter_speed() {
+    use meaupdater::apt::DownloadMeter;
+
+    let mut meter = DownloadMeter::default();
+    meter.add(0, 1_000_000);
+    assert_eq!(meter.speed(), None);
+    meter.add(500, 1_000_000);
+    assert_eq!(meter.speed(), None);
+    // Two files finished within two seconds
+    meter.add(2_000, 4_000_000);
+    assert_eq!(meter.speed(), Some(1_000_000));
+    // Old samples drop out of the window
+    meter.add(9_000, 1_000);
+    assert_eq!(meter.speed(), None);
+}
+
+#[test]
+fn progress_clock_estimates_time_left() {
+    use meaupdater::apt::ProgressClock;
+
+    let mut clock = ProgressClock::default();
+    clock.add(0, 0.1);
+    clock.add(1_000, 0.12);
+    assert_eq!(clock.remaining(), None);
+    // 10% in 4 seconds leaves 80% for 32 seconds
+    clock.add(4_000, 0.2);
+    assert_eq!(clock.remaining(), Some(32));
+    // Dropping back starts over
+    clock.add(5_000, 0.1);
+    assert_eq!(clock.remaining(), None);
+    clock.add(9_000, 0.1);
+    assert_eq!(clock.remaining(), None);
+}
+
+#[test]
+fn package_requests() {
+    use meaupdater::apt::{PackageRequest, VersionSelector};
+
+    assert_eq!(PackageRequest::parse("bash").unwrap(), PackageRequest::candidate("bash"));
+    assert_eq!(PackageRequest::parse("bash=5.2.15-2+b7").unwrap().selector, VersionSelector::Version("5.2.15-2+b7".to_string()));
+    assert_eq!(PackageRequest::parse("libc6:i386=2.36-9").unwrap().to_arg(), "libc6:i386=2.36-9");
+    assert_eq!(PackageRequest::parse("linux-image-amd64/bookworm-backports").unwrap().to_arg(), "linux-image-amd64/bookworm-backports");
+
+    for bad in ["-o", "Bash", "bash=", "bash=latest", "bash/", "bash/../x", "~nbash", "b"] {
+        assert!(PackageRequest::parse(bad).is_err(), "{} should be refused", bad);
+    }
+}
+
+#[test]
+fn version_requests_allow_downgrades() {
+    use meaupdater::apt::{PackageRequest, Transaction};
+
+    let downgrade = Transaction::install_requests(&[PackageRequest::version("bash", "5.2.15-2")]);
+    assert_eq!(downgrade.argv(), vec!["apt-get", "install", "-y", "-o", "APT::Status-Fd=1", "--allow-downgrades", "bash=5.2.15-2"]);
+    assert_eq!(downgrade.summary(), "install bash=5.2.15-2");
+
+    let backport = Transaction::install_requests(&[PackageRequest::release("firmware-misc-nonfree", "bookworm-backports")]);
+    assert!(!backport.argv().contains(&"--allow-downgrades".to_string()));
+    assert_eq!(backport.packages(), ["firmware-misc-nonfree/bookworm-backports"]);
+}
+
+#[test]
+fn checks_requested_versions_against_the_policy() {
+    use meaupdater::apt::{check_available, parse_version_table, PackageRequest};
+
+    let output = "nvidia-driver:\n  Installed: 525.147.05-4~deb12u1\n  Candidate: 535.183.01-1\n  Version table:\n     535.183.01-1 500\n        500 http://deb.debian.org/debian bookworm/non-free amd64 Packages\n\
+                  *** 525.147.05-4~deb12u1 100\n        100 http://deb.debian.org/debian bookworm-backports/non-free amd64 Packages\n        100 /var/lib/dpkg/status\n\
+                  local-only:\n  Installed: 1.0\n  Candidate: 1.0\n  Version table:\n *** 1.0 100\n        100 /var/lib/dpkg/status\n";
+    let tables = parse_version_table(output);
+    let versions: Vec<&str> = tables["nvidia-driver"].iter().map(|v| v.version.as_str()).collect();
+    assert_eq!(versions, vec!["535.183.01-1", "525.147.05-4~deb12u1"]);
+    assert_eq!(tables["nvidia-driver"][1].releases, vec!["bookworm-backports"]);
+    assert!(!tables.contains_key("local-only"));
+
+    assert!(check_available(&[PackageRequest::version("nvidia-driver", "525.147.05-4~deb12u1")], &tables).is_ok());
+    assert!(check_available(&[PackageRequest::release("nvidia-driver", "bookworm-backports")], &tables).is_ok());
+    assert!(check_available(&[PackageRequest::candidate("local-only")], &tables).is_ok());
+
+    let missing = check_available(&[PackageRequest::version("nvidia-driver", "470.0-1")], &tables).unwrap_err().to_string();
+    assert!(missing.contains("Available versions: 535.183.01-1, 525.147.05-4~deb12u1"), "{}", missing);
+    let wrong_release = check_available(&[PackageRequest::release("nvidia-driver", "trixie")], &tables).unwrap_err().to_string();
+    assert!(wrong_release.contains("It is offered by: bookworm, bookworm-backports"), "{}", wrong_release);
+    let not_downloadable = check_available(&[PackageRequest::version("local-only", "0.9")], &tables).unwrap_err().to_string();
+    assert!(not_downloadable.contains("no repository offers local-only"), "{}", not_downloadable);
+}
+
+#[test]
+fn reads_versions_from_flat_file_repositories() {
+    use meaupdater::apt::{check_available, parse_version_table, PackageRequest};
+
+    let output = "in-house-tool:\n  Installed: 1.2\n  Candidate: 1.3\n  Version table:\n     1.3 500\n        500 file:/srv/repo ./ Packages\n\
+                  *** 1.2 100\n        100 /var/lib/dpkg/status\n";
+    let tables = parse_version_table(output);
+    assert_eq!(tables["in-house-tool"].len(), 1);
+    assert_eq!(tables["in-house-tool"][0].version, "1.3");
+    assert!(tables["in-house-tool"][0].releases.is_empty());
+    assert!(check_available(&[PackageRequest::version("in-house-tool", "1.3")], &tables).is_ok());
+}
+
+#[test]
+fn plain_transactions_go_through_packagekit() {
+    use meaupdater::apt::{PackageRequest, Transaction};
+    use meaupdater::packagekit::Action;
+
+    let pkgs = vec!["firefox-esr".to_string()];
+    assert_eq!(Transaction::install(&pkgs).packagekit_action(), Some(Action::Install));
+    assert_eq!(Transaction::install(&pkgs).only_upgrade().packagekit_action(), Some(Action::Update));
+    assert_eq!(Transaction::remove(&pkgs).then_autoremove().packagekit_action(), Some(Action::Remove { autoremove: true }));
+
+    // Everything PackageKit cannot express stays with apt-get
+    assert_eq!(Transaction::purge(&pkgs).packagekit_action(), None);
+    assert_eq!(Transaction::full_upgrade().packagekit_action(), None);
+    assert_eq!(Transaction::install(&["/tmp/meaupdater.deb".to_string()]).packagekit_action(), None);
+    assert_eq!(Transaction::install(&["libc6:i386".to_string()]).packagekit_action(), None);
+    assert_eq!(Transaction::install(&pkgs).then_autoremove().packagekit_action(), None);
+    assert_eq!(Transaction::install(&pkgs).run_after(&["modprobe", "nvidia"]).packagekit_action(), None);
+    assert_eq!(Transaction::install_requests(&[PackageRequest::version("bash", "5.2.15-2")]).packagekit_action(), None);
+}
+
+#[test]
+fn every_privileged_step_runs_in_one_script() {
+    use meaupdater::apt::Transaction;
+
+    let removal = Transaction::purge(&["nvidia-driver".to_string()])
+        .run_before(&["modprobe", "-r", "nvidia"])
+        .then_autoremove()
+        .run_after(&["apt-get", "autoclean"]);
+    assert_eq!(
+        removal.commands(true),
+        vec![
+            "apt-get update",
+            "modprobe -r nvidia",
+            "apt-get purge -y -o APT::Status-Fd=1 nvidia-driver",
+            "apt-get autoremove -y -o APT::Status-Fd=1",
+            "apt-get autoclean",
+        ]
+    );
+    assert_eq!(Transaction::remove(&["less".to_string()]).commands(false), vec!["apt-get remove -y -o APT::Status-Fd=1 less"]);
+}
+
+#[test]
+fn security_comes_from_the_archive_not_the_name() {
+    use meaupdater::apt::{determine_update_type, from_security_archive, is_security_suite};
+
+    assert_eq!(determine_update_type("curl", "curl/stable"), UpdateType::Software);
+    assert_eq!(determine_update_type("libfoo1", "libfoo1/noble-updates,noble-security"), UpdateType::Security);
+    assert_eq!(determine_update_type("bash", "bash/stable-security"), UpdateType::Security);
+    assert!(is_security_suite("bookworm-security/main"));
+    assert!(!is_security_suite("bookworm-updates"));
+    assert!(!is_security_suite("security-tools"));
+    // Kernels and microcode keep their own group but still count as security fixes
+    assert_eq!(determine_update_type("linux-image-amd64", "linux-image-amd64/stable-security"), UpdateType::Kernel);
+    assert!(from_security_archive("linux-image-amd64/stable-security"));
+    assert!(from_security_archive("intel-microcode/noble-updates,noble-security"));
+    assert!(!from_security_archive("intel-microcode/stable"));
+}
+
+#[test]
+fn security_updates_from_policy() {
+    use meaupdater::apt::parse_security_updates;
+
+    let output = "libssl3:\n  Installed: 3.0.13-0ubuntu3\n  Candidate: 3.0.13-0ubuntu3.4\n  Version table:\n     3.0.13-0ubuntu3.4 500\n        500 http://archive.ubuntu.com/ubuntu noble-updates/main amd64 Packages\n        500 http://security.ubuntu.com/ubuntu noble-security/main amd64 Packages\n *** 3.0.13-0ubuntu3 500\n        500 http://archive.ubuntu.com/ubuntu noble/main amd64 Packages\n        100 /var/lib/dpkg/status\n\
+vim:\n  Installed: 2:9.1.0016-1ubuntu7\n  Candidate: 2:9.1.0016-1ubuntu7.2\n  Version table:\n     2:9.1.0016-1ubuntu7.2 500\n        500 http://archive.ubuntu.com/ubuntu noble-updates/main amd64 Packages\n     2:9.1.0016-1ubuntu7.1 500\n        500 http://security.ubuntu.com/ubuntu noble-security/main amd64 Packages\n";
+    let packages = [
+        PackageUpdate { new_version: "3.0.13-0ubuntu3.4".to_string(), ..update("libssl3", UpdateType::Software, None) },
+        PackageUpdate { new_version: "2:9.1.0016-1ubuntu7.2".to_string(), ..update("vim", UpdateType::Software, None) },
+    ];
+    let security = parse_security_updates(output, &packages);
+    assert!(security.contains("libssl3"));
+    // Only an older version is a security fix
+    assert!(!security.contains("vim"));
+
+    // The version `apt list` reported counts, not the candidate
+    let security = parse_security_updates(output, &[PackageUpdate { new_version: "2:9.1.0016-1ubuntu7.1".to_string(), ..update("vim", UpdateType::Software, None) }]);
+    assert!(security.contains("vim"));
+}
@@ -0,0 +1,127 @@
+// tests/boot_layout_tests.rs
+use meaupdater::boot_layout::{self, BootLayout, KernelOperation, FALLBACK_KERNEL_BYTES};
+use meaupdater::boot_platform::BootPlatform;
+
+const MOUNTS: &str = "\
+/dev/mapper/vg-root / ext4 rw,relatime 0 0
+/dev/nvme0n1p2 /boot ext4 rw,relatime 0 0
+/dev/nvme0n1p1 /boot/efi vfat rw,relatime 0 0
+";
+
+const FSTAB: &str = "\
+# /etc/fstab
+/dev/mapper/vg-root /         ext4 errors=remount-ro 0 1
+UUID=1234           /boot     ext4 defaults          0 2
+UUID=ABCD           /boot/efi vfat umask=0077        0 1
+UUID=5678           /mnt/data ext4 noauto            0 2
+";
+
+fn layout() -> BootLayout {
+    BootLayout {
+        separate_boot: true,
+        boot_stack: vec!["part".to_string(), "disk".to_string()],
+        root_stack: vec!["lvm".to_string(), "crypt".to_string(), "part".to_string(), "disk".to_string()],
+        initramfs_cryptsetup: true,
+        boot_free: Some(500_000_000),
+        ..BootLayout::default()
+    }
+}
+
+#[test]
+fn mount_source_takes_the_last_mount() {
+    assert_eq!(boot_layout::mount_source(MOUNTS, "/boot"), Some(("/dev/nvme0n1p2".to_string(), "ext4".to_string())));
+    assert_eq!(boot_layout::mount_source(MOUNTS, "/home"), None);
+    let remounted = format!("{}tmpfs / tmpfs rw 0 0\n", MOUNTS);
+    assert_eq!(boot_layout::mount_source(&remounted, "/").unwrap().1, "tmpfs");
+}
+
+#[test]
+fn fstab_skips_comments_and_noauto() {
+    assert!(boot_layout::fstab_mounts(FSTAB, "/boot"));
+    assert!(boot_layout::fstab_mounts(FSTAB, "/boot/efi"));
+    assert!(!boot_layout::fstab_mounts(FSTAB, "/mnt/data"));
+    assert!(!boot_layout::fstab_mounts("# UUID=1 /boot ext4 defaults 0 2\n", "/boot"));
+}
+
+#[test]
+fn parses_lsblk_df_and_grub_settings() {
+    assert_eq!(boot_layout::parse_lsblk_types("lvm\n  crypt\npart\ndisk\n"), vec!["lvm", "crypt", "part", "disk"]);
+    assert_eq!(boot_layout::parse_df_available("    Avail\n 523239424\n"), Some(523_239_424));
+    assert_eq!(boot_layout::parse_df_available("Avail\n"), None);
+    assert!(boot_layout::grub_cryptodisk("GRUB_TIMEOUT=5\nGRUB_ENABLE_CRYPTODISK=\"y\"\n"));
+    assert!(!boot_layout::grub_cryptodisk("GRUB_ENABLE_CRYPTODISK=y\nGRUB_ENABLE_CRYPTODISK=n\n"));
+    assert!(!boot_layout::grub_cryptodisk("#GRUB_ENABLE_CRYPTODISK=y\n"));
+}
+
+#[test]
+fn plain_boot_with_encrypted_root_has_no_warnings() {
+    let layout = layout();
+    assert!(layout.encrypted_root());
+    assert!(!layout.encrypted_boot());
+    assert!(layout.grubenv_writable());
+    for operation in [KernelOperation::Install, KernelOperation::Remove, KernelOperation::SetDefault] {
+        assert!(layout.warnings(operation, 200_000_000).is_empty());
+    }
+}
+
+#[test]
+fn encrypted_boot_needs_cryptodisk() {
+    let mut layout = layout();
+    layout.separate_boot = false;
+    layout.boot_stack = layout.root_stack.clone();
+    assert!(layout.encrypted_boot());
+    assert!(!layout.grubenv_writable());
+
+    let warnings = layout.warnings(KernelOperation::SetDefault, 0);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("GRUB_ENABLE_CRYPTODISK=y"));
+
+    layout.grub_cryptodisk = true;
+    assert!(layout.warnings(KernelOperation::SetDefault, 0).is_empty());
+}
+
+#[test]
+fn install_checks_space_and_the_cryptsetup_hook() {
+    let mut layout = layout();
+    layout.boot_free = Some(100_000_000);
+    layout.initramfs_cryptsetup = false;
+
+    let warnings = layout.warnings(KernelOperation::Install, 200_000_000);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("cryptsetup-initramfs"));
+    assert!(warnings[1].starts_with("/boot has"));
+    // Removing a kernel only frees space
+    assert!(layout.warnings(KernelOperation::Remove, 200_000_000).is_empty());
+}
+
+#[test]
+fn unmounted_boot_warns_for_every_operation() {
+    let layout = BootLayout { boot_unmounted: true, esp_unmounted: true, ..BootLayout::default() };
+    for operation in [KernelOperation::Install, KernelOperation::Remove, KernelOperation::SetDefault] {
+        let warnings = layout.warnings(operation, 0);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("/boot is listed in /etc/fstab"));
+        assert!(warnings[1].contains("/boot/efi"));
+    }
+}
+
+#[test]
+fn new_kernel_needs_twice_the_running_one() {
+    assert_eq!(boot_layout::needed_for_install(90_000_000), 180_000_000);
+    assert_eq!(boot_layout::needed_for_install(0), FALLBACK_KERNEL_BYTES);
+}
+
+#[test]
+fn raspberry_pi_skips_grub_checks_and_measures_the_firmware_partition() {
+    let layout = BootLayout {
+        platform: BootPlatform::RaspberryPi { firmware_dir: "/boot/firmware".to_string() },
+        esp_unmounted: true,
+        boot_stack: vec!["crypt".to_string()],
+        boot_free: Some(10_000_000),
+        ..BootLayout::default()
+    };
+    assert!(layout.warnings(KernelOperation::Remove, 0).is_empty());
+    let warnings = layout.warnings(KernelOperation::Install, 60_000_000);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("/boot/firmware has"));
+}
@@ -0,0 +1,31 @@
+// tests/boot_platform_tests.rs
+use meaupdater::boot_platform::{self, BootPlatform};
+
+#[test]
+fn raspberry_pi_uses_the_firmware_partition() {
+    let platform = boot_platform::detect_from(Some("Raspberry Pi 4 Model B Rev 1.4"), "aarch64", false, true);
+    assert_eq!(platform, BootPlatform::RaspberryPi { firmware_dir: "/boot/firmware".to_string() });
+    assert!(!platform.uses_grub());
+    assert_eq!(platform.firmware_dir(), Some("/boot/firmware"));
+}
+
+#[test]
+fn older_raspberry_pi_os_boots_from_boot() {
+    let platform = boot_platform::detect_from(Some("Raspberry Pi 3 Model B Plus Rev 1.3"), "arm", false, false);
+    assert_eq!(platform, BootPlatform::RaspberryPi { firmware_dir: "/boot".to_string() });
+    assert_eq!(platform.firmware_dir(), None);
+}
+
+#[test]
+fn arm_board_without_grub_is_not_grub() {
+    let platform = boot_platform::detect_from(Some("Pine64 RockPro64 v2.1"), "aarch64", false, false);
+    assert_eq!(platform, BootPlatform::OtherArm);
+    assert_eq!(platform.name(), "U-Boot or flash-kernel");
+}
+
+#[test]
+fn arm_server_with_grub_and_pcs_use_grub() {
+    assert!(boot_platform::detect_from(None, "aarch64", true, false).uses_grub());
+    assert!(boot_platform::detect_from(None, "x86_64", false, false).uses_grub());
+    assert_eq!(BootPlatform::default(), BootPlatform::Grub);
+}
@@ -0,0 +1,44 @@
+// tests/changelog_tests.rs
+
+use meaupdater::changelog::{cache_key, entries_since, has_changelog};
+use meaupdater::model::UpdateType;
+
+const CHANGELOG: &str = "bash (5.2.15-2+b8) bookworm; urgency=medium
+
+  * Binary-only non-maintainer upload.
+
+ -- Debian Release Team <release@debian.org>  Mon, 10 Jun 2024 10:00:00 +0000
+
+bash (5.2.15-2+b7) bookworm; urgency=medium
+
+  * Binary-only non-maintainer upload.
+
+ -- Debian Release Team <release@debian.org>  Sat, 02 Mar 2024 10:00:00 +0000
+";
+
+#[test]
+fn test_entries_since_stops_at_installed_version() {
+    let entries = entries_since(CHANGELOG, "5.2.15-2+b7");
+    assert!(entries.starts_with("bash (5.2.15-2+b8)"));
+    assert!(!entries.contains("5.2.15-2+b7"));
+}
+
+#[test]
+fn test_entries_since_unknown_version_keeps_everything() {
+    assert_eq!(entries_since(CHANGELOG, "5.1-6"), CHANGELOG);
+    assert_eq!(entries_since(CHANGELOG, ""), CHANGELOG);
+}
+
+#[test]
+fn test_cache_key_escapes_epochs() {
+    assert_eq!(cache_key("vim", "2:9.0.1378-2"), "vim_2%9.0.1378-2");
+    assert_eq!(cache_key("bash", "5.2.15-2+b7"), "bash_5.2.15-2+b7");
+}
+
+#[test]
+fn only_apt_updates_have_changelogs() {
+    assert!(has_changelog(&UpdateType::Security));
+    assert!(has_changelog(&UpdateType::Kernel));
+    assert!(!has_changelog(&UpdateType::Flatpak));
+    assert!(!has_changelog(&UpdateType::Snap));
+}
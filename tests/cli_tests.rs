@@ -0,0 +1,81 @@
+// tests/cli_tests.rs
+mod common;
+
+use meaupdater::cli::{format_json, format_table, parse_options, select, OutputFormat};
+use meaupdater::model::UpdateType;
+use common::update;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn parses_formats_and_packages() {
+    let options = parse_options(&args(&["--json", "bash", "openssl"])).unwrap();
+    assert_eq!(options.format, OutputFormat::Json);
+    assert_eq!(options.packages, vec!["bash", "openssl"]);
+    assert!(!options.all);
+
+    assert_eq!(parse_options(&args(&["--format", "table"])).unwrap().format, OutputFormat::Table);
+    assert_eq!(parse_options(&args(&["--format=json", "--all"])).unwrap().format, OutputFormat::Json);
+    assert!(parse_options(&args(&["--format", "xml"])).is_err());
+    assert!(parse_options(&args(&["--yes"])).is_err());
+}
+
+#[test]
+fn table_lists_type_and_versions() {
+    let table = format_table(&[update("linux-image-amd64", UpdateType::Kernel, None), update("bash", UpdateType::Security, Some(1024))]);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("PACKAGE "));
+    assert!(lines[1].starts_with("linux-image-amd64  kernel "));
+    assert!(lines[2].starts_with("bash               security "));
+    assert!(format_table(&[]).contains("up to date"));
+}
+
+#[test]
+fn json_has_one_object_per_update() {
+    let json = format_json(&[update("bash", UpdateType::Security, Some(2048)), update("vim", UpdateType::Software, None)]);
+    assert!(json.contains("{\"name\": \"bash\", \"type\": \"security\", \"current_version\": \"1.0\", \"new_version\": \"1.1\", \"size\": 2048}"));
+    assert!(json.contains("\"size\": null"));
+    assert_eq!(format_json(&[]), "[]\n");
+}
+
+#[test]
+fn selects_only_pending_packages() {
+    let pending = vec![update("bash", UpdateType::Security, None), update("vim", UpdateType::Software, None)];
+    assert_eq!(select(&pending, &parse_options(&args(&["--all"])).unwrap()).unwrap(), vec!["bash", "vim"]);
+    assert_eq!(select(&pending, &parse_options(&args(&["vim"])).unwrap()).unwrap(), vec!["vim"]);
+    assert!(select(&pending, &parse_options(&args(&["emacs"])).unwrap()).is_err());
+    assert!(select(&pending, &parse_options(&[]).unwrap()).is_err());
+}
+
+#[test]
+fn separates_pinned_requests() {
+    use meaupdater::apt::PackageRequest;
+    use meaupdater::cli::pinned_requests;
+
+    let options = parse_options(&args(&["vim", "bash=5.2.15-2", "linux-image-amd64/bookworm-backports"])).unwrap();
+    let (names, pinned) = pinned_requests(&options).unwrap();
+    assert_eq!(names, vec!["vim"]);
+    assert_eq!(pinned, vec![
+        PackageRequest::version("bash", "5.2.15-2"),
+        PackageRequest::release("linux-image-amd64", "bookworm-backports"),
+    ]);
+    assert!(pinned_requests(&parse_options(&args(&["bash=latest"])).unwrap()).is_err());
+}
+
+#[test]
+fn selecting_one_kernel_package_selects_the_set() {
+    use meaupdater::cli::with_kernel_set;
+
+    let pending = vec![
+        update("linux-image-amd64", UpdateType::Kernel, None),
+        update("linux-image-6.1.0-20-amd64", UpdateType::Kernel, None),
+        update("vim", UpdateType::Software, None),
+    ];
+    let selected = with_kernel_set(&pending, vec!["linux-image-6.1.0-20-amd64".to_string()]);
+    assert_eq!(selected.len(), 2);
+    assert!(selected.contains(&"linux-image-amd64".to_string()));
+    assert_eq!(with_kernel_set(&pending, vec!["vim".to_string()]), vec!["vim"]);
+}
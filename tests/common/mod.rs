@@ -0,0 +1,20 @@
+// tests/common/mod.rs
+//
+// Fixtures shared by the integration tests; each test file that needs them
+// declares `mod common;`.
+
+use meaupdater::model::{PackageUpdate, UpdateType};
+
+/// An update of `name` from 1.0 to 1.1. Security updates come from a
+/// security archive.
+pub fn update(name: &str, update_type: UpdateType, size: Option<u64>) -> PackageUpdate {
+    PackageUpdate {
+        name: name.to_string(),
+        current_version: "1.0".to_string(),
+        new_version: "1.1".to_string(),
+        from_security: update_type == UpdateType::Security,
+        update_type,
+        size,
+        first_seen: None,
+    }
+}
@@ -0,0 +1,36 @@
+// tests/conffile_tests.rs
+use meaupdater::conffile::{is_prompt_end, parse_conffile_line, ConffileChoice};
+
+const PROMPT: &str = "\
+Configuration file '/etc/default/grub'
+ ==> Modified (by you or by a script) since installation.
+ ==> Package distributor has shipped an updated version.
+   What would you like to do about it ?  Your options are:
+    Y or I  : install the package maintainer's version
+    N or O  : keep your currently-installed version
+      D     : show the differences between the versions
+      Z     : start a shell to examine the situation
+ The default action is to keep your current version.";
+
+#[test]
+fn detects_conffile_prompt() {
+    let lines: Vec<&str> = PROMPT.lines().collect();
+
+    assert_eq!(parse_conffile_line(lines[0]), Some("/etc/default/grub".to_string()));
+    assert!(lines[1..].iter().all(|line| parse_conffile_line(line).is_none()));
+
+    assert!(is_prompt_end(lines[lines.len() - 1]));
+    assert!(lines[..lines.len() - 1].iter().all(|line| !is_prompt_end(line)));
+}
+
+#[test]
+fn ignores_unrelated_output() {
+    assert_eq!(parse_conffile_line("Setting up grub-common (2.06-13) ..."), None);
+    assert_eq!(parse_conffile_line("Configuration file ''"), None);
+}
+
+#[test]
+fn answers_match_dpkg_options() {
+    assert_eq!(ConffileChoice::KeepCurrent.answer(), "N\n");
+    assert_eq!(ConffileChoice::InstallNew.answer(), "Y\n");
+}
@@ -0,0 +1,106 @@
+// tests/config_tests.rs
+use meaupdater::config::{check_only_mode, AdminPolicy, Config};
+use meaupdater::policy::{AutoInstallPolicy, Schedule, ScheduleFrequency};
+
+#[test]
+fn defaults_when_empty() {
+    assert_eq!(Config::parse("", Config::default()), Config::default());
+    assert_eq!(Config::default().check_interval_seconds(), 1800);
+}
+
+#[test]
+fn round_trips_and_clamps() {
+    let config = Config {
+        automatic_checks: false,
+        check_interval_minutes: 360,
+        auto_install: AutoInstallPolicy { security: true, drivers: true, ..AutoInstallPolicy::default() },
+        schedule: Schedule { frequency: ScheduleFrequency::Weekly, time_of_day: 22 * 60 + 30, weekday: 6, notify_only: true },
+        weekly_digest: true,
+        weekly_report: false,
+        collapsed_groups: vec!["software".to_string(), "kept-back".to_string()],
+        notify_updates: false,
+        sort_by_type: true,
+        sort_by_size: false,
+        window_width: 1200,
+        window_height: 800,
+        driver_backup: false,
+    };
+    assert_eq!(Config::parse(&config.to_toml(), Config::default()), config);
+
+    assert_eq!(Config::parse("check_interval_minutes = 1", Config::default()).check_interval_minutes, 15);
+    assert_eq!(Config::parse("check_interval_minutes = 99999", Config::default()).check_interval_minutes, 1440);
+    assert!(Config::parse("automatic_checks = maybe", Config::default()).automatic_checks);
+}
+
+#[test]
+fn automatic_installation_is_off_by_default() {
+    assert!(!Config::default().auto_install.is_enabled());
+    let config = Config::parse("auto_install_security = true\nauto_install_kernel = yes\n", Config::default());
+    assert!(config.auto_install.security);
+    assert!(!config.auto_install.kernel);
+}
+
+#[test]
+fn check_only_flag_is_consumed() {
+    let args: Vec<String> = ["meaupdater", "--check-only"].iter().map(|a| a.to_string()).collect();
+    assert_eq!(check_only_mode(&args, false), (true, vec!["meaupdater".to_string()]));
+
+    let plain = vec!["meaupdater".to_string()];
+    assert_eq!(check_only_mode(&plain, false), (false, plain.clone()));
+    // An administrator can enforce it without the flag
+    assert_eq!(check_only_mode(&plain, true), (true, plain.clone()));
+}
+
+#[test]
+fn admin_policy_parses_and_pins_settings() {
+    let policy = AdminPolicy::parse(
+        "# Lab machines\nhide_kernel_manager = true\nhide_repository_manager = true\nsecurity_only = true\ncheck_interval_minutes = 5\n",
+    );
+    assert!(policy.hide_kernel_manager && policy.hide_repository_manager && policy.security_only);
+    assert!(!policy.hide_driver_manager);
+    assert_eq!(policy.automatic_checks, None);
+    assert_eq!(policy.check_interval_minutes, Some(15));
+
+    let user = Config { automatic_checks: false, check_interval_minutes: 720, ..Config::default() };
+    assert_eq!(policy.apply(user), Config { automatic_checks: false, check_interval_minutes: 15, ..Config::default() });
+    assert_eq!(AdminPolicy::default().apply(Config::default()), Config::default());
+}
+
+#[test]
+fn schedule_is_read_from_the_config_file() {
+    let config = Config::parse("schedule = \"daily\"\nschedule_time = \"03:00\"\nschedule_weekday = \"sun\"\nauto_install_security = true\n", Config::default());
+    assert_eq!(config.schedule, Schedule { frequency: ScheduleFrequency::Daily, time_of_day: 180, weekday: 7, notify_only: false });
+    assert!(config.auto_install.security);
+
+    let bad = Config::parse("schedule = hourly\nschedule_time = 25:00\nschedule_weekday = x\n", Config::default());
+    assert_eq!(bad.schedule, Schedule::default());
+
+    let pinned = AdminPolicy { check_interval_minutes: Some(60), ..AdminPolicy::default() };
+    assert_eq!(pinned.apply(config).schedule.frequency, ScheduleFrequency::Interval);
+}
+
+#[test]
+fn collapsed_groups_are_remembered() {
+    let mut config = Config::parse("collapsed_groups = \"software, flatpak,\"\n", Config::default());
+    assert_eq!(config.collapsed_groups, ["software", "flatpak"]);
+    assert!(config.is_group_collapsed("flatpak"));
+    assert!(!config.is_group_collapsed("security"));
+
+    config.set_group_collapsed("flatpak", false);
+    config.set_group_collapsed("kernel", true);
+    config.set_group_collapsed("kernel", true);
+    assert_eq!(config.collapsed_groups, ["software", "kernel"]);
+    assert!(Config::default().collapsed_groups.is_empty());
+}
+
+#[test]
+fn view_settings_survive_a_restart() {
+    let defaults = Config::default();
+    assert!(defaults.notify_updates && defaults.driver_backup);
+    assert!(!defaults.sort_by_type && !defaults.sort_by_size);
+
+    let config = Config::parse("sort_by_size = true\nwindow_width = 1280\nwindow_height = 12\ndriver_backup = false\n", Config::default());
+    assert!(config.sort_by_size);
+    assert_eq!((config.window_width, config.window_height), (1280, defaults.window_height));
+    assert!(!config.driver_backup);
+}
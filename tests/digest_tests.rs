@@ -0,0 +1,56 @@
+// tests/digest_tests.rs
+mod common;
+
+use meaupdater::digest::{build, date, format_html, is_due, notification_body, parse_boot_times, DIGEST_INTERVAL_SECS};
+use meaupdater::history::parse_entry;
+use meaupdater::model::UpdateType;
+use common::update;
+
+const NOW: u64 = 1_730_800_000; // 2024-11-05
+
+#[test]
+fn formats_dates_in_utc() {
+    assert_eq!(date(0), "1970-01-01");
+    assert_eq!(date(NOW), "2024-11-05");
+    assert_eq!(date(951_782_400), "2000-02-29");
+}
+
+#[test]
+fn due_once_a_week() {
+    assert!(is_due(None, NOW));
+    assert!(!is_due(Some(NOW - 3600), NOW));
+    assert!(is_due(Some(NOW - DIGEST_INTERVAL_SECS), NOW));
+}
+
+#[test]
+fn summarizes_the_last_week() {
+    let history: Vec<_> = [
+        format!("{}\tinstall\tok\tupgraded bash 5.1 5.2,installed libfoo - 1.0", NOW - 3600),
+        format!("{}\tinstall\tfailed\tupgraded vim 9.0 9.1", NOW - 7200),
+        format!("{}\tinstall\tok\tupgraded old 1 2", NOW - DIGEST_INTERVAL_SECS - 10),
+    ].iter().filter_map(|line| parse_entry(line)).collect();
+    let boots = [NOW - 86_400, NOW - 2 * DIGEST_INTERVAL_SECS];
+    let digest = build(&history, &[update("openssl", UpdateType::Security, None), update("gimp", UpdateType::Software, None)], Some(&boots), NOW);
+
+    assert_eq!(digest.operations.len(), 1);
+    assert_eq!((digest.upgraded(), digest.installed(), digest.removed()), (1, 1, 0));
+    assert_eq!(digest.failed_operations, 1);
+    assert_eq!((digest.pending, digest.pending_security), (2, 1));
+    assert_eq!(digest.reboots, Some(1));
+
+    let body = notification_body(&digest);
+    assert!(body.contains("1 upgraded, 1 newly installed"));
+    assert!(body.contains("❌ 1 operation failed"));
+    assert!(body.contains("2 updates pending, 1 of them security"));
+
+    let html = format_html(&digest);
+    assert!(html.contains("<td>bash</td><td>5.1</td><td>5.2</td>"));
+    assert!(!html.contains(">old<"));
+}
+
+#[test]
+fn reads_boot_times_from_journal_json() {
+    let json = r#"[{"index":-1,"boot_id":"a","first_entry":1730700000000000,"last_entry":1730710000000000},
+                   {"index":0,"boot_id":"b","first_entry":1730790000123456,"last_entry":1730800000000000}]"#;
+    assert_eq!(parse_boot_times(json), vec![1_730_700_000, 1_730_790_000]);
+}
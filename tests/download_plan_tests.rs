@@ -0,0 +1,72 @@
+// tests/download_plan_tests.rs
+use meaupdater::bundle::{parse_manifest, verify_against, Verification};
+use meaupdater::download_plan::{export_script, parse_print_uris, total_size, DownloadUri};
+
+const OUTPUT: &str = "\
+'http://deb.debian.org/debian/pool/main/c/curl/curl_7.88.1-10%2bdeb12u5_amd64.deb' curl_7.88.1-10+deb12u5_amd64.deb 315520 SHA256:0d0b8e3e1f9b2e8e2a5c1f6f6a4e8b0e7c2d1a9f8e7d6c5b4a3928171605f4e3
+'http://security.debian.org/pool/updates/main/o/openssl/libssl3_3.0.11-1%7edeb12u2_amd64.deb' libssl3_3.0.11-1~deb12u2_amd64.deb 2019496 MD5Sum:5d41402abc4b2a76b9719d911017c592
+Reading package lists...
+";
+
+#[test]
+fn parses_print_uris_output() {
+    let plan = parse_print_uris(OUTPUT);
+    assert_eq!(plan.len(), 2);
+
+    assert_eq!(plan[0].filename, "curl_7.88.1-10+deb12u5_amd64.deb");
+    assert_eq!(plan[0].size, 315520);
+    assert_eq!(plan[0].checksum().map(|(tool, _)| tool), Some("sha256sum"));
+
+    assert!(plan[1].uri.starts_with("http://security.debian.org/"));
+    assert_eq!(plan[1].checksum(), Some(("md5sum", "5d41402abc4b2a76b9719d911017c592")));
+
+    assert_eq!(total_size(&plan), 315520 + 2019496);
+}
+
+#[test]
+fn script_downloads_and_verifies_each_file() {
+    let plan = parse_print_uris(OUTPUT);
+    let script = export_script(&["curl".to_string(), "libssl3".to_string()], &plan);
+
+    assert!(script.starts_with("#!/bin/sh\n"));
+    assert!(script.contains("fetch 'http://deb.debian.org/debian/pool/main/c/curl/curl_7.88.1-10%2bdeb12u5_amd64.deb' 'curl_7.88.1-10+deb12u5_amd64.deb'"));
+    assert!(script.contains("| md5sum -c -"));
+    assert_eq!(script.matches("fetch '").count(), 2);
+}
+
+#[test]
+fn script_writes_bundle_manifest() {
+    let plan = parse_print_uris(OUTPUT);
+    let script = export_script(&["curl".to_string()], &plan);
+    let manifest_start = script.find("<<'EOF'\n").unwrap() + 8;
+    let manifest_end = script[manifest_start..].find("EOF\n").unwrap() + manifest_start;
+
+    let manifest = parse_manifest(&script[manifest_start..manifest_end]);
+    assert_eq!(manifest.len(), 2);
+    assert_eq!(manifest["libssl3_3.0.11-1~deb12u2_amd64.deb"].size, 2019496);
+    assert_eq!(manifest["libssl3_3.0.11-1~deb12u2_amd64.deb"].hash, "MD5Sum:5d41402abc4b2a76b9719d911017c592");
+}
+
+#[test]
+fn verifies_size_and_checksum() {
+    let dir = std::env::temp_dir().join(format!("meaupdater-bundle-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("hello.deb");
+    std::fs::write(&path, "hello").unwrap();
+
+    let mut expected = DownloadUri {
+        uri: String::new(),
+        filename: "hello.deb".to_string(),
+        size: 5,
+        hash: "MD5Sum:5d41402abc4b2a76b9719d911017c592".to_string(),
+    };
+    assert_eq!(verify_against(&path, &expected), Verification::Verified);
+
+    expected.hash = "MD5Sum:00000000000000000000000000000000".to_string();
+    assert!(!verify_against(&path, &expected).is_ok());
+
+    expected.size = 6;
+    assert!(matches!(verify_against(&path, &expected), Verification::Mismatch(_)));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,196 @@
+// tests/driver_tests.rs
+use meaupdater::driver_manager::{
+    display_fallback_packages, format_driver_cache, is_active_display_driver, parse_installed_status, is_hotplug_event, parse_driver_cache, parse_installed_drivers, parse_lsmod,
+    parse_lspci_devices, recommended_nvidia_branch, nonfree_enabled, required_components, unavailable_reason, DriverInfo, DriverLicense, DriverType, NvidiaGeneration,
+    NVIDIA_BRANCHES,
+};
+use meaupdater::repo_manager::{ArchiveFamily, Repository};
+
+#[test]
+fn lsmod_module_names() {
+    let output = "Module                  Size  Used by\n\
+                  nvidia_drm             77824  4\n\
+                  nvidia               5459968  1 nvidia_drm\n\
+                  wl                   6455296  0\n";
+    let modules = parse_lsmod(output);
+    assert_eq!(modules.len(), 3);
+    assert!(modules.contains("nvidia"));
+    assert!(modules.contains("wl"));
+    assert!(!modules.contains("Module"));
+}
+
+#[test]
+fn lspci_devices_with_ids() {
+    let output = "01:00.0 VGA compatible controller [0300]: NVIDIA Corporation TU106 [GeForce RTX 2060] [10de:1f08] (rev a1)\n\
+                  00:1f.3 Audio device [0403]: Intel Corporation Cannon Lake PCH cAVS [8086:a348] (rev 10)\n";
+    let devices = parse_lspci_devices(output);
+    assert_eq!(devices.len(), 2);
+    assert_eq!(devices[0].0, "01:00.0 VGA compatible controller [0300]");
+    assert!(devices[0].1.contains("GeForce RTX 2060"));
+}
+
+#[test]
+fn installed_driver_packages_only() {
+    let output = "ii  nvidia-driver   535.183.01-1   amd64   NVIDIA metapackage\n\
+                  ii  coreutils       9.1-1          amd64   GNU core utilities\n\
+                  rc  firmware-realtek 20230210-5    all     Binary firmware\n";
+    let installed = parse_installed_drivers(output);
+    assert_eq!(installed.get("nvidia-driver").map(String::as_str), Some("535.183.01-1"));
+    assert!(!installed.contains_key("coreutils"));
+    assert!(!installed.contains_key("firmware-realtek"));
+}
+
+#[test]
+fn driver_cache_round_trip() {
+    let mut driver = DriverInfo::new(
+        "nvidia-driver".to_string(),
+        "NVIDIA Driver (Metapackage) - GeForce\tRTX".to_string(),
+        "nvidia-driver".to_string(),
+        "535.183.01-1".to_string(),
+        DriverType::Graphics,
+        DriverLicense::NonFree,
+        "NVIDIA".to_string(),
+        "10de:1f08".to_string(),
+    );
+    driver.is_installed = true;
+    driver.is_recommended = true;
+    let mut candidate = DriverInfo::new(
+        "firmware-realtek".to_string(),
+        "Realtek WiFi Firmware".to_string(),
+        "firmware-realtek".to_string(),
+        "20230210-5".to_string(),
+        DriverType::Other,
+        DriverLicense::NonFree,
+        "Realtek".to_string(),
+        String::new(),
+    );
+    candidate.origin = Some("deb.debian.org/debian bookworm/non-free-firmware".to_string());
+    let mut missing = DriverInfo::new(
+        "nvidia-tesla-470-driver".to_string(),
+        "NVIDIA 470.xx Tesla Driver".to_string(),
+        "nvidia-tesla-470-driver".to_string(),
+        "Unavailable".to_string(),
+        DriverType::Graphics,
+        DriverLicense::NonFree,
+        "NVIDIA".to_string(),
+        "10de:1f08".to_string(),
+    );
+    missing.unavailable = Some("Requires the non-free repository".to_string());
+    missing.missing_components = vec!["contrib".to_string(), "non-free".to_string()];
+
+    let content = format_driver_cache("abc", &[driver, candidate, missing]);
+    let drivers = parse_driver_cache(&content, "abc").unwrap();
+    assert_eq!(drivers.len(), 3);
+    assert_eq!(drivers[0].description, "NVIDIA Driver (Metapackage) - GeForce RTX");
+    assert_eq!(drivers[0].driver_type, DriverType::Graphics);
+    assert_eq!(drivers[0].license, DriverLicense::NonFree);
+    assert!(drivers[0].is_installed && !drivers[0].is_active && drivers[0].is_recommended);
+    assert!(drivers[0].modalias.is_none());
+    assert_eq!(drivers[0].version_text(), "535.183.01-1");
+    assert_eq!(drivers[1].version_text(), "20230210-5 from deb.debian.org/debian bookworm/non-free-firmware");
+    assert!(drivers[1].unavailable.is_none());
+    assert_eq!(drivers[2].unavailable.as_deref(), Some("Requires the non-free repository"));
+    assert_eq!(drivers[2].missing_components, ["contrib", "non-free"]);
+    assert!(drivers[1].missing_components.is_empty());
+
+    // A different fingerprint means the hardware or packages changed
+    assert!(parse_driver_cache(&content, "def").is_none());
+}
+
+#[test]
+fn hotplug_events_from_udevadm() {
+    assert!(is_hotplug_event("UDEV  [4711.123456] add      /devices/pci0000:00/0000:00:14.0/usb1/1-2 (usb)"));
+    assert!(is_hotplug_event("UDEV  [4712.000001] remove   /devices/pci0000:00/0000:00:14.0/usb1/1-2 (usb)"));
+    assert!(!is_hotplug_event("UDEV  [4713.000001] change   /devices/pci0000:00/0000:00:02.0 (pci)"));
+    assert!(!is_hotplug_event("UDEV - the event which udev sends out after rule processing"));
+}
+
+#[test]
+fn nvidia_generation_from_pci_id() {
+    assert_eq!(NvidiaGeneration::from_device_id("10de:1f08"), Some(NvidiaGeneration::Turing));
+    assert_eq!(NvidiaGeneration::from_device_id("10de:1004"), Some(NvidiaGeneration::Kepler));
+    assert_eq!(NvidiaGeneration::from_device_id("10de:1288"), Some(NvidiaGeneration::Kepler));
+    assert_eq!(NvidiaGeneration::from_device_id("10de:1244"), Some(NvidiaGeneration::Fermi));
+    assert_eq!(NvidiaGeneration::from_device_id("10de:1b80"), Some(NvidiaGeneration::Pascal));
+    assert_eq!(NvidiaGeneration::from_device_id("10de:2684"), Some(NvidiaGeneration::Ada));
+    assert_eq!(NvidiaGeneration::from_device_id("8086:a348"), None);
+}
+
+#[test]
+fn nvidia_branch_per_generation() {
+    let package = |generation| recommended_nvidia_branch(generation).map(|b| b.package);
+    assert_eq!(package(Some(NvidiaGeneration::Fermi)), Some("nvidia-legacy-390xx-driver"));
+    assert_eq!(package(Some(NvidiaGeneration::Kepler)), Some("nvidia-tesla-470-driver"));
+    assert_eq!(package(Some(NvidiaGeneration::Turing)), Some("nvidia-driver"));
+    assert_eq!(package(Some(NvidiaGeneration::Blackwell)), Some("nvidia-open-kernel-dkms"));
+    assert_eq!(package(Some(NvidiaGeneration::Tesla)), None);
+    assert_eq!(package(None), Some("nvidia-driver"));
+
+    let open = NVIDIA_BRANCHES.iter().find(|b| b.package == "nvidia-open-kernel-dkms").unwrap();
+    assert!(!open.supports(NvidiaGeneration::Pascal));
+    assert!(open.supports(NvidiaGeneration::Ampere));
+}
+
+#[test]
+fn active_display_driver_needs_fallback() {
+    let mut nvidia = DriverInfo::new(
+        "nvidia-driver".to_string(),
+        "NVIDIA Driver (Current)".to_string(),
+        "nvidia-driver".to_string(),
+        "535.183.01-1".to_string(),
+        DriverType::Graphics,
+        DriverLicense::NonFree,
+        "NVIDIA".to_string(),
+        "10de:1f08".to_string(),
+    );
+    assert!(!is_active_display_driver(&nvidia));
+    nvidia.is_installed = true;
+    nvidia.is_active = true;
+    assert!(is_active_display_driver(&nvidia));
+    assert_eq!(display_fallback_packages(&nvidia), vec!["xserver-xorg-core", "xserver-xorg-video-nouveau"]);
+
+    let output = "xserver-xorg-core ii \nxserver-xorg-video-nouveau rc \n";
+    let installed = parse_installed_status(output);
+    assert!(installed.contains("xserver-xorg-core"));
+    assert!(!installed.contains("xserver-xorg-video-nouveau"));
+}
+
+#[test]
+fn unavailable_reason_points_at_non_free() {
+    let mut repo = Repository {
+        name: "Debian".to_string(),
+        uri: "http://deb.debian.org/debian".to_string(),
+        distribution: "bookworm".to_string(),
+        components: "main non-free-firmware".to_string(),
+        enabled: true,
+        is_source: false,
+        architectures: Vec::new(),
+        signed_by: None,
+        file_path: None,
+        line_number: None,
+        managed_by: None,
+    };
+    assert!(!nonfree_enabled(&[repo.clone()]));
+    assert_eq!(unavailable_reason(&DriverLicense::NonFree, false), "Requires the non-free repository");
+    assert_eq!(unavailable_reason(&DriverLicense::Free, false), "Not in your enabled repositories");
+
+    repo.components = "main contrib non-free".to_string();
+    assert!(nonfree_enabled(&[repo.clone()]));
+    assert_eq!(unavailable_reason(&DriverLicense::NonFree, true), "Not in your enabled repositories");
+
+    repo.enabled = false;
+    assert!(!nonfree_enabled(&[repo]));
+}
+
+#[test]
+fn closed_drivers_name_the_components_they_need() {
+    let debian = ArchiveFamily::Debian;
+    assert_eq!(required_components(&DriverLicense::NonFree, "nvidia-driver", debian), ["contrib", "non-free"]);
+    assert_eq!(required_components(&DriverLicense::NonFree, "firmware-iwlwifi", debian), ["non-free-firmware"]);
+    assert_eq!(required_components(&DriverLicense::NonFree, "intel-microcode", debian), ["non-free-firmware"]);
+    assert!(required_components(&DriverLicense::Free, "firmware-linux", debian).is_empty());
+
+    let ubuntu = ArchiveFamily::Ubuntu;
+    assert_eq!(required_components(&DriverLicense::NonFree, "nvidia-driver-535", ubuntu), ["restricted"]);
+    assert_eq!(required_components(&DriverLicense::NonFree, "broadcom-sta-dkms", ubuntu), ["multiverse"]);
+}
@@ -0,0 +1,61 @@
+// tests/error_tests.rs
+use meaupdater::error::{Context, Error};
+
+#[test]
+fn lock_errors_are_recognized() {
+    let stderr = "E: Could not get lock /var/lib/dpkg/lock-frontend. It is held by process 1234 (unattended-upgr)\n\
+                  E: Unable to acquire the dpkg frontend lock (/var/lib/dpkg/lock-frontend), is another process using it?";
+    let error = Error::from_apt_output(stderr);
+    assert!(matches!(error, Error::LockHeld(_)));
+    assert!(error.is_transient());
+}
+
+#[test]
+fn network_errors_are_recognized() {
+    let stderr = "W: Failed to fetch http://deb.debian.org/debian/dists/bookworm/InRelease  Temporary failure resolving 'deb.debian.org'";
+    assert!(matches!(Error::from_apt_output(stderr), Error::Network(_)));
+}
+
+#[test]
+fn permission_errors_are_recognized() {
+    let stderr = "E: Could not open lock file /var/lib/dpkg/lock-frontend - open (13: Permission denied)\nE: Unable to acquire the dpkg frontend lock, are you root?";
+    // Not being root also means the lock cannot be taken
+    assert!(matches!(Error::from_apt_output(stderr), Error::PermissionDenied(_)));
+
+    let stderr = "E: List directory /var/lib/apt/lists/partial is missing. - Acquire (13: Permission denied)";
+    assert!(matches!(Error::from_apt_output(stderr), Error::PermissionDenied(_)));
+}
+
+#[test]
+fn other_apt_errors_stay_generic() {
+    let error = Error::from_apt_output("E: Unable to locate package nosuchpackage\n");
+    assert!(matches!(error, Error::Apt(_)));
+    assert_eq!(error.to_string(), "E: Unable to locate package nosuchpackage");
+    assert!(!error.is_transient());
+    assert!(error.hint().is_some());
+}
+
+#[test]
+fn context_is_prepended() {
+    let result: Result<(), std::io::Error> = Err(std::io::Error::new(std::io::ErrorKind::NotFound, "no such file"));
+    let error = result.context("apt could not be run").unwrap_err();
+    assert!(matches!(error, Error::Io { .. }));
+    assert_eq!(error.to_string(), "apt could not be run: no such file");
+
+    let missing: Option<u32> = None;
+    assert_eq!(missing.context("HOME is not set").unwrap_err().to_string(), "HOME is not set");
+}
+
+#[test]
+fn io_permission_errors_become_permission_denied() {
+    let error: Error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied").into();
+    assert!(matches!(error, Error::PermissionDenied(_)));
+}
+
+#[test]
+fn cancelling_survives_context() {
+    let error = Error::Cancelled.with_context("Could not install packages");
+    assert!(matches!(error, Error::Cancelled));
+    assert!(!error.is_transient());
+    assert!(error.hint().is_none());
+}
@@ -0,0 +1,47 @@
+// tests/flatpak_tests.rs
+use meaupdater::flatpak_manager::{flatpak_names, parse_installed, parse_progress_line, parse_size, parse_updates};
+use meaupdater::model::UpdateType;
+
+const INSTALLED: &str = "org.gimp.GIMP\t2.10.36\tstable\norg.gnome.Platform\t\t45\n";
+const UPDATES: &str = "org.gimp.GIMP\t2.10.38\tstable\t120.5\u{a0}MB\n\
+                       org.gnome.Platform\t\t45\t3.2 MB\n\
+                       org.gnome.Platform\t\t46\t310.0 MB\n";
+
+#[test]
+fn parses_glib_sizes() {
+    assert_eq!(parse_size("120.5\u{a0}MB"), Some(120_500_000));
+    assert_eq!(parse_size("512 bytes"), Some(512));
+    assert_eq!(parse_size("1,5 kB"), Some(1500));
+    assert_eq!(parse_size("unknown"), None);
+}
+
+#[test]
+fn lists_each_application_once_with_versions() {
+    let installed = parse_installed(INSTALLED);
+    assert_eq!(installed.get("org.gnome.Platform").map(String::as_str), Some("45"));
+
+    let updates = parse_updates(UPDATES, &installed);
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].name, "org.gimp.GIMP");
+    assert_eq!(updates[0].current_version, "2.10.36");
+    assert_eq!(updates[0].new_version, "2.10.38");
+    assert_eq!(updates[0].size, Some(120_500_000));
+    assert_eq!(updates[0].update_type, UpdateType::Flatpak);
+    // Runtimes without a version are shown by branch; an unchanged branch is not a "from" version
+    assert_eq!(updates[1].new_version, "45");
+    assert_eq!(updates[1].current_version, "");
+}
+
+#[test]
+fn reads_progress_counters() {
+    assert_eq!(parse_progress_line("Updating 2/5…"), Some((2, 5)));
+    assert_eq!(parse_progress_line("Updating org.gimp.GIMP"), None);
+    assert_eq!(parse_progress_line("Looking for updates…"), None);
+}
+
+#[test]
+fn picks_flatpak_names_from_selection() {
+    let updates = parse_updates(UPDATES, &parse_installed(INSTALLED));
+    let selected = vec!["bash".to_string(), "org.gimp.GIMP".to_string()];
+    assert_eq!(flatpak_names(&updates, &selected), vec!["org.gimp.GIMP"]);
+}
@@ -0,0 +1,43 @@
+// tests/format_tests.rs
+use meaupdater::format::{days, decimal_separator_for, plural, relative_time, size_with_separator, time_left};
+
+#[test]
+fn relative_times_are_readable() {
+    assert_eq!(relative_time(1_000, 1_030), "just now");
+    assert_eq!(relative_time(1_000, 1_060), "1 minute ago");
+    assert_eq!(relative_time(0, 7_200), "2 hours ago");
+    assert_eq!(relative_time(0, 3 * 86_400), "3 days ago");
+    // Clock changes must not produce "in the future" nonsense
+    assert_eq!(relative_time(2_000, 1_000), "just now");
+}
+
+#[test]
+fn counts_use_singular_and_plural() {
+    assert_eq!(plural(1, "day", "days"), "1 day");
+    assert_eq!(plural(0, "day", "days"), "0 days");
+    assert_eq!(days(0), "New today");
+    assert_eq!(days(1), "1 day");
+}
+
+#[test]
+fn sizes_follow_the_locale() {
+    assert_eq!(size_with_separator(512, '.'), "512 B");
+    assert_eq!(size_with_separator(1536, '.'), "1.5 KB");
+    assert_eq!(size_with_separator(1536, ','), "1,5 KB");
+    assert_eq!(size_with_separator(5 * 1024 * 1024 * 1024, '.'), "5.0 GB");
+
+    assert_eq!(decimal_separator_for("de_DE.UTF-8"), ',');
+    assert_eq!(decimal_separator_for("tr_TR.UTF-8"), ',');
+    assert_eq!(decimal_separator_for("en_US.UTF-8"), '.');
+    assert_eq!(decimal_separator_for("C"), '.');
+    assert_eq!(decimal_separator_for(""), '.');
+}
+
+#[test]
+fn time_left_is_rounded_up_to_minutes() {
+    assert_eq!(time_left(0), "less than a minute left");
+    assert_eq!(time_left(59), "less than a minute left");
+    assert_eq!(time_left(60), "about 1 minute left");
+    assert_eq!(time_left(61), "about 2 minutes left");
+    assert_eq!(time_left(3 * 3600 + 100), "about 3 hours left");
+}
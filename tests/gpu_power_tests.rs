@@ -0,0 +1,27 @@
+// tests/gpu_power_tests.rs
+
+use meaupdater::gpu_power::{parse_bbswitch, vendor_name, PowerState};
+
+#[test]
+fn test_power_state_from_sysfs_and_bbswitch() {
+    assert_eq!(PowerState::parse("active\n"), PowerState::Active);
+    assert_eq!(PowerState::parse("suspended"), PowerState::Suspended);
+    assert_eq!(PowerState::parse("OFF"), PowerState::Suspended);
+    assert_eq!(PowerState::parse("suspending"), PowerState::Other("suspending".to_string()));
+}
+
+#[test]
+fn test_parse_bbswitch() {
+    assert_eq!(
+        parse_bbswitch("0000:01:00.0 OFF\n"),
+        Some(("0000:01:00.0".to_string(), PowerState::Suspended))
+    );
+    assert_eq!(parse_bbswitch(""), None);
+}
+
+#[test]
+fn test_vendor_name() {
+    assert_eq!(vendor_name("0x10de\n"), "NVIDIA");
+    assert_eq!(vendor_name("0x1002"), "AMD");
+    assert_eq!(vendor_name("0x1234"), "Unknown");
+}
@@ -0,0 +1,53 @@
+// tests/hardware_profile_tests.rs
+use meaupdater::driver_manager::{DriverInfo, DriverLicense, DriverType};
+use meaupdater::hardware_profile::{anonymize, format_json, format_text, ProfileFormat, Section};
+use std::path::Path;
+
+fn driver() -> DriverInfo {
+    let mut driver = DriverInfo::new(
+        "nvidia-driver".to_string(),
+        "NVIDIA Driver \"Metapackage\"".to_string(),
+        "nvidia-driver".to_string(),
+        "535.183.01-1".to_string(),
+        DriverType::Graphics,
+        DriverLicense::NonFree,
+        "NVIDIA".to_string(),
+        "10de:1f08".to_string(),
+    );
+    driver.is_installed = true;
+    driver
+}
+
+#[test]
+fn anonymize_masks_macs_and_names() {
+    let text = "link/ether 3c:7c:3f:aa:01:ff brd ff:ff:ff:ff:ff:ff\n00:1f.3 Audio [0403]: Intel [8086:a348]\n/home/alice on laptop-alice";
+    let masked = anonymize(text, &[("laptop-alice", "<hostname>"), ("alice", "<user>"), ("x", "<user>")]);
+    assert_eq!(
+        masked,
+        "link/ether xx:xx:xx:xx:xx:xx brd xx:xx:xx:xx:xx:xx\n00:1f.3 Audio [0403]: Intel [8086:a348]\n/home/<user> on <hostname>"
+    );
+    // Part of a longer colon separated value is left alone
+    assert_eq!(anonymize("fe80:3c:7c:3f:aa:01:ff", &[]), "fe80:3c:7c:3f:aa:01:ff");
+}
+
+#[test]
+fn format_from_extension() {
+    assert_eq!(ProfileFormat::from_path(Path::new("/tmp/profile.JSON")), ProfileFormat::Json);
+    assert_eq!(ProfileFormat::from_path(Path::new("/tmp/profile.txt")), ProfileFormat::Text);
+    assert_eq!(ProfileFormat::from_path(Path::new("/tmp/profile")), ProfileFormat::Text);
+}
+
+#[test]
+fn reports_list_sections_and_drivers() {
+    let sections = vec![Section { command: "dkms status".to_string(), output: "nvidia/535, 6.1.0-18-amd64: installed".to_string() }];
+    let drivers = vec![driver()];
+
+    let text = format_text(&sections, &drivers);
+    assert!(text.contains("## dkms status\nnvidia/535, 6.1.0-18-amd64: installed\n"));
+    assert!(text.contains("nvidia-driver [installed] 535.183.01-1 10de:1f08 (graphics, non-free)"));
+
+    let json = format_json(&sections, &drivers);
+    assert!(json.contains("{\"command\": \"dkms status\", \"output\": \"nvidia/535, 6.1.0-18-amd64: installed\"}"));
+    assert!(json.contains("\"package\": \"nvidia-driver\", \"state\": \"installed\""));
+    assert!(format_json(&[Section { command: "x".into(), output: "a\"b\\c\nd".into() }], &[]).contains("\"a\\\"b\\\\c\\nd\""));
+}
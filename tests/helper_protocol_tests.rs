@@ -0,0 +1,98 @@
+// tests/helper_protocol_tests.rs
+use glib::prelude::*;
+use meaupdater::helper_protocol::{is_package_arg, is_sources_path, Operation, ACTION_INSTALL, ACTION_MANAGE};
+
+fn argv(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn reads_the_apt_get_command_lines_meaupdater_builds() {
+    assert_eq!(Operation::from_argv(&argv(&["apt-get", "update"])), Some(Operation::Update));
+    assert_eq!(
+        Operation::from_argv(&argv(&["apt-get", "install", "-y", "-o", "APT::Status-Fd=1", "--only-upgrade", "firefox-esr"])),
+        Some(Operation::Install { packages: argv(&["firefox-esr"]), only_upgrade: true })
+    );
+    assert_eq!(
+        Operation::from_argv(&argv(&["apt-get", "purge", "-y", "-o", "APT::Status-Fd=1", "nvidia-driver"])),
+        Some(Operation::Remove { packages: argv(&["nvidia-driver"]), purge: true })
+    );
+    assert_eq!(Operation::from_argv(&argv(&["apt-get", "autoremove", "-y", "-o", "APT::Status-Fd=1"])), Some(Operation::Autoremove));
+    assert_eq!(
+        Operation::from_argv(&argv(&["apt-get", "install", "-y", "-o", "APT::Status-Fd=1", "--allow-downgrades", "vim=2:9.0.1378-2"])),
+        Some(Operation::Downgrade { packages: argv(&["vim=2:9.0.1378-2"]) })
+    );
+}
+
+#[test]
+fn refuses_other_programs_and_options() {
+    assert_eq!(Operation::from_argv(&argv(&[])), None);
+    assert_eq!(Operation::from_argv(&argv(&["sh", "-c", "id"])), None);
+    assert_eq!(Operation::from_argv(&argv(&["dpkg", "-i", "/tmp/evil.deb"])), None);
+    assert_eq!(Operation::from_argv(&argv(&["/tmp/apt-get", "update"])), None);
+    assert_eq!(Operation::from_argv(&argv(&["apt-get", "install", "-o", "APT::Update::Pre-Invoke::=id", "vim"])), None);
+    assert_eq!(Operation::from_argv(&argv(&["apt-get", "install", "-y", "--allow-unauthenticated", "vim"])), None);
+    assert_eq!(Operation::from_argv(&argv(&["apt-get", "update", "vim"])), None);
+}
+
+#[test]
+fn validates_package_arguments() {
+    assert!(is_package_arg("linux-image-amd64"));
+    assert!(is_package_arg("libc6:i386"));
+    assert!(is_package_arg("firefox-esr=115.9.0esr-1~deb12u1"));
+    assert!(is_package_arg("mesa-utils/bookworm-backports"));
+    assert!(is_package_arg("gnome-shell+"));
+    assert!(!is_package_arg("--force-all"));
+    assert!(!is_package_arg("-oDpkg::Pre-Invoke=id"));
+    assert!(!is_package_arg("./evil.deb"));
+    assert!(!is_package_arg("vim=-1"));
+
+    let operation = Operation::Install { packages: argv(&["vim", "-oAPT::Update::Pre-Invoke=id"]), only_upgrade: false };
+    assert!(operation.validate().is_err());
+    assert!(Operation::Remove { packages: Vec::new(), purge: false }.validate().is_err());
+    assert!(Operation::Downgrade { packages: argv(&["vim"]) }.validate().is_err());
+    assert!(Operation::Downgrade { packages: argv(&["vim=2:9.0.1378-2"]) }.validate().is_ok());
+}
+
+#[test]
+fn only_writes_apt_sources_files() {
+    assert!(is_sources_path("/etc/apt/sources.list"));
+    assert!(is_sources_path("/etc/apt/sources.list.d/vscode.list"));
+    assert!(is_sources_path("/etc/apt/sources.list.d/extrepo_debian_official.sources"));
+    assert!(!is_sources_path("/etc/apt/sources.list.d/../apt.conf.d/99evil.list"));
+    assert!(!is_sources_path("/etc/apt/apt.conf.d/99evil"));
+    assert!(!is_sources_path("/etc/apt/sources.list.d/.list"));
+    assert!(!is_sources_path("/etc/sudoers"));
+}
+
+#[test]
+fn the_helper_builds_its_own_command_lines() {
+    let install = Operation::Install { packages: argv(&["vim"]), only_upgrade: false };
+    assert_eq!(install.argv(), Some(argv(&["apt-get", "install", "-y", "-o", "APT::Status-Fd=1", "--", "vim"])));
+    assert_eq!(install.polkit_action(), ACTION_INSTALL);
+
+    let remove = Operation::Remove { packages: argv(&["vim"]), purge: false };
+    assert_eq!(remove.polkit_action(), ACTION_MANAGE);
+    let downgrade = Operation::Downgrade { packages: argv(&["vim=2:9.0.1378-2"]) };
+    assert_eq!(downgrade.argv(), Some(argv(&["apt-get", "install", "-y", "-o", "APT::Status-Fd=1", "--allow-downgrades", "--", "vim=2:9.0.1378-2"])));
+    assert_eq!(downgrade.polkit_action(), ACTION_MANAGE);
+    let sources = Operation::WriteSources { path: "/etc/apt/sources.list".to_string(), content: String::new() };
+    assert_eq!(sources.polkit_action(), ACTION_MANAGE);
+    assert_eq!(sources.argv(), None);
+}
+
+#[test]
+fn calls_round_trip_over_d_bus() {
+    let operations = [
+        Operation::Update,
+        Operation::Install { packages: argv(&["vim", "git"]), only_upgrade: true },
+        Operation::Remove { packages: argv(&["vim"]), purge: true },
+        Operation::Downgrade { packages: argv(&["vim=2:9.0.1378-2"]) },
+        Operation::WriteSources { path: "/etc/apt/sources.list".to_string(), content: "deb http://deb.debian.org/debian bookworm main\n".to_string() },
+    ];
+    for operation in operations {
+        let (method, params) = operation.to_call();
+        assert_eq!(Operation::from_call(method, &params), Some(operation));
+    }
+    assert_eq!(Operation::from_call("Run", &(argv(&["sh"]),).to_variant()), None);
+}
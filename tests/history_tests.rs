@@ -0,0 +1,126 @@
+// tests/history_tests.rs
+use meaupdater::apt::{parse_simulation, PackageRequest};
+use meaupdater::history::{
+    downgrade_request, format_entry, format_origins, is_latest_change, parse_entry, parse_origins, ChangeAction, HistoryEntry,
+};
+use meaupdater::journal::OperationKind;
+use std::collections::HashMap;
+
+#[test]
+fn entries_separate_upgrades_new_packages_and_removals() {
+    let plan = parse_simulation(
+        "Inst linux-image-amd64 [6.1.76-1] (6.1.85-1 Debian:12.5/stable [amd64])\n\
+         Inst linux-image-6.1.0-20-amd64 (6.1.85-1 Debian:12.5/stable [amd64])\n\
+         Remv firmware-old [1.0]\n",
+    );
+    let entry = HistoryEntry::from_plan(OperationKind::Install, &plan, true, 1_700_000_000);
+
+    let upgraded: Vec<&str> = entry.changes_of(ChangeAction::Upgraded).map(|c| c.name.as_str()).collect();
+    let installed: Vec<&str> = entry.changes_of(ChangeAction::Installed).map(|c| c.name.as_str()).collect();
+    let removed: Vec<&str> = entry.changes_of(ChangeAction::Removed).map(|c| c.name.as_str()).collect();
+    assert_eq!(upgraded, vec!["linux-image-amd64"]);
+    assert_eq!(installed, vec!["linux-image-6.1.0-20-amd64"]);
+    assert_eq!(removed, vec!["firmware-old"]);
+    assert_eq!(entry.summary(), "1 upgraded, 1 newly installed, 1 removed");
+}
+
+#[test]
+fn entries_round_trip() {
+    let plan = parse_simulation("Inst bash [5.2.15-2] (1:5.2.15-2+b2 Debian:12.5/stable [amd64])\nInst newdep (2.0 Debian:12.5/stable [amd64])\n");
+    let entry = HistoryEntry::from_plan(OperationKind::KernelInstall, &plan, false, 42);
+    let line = format_entry(&entry);
+    assert_eq!(line, "42\tkernel-install\tfailed\tupgraded bash 5.2.15-2 1:5.2.15-2+b2,installed newdep - 2.0");
+    assert_eq!(parse_entry(&line), Some(entry));
+
+    assert_eq!(parse_entry("42\tunknown-kind\tok\t"), None);
+    assert_eq!(parse_entry("garbage"), None);
+}
+
+#[test]
+fn origins_round_trip() {
+    let mut origins = HashMap::new();
+    origins.insert("bash".to_string(), "deb.debian.org/debian bookworm/main".to_string());
+    origins.insert("node".to_string(), "deb.nodesource.com/node_20.x nodistro/main".to_string());
+    assert_eq!(parse_origins(&format_origins(&origins)), origins);
+    assert!(parse_origins("garbage\n").is_empty());
+}
+
+#[test]
+fn older_versions_are_recorded_as_downgrades() {
+    let plan = parse_simulation("Inst bash [5.2.15-3] (5.2.15-2 Debian:12.5/stable [amd64])\n");
+    let entry = HistoryEntry::from_plan(OperationKind::Downgrade, &plan, true, 42);
+    assert_eq!(entry.changes_of(ChangeAction::Downgraded).count(), 1);
+    assert_eq!(entry.summary(), "1 downgraded");
+
+    let line = format_entry(&entry);
+    assert_eq!(line, "42\tdowngrade\tok\tdowngraded bash 5.2.15-3 5.2.15-2");
+    assert_eq!(parse_entry(&line), Some(entry));
+}
+
+#[test]
+fn only_the_latest_upgrade_can_be_undone() {
+    let older = HistoryEntry::from_plan(OperationKind::Install, &parse_simulation("Inst bash [5.2.15-1] (5.2.15-2 Debian:12.5/stable [amd64])\n"), true, 10);
+    let newer = HistoryEntry::from_plan(OperationKind::Install, &parse_simulation("Inst bash [5.2.15-2] (5.2.15-3 Debian:12.5/stable [amd64])\nInst newdep (2.0 Debian:12.5/stable [amd64])\n"), true, 20);
+    let failed = HistoryEntry::from_plan(OperationKind::Install, &parse_simulation("Inst bash [5.2.15-3] (5.2.15-4 Debian:12.5/stable [amd64])\n"), false, 30);
+    let entries = vec![failed, newer.clone(), older];
+
+    assert!(is_latest_change(&entries, 20, "bash"));
+    assert!(!is_latest_change(&entries, 10, "bash"));
+    assert!(!is_latest_change(&entries, 30, "bash"));
+
+    let requests: Vec<PackageRequest> = newer.changes.iter().filter_map(downgrade_request).collect();
+    assert_eq!(requests, vec![PackageRequest::version("bash", "5.2.15-2")]);
+    assert_eq!(requests[0].to_arg(), "bash=5.2.15-2");
+}
+
+#[test]
+fn kernel_and_driver_notes_survive_a_round_trip() {
+    let default_kernel = HistoryEntry {
+        timestamp: 42,
+        kind: OperationKind::GrubUpdate,
+        succeeded: true,
+        changes: Vec::new(),
+        notes: vec![
+            "Default kernel: 6.1.0-18-amd64".to_string(),
+            "GRUB entry: Advanced options for Debian GNU/Linux>Debian GNU/Linux, with Linux 6.1.0-18-amd64".to_string(),
+        ],
+    };
+    let line = format_entry(&default_kernel);
+    assert_eq!(parse_entry(&line), Some(default_kernel.clone()));
+    assert_eq!(default_kernel.summary(), "Default kernel: 6.1.0-18-amd64");
+
+    let plan = parse_simulation("Inst nvidia-driver (535.183.01-1 Debian:12.5/stable [amd64])\n");
+    let mut driver = HistoryEntry::from_plan(OperationKind::DriverInstall, &plan, true, 43);
+    driver.notes = vec!["Backup:\t/home/user/.local/state/meaupdater/driver-backups/43".to_string()];
+    let parsed = parse_entry(&format_entry(&driver)).unwrap();
+    assert_eq!(parsed.notes, vec!["Backup: /home/user/.local/state/meaupdater/driver-backups/43"]);
+    assert_eq!(parsed.summary(), "1 newly installed");
+}
+
+#[test]
+fn entries_without_notes_still_parse() {
+    let entry = parse_entry("42\tkernel-remove\tok\tremoved linux-image-6.1.0-17-amd64 6.1.69-1 -").unwrap();
+    assert!(entry.notes.is_empty());
+    assert_eq!(entry.changes.len(), 1);
+}
+
+#[test]
+fn grub_backup_is_offered_for_successful_default_kernel_changes() {
+    use meaupdater::history::grub_backup;
+
+    let mut entry = HistoryEntry {
+        timestamp: 42,
+        kind: OperationKind::GrubUpdate,
+        succeeded: true,
+        changes: Vec::new(),
+        notes: vec!["Default kernel: 6.1.0-18-amd64".to_string(), "Backup: /etc/default/grub.backup-20240915-213000".to_string()],
+    };
+    assert_eq!(grub_backup(&entry), Some("/etc/default/grub.backup-20240915-213000"));
+
+    entry.succeeded = false;
+    assert_eq!(grub_backup(&entry), None);
+
+    entry.succeeded = true;
+    entry.kind = OperationKind::DriverInstall;
+    assert_eq!(grub_backup(&entry), None);
+}
@@ -0,0 +1,48 @@
+// tests/icons_tests.rs
+use meaupdater::driver_manager::{DriverInfo, DriverLicense, DriverType};
+use meaupdater::icons::{has_color_emoji, style_override, Icon};
+
+#[test]
+fn style_from_environment() {
+    assert_eq!(style_override(Some("emoji")), Some(true));
+    assert_eq!(style_override(Some("symbolic")), Some(false));
+    assert_eq!(style_override(Some("fancy")), None);
+    assert_eq!(style_override(None), None);
+}
+
+#[test]
+fn emoji_font_from_fc_list() {
+    assert!(has_color_emoji("Noto Color Emoji\n"));
+    assert!(!has_color_emoji(""));
+    assert!(!has_color_emoji("\n"));
+}
+
+#[test]
+fn icons_have_symbolic_fallbacks() {
+    for icon in [Icon::Security, Icon::Kernel, Icon::Installed, Icon::NonFreeLicense, Icon::Device] {
+        assert!(icon.icon_name().ends_with("-symbolic"));
+        assert!(!icon.emoji().is_empty());
+        assert!(!icon.description().is_empty());
+    }
+}
+
+#[test]
+fn driver_icons_follow_state() {
+    let mut driver = DriverInfo::new(
+        "firmware-realtek".to_string(),
+        "Realtek WiFi Firmware".to_string(),
+        "firmware-realtek".to_string(),
+        "20230210-5".to_string(),
+        DriverType::Network,
+        DriverLicense::NonFree,
+        "Realtek".to_string(),
+        String::new(),
+    );
+    assert_eq!(driver.status_icon(), Icon::Available);
+    assert_eq!(driver.type_icon(), Icon::Network);
+    assert_eq!(driver.license_icon(), Icon::NonFreeLicense);
+    driver.is_installed = true;
+    assert_eq!(driver.status_icon(), Icon::Installed);
+    driver.is_active = true;
+    assert_eq!(driver.status_icon(), Icon::Active);
+}
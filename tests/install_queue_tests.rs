@@ -0,0 +1,16 @@
+// tests/install_queue_tests.rs
+use meaupdater::install_queue::resumable;
+
+fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn only_resumes_packages_that_are_still_upgradable() {
+    let queue = names(&["firefox-esr", "curl", "libssl3"]);
+    let available = names(&["libssl3", "curl", "tzdata"]);
+
+    assert_eq!(resumable(&queue, &available), names(&["curl", "libssl3"]));
+    assert!(resumable(&queue, &[]).is_empty());
+    assert!(resumable(&[], &available).is_empty());
+}
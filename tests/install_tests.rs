@@ -0,0 +1,41 @@
+// tests/install_tests.rs
+use meaupdater::install::{install_plan, parse_options, staged_path, InstallSource, APP_ID};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn plan_follows_prefix() {
+    let plan = install_plan(Path::new("/usr/local"), Path::new("/build/meaupdater"), None);
+
+    let desktop = plan.iter()
+        .find(|entry| entry.path == Path::new("/usr/local/share/applications").join(format!("{}.desktop", APP_ID)))
+        .expect("desktop entry");
+    match &desktop.source {
+        InstallSource::Text(text) => assert!(text.contains("Exec=/usr/local/bin/meaupdater")),
+        other => panic!("unexpected source {:?}", other),
+    }
+
+    assert!(plan.iter().any(|entry| entry.path.ends_with(format!("hicolor/256x256/apps/{}.png", APP_ID))));
+    assert!(!plan.iter().any(|entry| entry.path.starts_with("/usr/share/polkit-1")));
+}
+
+#[test]
+fn helper_files_point_at_libexec() {
+    let plan = install_plan(Path::new("/opt/mea"), Path::new("/b/meaupdater"), Some(Path::new("/b/meaupdater-helper")));
+
+    let service = plan.iter().find(|entry| entry.path.ends_with("io.github.mthakan.MeaUpdater.Helper.service")).unwrap();
+    match &service.source {
+        InstallSource::Text(text) => assert!(text.contains("Exec=/opt/mea/libexec/meaupdater-helper")),
+        other => panic!("unexpected source {:?}", other),
+    }
+    assert!(plan.iter().any(|entry| entry.path == Path::new("/usr/share/polkit-1/actions/io.github.mthakan.MeaUpdater.policy")));
+}
+
+#[test]
+fn options_and_staging() {
+    let args: Vec<String> = ["--prefix", "/usr", "--destdir", "/tmp/pkg"].iter().map(|s| s.to_string()).collect();
+    let options = parse_options(&args).unwrap();
+    assert_eq!(options.prefix, PathBuf::from("/usr"));
+    assert_eq!(staged_path(options.destdir.as_deref(), Path::new("/usr/bin/meaupdater")), PathBuf::from("/tmp/pkg/usr/bin/meaupdater"));
+
+    assert!(parse_options(&["--prefix".to_string(), "relative".to_string()]).is_err());
+}
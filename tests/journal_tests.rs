@@ -0,0 +1,43 @@
+// tests/journal_tests.rs
+use meaupdater::boot_platform::BootPlatform;
+use meaupdater::journal::{format_entry, parse_entry, recovery_steps, JournalEntry, OperationKind};
+
+#[test]
+fn entry_round_trips() {
+    let entry = JournalEntry {
+        kind: OperationKind::KernelRemove,
+        detail: "linux-image-6.1.0-17-amd64".to_string(),
+        started: 1_700_000_000,
+    };
+    assert_eq!(parse_entry(&format_entry(&entry)), Some(entry));
+    assert_eq!(parse_entry("kind=unknown\n"), None);
+}
+
+#[test]
+fn recovery_matches_operation() {
+    let argv = |kind: OperationKind| -> Vec<String> {
+        recovery_steps(&[kind], &BootPlatform::Grub).iter().map(|s| s.argv.join(" ")).collect()
+    };
+
+    assert_eq!(argv(OperationKind::Install), vec!["dpkg --configure -a", "apt-get install -f -y"]);
+    assert!(argv(OperationKind::KernelRemove).contains(&"apt-get autoremove -y".to_string()));
+    assert!(argv(OperationKind::KernelRemove).contains(&"update-grub".to_string()));
+    assert_eq!(argv(OperationKind::GrubUpdate), vec!["update-grub"]);
+}
+
+#[test]
+fn overlapping_operations_recover_once() {
+    let argv: Vec<String> = recovery_steps(&[OperationKind::KernelInstall, OperationKind::GrubUpdate], &BootPlatform::Grub)
+        .iter()
+        .map(|s| s.argv.join(" "))
+        .collect();
+    assert_eq!(argv, vec!["dpkg --configure -a", "apt-get install -f -y", "update-grub"]);
+}
+
+#[test]
+fn boards_without_grub_skip_update_grub() {
+    let steps = recovery_steps(&[OperationKind::KernelRemove], &BootPlatform::OtherArm);
+    assert!(steps.iter().all(|s| s.argv != ["update-grub"]));
+    assert!(!steps.is_empty());
+    assert!(recovery_steps(&[OperationKind::GrubUpdate], &BootPlatform::RaspberryPi { firmware_dir: "/boot/firmware".to_string() }).is_empty());
+}
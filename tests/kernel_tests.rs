@@ -0,0 +1,124 @@
+// tests/kernel_tests.rs
+mod common;
+
+use meaupdater::kernel_manager::{
+    cleanup_candidates, dir_size, expand_kernel_set, is_safe_kernel_version, kernel_update_set, merge_kernel_set, newer_installed_kernel,
+    parse_dpkg_search, KernelInfo,
+};
+use meaupdater::model::UpdateType;
+use std::fs;
+use common::update;
+
+fn installed(version: &str, current: bool) -> KernelInfo {
+    let mut kernel = KernelInfo::new(&format!("linux-image-{}", version), version, true);
+    kernel.is_current = current;
+    kernel
+}
+
+#[test]
+fn cleanup_keeps_running_and_newest_kernels() {
+    let kernels = vec![
+        installed("6.1.0-18-amd64", false),
+        installed("6.1.0-9-amd64", true),
+        installed("6.1.0-21-amd64", false),
+        installed("5.10.0-28-amd64", false),
+        KernelInfo::new("linux-image-6.12.9-amd64", "6.12.9-amd64", false),
+    ];
+
+    let candidates: Vec<&str> = cleanup_candidates(&kernels).iter().map(|kernel| kernel.version.as_str()).collect();
+    assert_eq!(candidates, vec!["6.1.0-18-amd64", "5.10.0-28-amd64"]);
+}
+
+#[test]
+fn nothing_to_clean_with_a_single_kernel() {
+    let kernels = vec![installed("6.1.0-21-amd64", true)];
+    assert!(cleanup_candidates(&kernels).is_empty());
+}
+
+#[test]
+fn dir_size_adds_up_nested_files() {
+    let root = std::env::temp_dir().join(format!("meaupdater-dir-size-{}", std::process::id()));
+    fs::create_dir_all(root.join("kernel/drivers")).unwrap();
+    fs::write(root.join("modules.dep"), vec![0u8; 100]).unwrap();
+    fs::write(root.join("kernel/drivers/e1000e.ko"), vec![0u8; 250]).unwrap();
+
+    assert_eq!(dir_size(&root), 350);
+    assert_eq!(dir_size(&root.join("missing")), 0);
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn owned_module_dirs_come_from_dpkg_search() {
+    let output = "linux-image-6.1.0-21-amd64: /lib/modules/6.1.0-21-amd64\n\
+                  linux-image-6.12.9-amd64, linux-modules-6.12.9: /usr/lib/modules/6.12.9-amd64\n\
+                  diversion by foo from: /lib/modules/bar\n";
+    let owned = parse_dpkg_search(output);
+    assert!(owned.contains("/lib/modules/6.1.0-21-amd64"));
+    assert!(owned.contains("/usr/lib/modules/6.12.9-amd64"));
+    assert!(!owned.contains("/lib/modules/bar"));
+    assert_eq!(owned.len(), 2);
+}
+
+#[test]
+fn only_plain_kernel_versions_are_deletable() {
+    assert!(is_safe_kernel_version("6.1.0-18-amd64"));
+    assert!(is_safe_kernel_version("5.15.0-91-generic"));
+    assert!(is_safe_kernel_version("6.8.0+custom_1"));
+    assert!(!is_safe_kernel_version(""));
+    assert!(!is_safe_kernel_version(".."));
+    assert!(!is_safe_kernel_version("6.1'; rm -rf /; '"));
+    assert!(!is_safe_kernel_version("6.1/../../etc"));
+}
+
+#[test]
+fn reboot_is_pending_only_for_a_newer_kernel() {
+    let kernels = vec![installed("6.1.0-18-amd64", true), installed("6.1.0-21-amd64", false)];
+    assert_eq!(newer_installed_kernel(&kernels, "6.1.0-18-amd64"), Some("6.1.0-21-amd64".to_string()));
+    assert_eq!(newer_installed_kernel(&kernels, "6.1.0-21-amd64"), None);
+
+    // Booted into something newer than the packaged kernels, e.g. a custom build
+    assert_eq!(newer_installed_kernel(&kernels, "6.12.9-custom"), None);
+    assert_eq!(newer_installed_kernel(&[], "6.1.0-18-amd64"), None);
+}
+
+#[test]
+fn kernel_packages_form_one_update() {
+    let packages = vec![
+        update("linux-headers-amd64", UpdateType::Kernel, Some(1_000)),
+        update("bash", UpdateType::Software, Some(50)),
+        update("linux-image-6.1.0-20-amd64", UpdateType::Security, Some(60_000)),
+        update("linux-image-amd64", UpdateType::Kernel, None),
+        update("linux-headers-6.1.0-20-amd64", UpdateType::Kernel, Some(1_500)),
+    ];
+    let set = kernel_update_set(&packages);
+    assert_eq!(set, vec!["linux-image-amd64", "linux-image-6.1.0-20-amd64", "linux-headers-amd64", "linux-headers-6.1.0-20-amd64"]);
+
+    let merged = merge_kernel_set(packages, &set);
+    assert_eq!(merged.len(), 2);
+    let kernel = merged.iter().find(|pkg| pkg.name == "linux-image-amd64").unwrap();
+    assert_eq!(kernel.size, Some(62_500));
+    assert_eq!(kernel.update_type, UpdateType::Security);
+
+    let selected = expand_kernel_set(vec!["bash".to_string(), "linux-image-amd64".to_string()], &set);
+    assert_eq!(selected.len(), 5);
+    assert_eq!(expand_kernel_set(vec!["bash".to_string()], &set), vec!["bash"]);
+}
+
+#[test]
+fn a_single_kernel_package_stays_on_its_own() {
+    let packages = vec![update("linux-firmware", UpdateType::Firmware, None), update("bash", UpdateType::Software, None)];
+    assert!(kernel_update_set(&packages).is_empty());
+    assert_eq!(merge_kernel_set(packages.clone(), &[]), packages);
+}
+
+#[test]
+fn only_grub_backups_can_be_restored() {
+    use meaupdater::kernel_manager::is_grub_backup;
+
+    assert!(is_grub_backup("/etc/default/grub.backup-20240915-213000"));
+    assert!(!is_grub_backup("/etc/default/grub.backup-"));
+    assert!(!is_grub_backup("/etc/default/grub.backup-../../shadow"));
+    assert!(!is_grub_backup("/etc/shadow"));
+    assert!(!is_grub_backup("/etc/default/grub.backup-1; rm -rf /"));
+}
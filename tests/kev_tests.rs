@@ -0,0 +1,42 @@
+// tests/kev_tests.rs
+use meaupdater::kev::{cve_ids, exploited_cves, fixed_cves, parse_catalog};
+
+const CATALOG: &str = r#"{
+    "title": "CISA Catalog of Known Exploited Vulnerabilities",
+    "count": 2,
+    "vulnerabilities": [
+        {"cveID": "CVE-2024-3094", "vendorProject": "XZ Utils", "product": "XZ Utils"},
+        {"cveID": "CVE-2023-4863", "vendorProject": "Google", "product": "Chromium WebP"}
+    ]
+}"#;
+
+#[test]
+fn parses_catalog_ids() {
+    let catalog = parse_catalog(CATALOG).unwrap();
+    assert_eq!(catalog.len(), 2);
+    assert!(catalog.contains("CVE-2024-3094"));
+    assert!(parse_catalog("<html>Sign in to the network</html>").is_err());
+}
+
+#[test]
+fn finds_cves_in_changelog_entries() {
+    let entries = "xz-utils (5.4.1-0.2) bookworm-security; urgency=high\n\n  \
+                   * Fix backdoor (CVE-2024-3094, cve-2023-4863).\n  \
+                   * Mention of CVE-2024-3094 again and of CVE-24-1 which is malformed.\n";
+    assert_eq!(cve_ids(entries), vec!["CVE-2023-4863", "CVE-2024-3094"]);
+}
+
+#[test]
+fn only_catalogued_cves_are_exploited() {
+    let catalog = parse_catalog(CATALOG).unwrap();
+    assert_eq!(exploited_cves("Fixes CVE-2024-3094 and CVE-2024-0001", &catalog), vec!["CVE-2024-3094"]);
+    assert!(exploited_cves("No security fixes", &catalog).is_empty());
+}
+
+#[test]
+fn fixed_cves_come_from_the_new_entries_only() {
+    let changelog = "openssl (3.0.15-1~deb12u1) bookworm-security; urgency=medium\n\n  * Fix CVE-2024-5535 and cve-2024-6119.\n\n -- Debian Security Team <team@security.debian.org>  Mon, 02 Sep 2024 10:00:00 +0000\n\n\
+openssl (3.0.14-1~deb12u2) bookworm; urgency=medium\n\n  * Fix CVE-2024-4741.\n\n -- Debian Security Team <team@security.debian.org>  Mon, 01 Jul 2024 10:00:00 +0000\n";
+    assert_eq!(fixed_cves(changelog, "3.0.14-1~deb12u2"), vec!["CVE-2024-5535", "CVE-2024-6119"]);
+    assert_eq!(fixed_cves(changelog, "").len(), 3);
+}
@@ -0,0 +1,16 @@
+// tests/log_filter_tests.rs
+#![cfg(feature = "gui")]
+use meaupdater::log_filter::match_ranges;
+
+#[test]
+fn finds_case_insensitive_matches() {
+    assert_eq!(match_ranges("Setting up libssl3:amd64 (3.0.11)", "LIBSSL"), vec![(11, 17)]);
+    assert_eq!(match_ranges("aaaa", "aa"), vec![(0, 2), (2, 4)]);
+    assert!(match_ranges("Unpacking curl", "wget").is_empty());
+    assert!(match_ranges("anything", "").is_empty());
+}
+
+#[test]
+fn offsets_are_characters() {
+    assert_eq!(match_ranges("✅ Güncelleme tamam", "tamam"), vec![(13, 18)]);
+}
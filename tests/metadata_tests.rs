@@ -0,0 +1,35 @@
+// tests/metadata_tests.rs
+use meaupdater::metadata::{MetadataCache, Stamp};
+use std::time::{Duration, Instant, SystemTime};
+
+fn argv(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn answers_are_reused_until_the_ttl_runs_out() {
+    let mut cache = MetadataCache::new(Duration::from_secs(300));
+    let now = Instant::now();
+    let stamp: Stamp = (Some(SystemTime::UNIX_EPOCH), None);
+    let search = argv(&["apt", "search", "^linux-image-[0-9]"]);
+
+    assert_eq!(cache.get(&search, now, stamp), None);
+    cache.insert(search.clone(), "linux-image-6.1.0-18-amd64/stable".to_string(), now, stamp);
+
+    assert_eq!(cache.get(&search, now + Duration::from_secs(299), stamp).as_deref(), Some("linux-image-6.1.0-18-amd64/stable"));
+    assert_eq!(cache.get(&search, now + Duration::from_secs(300), stamp), None);
+    assert_eq!(cache.get(&argv(&["apt", "search", "nvidia-driver"]), now, stamp), None);
+}
+
+#[test]
+fn changed_package_lists_invalidate_answers() {
+    let mut cache = MetadataCache::new(Duration::from_secs(300));
+    let now = Instant::now();
+    let before: Stamp = (Some(SystemTime::UNIX_EPOCH), Some(SystemTime::UNIX_EPOCH));
+    let updated: Stamp = (Some(SystemTime::UNIX_EPOCH + Duration::from_secs(60)), before.1);
+    let policy = argv(&["apt-cache", "policy", "bash"]);
+
+    cache.insert(policy.clone(), "bash:".to_string(), now, before);
+    assert_eq!(cache.get(&policy, now, updated), None);
+    assert!(cache.get(&policy, now, before).is_some());
+}
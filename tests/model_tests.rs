@@ -0,0 +1,27 @@
+// tests/model_tests.rs
+mod common;
+
+use meaupdater::model::{sort_by_size, total_size, UpdateType};
+use common::update;
+
+#[test]
+fn sizes_sort_numerically() {
+    // "9.0 KB" sorts after "10.0 MB" as text; the byte counts do not
+    let mut packages = vec![
+        update("small", UpdateType::Software, Some(9 * 1024)),
+        update("unknown", UpdateType::Software, None),
+        update("large", UpdateType::Software, Some(10 * 1024 * 1024)),
+        update("also-small", UpdateType::Software, Some(9 * 1024)),
+    ];
+    sort_by_size(&mut packages);
+    let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["large", "also-small", "small", "unknown"]);
+}
+
+#[test]
+fn totals_count_unknown_sizes() {
+    let packages = vec![update("a", UpdateType::Software, Some(1_000)), update("b", UpdateType::Software, Some(500)), update("c", UpdateType::Software, None)];
+    let selected = vec!["a".to_string(), "c".to_string()];
+    assert_eq!(total_size(&packages, &selected), (1_000, 1));
+    assert_eq!(total_size(&packages, &[]), (0, 0));
+}
@@ -0,0 +1,49 @@
+// tests/motd_tests.rs
+use meaupdater::motd::{add_greeting, check_root_owned, format_summary, has_greeting, remove_greeting, summarize, system_script, UpdateSummary};
+use std::path::Path;
+
+#[test]
+fn counts_upgradable_lines() {
+    let output = "Listing...\n\
+        openssl/stable-security 3.0.15-1~deb12u1 amd64 [upgradable from: 3.0.14-1~deb12u2]\n\
+        linux-image-amd64/stable 6.1.119-1 amd64 [upgradable from: 6.1.115-1]\n\
+        tzdata/stable-updates 2024b-0+deb12u1 all [upgradable from: 2024a-0+deb12u1]\n";
+
+    assert_eq!(summarize(output), UpdateSummary { total: 3, security: 1, kernel: 1 });
+    assert_eq!(
+        format_summary(&summarize(output)),
+        "MeaUpdater: 3 updates can be installed (1 security, 1 kernel)."
+    );
+    assert_eq!(format_summary(&summarize("Listing...\n")), "MeaUpdater: the system is up to date.");
+}
+
+#[test]
+fn greeting_block_is_replaced_and_removed() {
+    let rc = "alias ll='ls -l'";
+    let once = add_greeting(rc, "/usr/bin/meaupdater");
+    let twice = add_greeting(&once, "/usr/local/bin/meaupdater");
+
+    assert!(has_greeting(&twice));
+    assert_eq!(twice.matches("--motd").count(), 1);
+    assert!(twice.contains("/usr/local/bin/meaupdater"));
+    assert_eq!(remove_greeting(&twice), "alias ll='ls -l'\n");
+}
+
+#[test]
+fn system_script_quotes_the_executable() {
+    let script = system_script("/opt/mea's apps/meaupdater");
+    assert!(script.contains("[ -x '/opt/mea'\\''s apps/meaupdater' ] && '/opt/mea'\\''s apps/meaupdater' --motd"));
+}
+
+#[test]
+fn login_hook_needs_a_root_owned_executable() {
+    let dir = std::env::temp_dir().join(format!("meaupdater-motd-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let exe = dir.join("meaupdater");
+    std::fs::write(&exe, "").unwrap();
+
+    // The temporary directory is writable by everyone
+    assert!(check_root_owned(&exe).is_err());
+    assert!(check_root_owned(Path::new("/nonexistent/meaupdater")).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
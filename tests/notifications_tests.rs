@@ -0,0 +1,15 @@
+// tests/notifications_tests.rs
+use meaupdater::notifications::new_packages;
+use std::collections::HashSet;
+
+#[test]
+fn only_unseen_packages_are_new() {
+    let previous: HashSet<String> = ["bash", "openssl"].iter().map(|s| s.to_string()).collect();
+
+    assert!(new_packages(&previous, &["bash".to_string()]).is_empty());
+    assert_eq!(
+        new_packages(&previous, &["openssl".to_string(), "curl".to_string()]),
+        vec!["curl".to_string()]
+    );
+    assert!(new_packages(&previous, &[]).is_empty());
+}
@@ -0,0 +1,70 @@
+// tests/obsolete_tests.rs
+use meaupdater::obsolete_manager::{parse_local_list, parse_repository_versions, replacement_pattern, replacement_stem};
+
+#[test]
+fn parses_local_packages_from_apt_list() {
+    let output = "Listing...\n\
+        bash/stable,now 5.2.15-2+b2 amd64 [installed]\n\
+        libssl1.1/now 1.1.1n-0+deb11u5 amd64 [installed,local]\n\
+        zoom/now 5.17.5 amd64 [installed,local]\n";
+
+    let packages = parse_local_list(output);
+    assert_eq!(packages.len(), 2);
+    assert_eq!(packages[0].name, "libssl1.1");
+    assert_eq!(packages[0].version, "1.1.1n-0+deb11u5");
+    assert_eq!(packages[1].architecture, "amd64");
+}
+
+#[test]
+fn reads_repository_versions_from_policy() {
+    let output = "\
+libssl1.1:
+  Installed: 1.1.1n-0+deb11u5
+  Candidate: 1.1.1n-0+deb11u5
+  Version table:
+ *** 1.1.1n-0+deb11u5 100
+        100 /var/lib/dpkg/status
+firefox-esr:
+  Installed: 115.9.0esr-1~deb12u1
+  Candidate: 115.10.0esr-1~deb12u1
+  Version table:
+     115.10.0esr-1~deb12u1 500
+        500 http://security.debian.org/debian-security bookworm-security/main amd64 Packages
+ *** 115.9.0esr-1~deb12u1 100
+        100 /var/lib/dpkg/status
+mesa-utils:
+  Installed: 9.0.0-1~local
+  Candidate: 9.0.0-1~local
+  Version table:
+ *** 9.0.0-1~local 100
+        100 /var/lib/dpkg/status
+     8.5.0-1 500
+        500 http://deb.debian.org/debian bookworm/main amd64 Packages
+zoom:
+  Installed: 5.17.5
+  Candidate: (none)
+";
+
+    let versions = parse_repository_versions(output);
+    assert_eq!(versions.get("firefox-esr").map(String::as_str), Some("115.10.0esr-1~deb12u1"));
+    // Only dpkg knows this version
+    assert!(!versions.contains_key("libssl1.1"));
+    // The newer local build is the candidate, yet the repository still has the package
+    assert_eq!(versions.get("mesa-utils").map(String::as_str), Some("8.5.0-1"));
+    assert!(!versions.contains_key("zoom"));
+}
+
+#[test]
+fn strips_versions_for_replacement_search() {
+    assert_eq!(replacement_stem("libssl1.1"), "libssl");
+    assert_eq!(replacement_stem("python3.9-minimal"), "python");
+    assert_eq!(replacement_stem("gcc-10"), "gcc");
+    assert_eq!(replacement_stem("vim"), "vim");
+}
+
+#[test]
+fn escapes_regex_characters_in_replacement_pattern() {
+    assert_eq!(replacement_pattern("libstdc++"), "^libstdc\\+\\+");
+    assert_eq!(replacement_pattern("libssl"), "^libssl");
+    assert_eq!(replacement_pattern("libgtk-3"), "^libgtk-3");
+}
@@ -0,0 +1,76 @@
+// tests/offline_update_tests.rs
+use meaupdater::offline_update::{self, OfflineStatus, STAGING_DIR, TRIGGER};
+
+fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn manifest_round_trips() {
+    let packages = names(&["openssl", "libssl3"]);
+    let text = offline_update::manifest_text(&packages);
+    assert_eq!(text, "openssl\nlibssl3\n");
+    assert_eq!(offline_update::parse_manifest(&text), packages);
+    assert!(offline_update::parse_manifest("\n  \n").is_empty());
+}
+
+#[test]
+fn staged_while_the_trigger_points_to_the_staging_directory() {
+    let status = offline_update::parse_status(Some(STAGING_DIR), Some("vim\ncurl\n"), None);
+    assert_eq!(status, OfflineStatus::Staged(names(&["vim", "curl"])));
+    assert_eq!(status.indicator_label().as_deref(), Some("⏳ 2 updates staged for restart"));
+}
+
+#[test]
+fn trigger_of_another_tool_is_not_ours() {
+    let status = offline_update::parse_status(Some("/var/cache/PackageKit"), Some("vim\n"), None);
+    assert_eq!(status, OfflineStatus::Idle);
+    assert_eq!(status.indicator_label(), None);
+}
+
+#[test]
+fn failed_run_is_reported_until_dismissed() {
+    let status = offline_update::parse_status(None, None, Some("100\n"));
+    assert_eq!(status, OfflineStatus::Failed { log: format!("{}/apply.log", STAGING_DIR) });
+    assert_eq!(status.indicator_label().as_deref(), Some("⚠️ Offline update failed"));
+
+    assert_eq!(offline_update::parse_status(None, None, Some("0\n")), OfflineStatus::Idle);
+}
+
+#[test]
+fn apply_script_removes_the_trigger_before_installing() {
+    let script = offline_update::apply_script();
+    let trigger = script.find(&format!("rm -f {}", TRIGGER)).unwrap();
+    let install = script.find("apt-get install").unwrap();
+    assert!(trigger < install);
+    assert!(script.contains("--no-download"));
+    assert!(script.contains("systemctl \"$action\""));
+}
+
+#[test]
+fn unit_runs_only_with_the_trigger() {
+    let unit = offline_update::unit_file();
+    assert!(unit.contains(&format!("ConditionPathExists={}", TRIGGER)));
+    assert!(unit.contains(&format!("ExecStart=/bin/sh {}/apply.sh", STAGING_DIR)));
+}
+
+#[test]
+fn stage_script_sets_the_trigger_last() {
+    let script = offline_update::stage_script(&names(&["vim"]));
+    assert!(script.starts_with("set -e\n"));
+    assert!(script.contains(&format!("cat > {}/packages <<'MEAUPDATER_EOF'\nvim\nMEAUPDATER_EOF\n", STAGING_DIR)));
+    assert!(script.trim_end().ends_with(&format!("ln -sfn {} {}", STAGING_DIR, TRIGGER)));
+}
+
+#[test]
+fn staging_downloads_into_the_staging_directory() {
+    let transaction = offline_update::stage_transaction(&names(&["vim"]));
+    let argv = transaction.argv();
+    assert!(argv.contains(&"--download-only".to_string()));
+    assert!(argv.contains(&format!("Dir::Cache::archives={}", offline_update::archives_dir())));
+    assert_eq!(transaction.packagekit_action(), None);
+
+    let commands = transaction.commands(false);
+    assert!(commands[0].starts_with("mkdir -p"));
+    assert!(commands.last().unwrap().starts_with("sh -c"));
+}
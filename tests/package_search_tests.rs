@@ -0,0 +1,31 @@
+// tests/package_search_tests.rs
+use meaupdater::package_search::{file_pattern, parse_file_results, parse_name_results};
+
+#[test]
+fn parses_name_search() {
+    let results = parse_name_results("htop - interactive processes viewer\nbtop - Modern and colorful command line resource monitor\n");
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].package, "htop");
+    assert_eq!(results[1].detail, "Modern and colorful command line resource monitor");
+}
+
+#[test]
+fn parses_file_search() {
+    let output = "imagemagick-6.q16: /usr/bin/convert-im6.q16\n\
+        graphicsmagick-imagemagick-compat: /usr/bin/convert\n\
+        diversion by dash from: /bin/sh\n\
+        libc6:amd64, libc6:i386: /usr/share/doc/libc6\n";
+
+    let results = parse_file_results(output);
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[1].package, "graphicsmagick-imagemagick-compat");
+    assert_eq!(results[1].detail, "/usr/bin/convert");
+    assert_eq!(results[3].package, "libc6:i386");
+}
+
+#[test]
+fn bare_words_search_commands() {
+    assert_eq!(file_pattern("convert"), (true, "/s?bin/convert$".to_string()));
+    assert_eq!(file_pattern("g++"), (true, "/s?bin/g\\+\\+$".to_string()));
+    assert_eq!(file_pattern("/etc/hosts"), (false, "/etc/hosts".to_string()));
+}
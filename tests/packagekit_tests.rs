@@ -0,0 +1,65 @@
+// tests/packagekit_tests.rs
+use meaupdater::error::Error;
+use meaupdater::packagekit::{error_for, finish_result, parse_package_id, progress, select_ids, status_text, Action, PackageId};
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn parses_package_ids() {
+    assert_eq!(
+        parse_package_id("bash;5.2.15-2+b7;amd64;installed:debian-bookworm-main"),
+        Some(PackageId {
+            name: "bash".to_string(),
+            version: "5.2.15-2+b7".to_string(),
+            arch: "amd64".to_string(),
+            data: "installed:debian-bookworm-main".to_string(),
+        })
+    );
+    assert_eq!(parse_package_id("bash;5.2"), None);
+    assert_eq!(parse_package_id(";1.0;amd64;"), None);
+}
+
+#[test]
+fn selects_the_ids_to_change() {
+    let resolved = vec![
+        (1, "bash;5.2.15-2;amd64;installed:debian".to_string()),
+        (2, "curl;7.88.1-10;amd64;debian".to_string()),
+        (1, "less;590-2;amd64;installed:debian".to_string()),
+    ];
+
+    // Already current packages are skipped, as apt-get does
+    assert_eq!(
+        select_ids(Action::Install, &names(&["bash", "curl"]), &resolved).unwrap(),
+        vec!["curl;7.88.1-10;amd64;debian".to_string()]
+    );
+    assert_eq!(
+        select_ids(Action::Remove { autoremove: false }, &names(&["bash", "less"]), &resolved).unwrap(),
+        vec!["bash;5.2.15-2;amd64;installed:debian".to_string(), "less;590-2;amd64;installed:debian".to_string()]
+    );
+    assert!(select_ids(Action::Update, &names(&["bash"]), &resolved[..1]).unwrap().is_empty());
+
+    match select_ids(Action::Install, &names(&["curl", "no-such-package"]), &resolved) {
+        Err(Error::Apt(message)) => assert!(message.contains("no-such-package")),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn maps_progress_and_status() {
+    assert_eq!(progress(50, 0.2, 0.6), Some(0.4));
+    assert_eq!(progress(101, 0.2, 0.6), None);
+    assert_eq!(status_text(8), Some("Downloading packages..."));
+    assert_eq!(status_text(0), None);
+}
+
+#[test]
+fn classifies_failures() {
+    assert!(finish_result(1, &[]).is_ok());
+    assert!(matches!(finish_result(2, &[(26, "Cannot get lock".to_string())]), Err(Error::LockHeld(_))));
+    assert!(matches!(finish_result(2, &[(48, "Not authorized".to_string())]), Err(Error::PermissionDenied(_))));
+    assert!(matches!(finish_result(3, &[]), Err(Error::Other(_))));
+    assert!(matches!(error_for(2, "No network connection"), Error::Network(_)));
+    assert!(matches!(error_for(13, "Unmet dependencies"), Error::Apt(_)));
+}
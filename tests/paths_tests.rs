@@ -0,0 +1,56 @@
+// tests/paths_tests.rs
+
+use meaupdater::paths;
+use std::path::PathBuf;
+
+fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+    move |key| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string())
+}
+
+#[test]
+fn directories_default_to_home() {
+    let lookup = env(&[("HOME", "/home/ana")]);
+    assert_eq!(paths::config_dir_from(&lookup), Some(PathBuf::from("/home/ana/.config/meaupdater")));
+    assert_eq!(paths::cache_dir_from(&lookup), Some(PathBuf::from("/home/ana/.cache/meaupdater")));
+    assert_eq!(paths::state_dir_from(&lookup), Some(PathBuf::from("/home/ana/.local/state/meaupdater")));
+}
+
+#[test]
+fn xdg_variables_override_the_defaults() {
+    let lookup = env(&[
+        ("HOME", "/home/ana"),
+        ("XDG_CONFIG_HOME", "/cfg"),
+        ("XDG_CACHE_HOME", "/var/cache/ana"),
+        ("XDG_STATE_HOME", "/state"),
+        ("XDG_RUNTIME_DIR", "/run/user/1000"),
+    ]);
+    assert_eq!(paths::config_dir_from(&lookup), Some(PathBuf::from("/cfg/meaupdater")));
+    assert_eq!(paths::cache_dir_from(&lookup), Some(PathBuf::from("/var/cache/ana/meaupdater")));
+    assert_eq!(paths::state_dir_from(&lookup), Some(PathBuf::from("/state/meaupdater")));
+    assert_eq!(paths::runtime_dir_from(&lookup), Some(PathBuf::from("/run/user/1000/meaupdater")));
+}
+
+#[test]
+fn empty_variables_count_as_unset() {
+    let lookup = env(&[("HOME", "/home/ana"), ("XDG_STATE_HOME", ""), ("XDG_RUNTIME_DIR", "")]);
+    assert_eq!(paths::state_dir_from(&lookup), Some(PathBuf::from("/home/ana/.local/state/meaupdater")));
+    assert_eq!(paths::runtime_dir_from(&lookup), Some(PathBuf::from("/home/ana/.cache/meaupdater/run")));
+}
+
+#[test]
+fn nothing_without_home() {
+    let lookup = env(&[("HOME", "")]);
+    assert_eq!(paths::config_dir_from(&lookup), None);
+    assert_eq!(paths::runtime_dir_from(&lookup), None);
+    assert!(paths::user_dirs_from(&lookup).is_empty());
+}
+
+#[test]
+fn user_dirs_leave_out_the_runtime_dir() {
+    let lookup = env(&[("HOME", "/home/ana"), ("XDG_RUNTIME_DIR", "/run/user/1000")]);
+    assert_eq!(paths::user_dirs_from(&lookup), vec![
+        PathBuf::from("/home/ana/.config/meaupdater"),
+        PathBuf::from("/home/ana/.cache/meaupdater"),
+        PathBuf::from("/home/ana/.local/state/meaupdater"),
+    ]);
+}
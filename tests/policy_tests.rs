@@ -1,5 +1,13 @@
 // tests/policy_tests.rs
-use meaupdater::policy::{build_install_command, install_packages};
+mod common;
+
+use meaupdater::model::UpdateType;
+use meaupdater::policy::{
+    build_install_command, desktop_component, install_packages, session_restart_component, update_class,
+    parse_time_of_day, parse_weekday, AutoInstallPolicy, DesktopComponent, Schedule, ScheduleFrequency, UpdateClass,
+};
+use std::collections::HashSet;
+use common::update;
 
 #[test]
 fn build_command_empty() {
@@ -23,3 +31,80 @@ fn install_empty_should_error() {
     let res = install_packages(&[]);
     assert!(res.is_err());
 }
+
+#[test]
+fn desktop_component_matches_names_and_prefixes() {
+    assert_eq!(desktop_component("gnome-shell"), Some(DesktopComponent::GnomeShell));
+    assert_eq!(desktop_component("libmutter-12-0"), Some(DesktopComponent::GnomeShell));
+    assert_eq!(desktop_component("kwin-x11"), Some(DesktopComponent::Plasma));
+    assert_eq!(desktop_component("xserver-xorg-core"), Some(DesktopComponent::Xorg));
+    // Extensions are not the shell itself
+    assert_eq!(desktop_component("gnome-shell-extensions"), None);
+    assert_eq!(desktop_component("bash"), None);
+}
+
+#[test]
+fn session_restart_only_for_running_components() {
+    let processes: HashSet<String> = ["gnome-shell", "Xwayland", "gdm-session-wor"].iter().map(|p| p.to_string()).collect();
+    assert_eq!(session_restart_component("mutter", &processes), Some(DesktopComponent::GnomeShell));
+    assert_eq!(session_restart_component("xwayland", &processes), Some(DesktopComponent::Xorg));
+    assert_eq!(session_restart_component("cinnamon", &processes), None);
+    assert_eq!(session_restart_component("sddm", &processes), None);
+}
+
+#[test]
+fn classifies_kernels_and_drivers_before_security() {
+    assert_eq!(update_class(&update("linux-image-amd64", UpdateType::Kernel, None)), UpdateClass::Kernel);
+    assert_eq!(update_class(&update("nvidia-driver", UpdateType::Security, None)), UpdateClass::Drivers);
+    assert_eq!(update_class(&update("firmware-iwlwifi", UpdateType::Firmware, None)), UpdateClass::Drivers);
+    assert_eq!(update_class(&update("openssl", UpdateType::Security, None)), UpdateClass::Security);
+    assert_eq!(update_class(&update("firefox-esr", UpdateType::Software, None)), UpdateClass::Applications);
+}
+
+#[test]
+fn auto_install_holds_back_kernels_and_drivers() {
+    let packages = vec![
+        update("openssl", UpdateType::Security, None),
+        update("linux-image-amd64", UpdateType::Kernel, None),
+        update("xserver-xorg-video-amdgpu", UpdateType::Software, None),
+        update("gimp", UpdateType::Software, None),
+    ];
+    let policy = AutoInstallPolicy { security: true, applications: true, ..AutoInstallPolicy::default() };
+    let (automatic, manual) = policy.split(&packages);
+    assert_eq!(automatic, vec!["openssl", "gimp"]);
+    assert_eq!(manual, vec!["linux-image-amd64", "xserver-xorg-video-amdgpu"]);
+
+    let (automatic, _) = AutoInstallPolicy::default().split(&packages);
+    assert!(automatic.is_empty());
+}
+
+#[test]
+fn schedules_wait_for_the_next_wall_clock_time() {
+    const HOUR: u32 = 3600;
+    let interval = Schedule::default();
+    assert_eq!(interval.seconds_until_next(1800, 3, 12 * HOUR), 1800);
+
+    let daily = Schedule { frequency: ScheduleFrequency::Daily, time_of_day: 3 * 60, ..Schedule::default() };
+    assert_eq!(daily.seconds_until_next(1800, 3, HOUR), 2 * HOUR);
+    assert_eq!(daily.seconds_until_next(1800, 3, 3 * HOUR), 24 * HOUR);
+    assert_eq!(daily.seconds_until_next(1800, 3, 23 * HOUR), 4 * HOUR);
+    assert_eq!(daily.describe(30), "Daily at 03:00");
+
+    // Monday 03:00, asked on Wednesday noon and on Monday just after
+    let weekly = Schedule { frequency: ScheduleFrequency::Weekly, weekday: 1, notify_only: true, ..daily };
+    assert_eq!(weekly.seconds_until_next(1800, 3, 12 * HOUR), (4 * 24 + 15) * HOUR);
+    assert_eq!(weekly.seconds_until_next(1800, 1, 3 * HOUR + 1), 7 * 24 * HOUR - 1);
+    assert_eq!(weekly.seconds_until_next(1800, 1, 2 * HOUR), HOUR);
+    assert_eq!(weekly.describe(30), "Every Monday at 03:00, notify only");
+}
+
+#[test]
+fn schedule_times_and_days_parse() {
+    assert_eq!(parse_time_of_day("03:00"), Some(180));
+    assert_eq!(parse_time_of_day("23:59"), Some(1439));
+    assert_eq!(parse_time_of_day("24:00"), None);
+    assert_eq!(parse_time_of_day("3"), None);
+    assert_eq!(parse_weekday("Monday"), Some(1));
+    assert_eq!(parse_weekday("sun"), Some(7));
+    assert_eq!(parse_weekday("s"), None);
+}
@@ -0,0 +1,67 @@
+// tests/privilege_tests.rs
+use meaupdater::privilege::{display_server_from, session_environment_from, DisplayServer};
+use std::collections::HashMap;
+
+fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let map: HashMap<String, String> = vars.iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    move |key| map.get(key).cloned()
+}
+
+fn value<'a>(env: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    env.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+#[test]
+fn detects_display_server() {
+    assert_eq!(display_server_from(lookup(&[("WAYLAND_DISPLAY", "wayland-1"), ("DISPLAY", ":0")])), DisplayServer::Wayland);
+    assert_eq!(display_server_from(lookup(&[("DISPLAY", ":0")])), DisplayServer::X11);
+    assert_eq!(display_server_from(lookup(&[("DISPLAY", "")])), DisplayServer::Headless);
+    assert_eq!(display_server_from(lookup(&[])), DisplayServer::Headless);
+}
+
+#[test]
+fn forwards_wayland_session_variables() {
+    let env = session_environment_from(
+        lookup(&[("WAYLAND_DISPLAY", "wayland-1"), ("XDG_RUNTIME_DIR", "/run/user/1000"), ("PATH", "/usr/bin")]),
+        Some(1000),
+        Some("/home/mea"),
+    );
+
+    assert_eq!(value(&env, "WAYLAND_DISPLAY"), Some("wayland-1"));
+    assert_eq!(value(&env, "XDG_RUNTIME_DIR"), Some("/run/user/1000"));
+    assert_eq!(value(&env, "PATH"), None);
+    assert_eq!(value(&env, "XAUTHORITY"), None);
+}
+
+#[test]
+fn derives_missing_values() {
+    let env = session_environment_from(lookup(&[("DISPLAY", ":1")]), Some(1001), Some("/home/mea"));
+    assert_eq!(value(&env, "XDG_RUNTIME_DIR"), Some("/run/user/1001"));
+    assert_eq!(value(&env, "XAUTHORITY"), Some("/home/mea/.Xauthority"));
+
+    let env = session_environment_from(lookup(&[("XDG_SESSION_TYPE", "wayland")]), None, None);
+    assert_eq!(value(&env, "WAYLAND_DISPLAY"), Some("wayland-0"));
+    assert_eq!(value(&env, "XDG_RUNTIME_DIR"), None);
+}
+
+#[test]
+fn user_data_dirs_follow_xdg_variables() {
+    use meaupdater::privilege::user_data_dirs;
+    use std::path::PathBuf;
+
+    assert_eq!(
+        user_data_dirs(lookup(&[("HOME", "/home/ada")])),
+        vec![
+            PathBuf::from("/home/ada/.config/meaupdater"),
+            PathBuf::from("/home/ada/.cache/meaupdater"),
+            PathBuf::from("/home/ada/.local/state/meaupdater"),
+        ]
+    );
+    assert_eq!(
+        user_data_dirs(lookup(&[("HOME", "/home/ada"), ("XDG_CACHE_HOME", "/var/tmp/ada-cache"), ("XDG_STATE_HOME", "")]))[1..],
+        [PathBuf::from("/var/tmp/ada-cache/meaupdater"), PathBuf::from("/home/ada/.local/state/meaupdater")]
+    );
+    assert!(user_data_dirs(lookup(&[])).is_empty());
+}
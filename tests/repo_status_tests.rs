@@ -0,0 +1,74 @@
+// tests/repo_status_tests.rs
+use meaupdater::repo_status::{merge_statuses, parse_statuses, parse_update_output, status_key, RepoStatus};
+use std::collections::HashMap;
+
+const UPDATE_OUTPUT: &str = "\
+Hit:1 http://deb.debian.org/debian bookworm InRelease
+Get:2 http://deb.debian.org/debian bookworm-updates InRelease [55.4 kB]
+Get:3 http://security.debian.org/debian-security bookworm-security/main amd64 Packages [245 kB]
+Err:4 https://repo.example.com/apt stable InRelease
+  Could not resolve 'repo.example.com'
+Hit:5 https://download.vendor.com/linux/ ./ InRelease
+Hit:6 https://packages.microsoft.com/repos/code stable InRelease
+Reading package lists...
+W: GPG error: https://packages.microsoft.com/repos/code stable InRelease: The following signatures couldn't be verified because the public key is not available: NO_PUBKEY EB3E94ADBE1229CF
+E: The repository 'https://ppa.launchpadcontent.net/old/ppa/ubuntu jammy Release' does not have a Release file.
+W: Failed to fetch https://repo.example.com/apt/dists/stable/InRelease  Could not resolve 'repo.example.com'
+";
+
+#[test]
+fn update_output_gives_per_repository_outcomes() {
+    let outcomes = parse_update_output(UPDATE_OUTPUT);
+
+    assert_eq!(outcomes[&status_key("http://deb.debian.org/debian", "bookworm")], None);
+    assert_eq!(outcomes[&status_key("http://deb.debian.org/debian", "bookworm-updates")], None);
+    assert_eq!(outcomes[&status_key("http://security.debian.org/debian-security", "bookworm-security")], None);
+    assert_eq!(outcomes[&status_key("https://download.vendor.com/linux", "./")], None);
+    assert_eq!(
+        outcomes[&status_key("https://repo.example.com/apt/", "stable")].as_deref(),
+        Some("Could not resolve 'repo.example.com'")
+    );
+    assert!(outcomes[&status_key("https://packages.microsoft.com/repos/code", "stable")]
+        .as_deref()
+        .unwrap()
+        .contains("NO_PUBKEY"));
+    assert_eq!(
+        outcomes[&status_key("https://ppa.launchpadcontent.net/old/ppa/ubuntu", "jammy")].as_deref(),
+        Some("does not have a Release file")
+    );
+    assert_eq!(outcomes.len(), 7);
+}
+
+#[test]
+fn failures_keep_the_last_success() {
+    let key = status_key("https://repo.example.com/apt", "stable");
+    let mut previous = HashMap::new();
+    previous.insert(key.clone(), RepoStatus { last_attempt: 100, last_success: Some(100), error: None });
+    previous.insert("untouched".to_string(), RepoStatus { last_attempt: 50, last_success: None, error: Some("x".to_string()) });
+
+    let mut outcomes = HashMap::new();
+    outcomes.insert(key.clone(), Some("Could not resolve".to_string()));
+    let merged = merge_statuses(&previous, &outcomes, 200);
+    assert_eq!(merged[&key], RepoStatus { last_attempt: 200, last_success: Some(100), error: Some("Could not resolve".to_string()) });
+    assert_eq!(merged["untouched"], previous["untouched"]);
+
+    outcomes.insert(key.clone(), None);
+    let merged = merge_statuses(&merged, &outcomes, 300);
+    assert_eq!(merged[&key], RepoStatus { last_attempt: 300, last_success: Some(300), error: None });
+}
+
+#[test]
+fn status_file_round_trips_fields() {
+    let statuses = parse_statuses("http://deb.debian.org/debian bookworm\t300\t300\t\nhttps://x stable\t200\t\tGPG error\nbroken line\n");
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses["http://deb.debian.org/debian bookworm"].error, None);
+    assert_eq!(statuses["https://x stable"].last_success, None);
+    assert!(statuses["https://x stable"].is_stale(300));
+}
+
+#[test]
+fn flat_repositories_with_a_subdirectory_keep_their_path() {
+    let outcomes = parse_update_output("Hit:1 http://example.com/repo binary/ InRelease\nGet:2 file:/srv/local-repo ./ Release [1,234 B]\n");
+    assert_eq!(outcomes[&status_key("http://example.com/repo", "binary/")], None);
+    assert_eq!(outcomes[&status_key("file:/srv/local-repo", "./")], None);
+}
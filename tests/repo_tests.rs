@@ -0,0 +1,372 @@
+// tests/repo_tests.rs
+use meaupdater::repo_manager::{
+    component_target, detect_manager, enabled_components, is_armored_key, is_official_uri, is_public_key_packet, parse_deb822_sources, parse_dpkg_search,
+    parse_extrepo_policies, parse_extrepo_search, pending_changes, parse_release, release_urls, repository_slug, suite_candidates,
+    uri_host, ArchiveFamily, Repository, RepositoryEdit, SourceEntry, SourcesFile,
+};
+
+const DEBIAN_SOURCES: &str = "\
+#deb cdrom:[Debian GNU/Linux 12.5.0 _Bookworm_ - Official amd64 NETINST with firmware 20240210-11:27]/ bookworm contrib main non-free-firmware
+
+deb http://deb.debian.org/debian/ bookworm main non-free-firmware
+deb-src http://deb.debian.org/debian/ bookworm main non-free-firmware
+
+deb http://security.debian.org/debian-security bookworm-security main non-free-firmware
+deb-src http://security.debian.org/debian-security bookworm-security main non-free-firmware
+
+# bookworm-updates, to get updates before a point release is made;
+# see https://www.debian.org/doc/manuals/debian-reference/ch02.en.html#_updates_and_backports
+deb http://deb.debian.org/debian/ bookworm-updates main non-free-firmware
+deb-src http://deb.debian.org/debian/ bookworm-updates main non-free-firmware
+
+# This system was installed using small removable media
+# (e.g. netinst, live or single-floppy images).
+";
+
+const THIRD_PARTY_SOURCES: &str = "\
+### THIS FILE IS AUTOMATICALLY CONFIGURED ###
+# You may comment out this entry, but any other modifications may be lost.
+deb [arch=amd64,arm64,armhf signed-by=/usr/share/keyrings/microsoft.gpg] https://packages.microsoft.com/repos/code stable main # VS Code
+##deb http://ppa.launchpad.net/mozillateam/ppa/ubuntu jammy main
+deb [trusted=yes] file:/srv/local-repo ./
+";
+
+#[test]
+fn unchanged_files_render_identically() {
+    for sample in [DEBIAN_SOURCES, THIRD_PARTY_SOURCES] {
+        assert_eq!(SourcesFile::parse(sample).render(), sample);
+    }
+}
+
+#[test]
+fn prose_comments_are_not_entries() {
+    let sources = SourcesFile::parse(DEBIAN_SOURCES);
+    let entries: Vec<_> = sources.entries().collect();
+    assert_eq!(entries.len(), 7);
+
+    let (line, cdrom) = entries[0];
+    assert_eq!(line, 0);
+    assert!(!cdrom.enabled);
+    assert!(cdrom.uri.starts_with("cdrom:[Debian GNU/Linux 12.5.0"));
+    assert_eq!(cdrom.distribution, "bookworm");
+    assert_eq!(cdrom.components, vec!["contrib", "main", "non-free-firmware"]);
+}
+
+#[test]
+fn options_and_trailing_comments_are_parsed() {
+    let entry = SourceEntry::parse("deb [arch=amd64,arm64,armhf signed-by=/usr/share/keyrings/microsoft.gpg] https://packages.microsoft.com/repos/code stable main # VS Code").unwrap();
+    assert_eq!(entry.options, vec!["arch=amd64,arm64,armhf", "signed-by=/usr/share/keyrings/microsoft.gpg"]);
+    assert_eq!(entry.uri, "https://packages.microsoft.com/repos/code");
+    assert_eq!(entry.components, vec!["main"]);
+    assert_eq!(entry.comment.as_deref(), Some("VS Code"));
+
+    let flat = SourceEntry::parse("deb [trusted=yes] file:/srv/local-repo ./").unwrap();
+    assert_eq!(flat.distribution, "./");
+    assert!(flat.components.is_empty());
+
+    assert!(SourceEntry::parse("# deb packages are signed").is_none());
+    assert!(SourceEntry::parse("deb http://example.org/debian bookworm").is_none());
+}
+
+#[test]
+fn toggling_keeps_everything_else() {
+    let mut sources = SourcesFile::parse(THIRD_PARTY_SOURCES);
+    sources.entry_mut(2).unwrap().enabled = false;
+    sources.entry_mut(3).unwrap().enabled = true;
+
+    let expected = THIRD_PARTY_SOURCES
+        .replace("deb [arch", "# deb [arch")
+        .replace("##deb http://ppa", "deb http://ppa");
+    assert_eq!(sources.render(), expected);
+}
+
+#[test]
+fn editing_and_removing_preserve_comments() {
+    let mut sources = SourcesFile::parse(DEBIAN_SOURCES);
+    let entry = sources.entry_mut(10).unwrap();
+    entry.distribution = "trixie-updates".to_string();
+    entry.components = vec!["main".to_string()];
+    assert!(sources.remove_entry(11));
+    assert!(!sources.remove_entry(8), "comment lines are never removed");
+
+    let rendered = sources.render();
+    assert!(rendered.contains("# see https://www.debian.org/doc/manuals/debian-reference/ch02.en.html#_updates_and_backports\ndeb http://deb.debian.org/debian/ trixie-updates main\n\n# This system"));
+    assert!(rendered.ends_with("(e.g. netinst, live or single-floppy images).\n"));
+}
+
+#[test]
+fn options_are_replaced_in_place() {
+    let mut entry = SourceEntry::parse("deb [arch=amd64 signed-by=/usr/share/keyrings/microsoft.gpg] https://packages.microsoft.com/repos/code stable main # VS Code").unwrap();
+    assert_eq!(entry.option("signed-by"), Some("/usr/share/keyrings/microsoft.gpg"));
+
+    entry.set_option("arch", Some("amd64,arm64"));
+    entry.set_option("signed-by", None);
+    entry.set_option("lang", Some("none"));
+    assert_eq!(entry.render(), "deb [arch=amd64,arm64 lang=none] https://packages.microsoft.com/repos/code stable main # VS Code");
+
+    entry.options.clear();
+    assert_eq!(entry.render(), "deb https://packages.microsoft.com/repos/code stable main # VS Code");
+}
+
+#[test]
+fn edits_are_validated() {
+    let valid = RepositoryEdit {
+        uri: "http://deb.debian.org/debian".to_string(),
+        distribution: "bookworm".to_string(),
+        components: vec!["main".to_string(), "non-free-firmware".to_string()],
+        architectures: vec!["amd64".to_string()],
+        signed_by: None,
+    };
+    assert!(valid.validate().is_ok());
+
+    let typo = RepositoryEdit { uri: "htp://deb.debian.org/debian".to_string(), ..valid.clone() };
+    assert!(typo.validate().is_err());
+
+    let no_components = RepositoryEdit { components: Vec::new(), ..valid.clone() };
+    assert!(no_components.validate().is_err());
+
+    let flat = RepositoryEdit { distribution: "./".to_string(), components: Vec::new(), ..valid.clone() };
+    assert!(flat.validate().is_ok());
+
+    let bad_arch = RepositoryEdit { architectures: vec!["x86_64".to_string()], ..valid.clone() };
+    assert!(bad_arch.validate().is_err());
+
+    let relative_key = RepositoryEdit { signed_by: Some("keyrings/debian.gpg".to_string()), ..valid.clone() };
+    assert!(relative_key.validate().is_err());
+
+    let fingerprint = RepositoryEdit { signed_by: Some("BC528686B50D79E339D3721CEB3E94ADBE1229CF".to_string()), ..valid };
+    assert!(fingerprint.validate().is_ok());
+}
+
+#[test]
+fn release_file_fields() {
+    let content = "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA512\n\nOrigin: Debian\nLabel: Debian\nSuite: stable\nVersion: 12.5\nCodename: bookworm\nArchitectures: all amd64 arm64 armel armhf i386 mips64el mipsel ppc64el s390x\nComponents: main contrib non-free-firmware non-free\nDescription: Debian 12.5 Released 10 February 2024\nMD5Sum:\n 0ed6d4c8891eb86358b94bb35d9e4da4  1484322 contrib/Contents-all\n";
+    let info = parse_release(content);
+    assert_eq!(info.suite.as_deref(), Some("stable"));
+    assert_eq!(info.codename.as_deref(), Some("bookworm"));
+    assert_eq!(info.components, vec!["main", "contrib", "non-free-firmware", "non-free"]);
+    assert_eq!(info.architectures.len(), 10);
+}
+
+#[test]
+fn suites_to_try() {
+    let os_release = "PRETTY_NAME=\"Linux Mint 21.3\"\nVERSION_CODENAME=virginia\nUBUNTU_CODENAME=jammy\n";
+    assert_eq!(suite_candidates(os_release), vec!["virginia", "jammy", "stable", "./"]);
+
+    assert_eq!(release_urls("http://deb.debian.org/debian/", "bookworm"), vec![
+        "http://deb.debian.org/debian/dists/bookworm/InRelease",
+        "http://deb.debian.org/debian/dists/bookworm/Release",
+    ]);
+    assert_eq!(release_urls("https://example.org/repo", "./")[0], "https://example.org/repo/InRelease");
+}
+
+#[test]
+fn repository_slug_names_list_and_keyring() {
+    assert_eq!(repository_slug("https://packages.microsoft.com/repos/code"), "packages.microsoft.com-repos-code");
+    assert_eq!(repository_slug("http://dl.google.com/linux/chrome/deb/"), "dl.google.com-linux-chrome-deb");
+    assert_eq!(repository_slug("file:///"), "custom-repository");
+}
+
+#[test]
+fn public_key_packets_are_recognized() {
+    // Old format, tag 6, two length bytes
+    assert!(is_public_key_packet(&[0x99, 0x01, 0x0d, 0x04]));
+    // New format, tag 6
+    assert!(is_public_key_packet(&[0xc6, 0x33, 0x04]));
+    // Signature packet and plain text
+    assert!(!is_public_key_packet(&[0x89, 0x01]));
+    assert!(!is_public_key_packet(b"<html>"));
+    assert!(!is_public_key_packet(&[]));
+    assert!(is_armored_key(b"-----BEGIN PGP PUBLIC KEY BLOCK-----\n\nmQ=="));
+}
+
+#[test]
+fn official_archives_are_not_third_party() {
+    assert!(is_official_uri("http://deb.debian.org/debian"));
+    assert!(is_official_uri("http://security.debian.org/debian-security"));
+    assert!(is_official_uri("http://ftp.de.debian.org/debian/"));
+    assert!(is_official_uri("http://de.archive.ubuntu.com/ubuntu"));
+    assert!(is_official_uri("mirror+https://archive.ubuntu.com/ubuntu"));
+    assert!(is_official_uri("cdrom:[Debian GNU/Linux 12.5.0]/"));
+    assert!(!is_official_uri("https://packages.microsoft.com/repos/code"));
+    assert!(!is_official_uri("https://deb.debian.org.example.com/debian"));
+    assert_eq!(uri_host("https://user@dl.google.com:443/linux/chrome/deb/"), "dl.google.com");
+}
+
+#[test]
+fn managed_sources_files_are_detected() {
+    let chrome = "### THIS FILE IS AUTOMATICALLY CONFIGURED ###\n# You may comment out this entry, but any other modifications may be lost.\ndeb [arch=amd64] https://dl.google.com/linux/chrome/deb/ stable main\n";
+    assert_eq!(detect_manager("google-chrome.list", chrome, None, None).as_deref(), Some("the package's cron job"));
+    assert_eq!(
+        detect_manager("vendor.list", "deb https://vendor.example/apt stable main\n", None, Some("vendor-archive-keyring")).as_deref(),
+        Some("the vendor-archive-keyring package")
+    );
+    assert_eq!(detect_manager("extrepo_librewolf.list", "", None, None).as_deref(), Some("extrepo"));
+    assert_eq!(
+        detect_manager("shared.list", "deb http://mirror/debian stable main\n", Some("/srv/config/shared.list"), None).as_deref(),
+        Some("a symlink to /srv/config/shared.list")
+    );
+    // Markers after the first entry are just comments
+    let plain = "deb http://deb.debian.org/debian bookworm main\n# managed by puppet, once upon a time\n";
+    assert_eq!(detect_manager("sources.list", plain, None, None), None);
+}
+
+#[test]
+fn dpkg_search_gives_owners() {
+    let output = "google-chrome-stable: /etc/apt/sources.list.d/google-chrome.list\n\
+                  diversion by foo from: /etc/apt/sources.list\n\
+                  a, b: /etc/apt/sources.list.d/shared.list\n";
+    let owners = parse_dpkg_search(output);
+    assert_eq!(owners["/etc/apt/sources.list.d/google-chrome.list"], "google-chrome-stable");
+    assert_eq!(owners["/etc/apt/sources.list.d/shared.list"], "a");
+    assert!(!owners.contains_key("/etc/apt/sources.list"));
+}
+
+#[test]
+fn extrepo_catalog_is_parsed() {
+    let output = "Found librewolf:\n---\ndescription: LibreWolf is a fork of Firefox, focused on privacy\n\
+                  gpg-key-checksum:\n  sha256: 0123\npolicy: main\nsource:\n  Components: main\n  Suites: <SUITE>\n\
+                  \x20 Types: deb\n  URIs: https://repo.librewolf.net\n\
+                  Found vscodium:\n---\ndescription: 'Free/Libre Open Source Software Binaries of VS Code'\n\
+                  policy: non-free\nsource:\n  URIs: https://download.vscodium.com/debs\n";
+    let entries = parse_extrepo_search(output);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name, "librewolf");
+    assert_eq!(entries[0].policy, "main");
+    assert_eq!(entries[0].uri.as_deref(), Some("https://repo.librewolf.net"));
+    assert_eq!(entries[1].description, "Free/Libre Open Source Software Binaries of VS Code");
+    assert_eq!(entries[1].policy, "non-free");
+
+    assert_eq!(parse_extrepo_policies("---\nurl: https://extrepo-team.pages.debian.net/extrepo-data\n"), vec!["main"]);
+    assert_eq!(
+        parse_extrepo_policies("enabled_policies:\n- main\n# - contrib\n- non-free\nurl: x\n"),
+        vec!["main", "non-free"]
+    );
+}
+
+#[test]
+fn deb822_sources_are_listed() {
+    let content = "Types: deb deb-src\nURIs: https://repo.librewolf.net\nSuites: librewolf\nComponents: main\n\
+                   Architectures: amd64 arm64\nSigned-By: /var/lib/extrepo/keys/librewolf.asc\n\n\
+                   Types: deb\nURIs: https://example.org/apt\nSuites: stable\nComponents: main\nEnabled: no\n";
+    let repos = parse_deb822_sources(content);
+    assert_eq!(repos.len(), 3);
+    assert!(!repos[0].is_source && repos[1].is_source);
+    assert_eq!(repos[0].architectures, vec!["amd64", "arm64"]);
+    assert_eq!(repos[0].signed_by.as_deref(), Some("/var/lib/extrepo/keys/librewolf.asc"));
+    assert!(repos[0].enabled);
+    assert!(!repos[2].enabled);
+}
+
+fn repository(name: &str, enabled: bool, managed_by: Option<&str>) -> Repository {
+    Repository {
+        name: name.to_string(),
+        uri: format!("https://example.com/{}", name),
+        distribution: "stable".to_string(),
+        components: "main".to_string(),
+        enabled,
+        is_source: false,
+        architectures: Vec::new(),
+        signed_by: None,
+        file_path: Some(format!("/etc/apt/sources.list.d/{}.list", name)),
+        line_number: Some(1),
+        managed_by: managed_by.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_pending_changes_only_lists_differences() {
+    let repos = vec![
+        repository("one", true, None),
+        repository("two", false, None),
+        repository("three", true, None),
+    ];
+    let changes = pending_changes(&repos, &[true, true, false]);
+    let names: Vec<(&str, bool)> = changes.iter().map(|(repo, enabled)| (repo.name.as_str(), *enabled)).collect();
+    assert_eq!(names, vec![("two", true), ("three", false)]);
+    assert!(pending_changes(&repos, &[true, false, true]).is_empty());
+}
+
+#[test]
+fn test_pending_changes_skips_managed_repositories() {
+    let repos = vec![repository("tool", true, Some("extrepo"))];
+    assert!(pending_changes(&repos, &[false]).is_empty());
+}
+
+#[test]
+fn names_flat_and_local_repositories() {
+    use meaupdater::repo_manager::repository_name;
+
+    assert_eq!(repository_name("http://deb.debian.org/debian"), "debian");
+    assert_eq!(repository_name("http://deb.debian.org/debian/"), "debian");
+    assert_eq!(repository_name("file:/srv/local-repo/"), "local-repo");
+    assert_eq!(repository_name("file:///media/usb/debian"), "debian");
+    assert_eq!(repository_name("https://download.vendor.com/"), "download.vendor.com");
+    assert_eq!(repository_name("cdrom:[Debian GNU/Linux 12.5.0 _Bookworm_]/"), "Debian GNU/Linux 12.5.0 _Bookworm_");
+    assert_eq!(repository_name("file:/"), "file:/");
+}
+
+#[test]
+fn flat_repositories_are_listed_and_toggled() {
+    let content = "deb [trusted=yes] file:/srv/local-repo/ ./\ndeb http://example.com/repo binary/\ndeb file:///srv/mirror/debian bookworm main\n";
+    let mut file = SourcesFile::parse(content);
+    let entries: Vec<_> = file.entries().map(|(_, entry)| entry.clone()).collect();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[1].distribution, "binary/");
+
+    let repos = parse_deb822_sources("Types: deb\nURIs: file:/srv/local-repo\nSuites: ./\nTrusted: yes\n");
+    assert_eq!(repos.len(), 1);
+    assert!(repos[0].is_flat());
+    assert!(repos[0].is_local());
+    assert_eq!(repos[0].name, "local-repo");
+    assert_eq!(repos[0].suite_label(), "(flat)");
+    assert_eq!(repos[0].components, "");
+
+    let line = file.entries().next().unwrap().0;
+    file.entry_mut(line).unwrap().enabled = false;
+    assert_eq!(file.render().lines().next(), Some("# deb [trusted=yes] file:/srv/local-repo/ ./"));
+    file.entry_mut(line).unwrap().enabled = true;
+    assert_eq!(file.render(), content);
+}
+
+#[test]
+fn detects_the_release_of_a_local_flat_repository() {
+    use meaupdater::repo_manager::detect_release;
+
+    let dir = std::env::temp_dir().join(format!("meaupdater-flat-repo-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("Release"), "Origin: Local\nLabel: Local\nArchitectures: amd64\n").unwrap();
+
+    let uri = format!("file:{}", dir.display());
+    let (suite, info) = detect_release(&uri, Some("./")).unwrap();
+    assert_eq!(suite, "./");
+    assert!(info.components.is_empty());
+    assert!(detect_release(&format!("file://{}", dir.display()), Some("./")).is_ok());
+    assert!(detect_release(&uri, Some("bookworm")).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn missing_components_go_to_the_release_entry_of_the_archive() {
+    let repos = parse_deb822_sources(
+        "Types: deb deb-src\nURIs: http://deb.debian.org/debian\nSuites: trixie-updates\nComponents: main non-free-firmware\n\n\
+         Types: deb\nURIs: http://deb.debian.org/debian\nSuites: trixie\nComponents: main non-free-firmware\n\n\
+         Types: deb\nURIs: https://packages.microsoft.com/repos/code\nSuites: stable\nComponents: main\n",
+    );
+    assert!(enabled_components(&repos).contains("non-free-firmware"));
+    assert!(!enabled_components(&repos).contains("non-free"));
+
+    let target = component_target(&repos, "non-free").unwrap();
+    assert_eq!((target.uri.as_str(), target.distribution.as_str()), ("http://deb.debian.org/debian", "trixie"));
+    assert!(component_target(&repos, "non-free-firmware").is_none());
+    assert!(component_target(&[repository("vendor", true, None)], "contrib").is_none());
+}
+
+#[test]
+fn archive_family_follows_os_release() {
+    assert_eq!(ArchiveFamily::from_os_release("ID=ubuntu\nVERSION_CODENAME=noble\n"), ArchiveFamily::Ubuntu);
+    assert_eq!(ArchiveFamily::from_os_release("ID=linuxmint\nID_LIKE=\"ubuntu debian\"\n"), ArchiveFamily::Ubuntu);
+    assert_eq!(ArchiveFamily::from_os_release("ID=debian\n"), ArchiveFamily::Debian);
+    assert_eq!(ArchiveFamily::from_os_release(""), ArchiveFamily::Debian);
+}
@@ -0,0 +1,60 @@
+// tests/security_news_tests.rs
+use meaupdater::security_news::{correlate, fixes_by_package, parse_feed, parse_source_map, FeedSource};
+
+const DSA_FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rdf:RDF>
+  <item rdf:about="https://www.debian.org/security/2024/dsa-5801">
+    <title>DSA-5801-1 openssl - security update</title>
+    <link>https://www.debian.org/security/2024/dsa-5801</link>
+    <description>Several vulnerabilities were discovered in OpenSSL &amp; friends.</description>
+    <dc:date>2024-11-05</dc:date>
+  </item>
+  <item rdf:about="https://www.debian.org/security/2024/dsa-5800">
+    <title>DSA-5800-1 xorg-server - security update</title>
+    <link>https://www.debian.org/security/2024/dsa-5800</link>
+    <dc:date>2024-11-03</dc:date>
+  </item>
+</rdf:RDF>"#;
+
+#[test]
+fn detects_feed_from_os_release() {
+    assert_eq!(FeedSource::from_os_release("ID=linuxmint\nID_LIKE=\"ubuntu debian\"\n"), Some(FeedSource::Ubuntu));
+    assert_eq!(FeedSource::from_os_release("ID=debian\n"), Some(FeedSource::Debian));
+    assert_eq!(FeedSource::from_os_release("ID=fedora\n"), None);
+}
+
+#[test]
+fn parses_dsa_and_usn_items() {
+    let advisories = parse_feed(DSA_FEED, FeedSource::Debian);
+    assert_eq!(advisories.len(), 2);
+    assert_eq!(advisories[0].id, "DSA-5801-1");
+    assert_eq!(advisories[0].packages, vec!["openssl"]);
+    assert_eq!(advisories[0].date, "2024-11-05");
+
+    let usn = "<rss><channel><item><title><![CDATA[USN-7100-1: OpenSSL vulnerabilities]]></title>\
+               <link>https://ubuntu.com/security/notices/USN-7100-1</link>\
+               <pubDate>Tue, 05 Nov 2024 12:00:00 +0000</pubDate></item></channel></rss>";
+    let advisories = parse_feed(usn, FeedSource::Ubuntu);
+    assert_eq!(advisories[0].id, "USN-7100-1");
+    assert_eq!(advisories[0].packages, vec!["openssl"]);
+}
+
+#[test]
+fn correlates_binaries_through_source_packages() {
+    let advisories = parse_feed(DSA_FEED, FeedSource::Debian);
+    let sources = parse_source_map("libssl3:amd64 openssl\nopenssl openssl\nbash bash\n");
+    let pending = vec!["libssl3".to_string()];
+
+    let matches = correlate(&advisories, &sources, &pending);
+    assert_eq!(matches[0].installed, vec!["libssl3", "openssl"]);
+    assert_eq!(matches[0].pending, vec!["libssl3"]);
+    assert!(matches[1].installed.is_empty());
+
+    assert_eq!(fixes_by_package(&matches).get("libssl3"), Some(&vec!["DSA-5801-1".to_string()]));
+}
+
+#[test]
+fn cve_links_point_to_the_distribution_tracker() {
+    assert_eq!(FeedSource::Debian.cve_url("CVE-2024-3094"), "https://security-tracker.debian.org/tracker/CVE-2024-3094");
+    assert_eq!(FeedSource::Ubuntu.cve_url("CVE-2024-3094"), "https://ubuntu.com/security/CVE-2024-3094");
+}
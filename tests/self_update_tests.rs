@@ -0,0 +1,59 @@
+// tests/self_update_tests.rs
+use meaupdater::self_update::{checksum_for, deb_asset, is_newer, parse_release, replaced_executable, updates_self};
+use std::path::PathBuf;
+
+#[test]
+fn detects_own_package() {
+    assert!(updates_self(&["firefox-esr".to_string(), "meaupdater".to_string()]));
+    assert!(updates_self(&["meaupdater:amd64".to_string()]));
+    assert!(!updates_self(&["meaupdater-helper".to_string()]));
+    assert!(!updates_self(&[]));
+}
+
+#[test]
+fn replaced_executable_from_proc_link() {
+    assert_eq!(
+        replaced_executable("/usr/bin/meaupdater (deleted)"),
+        Some(PathBuf::from("/usr/bin/meaupdater"))
+    );
+    assert_eq!(replaced_executable("/usr/bin/meaupdater"), None);
+}
+
+#[test]
+fn release_from_github_response() {
+    let json = r#"{"url": "https://api.github.com/repos/mthakan/meaupdater/releases/1",
+        "html_url": "https://github.com/mthakan/meaupdater/releases/tag/v0.6.0", "tag_name" : "v0.6.0",
+        "name": "MeaUpdater \"0.6\"", "author": {"html_url": "https://github.com/mthakan"},
+        "assets": [
+          {"name": "meaupdater_0.6.0_amd64.deb", "browser_download_url": "https://github.com/mthakan/meaupdater/releases/download/v0.6.0/meaupdater_0.6.0_amd64.deb"},
+          {"name": "SHA256SUMS", "browser_download_url": "https://github.com/mthakan/meaupdater/releases/download/v0.6.0/SHA256SUMS"}
+        ]}"#;
+    let release = parse_release(json).unwrap();
+    assert_eq!(release.version, "0.6.0");
+    assert_eq!(release.url, "https://github.com/mthakan/meaupdater/releases/tag/v0.6.0");
+    assert_eq!(release.assets.len(), 2);
+    assert_eq!(release.assets[1], (
+        "SHA256SUMS".to_string(),
+        "https://github.com/mthakan/meaupdater/releases/download/v0.6.0/SHA256SUMS".to_string(),
+    ));
+    assert_eq!(deb_asset(&release.assets, "amd64").map(|(name, _)| name.as_str()), Some("meaupdater_0.6.0_amd64.deb"));
+    assert_eq!(deb_asset(&release.assets, "arm64"), None);
+    assert_eq!(parse_release(r#"{"message": "Not Found"}"#), None);
+}
+
+#[test]
+fn version_ordering() {
+    assert!(is_newer("0.10.0", "0.5.0"));
+    assert!(is_newer("v0.5.1", "0.5.0"));
+    assert!(is_newer("1.0", "0.5.0"));
+    assert!(!is_newer("0.5", "0.5.0"));
+    assert!(!is_newer("0.4.9", "0.5.0"));
+}
+
+#[test]
+fn checksum_lookup() {
+    let sums = "0a1b  meaupdater_0.6.0_amd64.deb\nFFEE *meaupdater_0.6.0_arm64.deb\n";
+    assert_eq!(checksum_for(sums, "meaupdater_0.6.0_amd64.deb").as_deref(), Some("0a1b"));
+    assert_eq!(checksum_for(sums, "meaupdater_0.6.0_arm64.deb").as_deref(), Some("ffee"));
+    assert_eq!(checksum_for(sums, "meaupdater_0.6.0_all.deb"), None);
+}
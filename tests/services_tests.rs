@@ -0,0 +1,13 @@
+// tests/services_tests.rs
+use meaupdater::services::UpdateStatus;
+
+#[test]
+fn notification_bodies() {
+    assert_eq!(UpdateStatus::Complete(3).body(), "3 updates available");
+    assert_eq!(UpdateStatus::Complete(0).body(), "✅ Your system is up to date");
+    assert_eq!(
+        UpdateStatus::SecurityAvailable { updates: 5, security: 2 }.body(),
+        "🔒 5 updates available, 2 of them security updates"
+    );
+    assert!(UpdateStatus::Failed.body().starts_with("❌"));
+}
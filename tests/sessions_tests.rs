@@ -0,0 +1,24 @@
+// tests/sessions_tests.rs
+use meaupdater::sessions::{is_other_user, parse_session_ids, parse_session_properties};
+
+#[test]
+fn parses_session_list() {
+    let output = "      2 1000 mea  seat0 tty2\n     c4 1001 anna       pts/1\n";
+    assert_eq!(parse_session_ids(output), vec!["2", "c4"]);
+}
+
+#[test]
+fn finds_other_users() {
+    let local = parse_session_properties("2", "Name=mea\nTTY=tty2\nRemote=no\nRemoteHost=\nClass=user\nState=active\n");
+    let remote = parse_session_properties("c4", "Name=anna\nTTY=pts/1\nRemote=yes\nRemoteHost=192.168.1.20\nClass=user\nState=active\n");
+    let greeter = parse_session_properties("c1", "Name=lightdm\nTTY=\nRemote=no\nClass=greeter\nState=online\n");
+    let closing = parse_session_properties("5", "Name=bob\nTTY=tty3\nRemote=no\nClass=user\nState=closing\n");
+
+    assert!(!is_other_user(&local, "mea"));
+    assert!(is_other_user(&remote, "mea"));
+    assert!(!is_other_user(&greeter, "mea"));
+    assert!(!is_other_user(&closing, "mea"));
+
+    assert_eq!(remote.describe(), "anna remotely from 192.168.1.20");
+    assert_eq!(local.describe(), "mea on tty2");
+}
@@ -0,0 +1,39 @@
+// tests/snap_tests.rs
+use meaupdater::model::UpdateType;
+use meaupdater::snap_manager::{parse_installed, parse_size, parse_updates, snap_names};
+
+const LIST: &str = "Name      Version    Rev    Tracking       Publisher   Notes\n\
+                    core22    20240111   1122   latest/stable  canonical✓  base\n\
+                    firefox   121.0      3600   latest/stable  mozilla✓    -\n";
+const REFRESH: &str = "Name     Version  Rev   Size   Publisher  Notes\n\
+                       firefox  122.0    3779  245MB  mozilla✓   -\n\
+                       core22   20240208 1380  77kB   canonical✓ base\n";
+
+#[test]
+fn parses_snap_sizes() {
+    assert_eq!(parse_size("245MB"), Some(245_000_000));
+    assert_eq!(parse_size("77kB"), Some(77_000));
+    assert_eq!(parse_size("1.5GB"), Some(1_500_000_000));
+    assert_eq!(parse_size("-"), None);
+}
+
+#[test]
+fn lists_refreshes_with_installed_versions() {
+    let updates = parse_updates(REFRESH, &parse_installed(LIST));
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].name, "firefox");
+    assert_eq!(updates[0].current_version, "121.0");
+    assert_eq!(updates[0].new_version, "122.0");
+    assert_eq!(updates[0].size, Some(245_000_000));
+    assert_eq!(updates[0].update_type, UpdateType::Snap);
+    assert_eq!(updates[1].current_version, "20240111");
+
+    assert!(parse_updates("", &parse_installed(LIST)).is_empty());
+}
+
+#[test]
+fn picks_snap_names_from_selection() {
+    let updates = parse_updates(REFRESH, &parse_installed(LIST));
+    let selected = vec!["bash".to_string(), "firefox".to_string()];
+    assert_eq!(snap_names(&updates, &selected), vec!["firefox"]);
+}
@@ -0,0 +1,92 @@
+// tests/sysroot_tests.rs
+use meaupdater::driver_manager;
+use meaupdater::kernel_manager;
+use meaupdater::repo_manager;
+use meaupdater::security_news::FeedSource;
+use meaupdater::sysroot::{self, resolve_under, with_root};
+use meaupdater::xorg_config;
+use std::path::{Path, PathBuf};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+#[test]
+fn resolves_absolute_paths_below_the_root() {
+    assert_eq!(resolve_under(Path::new("/tmp/root"), "/etc/os-release"), PathBuf::from("/tmp/root/etc/os-release"));
+    assert_eq!(resolve_under(Path::new("/tmp/root"), "etc"), PathBuf::from("/tmp/root/etc"));
+}
+
+#[test]
+fn root_only_applies_inside_with_root() {
+    let root = fixture("bookworm");
+    assert_eq!(sysroot::path("/etc/os-release"), PathBuf::from("/etc/os-release"));
+    let inside = with_root(&root, || sysroot::path("/etc/os-release"));
+    assert_eq!(inside, root.join("etc/os-release"));
+    assert_eq!(sysroot::path("/etc/os-release"), PathBuf::from("/etc/os-release"));
+}
+
+#[test]
+fn root_is_restored_after_a_panic() {
+    let root = fixture("bookworm");
+    let result = std::panic::catch_unwind(|| with_root(&root, || panic!("fixture broken")));
+    assert!(result.is_err());
+    assert_eq!(sysroot::root(), None);
+}
+
+#[test]
+fn reads_repositories_from_the_fixture() {
+    let root = fixture("bookworm");
+    let repositories = with_root(&root, repo_manager::get_repositories).unwrap();
+
+    let uris: Vec<&str> = repositories.iter().map(|repo| repo.uri.as_str()).collect();
+    assert!(uris.contains(&"http://deb.debian.org/debian"));
+    assert!(uris.contains(&"http://security.debian.org/debian-security"));
+
+    let vscode = repositories.iter().find(|repo| repo.uri == "https://packages.microsoft.com/repos/code").unwrap();
+    assert_eq!(vscode.architectures, vec!["amd64"]);
+    assert_eq!(vscode.signed_by.as_deref(), Some("/usr/share/keyrings/microsoft.gpg"));
+    assert!(vscode.file_path.as_deref().unwrap().starts_with(root.to_str().unwrap()));
+}
+
+#[test]
+fn detects_hardware_from_fixture_modaliases() {
+    let root = fixture("bookworm");
+    let mut modaliases = with_root(&root, driver_manager::detect_hardware).unwrap();
+    modaliases.sort();
+    assert_eq!(modaliases, vec![
+        "pci:v000010DEd00002484sv00001458sd0000404Cbc03sc00i00",
+        "usb:v8087p0029d0001dcE0dsc01dp01icE0isc01ip01in00",
+    ]);
+}
+
+#[test]
+fn detects_the_distribution_from_the_fixture() {
+    let root = fixture("bookworm");
+    assert_eq!(with_root(&root, kernel_manager::detect_distribution_name), "Debian GNU/Linux");
+    assert_eq!(with_root(&root, FeedSource::detect), Some(FeedSource::Debian));
+}
+
+#[test]
+fn non_free_firmware_is_not_non_free() {
+    let root = fixture("bookworm");
+    let repositories = with_root(&root, repo_manager::get_repositories).unwrap();
+    assert!(!driver_manager::nonfree_enabled(&repositories));
+}
+
+#[test]
+fn finds_conflicting_xorg_fragments_in_the_fixture() {
+    let root = fixture("bookworm");
+    let files = with_root(&root, || xorg_config::conflicting_files("nvidia-driver"));
+    assert_eq!(files, vec!["/etc/X11/xorg.conf.d/20-nouveau.conf"]);
+    assert!(with_root(&root, || xorg_config::conflicting_files("xserver-xorg-video-nouveau")).is_empty());
+}
+
+#[test]
+fn empty_root_finds_nothing() {
+    let root = std::env::temp_dir().join(format!("meaupdater-empty-root-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    assert!(with_root(&root, driver_manager::detect_hardware).unwrap().is_empty());
+    assert_eq!(with_root(&root, FeedSource::detect), None);
+    let _ = std::fs::remove_dir_all(&root);
+}
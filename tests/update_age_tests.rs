@@ -0,0 +1,52 @@
+// tests/update_age_tests.rs
+use meaupdater::format;
+use meaupdater::update_age::{days_pending, merge_first_seen, parse_first_seen};
+use std::collections::HashMap;
+
+fn key(name: &str, version: &str) -> (String, String) {
+    (name.to_string(), version.to_string())
+}
+
+#[test]
+fn parse_ignores_malformed_lines() {
+    let table = parse_first_seen("bash\t5.2-1\t100\ngarbage\nopenssl\t3.0\tnot-a-number\ncurl\t200\ncurl\t7.88\t200\n");
+    assert_eq!(table.len(), 2);
+    assert_eq!(table[&key("bash", "5.2-1")], 100);
+    assert_eq!(table[&key("curl", "7.88")], 200);
+}
+
+#[test]
+fn merge_keeps_existing_and_drops_resolved() {
+    let mut previous = HashMap::new();
+    previous.insert(key("bash", "5.2-1"), 100);
+    previous.insert(key("openssl", "3.0.11"), 50);
+
+    let pending = vec![key("bash", "5.2-1"), key("curl", "7.88")];
+    let merged = merge_first_seen(&previous, &pending, 1000);
+
+    assert_eq!(merged.len(), 2);
+    assert_eq!(merged[&key("bash", "5.2-1")], 100);
+    assert_eq!(merged[&key("curl", "7.88")], 1000);
+    assert!(!merged.contains_key(&key("openssl", "3.0.11")));
+}
+
+#[test]
+fn newer_pending_version_starts_counting_again() {
+    let mut previous = HashMap::new();
+    previous.insert(key("openssl", "3.0.11"), 50);
+
+    let merged = merge_first_seen(&previous, &[key("openssl", "3.0.13")], 1000);
+
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[&key("openssl", "3.0.13")], 1000);
+}
+
+#[test]
+fn age_formatting() {
+    assert_eq!(days_pending(0, 86_399), 0);
+    assert_eq!(days_pending(0, 3 * 86_400 + 5), 3);
+    assert_eq!(days_pending(500, 100), 0);
+    assert_eq!(format::days(0), "New today");
+    assert_eq!(format::days(1), "1 day");
+    assert_eq!(format::days(12), "12 days");
+}
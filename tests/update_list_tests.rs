@@ -0,0 +1,175 @@
+// tests/update_list_tests.rs
+mod common;
+
+use meaupdater::app_state::AppState;
+use meaupdater::format;
+use meaupdater::model::{PackageUpdate, UpdateType};
+use meaupdater::update_list::{self, SelectionSummary, KEPT_BACK_SECTION};
+use std::collections::HashMap;
+use common::update;
+
+fn names(packages: &[PackageUpdate]) -> Vec<&str> {
+    packages.iter().map(|pkg| pkg.name.as_str()).collect()
+}
+
+fn state_with(packages: Vec<PackageUpdate>) -> AppState {
+    let state = AppState::new();
+    state.set_packages(packages);
+    state
+}
+
+fn pending() -> Vec<PackageUpdate> {
+    vec![
+        update("vim", UpdateType::Software, Some(2_000)),
+        update("curl", UpdateType::Software, Some(9_000)),
+        update("openssl", UpdateType::Security, Some(5_000)),
+        update("org.gimp.GIMP", UpdateType::Flatpak, Some(80_000)),
+        update("firefox", UpdateType::Snap, None),
+    ]
+}
+
+#[test]
+fn groups_follow_the_fixed_order_and_sort_by_name() {
+    let state = state_with(pending());
+    let sections = update_list::sections(&state, pending(), true, false);
+
+    let keys: Vec<_> = sections.iter().map(|section| section.key).collect();
+    assert_eq!(keys, vec![Some("security"), Some("software"), Some("flatpak"), Some("snap")]);
+    assert_eq!(sections[0].title, "🔒 Security Updates");
+    assert_eq!(names(&sections[1].packages), vec!["curl", "vim"]);
+}
+
+#[test]
+fn groups_sort_by_size_when_asked() {
+    let state = state_with(pending());
+    let sections = update_list::sections(&state, pending(), true, true);
+    assert_eq!(names(&sections[1].packages), vec!["curl", "vim"]);
+
+    let packages = vec![update("a", UpdateType::Software, Some(1)), update("b", UpdateType::Software, Some(2))];
+    let sections = update_list::sections(&state_with(packages.clone()), packages, true, true);
+    assert_eq!(names(&sections[0].packages), vec!["b", "a"]);
+}
+
+#[test]
+fn ungrouped_list_keeps_the_given_order_without_a_header() {
+    let state = state_with(pending());
+    let sections = update_list::sections(&state, pending(), false, false);
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections[0].key, None);
+    assert_eq!(names(&sections[0].packages), names(&pending()));
+}
+
+#[test]
+fn kept_back_updates_come_last_in_their_own_section() {
+    let state = state_with(pending());
+    state.set_kept_back(HashMap::from([("curl".to_string(), "needs new packages".to_string())]));
+
+    for group_by_type in [true, false] {
+        let sections = update_list::sections(&state, pending(), group_by_type, false);
+        let last = sections.last().unwrap();
+        assert!(last.is_kept_back());
+        assert_eq!(last.key, Some(KEPT_BACK_SECTION));
+        assert_eq!(names(&last.packages), vec!["curl"]);
+        assert!(sections[..sections.len() - 1].iter().all(|section| !names(&section.packages).contains(&"curl")));
+    }
+}
+
+#[test]
+fn kernel_set_is_one_row_listed_as_security() {
+    let packages = vec![
+        update("linux-image-amd64", UpdateType::Kernel, Some(100)),
+        update("linux-headers-amd64", UpdateType::Security, Some(20)),
+        update("bash", UpdateType::Software, Some(1)),
+    ];
+    let state = state_with(packages.clone());
+    let sections = update_list::sections(&state, packages, true, false);
+
+    assert_eq!(sections[0].key, Some("security"));
+    assert_eq!(names(&sections[0].packages), vec!["linux-image-amd64"]);
+    assert_eq!(sections[0].packages[0].size, Some(120));
+}
+
+#[test]
+fn no_sections_without_updates() {
+    assert!(update_list::sections(&AppState::new(), Vec::new(), true, false).is_empty());
+    assert!(update_list::sections(&AppState::new(), Vec::new(), false, false).is_empty());
+}
+
+#[test]
+fn security_only_policy_filters_the_offered_updates() {
+    let state = AppState::new();
+    assert_eq!(update_list::offered(&state, pending()).len(), 5);
+
+    state.set_security_only(true);
+    assert_eq!(names(&update_list::offered(&state, pending())), vec!["openssl"]);
+}
+
+#[test]
+fn security_only_keeps_kernel_and_microcode_fixes_from_the_security_archive() {
+    let mut kernel = update("linux-image-amd64", UpdateType::Kernel, Some(100));
+    kernel.from_security = true;
+    let mut microcode = update("intel-microcode", UpdateType::Firmware, Some(3));
+    microcode.from_security = true;
+    let packages = vec![kernel, microcode, update("firmware-misc-nonfree", UpdateType::Firmware, None), update("vim", UpdateType::Software, None)];
+
+    let state = state_with(packages.clone());
+    state.set_security_only(true);
+    assert_eq!(names(&update_list::offered(&state, packages)), vec!["linux-image-amd64", "intel-microcode"]);
+    assert_eq!(update_list::security_updates(&state), vec!["linux-image-amd64", "intel-microcode"]);
+}
+
+#[test]
+fn security_selection_skips_kept_back_and_ticks_the_kernel_row() {
+    let state = state_with(vec![
+        update("linux-image-amd64", UpdateType::Kernel, Some(100)),
+        update("linux-headers-amd64", UpdateType::Security, Some(20)),
+        update("openssl", UpdateType::Security, Some(5)),
+        update("libssl3", UpdateType::Security, Some(5)),
+        update("bash", UpdateType::Software, Some(1)),
+    ]);
+    state.set_kept_back(HashMap::from([("libssl3".to_string(), "phased".to_string())]));
+
+    assert_eq!(update_list::security_updates(&state), vec!["linux-headers-amd64", "openssl"]);
+    assert_eq!(update_list::security_rows(&state), vec!["linux-headers-amd64", "openssl", "linux-image-amd64"]);
+}
+
+#[test]
+fn ticking_the_kernel_row_selects_the_whole_set() {
+    let state = state_with(vec![
+        update("linux-image-amd64", UpdateType::Kernel, Some(100)),
+        update("linux-headers-amd64", UpdateType::Kernel, Some(20)),
+        update("bash", UpdateType::Software, Some(1)),
+    ]);
+    let ticked = vec!["bash".to_string(), "linux-image-amd64".to_string()];
+    assert_eq!(update_list::expand_selection(&state, ticked), vec!["bash", "linux-image-amd64", "linux-headers-amd64"]);
+    assert_eq!(update_list::expand_selection(&state, vec!["bash".to_string()]), vec!["bash"]);
+}
+
+#[test]
+fn apt_selection_leaves_out_flatpaks_and_snaps() {
+    let selected: Vec<String> = pending().into_iter().map(|pkg| pkg.name).collect();
+    assert_eq!(update_list::apt_only(&pending(), selected), vec!["vim", "curl", "openssl"]);
+}
+
+#[test]
+fn select_all_unticks_only_when_everything_is_ticked() {
+    assert!(update_list::select_all_ticks(&[true, false]));
+    assert!(update_list::select_all_ticks(&[false, false]));
+    assert!(!update_list::select_all_ticks(&[true, true]));
+}
+
+#[test]
+fn summary_counts_sizes_and_unknown_packages() {
+    let selected = vec!["vim".to_string(), "curl".to_string(), "firefox".to_string()];
+    let summary = SelectionSummary::new(&pending(), &selected);
+    assert_eq!(summary, SelectionSummary { count: 3, total: 11_000, unknown: 1 });
+    assert_eq!(summary.install_label(), format!("⬇️ Install Selected ({})", format::size(11_000)));
+    assert_eq!(summary.tooltip().as_deref(), Some("The size of 1 package is not known"));
+}
+
+#[test]
+fn empty_selection_has_a_plain_label() {
+    let summary = SelectionSummary::new(&pending(), &[]);
+    assert_eq!(summary.install_label(), "⬇️ Install Selected");
+    assert_eq!(summary.tooltip(), None);
+}
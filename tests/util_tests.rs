@@ -0,0 +1,96 @@
+// tests/util_tests.rs
+use meaupdater::util::{compare_versions, json_string, json_string_field, json_string_fields, si_unit_factor};
+use proptest::prelude::*;
+use std::cmp::Ordering;
+
+#[test]
+fn reads_si_units() {
+    assert_eq!(si_unit_factor("B"), Some(1.0));
+    assert_eq!(si_unit_factor("bytes"), Some(1.0));
+    assert_eq!(si_unit_factor("kB"), Some(1e3));
+    assert_eq!(si_unit_factor("GB"), Some(1e9));
+    assert_eq!(si_unit_factor("KiB"), None);
+}
+
+#[test]
+fn reads_json_string_fields() {
+    let json = r#"{"name": "MeaUpdater \"0.6\"", "items": [{"id" : "a"}, {"id": 7}, {"id": "b\nc"}]}"#;
+    assert_eq!(json_string_field(json, "name").as_deref(), Some("MeaUpdater \"0.6\""));
+    assert_eq!(json_string_fields(json, "id"), vec!["a", "b\nc"]);
+    assert_eq!(json_string_field(json, "missing"), None);
+}
+
+#[test]
+fn writes_json_strings_the_reader_understands() {
+    let written = format!("{{\"name\": {}}}", json_string("tab\there \"quoted\"\n"));
+    assert_eq!(written, r#"{"name": "tab\there \"quoted\"\n"}"#);
+    assert_eq!(json_string_field(&written, "name").as_deref(), Some("tab\there \"quoted\"\n"));
+}
+
+#[test]
+fn compares_like_dpkg() {
+    let ordered = [
+        ("6.1.0-9-amd64", "6.1.0-18-amd64"),
+        ("6.1.0-18", "6.1.0-18+deb12u1"),
+        ("1.0~rc1", "1.0"),
+        ("1.0~~", "1.0~"),
+        ("1.0", "1.0a"),
+        ("1.0a", "1.0+"),
+        ("2.9", "2.10"),
+        ("9.9", "1:0.1"),
+        ("5.2.15-2", "5.2.15-2+b7"),
+        ("1.2-3", "1.2-10"),
+        ("0.9.9", "1.0.0~beta"),
+    ];
+    for (older, newer) in ordered {
+        assert_eq!(compare_versions(older, newer), Ordering::Less, "{} < {}", older, newer);
+        assert_eq!(compare_versions(newer, older), Ordering::Greater, "{} > {}", newer, older);
+    }
+
+    assert_eq!(compare_versions("1.0", "1.0-0"), Ordering::Equal);
+    assert_eq!(compare_versions("0:1.0", "1.0"), Ordering::Equal);
+    assert_eq!(compare_versions("1.007", "1.7"), Ordering::Equal);
+}
+
+#[test]
+fn sorts_kernel_versions() {
+    let mut versions = vec!["6.1.0-9-amd64", "6.1.0-18-amd64", "6.1.0-10-amd64", "5.10.0-28-amd64"];
+    versions.sort_by(|a, b| compare_versions(a, b));
+    assert_eq!(versions, vec!["5.10.0-28-amd64", "6.1.0-9-amd64", "6.1.0-10-amd64", "6.1.0-18-amd64"]);
+}
+
+fn version() -> impl Strategy<Value = String> {
+    "([0-9]:)?[0-9][0-9a-z.+~]{0,8}(-[0-9a-z.+~]{1,6})?"
+}
+
+proptest! {
+    #[test]
+    fn is_reflexive(a in version()) {
+        prop_assert_eq!(compare_versions(&a, &a), Ordering::Equal);
+    }
+
+    #[test]
+    fn is_antisymmetric(a in version(), b in version()) {
+        prop_assert_eq!(compare_versions(&a, &b), compare_versions(&b, &a).reverse());
+    }
+
+    #[test]
+    fn is_transitive(a in version(), b in version(), c in version()) {
+        if compare_versions(&a, &b) != Ordering::Greater && compare_versions(&b, &c) != Ordering::Greater {
+            prop_assert_ne!(compare_versions(&a, &c), Ordering::Greater);
+        }
+    }
+
+    #[test]
+    fn orders_numbers_numerically(prefix in "[0-9]{1,3}\\.[0-9]{1,3}", x in 0u32..100_000, y in 0u32..100_000) {
+        let a = format!("{}.{}-1", prefix, x);
+        let b = format!("{}.{}-1", prefix, y);
+        prop_assert_eq!(compare_versions(&a, &b), x.cmp(&y));
+    }
+
+    #[test]
+    fn tilde_sorts_before_release(base in "[0-9]{1,2}(\\.[0-9]{1,2}){0,2}", suffix in "[a-z0-9]{0,4}") {
+        let pre = format!("{}~{}", base, suffix);
+        prop_assert_eq!(compare_versions(&pre, &base), Ordering::Less);
+    }
+}
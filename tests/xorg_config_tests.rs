@@ -0,0 +1,58 @@
+// tests/xorg_config_tests.rs
+
+use meaupdater::xorg_config::{
+    configured_drivers, conflicts_with, move_aside_script, parse_backups, restore_script, xorg_driver_of,
+};
+
+const NVIDIA_CONF: &str = r#"# nvidia-xconfig: X configuration file generated by nvidia-xconfig
+Section "Device"
+    Identifier     "Device0"
+    Driver         "nvidia"
+    VendorName     "NVIDIA Corporation"
+EndSection
+"#;
+
+#[test]
+fn test_configured_drivers_skips_comments() {
+    let content = "Section \"Device\"\n    # Driver \"intel\"\n    Driver \"modesetting\" # fallback\nEndSection\n";
+    assert_eq!(configured_drivers(content), vec!["modesetting"]);
+    assert_eq!(configured_drivers(NVIDIA_CONF), vec!["nvidia"]);
+}
+
+#[test]
+fn test_xorg_driver_of_packages() {
+    assert_eq!(xorg_driver_of("nvidia-driver"), Some("nvidia"));
+    assert_eq!(xorg_driver_of("xserver-xorg-video-nouveau"), Some("nouveau"));
+    assert_eq!(xorg_driver_of("firmware-amd-graphics"), Some("amdgpu"));
+    assert_eq!(xorg_driver_of("bluez"), None);
+}
+
+#[test]
+fn test_conflicts_with_other_video_driver() {
+    assert!(conflicts_with(NVIDIA_CONF, "xserver-xorg-video-nouveau"));
+    assert!(!conflicts_with(NVIDIA_CONF, "nvidia-driver"));
+    // Input drivers are not video drivers
+    assert!(!conflicts_with("Section \"InputClass\"\n    Driver \"libinput\"\nEndSection\n", "nvidia-driver"));
+}
+
+#[test]
+fn test_backup_scripts() {
+    let paths = vec!["/etc/X11/xorg.conf".to_string(), "/etc/X11/xorg.conf.d/20-nvidia.conf".to_string()];
+    assert_eq!(
+        move_aside_script("/var/backups/meaupdater/xorg-1700000000", &paths),
+        "mkdir -p '/var/backups/meaupdater/xorg-1700000000' && cp -a --parents '/etc/X11/xorg.conf' '/etc/X11/xorg.conf.d/20-nvidia.conf' '/var/backups/meaupdater/xorg-1700000000' && rm -f '/etc/X11/xorg.conf' '/etc/X11/xorg.conf.d/20-nvidia.conf'"
+    );
+    assert_eq!(
+        restore_script("/var/backups/meaupdater/xorg-1700000000"),
+        "cp -a '/var/backups/meaupdater/xorg-1700000000'/etc/. /etc/"
+    );
+}
+
+#[test]
+fn test_parse_backups() {
+    let content = "nvidia-driver\t/var/backups/meaupdater/xorg-1\nbroken line\n";
+    assert_eq!(
+        parse_backups(content),
+        vec![("nvidia-driver".to_string(), "/var/backups/meaupdater/xorg-1".to_string())]
+    );
+}
@@ -0,0 +1,36 @@
+// tests/zfs_tests.rs
+use meaupdater::zfs::{detect_from, root_dataset, save_state_command, state_id, ZfsRoot};
+
+const UBUNTU_ZFS: &str = "sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+rpool/ROOT/ubuntu_6x1q2b / zfs rw,relatime,xattr,posixacl 0 0
+bpool/BOOT/ubuntu_6x1q2b /boot zfs rw,nodev,relatime,xattr,posixacl 0 0
+rpool/USERDATA/user_k3n2 /home/user zfs rw,relatime,xattr,posixacl 0 0
+";
+
+#[test]
+fn finds_the_dataset_mounted_on_root() {
+    assert_eq!(root_dataset(UBUNTU_ZFS).as_deref(), Some("rpool/ROOT/ubuntu_6x1q2b"));
+    assert_eq!(
+        detect_from(UBUNTU_ZFS, true),
+        Some(ZfsRoot { dataset: "rpool/ROOT/ubuntu_6x1q2b".to_string(), zsys: true })
+    );
+}
+
+#[test]
+fn other_root_file_systems_are_not_zfs() {
+    let ext4 = "/dev/nvme0n1p2 / ext4 rw,relatime,errors=remount-ro 0 0\ntank/data /srv zfs rw 0 0\n";
+    assert_eq!(root_dataset(ext4), None);
+    assert_eq!(detect_from(ext4, true), None);
+}
+
+#[test]
+fn the_last_mount_over_root_wins() {
+    let live = "rpool/ROOT/debian / zfs rw 0 0\noverlay / overlay rw 0 0\n";
+    assert_eq!(root_dataset(live), None);
+}
+
+#[test]
+fn zsys_state_is_saved_for_the_whole_system() {
+    assert_eq!(state_id(1_700_000_000), "meaupdater-1700000000");
+    assert_eq!(save_state_command("meaupdater-1"), vec!["zsysctl", "save", "meaupdater-1", "--system"]);
+}